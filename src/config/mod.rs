@@ -48,11 +48,13 @@ mod schema;
 
 pub use error::{ConfigError, ConfigResult};
 pub use loader::{
-    get_default_config_dir, get_default_config_path, resolve_config_path, ConfigLoader,
+    get_default_config_dir, get_default_config_path, resolve_config_path, ConfigChange,
+    ConfigLoader, SharedConfig,
 };
 pub use schema::{
-    Config, KeybindingsConfig, LogFormat, LoggingConfig, McpConfig, SerialConfig, ServerConfig,
-    ServerMode, TestDiscoveryConfig, TestingConfig, TuiConfig,
+    resolve_device_alias, Config, DeviceAlias, HeartbeatMethod, KeybindingsConfig, LogFormat,
+    LoggingConfig, McpConfig, MessageOversize, ProtocolDescriptor, SerialConfig, ServerConfig,
+    ServerMode, SessionConfig, TestDiscoveryConfig, TestingConfig, TuiConfig, WsBackpressure,
 };
 
 // Future: ConfigWatcher for hot-reload feature
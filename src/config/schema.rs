@@ -3,6 +3,7 @@
 //! This module defines the structure of the configuration file using serde.
 //! All configuration sections are defined here with appropriate defaults.
 
+use super::error::{ConfigError, ConfigResult};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -22,8 +23,16 @@ pub struct Config {
     pub tui: TuiConfig,
     /// MCP server configuration
     pub mcp: McpConfig,
+    /// Session log configuration
+    pub session: SessionConfig,
     /// Logging configuration
     pub logging: LoggingConfig,
+    /// Device aliases matched by USB VID/PID/serial number
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub device_aliases: Vec<DeviceAlias>,
+    /// Command/response protocols usable with `protocol_command`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub protocols: Vec<ProtocolDescriptor>,
 }
 
 impl Default for Config {
@@ -34,11 +43,207 @@ impl Default for Config {
             testing: TestingConfig::default(),
             tui: TuiConfig::default(),
             mcp: McpConfig::default(),
+            session: SessionConfig::default(),
             logging: LoggingConfig::default(),
+            device_aliases: Vec::new(),
+            protocols: Vec::new(),
         }
     }
 }
 
+impl Config {
+    /// Resolve a device alias to the current path of the matching port.
+    ///
+    /// Enumerates the system's serial ports and returns the path of the first
+    /// USB device matching the alias. Returns `None` if the alias is unknown,
+    /// no device matches, or enumeration fails.
+    pub fn resolve_alias(&self, alias: &str) -> Option<String> {
+        self.resolve_alias_with(alias, serialport::available_ports)
+    }
+
+    /// Resolve a device alias using a custom port enumerator.
+    pub fn resolve_alias_with<F>(&self, alias: &str, enumerate: F) -> Option<String>
+    where
+        F: FnOnce() -> Result<Vec<serialport::SerialPortInfo>, serialport::Error>,
+    {
+        resolve_device_alias(&self.device_aliases, alias, enumerate)
+    }
+
+    /// Look up a configured protocol by name.
+    pub fn protocol(&self, name: &str) -> Option<&ProtocolDescriptor> {
+        self.protocols.iter().find(|p| p.name == name)
+    }
+
+    /// Check values that would break the server if applied at runtime.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ConfigError::ValidationError`] naming the first bad key.
+    pub fn validate(&self) -> ConfigResult<()> {
+        let positive = [
+            ("serial.default_baud", u64::from(self.serial.default_baud)),
+            ("serial.default_timeout_ms", self.serial.default_timeout_ms),
+            ("serial.max_read_bytes", self.serial.max_read_bytes as u64),
+            (
+                "serial.max_write_repeat_bytes",
+                self.serial.max_write_repeat_bytes,
+            ),
+            (
+                "serial.metrics_history_interval_ms",
+                self.serial.metrics_history_interval_ms,
+            ),
+            (
+                "serial.metrics_history_len",
+                self.serial.metrics_history_len as u64,
+            ),
+            (
+                "serial.recent_errors_len",
+                self.serial.recent_errors_len as u64,
+            ),
+            ("tui.buffer_lines", self.tui.buffer_lines as u64),
+            ("tui.buffer_bytes", self.tui.buffer_bytes as u64),
+        ];
+        for (key, value) in positive {
+            if value == 0 {
+                return Err(ConfigError::validation(key, "must be greater than 0"));
+            }
+        }
+        if self.mcp.heartbeat_interval_ms == Some(0) {
+            return Err(ConfigError::validation(
+                "mcp.heartbeat_interval_ms",
+                "must be greater than 0",
+            ));
+        }
+        if self.session.max_message_bytes == Some(0) {
+            return Err(ConfigError::validation(
+                "session.max_message_bytes",
+                "must be greater than 0",
+            ));
+        }
+        for (i, pattern) in self.serial.write_allowlist.iter().enumerate() {
+            crate::service::write_allowlist_regex(pattern).map_err(|e| {
+                ConfigError::validation(format!("serial.write_allowlist[{}]", i), e.to_string())
+            })?;
+        }
+        for (i, pattern) in self.session.redact_patterns.iter().enumerate() {
+            regex::Regex::new(pattern).map_err(|e| {
+                ConfigError::validation(format!("session.redact_patterns[{}]", i), e.to_string())
+            })?;
+        }
+        for protocol in &self.protocols {
+            if protocol.response_terminator.is_empty() {
+                return Err(ConfigError::validation(
+                    format!("protocols.{}.response_terminator", protocol.name),
+                    "must not be empty",
+                ));
+            }
+        }
+        tracing_subscriber::EnvFilter::try_new(&self.logging.level)
+            .map_err(|e| ConfigError::validation("logging.level", e.to_string()))?;
+        Ok(())
+    }
+}
+
+/// Resolve `alias` against a list of device aliases using `enumerate` to list ports.
+///
+/// Enumeration only happens if `alias` names a configured device alias.
+pub fn resolve_device_alias<F>(aliases: &[DeviceAlias], alias: &str, enumerate: F) -> Option<String>
+where
+    F: FnOnce() -> Result<Vec<serialport::SerialPortInfo>, serialport::Error>,
+{
+    let entry = aliases.iter().find(|a| a.name == alias)?;
+    enumerate()
+        .ok()?
+        .into_iter()
+        .find(|p| entry.matches(p))
+        .map(|p| p.port_name)
+}
+
+/// A named device identified by its USB VID/PID and optional serial number.
+///
+/// ```toml
+/// [[device_aliases]]
+/// name = "arduino"
+/// vid = 0x2341
+/// pid = 0x0043
+/// serial_number = "75735303331351F0E0A1"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceAlias {
+    /// Alias name accepted in place of a port path
+    pub name: String,
+    /// USB vendor ID
+    pub vid: u16,
+    /// USB product ID
+    pub pid: u16,
+    /// USB serial number (optional; required to tell identical devices apart)
+    #[serde(default)]
+    pub serial_number: Option<String>,
+}
+
+impl DeviceAlias {
+    /// Whether `port` is a USB device matching this alias.
+    pub fn matches(&self, port: &serialport::SerialPortInfo) -> bool {
+        self.query().matches(port)
+    }
+
+    /// The enumeration query this alias stands for.
+    pub fn query(&self) -> crate::port::PortQuery {
+        crate::port::PortQuery {
+            vid: Some(self.vid),
+            pid: Some(self.pid),
+            serial: self.serial_number.clone(),
+            manufacturer_contains: None,
+        }
+    }
+}
+
+/// A line-based command/response protocol: send a command, then wait for a
+/// line starting with a known token.
+///
+/// ```toml
+/// [[protocols]]
+/// name = "sensor"
+/// command_terminator = "\r\n"
+/// response_prefix = "$OK,"
+/// error_prefix = "$ERR,"
+/// timeout_ms = 500
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolDescriptor {
+    /// Name passed to `protocol_command`
+    pub name: String,
+    /// Appended to each command in place of the port terminator
+    #[serde(default = "default_command_terminator")]
+    pub command_terminator: String,
+    /// Ends each response line; surrounding whitespace is trimmed, so "\n"
+    /// also handles "\r\n"
+    #[serde(default = "default_response_terminator")]
+    pub response_terminator: String,
+    /// Marks the success line; without it, any line that is not an error is
+    /// the response
+    #[serde(default)]
+    pub response_prefix: Option<String>,
+    /// Marks the error line
+    #[serde(default)]
+    pub error_prefix: Option<String>,
+    /// How long to wait for the response line
+    #[serde(default = "default_protocol_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+fn default_command_terminator() -> String {
+    "\r\n".to_string()
+}
+
+fn default_response_terminator() -> String {
+    "\n".to_string()
+}
+
+fn default_protocol_timeout_ms() -> u64 {
+    1000
+}
+
 /// Server configuration section.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
@@ -51,6 +256,25 @@ pub struct ServerConfig {
     pub mode: ServerMode,
     /// Log level: "trace", "debug", "info", "warn", "error"
     pub log_level: String,
+    /// How often the WebSocket reader polls the serial port, in milliseconds
+    pub ws_read_interval_ms: u64,
+    /// How often subscribed WebSocket clients get a status update with the
+    /// current metrics, in milliseconds; 0 disables
+    pub ws_status_interval_ms: u64,
+    /// Messages buffered per WebSocket connection before it counts as lagging
+    pub ws_buffer_size: usize,
+    /// What to do with a WebSocket client that falls behind the buffer
+    pub ws_backpressure: WsBackpressure,
+    /// Maximum concurrent WebSocket connections; further upgrades get 503
+    pub max_ws_connections: usize,
+    /// Answer REST errors with HTTP 200 and only the JSON body marking the
+    /// failure, as before error statuses were introduced
+    pub legacy_error_status: bool,
+    /// Retries when the session database cannot be opened at startup, before
+    /// falling back to an in-memory database
+    pub session_db_connect_retries: u32,
+    /// Delay before the first startup retry, doubled for each further one
+    pub session_db_connect_delay_ms: u64,
 }
 
 impl Default for ServerConfig {
@@ -60,10 +284,46 @@ impl Default for ServerConfig {
             port: 3000,
             mode: ServerMode::Mcp,
             log_level: "info".to_string(),
+            ws_read_interval_ms: 50,
+            ws_status_interval_ms: 2000,
+            ws_buffer_size: 100,
+            ws_backpressure: WsBackpressure::DropOldest,
+            max_ws_connections: 32,
+            legacy_error_status: false,
+            session_db_connect_retries: 3,
+            session_db_connect_delay_ms: 100,
         }
     }
 }
 
+impl ServerConfig {
+    /// Get the WebSocket read interval as Duration (at least 1 ms)
+    pub fn ws_read_interval(&self) -> Duration {
+        Duration::from_millis(self.ws_read_interval_ms.max(1))
+    }
+
+    /// Get the periodic WebSocket status interval, or `None` if disabled
+    pub fn ws_status_interval(&self) -> Option<Duration> {
+        (self.ws_status_interval_ms > 0).then(|| Duration::from_millis(self.ws_status_interval_ms))
+    }
+
+    /// Get the initial session database connect retry delay as Duration
+    pub fn session_db_connect_delay(&self) -> Duration {
+        Duration::from_millis(self.session_db_connect_delay_ms)
+    }
+}
+
+/// Policy for WebSocket clients that lag behind the broadcast buffer.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WsBackpressure {
+    /// Skip the missed messages, notify the client, and keep streaming
+    #[default]
+    DropOldest,
+    /// Notify the client and close the connection
+    CloseOnLag,
+}
+
 /// Server operating mode.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -94,6 +354,33 @@ pub struct SerialConfig {
     pub auto_discover: bool,
     /// Discovery interval in milliseconds
     pub discovery_interval_ms: u64,
+    /// Maximum number of bytes a single read request may ask for
+    pub max_read_bytes: usize,
+    /// Maximum total bytes a single `write_repeat` call may send
+    pub max_write_repeat_bytes: u64,
+    /// How long a `list_ports` enumeration is reused, in milliseconds
+    pub port_list_ttl_ms: u64,
+    /// Minimum interval between warnings about consecutive read timeouts
+    pub timeout_warn_interval_ms: u64,
+    /// Idle-disconnect applied to opens that do not set `idle_disconnect_ms`
+    pub default_idle_disconnect_ms: Option<u64>,
+    /// Open-duration limit applied to opens that do not set `max_open_duration_ms`
+    pub default_max_open_duration_ms: Option<u64>,
+    /// Close the open port after this long without an MCP tool call
+    pub inactivity_close_ms: Option<u64>,
+    /// Reject opens at unusual baud rates instead of warning
+    pub strict_baud: bool,
+    /// How often a metrics sample is added to the history, in milliseconds
+    pub metrics_history_interval_ms: u64,
+    /// Number of metrics samples kept; older ones are dropped
+    pub metrics_history_len: usize,
+    /// Number of recent port failures kept for diagnostics; older ones are dropped
+    pub recent_errors_len: usize,
+    /// Regexes that a whole write payload must match; empty permits every write
+    pub write_allowlist: Vec<String>,
+    /// Port opened at startup (`[serial.auto_open]`); `port_name` may be an alias
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auto_open: Option<crate::service::OpenConfig>,
     /// Port aliases for convenience
     #[serde(default)]
     pub port_aliases: HashMap<String, String>,
@@ -106,6 +393,19 @@ impl Default for SerialConfig {
             default_timeout_ms: 1000,
             auto_discover: true,
             discovery_interval_ms: 5000,
+            max_read_bytes: crate::service::DEFAULT_MAX_READ_BYTES,
+            max_write_repeat_bytes: crate::service::DEFAULT_MAX_WRITE_REPEAT_BYTES,
+            port_list_ttl_ms: 1000,
+            timeout_warn_interval_ms: 10_000,
+            default_idle_disconnect_ms: None,
+            default_max_open_duration_ms: None,
+            inactivity_close_ms: None,
+            strict_baud: false,
+            metrics_history_interval_ms: 1000,
+            metrics_history_len: crate::service::DEFAULT_METRICS_HISTORY_LEN,
+            recent_errors_len: crate::service::DEFAULT_RECENT_ERRORS_LEN,
+            write_allowlist: Vec::new(),
+            auto_open: None,
             port_aliases: HashMap::new(),
         }
     }
@@ -117,6 +417,28 @@ impl SerialConfig {
         Duration::from_millis(self.default_timeout_ms)
     }
 
+    /// Get the timeout warning interval as Duration
+    pub fn timeout_warn_interval(&self) -> Duration {
+        Duration::from_millis(self.timeout_warn_interval_ms)
+    }
+
+    /// Get the metrics history sampling interval as Duration
+    pub fn metrics_history_interval(&self) -> Duration {
+        Duration::from_millis(self.metrics_history_interval_ms)
+    }
+
+    /// Get the MCP inactivity window as Duration; `None` or 0 disables it
+    pub fn inactivity_close(&self) -> Option<Duration> {
+        self.inactivity_close_ms
+            .filter(|&ms| ms > 0)
+            .map(Duration::from_millis)
+    }
+
+    /// Get the port list cache TTL as Duration
+    pub fn port_list_ttl(&self) -> Duration {
+        Duration::from_millis(self.port_list_ttl_ms)
+    }
+
     /// Resolve a port name through aliases
     pub fn resolve_port(&self, name: &str) -> String {
         self.port_aliases
@@ -206,6 +528,10 @@ pub struct TuiConfig {
     pub show_timestamps: bool,
     /// Command history size
     pub history_size: usize,
+    /// Most lines kept in the terminal buffer
+    pub buffer_lines: usize,
+    /// Most bytes kept in the terminal buffer
+    pub buffer_bytes: usize,
     /// Custom keybindings
     pub keybindings: KeybindingsConfig,
 }
@@ -218,6 +544,8 @@ impl Default for TuiConfig {
             hex_columns: 16,
             show_timestamps: true,
             history_size: 1000,
+            buffer_lines: 1000,
+            buffer_bytes: 1024 * 1024,
             keybindings: KeybindingsConfig::default(),
         }
     }
@@ -271,6 +599,12 @@ pub struct McpConfig {
     pub max_sessions: usize,
     /// Session timeout in seconds
     pub session_timeout_secs: u64,
+    /// Emit a heartbeat notification line on stdout at startup
+    pub heartbeat: bool,
+    /// Repeat the heartbeat at this interval; startup only when unset
+    pub heartbeat_interval_ms: Option<u64>,
+    /// JSON-RPC method name of the heartbeat
+    pub heartbeat_method: HeartbeatMethod,
 }
 
 impl Default for McpConfig {
@@ -279,6 +613,9 @@ impl Default for McpConfig {
             session_db: "sqlite://sessions.db".to_string(),
             max_sessions: 100,
             session_timeout_secs: 3600,
+            heartbeat: false,
+            heartbeat_interval_ms: None,
+            heartbeat_method: HeartbeatMethod::default(),
         }
     }
 }
@@ -288,12 +625,87 @@ impl McpConfig {
     pub fn session_timeout(&self) -> Duration {
         Duration::from_secs(self.session_timeout_secs)
     }
+
+    /// Get the heartbeat repeat interval as Duration
+    pub fn heartbeat_interval(&self) -> Option<Duration> {
+        self.heartbeat_interval_ms.map(Duration::from_millis)
+    }
+}
+
+/// Method name used for the `[mcp]` heartbeat notification.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeartbeatMethod {
+    /// `notifications/ping`
+    #[default]
+    Ping,
+    /// `_heartbeat`, as emitted by earlier versions
+    Legacy,
+}
+
+impl HeartbeatMethod {
+    /// JSON-RPC method name.
+    pub fn method(self) -> &'static str {
+        match self {
+            Self::Ping => "notifications/ping",
+            Self::Legacy => "_heartbeat",
+        }
+    }
+}
+
+/// Session log configuration section.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Maximum bytes of `content` stored per message; unlimited when unset
+    pub max_message_bytes: Option<usize>,
+    /// What to do with a message over `max_message_bytes`
+    pub oversize: MessageOversize,
+    /// Regexes whose matches in message content are replaced with `***`
+    /// before storage
+    pub redact_patterns: Vec<String>,
+    /// Reconnect attempts after the database connection is lost; 0 disables
+    pub reconnect_retries: u32,
+    /// Delay before the first reconnect attempt, doubled for each retry
+    pub reconnect_delay_ms: u64,
+}
+
+impl Default for SessionConfig {
+    fn default() -> Self {
+        Self {
+            max_message_bytes: None,
+            oversize: MessageOversize::default(),
+            redact_patterns: Vec::new(),
+            reconnect_retries: 3,
+            reconnect_delay_ms: 100,
+        }
+    }
+}
+
+impl SessionConfig {
+    /// Get the initial reconnect delay as Duration
+    pub fn reconnect_delay(&self) -> Duration {
+        Duration::from_millis(self.reconnect_delay_ms)
+    }
+}
+
+/// Policy for session messages longer than `session.max_message_bytes`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageOversize {
+    /// Store the first `max_message_bytes` followed by a `...[truncated N bytes]` marker
+    #[default]
+    Truncate,
+    /// Fail the append without storing anything
+    Reject,
 }
 
 /// Logging configuration section.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
 pub struct LoggingConfig {
+    /// Log level filter (e.g. "info", "serial_mcp_agent=debug"); `RUST_LOG` overrides it
+    pub level: String,
     /// Log file path (optional)
     pub file: Option<PathBuf>,
     /// Max log file size in MB
@@ -307,6 +719,7 @@ pub struct LoggingConfig {
 impl Default for LoggingConfig {
     fn default() -> Self {
         Self {
+            level: "info".to_string(),
             file: None,
             max_size_mb: 10,
             rotation: "daily".to_string(),
@@ -356,6 +769,97 @@ mod tests {
         assert_eq!(config.resolve_port("COM5"), "COM5");
     }
 
+    fn usb_port(
+        name: &str,
+        vid: u16,
+        pid: u16,
+        serial: Option<&str>,
+    ) -> serialport::SerialPortInfo {
+        serialport::SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: serialport::SerialPortType::UsbPort(serialport::UsbPortInfo {
+                vid,
+                pid,
+                serial_number: serial.map(str::to_string),
+                manufacturer: None,
+                product: None,
+                location: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_device_alias_resolution() {
+        let config: Config = toml::from_str(
+            r#"
+            [[device_aliases]]
+            name = "arduino"
+            vid = 0x2341
+            pid = 0x0043
+
+            [[device_aliases]]
+            name = "ftdi-b"
+            vid = 0x0403
+            pid = 0x6001
+            serial_number = "B"
+        "#,
+        )
+        .unwrap();
+        let ports = || {
+            Ok(vec![
+                usb_port("/dev/ttyUSB0", 0x0403, 0x6001, Some("A")),
+                usb_port("/dev/ttyUSB1", 0x0403, 0x6001, Some("B")),
+                usb_port("/dev/ttyACM0", 0x2341, 0x0043, None),
+            ])
+        };
+
+        assert_eq!(
+            config.resolve_alias_with("arduino", ports).as_deref(),
+            Some("/dev/ttyACM0")
+        );
+        assert_eq!(
+            config.resolve_alias_with("ftdi-b", ports).as_deref(),
+            Some("/dev/ttyUSB1")
+        );
+        assert_eq!(config.resolve_alias_with("unknown", ports), None);
+    }
+
+    #[test]
+    fn test_protocol_descriptors() {
+        let config: Config = toml::from_str(
+            r#"
+            [[protocols]]
+            name = "sensor"
+            response_prefix = "$OK,"
+            error_prefix = "$ERR,"
+
+            [[protocols]]
+            name = "shell"
+            command_terminator = "\n"
+            response_terminator = "\r\n"
+            timeout_ms = 250
+        "#,
+        )
+        .unwrap();
+        let sensor = config.protocol("sensor").unwrap();
+        assert_eq!(sensor.command_terminator, "\r\n");
+        assert_eq!(sensor.response_terminator, "\n");
+        assert_eq!(sensor.timeout_ms, 1000);
+        let shell = config.protocol("shell").unwrap();
+        assert_eq!(shell.response_prefix, None);
+        assert_eq!(shell.timeout_ms, 250);
+        assert!(config.protocol("modem").is_none());
+        assert!(config.validate().is_ok());
+
+        let mut config = config;
+        config.protocols[1].response_terminator.clear();
+        assert!(config
+            .validate()
+            .unwrap_err()
+            .to_string()
+            .contains("protocols.shell.response_terminator"));
+    }
+
     #[test]
     fn test_config_serialization() {
         let config = Config::default();
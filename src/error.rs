@@ -4,6 +4,10 @@ use axum::{
     response::{IntoResponse, Response},
 };
 use std::fmt;
+use std::time::Duration;
+
+use crate::port::PortError;
+use crate::service::ServiceError;
 
 #[cfg(feature = "rest-api")]
 use serde_json::json;
@@ -26,6 +30,45 @@ pub enum AppError {
     SerialError(serialport::Error),
     IoError(std::io::Error),
     SerdeError(serde_json::Error),
+    Timeout(Duration),
+    /// Any other service failure, kept whole so its kind can be reported
+    Service(ServiceError),
+    /// A failed operation, reported under `kind` (e.g. "WriteError") unless
+    /// the service error has a more specific type of its own
+    Operation {
+        kind: &'static str,
+        error: ServiceError,
+    },
+    /// A request rejected before it reached the service (e.g. "InvalidVID")
+    Rejected {
+        kind: &'static str,
+        message: String,
+    },
+    /// A failure outside the port service, such as sessions or config reload
+    Failed {
+        kind: &'static str,
+        message: String,
+    },
+}
+
+impl AppError {
+    pub fn operation(kind: &'static str, error: ServiceError) -> Self {
+        Self::Operation { kind, error }
+    }
+
+    pub fn rejected(kind: &'static str, error: impl fmt::Display) -> Self {
+        Self::Rejected {
+            kind,
+            message: error.to_string(),
+        }
+    }
+
+    pub fn failed(kind: &'static str, error: impl fmt::Display) -> Self {
+        Self::Failed {
+            kind,
+            message: error.to_string(),
+        }
+    }
 }
 
 impl fmt::Display for AppError {
@@ -43,14 +86,40 @@ impl fmt::Display for AppError {
             Self::SerialError(e) => write!(f, "A serial port error occurred: {e}"),
             Self::IoError(e) => write!(f, "An I/O error occurred: {e}"),
             Self::SerdeError(e) => write!(f, "A serialization/deserialization error occurred: {e}"),
+            Self::Timeout(d) => write!(f, "The operation timed out after {d:?}."),
+            Self::Service(e) | Self::Operation { error: e, .. } => write!(f, "{e}"),
+            Self::Rejected { message, .. } | Self::Failed { message, .. } => f.write_str(message),
         }
     }
 }
 
+/// Marks a response built from an [`AppError`], so the REST layer can find
+/// error responses without parsing their bodies.
+#[cfg(feature = "rest-api")]
+#[derive(Debug, Clone, Copy)]
+pub struct ErrorResponse;
+
 /// Allows Axum to convert `AppError` into an HTTP response (only when rest-api feature enabled).
 #[cfg(feature = "rest-api")]
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
+        let write_progress = match &self {
+            Self::Service(ServiceError::WriteTimeout {
+                written,
+                flow_paused,
+                ..
+            })
+            | Self::Operation {
+                error:
+                    ServiceError::WriteTimeout {
+                        written,
+                        flow_paused,
+                        ..
+                    },
+                ..
+            } => Some((*written, *flow_paused)),
+            _ => None,
+        };
         let (status, error_type, error_message) = match self {
             Self::PortNotOpen => (StatusCode::CONFLICT, "PortNotOpen", self.to_string()),
             Self::PortAlreadyOpen => (StatusCode::CONFLICT, "PortAlreadyOpen", self.to_string()),
@@ -72,13 +141,48 @@ impl IntoResponse for AppError {
                 "DeserializationError",
                 self.to_string(),
             ),
+            Self::Timeout(_) => (StatusCode::GATEWAY_TIMEOUT, "Timeout", self.to_string()),
+            Self::Service(ref e) => (service_status(e), e.kind(), self.to_string()),
+            Self::Operation { kind, ref error } => {
+                let error_type = match error {
+                    ServiceError::PortError(_)
+                    | ServiceError::Io { .. }
+                    | ServiceError::StateLockPoisoned => kind,
+                    e => e.kind(),
+                };
+                (service_status(error), error_type, self.to_string())
+            }
+            Self::Rejected { kind, message } => (StatusCode::BAD_REQUEST, kind, message),
+            Self::Failed { kind, message } => (StatusCode::INTERNAL_SERVER_ERROR, kind, message),
         };
 
-        let body = axum::Json(json!({
+        let mut body = json!({
             "status": "error",
             "error": { "type": error_type, "message": error_message }
-        }));
-        (status, body).into_response()
+        });
+        if let Some((written, flow_paused)) = write_progress {
+            body["bytes_written"] = json!(written);
+            body["flow_paused"] = json!(flow_paused);
+        }
+        let mut response = (status, axum::Json(body)).into_response();
+        response.extensions_mut().insert(ErrorResponse);
+        response
+    }
+}
+
+/// HTTP status for a service error.
+///
+/// Port state conflicts are 409, invalid requests 400, missing devices 404,
+/// permission problems 403 and timeouts 504; anything else is a 500.
+#[cfg(feature = "rest-api")]
+fn service_status(error: &ServiceError) -> StatusCode {
+    match error {
+        ServiceError::PortAlreadyOpen | ServiceError::PortNotOpen => StatusCode::CONFLICT,
+        ServiceError::InvalidConfig(_) | ServiceError::NoPortSpecified => StatusCode::BAD_REQUEST,
+        ServiceError::DeviceNotFound(_) => StatusCode::NOT_FOUND,
+        ServiceError::PermissionDenied(_) => StatusCode::FORBIDDEN,
+        e if e.is_timeout() => StatusCode::GATEWAY_TIMEOUT,
+        _ => StatusCode::INTERNAL_SERVER_ERROR,
     }
 }
 
@@ -100,3 +204,69 @@ impl From<serde_json::Error> for AppError {
         AppError::SerdeError(err)
     }
 }
+
+/// Service errors keep their meaning: port state conflicts, bad requests,
+/// timeouts and I/O errors map to the matching variants.
+impl From<ServiceError> for AppError {
+    fn from(err: ServiceError) -> Self {
+        match err {
+            ServiceError::PortNotOpen => AppError::PortNotOpen,
+            ServiceError::PortAlreadyOpen => AppError::PortAlreadyOpen,
+            ServiceError::InvalidConfig(msg) => AppError::InvalidPayload(msg),
+            ServiceError::Timeout(d) => AppError::Timeout(d),
+            ServiceError::Io { kind, message } => {
+                AppError::IoError(std::io::Error::new(kind, message))
+            }
+            e => AppError::Service(e),
+        }
+    }
+}
+
+impl From<PortError> for AppError {
+    fn from(err: PortError) -> Self {
+        ServiceError::from(err).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timeout_is_recognisable_at_every_layer() {
+        let timeout = Duration::from_millis(250);
+
+        let service = ServiceError::from(PortError::timeout(timeout));
+        assert_eq!(service, ServiceError::Timeout(timeout));
+        assert!(service.is_timeout());
+        assert!(matches!(AppError::from(service), AppError::Timeout(d) if d == timeout));
+
+        let io = PortError::Io(std::io::Error::from(std::io::ErrorKind::TimedOut));
+        let service = ServiceError::from(io);
+        assert!(service.is_timeout());
+        assert!(matches!(
+            AppError::from(service),
+            AppError::IoError(e) if e.kind() == std::io::ErrorKind::TimedOut
+        ));
+    }
+
+    #[test]
+    fn port_errors_keep_their_meaning() {
+        assert!(matches!(
+            ServiceError::from(PortError::not_found("/dev/ttyUSB0")),
+            ServiceError::DeviceNotFound(_)
+        ));
+        assert_eq!(
+            ServiceError::from(PortError::PermissionDenied("/dev/ttyUSB0".into())),
+            ServiceError::PermissionDenied("/dev/ttyUSB0".into())
+        );
+        assert!(matches!(
+            AppError::from(PortError::NotOpen),
+            AppError::PortNotOpen
+        ));
+        assert!(matches!(
+            AppError::from(PortError::config("bad baud")),
+            AppError::Service(ServiceError::PortError(_))
+        ));
+    }
+}
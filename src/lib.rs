@@ -8,6 +8,7 @@
 //! - `config`: Configuration management with TOML support
 //! - `state`: Port configuration and state management
 //! - `error`: Unified error handling
+//! - `logging`: Tracing subscriber setup from the `[logging]` config section
 //! - `session`: Session tracking and management
 //! - `port`: Port abstraction layer for serial communication
 //! - `service`: Business logic layer for port operations
@@ -18,6 +19,7 @@
 
 pub mod config;
 pub mod error;
+pub mod logging;
 pub mod port;
 pub mod service;
 pub mod session;
@@ -50,8 +52,9 @@ pub use port::{
     StopBits, SyncSerialPort,
 };
 pub use service::{
-    AutoCloseInfo, CloseResult, MetricsResult, OpenConfig, OpenResult, PortMetrics, PortService,
-    ReadResult, ReconfigureConfig, ServiceError, ServiceResult, StatusResult, WriteResult,
+    AutoCloseInfo, CloseReason, CloseResult, MetricsResult, OpenConfig, OpenResult, PortMetrics,
+    PortService, ReadResult, ReconfigureConfig, ServiceError, ServiceResult, StatusResult,
+    SubsystemResetResult, WriteResult,
 };
 pub use state::{
     AppState, DataBitsCfg, FlowControlCfg, ParityCfg, PortConfig, PortState, StopBitsCfg,
@@ -0,0 +1,155 @@
+//! Tracing subscriber setup driven by the `[logging]` config section.
+//!
+//! Logs never go to stdout: the MCP stdio transport owns it. Output goes to
+//! stderr, or to `logging.file` when one is configured.
+
+use crate::config::{LogFormat, LoggingConfig};
+use std::fs::OpenOptions;
+use std::sync::{Mutex, OnceLock};
+use tracing::Subscriber;
+use tracing_subscriber::fmt::writer::BoxMakeWriter;
+use tracing_subscriber::{reload, EnvFilter};
+
+/// Boxed subscriber returned by [`build_subscriber`].
+pub type BoxedSubscriber = Box<dyn Subscriber + Send + Sync>;
+
+/// Swaps the level filter of a built subscriber.
+type FilterReloader = Box<dyn Fn(EnvFilter) -> Result<(), String> + Send + Sync>;
+
+/// Filter reloader of the subscriber installed by [`init`].
+static GLOBAL_RELOADER: OnceLock<FilterReloader> = OnceLock::new();
+
+/// Build the level filter: `RUST_LOG` when set, otherwise `logging.level`.
+///
+/// An unparsable level falls back to `info`.
+pub fn env_filter(config: &LoggingConfig) -> EnvFilter {
+    EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(&config.level))
+        .unwrap_or_else(|_| EnvFilter::new("info"))
+}
+
+/// Build a subscriber for the configured format and target.
+///
+/// # Errors
+///
+/// Returns an error if `logging.file` is set and cannot be opened for appending.
+pub fn build_subscriber(config: &LoggingConfig) -> std::io::Result<BoxedSubscriber> {
+    build(config).map(|(subscriber, _)| subscriber)
+}
+
+fn build(config: &LoggingConfig) -> std::io::Result<(BoxedSubscriber, FilterReloader)> {
+    let (writer, ansi) = match &config.file {
+        Some(path) => {
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            (BoxMakeWriter::new(Mutex::new(file)), false)
+        }
+        None => (BoxMakeWriter::new(std::io::stderr), true),
+    };
+    let builder = tracing_subscriber::fmt()
+        .with_env_filter(env_filter(config))
+        .with_writer(writer);
+
+    Ok(match config.format {
+        LogFormat::Pretty => {
+            let builder = builder.with_ansi(ansi).pretty().with_filter_reloading();
+            let reloader = filter_reloader(builder.reload_handle());
+            (Box::new(builder.finish()), reloader)
+        }
+        LogFormat::Compact => {
+            let builder = builder.with_ansi(ansi).compact().with_filter_reloading();
+            let reloader = filter_reloader(builder.reload_handle());
+            (Box::new(builder.finish()), reloader)
+        }
+        LogFormat::Json => {
+            let builder = builder.with_ansi(false).json().with_filter_reloading();
+            let reloader = filter_reloader(builder.reload_handle());
+            (Box::new(builder.finish()), reloader)
+        }
+    })
+}
+
+fn filter_reloader<S: 'static>(handle: reload::Handle<EnvFilter, S>) -> FilterReloader {
+    Box::new(move |filter| handle.reload(filter).map_err(|e| e.to_string()))
+}
+
+/// Install the configured subscriber as the global default.
+///
+/// If the log file cannot be opened, logging falls back to stderr and the
+/// open error is returned so the caller can report it. Does nothing if a
+/// global subscriber is already set.
+pub fn init(config: &LoggingConfig) -> std::io::Result<()> {
+    let ((subscriber, reloader), result) = match build(config) {
+        Ok(built) => (built, Ok(())),
+        Err(e) => {
+            let stderr_only = LoggingConfig {
+                file: None,
+                ..config.clone()
+            };
+            (build(&stderr_only)?, Err(e))
+        }
+    };
+    if tracing::subscriber::set_global_default(subscriber).is_ok() {
+        let _ = GLOBAL_RELOADER.set(reloader);
+    }
+    result
+}
+
+/// Apply `logging.level` to the subscriber installed by [`init`].
+///
+/// `RUST_LOG` still takes precedence, as in [`env_filter`]. Format and file
+/// changes need a restart. Returns `false` if [`init`] did not install the
+/// global subscriber or the filter could not be swapped.
+pub fn reload_level(config: &LoggingConfig) -> bool {
+    let reload = match GLOBAL_RELOADER.get() {
+        Some(reload) => reload,
+        None => return false,
+    };
+    match reload(env_filter(config)) {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!(error = %e, "Failed to reload log level");
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_subscriber_for_each_format() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in [LogFormat::Pretty, LogFormat::Compact, LogFormat::Json] {
+            let path = dir.path().join(format!("{:?}.log", format));
+            for file in [None, Some(path.clone())] {
+                let config = LoggingConfig {
+                    format,
+                    file,
+                    level: "debug".to_string(),
+                    ..LoggingConfig::default()
+                };
+                let subscriber = build_subscriber(&config).unwrap();
+                tracing::subscriber::with_default(subscriber, || {
+                    tracing::info!(format = ?format, "logging smoke test");
+                });
+            }
+            let written = std::fs::read_to_string(&path).unwrap();
+            assert!(written.contains("logging smoke test"), "{:?}", format);
+            if format == LogFormat::Json {
+                let line = written.lines().next().unwrap();
+                serde_json::from_str::<serde_json::Value>(line).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_unopenable_log_file_is_an_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let config = LoggingConfig {
+            file: Some(dir.path().join("missing").join("app.log")),
+            ..LoggingConfig::default()
+        };
+        assert!(build_subscriber(&config).is_err());
+    }
+}
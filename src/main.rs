@@ -11,7 +11,7 @@ use tokio::signal;
 // All modules are now in the library - import what we need
 #[cfg(feature = "rest-api")]
 use serial_mcp_agent::AppResult;
-use serial_mcp_agent::{session, AppState, PortState};
+use serial_mcp_agent::{logging, session, AppState, ConfigLoader, PortState};
 
 #[cfg(feature = "mcp")]
 use serial_mcp_agent::mcp;
@@ -42,26 +42,67 @@ struct Args {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     #[cfg_attr(not(feature = "rest-api"), allow(unused_variables))]
     let args = Args::parse();
-    // Initialize tracing subscriber once. Keep stdout clean for MCP framed protocol; send logs to stderr.
-    let env_filter = std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into());
-    let _ = tracing_subscriber::fmt()
-        .with_env_filter(env_filter)
-        .with_writer(std::io::stderr)
-        .try_init();
+    // Load configuration (file + RUST_COMM_* env overrides); fall back to defaults on error.
+    // Loaded before tracing so the subscriber can honor the [logging] section.
+    let (loader, config_error) = match ConfigLoader::load() {
+        Ok(loader) => (loader, None),
+        Err(e) => (ConfigLoader::with_defaults(), Some(e)),
+    };
+    let config = loader.config();
+    // Initialize tracing subscriber once. Keep stdout clean for MCP framed protocol; logs go to
+    // stderr or the configured log file.
+    if let Err(e) = logging::init(&config.logging) {
+        tracing::warn!(error = %e, file = ?config.logging.file, "Failed to open log file; logging to stderr");
+    }
+    if let Some(e) = config_error {
+        tracing::warn!(error = %e, "Failed to load config; using defaults");
+    }
     // Initialize the shared application state
     let app_state: AppState = Arc::new(Mutex::new(PortState::default()));
+    // The MCP handler builds its own service over the same state, so a port opened
+    // here is already open for its first tool call.
+    let service = serial_mcp_agent::PortService::new(app_state.clone());
+    service.apply_config(config);
+    match service.auto_open(&config.serial) {
+        Some(Ok(opened)) => {
+            tracing::info!(port = %opened.port_name, baud = opened.baud_rate, "Auto-opened port")
+        }
+        Some(Err(e)) => {
+            tracing::warn!(error = %e, "Failed to auto-open port; continuing without it")
+        }
+        None => {}
+    }
     // Initialize session store. Default to on-disk file (sessions.db). Allow override via env SESSION_DB_URL.
-    // If the on-disk database cannot be opened (common in CI / read-only or sandboxed environments),
-    // fall back to an in-memory shared SQLite instance so the server can still start and tests pass.
+    // Opening is retried with backoff to ride out a slow filesystem at boot. If the on-disk database still
+    // cannot be opened (common in CI / read-only or sandboxed environments), fall back to an in-memory
+    // shared SQLite instance so the server can still start and tests pass.
     let db_url =
         std::env::var("SESSION_DB_URL").unwrap_or_else(|_| "sqlite://sessions.db".to_string());
-    let session_store = match session::SessionStore::new(&db_url).await {
+    let session_store = match session::SessionStore::connect_with_retry(
+        &db_url,
+        config.server.session_db_connect_retries,
+        config.server.session_db_connect_delay(),
+    )
+    .await
+    {
         Ok(store) => store,
         Err(e) => {
-            tracing::warn!(error = %e, db_url, "Failed to open session database; falling back to in-memory");
+            tracing::error!(
+                error = %e,
+                db_url,
+                retries = config.server.session_db_connect_retries,
+                "Failed to open session database; SESSION PERSISTENCE DISABLED, sessions are kept in memory only"
+            );
             session::SessionStore::new("sqlite::memory:?cache=shared").await?
         }
     };
+    let session_store = session_store
+        .with_max_message_bytes(config.session.max_message_bytes, config.session.oversize)
+        .with_redact_patterns(&config.session.redact_patterns)?
+        .with_reconnect_policy(
+            config.session.reconnect_retries,
+            config.session.reconnect_delay(),
+        );
 
     // If the --server flag is provided (and REST feature enabled), launch HTTP server; otherwise always fall back to
     // stdio MCP (preferred) or legacy stdio if MCP feature is disabled. This keeps a consistent developer UX and
@@ -70,11 +111,25 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     {
         if args.server {
             // --- HTTP Server Mode ---
-            let service = serial_mcp_agent::PortService::new(app_state.clone());
+            // Auto-closes of session-linked ports are noted in the session
+            let service = service.with_close_hook(session_store.auto_close_note_hook());
+            service.spawn_metrics_history(config.serial.metrics_history_interval());
             let rest_ctx = rest_api::RestContext {
                 state: app_state.clone(),
                 sessions: std::sync::Arc::new(session_store.clone()),
                 service,
+                port_cache: std::sync::Arc::new(serial_mcp_agent::port::PortListCache::new(
+                    config.serial.port_list_ttl(),
+                )),
+                config: std::sync::Arc::new(std::sync::RwLock::new(loader.clone())),
+                #[cfg(feature = "websocket")]
+                ws: serial_mcp_agent::websocket::WsSettings::from(&config.server),
+                #[cfg(feature = "websocket")]
+                ws_connections: Default::default(),
+                #[cfg(feature = "websocket")]
+                ws_readers: Default::default(),
+                #[cfg(feature = "auto-negotiation")]
+                negotiator: Default::default(),
             };
             let app = rest_api::build_router(rest_ctx);
 
@@ -90,7 +145,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             #[cfg(feature = "mcp")]
             {
                 tracing::info!("Serial MCP Server starting (stdio MCP mode)");
-                if let Err(e) = mcp::start_mcp_server_stdio(app_state.clone(), session_store).await
+                if let Err(e) =
+                    mcp::start_mcp_server_stdio(app_state.clone(), session_store, &loader).await
                 {
                     tracing::error!(error = %e, "MCP server exited with error");
                 }
@@ -112,7 +168,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         #[cfg(feature = "mcp")]
         {
             tracing::info!("Serial MCP Server starting (stdio MCP mode)");
-            if let Err(e) = mcp::start_mcp_server_stdio(app_state.clone(), session_store).await {
+            if let Err(e) =
+                mcp::start_mcp_server_stdio(app_state.clone(), session_store, &loader).await
+            {
                 tracing::error!(error = %e, "MCP server exited with error");
             }
         }
@@ -10,35 +10,40 @@ use rust_mcp_sdk::{
     mcp_server::{server_runtime, ServerHandler},
     schema::{
         CallToolRequest, CallToolResult, Implementation, InitializeResult, ListToolsRequest,
-        ListToolsResult, RpcError, ServerCapabilities, ServerCapabilitiesTools, TextContent,
-        LATEST_PROTOCOL_VERSION,
+        ListToolsResult, LoggingLevel, LoggingMessageNotificationParams, RpcError,
+        ServerCapabilities, ServerCapabilitiesTools, TextContent, LATEST_PROTOCOL_VERSION,
     },
     McpServer, StdioTransport, TransportOptions,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use std::{io::Write, sync::Arc};
-
-#[cfg(feature = "auto-negotiation")]
-use std::time::Duration;
+use std::{
+    io::Write,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+    time::Duration,
+};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 // CallToolError lives under schema_utils submodule path
-use rust_mcp_sdk::schema::mcp_2025_06_18::schema_utils::CallToolError;
+use rust_mcp_sdk::schema::mcp_2025_06_18::schema_utils::{CallToolError, NotificationFromServer};
 
-use crate::service::{OpenConfig, PortService, ReconfigureConfig, ServiceError};
-use crate::session::SessionStore;
+use crate::port::{
+    decode_values, DecodeSpec, Endianness, NumericType, PortQuery, RegisterKind, RegisterRange,
+};
+use crate::service::{
+    CloseReason, DiagnosticStep, FramingRequest, OpenConfig, PortService, ReadResult,
+    ReconfigureConfig, ResponseStatus, ServiceError, ServiceResult, SignalsRequest, StepStatus,
+    DEFAULT_CAPTURE_BYTES, DEFAULT_DIAGNOSE_PROBE_TIMEOUT_MS, DEFAULT_LOOPBACK_TOKEN_LEN,
+};
+use crate::session::{diff_messages, DeviceSignature, NewMessage, SessionStore};
 use crate::state::{
-    default_data_bits, default_flow_control, default_parity, default_reconfig_baud,
-    default_stop_bits, default_timeout, AppState, DataBitsCfg, FlowControlCfg, ParityCfg,
-    StopBitsCfg,
+    default_data_bits, default_flow_control, default_parity, default_stop_bits, default_timeout,
+    line_settings_json, AppState, ChecksumKind, DataBitsCfg, FlowControlCfg, ParityCfg,
+    PortConfigPatch, ReadEncoding, ReadStrategy, ReadTransform, ResetBoard, ResponseProbe,
+    SignalStep, StopBitsCfg, WriteEncoding,
 };
 
-#[cfg(feature = "auto-negotiation")]
-use crate::state::{PortConfig, PortState};
-
-#[cfg(feature = "auto-negotiation")]
-use crate::port::{PortConfiguration, SyncSerialPort};
-
 // ------------------ Config Type Conversions ------------------
 
 impl From<DataBitsCfg> for crate::port::DataBits {
@@ -66,6 +71,7 @@ impl From<StopBitsCfg> for crate::port::StopBits {
     fn from(bits: StopBitsCfg) -> Self {
         match bits {
             StopBitsCfg::One => crate::port::StopBits::One,
+            StopBitsCfg::OnedotFive => crate::port::StopBits::OnedotFive,
             StopBitsCfg::Two => crate::port::StopBits::Two,
         }
     }
@@ -85,14 +91,17 @@ impl From<FlowControlCfg> for crate::port::FlowControl {
 
 #[mcp_tool(
     name = "list_ports",
-    description = "List available serial ports on this system"
+    description = "List available serial ports on this system (cached briefly; set fresh=true to re-enumerate)"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
-pub struct ListPortsTool {}
+pub struct ListPortsTool {
+    #[serde(default)]
+    pub fresh: bool,
+}
 
 #[mcp_tool(
     name = "open_port",
-    description = "Open a serial port with configuration"
+    description = "Open a serial port with configuration (port_name may be a path or a configured device alias)"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct OpenPortTool {
@@ -110,25 +119,284 @@ pub struct OpenPortTool {
     pub flow_control: FlowControlCfg,
     #[serde(default)]
     pub terminator: Option<String>,
+    /// Omit to inherit serial.default_idle_disconnect_ms; 0 or null disables
     #[serde(default)]
     pub idle_disconnect_ms: Option<u64>,
+    #[serde(default)]
+    pub read_idle_gap_ms: Option<u64>,
+    #[serde(default)]
+    pub write_checksum: Option<ChecksumKind>,
+    /// Consume the local echo of each write (RS-485 half-duplex)
+    #[serde(default)]
+    pub half_duplex_echo: bool,
+    /// Block until each write has been transmitted
+    #[serde(default)]
+    pub flush_on_write: bool,
+    /// Infer `terminator` from the line endings of the first reads
+    #[serde(default)]
+    pub auto_detect_terminator: bool,
+    /// Clear the high bit of received bytes, which carries parity with 7 data
+    /// bits; defaults to on for `data_bits: seven`, off otherwise
+    #[serde(default)]
+    pub mask_parity_bit: Option<bool>,
+    /// Remove the shell's echo of the last write from the next read
+    #[serde(default)]
+    pub strip_echo: bool,
+    /// Shell prompt (e.g. "> ") removed from the end of read data
+    #[serde(default)]
+    pub strip_prompt: Option<String>,
+    /// Close the port after this long open, even while busy. Omit to inherit
+    /// serial.default_max_open_duration_ms; 0 disables
+    #[serde(default)]
+    pub max_open_duration_ms: Option<u64>,
+    #[serde(default)]
+    pub open_retries: Option<u32>,
+    #[serde(default)]
+    pub open_retry_delay_ms: Option<u64>,
+    /// Probe the device must answer, else the port is closed and open fails
+    #[serde(default)]
+    pub require_response: Option<ResponseProbe>,
+    /// `blocking` (default) or `{"poll": {"interval_ms": 1}}`, which spins on
+    /// the pending byte count for lower latency at the cost of CPU
+    #[serde(default)]
+    pub read_strategy: ReadStrategy,
+    /// Session to link the port to; it stays linked across reconfigure_port
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 #[mcp_tool(
     name = "write",
-    description = "Write UTF-8 data to the open serial port"
+    description = "Write data to the open serial port; encoding is text (default, sent as UTF-8), base64 or hex and is never guessed, so binary payloads must say so. The configured checksum and terminator are appended after decoding; append_terminator=false sends the decoded bytes verbatim, with neither"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct WriteTool {
     pub data: String,
+    /// `text` (default), `base64` or `hex`
+    #[serde(default)]
+    pub encoding: WriteEncoding,
+    /// Append the configured checksum and terminator for this call
+    /// (default true); false sends the bytes exactly as given
+    #[serde(default)]
+    pub append_terminator: Option<bool>,
+}
+
+#[mcp_tool(
+    name = "preview_write",
+    description = "Show the exact bytes `write` would send for data on the open port, as hex and base64, without writing: data is decoded per encoding, checked against the write allowlist, then the configured checksum and terminator are appended unless append_terminator is false"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PreviewWriteTool {
+    pub data: String,
+    /// `text` (default), `base64` or `hex`
+    #[serde(default)]
+    pub encoding: WriteEncoding,
+    /// Append the configured checksum and terminator, as `write` would
+    /// (default true)
+    #[serde(default)]
+    pub append_terminator: Option<bool>,
+}
+
+#[mcp_tool(
+    name = "write_repeat",
+    description = "Write the same data to the open serial port `count` times (optional delay_ms between writes, max 10000, and at most 60000 ms of delays in total); returns total bytes and elapsed time"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WriteRepeatTool {
+    pub data: String,
+    pub count: u32,
+    #[serde(default)]
+    pub delay_ms: Option<u64>,
 }
 
 #[mcp_tool(
     name = "read",
-    description = "Read data from the open serial port (up to 1024 bytes)"
+    description = "Read data from the open serial port (up to 1024 bytes, or max_bytes if given; capped by serial.max_read_bytes); encoding=base64 returns raw bytes base64-encoded, encoding=hexdump returns hexdump -C style lines; read_transform {strip_ansi, strip_control, trim} cleans up utf8 data and returns the original as raw; include_hex also returns the raw bytes as a hex string; long_poll_ms waits up to that long (max 60000) for data instead of the port timeout, returning as soon as any arrives"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ReadTool {
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    /// `utf8` (default), `base64` for raw binary data, or `hexdump`
+    #[serde(default)]
+    pub encoding: Option<ReadEncoding>,
+    /// Clean-up of utf8 data; the untransformed text is returned as `raw`
+    #[serde(default)]
+    pub read_transform: Option<ReadTransform>,
+    /// Also return the bytes read as `hex`
+    #[serde(default)]
+    pub include_hex: bool,
+    /// Wait up to this long for data, returning as soon as any arrives
+    #[serde(default)]
+    pub long_poll_ms: Option<u64>,
+}
+
+#[mcp_tool(
+    name = "read_line",
+    description = "Read one line (up to the port terminator, default \\n) from the open serial port. overall_timeout_ms (default 1000) bounds the total time; idle_timeout_ms, if given, gives up once no byte has arrived for that long. termination reports delimiter, idle_timeout, overall_timeout or max_bytes"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ReadLineTool {
+    #[serde(default)]
+    pub overall_timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub idle_timeout_ms: Option<u64>,
+}
+
+#[mcp_tool(
+    name = "read_until_silence",
+    description = "Capture everything the device sends until it stops talking for silence_ms (e.g. a boot banner). max_bytes (default 65536, capped by serial.max_read_bytes) and overall_timeout_ms (default 10000) bound the capture; termination reports idle_timeout (silence), max_bytes or overall_timeout"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct CaptureUntilSilenceTool {
+    pub silence_ms: u64,
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    #[serde(default)]
+    pub overall_timeout_ms: Option<u64>,
+}
+
+#[mcp_tool(
+    name = "subscribe_reads",
+    description = "Start pushing data received on the serial port to this client as notifications/message frames (logger \"serial\") instead of polling read. The port is polled every interval_ms (default 100); encoding is utf8 (default), base64 or hexdump. Streamed data is not returned by read tools. Replaces an existing subscription"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SubscribeReadsTool {
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+    /// `utf8` (default), `base64` for raw binary data, or `hexdump`
+    #[serde(default)]
+    pub encoding: Option<ReadEncoding>,
+}
+
+#[mcp_tool(
+    name = "unsubscribe_reads",
+    description = "Stop the read notifications started by subscribe_reads"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct UnsubscribeReadsTool {}
+
+#[mcp_tool(
+    name = "decode_bytes",
+    description = "Decode base64 `data` (e.g. from read with encoding=base64) as an array of value_type (u8, i8, u16, i16, u32, i32, f32) numbers; endianness is big (default) or little. Trailing bytes that do not fill a whole value are reported, not decoded"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DecodeBytesTool {
+    /// Standard base64 of the raw bytes
+    pub data: String,
+    pub value_type: NumericType,
+    #[serde(default)]
+    pub endianness: Option<Endianness>,
+}
+
+#[mcp_tool(
+    name = "wait_for_pattern",
+    description = "Read from the open serial port until the regex `pattern` matches the received text or timeout_ms elapses; returns the matched text and whether it timed out"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct WaitForPatternTool {
+    pub pattern: String,
+    pub timeout_ms: u64,
+}
+
+#[mcp_tool(
+    name = "loopback_test",
+    description = "Self-check for loopback plugs and echo devices: clear buffers, write a random token (token_len bytes, default 16) and verify it reads back within timeout_ms (default 1000); returns passed, sent, received and latency_ms"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct LoopbackTestTool {
+    #[serde(default)]
+    pub token_len: Option<u64>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[mcp_tool(
+    name = "benchmark",
+    description = "Qualify a link by timing round trips: write payload (with the configured checksum and terminator) iterations times, each time waiting up to timeout_ms (default 1000) for read_until (default: the port terminator, else \"\\n\"). Holds the port for the whole run; returns min/mean/p50/p90/p99/max latency in ms over the completed round trips, the timeout count and throughput"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct BenchmarkTool {
+    /// Number of round trips, at most 10000
+    pub iterations: u32,
+    pub payload: String,
+    /// Text that ends each reply
+    #[serde(default)]
+    pub read_until: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[mcp_tool(
+    name = "modbus_read_many",
+    description = "Read scattered Modbus RTU registers (holding by default, or input) from unit_id on the open port. Overlapping and adjacent ranges are merged into as few read requests as the 125-register limit allows; ranges at most max_gap (default 0) registers apart are merged too, reading the gap. Each request waits up to timeout_ms (default 1000). Returns values as a map of address to register value, plus the requests issued"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ModbusReadManyTool {
+    /// Modbus unit (slave) address
+    pub unit_id: u8,
+    pub ranges: Vec<RegisterRange>,
+    #[serde(default)]
+    pub register_type: Option<RegisterKind>,
+    /// Unrequested registers that may be read to merge two ranges
+    #[serde(default)]
+    pub max_gap: Option<u16>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[mcp_tool(
+    name = "protocol_command",
+    description = "Send a command using a protocol from the [[protocols]] config (command/response terminators, response_prefix, error_prefix, timeout_ms) and classify the reply line as success, error or timeout; returns the payload with the prefix stripped plus any lines skipped before it (e.g. the echo)"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ProtocolCommandTool {
+    /// Name of a configured protocol
+    pub protocol: String,
+    pub command: String,
+}
+
+#[mcp_tool(
+    name = "set_signals",
+    description = "Set DTR and/or RTS on the open serial port, optionally hold for hold_ms (max 10000) and restore the previous levels (lines never set since open are left as requested); returns the effective levels"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SetSignalsTool {
+    #[serde(default)]
+    pub dtr: Option<bool>,
+    #[serde(default)]
+    pub rts: Option<bool>,
+    #[serde(default)]
+    pub hold_ms: Option<u64>,
+    #[serde(default)]
+    pub restore: Option<bool>,
+}
+
+#[mcp_tool(
+    name = "reset_device",
+    description = "Reset the attached board with its DTR/RTS sequence: board arduino (DTR pulse), esp32 or esp8266 (RTS pulses EN with GPIO0 high), or custom with steps [{dtr, rts, hold_ms}] (at most 32 steps holding 10000 ms in total). Returns the steps executed and the final line levels"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ResetDeviceTool {
+    pub board: ResetBoard,
+    /// Required for `custom`; ignored for presets
+    #[serde(default)]
+    pub steps: Option<Vec<SignalStep>>,
+}
+
+#[mcp_tool(
+    name = "diagnose",
+    description = "Bring-up checklist for a port that will not respond: checks the port is listed, opens it at baud_rate (default 9600), checks modem status lines, probes for common device signatures (AT, SCPI, shell prompt) and runs a short auto-negotiation. Returns each step as pass/fail/skipped with suggestions. The port must not already be open and is closed afterwards"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
-pub struct ReadTool {}
+pub struct DiagnoseTool {
+    pub port_name: String,
+    #[serde(default)]
+    pub baud_rate: Option<u32>,
+    /// Reply timeout for each probe (default 300)
+    #[serde(default)]
+    pub probe_timeout_ms: Option<u64>,
+}
 
 #[mcp_tool(
     name = "close",
@@ -137,56 +405,190 @@ pub struct ReadTool {}
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct CloseTool {}
 
+#[mcp_tool(
+    name = "reconnect",
+    description = "Reopen the port with the settings it last had, e.g. after an idle or inactivity auto-close; no arguments. Settings are forgotten only by an explicit close. Does not restore the session link"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ReconnectTool {}
+
 #[mcp_tool(
     name = "status",
-    description = "Return current port status and configuration"
+    description = "Return current port status and configuration; verify=true also reports physically_present=false when the open port's device has been unplugged"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct StatusTool {
+    /// Also check the open port's device is still attached and report
+    /// `physically_present`; the port is not closed if it is gone
+    #[serde(default)]
+    pub verify: bool,
+}
+
+#[mcp_tool(
+    name = "port_capabilities",
+    description = "Report whether the open port (or, with no port open, this platform) supports break, hardware flow control, live baud changes and modem status lines, plus the supported baud range when known"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PortCapabilitiesTool {}
+
+#[mcp_tool(
+    name = "set_buffer_sizes",
+    description = "Resize the open port's driver receive (rx) and/or transmit (tx) buffers in bytes; omit both to query the current sizes. Returns the requested and effective sizes, which the driver may clamp. Fails with 'Unsupported:' where the port or platform cannot resize its buffers"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
-pub struct StatusTool {}
+pub struct SetBufferSizesTool {
+    #[serde(default)]
+    pub rx: Option<u64>,
+    #[serde(default)]
+    pub tx: Option<u64>,
+}
 
 #[mcp_tool(
     name = "metrics",
-    description = "Return cumulative port IO metrics and timing"
+    description = "Return cumulative port IO metrics and timing; verify=true also reports physically_present for the open port"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct MetricsTool {
+    /// Also report `physically_present` for the open port, as `status` does
+    #[serde(default)]
+    pub verify: bool,
+}
+
+#[mcp_tool(
+    name = "metrics_history",
+    description = "Return recent metrics samples (timestamp, open_id and cumulative counters), oldest first, taken every serial.metrics_history_interval_ms while a port is open; limit returns only the newest samples"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct MetricsHistoryTool {
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
+
+#[mcp_tool(
+    name = "recent_errors",
+    description = "Return recent port failures (timestamp, operation, error kind and message), oldest first, kept in memory up to serial.recent_errors_len; caller mistakes such as a closed port and routine read timeouts are not recorded. limit returns only the newest"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
-pub struct MetricsTool {}
+pub struct RecentErrorsTool {
+    #[serde(default)]
+    pub limit: Option<u64>,
+}
 
 // Reconfigure (close+open) an existing port with new settings, resetting metrics
 #[mcp_tool(
     name = "reconfigure_port",
-    description = "Reopen (or open) the serial port with new configuration, resetting runtime metrics"
+    description = "Reopen (or open) the serial port with new configuration, resetting runtime metrics. Omitted settings keep the open port's values (baud_rate 9600 if no port is open); pass null for terminator, idle_disconnect_ms, read_idle_gap_ms or write_checksum to clear it"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ReconfigurePortTool {
     #[serde(default)]
     pub port_name: Option<String>,
-    #[serde(default = "default_reconfig_baud")]
-    pub baud_rate: u32,
-    #[serde(default = "default_timeout")]
-    pub timeout_ms: u64,
-    #[serde(default = "default_data_bits")]
-    pub data_bits: DataBitsCfg,
-    #[serde(default = "default_parity")]
-    pub parity: ParityCfg,
-    #[serde(default = "default_stop_bits")]
-    pub stop_bits: StopBitsCfg,
-    #[serde(default = "default_flow_control")]
-    pub flow_control: FlowControlCfg,
+    #[serde(default)]
+    pub baud_rate: Option<u32>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub data_bits: Option<DataBitsCfg>,
+    #[serde(default)]
+    pub parity: Option<ParityCfg>,
+    #[serde(default)]
+    pub stop_bits: Option<StopBitsCfg>,
+    #[serde(default)]
+    pub flow_control: Option<FlowControlCfg>,
+    #[serde(default)]
+    pub terminator: Option<String>,
+    #[serde(default)]
+    pub idle_disconnect_ms: Option<u64>,
+    #[serde(default)]
+    pub read_idle_gap_ms: Option<u64>,
+    #[serde(default)]
+    pub write_checksum: Option<ChecksumKind>,
+    /// Consume the local echo of each write (RS-485 half-duplex)
+    #[serde(default)]
+    pub half_duplex_echo: Option<bool>,
+    /// Block until each write has been transmitted
+    #[serde(default)]
+    pub flush_on_write: Option<bool>,
+    /// Keep partially received frame bytes across the reopen
+    #[serde(default)]
+    pub soft_reconfigure: bool,
+}
+
+#[mcp_tool(
+    name = "patch_config",
+    description = "Change only the given settings of the open port, keeping the rest. Line settings (baud_rate, data_bits, parity, stop_bits, flow_control) reopen the port, keeping the linked session and pending frame bytes; other settings apply in place. Pass null for terminator, idle_disconnect_ms, read_idle_gap_ms or write_checksum to clear it"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct PatchConfigTool {
+    #[serde(default)]
+    pub baud_rate: Option<u32>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub data_bits: Option<DataBitsCfg>,
+    #[serde(default)]
+    pub parity: Option<ParityCfg>,
+    #[serde(default)]
+    pub stop_bits: Option<StopBitsCfg>,
+    #[serde(default)]
+    pub flow_control: Option<FlowControlCfg>,
     #[serde(default)]
     pub terminator: Option<String>,
     #[serde(default)]
     pub idle_disconnect_ms: Option<u64>,
+    #[serde(default)]
+    pub read_idle_gap_ms: Option<u64>,
+    #[serde(default)]
+    pub write_checksum: Option<ChecksumKind>,
+    #[serde(default)]
+    pub half_duplex_echo: Option<bool>,
+    #[serde(default)]
+    pub flush_on_write: Option<bool>,
+}
+
+#[mcp_tool(
+    name = "set_framing",
+    description = "Change data_bits, parity and/or stop_bits of the open port without reopening it, so buffered data is kept (e.g. to emulate 9-bit addressing with parity). Ports that cannot change framing live are reopened instead, keeping the linked session, and the result carries a warning"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SetFramingTool {
+    #[serde(default)]
+    pub data_bits: Option<DataBitsCfg>,
+    #[serde(default)]
+    pub parity: Option<ParityCfg>,
+    #[serde(default)]
+    pub stop_bits: Option<StopBitsCfg>,
 }
 
 // --- Session Tool Schemas ---
 #[mcp_tool(
     name = "create_session",
-    description = "Create a new session for a logical device id"
+    description = "Create a new session for a logical device id, optionally recording the device's USB signature (vid, pid, device_serial, manufacturer). When port_name is a configured device alias, parts not given are filled in from the connected device"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct CreateSessionTool {
     pub device_id: String,
     pub port_name: Option<String>,
+    /// USB vendor ID as listed by list_ports_extended, e.g. "0x2341"
+    #[serde(default)]
+    pub vid: Option<String>,
+    /// USB product ID, e.g. "0x0043"
+    #[serde(default)]
+    pub pid: Option<String>,
+    #[serde(default)]
+    pub device_serial: Option<String>,
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+}
+
+#[mcp_tool(
+    name = "get_or_create_session",
+    description = "Return the open session for a device id, creating one only if none is open"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct GetOrCreateSessionTool {
+    pub device_id: String,
+    pub port_name: Option<String>,
 }
 
 #[mcp_tool(
@@ -203,6 +605,16 @@ pub struct AppendMessageTool {
     pub latency_ms: Option<i64>,
 }
 
+#[mcp_tool(
+    name = "append_messages",
+    description = "Append many messages to a session timeline in one transaction; returns their ids in order"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct AppendMessagesTool {
+    pub session_id: String,
+    pub messages: Vec<NewMessage>,
+}
+
 #[mcp_tool(
     name = "list_messages",
     description = "List messages for a session (ascending)"
@@ -288,38 +700,126 @@ pub struct ListMessagesRangeTool {
 }
 
 #[mcp_tool(
-    name = "list_ports_extended",
-    description = "List serial ports with extended metadata (VID/PID, manufacturer, product, serial number, type)"
+    name = "replay_session",
+    description = "Re-send a session's logged outbound (direction=sent) messages, in order, to the open port. inter_message_delay_ms waits between writes; preserve_timing instead reuses the gaps between the original messages. capture_responses reads each reply until response_silence_ms (default 200) of silence and logs both sides into a new session"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
-pub struct ListPortsExtendedTool {}
+pub struct ReplaySessionTool {
+    pub session_id: String,
+    #[serde(default)]
+    pub inter_message_delay_ms: Option<u64>,
+    /// Wait as long between writes as the original messages were apart
+    #[serde(default)]
+    pub preserve_timing: bool,
+    #[serde(default)]
+    pub capture_responses: bool,
+    #[serde(default)]
+    pub response_silence_ms: Option<u64>,
+}
 
-// Phase 4: Auto-negotiation tools (require auto-negotiation feature)
-#[cfg(feature = "auto-negotiation")]
 #[mcp_tool(
-    name = "detect_port",
-    description = "Auto-detect baud rate and parameters for a port using negotiation strategies"
+    name = "diff_sessions",
+    description = "Compare an expected (golden) session with an actual run. Messages are aligned by role and direction (the n-th sent command with the n-th, the n-th reply with the n-th); returns each content mismatch, missing or unexpected message with both message ids, plus latency deltas. latency_threshold_ms also reports matching replies whose latency changed by more than that"
 )]
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
-pub struct DetectPortTool {
-    pub port_name: String,
-    #[serde(default)]
-    pub vid: Option<String>,
-    #[serde(default)]
-    pub pid: Option<String>,
+pub struct DiffSessionsTool {
+    pub expected_session_id: String,
+    pub actual_session_id: String,
     #[serde(default)]
-    pub manufacturer: Option<String>,
+    pub latency_threshold_ms: Option<u64>,
+}
+
+#[mcp_tool(
+    name = "list_ports_extended",
+    description = "List serial ports with extended metadata (VID/PID, manufacturer, product, serial number, USB bus/port location, type; set fresh=true to bypass the cache)"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ListPortsExtendedTool {
+    #[serde(default)]
+    pub fresh: bool,
+}
+
+#[mcp_tool(
+    name = "open_only_port",
+    description = "For single-adapter machines: enumerate serial ports and, if exactly one is present, open it. Takes the open_port arguments except port_name; with no port or several nothing is opened and the error lists the ports found"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct OpenOnlyPortTool {}
+
+/// `open_only_port` takes the `open_port` arguments minus `port_name`, so its
+/// schema is derived from [`OpenPortTool`] instead of declared twice.
+fn open_only_port_tool() -> rust_mcp_sdk::schema::Tool {
+    let mut schema = OpenPortTool::tool().input_schema;
+    if let Some(properties) = schema.properties.as_mut() {
+        properties.remove("port_name");
+    }
+    schema.required.retain(|name| name != "port_name");
+    rust_mcp_sdk::schema::Tool {
+        input_schema: schema,
+        ..OpenOnlyPortTool::tool()
+    }
+}
+
+#[mcp_tool(
+    name = "find_port_by_serial",
+    description = "Find the port name of a USB device by VID/PID (hex strings), exact serial number and/or manufacturer substring; returns the first match, or every match with all=true"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct FindPortTool {
+    #[serde(default)]
+    pub vid: Option<String>,
+    #[serde(default)]
+    pub pid: Option<String>,
+    #[serde(default)]
+    pub serial: Option<String>,
+    /// Case-insensitive
+    #[serde(default)]
+    pub manufacturer_contains: Option<String>,
+    #[serde(default)]
+    pub all: bool,
+    #[serde(default)]
+    pub fresh: bool,
+}
+
+// Phase 4: Auto-negotiation tools (require auto-negotiation feature)
+#[cfg(feature = "auto-negotiation")]
+#[mcp_tool(
+    name = "detect_port",
+    description = "Auto-detect baud rate and parameters for a port using negotiation strategies"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DetectPortTool {
+    pub port_name: String,
+    #[serde(default)]
+    pub vid: Option<String>,
+    #[serde(default)]
+    pub pid: Option<String>,
+    #[serde(default)]
+    pub manufacturer: Option<String>,
     #[serde(default)]
     pub suggested_baud_rates: Option<Vec<u32>>,
     #[serde(default = "default_detect_timeout_ms")]
     pub timeout_ms: u64,
     #[serde(default)]
     pub preferred_strategy: Option<String>,
+    /// Wait after each open before probing (0 = use the manufacturer profile)
+    #[serde(default)]
+    pub settle_delay_ms: u64,
+    /// Reuse the last successful detection of this port instead of probing
+    #[serde(default = "default_use_cache")]
+    pub use_cache: bool,
+    /// Probe again even if a result is cached, replacing it
+    #[serde(default)]
+    pub force: bool,
 }
 #[cfg(feature = "auto-negotiation")]
 fn default_detect_timeout_ms() -> u64 {
     500
 }
+#[cfg(feature = "auto-negotiation")]
+fn default_use_cache() -> bool {
+    true
+}
 
 #[cfg(feature = "auto-negotiation")]
 #[mcp_tool(
@@ -339,8 +839,25 @@ pub struct OpenPortAutoTool {
     pub timeout_ms: u64,
     #[serde(default)]
     pub terminator: Option<String>,
+    /// Omit to inherit serial.default_idle_disconnect_ms; 0 or null disables
     #[serde(default)]
     pub idle_disconnect_ms: Option<u64>,
+    #[serde(default)]
+    pub read_idle_gap_ms: Option<u64>,
+    #[serde(default)]
+    pub write_checksum: Option<ChecksumKind>,
+    /// Consume the local echo of each write (RS-485 half-duplex)
+    #[serde(default)]
+    pub half_duplex_echo: bool,
+    /// Block until each write has been transmitted
+    #[serde(default)]
+    pub flush_on_write: bool,
+    /// Reuse the last successful detection of this port instead of probing
+    #[serde(default = "default_use_cache")]
+    pub use_cache: bool,
+    /// Probe again even if a result is cached, replacing it
+    #[serde(default)]
+    pub force: bool,
 }
 
 #[cfg(feature = "auto-negotiation")]
@@ -351,40 +868,631 @@ pub struct OpenPortAutoTool {
 #[derive(Debug, Deserialize, Serialize, JsonSchema)]
 pub struct ListManufacturerProfilesTool {}
 
+#[mcp_tool(
+    name = "cancel_read",
+    description = "Interrupt a long-running read on the open port (wait_for_pattern, read_line, loopback_test, idle-gap read); the interrupted call fails with 'Operation cancelled'"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct CancelReadTool {}
+
+#[mcp_tool(
+    name = "describe_tools",
+    description = "Return example arguments for the serial tools and the accepted spellings of enum arguments (data_bits, parity, stop_bits, flow_control, ...)"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct DescribeToolsTool {}
+
+#[mcp_tool(
+    name = "server_info",
+    description = "Report the server version and which optional tool groups are compiled in; a disabled group names the Cargo feature that enables it (e.g. why detect_port is missing)"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ServerInfoTool {}
+
+#[mcp_tool(
+    name = "get_config",
+    description = "Return the effective configuration (config file merged with RUST_COMM_* environment overrides) and the file it was loaded from, or \"defaults\"; secrets are redacted"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct GetConfigTool {}
+
+#[mcp_tool(
+    name = "reload_config",
+    description = "Reload the configuration file, validate it and apply the runtime settings (serial limits and defaults, device aliases, logging level). Returns the changed keys; an invalid file is rejected and the current config kept"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ReloadConfigTool {}
+
+#[mcp_tool(
+    name = "reset_subsystem",
+    description = "Recover a wedged serial stack without restarting the server: cancels any read, closes the port, restarts the subscribe_reads stream (if one is running) and the writer and clears the port list and detection caches. Sessions are kept. Returns what was reset"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ResetSubsystemTool {}
+
+#[cfg(feature = "tui")]
+#[mcp_tool(
+    name = "list_themes",
+    description = "List the built-in TUI theme names and the currently selected one"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ListThemesTool {}
+
+#[cfg(feature = "tui")]
+#[mcp_tool(
+    name = "set_theme",
+    description = "Switch the theme of a TUI running in this server process; applied on its next render tick. Use list_themes for valid names"
+)]
+#[derive(Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SetThemeTool {
+    pub name: String,
+}
+
 // Future: binary read/write, streaming subscriptions, configure line endings, etc.
 
+/// Accepted string values for enum arguments of `open_port` / `reconfigure_port`.
+/// The line settings are matched case-insensitively.
+const DATA_BITS_SPELLINGS: &[&str] = &["5", "6", "7", "8", "five", "six", "seven", "eight"];
+const PARITY_SPELLINGS: &[&str] = &["none", "odd", "even"];
+const STOP_BITS_SPELLINGS: &[&str] = &["1", "1.5", "2", "one", "one_point_five", "two"];
+const FLOW_CONTROL_SPELLINGS: &[&str] = &["none", "hardware", "rtscts", "software", "xonxoff"];
+const WRITE_CHECKSUM_SPELLINGS: &[&str] = &["crc16_modbus", "crc8", "xor8", "sum8"];
+const READ_ENCODING_SPELLINGS: &[&str] = &["utf8", "base64", "hexdump"];
+const WRITE_ENCODING_SPELLINGS: &[&str] = &["text", "base64", "hex"];
+
+/// Accepted enum spellings, keyed by argument name.
+fn enum_spellings() -> serde_json::Value {
+    json!({
+        "data_bits": DATA_BITS_SPELLINGS,
+        "parity": PARITY_SPELLINGS,
+        "stop_bits": STOP_BITS_SPELLINGS,
+        "flow_control": FLOW_CONTROL_SPELLINGS,
+        "write_checksum": WRITE_CHECKSUM_SPELLINGS,
+        "encoding": READ_ENCODING_SPELLINGS,
+        "write_encoding": WRITE_ENCODING_SPELLINGS,
+    })
+}
+
+/// Tools that only exist when a Cargo feature is enabled.
+struct OptionalToolGroup {
+    feature: &'static str,
+    enabled: bool,
+    tools: &'static [&'static str],
+}
+
+const OPTIONAL_TOOL_GROUPS: &[OptionalToolGroup] = &[
+    OptionalToolGroup {
+        feature: "auto-negotiation",
+        enabled: cfg!(feature = "auto-negotiation"),
+        tools: &[
+            "detect_port",
+            "open_port_auto",
+            "list_manufacturer_profiles",
+        ],
+    },
+    OptionalToolGroup {
+        feature: "tui",
+        enabled: cfg!(feature = "tui"),
+        tools: &["list_themes", "set_theme"],
+    },
+];
+
+/// Availability of each optional tool group, as reported by `server_info`.
+fn tool_groups() -> serde_json::Value {
+    OPTIONAL_TOOL_GROUPS
+        .iter()
+        .map(|group| {
+            let mut entry = json!({
+                "feature": group.feature,
+                "enabled": group.enabled,
+                "tools": group.tools,
+            });
+            if !group.enabled {
+                entry["reason"] = json!(format!(
+                    "built without the '{}' feature; rebuild with --features {}",
+                    group.feature, group.feature
+                ));
+            }
+            entry
+        })
+        .collect()
+}
+
+/// Example arguments for the serial port tools.
+fn tool_examples() -> Vec<(&'static str, serde_json::Value)> {
+    vec![
+        ("list_ports", json!({"fresh": false})),
+        (
+            "find_port_by_serial",
+            json!({"vid": "0x0403", "serial": "A50285BI"}),
+        ),
+        (
+            "open_port",
+            json!({
+                "port_name": "/dev/ttyUSB0",
+                "baud_rate": 115200,
+                "data_bits": "8",
+                "parity": "none",
+                "stop_bits": "1",
+                "flow_control": "none",
+                "terminator": "\r\n",
+                "timeout_ms": 1000
+            }),
+        ),
+        (
+            "open_only_port",
+            json!({"baud_rate": 115200, "terminator": "\r\n"}),
+        ),
+        ("write", json!({"data": "AT"})),
+        (
+            "preview_write",
+            json!({"data": "01 03 00 00 00 02", "encoding": "hex"}),
+        ),
+        (
+            "write_repeat",
+            json!({"data": "PING", "count": 3, "delay_ms": 100}),
+        ),
+        (
+            "read",
+            json!({
+                "max_bytes": 256,
+                "encoding": "utf8",
+                "read_transform": {"strip_ansi": true, "trim": true}
+            }),
+        ),
+        (
+            "read_line",
+            json!({"overall_timeout_ms": 2000, "idle_timeout_ms": 200}),
+        ),
+        (
+            "read_until_silence",
+            json!({"silence_ms": 500, "max_bytes": 65536, "overall_timeout_ms": 10000}),
+        ),
+        (
+            "subscribe_reads",
+            json!({"interval_ms": 100, "encoding": "utf8"}),
+        ),
+        (
+            "decode_bytes",
+            json!({"data": "AAEAAg==", "value_type": "u16", "endianness": "big"}),
+        ),
+        (
+            "wait_for_pattern",
+            json!({"pattern": "READY|OK", "timeout_ms": 5000}),
+        ),
+        (
+            "loopback_test",
+            json!({"token_len": 16, "timeout_ms": 1000}),
+        ),
+        (
+            "benchmark",
+            json!({"iterations": 100, "payload": "PING", "read_until": "\r\n"}),
+        ),
+        (
+            "modbus_read_many",
+            json!({
+                "unit_id": 1,
+                "ranges": [{"start": 0, "count": 4}, {"start": 4, "count": 2}, {"start": 100, "count": 1}],
+                "max_gap": 8
+            }),
+        ),
+        (
+            "protocol_command",
+            json!({"protocol": "sensor", "command": "READ TEMP"}),
+        ),
+        (
+            "set_signals",
+            json!({"dtr": false, "rts": true, "hold_ms": 100, "restore": true}),
+        ),
+        ("set_buffer_sizes", json!({"rx": 16384, "tx": 4096})),
+        (
+            "reconfigure_port",
+            json!({"baud_rate": 9600, "data_bits": "seven", "parity": "even", "stop_bits": "one"}),
+        ),
+        (
+            "patch_config",
+            json!({"baud_rate": 115200, "terminator": null}),
+        ),
+        ("set_framing", json!({"parity": "even"})),
+        (
+            "reset_device",
+            json!({"board": "custom", "steps": [{"dtr": false, "rts": true, "hold_ms": 100}, {"rts": false}]}),
+        ),
+        (
+            "diagnose",
+            json!({"port_name": "/dev/ttyUSB0", "baud_rate": 115200}),
+        ),
+        (
+            "replay_session",
+            json!({"session_id": "<session id>", "inter_message_delay_ms": 100, "capture_responses": true}),
+        ),
+        (
+            "diff_sessions",
+            json!({"expected_session_id": "<golden session id>", "actual_session_id": "<session id>", "latency_threshold_ms": 50}),
+        ),
+        ("cancel_read", json!({})),
+        ("close", json!({})),
+        ("reconnect", json!({})),
+        ("status", json!({"verify": true})),
+        ("metrics", json!({})),
+        ("recent_errors", json!({"limit": 10})),
+    ]
+}
+
+/// Convert a count argument to `usize`, saturating where it does not fit so
+/// the service's own limits reject it.
+fn saturating_usize(n: u64) -> usize {
+    usize::try_from(n).unwrap_or(usize::MAX)
+}
+
+/// Parse `idle_disconnect_ms` for open tools: absent inherits the server
+/// default (`None`), an explicit `null` disables it (`Some(0)`).
+fn parse_idle_disconnect_arg(args: &serde_json::Map<String, serde_json::Value>) -> Option<u64> {
+    match args.get("idle_disconnect_ms") {
+        None => None,
+        Some(serde_json::Value::Null) => Some(0),
+        Some(v) => v.as_u64(),
+    }
+}
+
+/// Line settings given as strings, `None` where the key is absent.
+struct LineSettingsArgs {
+    data_bits: Option<DataBitsCfg>,
+    parity: Option<ParityCfg>,
+    stop_bits: Option<StopBitsCfg>,
+    flow_control: Option<FlowControlCfg>,
+}
+
+/// Parse `data_bits` / `parity` / `stop_bits` / `flow_control`, accepting the
+/// tolerant enum spellings listed by `describe_tools`.
+fn parse_line_settings(
+    tool_name: String,
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<LineSettingsArgs, CallToolError> {
+    let invalid =
+        |message: String| CallToolError::invalid_arguments(tool_name.clone(), Some(message));
+    // Numbers are taken as their spelling, so `8` and `1.5` read like "8" and "1.5"
+    let parse_enum = |key: &str| match args.get(key) {
+        Some(serde_json::Value::String(s)) => Some(s.to_lowercase()),
+        Some(serde_json::Value::Number(n)) => Some(n.to_string()),
+        _ => None,
+    };
+    let data_bits = match parse_enum("data_bits").as_deref() {
+        None => None,
+        Some("5" | "five") => Some(DataBitsCfg::Five),
+        Some("6" | "six") => Some(DataBitsCfg::Six),
+        Some("7" | "seven") => Some(DataBitsCfg::Seven),
+        Some("8" | "eight") => Some(DataBitsCfg::Eight),
+        Some(other) => return Err(invalid(format!("invalid data_bits: {other}"))),
+    };
+    let parity = match parse_enum("parity").as_deref() {
+        None => None,
+        Some("none") => Some(ParityCfg::None),
+        Some("odd") => Some(ParityCfg::Odd),
+        Some("even") => Some(ParityCfg::Even),
+        Some(other) => return Err(invalid(format!("invalid parity: {other}"))),
+    };
+    let stop_bits = match parse_enum("stop_bits").as_deref() {
+        None => None,
+        Some("1" | "one") => Some(StopBitsCfg::One),
+        Some("1.5" | "one_point_five") => Some(StopBitsCfg::OnedotFive),
+        Some("2" | "two") => Some(StopBitsCfg::Two),
+        Some(other) => return Err(invalid(format!("invalid stop_bits: {other}"))),
+    };
+    let flow_control = match parse_enum("flow_control").as_deref() {
+        None => None,
+        Some("none") => Some(FlowControlCfg::None),
+        Some("hardware" | "rtscts") => Some(FlowControlCfg::Hardware),
+        Some("software" | "xonxoff") => Some(FlowControlCfg::Software),
+        Some(other) => return Err(invalid(format!("invalid flow_control: {other}"))),
+    };
+    Ok(LineSettingsArgs {
+        data_bits,
+        parity,
+        stop_bits,
+        flow_control,
+    })
+}
+
+/// Parse the port settings of `patch_config` / `reconfigure_port`. Absent
+/// keys stay unchanged and `null` clears a nullable setting; enum spellings
+/// are the same as `open_port`.
+fn parse_settings_patch(
+    tool_name: String,
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<PortConfigPatch, CallToolError> {
+    let LineSettingsArgs {
+        data_bits,
+        parity,
+        stop_bits,
+        flow_control,
+    } = parse_line_settings(tool_name.clone(), args)?;
+    // The remaining fields use the patch's own null-aware deserialization
+    let mut rest = args.clone();
+    for key in ["data_bits", "parity", "stop_bits", "flow_control"] {
+        rest.remove(key);
+    }
+    let patch = serde_json::from_value::<PortConfigPatch>(serde_json::Value::Object(rest))
+        .map_err(|e| CallToolError::invalid_arguments(tool_name, Some(e.to_string())))?;
+    Ok(PortConfigPatch {
+        data_bits,
+        parity,
+        stop_bits,
+        flow_control,
+        ..patch
+    })
+}
+
+/// Parse `open_port` arguments, accepting the tolerant enum spellings listed
+/// by `describe_tools`.
+fn parse_open_port_args(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<OpenPortTool, CallToolError> {
+    let port_name = args
+        .get("port_name")
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| {
+            CallToolError::invalid_arguments(
+                OpenPortTool::tool_name(),
+                Some("port_name missing".into()),
+            )
+        })?
+        .to_string();
+    let baud_rate = args
+        .get("baud_rate")
+        .and_then(|v| v.as_u64())
+        .ok_or_else(|| {
+            CallToolError::invalid_arguments(
+                OpenPortTool::tool_name(),
+                Some("baud_rate missing".into()),
+            )
+        })? as u32;
+    let timeout_ms = args
+        .get("timeout_ms")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1000);
+    let line = parse_line_settings(OpenPortTool::tool_name(), args)?;
+    let data_bits = line.data_bits.unwrap_or_else(default_data_bits);
+    let parity = line.parity.unwrap_or_else(default_parity);
+    let stop_bits = line.stop_bits.unwrap_or_else(default_stop_bits);
+    let flow_control = line.flow_control.unwrap_or_else(default_flow_control);
+    let terminator = args
+        .get("terminator")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let idle_disconnect_ms = parse_idle_disconnect_arg(args);
+    let read_idle_gap_ms = args.get("read_idle_gap_ms").and_then(|v| v.as_u64());
+    let write_checksum = match args.get("write_checksum") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(v) => Some(
+            serde_json::from_value::<ChecksumKind>(v.clone()).map_err(|_| {
+                CallToolError::invalid_arguments(
+                    OpenPortTool::tool_name(),
+                    Some("write_checksum must be one of crc16_modbus, crc8, xor8, sum8".into()),
+                )
+            })?,
+        ),
+    };
+    let open_retries = args
+        .get("open_retries")
+        .and_then(|v| v.as_u64())
+        .map(|v| v.min(u32::MAX as u64) as u32);
+    let open_retry_delay_ms = args.get("open_retry_delay_ms").and_then(|v| v.as_u64());
+    let half_duplex_echo = args
+        .get("half_duplex_echo")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let flush_on_write = args
+        .get("flush_on_write")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let auto_detect_terminator = args
+        .get("auto_detect_terminator")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let mask_parity_bit = args.get("mask_parity_bit").and_then(|v| v.as_bool());
+    let strip_echo = args
+        .get("strip_echo")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let strip_prompt = args
+        .get("strip_prompt")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let max_open_duration_ms = args.get("max_open_duration_ms").and_then(|v| v.as_u64());
+    let require_response = match args.get("require_response") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(v) => Some(
+            serde_json::from_value::<ResponseProbe>(v.clone()).map_err(|e| {
+                CallToolError::invalid_arguments(
+                    OpenPortTool::tool_name(),
+                    Some(format!("require_response: {}", e)),
+                )
+            })?,
+        ),
+    };
+    let read_strategy = match args.get("read_strategy") {
+        None | Some(serde_json::Value::Null) => ReadStrategy::default(),
+        Some(v) => serde_json::from_value::<ReadStrategy>(v.clone()).map_err(|e| {
+            CallToolError::invalid_arguments(
+                OpenPortTool::tool_name(),
+                Some(format!("read_strategy: {}", e)),
+            )
+        })?,
+    };
+    let session_id = args
+        .get("session_id")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    Ok(OpenPortTool {
+        port_name,
+        baud_rate,
+        timeout_ms,
+        data_bits,
+        parity,
+        stop_bits,
+        flow_control,
+        terminator,
+        idle_disconnect_ms,
+        read_idle_gap_ms,
+        write_checksum,
+        half_duplex_echo,
+        flush_on_write,
+        auto_detect_terminator,
+        mask_parity_bit,
+        strip_echo,
+        strip_prompt,
+        max_open_duration_ms,
+        open_retries,
+        open_retry_delay_ms,
+        require_response,
+        read_strategy,
+        session_id,
+    })
+}
+
+/// Parse `reconfigure_port` arguments. Omitted settings are inherited from
+/// the open port; enum spellings are the same as `open_port`.
+fn parse_reconfigure_port_args(
+    args: &serde_json::Map<String, serde_json::Value>,
+) -> Result<ReconfigureConfig, CallToolError> {
+    Ok(ReconfigureConfig {
+        port_name: args
+            .get("port_name")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string()),
+        settings: parse_settings_patch(ReconfigurePortTool::tool_name(), args)?,
+        soft_reconfigure: args
+            .get("soft_reconfigure")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+    })
+}
+
 // ------------------ Handler ------------------
 pub struct SerialServerHandler {
     pub service: Arc<PortService>,
     pub sessions: SessionStore,
+    pub port_cache: Arc<crate::port::PortListCache>,
+    pub config: crate::config::SharedConfig,
     #[cfg(feature = "auto-negotiation")]
     pub state: AppState, // Needed for auto-negotiation direct state access
+    /// Negotiator shared across calls, so `detect_port` results are cached
+    #[cfg(feature = "auto-negotiation")]
+    pub negotiator: Arc<crate::negotiation::AutoNegotiator>,
+    /// Reader task started by `subscribe_reads`, if any
+    pub read_stream: Arc<Mutex<Option<ReadStream>>>,
+}
+
+/// A `subscribe_reads` reader task, with the settings to start it again.
+pub struct ReadStream {
+    task: JoinHandle<()>,
+    interval: Duration,
+    encoding: ReadEncoding,
+    notify: mpsc::UnboundedSender<LoggingMessageNotificationParams>,
+}
+
+impl ReadStream {
+    fn start(
+        service: &PortService,
+        interval: Duration,
+        encoding: ReadEncoding,
+        notify: mpsc::UnboundedSender<LoggingMessageNotificationParams>,
+    ) -> Self {
+        let sender = notify.clone();
+        let task = service.spawn_read_stream(interval, encoding, move |result| {
+            sender.send(read_notification(&result)).is_ok()
+        });
+        Self {
+            task,
+            interval,
+            encoding,
+            notify,
+        }
+    }
+
+    /// Stop this task and start a fresh one with the same settings.
+    fn restart(self, service: &PortService) -> Self {
+        self.task.abort();
+        Self::start(service, self.interval, self.encoding, self.notify)
+    }
+}
+
+/// Default polling interval of `subscribe_reads`.
+const DEFAULT_READ_STREAM_INTERVAL_MS: u64 = 100;
+
+/// Default quiet period that ends a captured reply in `replay_session`.
+const DEFAULT_REPLAY_SILENCE_MS: u64 = 200;
+
+/// Upper bound on capturing one reply in `replay_session`.
+const REPLAY_RESPONSE_TIMEOUT_MS: u64 = 10_000;
+
+/// `notifications/message` payload for one streamed read.
+fn read_notification(result: &ReadResult) -> LoggingMessageNotificationParams {
+    LoggingMessageNotificationParams {
+        data: json!({
+            "open_id": result.open_id,
+            "data": result.data,
+            "bytes_read": result.bytes_read,
+            "bytes_read_total": result.bytes_read_total,
+            "auto_closed": result.auto_closed,
+            "timestamp": chrono::Utc::now().to_rfc3339(),
+        }),
+        level: LoggingLevel::Info,
+        logger: Some("serial".into()),
+    }
 }
 
 impl SerialServerHandler {
     // Helper to convert ServiceError to CallToolError
     fn map_service_error(err: ServiceError) -> CallToolError {
-        CallToolError::from_message(err.to_string())
+        match err {
+            // Prefixed so agents can tell a fixable permissions problem apart
+            ServiceError::PermissionDenied(_) => {
+                CallToolError::from_message(format!("PermissionDenied: {}", err))
+            }
+            ServiceError::Unsupported(_) => {
+                CallToolError::from_message(format!("Unsupported: {}", err))
+            }
+            _ => CallToolError::from_message(err.to_string()),
+        }
     }
 
-    fn list_ports_impl(&self) -> Result<CallToolResult, CallToolError> {
-        let ports = serialport::available_ports()
-            .map_err(|e| CallToolError::from_message(e.to_string()))?;
+    /// Run a service call that blocks on the port on the blocking pool, so
+    /// long reads and waits do not stall the async runtime.
+    async fn run_blocking<T: Send + 'static>(
+        &self,
+        call: impl FnOnce(&PortService) -> ServiceResult<T> + Send + 'static,
+    ) -> Result<T, CallToolError> {
+        let service = Arc::clone(&self.service);
+        tokio::task::spawn_blocking(move || call(&service))
+            .await
+            .map_err(|e| CallToolError::from_message(format!("task failed: {}", e)))?
+            .map_err(Self::map_service_error)
+    }
+
+    fn list_ports_impl(&self, params: ListPortsTool) -> Result<CallToolResult, CallToolError> {
+        let (ports, warning) = self.port_cache.list_or_warn(params.fresh);
         let names: Vec<_> = ports
             .into_iter()
             .map(|p| json!({"port_name": p.port_name}))
             .collect();
         let mut structured = serde_json::Map::new();
         structured.insert("ports".into(), serde_json::Value::Array(names));
+        if let Some(w) = warning {
+            structured.insert("warning".into(), json!(w));
+        }
         Ok(
             CallToolResult::text_content(vec![TextContent::from("ports listed".to_string())])
                 .with_structured_content(structured),
         )
     }
-    fn list_ports_extended_impl(&self) -> Result<CallToolResult, CallToolError> {
+    fn list_ports_extended_impl(
+        &self,
+        params: ListPortsExtendedTool,
+    ) -> Result<CallToolResult, CallToolError> {
         use serialport::SerialPortType;
-        let ports = serialport::available_ports()
-            .map_err(|e| CallToolError::from_message(e.to_string()))?;
+        let (ports, warning) = self.port_cache.list_or_warn(params.fresh);
         let detailed: Vec<_> = ports
             .into_iter()
             .map(|p| {
@@ -404,6 +1512,9 @@ impl SerialServerHandler {
                         if let Some(prod) = info.product {
                             obj.insert("product".into(), json!(prod));
                         }
+                        if let Some(location) = info.location {
+                            obj.insert("usb_location".into(), json!(location.to_string()));
+                        }
                     }
                     SerialPortType::BluetoothPort => {
                         obj.insert("transport".into(), json!("bluetooth"));
@@ -420,12 +1531,95 @@ impl SerialServerHandler {
             .collect();
         let mut structured = serde_json::Map::new();
         structured.insert("ports".into(), serde_json::Value::Array(detailed));
+        if let Some(w) = warning {
+            structured.insert("warning".into(), json!(w));
+        }
         Ok(
             CallToolResult::text_content(vec![TextContent::from("ports detailed".to_string())])
                 .with_structured_content(structured),
         )
     }
-    fn open_port_impl(&self, tool: OpenPortTool) -> Result<CallToolResult, CallToolError> {
+    fn find_port_impl(&self, params: FindPortTool) -> Result<CallToolResult, CallToolError> {
+        let parse_id = |field: &str, value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|v| u16::from_str_radix(v.trim_start_matches("0x"), 16))
+                .transpose()
+                .map_err(|e| {
+                    CallToolError::invalid_arguments(
+                        FindPortTool::tool_name(),
+                        Some(format!("invalid {field}: {e}")),
+                    )
+                })
+        };
+        let query = PortQuery {
+            vid: parse_id("vid", &params.vid)?,
+            pid: parse_id("pid", &params.pid)?,
+            serial: params.serial,
+            manufacturer_contains: params.manufacturer_contains,
+        };
+        if query.is_empty() {
+            return Err(CallToolError::invalid_arguments(
+                FindPortTool::tool_name(),
+                Some("set at least one of vid, pid, serial or manufacturer_contains".into()),
+            ));
+        }
+        let ports = self
+            .port_cache
+            .list(params.fresh)
+            .map_err(|e| CallToolError::from_message(format!("port enumeration failed: {e}")))?;
+        let mut matches = query.find(&ports);
+        if matches.is_empty() {
+            return Err(CallToolError::from_message(format!(
+                "no connected USB port matches {query:?}"
+            )));
+        }
+        if !params.all {
+            matches.truncate(1);
+        }
+        let first = matches[0].clone();
+        let mut structured = serde_json::Map::new();
+        structured.insert("port_name".into(), json!(first));
+        structured.insert("matches".into(), json!(matches));
+        Ok(CallToolResult::text_content(vec![TextContent::from(first)])
+            .with_structured_content(structured))
+    }
+    /// Open the only port present, with `args` as for `open_port` minus
+    /// `port_name`. Always enumerates afresh.
+    async fn open_only_port_impl(
+        &self,
+        mut args: serde_json::Map<String, serde_json::Value>,
+    ) -> Result<CallToolResult, CallToolError> {
+        if args.contains_key("port_name") {
+            return Err(CallToolError::invalid_arguments(
+                OpenOnlyPortTool::tool_name(),
+                Some("port_name is chosen automatically; use open_port for a specific port".into()),
+            ));
+        }
+        let ports = self
+            .port_cache
+            .list(true)
+            .map_err(|e| CallToolError::from_message(format!("port enumeration failed: {e}")))?;
+        match ports.as_slice() {
+            [only] => {
+                args.insert("port_name".into(), json!(only.port_name));
+                let params = parse_open_port_args(&args)?;
+                self.open_port_impl(params).await
+            }
+            [] => Err(CallToolError::from_message(
+                "no serial ports found; nothing opened",
+            )),
+            _ => {
+                let names: Vec<&str> = ports.iter().map(|p| p.port_name.as_str()).collect();
+                Err(CallToolError::from_message(format!(
+                    "{} serial ports found, expected exactly one: [{}]; open one with open_port",
+                    names.len(),
+                    names.join(", ")
+                )))
+            }
+        }
+    }
+    async fn open_port_impl(&self, tool: OpenPortTool) -> Result<CallToolResult, CallToolError> {
         let config = OpenConfig {
             port_name: tool.port_name,
             baud_rate: tool.baud_rate,
@@ -436,21 +1630,64 @@ impl SerialServerHandler {
             flow_control: tool.flow_control,
             terminator: tool.terminator,
             idle_disconnect_ms: tool.idle_disconnect_ms,
+            read_idle_gap_ms: tool.read_idle_gap_ms,
+            write_checksum: tool.write_checksum,
+            half_duplex_echo: tool.half_duplex_echo,
+            flush_on_write: tool.flush_on_write,
+            auto_detect_terminator: tool.auto_detect_terminator,
+            mask_parity_bit: tool.mask_parity_bit,
+            strip_echo: tool.strip_echo,
+            strip_prompt: tool.strip_prompt,
+            max_open_duration_ms: tool.max_open_duration_ms,
+            open_retries: tool.open_retries,
+            open_retry_delay_ms: tool.open_retry_delay_ms,
+            require_response: tool.require_response,
+            read_strategy: tool.read_strategy,
         };
 
-        self.service.open(config).map_err(Self::map_service_error)?;
+        // Open retries back off and require_response waits for a reply, so
+        // keep them off the async executor
+        let result = self
+            .run_blocking(move |service| service.open(config))
+            .await?;
+        if tool.session_id.is_some() {
+            self.service
+                .link_session(tool.session_id.clone())
+                .map_err(Self::map_service_error)?;
+        }
 
-        Ok(CallToolResult::text_content(vec![TextContent::from(
-            "opened".to_string(),
-        )]))
+        let mut structured = serde_json::Map::new();
+        structured.insert("port_name".into(), json!(result.port_name));
+        structured.insert("baud_rate".into(), json!(result.baud_rate));
+        structured.insert("open_id".into(), json!(result.open_id));
+        if let Some(sid) = &tool.session_id {
+            structured.insert("session_id".into(), json!(sid));
+        }
+        if !result.warnings.is_empty() {
+            structured.insert("warnings".into(), json!(result.warnings));
+        }
+        if let Some(response) = &result.probe_response {
+            structured.insert("probe_response".into(), json!(response));
+        }
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(result.message)])
+                .with_structured_content(structured),
+        )
     }
-    fn write_impl(&self, tool: WriteTool) -> Result<CallToolResult, CallToolError> {
+    async fn write_impl(&self, tool: WriteTool) -> Result<CallToolResult, CallToolError> {
+        let bytes = tool
+            .encoding
+            .decode(&tool.data)
+            .map_err(|e| CallToolError::invalid_arguments(WriteTool::tool_name(), Some(e)))?;
+        // The write and any echo check block, so keep it off the async executor
         let result = self
-            .service
-            .write(&tool.data)
-            .map_err(Self::map_service_error)?;
+            .run_blocking(move |service| service.write_bytes_with(&bytes, tool.append_terminator))
+            .await?;
 
         let mut structured = serde_json::Map::new();
+        structured.insert("open_id".into(), json!(result.open_id));
+        structured.insert("payload_bytes".into(), json!(result.payload_bytes));
+        structured.insert("terminator_bytes".into(), json!(result.terminator_bytes));
         structured.insert(
             "bytes_written".into(),
             serde_json::Value::Number(result.bytes_written.into()),
@@ -459,6 +1696,18 @@ impl SerialServerHandler {
             "bytes_written_total".into(),
             serde_json::Value::Number(result.bytes_written_total.into()),
         );
+        if let Some(sum) = result.checksum {
+            structured.insert("checksum".into(), json!(sum));
+        }
+        if let Some(matched) = result.echo_matched {
+            structured.insert("echo_matched".into(), json!(matched));
+        }
+        if let Some(ms) = result.flush_duration_ms {
+            structured.insert("flush_duration_ms".into(), json!(ms));
+        }
+        if let Some(ms) = result.flow_paused_ms {
+            structured.insert("flow_paused_ms".into(), json!(ms));
+        }
 
         Ok(CallToolResult::text_content(vec![TextContent::from(format!(
             "wrote {} bytes",
@@ -466,30 +1715,105 @@ impl SerialServerHandler {
         ))])
         .with_structured_content(structured))
     }
-    fn read_impl(&self) -> Result<CallToolResult, CallToolError> {
-        let result = self.service.read().map_err(Self::map_service_error)?;
+    fn preview_write_impl(&self, tool: PreviewWriteTool) -> Result<CallToolResult, CallToolError> {
+        use base64::Engine;
+        let bytes = self
+            .service
+            .preview_write(&tool.data, tool.encoding, tool.append_terminator)
+            .map_err(Self::map_service_error)?;
+        let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("bytes".into(), json!(bytes.len()));
+        structured.insert("hex".into(), json!(hex));
+        structured.insert(
+            "base64".into(),
+            json!(base64::engine::general_purpose::STANDARD.encode(&bytes)),
+        );
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "would write {} bytes: {}",
+            bytes.len(),
+            hex
+        ))])
+        .with_structured_content(structured))
+    }
+    async fn write_repeat_impl(
+        &self,
+        params: WriteRepeatTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        // The writes and delays block, so keep them off the async executor
+        let result = self
+            .run_blocking(move |service| {
+                service.write_repeat(&params.data, params.count, params.delay_ms.unwrap_or(0))
+            })
+            .await?;
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("count".into(), json!(result.count));
+        structured.insert("bytes_written".into(), json!(result.bytes_written));
+        structured.insert(
+            "bytes_written_total".into(),
+            json!(result.bytes_written_total),
+        );
+        structured.insert("elapsed_ms".into(), json!(result.elapsed_ms));
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "wrote {} bytes in {} writes ({} ms)",
+            result.bytes_written, result.count, result.elapsed_ms
+        ))])
+        .with_structured_content(structured))
+    }
+    async fn read_impl(&self, params: ReadTool) -> Result<CallToolResult, CallToolError> {
+        let max_bytes = params
+            .max_bytes
+            .map_or(crate::service::DEFAULT_READ_BYTES, saturating_usize);
+        let encoding = params.encoding.unwrap_or_default();
+        let transform = params.read_transform.unwrap_or_default();
+        let long_poll = params.long_poll_ms.map(Duration::from_millis);
+        let result = self
+            .run_blocking(move |service| {
+                service.read_with_long_poll(
+                    max_bytes,
+                    encoding,
+                    transform,
+                    params.include_hex,
+                    long_poll,
+                )
+            })
+            .await?;
 
         // Handle auto-close case
         if let Some(auto_close) = result.auto_closed {
             let mut structured = serde_json::Map::new();
+            structured.insert("open_id".into(), json!(result.open_id));
             structured.insert("event".into(), json!("auto_close"));
             structured.insert("reason".into(), json!(auto_close.reason));
             structured.insert(
                 "idle_close_count".into(),
                 json!(auto_close.idle_close_count),
             );
-            return Ok(CallToolResult::text_content(vec![TextContent::from(
-                "closed (idle timeout)".to_string(),
-            )])
-            .with_structured_content(structured));
+            let text = match auto_close.reason {
+                CloseReason::MaxDuration => "closed (max open duration)",
+                _ => "closed (idle timeout)",
+            };
+            return Ok(
+                CallToolResult::text_content(vec![TextContent::from(text.to_string())])
+                    .with_structured_content(structured),
+            );
         }
 
         // Normal read response
         let mut structured = serde_json::Map::new();
-        structured.insert(
-            "data".into(),
-            serde_json::Value::String(result.data.clone()),
-        );
+        structured.insert("open_id".into(), json!(result.open_id));
+        structured.insert("data".into(), serde_json::Value::String(result.data));
+        if let Some(raw) = result.raw {
+            structured.insert("raw".into(), json!(raw));
+        }
+        if let Some(hex) = result.hex {
+            structured.insert("hex".into(), json!(hex));
+        }
+        structured.insert("encoding".into(), json!(encoding));
         structured.insert(
             "bytes_read".into(),
             serde_json::Value::Number(result.bytes_read.into()),
@@ -498,34 +1822,741 @@ impl SerialServerHandler {
             "bytes_read_total".into(),
             serde_json::Value::Number(result.bytes_read_total.into()),
         );
+        structured.insert("framing_errors".into(), json!(result.framing_errors));
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "read {} bytes",
+            result.bytes_read
+        ))])
+        .with_structured_content(structured))
+    }
+    async fn read_line_impl(&self, params: ReadLineTool) -> Result<CallToolResult, CallToolError> {
+        let overall_timeout_ms = params.overall_timeout_ms.unwrap_or(1000);
+        let result = self
+            .run_blocking(move |service| {
+                service.read_line(overall_timeout_ms, params.idle_timeout_ms)
+            })
+            .await?;
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("open_id".into(), json!(result.open_id));
+        structured.insert("data".into(), json!(result.data));
+        structured.insert("termination".into(), json!(result.termination));
+        structured.insert("bytes_read".into(), json!(result.bytes_read));
+        structured.insert("bytes_read_total".into(), json!(result.bytes_read_total));
+        structured.insert("framing_errors".into(), json!(result.framing_errors));
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "read {} bytes",
+            result.bytes_read
+        ))])
+        .with_structured_content(structured))
+    }
+    async fn capture_until_silence_impl(
+        &self,
+        params: CaptureUntilSilenceTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let max_bytes = params.max_bytes.map_or_else(
+            || DEFAULT_CAPTURE_BYTES.min(self.service.max_read_bytes()),
+            saturating_usize,
+        );
+        let overall_timeout_ms = params.overall_timeout_ms.unwrap_or(10_000);
+        let result = self
+            .run_blocking(move |service| {
+                service.capture_until_silence(params.silence_ms, max_bytes, overall_timeout_ms)
+            })
+            .await?;
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("open_id".into(), json!(result.open_id));
+        structured.insert("data".into(), json!(result.data));
+        structured.insert("termination".into(), json!(result.termination));
+        structured.insert("bytes_read".into(), json!(result.bytes_read));
+        structured.insert("bytes_read_total".into(), json!(result.bytes_read_total));
+        structured.insert("framing_errors".into(), json!(result.framing_errors));
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "captured {} bytes",
+            result.bytes_read
+        ))])
+        .with_structured_content(structured))
+    }
+    /// Start streaming reads into `notify`, replacing any running stream.
+    fn subscribe_reads_impl(
+        &self,
+        params: SubscribeReadsTool,
+        notify: mpsc::UnboundedSender<LoggingMessageNotificationParams>,
+    ) -> Result<CallToolResult, CallToolError> {
+        let interval_ms = params
+            .interval_ms
+            .unwrap_or(DEFAULT_READ_STREAM_INTERVAL_MS)
+            .max(1);
+        let stream = ReadStream::start(
+            &self.service,
+            Duration::from_millis(interval_ms),
+            params.encoding.unwrap_or_default(),
+            notify,
+        );
+        let previous = self
+            .read_stream
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .replace(stream);
+        if let Some(previous) = &previous {
+            previous.task.abort();
+        }
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("subscribed".into(), json!(true));
+        structured.insert("interval_ms".into(), json!(interval_ms));
+        structured.insert("replaced".into(), json!(previous.is_some()));
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "streaming reads every {} ms",
+            interval_ms
+        ))])
+        .with_structured_content(structured))
+    }
+    fn unsubscribe_reads_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let stream = self
+            .read_stream
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(stream) = &stream {
+            stream.task.abort();
+        }
+        let was_subscribed = stream.is_some();
 
+        let mut structured = serde_json::Map::new();
+        structured.insert("subscribed".into(), json!(false));
+        structured.insert("was_subscribed".into(), json!(was_subscribed));
+        let text = if was_subscribed {
+            "read stream stopped"
+        } else {
+            "no read stream was running"
+        };
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(text.to_string())])
+                .with_structured_content(structured),
+        )
+    }
+    fn decode_bytes_impl(&self, params: DecodeBytesTool) -> Result<CallToolResult, CallToolError> {
+        use base64::Engine;
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(params.data.as_bytes())
+            .map_err(|e| {
+                CallToolError::invalid_arguments(
+                    DecodeBytesTool::tool_name(),
+                    Some(format!("data is not valid base64: {e}")),
+                )
+            })?;
+        let spec = DecodeSpec {
+            value_type: params.value_type,
+            endianness: params.endianness.unwrap_or_default(),
+        };
+        let values = decode_values(&bytes, spec);
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("count".into(), json!(values.len()));
+        structured.insert(
+            "trailing_bytes".into(),
+            json!(bytes.len() % spec.value_type.width()),
+        );
+        structured.insert("values".into(), serde_json::Value::Array(values.clone()));
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(
+            serde_json::Value::Array(values).to_string(),
+        )])
+        .with_structured_content(structured))
+    }
+    async fn wait_for_pattern_impl(
+        &self,
+        params: WaitForPatternTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let result = self
+            .run_blocking(move |service| {
+                service.wait_for_pattern(&params.pattern, params.timeout_ms)
+            })
+            .await?;
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("matched".into(), json!(result.matched));
+        structured.insert("timed_out".into(), json!(result.timed_out));
+        structured.insert("data".into(), json!(result.data));
+        structured.insert("bytes_read".into(), json!(result.bytes_read));
+        structured.insert("elapsed_ms".into(), json!(result.elapsed_ms));
+
+        let text = match &result.matched {
+            Some(m) => format!("matched '{}' after {} ms", m, result.elapsed_ms),
+            None => format!("timed out after {} ms", result.elapsed_ms),
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    async fn loopback_test_impl(
+        &self,
+        params: LoopbackTestTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let token_len = params
+            .token_len
+            .map_or(DEFAULT_LOOPBACK_TOKEN_LEN, saturating_usize);
+        let timeout_ms = params.timeout_ms.unwrap_or(1000);
+        let result = self
+            .run_blocking(move |service| service.loopback_test(token_len, timeout_ms))
+            .await?;
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("passed".into(), json!(result.passed));
+        structured.insert("sent".into(), json!(result.sent));
+        structured.insert("received".into(), json!(result.received));
+        structured.insert("latency_ms".into(), json!(result.latency_ms));
+
+        let text = if result.passed {
+            format!("loopback passed in {} ms", result.latency_ms)
+        } else {
+            format!(
+                "loopback failed: sent {} bytes, received {}",
+                result.sent.len(),
+                result.received.len()
+            )
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    async fn protocol_command_impl(
+        &self,
+        params: ProtocolCommandTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let protocol = {
+            let loader = self.config.read().unwrap_or_else(PoisonError::into_inner);
+            let config = loader.config();
+            config.protocol(&params.protocol).cloned().ok_or_else(|| {
+                let known: Vec<&str> = config.protocols.iter().map(|p| p.name.as_str()).collect();
+                CallToolError::invalid_arguments(
+                    ProtocolCommandTool::tool_name(),
+                    Some(format!(
+                        "unknown protocol '{}'; configured: [{}]",
+                        params.protocol,
+                        known.join(", ")
+                    )),
+                )
+            })?
+        };
+        // Waiting for the reply blocks on serial reads, so keep it off the async executor
+        let descriptor = protocol.clone();
+        let result = self
+            .run_blocking(move |service| service.protocol_command(&descriptor, &params.command))
+            .await?;
+
+        let text = match result.status {
+            ResponseStatus::Success => format!("ok: {}", result.payload.as_deref().unwrap_or("")),
+            ResponseStatus::Error => format!("error: {}", result.payload.as_deref().unwrap_or("")),
+            ResponseStatus::Timeout => {
+                format!("no response within {} ms", protocol.timeout_ms)
+            }
+        };
+        let structured = match serde_json::to_value(&result) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    async fn modbus_read_many_impl(
+        &self,
+        params: ModbusReadManyTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let unit_id = params.unit_id;
+        // Each request waits for its reply, so keep them off the async executor
+        let result = self
+            .run_blocking(move |service| {
+                service.modbus_read_many(
+                    params.unit_id,
+                    params.register_type.unwrap_or_default(),
+                    &params.ranges,
+                    params.max_gap.unwrap_or(0),
+                    params.timeout_ms.unwrap_or(1000),
+                )
+            })
+            .await?;
+
+        let text = format!(
+            "read {} registers from unit {} in {} requests",
+            result.values.len(),
+            unit_id,
+            result.requests.len()
+        );
+        let structured = match serde_json::to_value(&result) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    async fn set_signals_impl(
+        &self,
+        params: SetSignalsTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let request = SignalsRequest {
+            dtr: params.dtr,
+            rts: params.rts,
+            hold_ms: params.hold_ms,
+            restore: params.restore.unwrap_or(false),
+        };
+        // The hold sleeps, so keep it off the async executor
+        let result = self
+            .run_blocking(move |service| service.set_signals(request))
+            .await?;
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("dtr".into(), json!(result.dtr));
+        structured.insert("rts".into(), json!(result.rts));
+        structured.insert("restored".into(), json!(result.restored));
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "signals set (dtr={:?}, rts={:?})",
+            result.dtr, result.rts
+        ))])
+        .with_structured_content(structured))
+    }
+    async fn reset_device_impl(
+        &self,
+        params: ResetDeviceTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        // The holds sleep, so keep them off the async executor
+        let result = self
+            .run_blocking(move |service| service.reset_device(params.board, params.steps))
+            .await?;
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("board".into(), json!(result.board));
+        structured.insert("steps".into(), json!(result.steps));
+        structured.insert("dtr".into(), json!(result.dtr));
+        structured.insert("rts".into(), json!(result.rts));
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "reset {:?} ({} steps)",
+            result.board,
+            result.steps.len()
+        ))])
+        .with_structured_content(structured))
+    }
+    async fn diagnose_impl(&self, params: DiagnoseTool) -> Result<CallToolResult, CallToolError> {
+        let baud_rate = params.baud_rate.unwrap_or(9600);
+        let probe_timeout_ms = params
+            .probe_timeout_ms
+            .unwrap_or(DEFAULT_DIAGNOSE_PROBE_TIMEOUT_MS);
+        let port_cache = Arc::clone(&self.port_cache);
+        let port_name = params.port_name;
+        // Enumeration, open and the signature probes all block
+        let mut report = self
+            .run_blocking(move |service| {
+                let enumerated = port_cache
+                    .list(true)
+                    .map(|ports| ports.into_iter().map(|p| p.port_name).collect())
+                    .map_err(|e| format!("Port enumeration failed: {}", e));
+                Ok(service.diagnose(&port_name, baud_rate, probe_timeout_ms, enumerated))
+            })
+            .await?;
+        let opened = report
+            .steps
+            .iter()
+            .any(|s| s.name == "open" && s.status == StepStatus::Pass);
+        let negotiation = if opened {
+            Self::diagnose_negotiation(&report.port_name, probe_timeout_ms).await
+        } else {
+            DiagnosticStep::skipped("auto_negotiation", "port did not open")
+        };
+        report.steps.push(negotiation);
+
+        let failed = report
+            .steps
+            .iter()
+            .filter(|s| s.status == StepStatus::Fail)
+            .count();
+        let mut structured = serde_json::Map::new();
+        structured.insert("port_name".into(), json!(report.port_name));
+        structured.insert("baud_rate".into(), json!(report.baud_rate));
+        structured.insert("passed".into(), json!(report.passed()));
+        structured.insert("steps".into(), json!(report.steps));
+
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "diagnose {}: {} of {} steps failed",
+            report.port_name,
+            failed,
+            report.steps.len()
+        ))])
+        .with_structured_content(structured))
+    }
+    /// Final `diagnose` step: a short auto-negotiation on the closed port.
+    #[cfg(feature = "auto-negotiation")]
+    async fn diagnose_negotiation(port_name: &str, timeout_ms: u64) -> DiagnosticStep {
+        use crate::negotiation::{AutoNegotiator, NegotiationHints};
+
+        let hints = NegotiationHints {
+            timeout_ms,
+            ..Default::default()
+        };
+        match AutoNegotiator::new().detect(port_name, Some(hints)).await {
+            Ok(p) => DiagnosticStep::pass(
+                "auto_negotiation",
+                format!(
+                    "{} baud via {} (confidence {:.2})",
+                    p.baud_rate, p.strategy_used, p.confidence
+                ),
+            ),
+            Err(e) => DiagnosticStep::fail(
+                "auto_negotiation",
+                e.to_string(),
+                "Set the baud rate from the device documentation; some devices stay silent until sent a command",
+            ),
+        }
+    }
+    #[cfg(not(feature = "auto-negotiation"))]
+    async fn diagnose_negotiation(_port_name: &str, _timeout_ms: u64) -> DiagnosticStep {
+        DiagnosticStep::skipped("auto_negotiation", "built without auto-negotiation")
+    }
+    fn describe_tools_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let tools: Vec<_> = tool_examples()
+            .into_iter()
+            .map(|(name, example)| json!({"name": name, "example": example}))
+            .collect();
+        let mut structured = serde_json::Map::new();
+        structured.insert("tools".into(), json!(tools));
+        structured.insert("enums".into(), enum_spellings());
+        structured.insert(
+            "notes".into(),
+            json!("Enum arguments are strings; data_bits, parity, stop_bits and flow_control are case-insensitive."),
+        );
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from("tool examples".to_string())])
+                .with_structured_content(structured),
+        )
+    }
+    fn server_info_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let groups = tool_groups();
+        let disabled: Vec<_> = OPTIONAL_TOOL_GROUPS
+            .iter()
+            .filter(|group| !group.enabled)
+            .map(|group| group.feature)
+            .collect();
+        let mut structured = serde_json::Map::new();
+        structured.insert("name".into(), json!(env!("CARGO_PKG_NAME")));
+        structured.insert("version".into(), json!(env!("CARGO_PKG_VERSION")));
+        structured.insert("tool_groups".into(), groups);
+
+        let text = if disabled.is_empty() {
+            "all optional tool groups are enabled".to_string()
+        } else {
+            format!("disabled features: {}", disabled.join(", "))
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    fn get_config_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let dump = self
+            .config
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .effective_config();
+        let text = format!(
+            "config from {}",
+            dump["source"].as_str().unwrap_or("defaults")
+        );
+        let structured = match dump {
+            serde_json::Value::Object(map) => map,
+            _ => serde_json::Map::new(),
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    fn reload_config_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let mut loader = self.config.write().unwrap_or_else(PoisonError::into_inner);
+        let changes = loader
+            .reload_validated()
+            .map_err(|e| CallToolError::from_message(format!("config reload rejected: {}", e)))?;
+        self.service.apply_config(loader.config());
+        let log_level_applied = crate::logging::reload_level(&loader.config().logging);
+        let text = format!(
+            "reloaded config from {}: {} change(s)",
+            loader.source(),
+            changes.len()
+        );
+        let mut structured = serde_json::Map::new();
+        structured.insert("source".into(), json!(loader.source()));
+        structured.insert("changes".into(), json!(changes));
+        structured.insert("log_level_applied".into(), json!(log_level_applied));
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    async fn reset_subsystem_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let stream = self
+            .read_stream
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if let Some(stream) = &stream {
+            stream.task.abort();
+        }
+        let service = self.service.clone();
+        // Waiting out a busy port blocks, so keep it off the async executor
+        let reset = tokio::task::spawn_blocking(move || service.reset())
+            .await
+            .map_err(|e| CallToolError::from_message(format!("reset failed: {}", e)))?;
+        self.port_cache.invalidate();
+        // A fresh reader picks up the next port opened
+        let read_stream_restarted = stream.is_some();
+        if let Some(stream) = stream {
+            let mut slot = self
+                .read_stream
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            // Unless a subscribe_reads during the reset started one already
+            if slot.is_none() {
+                *slot = Some(stream.restart(&self.service));
+            }
+        }
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("port_closed".into(), json!(reset.closed.is_some()));
+        structured.insert("port_busy".into(), json!(reset.port_busy));
+        if let Some(metrics) = reset.closed.and_then(|closed| closed.final_metrics) {
+            structured.insert("final_metrics".into(), json!(metrics));
+        }
+        structured.insert("read_stream_restarted".into(), json!(read_stream_restarted));
+        structured.insert("writer_restarted".into(), json!(reset.writer_restarted));
+        structured.insert("port_list_cache_cleared".into(), json!(true));
+        #[cfg(feature = "auto-negotiation")]
+        structured.insert(
+            "detections_cleared".into(),
+            json!(self.negotiator.clear_cache()),
+        );
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from("serial subsystem reset")])
+                .with_structured_content(structured),
+        )
+    }
+    #[cfg(feature = "tui")]
+    fn list_themes_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let names = crate::tui::Theme::names();
+        let mut structured = serde_json::Map::new();
+        structured.insert("themes".into(), json!(names));
+        structured.insert(
+            "current".into(),
+            json!(*crate::tui::shared_theme().borrow()),
+        );
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(names.join(", "))])
+                .with_structured_content(structured),
+        )
+    }
+    #[cfg(feature = "tui")]
+    fn set_theme_impl(&self, params: SetThemeTool) -> Result<CallToolResult, CallToolError> {
+        let theme = crate::tui::Theme::by_name(&params.name).ok_or_else(|| {
+            CallToolError::invalid_arguments(
+                SetThemeTool::tool_name(),
+                Some(format!(
+                    "unknown theme '{}' (expected one of: {})",
+                    params.name,
+                    crate::tui::Theme::names().join(", ")
+                )),
+            )
+        })?;
+        let previous = crate::tui::shared_theme().send_replace(theme.name);
+        let mut structured = serde_json::Map::new();
+        structured.insert("theme".into(), json!(theme.name));
+        structured.insert("previous".into(), json!(previous));
         Ok(CallToolResult::text_content(vec![TextContent::from(format!(
-            "read {} bytes",
-            result.bytes_read
+            "theme set to {}",
+            theme.name
         ))])
         .with_structured_content(structured))
     }
-    fn close_impl(&self) -> Result<CallToolResult, CallToolError> {
-        let result = self.service.close().map_err(Self::map_service_error)?;
+    fn cancel_read_impl(&self) -> Result<CallToolResult, CallToolError> {
+        self.service.cancel_read();
         Ok(CallToolResult::text_content(vec![TextContent::from(
-            result.message,
+            "cancel requested".to_string(),
         )]))
     }
-    fn status_impl(&self) -> Result<CallToolResult, CallToolError> {
+    fn close_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let result = self.service.close().map_err(Self::map_service_error)?;
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("reason".into(), json!(result.reason));
+        if let Some(metrics) = result.final_metrics {
+            structured.insert("final_metrics".into(), json!(metrics));
+        }
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(result.message)])
+                .with_structured_content(structured),
+        )
+    }
+    fn reconnect_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let result = self.service.reconnect().map_err(Self::map_service_error)?;
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("port_name".into(), json!(result.port_name));
+        structured.insert("baud_rate".into(), json!(result.baud_rate));
+        structured.insert("open_id".into(), json!(result.open_id));
+        structured.insert("config".into(), json!(result.config));
+        if !result.warnings.is_empty() {
+            structured.insert("warnings".into(), json!(result.warnings));
+        }
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(result.message)])
+                .with_structured_content(structured),
+        )
+    }
+    /// Same `StatusResult` payload as REST `GET /status`, under `status`.
+    /// Whether the open port's device is still attached; `None` with no
+    /// port open or when presence cannot be determined.
+    async fn port_presence(&self, verify: bool) -> Option<bool> {
+        if !verify {
+            return None;
+        }
+        let Ok(crate::service::StatusResult::Open { config, .. }) = self.service.status() else {
+            return None;
+        };
+        let port_cache = Arc::clone(&self.port_cache);
+        // The presence check enumerates ports afresh, so keep it off the
+        // async executor
+        tokio::task::spawn_blocking(move || port_cache.is_present(&config.port_name))
+            .await
+            .ok()
+            .flatten()
+    }
+    pub(crate) async fn status_impl(&self, verify: bool) -> Result<CallToolResult, CallToolError> {
         let status = self.service.status().map_err(Self::map_service_error)?;
         let val = serde_json::to_value(&status)
             .map_err(|e| CallToolError::from_message(e.to_string()))?;
         let mut structured = serde_json::Map::new();
         structured.insert("status".into(), val);
+        if let Some(present) = self.port_presence(verify).await {
+            structured.insert("physically_present".into(), json!(present));
+        }
         Ok(
             CallToolResult::text_content(vec![TextContent::from("status".to_string())])
                 .with_structured_content(structured),
         )
     }
-    fn metrics_impl(&self) -> Result<CallToolResult, CallToolError> {
+    fn port_capabilities_impl(&self) -> Result<CallToolResult, CallToolError> {
+        let result = self.service.capabilities();
+        let text = format!(
+            "capabilities of {} on {}",
+            result.port_name.as_deref().unwrap_or("platform"),
+            result.platform
+        );
+        let structured = match serde_json::to_value(&result)
+            .map_err(|e| CallToolError::from_message(e.to_string()))?
+        {
+            serde_json::Value::Object(map) => map,
+            _ => serde_json::Map::new(),
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    async fn benchmark_impl(&self, params: BenchmarkTool) -> Result<CallToolResult, CallToolError> {
+        let result = self
+            .run_blocking(move |service| {
+                service.benchmark(
+                    params.iterations,
+                    &params.payload,
+                    params.read_until.as_deref(),
+                    params.timeout_ms.unwrap_or(1000),
+                )
+            })
+            .await?;
+
+        let text = match &result.latency {
+            Some(stats) => format!(
+                "{}/{} round trips: min {:.2} ms, p50 {:.2} ms, p99 {:.2} ms, max {:.2} ms; {:.1} B/s",
+                result.completed,
+                result.iterations,
+                stats.min_ms,
+                stats.p50_ms,
+                stats.p99_ms,
+                stats.max_ms,
+                result.throughput_bytes_per_sec
+            ),
+            None => format!(
+                "0/{} round trips completed; every reply timed out",
+                result.iterations
+            ),
+        };
+        let structured = match serde_json::to_value(&result) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    fn set_buffer_sizes_impl(
+        &self,
+        params: SetBufferSizesTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let result = self
+            .service
+            .set_buffer_sizes(
+                params.rx.map(saturating_usize),
+                params.tx.map(saturating_usize),
+            )
+            .map_err(Self::map_service_error)?;
+
+        let size = |n: Option<usize>| n.map_or("unknown".to_string(), |n| n.to_string());
+        let text = format!(
+            "buffer sizes on {}: rx={}, tx={}",
+            result.port_name,
+            size(result.effective.rx),
+            size(result.effective.tx)
+        );
+        let structured = match serde_json::to_value(&result) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    fn metrics_history_impl(
+        &self,
+        params: MetricsHistoryTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let mut samples = self.service.metrics_history();
+        if let Some(limit) = params.limit.map(saturating_usize) {
+            samples.drain(..samples.len().saturating_sub(limit));
+        }
+        let mut structured = serde_json::Map::new();
+        structured.insert("count".into(), json!(samples.len()));
+        structured.insert("samples".into(), json!(samples));
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "{} metrics samples",
+            samples.len()
+        ))])
+        .with_structured_content(structured))
+    }
+    fn recent_errors_impl(
+        &self,
+        params: RecentErrorsTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let mut errors = self.service.recent_errors();
+        if let Some(limit) = params.limit.map(saturating_usize) {
+            errors.drain(..errors.len().saturating_sub(limit));
+        }
+        let mut structured = serde_json::Map::new();
+        structured.insert("count".into(), json!(errors.len()));
+        structured.insert("errors".into(), json!(errors));
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "{} recent errors",
+            errors.len()
+        ))])
+        .with_structured_content(structured))
+    }
+    async fn metrics_impl(&self, verify: bool) -> Result<CallToolResult, CallToolError> {
         let metrics = self.service.metrics().map_err(Self::map_service_error)?;
         let mut structured = serde_json::Map::new();
         structured.insert("state".into(), json!(metrics.state));
+        if let Some(present) = self.port_presence(verify).await {
+            structured.insert("physically_present".into(), json!(present));
+        }
         if let Some(val) = metrics.bytes_read_total {
             structured.insert("bytes_read_total".into(), json!(val));
         }
@@ -541,60 +2572,105 @@ impl SerialServerHandler {
         if let Some(val) = metrics.last_activity_ms {
             structured.insert("last_activity_ms".into(), json!(val));
         }
+        if let Some(val) = metrics.open_started_at {
+            structured.insert("open_started_at".into(), json!(val));
+        }
+        if let Some(val) = metrics.last_activity_at {
+            structured.insert("last_activity_at".into(), json!(val));
+        }
         if let Some(val) = metrics.timeout_streak {
             structured.insert("timeout_streak".into(), json!(val));
         }
+        if let Some(val) = metrics.pending_frame_bytes {
+            structured.insert("pending_frame_bytes".into(), json!(val));
+        }
         Ok(
             CallToolResult::text_content(vec![TextContent::from("metrics".to_string())])
                 .with_structured_content(structured),
         )
     }
-    fn reconfigure_port_impl(
+    async fn patch_config_impl(
         &self,
-        tool: ReconfigurePortTool,
+        patch: PortConfigPatch,
     ) -> Result<CallToolResult, CallToolError> {
-        let config = ReconfigureConfig {
-            port_name: tool.port_name.clone(),
-            baud_rate: tool.baud_rate,
-            timeout_ms: tool.timeout_ms,
-            data_bits: tool.data_bits,
-            parity: tool.parity,
-            stop_bits: tool.stop_bits,
-            flow_control: tool.flow_control,
-            terminator: tool.terminator.clone(),
-            idle_disconnect_ms: tool.idle_disconnect_ms,
+        // A line setting reopens the port, so keep it off the async executor
+        let result = self
+            .run_blocking(move |service| service.patch_config(patch))
+            .await?;
+        let text = format!(
+            "patched {}{}",
+            result.config.port_name,
+            if result.reopened { " (reopened)" } else { "" }
+        );
+        let mut structured = serde_json::Map::new();
+        structured.insert("open_id".into(), json!(result.open_id));
+        structured.insert("reopened".into(), json!(result.reopened));
+        structured.insert("config".into(), json!(result.config));
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    async fn set_framing_impl(
+        &self,
+        request: FramingRequest,
+    ) -> Result<CallToolResult, CallToolError> {
+        // Ports without live framing changes are reopened, so keep it off the
+        // async executor
+        let result = self
+            .run_blocking(move |service| service.set_framing(request))
+            .await?;
+        let text = match &result.warning {
+            Some(warning) => format!("framing changed ({warning})"),
+            None => format!("framing changed on {}", result.config.port_name),
         };
-
+        let mut structured = serde_json::Map::new();
+        structured.insert("open_id".into(), json!(result.open_id));
+        structured.insert("reopened".into(), json!(result.reopened));
+        structured.insert("warning".into(), json!(result.warning));
+        structured.insert("config".into(), json!(result.config));
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
+    async fn reconfigure_port_impl(
+        &self,
+        config: ReconfigureConfig,
+    ) -> Result<CallToolResult, CallToolError> {
+        // Closing and reopening blocks, so keep it off the async executor
         let result = self
-            .service
-            .reconfigure(config)
-            .map_err(Self::map_service_error)?;
+            .run_blocking(move |service| service.reconfigure(config))
+            .await?;
+        let applied = &result.config;
+        if let Some(sid) = &result.active_session {
+            let note = format!("reconfigured {}: {}", result.port_name, applied.summary());
+            if let Err(e) = self
+                .sessions
+                .append_message(sid, "system", None, &note, None, None)
+                .await
+            {
+                tracing::warn!(session_id = %sid, error = %e, "failed to record reconfigure in session");
+            }
+        }
 
         let mut structured = serde_json::Map::new();
         structured.insert("port_name".into(), json!(result.port_name));
         structured.insert("baud_rate".into(), json!(result.baud_rate));
-        structured.insert(
-            "data_bits".into(),
-            json!(format!("{:?}", tool.data_bits).to_lowercase()),
-        );
-        structured.insert(
-            "parity".into(),
-            json!(format!("{:?}", tool.parity).to_lowercase()),
-        );
-        structured.insert(
-            "stop_bits".into(),
-            json!(format!("{:?}", tool.stop_bits).to_lowercase()),
-        );
-        structured.insert(
-            "flow_control".into(),
-            json!(format!("{:?}", tool.flow_control).to_lowercase()),
-        );
-        if let Some(t) = &tool.terminator {
+        structured.extend(line_settings_json(
+            applied.data_bits,
+            applied.parity,
+            applied.stop_bits,
+            applied.flow_control,
+        ));
+        if let Some(t) = &applied.terminator {
             structured.insert("terminator".into(), json!(t));
         }
-        if let Some(ms) = tool.idle_disconnect_ms {
+        if let Some(ms) = applied.idle_disconnect_ms {
             structured.insert("idle_disconnect_ms".into(), json!(ms));
         }
+        if let Some(ms) = applied.read_idle_gap_ms {
+            structured.insert("read_idle_gap_ms".into(), json!(ms));
+        }
+        if !result.warnings.is_empty() {
+            structured.insert("warnings".into(), json!(result.warnings));
+        }
         Ok(
             CallToolResult::text_content(vec![TextContent::from("reconfigured".to_string())])
                 .with_structured_content(structured),
@@ -603,12 +2679,28 @@ impl SerialServerHandler {
     // --- Session Management ---
     async fn create_session_impl(
         &self,
-        device_id: String,
-        port_name: Option<String>,
+        params: CreateSessionTool,
     ) -> Result<CallToolResult, CallToolError> {
+        let mut signature = DeviceSignature {
+            vid: params.vid,
+            pid: params.pid,
+            device_serial: params.device_serial,
+            manufacturer: params.manufacturer,
+        };
+        if let Some(found) = params
+            .port_name
+            .as_deref()
+            .and_then(|name| self.alias_signature(name))
+        {
+            signature = signature.or(found);
+        }
         let s = self
             .sessions
-            .create_session(&device_id, port_name.as_deref())
+            .create_session_with_signature(
+                &params.device_id,
+                params.port_name.as_deref(),
+                signature,
+            )
             .await
             .map_err(|e| CallToolError::from_message(e.to_string()))?;
         let mut structured = serde_json::Map::new();
@@ -621,6 +2713,59 @@ impl SerialServerHandler {
                 .with_structured_content(structured),
         )
     }
+    /// USB signature of the connected device behind `port_name`, if it is a
+    /// configured device alias.
+    fn alias_signature(&self, port_name: &str) -> Option<DeviceSignature> {
+        let alias = {
+            let loader = self.config.read().unwrap_or_else(PoisonError::into_inner);
+            loader
+                .config()
+                .device_aliases
+                .iter()
+                .find(|a| a.name == port_name)
+                .cloned()?
+        };
+        let port = self
+            .port_cache
+            .list(false)
+            .ok()?
+            .into_iter()
+            .find(|p| alias.matches(p))?;
+        match port.port_type {
+            serialport::SerialPortType::UsbPort(info) => Some(DeviceSignature {
+                vid: Some(format!("0x{:04x}", info.vid)),
+                pid: Some(format!("0x{:04x}", info.pid)),
+                device_serial: info.serial_number,
+                manufacturer: info.manufacturer,
+            }),
+            _ => None,
+        }
+    }
+    async fn get_or_create_session_impl(
+        &self,
+        params: GetOrCreateSessionTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let (s, created) = self
+            .sessions
+            .get_or_create_session(&params.device_id, params.port_name.as_deref())
+            .await
+            .map_err(|e| CallToolError::from_message(e.to_string()))?;
+        let mut structured = serde_json::Map::new();
+        structured.insert(
+            "session".into(),
+            serde_json::to_value(s).unwrap_or_default(),
+        );
+        structured.insert("created".into(), json!(created));
+        let text = if created {
+            "session created"
+        } else {
+            "existing session"
+        };
+        Ok(
+            CallToolResult::text_content(vec![TextContent::from(text.to_string())])
+                .with_structured_content(structured),
+        )
+    }
     #[allow(dead_code)] // Reserved for future session message append via MCP
     async fn append_message_impl(
         &self,
@@ -695,6 +2840,25 @@ impl SerialServerHandler {
                 .with_structured_content(structured),
         )
     }
+    async fn append_messages_impl(
+        &self,
+        tool: AppendMessagesTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let ids = self
+            .sessions
+            .append_messages(&tool.session_id, &tool.messages)
+            .await
+            .map_err(|e| CallToolError::from_message(e.to_string()))?;
+        let mut structured = serde_json::Map::new();
+        structured.insert("session_id".into(), json!(tool.session_id));
+        structured.insert("count".into(), json!(ids.len()));
+        structured.insert("message_ids".into(), json!(ids));
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "{} messages stored",
+            ids.len()
+        ))])
+        .with_structured_content(structured))
+    }
     async fn list_messages_impl(
         &self,
         session_id: String,
@@ -796,6 +2960,174 @@ impl SerialServerHandler {
                 .with_structured_content(structured),
         )
     }
+    async fn replay_session_impl(
+        &self,
+        params: ReplaySessionTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let session_error = |e: sqlx::Error| CallToolError::from_message(e.to_string());
+        let source = self
+            .sessions
+            .get_session(&params.session_id)
+            .await
+            .map_err(session_error)?
+            .ok_or_else(|| {
+                CallToolError::from_message(format!("session not found: {}", params.session_id))
+            })?;
+        let sent = self
+            .sessions
+            .filter_messages(&source.id, None, None, Some("sent"), i64::MAX)
+            .await
+            .map_err(session_error)?;
+
+        let capture = if params.capture_responses {
+            let port_name = match self.service.status() {
+                Ok(crate::service::StatusResult::Open { config, .. }) => Some(config.port_name),
+                _ => None,
+            };
+            Some(
+                self.sessions
+                    .create_session(&source.device_id, port_name.as_deref())
+                    .await
+                    .map_err(session_error)?,
+            )
+        } else {
+            None
+        };
+        let silence_ms = params
+            .response_silence_ms
+            .unwrap_or(DEFAULT_REPLAY_SILENCE_MS);
+        let capture_bytes = DEFAULT_CAPTURE_BYTES.min(self.service.max_read_bytes());
+
+        let mut bytes_written = 0;
+        let mut responses = Vec::new();
+        for (i, msg) in sent.iter().enumerate() {
+            if i > 0 {
+                let delay = if params.preserve_timing {
+                    (msg.created_at - sent[i - 1].created_at)
+                        .to_std()
+                        .unwrap_or_default()
+                } else {
+                    Duration::from_millis(params.inter_message_delay_ms.unwrap_or(0))
+                };
+                if !delay.is_zero() {
+                    tokio::time::sleep(delay).await;
+                }
+            }
+            let content = msg.content.clone();
+            let written = self
+                .run_blocking(move |service| service.write(&content))
+                .await?;
+            bytes_written += written.bytes_written;
+
+            let Some(session) = &capture else {
+                continue;
+            };
+            self.sessions
+                .append_message(
+                    &session.id,
+                    &msg.role,
+                    Some("sent"),
+                    &msg.content,
+                    msg.features.as_deref(),
+                    None,
+                )
+                .await
+                .map_err(session_error)?;
+            let started = std::time::Instant::now();
+            let reply = self
+                .run_blocking(move |service| {
+                    service.capture_until_silence(
+                        silence_ms,
+                        capture_bytes,
+                        REPLAY_RESPONSE_TIMEOUT_MS,
+                    )
+                })
+                .await?;
+            if !reply.data.is_empty() {
+                // Time until the device went quiet, which bounds its response time
+                let latency_ms = started.elapsed().as_millis() as i64;
+                self.sessions
+                    .append_message(
+                        &session.id,
+                        "device",
+                        Some("received"),
+                        &reply.data,
+                        None,
+                        Some(latency_ms),
+                    )
+                    .await
+                    .map_err(session_error)?;
+            }
+            responses.push(reply.data);
+        }
+
+        let mut structured = serde_json::Map::new();
+        structured.insert("session_id".into(), json!(source.id));
+        structured.insert("messages_replayed".into(), json!(sent.len()));
+        structured.insert("bytes_written".into(), json!(bytes_written));
+        if let Some(session) = &capture {
+            structured.insert("capture_session_id".into(), json!(session.id));
+            structured.insert("responses".into(), json!(responses));
+        }
+        Ok(CallToolResult::text_content(vec![TextContent::from(format!(
+            "replayed {} messages ({} bytes)",
+            sent.len(),
+            bytes_written
+        ))])
+        .with_structured_content(structured))
+    }
+    async fn diff_sessions_impl(
+        &self,
+        params: DiffSessionsTool,
+    ) -> Result<CallToolResult, CallToolError> {
+        let session_error = |e: sqlx::Error| CallToolError::from_message(e.to_string());
+        let mut messages = Vec::new();
+        for id in [&params.expected_session_id, &params.actual_session_id] {
+            if self
+                .sessions
+                .get_session(id)
+                .await
+                .map_err(session_error)?
+                .is_none()
+            {
+                return Err(CallToolError::from_message(format!(
+                    "session not found: {}",
+                    id
+                )));
+            }
+            messages.push(
+                self.sessions
+                    .list_messages(id, i64::MAX)
+                    .await
+                    .map_err(session_error)?,
+            );
+        }
+        let threshold = params
+            .latency_threshold_ms
+            .map(|ms| ms.min(i64::MAX as u64) as i64);
+        let diff = diff_messages(&messages[0], &messages[1], threshold);
+
+        let text = match diff.differences.first() {
+            None => format!("sessions match ({} messages)", diff.matched),
+            Some(first) => format!(
+                "{} differences, first at {} message #{}",
+                diff.differences.len(),
+                first.role,
+                first.index
+            ),
+        };
+        let mut structured = match serde_json::to_value(&diff) {
+            Ok(serde_json::Value::Object(map)) => map,
+            _ => serde_json::Map::new(),
+        };
+        structured.insert(
+            "expected_session_id".into(),
+            json!(params.expected_session_id),
+        );
+        structured.insert("actual_session_id".into(), json!(params.actual_session_id));
+        Ok(CallToolResult::text_content(vec![TextContent::from(text)])
+            .with_structured_content(structured))
+    }
     async fn close_session_impl(
         &self,
         session_id: String,
@@ -855,10 +3187,11 @@ impl SerialServerHandler {
         &self,
         tool: DetectPortTool,
     ) -> Result<CallToolResult, CallToolError> {
-        use crate::negotiation::{AutoNegotiator, NegotiationHints};
+        use crate::negotiation::NegotiationHints;
 
         let mut hints = NegotiationHints {
             timeout_ms: tool.timeout_ms,
+            settle_delay_ms: tool.settle_delay_ms,
             ..Default::default()
         };
 
@@ -879,41 +3212,37 @@ impl SerialServerHandler {
             hints.suggested_baud_rates = rates;
         }
 
-        let negotiator = AutoNegotiator::new();
-        let params = if let Some(strategy) = &tool.preferred_strategy {
-            negotiator
-                .detect_with_preference(&tool.port_name, Some(hints), strategy)
-                .await
-        } else {
-            negotiator.detect(&tool.port_name, Some(hints)).await
-        };
+        let params = self
+            .negotiator
+            .detect_cached(
+                &tool.port_name,
+                Some(hints),
+                tool.preferred_strategy.as_deref(),
+                tool.use_cache,
+                tool.force,
+            )
+            .await;
 
         match params {
-            Ok(p) => {
+            Ok((p, from_cache)) => {
                 let mut structured = serde_json::Map::new();
                 structured.insert("port_name".into(), json!(tool.port_name));
                 structured.insert("baud_rate".into(), json!(p.baud_rate));
-                structured.insert(
-                    "data_bits".into(),
-                    json!(format!("{:?}", p.data_bits).to_lowercase()),
-                );
-                structured.insert(
-                    "parity".into(),
-                    json!(format!("{:?}", p.parity).to_lowercase()),
-                );
-                structured.insert(
-                    "stop_bits".into(),
-                    json!(format!("{:?}", p.stop_bits).to_lowercase()),
-                );
-                structured.insert(
-                    "flow_control".into(),
-                    json!(format!("{:?}", p.flow_control).to_lowercase()),
-                );
+                structured.extend(line_settings_json(
+                    p.data_bits.into(),
+                    p.parity.into(),
+                    p.stop_bits.into(),
+                    p.flow_control.into(),
+                ));
                 structured.insert("strategy_used".into(), json!(p.strategy_used));
                 structured.insert("confidence".into(), json!(p.confidence));
+                structured.insert("from_cache".into(), json!(from_cache));
                 Ok(CallToolResult::text_content(vec![TextContent::from(format!(
-                    "Detected {} baud (strategy: {}, confidence: {})",
-                    p.baud_rate, p.strategy_used, p.confidence
+                    "Detected {} baud (strategy: {}, confidence: {}{})",
+                    p.baud_rate,
+                    p.strategy_used,
+                    p.confidence,
+                    if from_cache { ", cached" } else { "" }
                 ))])
                 .with_structured_content(structured))
             }
@@ -929,7 +3258,7 @@ impl SerialServerHandler {
         &self,
         tool: OpenPortAutoTool,
     ) -> Result<CallToolResult, CallToolError> {
-        use crate::negotiation::{AutoNegotiator, NegotiationHints};
+        use crate::negotiation::NegotiationHints;
 
         // Check if port is already open using service
         if self.service.is_open() {
@@ -954,73 +3283,57 @@ impl SerialServerHandler {
         }
         hints.manufacturer = tool.manufacturer.clone();
 
-        // Auto-detect parameters
-        let negotiator = AutoNegotiator::new();
-        let params = negotiator
-            .detect(&tool.port_name, Some(hints))
+        // Auto-detect parameters, reusing an earlier detection of this port
+        // unless the caller opts out
+        let (params, from_cache) = self
+            .negotiator
+            .detect_cached(
+                &tool.port_name,
+                Some(hints),
+                None,
+                tool.use_cache,
+                tool.force,
+            )
             .await
             .map_err(|e| CallToolError::from_message(format!("Auto-detection failed: {}", e)))?;
 
         // Open the port with detected parameters
-        let config = PortConfiguration {
+        let config = OpenConfig {
+            port_name: tool.port_name.clone(),
             baud_rate: params.baud_rate,
-            timeout: Duration::from_millis(tool.timeout_ms),
-            data_bits: params.data_bits,
-            parity: params.parity,
-            stop_bits: params.stop_bits,
-            flow_control: params.flow_control,
-        };
-
-        let port = SyncSerialPort::open(&tool.port_name, config)
-            .map_err(|e| CallToolError::from_message(e.to_string()))?;
-
-        // Update state
-        let mut st = self
-            .state
-            .lock()
-            .map_err(|_| CallToolError::from_message("State lock poisoned"))?;
-        *st = PortState::Open {
-            port: Box::new(port),
-            config: PortConfig {
-                port_name: tool.port_name.clone(),
-                baud_rate: params.baud_rate,
-                timeout_ms: tool.timeout_ms,
-                data_bits: match params.data_bits {
-                    crate::port::DataBits::Five => DataBitsCfg::Five,
-                    crate::port::DataBits::Six => DataBitsCfg::Six,
-                    crate::port::DataBits::Seven => DataBitsCfg::Seven,
-                    crate::port::DataBits::Eight => DataBitsCfg::Eight,
-                },
-                parity: match params.parity {
-                    crate::port::Parity::None => ParityCfg::None,
-                    crate::port::Parity::Odd => ParityCfg::Odd,
-                    crate::port::Parity::Even => ParityCfg::Even,
-                },
-                stop_bits: match params.stop_bits {
-                    crate::port::StopBits::One => StopBitsCfg::One,
-                    crate::port::StopBits::Two => StopBitsCfg::Two,
-                },
-                flow_control: match params.flow_control {
-                    crate::port::FlowControl::None => FlowControlCfg::None,
-                    crate::port::FlowControl::Hardware => FlowControlCfg::Hardware,
-                    crate::port::FlowControl::Software => FlowControlCfg::Software,
-                },
-                terminator: tool.terminator,
-                idle_disconnect_ms: tool.idle_disconnect_ms,
-            },
-            last_activity: std::time::Instant::now(),
-            timeout_streak: 0,
-            bytes_read_total: 0,
-            bytes_written_total: 0,
-            idle_close_count: 0,
-            open_started: std::time::Instant::now(),
+            timeout_ms: tool.timeout_ms,
+            data_bits: params.data_bits.into(),
+            parity: params.parity.into(),
+            stop_bits: params.stop_bits.into(),
+            flow_control: params.flow_control.into(),
+            // An explicit terminator wins over the one learned during detection
+            terminator: tool.terminator.or_else(|| params.terminator.clone()),
+            idle_disconnect_ms: tool.idle_disconnect_ms,
+            read_idle_gap_ms: tool.read_idle_gap_ms,
+            write_checksum: tool.write_checksum,
+            half_duplex_echo: tool.half_duplex_echo,
+            flush_on_write: tool.flush_on_write,
+            auto_detect_terminator: false,
+            mask_parity_bit: None,
+            strip_echo: false,
+            strip_prompt: None,
+            max_open_duration_ms: None,
+            open_retries: None,
+            open_retry_delay_ms: None,
+            require_response: None,
+            read_strategy: Default::default(),
         };
+        let opened = self
+            .run_blocking(move |service| service.open(config))
+            .await?;
 
         let mut structured = serde_json::Map::new();
         structured.insert("port_name".into(), json!(tool.port_name));
         structured.insert("baud_rate".into(), json!(params.baud_rate));
+        structured.insert("terminator".into(), json!(opened.config.terminator));
         structured.insert("strategy_used".into(), json!(params.strategy_used));
         structured.insert("confidence".into(), json!(params.confidence));
+        structured.insert("from_cache".into(), json!(from_cache));
         Ok(CallToolResult::text_content(vec![TextContent::from(format!(
             "Opened {} at {} baud (auto-detected)",
             tool.port_name, params.baud_rate
@@ -1041,6 +3354,7 @@ impl SerialServerHandler {
                     "name": p.name,
                     "default_baud": p.default_baud,
                     "common_bauds": p.common_bauds,
+                    "settle_delay_ms": p.settle_delay_ms,
                 })
             })
             .collect();
@@ -1057,6 +3371,73 @@ impl SerialServerHandler {
     }
 }
 
+/// Every tool this build offers, in `list_tools` order.
+fn tool_list() -> Vec<rust_mcp_sdk::schema::Tool> {
+    vec![
+        ListPortsTool::tool(),
+        ListPortsExtendedTool::tool(),
+        FindPortTool::tool(),
+        OpenPortTool::tool(),
+        open_only_port_tool(),
+        WriteTool::tool(),
+        PreviewWriteTool::tool(),
+        WriteRepeatTool::tool(),
+        ReadTool::tool(),
+        ReadLineTool::tool(),
+        CaptureUntilSilenceTool::tool(),
+        SubscribeReadsTool::tool(),
+        UnsubscribeReadsTool::tool(),
+        DecodeBytesTool::tool(),
+        WaitForPatternTool::tool(),
+        LoopbackTestTool::tool(),
+        BenchmarkTool::tool(),
+        ModbusReadManyTool::tool(),
+        ProtocolCommandTool::tool(),
+        SetSignalsTool::tool(),
+        ResetDeviceTool::tool(),
+        DiagnoseTool::tool(),
+        DescribeToolsTool::tool(),
+        ServerInfoTool::tool(),
+        GetConfigTool::tool(),
+        ReloadConfigTool::tool(),
+        ResetSubsystemTool::tool(),
+        #[cfg(feature = "tui")]
+        ListThemesTool::tool(),
+        #[cfg(feature = "tui")]
+        SetThemeTool::tool(),
+        CancelReadTool::tool(),
+        CloseTool::tool(),
+        ReconnectTool::tool(),
+        StatusTool::tool(),
+        PortCapabilitiesTool::tool(),
+        SetBufferSizesTool::tool(),
+        MetricsTool::tool(),
+        MetricsHistoryTool::tool(),
+        RecentErrorsTool::tool(),
+        ReconfigurePortTool::tool(),
+        PatchConfigTool::tool(),
+        SetFramingTool::tool(),
+        CreateSessionTool::tool(),
+        GetOrCreateSessionTool::tool(),
+        AppendMessageTool::tool(),
+        AppendMessagesTool::tool(),
+        ListMessagesTool::tool(),
+        ExportSessionTool::tool(),
+        FilterMessagesTool::tool(),
+        FeatureIndexTool::tool(),
+        SessionStatsTool::tool(),
+        ReplaySessionTool::tool(),
+        DiffSessionsTool::tool(),
+        #[cfg(feature = "auto-negotiation")]
+        DetectPortTool::tool(),
+        #[cfg(feature = "auto-negotiation")]
+        OpenPortAutoTool::tool(),
+        #[cfg(feature = "auto-negotiation")]
+        ListManufacturerProfilesTool::tool(),
+        // session tools descriptors will be injected dynamically later if needed
+    ]
+}
+
 #[async_trait]
 impl ServerHandler for SerialServerHandler {
     async fn handle_list_tools_request(
@@ -1065,31 +3446,7 @@ impl ServerHandler for SerialServerHandler {
         _rt: Arc<dyn McpServer>,
     ) -> Result<ListToolsResult, RpcError> {
         Ok(ListToolsResult {
-            tools: vec![
-                ListPortsTool::tool(),
-                ListPortsExtendedTool::tool(),
-                OpenPortTool::tool(),
-                WriteTool::tool(),
-                ReadTool::tool(),
-                CloseTool::tool(),
-                StatusTool::tool(),
-                MetricsTool::tool(),
-                ReconfigurePortTool::tool(),
-                CreateSessionTool::tool(),
-                AppendMessageTool::tool(),
-                ListMessagesTool::tool(),
-                ExportSessionTool::tool(),
-                FilterMessagesTool::tool(),
-                FeatureIndexTool::tool(),
-                SessionStatsTool::tool(),
-                #[cfg(feature = "auto-negotiation")]
-                DetectPortTool::tool(),
-                #[cfg(feature = "auto-negotiation")]
-                OpenPortAutoTool::tool(),
-                #[cfg(feature = "auto-negotiation")]
-                ListManufacturerProfilesTool::tool(),
-                // session tools descriptors will be injected dynamically later if needed
-            ],
+            tools: tool_list(),
             meta: None,
             next_cursor: None,
         })
@@ -1098,218 +3455,437 @@ impl ServerHandler for SerialServerHandler {
     async fn handle_call_tool_request(
         &self,
         req: CallToolRequest,
-        _rt: Arc<dyn McpServer>,
+        rt: Arc<dyn McpServer>,
     ) -> Result<CallToolResult, CallToolError> {
+        let _control_call = self.service.begin_control_call();
         match req.tool_name() {
-            n if n == ListPortsTool::tool_name() => self.list_ports_impl(),
-            n if n == ListPortsExtendedTool::tool_name() => self.list_ports_extended_impl(),
+            n if n == ListPortsTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let fresh = args.get("fresh").and_then(|v| v.as_bool()).unwrap_or(false);
+                self.list_ports_impl(ListPortsTool { fresh })
+            }
+            n if n == ListPortsExtendedTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let fresh = args.get("fresh").and_then(|v| v.as_bool()).unwrap_or(false);
+                self.list_ports_extended_impl(ListPortsExtendedTool { fresh })
+            }
+            n if n == FindPortTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<FindPortTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                FindPortTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.find_port_impl(params)
+            }
             n if n == OpenPortTool::tool_name() => {
-                // Manually parse args from request params
                 let args = req.params.arguments.clone().unwrap_or_default();
-                let port_name = args
-                    .get("port_name")
+                let params = parse_open_port_args(&args)?;
+                self.open_port_impl(params).await
+            }
+            n if n == OpenOnlyPortTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                self.open_only_port_impl(args).await
+            }
+            n if n == WriteTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params = serde_json::from_value::<WriteTool>(serde_json::Value::Object(args))
+                    .map_err(|e| {
+                    CallToolError::invalid_arguments(WriteTool::tool_name(), Some(e.to_string()))
+                })?;
+                self.write_impl(params).await
+            }
+            n if n == PreviewWriteTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<PreviewWriteTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                PreviewWriteTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.preview_write_impl(params)
+            }
+            n if n == WriteRepeatTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let data = args
+                    .get("data")
+                    .and_then(|v| v.as_str())
+                    .ok_or_else(|| {
+                        CallToolError::invalid_arguments(
+                            WriteRepeatTool::tool_name(),
+                            Some("data missing".into()),
+                        )
+                    })?
+                    .to_string();
+                let count = args
+                    .get("count")
+                    .and_then(|v| v.as_u64())
+                    .and_then(|v| u32::try_from(v).ok())
+                    .ok_or_else(|| {
+                        CallToolError::invalid_arguments(
+                            WriteRepeatTool::tool_name(),
+                            Some("count missing or invalid".into()),
+                        )
+                    })?;
+                let delay_ms = args.get("delay_ms").and_then(|v| v.as_u64());
+                self.write_repeat_impl(WriteRepeatTool {
+                    data,
+                    count,
+                    delay_ms,
+                })
+                .await
+            }
+            n if n == ReadTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let max_bytes = args.get("max_bytes").and_then(|v| v.as_u64());
+                let encoding = match args.get("encoding") {
+                    None | Some(serde_json::Value::Null) => None,
+                    Some(v) => Some(serde_json::from_value::<ReadEncoding>(v.clone()).map_err(
+                        |_| {
+                            CallToolError::invalid_arguments(
+                                ReadTool::tool_name(),
+                                Some("encoding must be utf8, base64 or hexdump".into()),
+                            )
+                        },
+                    )?),
+                };
+                let read_transform = match args.get("read_transform") {
+                    None | Some(serde_json::Value::Null) => None,
+                    Some(v) => Some(serde_json::from_value::<ReadTransform>(v.clone()).map_err(
+                        |e| {
+                            CallToolError::invalid_arguments(
+                                ReadTool::tool_name(),
+                                Some(format!("invalid read_transform: {e}")),
+                            )
+                        },
+                    )?),
+                };
+                self.read_impl(ReadTool {
+                    max_bytes,
+                    encoding,
+                    read_transform,
+                    include_hex: args
+                        .get("include_hex")
+                        .and_then(|v| v.as_bool())
+                        .unwrap_or(false),
+                    long_poll_ms: args.get("long_poll_ms").and_then(|v| v.as_u64()),
+                })
+                .await
+            }
+            n if n == SetSignalsTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                self.set_signals_impl(SetSignalsTool {
+                    dtr: args.get("dtr").and_then(|v| v.as_bool()),
+                    rts: args.get("rts").and_then(|v| v.as_bool()),
+                    hold_ms: args.get("hold_ms").and_then(|v| v.as_u64()),
+                    restore: args.get("restore").and_then(|v| v.as_bool()),
+                })
+                .await
+            }
+            n if n == ReadLineTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                self.read_line_impl(ReadLineTool {
+                    overall_timeout_ms: args.get("overall_timeout_ms").and_then(|v| v.as_u64()),
+                    idle_timeout_ms: args.get("idle_timeout_ms").and_then(|v| v.as_u64()),
+                })
+                .await
+            }
+            n if n == CaptureUntilSilenceTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let silence_ms =
+                    args.get("silence_ms")
+                        .and_then(|v| v.as_u64())
+                        .ok_or_else(|| {
+                            CallToolError::invalid_arguments(
+                                CaptureUntilSilenceTool::tool_name(),
+                                Some("silence_ms missing or invalid".into()),
+                            )
+                        })?;
+                self.capture_until_silence_impl(CaptureUntilSilenceTool {
+                    silence_ms,
+                    max_bytes: args.get("max_bytes").and_then(|v| v.as_u64()),
+                    overall_timeout_ms: args.get("overall_timeout_ms").and_then(|v| v.as_u64()),
+                })
+                .await
+            }
+            n if n == SubscribeReadsTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<SubscribeReadsTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                        CallToolError::invalid_arguments(
+                            SubscribeReadsTool::tool_name(),
+                            Some(e.to_string()),
+                        )
+                    })?;
+                // Forward to the client until the stream (holding the sender) ends
+                let (notify, mut notifications) = mpsc::unbounded_channel();
+                tokio::spawn(async move {
+                    while let Some(notification) = notifications.recv().await {
+                        if let Err(e) = rt.send_logging_message(notification).await {
+                            tracing::warn!(error = %e, "Failed to send read notification");
+                            break;
+                        }
+                    }
+                });
+                self.subscribe_reads_impl(params, notify)
+            }
+            n if n == UnsubscribeReadsTool::tool_name() => self.unsubscribe_reads_impl(),
+            n if n == DecodeBytesTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<DecodeBytesTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                DecodeBytesTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.decode_bytes_impl(params)
+            }
+            n if n == WaitForPatternTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let pattern = args
+                    .get("pattern")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| {
                         CallToolError::invalid_arguments(
-                            OpenPortTool::tool_name(),
-                            Some("port_name missing".into()),
+                            WaitForPatternTool::tool_name(),
+                            Some("pattern missing".into()),
                         )
                     })?
                     .to_string();
-                let baud_rate = args
-                    .get("baud_rate")
-                    .and_then(|v| v.as_u64())
-                    .ok_or_else(|| {
-                        CallToolError::invalid_arguments(
-                            OpenPortTool::tool_name(),
-                            Some("baud_rate missing".into()),
-                        )
-                    })? as u32;
-                let timeout_ms = args
-                    .get("timeout_ms")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(1000);
-                // Helper to parse enum from string
-                let parse_enum = |key: &str| {
-                    args.get(key)
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_lowercase())
-                };
-                let data_bits = match parse_enum("data_bits").as_deref() {
-                    None => default_data_bits(),
-                    Some("5" | "five") => DataBitsCfg::Five,
-                    Some("6" | "six") => DataBitsCfg::Six,
-                    Some("7" | "seven") => DataBitsCfg::Seven,
-                    Some("8" | "eight") => DataBitsCfg::Eight,
-                    Some(other) => {
-                        return Err(CallToolError::invalid_arguments(
-                            OpenPortTool::tool_name(),
-                            Some(format!("invalid data_bits: {other}")),
-                        ))
-                    }
-                };
-                let parity = match parse_enum("parity").as_deref() {
-                    None => default_parity(),
-                    Some("none") => ParityCfg::None,
-                    Some("odd") => ParityCfg::Odd,
-                    Some("even") => ParityCfg::Even,
-                    Some(other) => {
-                        return Err(CallToolError::invalid_arguments(
-                            OpenPortTool::tool_name(),
-                            Some(format!("invalid parity: {other}")),
-                        ))
-                    }
-                };
-                let stop_bits = match parse_enum("stop_bits").as_deref() {
-                    None => default_stop_bits(),
-                    Some("1" | "one") => StopBitsCfg::One,
-                    Some("2" | "two") => StopBitsCfg::Two,
-                    Some(other) => {
-                        return Err(CallToolError::invalid_arguments(
-                            OpenPortTool::tool_name(),
-                            Some(format!("invalid stop_bits: {other}")),
-                        ))
-                    }
-                };
-                let flow_control = match parse_enum("flow_control").as_deref() {
-                    None => default_flow_control(),
-                    Some("none") => FlowControlCfg::None,
-                    Some("hardware" | "rtscts") => FlowControlCfg::Hardware,
-                    Some("software" | "xonxoff") => FlowControlCfg::Software,
-                    Some(other) => {
-                        return Err(CallToolError::invalid_arguments(
-                            OpenPortTool::tool_name(),
-                            Some(format!("invalid flow_control: {other}")),
-                        ))
-                    }
-                };
-                let terminator = args
-                    .get("terminator")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-                let idle_disconnect_ms = args.get("idle_disconnect_ms").and_then(|v| v.as_u64());
-                self.open_port_impl(OpenPortTool {
-                    port_name,
-                    baud_rate,
+                let timeout_ms =
+                    args.get("timeout_ms")
+                        .and_then(|v| v.as_u64())
+                        .ok_or_else(|| {
+                            CallToolError::invalid_arguments(
+                                WaitForPatternTool::tool_name(),
+                                Some("timeout_ms missing or invalid".into()),
+                            )
+                        })?;
+                self.wait_for_pattern_impl(WaitForPatternTool {
+                    pattern,
                     timeout_ms,
-                    data_bits,
-                    parity,
-                    stop_bits,
-                    flow_control,
-                    terminator,
-                    idle_disconnect_ms,
                 })
+                .await
             }
-            n if n == WriteTool::tool_name() => {
+            n if n == LoopbackTestTool::tool_name() => {
                 let args = req.params.arguments.clone().unwrap_or_default();
-                let data = args
-                    .get("data")
+                self.loopback_test_impl(LoopbackTestTool {
+                    token_len: args.get("token_len").and_then(|v| v.as_u64()),
+                    timeout_ms: args.get("timeout_ms").and_then(|v| v.as_u64()),
+                })
+                .await
+            }
+            n if n == BenchmarkTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<BenchmarkTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                BenchmarkTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.benchmark_impl(params).await
+            }
+            n if n == ResetDeviceTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<ResetDeviceTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                ResetDeviceTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.reset_device_impl(params).await
+            }
+            n if n == ModbusReadManyTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<ModbusReadManyTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                        CallToolError::invalid_arguments(
+                            ModbusReadManyTool::tool_name(),
+                            Some(e.to_string()),
+                        )
+                    })?;
+                self.modbus_read_many_impl(params).await
+            }
+            n if n == ProtocolCommandTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<ProtocolCommandTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                ProtocolCommandTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.protocol_command_impl(params).await
+            }
+            n if n == DiagnoseTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<DiagnoseTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                DiagnoseTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.diagnose_impl(params).await
+            }
+            n if n == ReplaySessionTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<ReplaySessionTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                ReplaySessionTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.replay_session_impl(params).await
+            }
+            n if n == DiffSessionsTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<DiffSessionsTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                DiffSessionsTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.diff_sessions_impl(params).await
+            }
+            n if n == DescribeToolsTool::tool_name() => self.describe_tools_impl(),
+            n if n == ServerInfoTool::tool_name() => self.server_info_impl(),
+            n if n == GetConfigTool::tool_name() => self.get_config_impl(),
+            n if n == ReloadConfigTool::tool_name() => self.reload_config_impl(),
+            n if n == ResetSubsystemTool::tool_name() => self.reset_subsystem_impl().await,
+            #[cfg(feature = "tui")]
+            n if n == ListThemesTool::tool_name() => self.list_themes_impl(),
+            #[cfg(feature = "tui")]
+            n if n == SetThemeTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let name = args
+                    .get("name")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| {
                         CallToolError::invalid_arguments(
-                            WriteTool::tool_name(),
-                            Some("data missing".into()),
+                            SetThemeTool::tool_name(),
+                            Some("name missing".into()),
                         )
                     })?
                     .to_string();
-                self.write_impl(WriteTool { data })
+                self.set_theme_impl(SetThemeTool { name })
             }
-            n if n == ReadTool::tool_name() => self.read_impl(),
+            n if n == CancelReadTool::tool_name() => self.cancel_read_impl(),
             n if n == CloseTool::tool_name() => self.close_impl(),
-            n if n == StatusTool::tool_name() => self.status_impl(),
-            n if n == MetricsTool::tool_name() => self.metrics_impl(),
+            n if n == ReconnectTool::tool_name() => self.reconnect_impl(),
+            n if n == StatusTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params = serde_json::from_value::<StatusTool>(serde_json::Value::Object(args))
+                    .map_err(|e| {
+                        CallToolError::invalid_arguments(
+                            StatusTool::tool_name(),
+                            Some(e.to_string()),
+                        )
+                    })?;
+                self.status_impl(params.verify).await
+            }
+            n if n == PortCapabilitiesTool::tool_name() => self.port_capabilities_impl(),
+            n if n == SetBufferSizesTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<SetBufferSizesTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                        CallToolError::invalid_arguments(
+                            SetBufferSizesTool::tool_name(),
+                            Some(e.to_string()),
+                        )
+                    })?;
+                self.set_buffer_sizes_impl(params)
+            }
+            n if n == MetricsTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params = serde_json::from_value::<MetricsTool>(serde_json::Value::Object(args))
+                    .map_err(|e| {
+                        CallToolError::invalid_arguments(
+                            MetricsTool::tool_name(),
+                            Some(e.to_string()),
+                        )
+                    })?;
+                self.metrics_impl(params.verify).await
+            }
+            n if n == MetricsHistoryTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                self.metrics_history_impl(MetricsHistoryTool {
+                    limit: args.get("limit").and_then(|v| v.as_u64()),
+                })
+            }
+            n if n == RecentErrorsTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<RecentErrorsTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                RecentErrorsTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                self.recent_errors_impl(params)
+            }
             n if n == ReconfigurePortTool::tool_name() => {
                 let args = req.params.arguments.clone().unwrap_or_default();
-                let parse_enum = |key: &str| {
-                    args.get(key)
-                        .and_then(|v| v.as_str())
-                        .map(|s| s.to_lowercase())
-                };
-                let data_bits = match parse_enum("data_bits").as_deref() {
-                    None => default_data_bits(),
-                    Some("5" | "five") => DataBitsCfg::Five,
-                    Some("6" | "six") => DataBitsCfg::Six,
-                    Some("7" | "seven") => DataBitsCfg::Seven,
-                    Some("8" | "eight") => DataBitsCfg::Eight,
-                    Some(other) => {
-                        return Err(CallToolError::invalid_arguments(
-                            ReconfigurePortTool::tool_name(),
-                            Some(format!("invalid data_bits: {other}")),
-                        ))
-                    }
-                };
-                let parity = match parse_enum("parity").as_deref() {
-                    None => default_parity(),
-                    Some("none") => ParityCfg::None,
-                    Some("odd") => ParityCfg::Odd,
-                    Some("even") => ParityCfg::Even,
-                    Some(other) => {
-                        return Err(CallToolError::invalid_arguments(
-                            ReconfigurePortTool::tool_name(),
-                            Some(format!("invalid parity: {other}")),
-                        ))
-                    }
-                };
-                let stop_bits = match parse_enum("stop_bits").as_deref() {
-                    None => default_stop_bits(),
-                    Some("1" | "one") => StopBitsCfg::One,
-                    Some("2" | "two") => StopBitsCfg::Two,
-                    Some(other) => {
-                        return Err(CallToolError::invalid_arguments(
-                            ReconfigurePortTool::tool_name(),
-                            Some(format!("invalid stop_bits: {other}")),
-                        ))
-                    }
-                };
-                let flow_control = match parse_enum("flow_control").as_deref() {
-                    None => default_flow_control(),
-                    Some("none") => FlowControlCfg::None,
-                    Some("hardware" | "rtscts") => FlowControlCfg::Hardware,
-                    Some("software" | "xonxoff") => FlowControlCfg::Software,
-                    Some(other) => {
-                        return Err(CallToolError::invalid_arguments(
-                            ReconfigurePortTool::tool_name(),
-                            Some(format!("invalid flow_control: {other}")),
-                        ))
-                    }
-                };
-                let port_name = args
-                    .get("port_name")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-                let baud_rate = args
-                    .get("baud_rate")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(9600) as u32;
-                let timeout_ms = args
-                    .get("timeout_ms")
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(1000);
-                let terminator = args
-                    .get("terminator")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-                let idle_disconnect_ms = args.get("idle_disconnect_ms").and_then(|v| v.as_u64());
-                self.reconfigure_port_impl(ReconfigurePortTool {
-                    port_name,
-                    baud_rate,
-                    timeout_ms,
-                    data_bits,
-                    parity,
-                    stop_bits,
-                    flow_control,
-                    terminator,
-                    idle_disconnect_ms,
+                let config = parse_reconfigure_port_args(&args)?;
+                self.reconfigure_port_impl(config).await
+            }
+            n if n == PatchConfigTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let patch = parse_settings_patch(PatchConfigTool::tool_name(), &args)?;
+                self.patch_config_impl(patch).await
+            }
+            n if n == SetFramingTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                // Same enum spellings as patch_config; other settings are ignored
+                let patch = parse_settings_patch(SetFramingTool::tool_name(), &args)?;
+                self.set_framing_impl(FramingRequest {
+                    data_bits: patch.data_bits,
+                    parity: patch.parity,
+                    stop_bits: patch.stop_bits,
                 })
+                .await
             }
             n if n == CreateSessionTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let params =
+                    serde_json::from_value::<CreateSessionTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                            CallToolError::invalid_arguments(
+                                CreateSessionTool::tool_name(),
+                                Some(e.to_string()),
+                            )
+                        })?;
+                return self.create_session_impl(params).await;
+            }
+            n if n == GetOrCreateSessionTool::tool_name() => {
                 let args = req.params.arguments.clone().unwrap_or_default();
                 let device_id = args
                     .get("device_id")
                     .and_then(|v| v.as_str())
                     .ok_or_else(|| {
                         CallToolError::invalid_arguments(
-                            CreateSessionTool::tool_name(),
+                            GetOrCreateSessionTool::tool_name(),
                             Some("device_id missing".into()),
                         )
                     })?
@@ -1318,8 +3894,12 @@ impl ServerHandler for SerialServerHandler {
                     .get("port_name")
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string());
-                // execute async (handler method is async so we can await here by returning future resolved value)
-                return self.create_session_impl(device_id, port_name).await;
+                return self
+                    .get_or_create_session_impl(GetOrCreateSessionTool {
+                        device_id,
+                        port_name,
+                    })
+                    .await;
             }
             n if n == AppendMessageTool::tool_name() => {
                 let args = req.params.arguments.clone().unwrap_or_default();
@@ -1363,6 +3943,18 @@ impl ServerHandler for SerialServerHandler {
                     )
                     .await;
             }
+            n if n == AppendMessagesTool::tool_name() => {
+                let args = req.params.arguments.clone().unwrap_or_default();
+                let tool =
+                    serde_json::from_value::<AppendMessagesTool>(serde_json::Value::Object(args))
+                        .map_err(|e| {
+                        CallToolError::invalid_arguments(
+                            AppendMessagesTool::tool_name(),
+                            Some(e.to_string()),
+                        )
+                    })?;
+                return self.append_messages_impl(tool).await;
+            }
             n if n == ListMessagesTool::tool_name() => {
                 let args = req.params.arguments.clone().unwrap_or_default();
                 let session_id = args
@@ -1551,6 +4143,15 @@ impl ServerHandler for SerialServerHandler {
                     .get("preferred_strategy")
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string());
+                let settle_delay_ms = args
+                    .get("settle_delay_ms")
+                    .and_then(|v| v.as_u64())
+                    .unwrap_or(0);
+                let use_cache = args
+                    .get("use_cache")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(true);
+                let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
                 return self
                     .detect_port_impl(DetectPortTool {
                         port_name,
@@ -1560,6 +4161,9 @@ impl ServerHandler for SerialServerHandler {
                         suggested_baud_rates,
                         timeout_ms,
                         preferred_strategy,
+                        settle_delay_ms,
+                        use_cache,
+                        force,
                     })
                     .await;
             }
@@ -1596,7 +4200,35 @@ impl ServerHandler for SerialServerHandler {
                     .get("terminator")
                     .and_then(|v| v.as_str())
                     .map(|s| s.to_string());
-                let idle_disconnect_ms = args.get("idle_disconnect_ms").and_then(|v| v.as_u64());
+                let idle_disconnect_ms = parse_idle_disconnect_arg(&args);
+                let read_idle_gap_ms = args.get("read_idle_gap_ms").and_then(|v| v.as_u64());
+                let write_checksum = match args.get("write_checksum") {
+                    None | Some(serde_json::Value::Null) => None,
+                    Some(v) => Some(serde_json::from_value::<ChecksumKind>(v.clone()).map_err(
+                        |_| {
+                            CallToolError::invalid_arguments(
+                                OpenPortAutoTool::tool_name(),
+                                Some(
+                                    "write_checksum must be one of crc16_modbus, crc8, xor8, sum8"
+                                        .into(),
+                                ),
+                            )
+                        },
+                    )?),
+                };
+                let half_duplex_echo = args
+                    .get("half_duplex_echo")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let flush_on_write = args
+                    .get("flush_on_write")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false);
+                let use_cache = args
+                    .get("use_cache")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(true);
+                let force = args.get("force").and_then(|v| v.as_bool()).unwrap_or(false);
                 return self
                     .open_port_auto_impl(OpenPortAutoTool {
                         port_name,
@@ -1606,6 +4238,12 @@ impl ServerHandler for SerialServerHandler {
                         timeout_ms,
                         terminator,
                         idle_disconnect_ms,
+                        read_idle_gap_ms,
+                        write_checksum,
+                        half_duplex_echo,
+                        flush_on_write,
+                        use_cache,
+                        force,
                     })
                     .await;
             }
@@ -1622,7 +4260,9 @@ impl ServerHandler for SerialServerHandler {
 pub async fn start_mcp_server_stdio(
     state: AppState,
     session_store: crate::session::SessionStore,
+    loader: &crate::config::ConfigLoader,
 ) -> SdkResult<()> {
+    let config = loader.config();
     let details = InitializeResult {
         server_info: Implementation {
             name: "Serial MCP Server".into(),
@@ -1631,6 +4271,8 @@ pub async fn start_mcp_server_stdio(
         },
         capabilities: ServerCapabilities {
             tools: Some(ServerCapabilitiesTools { list_changed: None }),
+            // subscribe_reads delivers data as notifications/message
+            logging: Some(serde_json::Map::new()),
             ..Default::default()
         },
         meta: None,
@@ -1638,34 +4280,658 @@ pub async fn start_mcp_server_stdio(
         protocol_version: LATEST_PROTOCOL_VERSION.to_string(),
     };
     let transport = StdioTransport::new(TransportOptions::default())?;
-    // Early heartbeat (now newline-delimited JSON to match rust-mcp-transport's line-based stdio protocol).
-    // The underlying StdioTransport reads newline-delimited JSON objects (not Content-Length frames),
-    // so we emit a lightweight notification line early to help test harnesses detect that the server
-    // is alive without relying on a manual framed message. Disable via MCP_DISABLE_HEARTBEAT.
-    if std::env::var("MCP_DISABLE_HEARTBEAT").is_err() {
-        let hb_body =
-            serde_json::json!({"jsonrpc":"2.0","method":"_heartbeat","params":{}}).to_string();
-        if let Err(e) = writeln!(std::io::stdout(), "{}", hb_body) {
+    // Early heartbeat (newline-delimited JSON to match rust-mcp-transport's line-based stdio protocol)
+    // so test harnesses can detect that the server is alive before initialize completes.
+    let heartbeat = match write_startup_heartbeat(&mut std::io::stdout(), &config.mcp) {
+        Ok(emitted) => emitted,
+        Err(e) => {
             tracing::warn!(error=%e, "failed to write heartbeat JSON");
+            false
         }
-        let _ = std::io::stdout().flush();
-    }
-    // Optional debug boot frame
+    };
+    // Optional debug boot marker; logged to stderr since stdout carries only protocol frames
     if std::env::var("MCP_DEBUG_BOOT").is_ok() {
-        let debug_body = serde_json::json!({"debug":"boot_marker"}).to_string();
-        if let Err(e) = writeln!(std::io::stdout(), "{}", debug_body) {
-            tracing::error!(error = %e, "failed to write debug boot marker");
-        }
-        let _ = std::io::stdout().flush();
+        tracing::info!(marker = "boot_marker", "MCP stdio server booting");
     }
     // Use the provided session store (caller is responsible for lifecycle)
-    let service = Arc::new(PortService::new(state.clone()));
+    let service = Arc::new(
+        PortService::new(state.clone())
+            .with_max_read_bytes(config.serial.max_read_bytes)
+            .with_max_write_repeat_bytes(config.serial.max_write_repeat_bytes)
+            .with_default_idle_disconnect_ms(config.serial.default_idle_disconnect_ms)
+            .with_default_max_open_duration_ms(config.serial.default_max_open_duration_ms)
+            .with_timeout_warn_interval(config.serial.timeout_warn_interval())
+            .with_strict_baud(config.serial.strict_baud)
+            .with_metrics_history_len(config.serial.metrics_history_len)
+            .with_recent_errors_len(config.serial.recent_errors_len)
+            .with_write_allowlist(&config.serial.write_allowlist)
+            .with_device_aliases(config.device_aliases.clone())
+            .with_close_hook(session_store.auto_close_note_hook()),
+    );
+    if let Some(window) = config.serial.inactivity_close() {
+        service.spawn_inactivity_watchdog(window);
+    }
+    service.spawn_metrics_history(config.serial.metrics_history_interval());
     let handler = SerialServerHandler {
         service,
         sessions: session_store,
+        port_cache: Arc::new(crate::port::PortListCache::new(
+            config.serial.port_list_ttl(),
+        )),
+        config: Arc::new(RwLock::new(loader.clone())),
         #[cfg(feature = "auto-negotiation")]
         state,
+        #[cfg(feature = "auto-negotiation")]
+        negotiator: Default::default(),
+        read_stream: Default::default(),
     };
     let server = server_runtime::create_server(details, transport, handler);
+    if let Some(interval) = config.mcp.heartbeat_interval().filter(|_| heartbeat) {
+        // Later beats go through the runtime so they never interleave with a response on stdout
+        let runtime = server.clone();
+        let method = config.mcp.heartbeat_method.method();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let beat = NotificationFromServer::CustomNotification(json!({"method": method}));
+                if let Err(e) = runtime.send_notification(beat).await {
+                    tracing::debug!(error = %e, "failed to send heartbeat");
+                }
+            }
+        });
+    }
     server.start().await
 }
+
+/// Write the startup heartbeat line to `out` if `[mcp] heartbeat` is on and
+/// `MCP_DISABLE_HEARTBEAT` is unset. Returns whether a line was written.
+fn write_startup_heartbeat(
+    out: &mut impl Write,
+    config: &crate::config::McpConfig,
+) -> std::io::Result<bool> {
+    if !config.heartbeat || std::env::var("MCP_DISABLE_HEARTBEAT").is_ok() {
+        return Ok(false);
+    }
+    let body = json!({"jsonrpc": "2.0", "method": config.heartbeat_method.method(), "params": {}});
+    writeln!(out, "{}", body)?;
+    out.flush()?;
+    Ok(true)
+}
+
+/// Handler around `service` with an in-memory session store, default config
+/// and a short-lived port list cache. Shared by the MCP and REST tests.
+#[cfg(test)]
+pub(crate) async fn test_handler(
+    #[cfg_attr(not(feature = "auto-negotiation"), allow(unused_variables))] state: AppState,
+    service: PortService,
+) -> SerialServerHandler {
+    SerialServerHandler {
+        service: Arc::new(service),
+        sessions: SessionStore::new("sqlite::memory:?cache=shared")
+            .await
+            .unwrap(),
+        port_cache: Arc::new(crate::port::PortListCache::new(
+            std::time::Duration::from_secs(1),
+        )),
+        config: Arc::new(RwLock::new(crate::config::ConfigLoader::with_defaults())),
+        #[cfg(feature = "auto-negotiation")]
+        state,
+        #[cfg(feature = "auto-negotiation")]
+        negotiator: Default::default(),
+        read_stream: Default::default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example(name: &str) -> serde_json::Map<String, serde_json::Value> {
+        tool_examples()
+            .into_iter()
+            .find(|(n, _)| *n == name)
+            .and_then(|(_, v)| v.as_object().cloned())
+            .unwrap_or_else(|| panic!("no example for {name}"))
+    }
+
+    #[test]
+    fn test_only_the_startup_heartbeat_writes_to_stdout() {
+        // Needles are split so this test does not match itself
+        let source = include_str!("mcp.rs");
+        let stdout = concat!("std::io::", "stdout()");
+        let writes: Vec<&str> = source
+            .lines()
+            .filter(|line| {
+                line.contains(stdout)
+                    || line.contains(concat!("print", "ln!"))
+                    || line.contains(concat!("print", "!("))
+            })
+            .collect();
+        assert_eq!(
+            writes.len(),
+            1,
+            "stdout writes outside the transport: {writes:#?}"
+        );
+        assert!(writes[0].contains("write_startup_heartbeat"));
+    }
+
+    #[test]
+    fn test_examples_parse_with_real_parsers() {
+        parse_open_port_args(&example("open_port")).expect("open_port example");
+        parse_reconfigure_port_args(&example("reconfigure_port"))
+            .expect("reconfigure_port example");
+        let patch = parse_settings_patch(PatchConfigTool::tool_name(), &example("patch_config"))
+            .expect("patch_config example");
+        assert_eq!(patch.baud_rate, Some(115200));
+        assert_eq!(patch.terminator, Some(None));
+        assert!(patch.parity.is_none());
+        let framing = parse_settings_patch(SetFramingTool::tool_name(), &example("set_framing"))
+            .expect("set_framing example");
+        assert!(matches!(framing.parity, Some(ParityCfg::Even)));
+
+        let v = |name| serde_json::Value::Object(example(name));
+        serde_json::from_value::<ListPortsTool>(v("list_ports")).unwrap();
+        serde_json::from_value::<RecentErrorsTool>(v("recent_errors")).unwrap();
+        serde_json::from_value::<FindPortTool>(v("find_port_by_serial")).unwrap();
+        serde_json::from_value::<StatusTool>(v("status")).unwrap();
+        serde_json::from_value::<MetricsTool>(v("metrics")).unwrap();
+        serde_json::from_value::<WriteTool>(v("write")).unwrap();
+        serde_json::from_value::<PreviewWriteTool>(v("preview_write")).unwrap();
+        serde_json::from_value::<WriteRepeatTool>(v("write_repeat")).unwrap();
+        serde_json::from_value::<ReadTool>(v("read")).unwrap();
+        serde_json::from_value::<ReadLineTool>(v("read_line")).unwrap();
+        serde_json::from_value::<CaptureUntilSilenceTool>(v("read_until_silence")).unwrap();
+        serde_json::from_value::<SubscribeReadsTool>(v("subscribe_reads")).unwrap();
+        serde_json::from_value::<DecodeBytesTool>(v("decode_bytes")).unwrap();
+        serde_json::from_value::<WaitForPatternTool>(v("wait_for_pattern")).unwrap();
+        serde_json::from_value::<LoopbackTestTool>(v("loopback_test")).unwrap();
+        serde_json::from_value::<BenchmarkTool>(v("benchmark")).unwrap();
+        serde_json::from_value::<ModbusReadManyTool>(v("modbus_read_many")).unwrap();
+        serde_json::from_value::<ProtocolCommandTool>(v("protocol_command")).unwrap();
+        serde_json::from_value::<SetSignalsTool>(v("set_signals")).unwrap();
+        serde_json::from_value::<SetBufferSizesTool>(v("set_buffer_sizes")).unwrap();
+        serde_json::from_value::<ResetDeviceTool>(v("reset_device")).unwrap();
+        serde_json::from_value::<DiagnoseTool>(v("diagnose")).unwrap();
+        serde_json::from_value::<ReplaySessionTool>(v("replay_session")).unwrap();
+        serde_json::from_value::<DiffSessionsTool>(v("diff_sessions")).unwrap();
+    }
+
+    #[test]
+    fn test_every_listed_spelling_is_accepted() {
+        let enums = enum_spellings();
+        for key in ["data_bits", "parity", "stop_bits", "flow_control"] {
+            for spelling in enums[key].as_array().unwrap() {
+                let s = spelling.as_str().unwrap();
+                for candidate in [s.to_string(), s.to_uppercase()] {
+                    let mut args = example("open_port");
+                    args.insert(key.into(), json!(candidate));
+                    assert!(
+                        parse_open_port_args(&args).is_ok(),
+                        "{key}={candidate} rejected"
+                    );
+                }
+            }
+        }
+        for spelling in enums["write_checksum"].as_array().unwrap() {
+            let mut args = example("open_port");
+            args.insert("write_checksum".into(), spelling.clone());
+            assert!(parse_open_port_args(&args).is_ok(), "{spelling} rejected");
+        }
+        for spelling in enums["encoding"].as_array().unwrap() {
+            serde_json::from_value::<ReadEncoding>(spelling.clone()).unwrap();
+        }
+        for spelling in enums["write_encoding"].as_array().unwrap() {
+            serde_json::from_value::<WriteEncoding>(spelling.clone()).unwrap();
+        }
+
+        // The numbers responses report are accepted as they are
+        let mut args = example("open_port");
+        args.insert("data_bits".into(), json!(7));
+        args.insert("stop_bits".into(), json!(1.5));
+        let parsed = parse_open_port_args(&args).unwrap();
+        assert_eq!(parsed.data_bits.bits(), 7);
+        assert!(matches!(parsed.stop_bits, StopBitsCfg::OnedotFive));
+
+        let mut args = example("open_port");
+        args.insert("data_bits".into(), json!("8N1"));
+        assert!(parse_open_port_args(&args).is_err());
+        args.insert("data_bits".into(), json!(9));
+        assert!(parse_open_port_args(&args).is_err());
+    }
+
+    #[cfg(feature = "tui")]
+    #[tokio::test]
+    async fn test_list_and_set_theme() {
+        let state: AppState = Arc::new(std::sync::Mutex::new(crate::state::PortState::Closed));
+        let handler = test_handler(state.clone(), PortService::new(state)).await;
+        let mut rx = crate::tui::shared_theme().subscribe();
+
+        let listed = handler
+            .list_themes_impl()
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(
+            listed["themes"],
+            json!(["dark", "light", "solarized", "dracula", "nord"])
+        );
+        assert_eq!(listed["current"], "dark");
+
+        handler
+            .set_theme_impl(SetThemeTool {
+                name: "nord".into(),
+            })
+            .unwrap();
+        assert!(rx.has_changed().unwrap());
+        assert_eq!(*rx.borrow_and_update(), "nord");
+
+        let err = handler
+            .set_theme_impl(SetThemeTool {
+                name: "neon".into(),
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown theme 'neon'"));
+        assert!(!rx.has_changed().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_open_only_port_requires_exactly_one_port() {
+        use serialport::{SerialPortInfo, SerialPortType};
+        let port = |name: &str| SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: SerialPortType::Unknown,
+        };
+        async fn handler(ports: Vec<SerialPortInfo>) -> SerialServerHandler {
+            let state: AppState = Arc::new(std::sync::Mutex::new(crate::state::PortState::Closed));
+            let opener: crate::service::PortOpener = Arc::new(|name: &str, _config| {
+                Ok(Box::new(crate::port::MockSerialPort::new(name)) as crate::state::PortAdapter)
+            });
+            let service = PortService::new(state.clone()).with_port_opener(opener);
+            let mut handler = test_handler(state, service).await;
+            handler.port_cache = Arc::new(crate::port::PortListCache::with_enumerator(
+                std::time::Duration::from_secs(60),
+                Arc::new(move || Ok(ports.clone())),
+            ));
+            handler
+        }
+        let schema = open_only_port_tool().input_schema;
+        let properties = schema.properties.unwrap();
+        assert!(!properties.contains_key("port_name"));
+        assert!(properties.contains_key("strip_prompt"));
+        assert!(!schema.required.contains(&"port_name".to_string()));
+        assert!(schema.required.contains(&"baud_rate".to_string()));
+
+        let args = example("open_only_port");
+
+        let none = handler(vec![]).await;
+        let err = none.open_only_port_impl(args.clone()).await.unwrap_err();
+        assert!(err.to_string().contains("no serial ports found"), "{err}");
+        assert!(!none.service.is_open());
+
+        let one = handler(vec![port("/dev/ttyUSB0")]).await;
+        let opened = one
+            .open_only_port_impl(args.clone())
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(opened["port_name"], "/dev/ttyUSB0");
+        assert_eq!(opened["baud_rate"], 115200);
+        assert!(one.service.is_open());
+
+        let many = handler(vec![port("/dev/ttyUSB0"), port("/dev/ttyACM0")]).await;
+        let err = many.open_only_port_impl(args.clone()).await.unwrap_err();
+        assert!(
+            err.to_string().contains("[/dev/ttyUSB0, /dev/ttyACM0]"),
+            "{err}"
+        );
+        assert!(!many.service.is_open());
+
+        let mut named = args;
+        named.insert("port_name".into(), json!("/dev/ttyUSB0"));
+        assert!(handler(vec![port("/dev/ttyUSB0")])
+            .await
+            .open_only_port_impl(named)
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_find_port_by_serial_and_manufacturer() {
+        use serialport::{SerialPortInfo, SerialPortType, UsbPortInfo};
+        let usb = |name: &str, serial: &str, manufacturer: &str| SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: SerialPortType::UsbPort(UsbPortInfo {
+                vid: 0x0403,
+                pid: 0x6001,
+                serial_number: Some(serial.to_string()),
+                manufacturer: Some(manufacturer.to_string()),
+                product: None,
+                location: None,
+            }),
+        };
+        let ports = vec![
+            usb("/dev/ttyUSB0", "A1", "FTDI"),
+            usb("/dev/ttyUSB1", "B2", "Silicon Labs"),
+            usb("/dev/ttyUSB2", "C3", "FTDI"),
+        ];
+        let state: AppState = Arc::new(std::sync::Mutex::new(crate::state::PortState::Closed));
+        let mut handler = test_handler(state.clone(), PortService::new(state)).await;
+        handler.port_cache = Arc::new(crate::port::PortListCache::with_enumerator(
+            std::time::Duration::from_secs(1),
+            Arc::new(move || Ok(ports.clone())),
+        ));
+        let find =
+            |args: serde_json::Value| handler.find_port_impl(serde_json::from_value(args).unwrap());
+
+        let found = find(json!({"serial": "B2"}))
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(found["port_name"], "/dev/ttyUSB1");
+
+        let found = find(json!({"vid": "0x0403", "manufacturer_contains": "ftdi", "all": true}))
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(found["port_name"], "/dev/ttyUSB0");
+        assert_eq!(found["matches"], json!(["/dev/ttyUSB0", "/dev/ttyUSB2"]));
+
+        let err = find(json!({"serial": "Z9"})).unwrap_err();
+        assert!(err.to_string().contains("no connected USB port matches"));
+        assert!(find(json!({})).is_err());
+        assert!(find(json!({"vid": "xyz"})).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_write_encodings_send_decoded_bytes() {
+        let state: AppState = Arc::new(std::sync::Mutex::new(crate::state::PortState::Closed));
+        let mock = crate::port::MockSerialPort::new("MOCK0");
+        let opened = mock.clone();
+        let opener: crate::service::PortOpener = Arc::new(move |_name, _config| {
+            Ok(Box::new(opened.clone()) as crate::state::PortAdapter)
+        });
+        let service = PortService::new(state.clone()).with_port_opener(opener);
+        service
+            .open(
+                serde_json::from_value(json!({"port_name": "MOCK0", "terminator": "\r\n"}))
+                    .unwrap(),
+            )
+            .unwrap();
+        let handler = test_handler(state, service).await;
+        let write = |args: serde_json::Value| {
+            mock.clone().clear_write_log();
+            let (handler, mock) = (&handler, mock.clone());
+            async move {
+                handler
+                    .write_impl(serde_json::from_value(args).unwrap())
+                    .await
+                    .map(|_| mock.get_write_log().concat())
+            }
+        };
+
+        // Text that looks like base64 is still sent as text
+        assert_eq!(write(json!({"data": "QUJD"})).await.unwrap(), b"QUJD\r\n");
+        assert_eq!(
+            write(json!({"data": "QUJD", "encoding": "base64"}))
+                .await
+                .unwrap(),
+            b"ABC\r\n"
+        );
+        assert_eq!(
+            write(json!({"data": "00 ff 10", "encoding": "hex"}))
+                .await
+                .unwrap(),
+            vec![0x00, 0xff, 0x10, b'\r', b'\n']
+        );
+        let err = write(json!({"data": "0g", "encoding": "hex"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid hex byte '0g'"));
+        assert!(
+            serde_json::from_value::<WriteTool>(json!({"data": "AT", "encoding": "utf16"}))
+                .is_err()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_subscribe_reads_notifies_incoming_data() {
+        let state: AppState = Arc::new(std::sync::Mutex::new(crate::state::PortState::Closed));
+        let mock = crate::port::MockSerialPort::new("MOCK0");
+        let opened = mock.clone();
+        let opener: crate::service::PortOpener = Arc::new(move |_name, _config| {
+            Ok(Box::new(opened.clone()) as crate::state::PortAdapter)
+        });
+        let service = PortService::new(state.clone()).with_port_opener(opener);
+        service
+            .open(serde_json::from_value(json!({"port_name": "MOCK0"})).unwrap())
+            .unwrap();
+        let handler = test_handler(state, service).await;
+
+        let (notify, mut notifications) = mpsc::unbounded_channel();
+        let subscribed = handler
+            .subscribe_reads_impl(
+                SubscribeReadsTool {
+                    interval_ms: Some(10),
+                    encoding: None,
+                },
+                notify,
+            )
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(subscribed["replaced"], json!(false));
+
+        mock.clone().enqueue_read(b"ALARM 3");
+        let notification = tokio::time::timeout(Duration::from_secs(2), notifications.recv())
+            .await
+            .expect("no notification")
+            .unwrap();
+        assert_eq!(notification.logger.as_deref(), Some("serial"));
+        assert_eq!(notification.data["data"], "ALARM 3");
+        assert_eq!(notification.data["bytes_read"], 7);
+
+        let stopped = handler
+            .unsubscribe_reads_impl()
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(stopped["was_subscribed"], json!(true));
+        // The aborted stream drops its sender
+        assert!(
+            tokio::time::timeout(Duration::from_secs(2), notifications.recv())
+                .await
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_reset_subsystem_rearms_read_stream() {
+        let state: AppState = Arc::new(std::sync::Mutex::new(crate::state::PortState::Closed));
+        let mock = crate::port::MockSerialPort::new("MOCK0");
+        let opened = mock.clone();
+        let opener: crate::service::PortOpener = Arc::new(move |_name, _config| {
+            Ok(Box::new(opened.clone()) as crate::state::PortAdapter)
+        });
+        let service = PortService::new(state.clone()).with_port_opener(opener);
+        let open = || serde_json::from_value(json!({"port_name": "MOCK0"})).unwrap();
+        service.open(open()).unwrap();
+        let handler = test_handler(state, service).await;
+
+        let (notify, mut notifications) = mpsc::unbounded_channel();
+        handler
+            .subscribe_reads_impl(
+                SubscribeReadsTool {
+                    interval_ms: Some(10),
+                    encoding: None,
+                },
+                notify,
+            )
+            .unwrap();
+
+        let reset = handler
+            .reset_subsystem_impl()
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(reset["port_closed"], json!(true));
+        assert_eq!(reset["read_stream_restarted"], json!(true));
+        assert!(!handler.service.is_open());
+
+        // The fresh reader streams from the next port opened
+        handler.service.open(open()).unwrap();
+        mock.clone().enqueue_read(b"BACK");
+        let notification = tokio::time::timeout(Duration::from_secs(2), notifications.recv())
+            .await
+            .expect("no notification after reset")
+            .unwrap();
+        assert_eq!(notification.data["data"], "BACK");
+
+        handler.unsubscribe_reads_impl().unwrap();
+        let reset = handler
+            .reset_subsystem_impl()
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(reset["read_stream_restarted"], json!(false));
+        assert!(handler.read_stream.lock().unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replay_session_resends_sent_messages() {
+        let state: AppState = Arc::new(std::sync::Mutex::new(crate::state::PortState::Closed));
+        let mock = crate::port::MockSerialPort::new("MOCK0");
+        let opened = mock.clone();
+        let opener: crate::service::PortOpener = Arc::new(move |_name, _config| {
+            Ok(Box::new(opened.clone()) as crate::state::PortAdapter)
+        });
+        let service = PortService::new(state.clone()).with_port_opener(opener);
+        service
+            .open(
+                serde_json::from_value(json!({"port_name": "MOCK0", "terminator": "\n"})).unwrap(),
+            )
+            .unwrap();
+        let handler = test_handler(state, service).await;
+
+        let recorded = handler
+            .sessions
+            .create_session("modem", Some("MOCK0"))
+            .await
+            .unwrap();
+        for (direction, content) in [("sent", "AT"), ("received", "OK"), ("sent", "AT+GMR")] {
+            handler
+                .sessions
+                .append_message(&recorded.id, "user", Some(direction), content, None, None)
+                .await
+                .unwrap();
+        }
+
+        let replayed = handler
+            .replay_session_impl(ReplaySessionTool {
+                session_id: recorded.id.clone(),
+                inter_message_delay_ms: Some(5),
+                preserve_timing: false,
+                capture_responses: false,
+                response_silence_ms: None,
+            })
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(replayed["messages_replayed"], 2);
+        assert!(replayed.get("capture_session_id").is_none());
+        assert_eq!(mock.get_write_log().concat(), b"AT\nAT+GMR\n");
+
+        // Replies are logged alongside the commands in a new session
+        mock.clone().clear_write_log();
+        mock.clone().enqueue_read(b"OK");
+        let replayed = handler
+            .replay_session_impl(ReplaySessionTool {
+                session_id: recorded.id.clone(),
+                inter_message_delay_ms: None,
+                preserve_timing: false,
+                capture_responses: true,
+                response_silence_ms: Some(20),
+            })
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(mock.get_write_log().concat(), b"AT\nAT+GMR\n");
+        assert_eq!(replayed["responses"], json!(["OK", ""]));
+        let captured = handler
+            .sessions
+            .list_messages(replayed["capture_session_id"].as_str().unwrap(), 10)
+            .await
+            .unwrap();
+        let timeline: Vec<_> = captured
+            .iter()
+            .map(|m| (m.direction.as_deref().unwrap(), m.content.as_str()))
+            .collect();
+        assert_eq!(
+            timeline,
+            [("sent", "AT"), ("received", "OK"), ("sent", "AT+GMR")]
+        );
+    }
+
+    #[test]
+    fn test_heartbeat_follows_config_toggle() {
+        let mut config = crate::config::McpConfig::default();
+        let mut out = Vec::new();
+        assert!(!write_startup_heartbeat(&mut out, &config).unwrap());
+        assert!(out.is_empty());
+
+        config.heartbeat = true;
+        assert!(write_startup_heartbeat(&mut out, &config).unwrap());
+        let line: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(line["method"], "notifications/ping");
+
+        config.heartbeat_method = crate::config::HeartbeatMethod::Legacy;
+        out.clear();
+        write_startup_heartbeat(&mut out, &config).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "{\"jsonrpc\":\"2.0\",\"method\":\"_heartbeat\",\"params\":{}}\n"
+        );
+    }
+
+    #[test]
+    fn test_tool_groups_match_compiled_features() {
+        let listed: Vec<String> = tool_list().into_iter().map(|t| t.name).collect();
+        let groups = tool_groups();
+        for group in groups.as_array().unwrap() {
+            let enabled = group["enabled"].as_bool().unwrap();
+            for tool in group["tools"].as_array().unwrap() {
+                let tool = tool.as_str().unwrap();
+                assert_eq!(listed.iter().any(|n| n == tool), enabled, "{tool}");
+            }
+            assert_eq!(group["reason"].is_null(), enabled);
+        }
+
+        let group = |feature: &str| {
+            groups
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|g| g["feature"] == feature)
+                .cloned()
+                .unwrap()
+        };
+        #[cfg(feature = "auto-negotiation")]
+        assert_eq!(group("auto-negotiation")["enabled"], json!(true));
+        #[cfg(not(feature = "auto-negotiation"))]
+        assert!(group("auto-negotiation")["reason"]
+            .as_str()
+            .unwrap()
+            .contains("--features auto-negotiation"));
+        #[cfg(feature = "tui")]
+        assert_eq!(group("tui")["enabled"], json!(true));
+        #[cfg(not(feature = "tui"))]
+        assert_eq!(group("tui")["enabled"], json!(false));
+    }
+}
@@ -7,6 +7,8 @@ use super::strategies::{
     EchoProbeStrategy, ManufacturerStrategy, NegotiatedParams, NegotiationError, NegotiationHints,
     NegotiationStrategy, StandardBaudsStrategy,
 };
+use std::collections::HashMap;
+use std::sync::Mutex;
 use tracing::{debug, info, warn};
 
 /// Main auto-negotiation orchestrator.
@@ -15,6 +17,37 @@ use tracing::{debug, info, warn};
 /// them in priority order to find the correct port parameters.
 pub struct AutoNegotiator {
     strategies: Vec<Box<dyn NegotiationStrategy>>,
+    /// Last successful detection per port and what it was asked for, used by
+    /// [`detect_cached`](Self::detect_cached)
+    cache: Mutex<HashMap<String, (DetectionKey, NegotiatedParams)>>,
+}
+
+/// The request a cached detection answered. A cached result is only reused
+/// for the same device hints and preferred strategy; timeouts and settle
+/// delays are left out, as they change how long probing waits rather than
+/// what it looks for.
+#[derive(Debug, Clone, PartialEq)]
+struct DetectionKey {
+    vid: Option<u16>,
+    pid: Option<u16>,
+    manufacturer: Option<String>,
+    suggested_baud_rates: Vec<u32>,
+    restrict_to_suggested: bool,
+    preferred_strategy: Option<String>,
+}
+
+impl DetectionKey {
+    fn new(hints: Option<&NegotiationHints>, preferred_strategy: Option<&str>) -> Self {
+        let hints = hints.cloned().unwrap_or_default();
+        Self {
+            vid: hints.vid,
+            pid: hints.pid,
+            manufacturer: hints.manufacturer,
+            suggested_baud_rates: hints.suggested_baud_rates,
+            restrict_to_suggested: hints.restrict_to_suggested,
+            preferred_strategy: preferred_strategy.map(str::to_string),
+        }
+    }
 }
 
 impl AutoNegotiator {
@@ -34,14 +67,17 @@ impl AutoNegotiator {
         // Sort by priority (highest first)
         strategies.sort_by_key(|s| std::cmp::Reverse(s.priority()));
 
-        Self { strategies }
+        Self::with_strategies(strategies)
     }
 
     /// Create a negotiator with custom strategies.
     pub fn with_strategies(strategies: Vec<Box<dyn NegotiationStrategy>>) -> Self {
         let mut strategies = strategies;
         strategies.sort_by_key(|s| std::cmp::Reverse(s.priority()));
-        Self { strategies }
+        Self {
+            strategies,
+            cache: Mutex::default(),
+        }
     }
 
     /// Add a strategy to the negotiator.
@@ -154,6 +190,70 @@ impl AutoNegotiator {
         self.detect(port_name, Some(hints)).await
     }
 
+    /// Detect parameters, reusing the last successful detection of the port.
+    ///
+    /// With `use_cache` set, a result cached for the same hints and preferred
+    /// strategy is returned without touching the port, and a fresh success is
+    /// remembered. `force` probes again even when
+    /// a result is cached and replaces it. With neither set the cache is left
+    /// alone. A failed detection that would have been stored forgets the port.
+    ///
+    /// Returns the parameters and whether they came from the cache.
+    pub async fn detect_cached(
+        &self,
+        port_name: &str,
+        hints: Option<NegotiationHints>,
+        preferred_strategy: Option<&str>,
+        use_cache: bool,
+        force: bool,
+    ) -> Result<(NegotiatedParams, bool), NegotiationError> {
+        let key = DetectionKey::new(hints.as_ref(), preferred_strategy);
+        if use_cache && !force {
+            let cache = self.cache.lock().unwrap();
+            if let Some((_, params)) = cache.get(port_name).filter(|(cached, _)| *cached == key) {
+                debug!("Using cached negotiation result for {}", port_name);
+                return Ok((params.clone(), true));
+            }
+        }
+
+        let result = match preferred_strategy {
+            Some(strategy) => {
+                self.detect_with_preference(port_name, hints, strategy)
+                    .await
+            }
+            None => self.detect(port_name, hints).await,
+        };
+        if use_cache || force {
+            let mut cache = self.cache.lock().unwrap();
+            match &result {
+                Ok(params) => {
+                    cache.insert(port_name.to_string(), (key, params.clone()));
+                }
+                Err(_) => {
+                    cache.remove(port_name);
+                }
+            }
+        }
+        result.map(|params| (params, false))
+    }
+
+    /// The cached result of the last successful detection of `port_name`.
+    pub fn cached(&self, port_name: &str) -> Option<NegotiatedParams> {
+        self.cache
+            .lock()
+            .unwrap()
+            .get(port_name)
+            .map(|(_, params)| params.clone())
+    }
+
+    /// Forget every cached detection; returns how many ports had one.
+    pub fn clear_cache(&self) -> usize {
+        let mut cache = self.cache.lock().unwrap();
+        let cleared = cache.len();
+        cache.clear();
+        cleared
+    }
+
     /// Get a manufacturer profile by VID.
     ///
     /// This is a convenience method for accessing the manufacturer database.
@@ -190,6 +290,30 @@ impl AutoNegotiator {
 
         join_all(futures).await
     }
+
+    /// Detect parameters for many ports with at most `concurrency` probes in
+    /// flight at once.
+    ///
+    /// Unlike [`detect_multiple`](Self::detect_multiple), which probes every
+    /// port at the same time, this bounds the load on the host and the USB
+    /// bus when sweeping all enumerated ports. Results are returned in input
+    /// order. A `concurrency` of 0 is treated as 1.
+    #[cfg(feature = "async-serial")]
+    pub async fn detect_all(
+        &self,
+        ports: Vec<(String, Option<NegotiationHints>)>,
+        concurrency: usize,
+    ) -> Vec<(String, Result<NegotiatedParams, NegotiationError>)> {
+        use futures::stream::{self, StreamExt};
+
+        stream::iter(ports.into_iter().map(|(port_name, hints)| async move {
+            let result = self.detect(&port_name, hints).await;
+            (port_name, result)
+        }))
+        .buffered(concurrency.max(1))
+        .collect()
+        .await
+    }
 }
 
 impl Default for AutoNegotiator {
@@ -257,4 +381,90 @@ mod tests {
         assert_eq!(negotiator.strategies().len(), 1);
         assert_eq!(negotiator.strategies()[0].name(), "standard_bauds");
     }
+
+    #[tokio::test]
+    async fn test_detect_cached_reuses_and_refreshes_results() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+        use std::sync::Arc;
+
+        /// Reports a different baud rate on every run.
+        struct CountingStrategy(Arc<AtomicU32>);
+
+        #[async_trait::async_trait]
+        impl NegotiationStrategy for CountingStrategy {
+            fn name(&self) -> &'static str {
+                "counting"
+            }
+
+            async fn negotiate(
+                &self,
+                _port_name: &str,
+                _hints: &NegotiationHints,
+            ) -> Result<NegotiatedParams, NegotiationError> {
+                let run = self.0.fetch_add(1, Ordering::SeqCst) + 1;
+                Ok(NegotiatedParams::new(9600 * run, "counting"))
+            }
+        }
+
+        let runs = Arc::new(AtomicU32::new(0));
+        let negotiator =
+            AutoNegotiator::with_strategies(vec![Box::new(CountingStrategy(runs.clone()))]);
+
+        let (params, from_cache) = negotiator
+            .detect_cached("MOCK0", None, None, true, false)
+            .await
+            .unwrap();
+        assert_eq!((params.baud_rate, from_cache), (9600, false));
+
+        let (params, from_cache) = negotiator
+            .detect_cached("MOCK0", None, None, true, false)
+            .await
+            .unwrap();
+        assert_eq!((params.baud_rate, from_cache), (9600, true));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+        // Forcing probes again and replaces the cached result
+        let (params, from_cache) = negotiator
+            .detect_cached("MOCK0", None, None, true, true)
+            .await
+            .unwrap();
+        assert_eq!((params.baud_rate, from_cache), (19200, false));
+        assert_eq!(negotiator.cached("MOCK0").unwrap().baud_rate, 19200);
+
+        // Bypassing the cache neither reads nor updates it
+        let (params, from_cache) = negotiator
+            .detect_cached("MOCK0", None, None, false, false)
+            .await
+            .unwrap();
+        assert_eq!((params.baud_rate, from_cache), (28800, false));
+        assert_eq!(negotiator.cached("MOCK0").unwrap().baud_rate, 19200);
+        assert!(negotiator.cached("MOCK1").is_none());
+
+        // A result is only reused for the hints and strategy it was probed with
+        let (params, from_cache) = negotiator
+            .detect_cached(
+                "MOCK0",
+                Some(NegotiationHints::with_vid(0x0403)),
+                None,
+                true,
+                false,
+            )
+            .await
+            .unwrap();
+        assert_eq!((params.baud_rate, from_cache), (38400, false));
+        let (params, from_cache) = negotiator
+            .detect_cached("MOCK0", None, Some("counting"), true, false)
+            .await
+            .unwrap();
+        assert_eq!((params.baud_rate, from_cache), (48000, false));
+        let timing_only = NegotiationHints {
+            timeout_ms: 2000,
+            ..Default::default()
+        };
+        let (params, from_cache) = negotiator
+            .detect_cached("MOCK0", Some(timing_only), Some("counting"), true, false)
+            .await
+            .unwrap();
+        assert_eq!((params.baud_rate, from_cache), (48000, true));
+    }
 }
@@ -5,6 +5,8 @@
 
 use super::{NegotiatedParams, NegotiationError, NegotiationHints, NegotiationStrategy};
 use crate::port::{DataBits, FlowControl, Parity, PortConfiguration, StopBits};
+#[cfg(feature = "async-serial")]
+use crate::state::TerminatorDetector;
 use async_trait::async_trait;
 use std::time::Duration;
 use tracing::{debug, warn};
@@ -145,7 +147,8 @@ impl EchoProbeStrategy {
         baud_rate: u32,
         probe: &ProbeSequence,
         timeout: Duration,
-    ) -> Result<Option<f32>, NegotiationError> {
+        settle: Duration,
+    ) -> Result<Option<(f32, Option<&'static str>)>, NegotiationError> {
         debug!(
             "Trying {} probe at {} baud on {}",
             probe.description, baud_rate, port_name
@@ -168,10 +171,31 @@ impl EchoProbeStrategy {
             }
         };
 
+        Ok(Self::probe_port(&mut port, baud_rate, probe, timeout, settle).await)
+    }
+
+    /// Send `probe` on an open port and rate the response, along with the
+    /// line ending the response used, if any.
+    ///
+    /// Waits `settle` first so devices that reset when the port opens are
+    /// ready to answer.
+    #[cfg(feature = "async-serial")]
+    async fn probe_port<P: AsyncSerialPortAdapter>(
+        port: &mut P,
+        baud_rate: u32,
+        probe: &ProbeSequence,
+        timeout: Duration,
+        settle: Duration,
+    ) -> Option<(f32, Option<&'static str>)> {
+        if !settle.is_zero() {
+            debug!("Waiting {:?} for device to settle", settle);
+            tokio::time::sleep(settle).await;
+        }
+
         // Send the probe command
         if let Err(e) = port.write_bytes(&probe.command).await {
             warn!("Failed to send probe at {} baud: {}", baud_rate, e);
-            return Ok(None);
+            return None;
         }
 
         // Brief delay to let device process
@@ -187,32 +211,35 @@ impl EchoProbeStrategy {
                     n,
                     String::from_utf8_lossy(response)
                 );
+                let mut detector = TerminatorDetector::default();
+                detector.observe(response);
+                let terminator = detector.detected();
 
                 if probe.matches(response) {
                     debug!(
                         "Probe '{}' matched at {} baud!",
                         probe.description, baud_rate
                     );
-                    Ok(Some(0.95)) // Very high confidence - expected response
+                    Some((0.95, terminator)) // Very high confidence - expected response
                 } else {
                     debug!(
                         "Got response but no match for probe '{}'",
                         probe.description
                     );
-                    Ok(Some(0.4)) // Some confidence - got response but wrong pattern
+                    Some((0.4, terminator)) // Some confidence - got response but wrong pattern
                 }
             }
             Ok(Ok(_)) => {
                 debug!("No response to probe at {} baud", baud_rate);
-                Ok(None)
+                None
             }
             Ok(Err(e)) => {
                 warn!("Read error at {} baud: {}", baud_rate, e);
-                Ok(None)
+                None
             }
             Err(_) => {
                 debug!("Timeout waiting for response at {} baud", baud_rate);
-                Ok(None)
+                None
             }
         }
     }
@@ -241,6 +268,7 @@ impl NegotiationStrategy for EchoProbeStrategy {
         hints: &NegotiationHints,
     ) -> Result<NegotiatedParams, NegotiationError> {
         let timeout = hints.timeout();
+        let settle = hints.settle_delay();
 
         // Use suggested baud rates if provided, otherwise use defaults
         let baud_rates = if !hints.suggested_baud_rates.is_empty() {
@@ -255,13 +283,13 @@ impl NegotiationStrategy for EchoProbeStrategy {
             self.probe_sequences.len()
         );
 
-        let mut best_result: Option<(u32, f32, String)> = None;
+        let mut best_result: Option<(u32, f32, Option<&'static str>, String)> = None;
 
         // Try each baud rate with each probe
         for &baud_rate in baud_rates {
             for probe in &self.probe_sequences {
-                match Self::try_probe_at_baud(port_name, baud_rate, probe, timeout).await? {
-                    Some(confidence) => {
+                match Self::try_probe_at_baud(port_name, baud_rate, probe, timeout, settle).await? {
+                    Some((confidence, terminator)) => {
                         debug!(
                             "Baud {} with probe '{}' has confidence {}",
                             baud_rate, probe.description, confidence
@@ -269,14 +297,19 @@ impl NegotiationStrategy for EchoProbeStrategy {
 
                         // Update best result if this is better
                         if best_result.is_none() || confidence > best_result.as_ref().unwrap().1 {
-                            best_result =
-                                Some((baud_rate, confidence, probe.description.to_string()));
+                            best_result = Some((
+                                baud_rate,
+                                confidence,
+                                terminator,
+                                probe.description.to_string(),
+                            ));
                         }
 
                         // If we have very high confidence, we can stop
                         if confidence >= 0.9 {
                             return Ok(NegotiatedParams::new(baud_rate, self.name())
-                                .with_confidence(confidence));
+                                .with_confidence(confidence)
+                                .with_terminator(terminator));
                         }
                     }
                     None => continue,
@@ -284,12 +317,14 @@ impl NegotiationStrategy for EchoProbeStrategy {
             }
         }
 
-        if let Some((baud_rate, confidence, probe_desc)) = best_result {
+        if let Some((baud_rate, confidence, terminator, probe_desc)) = best_result {
             debug!(
                 "Best result: {} baud (probe: {}, confidence: {})",
                 baud_rate, probe_desc, confidence
             );
-            Ok(NegotiatedParams::new(baud_rate, self.name()).with_confidence(confidence))
+            Ok(NegotiatedParams::new(baud_rate, self.name())
+                .with_confidence(confidence)
+                .with_terminator(terminator))
         } else {
             Err(NegotiationError::StrategyError {
                 strategy: self.name().to_string(),
@@ -315,6 +350,74 @@ impl NegotiationStrategy for EchoProbeStrategy {
 mod tests {
     use super::*;
 
+    /// Device that ignores probes sent before it has finished booting.
+    #[cfg(feature = "async-serial")]
+    struct SlowBootDevice {
+        config: PortConfiguration,
+        ready_at: std::time::Instant,
+        reply: Vec<u8>,
+    }
+
+    #[cfg(feature = "async-serial")]
+    impl SlowBootDevice {
+        fn booting_for(boot: Duration) -> Self {
+            Self {
+                config: PortConfiguration::default(),
+                ready_at: std::time::Instant::now() + boot,
+                reply: Vec::new(),
+            }
+        }
+    }
+
+    #[cfg(feature = "async-serial")]
+    #[async_trait]
+    impl AsyncSerialPortAdapter for SlowBootDevice {
+        async fn write_bytes(&mut self, data: &[u8]) -> Result<usize, crate::port::PortError> {
+            if std::time::Instant::now() >= self.ready_at && data == b"AT\r\n" {
+                self.reply = b"OK\r\n".to_vec();
+            }
+            Ok(data.len())
+        }
+
+        async fn read_bytes(&mut self, buffer: &mut [u8]) -> Result<usize, crate::port::PortError> {
+            let n = self.reply.len().min(buffer.len());
+            buffer[..n].copy_from_slice(&self.reply[..n]);
+            self.reply.drain(..n);
+            Ok(n)
+        }
+
+        fn name(&self) -> &str {
+            "MOCK0"
+        }
+
+        fn config(&self) -> &PortConfiguration {
+            &self.config
+        }
+
+        async fn bytes_available(&mut self) -> Result<u32, crate::port::PortError> {
+            Ok(self.reply.len() as u32)
+        }
+    }
+
+    #[cfg(feature = "async-serial")]
+    #[tokio::test]
+    async fn test_settle_delay_lets_slow_device_answer() {
+        let probe = CommonProbes::at_command();
+        let timeout = Duration::from_millis(100);
+        let boot = Duration::from_millis(150);
+
+        let mut device = SlowBootDevice::booting_for(boot);
+        let confidence =
+            EchoProbeStrategy::probe_port(&mut device, 9600, &probe, timeout, Duration::ZERO).await;
+        assert_eq!(confidence, None);
+
+        let mut device = SlowBootDevice::booting_for(boot);
+        let confidence =
+            EchoProbeStrategy::probe_port(&mut device, 9600, &probe, timeout, boot).await;
+        // The reply's line ending is learned along with the baud rate
+        assert_eq!(confidence, Some((0.95, Some("\r\n"))));
+    }
+
     #[test]
     fn test_probe_sequence_matches() {
         let probe = CommonProbes::at_command();
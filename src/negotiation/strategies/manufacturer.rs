@@ -26,6 +26,10 @@ pub struct ManufacturerProfile {
 
     /// Common baud rates to try (ordered by likelihood).
     pub common_bauds: &'static [u32],
+
+    /// Time the device needs after the port opens before it answers
+    /// (milliseconds), e.g. for boards that reset on DTR.
+    pub settle_delay_ms: u64,
 }
 
 /// Database of known manufacturer profiles.
@@ -37,48 +41,56 @@ pub const MANUFACTURER_PROFILES: &[ManufacturerProfile] = &[
         name: "FTDI",
         default_baud: 115200,
         common_bauds: &[9600, 19200, 38400, 57600, 115200, 230400, 460800, 921600],
+        settle_delay_ms: 0,
     },
     ManufacturerProfile {
         vid: 0x10C4,
         name: "Silicon Labs CP210x",
         default_baud: 9600,
         common_bauds: &[9600, 19200, 38400, 57600, 115200],
+        settle_delay_ms: 0,
     },
     ManufacturerProfile {
         vid: 0x1A86,
         name: "WCH CH340/CH341",
         default_baud: 9600,
         common_bauds: &[9600, 19200, 57600, 115200],
+        settle_delay_ms: 0,
     },
     ManufacturerProfile {
         vid: 0x2341,
         name: "Arduino",
         default_baud: 9600,
         common_bauds: &[9600, 57600, 115200],
+        settle_delay_ms: 2000,
     },
     ManufacturerProfile {
         vid: 0x239A,
         name: "Adafruit",
         default_baud: 115200,
         common_bauds: &[9600, 115200],
+        settle_delay_ms: 0,
     },
     ManufacturerProfile {
         vid: 0x2E8A,
         name: "Raspberry Pi Pico",
         default_baud: 115200,
         common_bauds: &[9600, 115200],
+        settle_delay_ms: 0,
     },
     ManufacturerProfile {
         vid: 0x067B,
         name: "Prolific PL2303",
         default_baud: 9600,
         common_bauds: &[9600, 19200, 38400, 57600, 115200],
+        settle_delay_ms: 0,
     },
     ManufacturerProfile {
         vid: 0x0483,
         name: "STMicroelectronics",
         default_baud: 115200,
         common_bauds: &[9600, 38400, 115200],
+        settle_delay_ms: 0,
     },
 ];
 
@@ -106,6 +118,7 @@ impl ManufacturerStrategy {
         port_name: &str,
         baud_rate: u32,
         timeout: Duration,
+        settle: Duration,
     ) -> Result<bool, NegotiationError> {
         debug!("Trying baud rate {} on {}", baud_rate, port_name);
 
@@ -125,6 +138,10 @@ impl ManufacturerStrategy {
                 // For manufacturer strategy, we trust the profile
                 debug!("Successfully opened port at {} baud", baud_rate);
 
+                if !settle.is_zero() {
+                    tokio::time::sleep(settle).await;
+                }
+
                 // Try a simple write/flush to verify the port works
                 if let Err(e) = port.write_bytes(b"\r\n").await {
                     warn!("Port opened but write failed: {}", e);
@@ -179,9 +196,10 @@ impl NegotiationStrategy for ManufacturerStrategy {
         );
 
         let timeout = hints.timeout();
+        let settle = hints.settle_delay();
 
         // Try the default baud rate first
-        if Self::try_baud_rate(port_name, profile.default_baud, timeout).await? {
+        if Self::try_baud_rate(port_name, profile.default_baud, timeout, settle).await? {
             return Ok(
                 NegotiatedParams::new(profile.default_baud, self.name()).with_confidence(0.9)
             ); // High confidence for manufacturer default
@@ -193,7 +211,7 @@ impl NegotiationStrategy for ManufacturerStrategy {
                 continue; // Already tried
             }
 
-            if Self::try_baud_rate(port_name, baud_rate, timeout).await? {
+            if Self::try_baud_rate(port_name, baud_rate, timeout, settle).await? {
                 return Ok(NegotiatedParams::new(baud_rate, self.name()).with_confidence(0.7));
                 // Good confidence for manufacturer profile
             }
@@ -69,6 +69,11 @@ pub struct NegotiationHints {
 
     /// Whether to try only suggested baud rates (skip standard set).
     pub restrict_to_suggested: bool,
+
+    /// Time to let the device settle after opening each candidate baud,
+    /// before probing (milliseconds, 0 = use the manufacturer profile's).
+    #[serde(default)]
+    pub settle_delay_ms: u64,
 }
 
 impl NegotiationHints {
@@ -103,6 +108,27 @@ impl NegotiationHints {
         self
     }
 
+    /// Set the post-open settle delay for slow-booting devices.
+    pub fn with_settle_delay_ms(mut self, settle_delay_ms: u64) -> Self {
+        self.settle_delay_ms = settle_delay_ms;
+        self
+    }
+
+    /// Get the settle delay as a Duration.
+    ///
+    /// Falls back to the manufacturer profile for `vid` when no delay is set,
+    /// so boards that reset on open are waited for by every strategy.
+    pub fn settle_delay(&self) -> Duration {
+        let ms = match self.settle_delay_ms {
+            0 => self
+                .vid
+                .and_then(ManufacturerStrategy::get_profile)
+                .map_or(0, |profile| profile.settle_delay_ms),
+            ms => ms,
+        };
+        Duration::from_millis(ms)
+    }
+
     /// Get the timeout as a Duration.
     pub fn timeout(&self) -> Duration {
         if self.timeout_ms > 0 {
@@ -139,6 +165,11 @@ pub struct NegotiatedParams {
     /// 0.5 = medium confidence (e.g., got some response)
     /// 0.1 = low confidence (e.g., no errors but no confirmation)
     pub confidence: f32,
+
+    /// Line ending the device used in its replies, if one was observed.
+    /// Applied by open-auto when the caller gives no terminator.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub terminator: Option<String>,
 }
 
 impl NegotiatedParams {
@@ -152,6 +183,7 @@ impl NegotiatedParams {
             flow_control: FlowControl::None,
             strategy_used: strategy_used.into(),
             confidence: 1.0,
+            terminator: None,
         }
     }
 
@@ -165,6 +197,7 @@ impl NegotiatedParams {
             flow_control: FlowControl::None,
             strategy_used: strategy_used.into(),
             confidence: 0.5,
+            terminator: None,
         }
     }
 
@@ -174,6 +207,12 @@ impl NegotiatedParams {
         self
     }
 
+    /// Set the line ending observed in the device's replies.
+    pub fn with_terminator(mut self, terminator: Option<&str>) -> Self {
+        self.terminator = terminator.map(str::to_string);
+        self
+    }
+
     /// Set custom serial parameters.
     pub fn with_params(
         mut self,
@@ -233,6 +272,20 @@ mod tests {
         assert!(hints.pid.is_none());
         assert!(hints.suggested_baud_rates.is_empty());
         assert_eq!(hints.timeout(), Duration::from_millis(500));
+        assert_eq!(hints.settle_delay(), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_settle_delay_falls_back_to_profile() {
+        // Arduino boards reset when the port opens
+        let hints = NegotiationHints::with_vid(0x2341);
+        assert_eq!(hints.settle_delay(), Duration::from_millis(2000));
+        let hints = hints.with_settle_delay_ms(300);
+        assert_eq!(hints.settle_delay(), Duration::from_millis(300));
+        assert_eq!(
+            NegotiationHints::with_vid(0x0403).settle_delay(),
+            Duration::ZERO
+        );
     }
 
     #[test]
@@ -107,6 +107,7 @@ impl StandardBaudsStrategy {
         port_name: &str,
         baud_rate: u32,
         timeout: Duration,
+        settle: Duration,
         verify: bool,
     ) -> Result<Option<f32>, NegotiationError> {
         debug!("Probing {} at {} baud", port_name, baud_rate);
@@ -138,6 +139,10 @@ impl StandardBaudsStrategy {
             return Ok(Some(0.3)); // Low confidence - just opened
         }
 
+        if !settle.is_zero() {
+            tokio::time::sleep(settle).await;
+        }
+
         // Try to send a newline and see if we get any response or error
         match port.write_bytes(b"\r\n").await {
             Ok(_) => {
@@ -199,13 +204,21 @@ impl NegotiationStrategy for StandardBaudsStrategy {
     ) -> Result<NegotiatedParams, NegotiationError> {
         let rates = self.get_baud_rates(hints);
         let timeout = hints.timeout();
+        let settle = hints.settle_delay();
 
         debug!("Trying {} baud rates for port {}", rates.len(), port_name);
 
         let mut best_result: Option<(u32, f32)> = None;
 
         for baud_rate in rates {
-            match Self::try_baud_rate(port_name, baud_rate, timeout, self.verify_with_probe).await?
+            match Self::try_baud_rate(
+                port_name,
+                baud_rate,
+                timeout,
+                settle,
+                self.verify_with_probe,
+            )
+            .await?
             {
                 Some(confidence) => {
                     debug!("Baud rate {} has confidence {}", baud_rate, confidence);
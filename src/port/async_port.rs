@@ -78,20 +78,24 @@ impl TokioSerialPort {
     /// ```
     pub fn open(port_name: &str, config: &PortConfiguration) -> Result<Self, PortError> {
         // Build tokio-serial configuration
-        let mut builder = tokio_serial::new(port_name, config.baud_rate);
+        let mut builder =
+            tokio_serial::new(super::sync_port::os_port_path(port_name), config.baud_rate);
 
         // Configure serial port parameters
         builder = builder
             .data_bits(convert_data_bits(config.data_bits))
             .flow_control(convert_flow_control(config.flow_control))
             .parity(convert_parity(config.parity))
-            .stop_bits(convert_stop_bits(config.stop_bits))
+            .stop_bits(convert_stop_bits(config.stop_bits)?)
             .timeout(config.timeout);
 
         // Open the port
         let inner = tokio_serial::SerialStream::open(&builder).map_err(|e| match e.kind {
             tokio_serial::ErrorKind::NoDevice => PortError::not_found(port_name),
             tokio_serial::ErrorKind::InvalidInput => PortError::config(e.to_string()),
+            tokio_serial::ErrorKind::Io(std::io::ErrorKind::PermissionDenied) => {
+                PortError::PermissionDenied(port_name.to_string())
+            }
             _ => PortError::Io(std::io::Error::other(e.to_string())),
         })?;
 
@@ -291,12 +295,10 @@ fn convert_parity(parity: super::traits::Parity) -> tokio_serial::Parity {
     }
 }
 
-fn convert_stop_bits(stop_bits: super::traits::StopBits) -> tokio_serial::StopBits {
-    use super::traits::StopBits;
-    match stop_bits {
-        StopBits::One => tokio_serial::StopBits::One,
-        StopBits::Two => tokio_serial::StopBits::Two,
-    }
+fn convert_stop_bits(
+    stop_bits: super::traits::StopBits,
+) -> Result<tokio_serial::StopBits, PortError> {
+    stop_bits.try_into()
 }
 
 #[cfg(test)]
@@ -340,13 +342,14 @@ mod tests {
     fn test_stop_bits_conversion() {
         use super::super::traits::StopBits;
         assert_eq!(
-            convert_stop_bits(StopBits::Two),
+            convert_stop_bits(StopBits::Two).unwrap(),
             tokio_serial::StopBits::Two
         );
         assert_eq!(
-            convert_stop_bits(StopBits::One),
+            convert_stop_bits(StopBits::One).unwrap(),
             tokio_serial::StopBits::One
         );
+        assert!(convert_stop_bits(StopBits::OnedotFive).is_err());
     }
 
     #[tokio::test]
@@ -0,0 +1,207 @@
+//! Decoding of raw register bytes into numbers.
+//!
+//! Turns a binary read (e.g. a Modbus register block) into an array of
+//! integers or floats, so callers do not have to unpack bytes themselves.
+
+use rust_mcp_sdk::macros::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Numeric type of each decoded value.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NumericType {
+    U8,
+    I8,
+    U16,
+    I16,
+    U32,
+    I32,
+    F32,
+}
+
+impl NumericType {
+    /// Bytes occupied by one value.
+    pub fn width(self) -> usize {
+        match self {
+            Self::U8 | Self::I8 => 1,
+            Self::U16 | Self::I16 => 2,
+            Self::U32 | Self::I32 | Self::F32 => 4,
+        }
+    }
+}
+
+/// Byte order of multi-byte values.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Endianness {
+    /// Most significant byte first (network order, Modbus registers)
+    #[default]
+    Big,
+    /// Least significant byte first
+    Little,
+}
+
+/// How to interpret a byte slice in [`decode_values`].
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub struct DecodeSpec {
+    pub value_type: NumericType,
+    #[serde(default)]
+    pub endianness: Endianness,
+}
+
+/// Decode `bytes` as consecutive values of `spec.value_type`.
+///
+/// Trailing bytes that do not fill a whole value are ignored. `f32` values
+/// that are NaN or infinite decode to `null`, which JSON cannot represent
+/// otherwise.
+///
+/// # Example
+/// ```
+/// use serial_mcp_agent::port::{decode_values, DecodeSpec, Endianness, NumericType};
+///
+/// let spec = DecodeSpec {
+///     value_type: NumericType::U16,
+///     endianness: Endianness::Big,
+/// };
+/// assert_eq!(decode_values(&[0x01, 0x02, 0xff], spec), vec![serde_json::json!(258)]);
+/// ```
+pub fn decode_values(bytes: &[u8], spec: DecodeSpec) -> Vec<serde_json::Value> {
+    bytes
+        .chunks_exact(spec.value_type.width())
+        .map(|chunk| decode_one(chunk, spec))
+        .collect()
+}
+
+fn decode_one(chunk: &[u8], spec: DecodeSpec) -> serde_json::Value {
+    let big = spec.endianness == Endianness::Big;
+    let two = || {
+        let b = [chunk[0], chunk[1]];
+        if big {
+            u16::from_be_bytes(b)
+        } else {
+            u16::from_le_bytes(b)
+        }
+    };
+    let four = || {
+        let b = [chunk[0], chunk[1], chunk[2], chunk[3]];
+        if big {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    };
+    match spec.value_type {
+        NumericType::U8 => chunk[0].into(),
+        NumericType::I8 => (chunk[0] as i8).into(),
+        NumericType::U16 => two().into(),
+        NumericType::I16 => (two() as i16).into(),
+        NumericType::U32 => four().into(),
+        NumericType::I32 => (four() as i32).into(),
+        NumericType::F32 => f64::from(f32::from_bits(four())).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn decode(bytes: &[u8], value_type: NumericType, endianness: Endianness) -> serde_json::Value {
+        serde_json::Value::Array(decode_values(
+            bytes,
+            DecodeSpec {
+                value_type,
+                endianness,
+            },
+        ))
+    }
+
+    #[test]
+    fn test_decode_bytes() {
+        let bytes = [0x00, 0x7f, 0x80, 0xff];
+        for endianness in [Endianness::Big, Endianness::Little] {
+            assert_eq!(
+                decode(&bytes, NumericType::U8, endianness),
+                json!([0, 127, 128, 255])
+            );
+            assert_eq!(
+                decode(&bytes, NumericType::I8, endianness),
+                json!([0, 127, -128, -1])
+            );
+        }
+    }
+
+    #[test]
+    fn test_decode_16_bit() {
+        let bytes = [0x12, 0x34, 0xff, 0xfe];
+        assert_eq!(
+            decode(&bytes, NumericType::U16, Endianness::Big),
+            json!([0x1234, 0xfffe])
+        );
+        assert_eq!(
+            decode(&bytes, NumericType::U16, Endianness::Little),
+            json!([0x3412, 0xfeff])
+        );
+        assert_eq!(
+            decode(&bytes, NumericType::I16, Endianness::Big),
+            json!([0x1234, -2])
+        );
+        assert_eq!(
+            decode(&bytes, NumericType::I16, Endianness::Little),
+            json!([0x3412, -257])
+        );
+    }
+
+    #[test]
+    fn test_decode_32_bit() {
+        let bytes = [0x12, 0x34, 0x56, 0x78, 0xff, 0xff, 0xff, 0xfe];
+        assert_eq!(
+            decode(&bytes, NumericType::U32, Endianness::Big),
+            json!([0x1234_5678u32, 0xffff_fffeu32])
+        );
+        assert_eq!(
+            decode(&bytes, NumericType::U32, Endianness::Little),
+            json!([0x7856_3412u32, 0xfeff_ffffu32])
+        );
+        assert_eq!(
+            decode(&bytes, NumericType::I32, Endianness::Big),
+            json!([0x1234_5678, -2])
+        );
+        assert_eq!(
+            decode(&bytes, NumericType::I32, Endianness::Little),
+            json!([0x7856_3412, -16_777_217])
+        );
+    }
+
+    #[test]
+    fn test_decode_f32() {
+        // 1.5 = 0x3fc00000, -2.0 = 0xc0000000
+        let big = [0x3f, 0xc0, 0x00, 0x00, 0xc0, 0x00, 0x00, 0x00];
+        assert_eq!(
+            decode(&big, NumericType::F32, Endianness::Big),
+            json!([1.5, -2.0])
+        );
+        let little = [0x00, 0x00, 0xc0, 0x3f, 0x00, 0x00, 0x00, 0xc0];
+        assert_eq!(
+            decode(&little, NumericType::F32, Endianness::Little),
+            json!([1.5, -2.0])
+        );
+        // NaN has no JSON representation
+        assert_eq!(
+            decode(&[0x7f, 0xc0, 0x00, 0x00], NumericType::F32, Endianness::Big),
+            json!([null])
+        );
+    }
+
+    #[test]
+    fn test_decode_ignores_partial_trailing_value() {
+        assert_eq!(
+            decode(&[0x00, 0x01, 0x02], NumericType::U16, Endianness::Big),
+            json!([1])
+        );
+        assert_eq!(
+            decode(&[0x01, 0x02], NumericType::U32, Endianness::Big),
+            json!([])
+        );
+    }
+}
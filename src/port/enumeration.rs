@@ -0,0 +1,298 @@
+//! Cached serial port enumeration.
+//!
+//! `serialport::available_ports()` can take hundreds of milliseconds on some
+//! platforms. [`PortListCache`] keeps the last enumeration around for a short
+//! TTL so rapid successive `list_ports` calls reuse it. [`PortQuery`] picks
+//! USB devices out of an enumeration by VID/PID, serial number or
+//! manufacturer.
+
+use serialport::{SerialPortInfo, SerialPortType};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Default time-to-live for a cached port enumeration.
+pub const DEFAULT_PORT_LIST_TTL: Duration = Duration::from_millis(1000);
+
+/// Function used to enumerate ports (injectable for tests).
+pub type PortEnumerator =
+    Arc<dyn Fn() -> Result<Vec<SerialPortInfo>, serialport::Error> + Send + Sync>;
+
+/// In-memory cache of the most recent port enumeration.
+pub struct PortListCache {
+    ttl: Duration,
+    enumerator: PortEnumerator,
+    entry: Mutex<Option<(Instant, Vec<SerialPortInfo>)>>,
+}
+
+impl PortListCache {
+    /// Create a cache backed by `serialport::available_ports()`.
+    pub fn new(ttl: Duration) -> Self {
+        Self::with_enumerator(ttl, Arc::new(serialport::available_ports))
+    }
+
+    /// Create a cache backed by a custom enumerator.
+    pub fn with_enumerator(ttl: Duration, enumerator: PortEnumerator) -> Self {
+        Self {
+            ttl,
+            enumerator,
+            entry: Mutex::new(None),
+        }
+    }
+
+    /// Configured time-to-live.
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
+    /// List ports, reusing the cached enumeration unless it is older than the
+    /// TTL or `fresh` is set.
+    ///
+    /// Enumeration errors are returned as-is and never cached.
+    pub fn list(&self, fresh: bool) -> Result<Vec<SerialPortInfo>, serialport::Error> {
+        let mut entry = self.entry.lock().unwrap_or_else(|e| e.into_inner());
+        if !fresh {
+            if let Some((at, ports)) = entry.as_ref() {
+                if at.elapsed() < self.ttl {
+                    return Ok(ports.clone());
+                }
+            }
+        }
+        let ports = (self.enumerator)()?;
+        *entry = Some((Instant::now(), ports.clone()));
+        Ok(ports)
+    }
+
+    /// List ports, degrading to an empty list when enumeration fails.
+    ///
+    /// Some environments (e.g. Linux containers without udev) cannot enumerate
+    /// ports at all even though explicit opens work. Instead of failing, this
+    /// returns no ports and the enumeration error as a warning.
+    pub fn list_or_warn(&self, fresh: bool) -> (Vec<SerialPortInfo>, Option<String>) {
+        match self.list(fresh) {
+            Ok(ports) => (ports, None),
+            Err(e) => {
+                tracing::warn!(error = %e, "Port enumeration failed; returning empty list");
+                (Vec::new(), Some(format!("Port enumeration failed: {}", e)))
+            }
+        }
+    }
+
+    /// Whether the device behind `port_name` is still attached.
+    ///
+    /// On Unix a device node that still exists is enough; otherwise the port
+    /// must appear in a fresh enumeration, since a cached one may predate the
+    /// unplug. `None` if that enumeration fails, so presence is unknown.
+    pub fn is_present(&self, port_name: &str) -> Option<bool> {
+        if cfg!(unix) && std::path::Path::new(port_name).exists() {
+            return Some(true);
+        }
+        match self.list(true) {
+            Ok(ports) => Some(ports.iter().any(|p| p.port_name == port_name)),
+            Err(e) => {
+                tracing::warn!(error = %e, port = port_name, "Port enumeration failed; presence unknown");
+                None
+            }
+        }
+    }
+
+    /// Drop the cached enumeration.
+    pub fn invalidate(&self) {
+        *self.entry.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+}
+
+/// USB identity to look for among enumerated ports; unset fields match
+/// anything.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PortQuery {
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    /// Exact USB serial number
+    pub serial: Option<String>,
+    /// Case-insensitive substring of the manufacturer string
+    pub manufacturer_contains: Option<String>,
+}
+
+impl PortQuery {
+    /// Whether no field is set, so every USB port would match.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// Whether `port` is a USB device matching every set field.
+    pub fn matches(&self, port: &SerialPortInfo) -> bool {
+        let SerialPortType::UsbPort(info) = &port.port_type else {
+            return false;
+        };
+        self.vid.map_or(true, |vid| info.vid == vid)
+            && self.pid.map_or(true, |pid| info.pid == pid)
+            && self
+                .serial
+                .as_ref()
+                .map_or(true, |sn| info.serial_number.as_ref() == Some(sn))
+            && self.manufacturer_contains.as_ref().map_or(true, |needle| {
+                info.manufacturer
+                    .as_ref()
+                    .is_some_and(|m| m.to_lowercase().contains(&needle.to_lowercase()))
+            })
+    }
+
+    /// Names of the ports in `ports` that match, in enumeration order.
+    pub fn find(&self, ports: &[SerialPortInfo]) -> Vec<String> {
+        ports
+            .iter()
+            .filter(|p| self.matches(p))
+            .map(|p| p.port_name.clone())
+            .collect()
+    }
+}
+
+impl Default for PortListCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_PORT_LIST_TTL)
+    }
+}
+
+impl std::fmt::Debug for PortListCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PortListCache")
+            .field("ttl", &self.ttl)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn counting_cache(ttl: Duration) -> (PortListCache, Arc<AtomicUsize>) {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let cache = PortListCache::with_enumerator(
+            ttl,
+            Arc::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(vec![SerialPortInfo {
+                    port_name: "MOCK0".to_string(),
+                    port_type: SerialPortType::Unknown,
+                }])
+            }),
+        );
+        (cache, calls)
+    }
+
+    #[test]
+    fn test_cached_calls_reuse_enumeration() {
+        let (cache, calls) = counting_cache(Duration::from_secs(60));
+        for _ in 0..5 {
+            let ports = cache.list(false).unwrap();
+            assert_eq!(ports[0].port_name, "MOCK0");
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_fresh_bypasses_cache() {
+        let (cache, calls) = counting_cache(Duration::from_secs(60));
+        cache.list(false).unwrap();
+        cache.list(true).unwrap();
+        cache.list(false).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_is_present_ignores_cached_list() {
+        let (cache, calls) = counting_cache(Duration::from_secs(60));
+        cache.list(false).unwrap();
+        assert_eq!(cache.is_present("MOCK0"), Some(true));
+        assert_eq!(cache.is_present("MOCK1"), Some(false));
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_expired_entry_re_enumerates() {
+        let (cache, calls) = counting_cache(Duration::ZERO);
+        cache.list(false).unwrap();
+        cache.list(false).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_list_or_warn_degrades_on_error() {
+        let cache = PortListCache::with_enumerator(
+            Duration::from_secs(60),
+            Arc::new(|| {
+                Err(serialport::Error::new(
+                    serialport::ErrorKind::Unknown,
+                    "udev unavailable",
+                ))
+            }),
+        );
+        let (ports, warning) = cache.list_or_warn(false);
+        assert!(ports.is_empty());
+        assert!(warning.unwrap().contains("udev unavailable"));
+    }
+
+    fn usb(name: &str, serial: Option<&str>, manufacturer: Option<&str>) -> SerialPortInfo {
+        SerialPortInfo {
+            port_name: name.to_string(),
+            port_type: SerialPortType::UsbPort(serialport::UsbPortInfo {
+                vid: 0x0403,
+                pid: 0x6001,
+                serial_number: serial.map(str::to_string),
+                manufacturer: manufacturer.map(str::to_string),
+                product: None,
+                location: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_port_query_matches_usb_identity() {
+        let ports = vec![
+            SerialPortInfo {
+                port_name: "/dev/ttyS0".to_string(),
+                port_type: SerialPortType::Unknown,
+            },
+            usb("/dev/ttyUSB0", Some("A1"), Some("FTDI")),
+            usb("/dev/ttyUSB1", Some("B2"), None),
+        ];
+        let query = |q: PortQuery| q.find(&ports);
+
+        assert_eq!(
+            query(PortQuery {
+                serial: Some("B2".into()),
+                ..Default::default()
+            }),
+            vec!["/dev/ttyUSB1"]
+        );
+        assert_eq!(
+            query(PortQuery {
+                manufacturer_contains: Some("ftd".into()),
+                ..Default::default()
+            }),
+            vec!["/dev/ttyUSB0"]
+        );
+        // Every set field must match; non-USB ports never do
+        assert!(query(PortQuery {
+            vid: Some(0x0403),
+            serial: Some("A2".into()),
+            ..Default::default()
+        })
+        .is_empty());
+        assert_eq!(
+            query(PortQuery::default()),
+            vec!["/dev/ttyUSB0", "/dev/ttyUSB1"]
+        );
+    }
+
+    #[test]
+    fn test_invalidate() {
+        let (cache, calls) = counting_cache(Duration::from_secs(60));
+        cache.list(false).unwrap();
+        cache.invalidate();
+        cache.list(false).unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -24,6 +24,21 @@ pub enum PortError {
     #[error("Operation timed out after {0:?}")]
     Timeout(std::time::Duration),
 
+    /// The port exists but is held by another process; `holder` names it
+    /// when it could be found.
+    #[error("Serial port busy: {port}{}", held_by(.holder))]
+    Busy {
+        port: String,
+        holder: Option<String>,
+    },
+
+    /// The current user may not open the port (EACCES).
+    #[error(
+        "Permission denied opening {0}: on Linux, add the user to the 'dialout' group \
+         (e.g. `sudo usermod -aG dialout $USER`) and log in again"
+    )]
+    PermissionDenied(String),
+
     /// Attempted to open a port that's already open.
     #[error("Port is already open")]
     AlreadyOpen,
@@ -32,11 +47,26 @@ pub enum PortError {
     #[error("Port is not open")]
     NotOpen,
 
+    /// A framing or parity error was detected on received data.
+    #[error("Framing error: {0}")]
+    Framing(String),
+
+    /// The port or platform does not support the operation.
+    #[error("Not supported by this port: {0}")]
+    Unsupported(String),
+
     /// A serialport-specific error occurred.
     #[error("Serial port error: {0}")]
     Serial(#[from] serialport::Error),
 }
 
+fn held_by(holder: &Option<String>) -> String {
+    holder
+        .as_ref()
+        .map(|h| format!(", held by {h}"))
+        .unwrap_or_default()
+}
+
 /// Convert from serialport::ErrorKind to PortError for better error handling.
 impl PortError {
     /// Create a NotFound error from a port name.
@@ -49,16 +79,67 @@ impl PortError {
         Self::Config(message.into())
     }
 
+    /// Create a Framing error from a message.
+    pub fn framing(message: impl Into<String>) -> Self {
+        Self::Framing(message.into())
+    }
+
+    /// Create an Unsupported error naming the operation.
+    pub fn unsupported(operation: impl Into<String>) -> Self {
+        Self::Unsupported(operation.into())
+    }
+
+    /// Create a Busy error for a port, naming the process holding it if known.
+    pub fn busy(port: impl Into<String>, holder: Option<String>) -> Self {
+        Self::Busy {
+            port: port.into(),
+            holder,
+        }
+    }
+
     /// Create a Timeout error from a duration.
     pub fn timeout(duration: std::time::Duration) -> Self {
         Self::Timeout(duration)
     }
+
+    /// Whether an open failure may succeed if retried shortly.
+    ///
+    /// True for busy ports and permission errors, which on Linux are commonly
+    /// seen right after another process releases a device. A missing port is
+    /// never transient.
+    pub fn is_transient_open_error(&self) -> bool {
+        match self {
+            Self::Busy { .. } | Self::PermissionDenied(_) => true,
+            Self::Io(e) => e.kind() == std::io::ErrorKind::PermissionDenied,
+            Self::Serial(e) => {
+                e.kind() == serialport::ErrorKind::Io(std::io::ErrorKind::PermissionDenied)
+            }
+            _ => false,
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_transient_open_errors() {
+        assert!(PortError::busy("/dev/ttyUSB0", None).is_transient_open_error());
+        assert!(PortError::PermissionDenied("/dev/ttyUSB0".into()).is_transient_open_error());
+        assert!(
+            PortError::Io(std::io::Error::from(std::io::ErrorKind::PermissionDenied))
+                .is_transient_open_error()
+        );
+        assert!(PortError::Serial(serialport::Error::new(
+            serialport::ErrorKind::Io(std::io::ErrorKind::PermissionDenied),
+            "denied"
+        ))
+        .is_transient_open_error());
+        assert!(!PortError::not_found("/dev/ttyUSB0").is_transient_open_error());
+        assert!(!PortError::config("bad baud").is_transient_open_error());
+    }
+
     #[test]
     fn test_error_display() {
         let err = PortError::not_found("/dev/ttyUSB0");
@@ -69,6 +150,14 @@ mod tests {
 
         let err = PortError::AlreadyOpen;
         assert_eq!(err.to_string(), "Port is already open");
+
+        let err = PortError::busy("/dev/ttyUSB0", None);
+        assert_eq!(err.to_string(), "Serial port busy: /dev/ttyUSB0");
+        let err = PortError::busy("/dev/ttyUSB0", Some("minicom (pid 4242)".into()));
+        assert_eq!(
+            err.to_string(),
+            "Serial port busy: /dev/ttyUSB0, held by minicom (pid 4242)"
+        );
     }
 
     #[test]
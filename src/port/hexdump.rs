@@ -0,0 +1,111 @@
+//! Canonical hex dump formatting for received bytes.
+//!
+//! Shared by the TUI hex view and the `hexdump` read encoding so both show
+//! binary data the same way.
+
+use std::fmt::Write;
+
+/// Bytes shown on each hex dump line.
+pub const HEXDUMP_WIDTH: usize = 16;
+
+/// Format `bytes` as `hexdump -C` style lines.
+///
+/// Each line is `offset  hh hh .. hh  hh .. hh  |ascii|`: an 8-digit hex
+/// offset, up to 16 lowercase hex bytes split into two groups of eight, and an
+/// ASCII gutter where non-printable bytes are shown as `.`. Short final lines
+/// are padded so the gutter stays aligned. Lines are separated by `\n`, with no
+/// trailing newline; empty input yields an empty string.
+///
+/// # Example
+/// ```
+/// use serial_mcp_agent::port::hexdump;
+///
+/// assert_eq!(
+///     hexdump(b"OK\r\n"),
+///     "00000000  4f 4b 0d 0a                                       |OK..|"
+/// );
+/// ```
+pub fn hexdump(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    for (line, chunk) in bytes.chunks(HEXDUMP_WIDTH).enumerate() {
+        if line > 0 {
+            out.push('\n');
+        }
+        let _ = write!(out, "{:08x}  ", line * HEXDUMP_WIDTH);
+        for i in 0..HEXDUMP_WIDTH {
+            if i == HEXDUMP_WIDTH / 2 {
+                out.push(' ');
+            }
+            match chunk.get(i) {
+                Some(b) => {
+                    let _ = write!(out, "{:02x} ", b);
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|&b| {
+            if b.is_ascii_graphic() || b == b' ' {
+                b as char
+            } else {
+                '.'
+            }
+        }));
+        out.push('|');
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Column of the opening `|` of the ASCII gutter.
+    const GUTTER: usize = 10 + HEXDUMP_WIDTH * 3 + 2;
+
+    #[test]
+    fn test_hexdump_empty() {
+        assert_eq!(hexdump(&[]), "");
+    }
+
+    #[test]
+    fn test_hexdump_single_byte() {
+        let dump = hexdump(&[0x41]);
+        assert_eq!(dump.find('|'), Some(GUTTER));
+        assert_eq!(
+            dump,
+            "00000000  41                                                |A|"
+        );
+    }
+
+    #[test]
+    fn test_hexdump_full_line() {
+        let bytes: Vec<u8> = (0x30..0x40).collect();
+        assert_eq!(
+            hexdump(&bytes),
+            "00000000  30 31 32 33 34 35 36 37  38 39 3a 3b 3c 3d 3e 3f  |0123456789:;<=>?|"
+        );
+    }
+
+    #[test]
+    fn test_hexdump_wraps_and_aligns_gutter() {
+        let mut bytes = b"Hello, world!\r\n\x00".to_vec();
+        bytes.push(0xff);
+        assert_eq!(bytes.len(), 17);
+
+        let dump = hexdump(&bytes);
+        let lines: Vec<&str> = dump.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            lines[0],
+            "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 0d 0a 00  |Hello, world!...|"
+        );
+        assert_eq!(
+            lines[1],
+            "00000010  ff                                                |.|"
+        );
+        for line in lines {
+            assert_eq!(line.find('|'), Some(GUTTER));
+        }
+    }
+}
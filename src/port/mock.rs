@@ -5,28 +5,131 @@
 //! expectation verification.
 
 use super::error::PortError;
-use super::traits::SerialPortAdapter;
+use super::traits::{
+    BufferSizes, ControlSignal, DataBits, ModemStatus, Parity, PortCapabilities, SerialPortAdapter,
+    StopBits,
+};
 use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 /// Inner state of the mock port, protected by a mutex for interior mutability.
 #[derive(Debug, Default)]
 struct MockPortState {
     /// Queue of bytes to be returned by read operations.
     read_queue: VecDeque<u8>,
+    /// Bursts that become readable at a later time, in arrival order.
+    delayed_reads: VecDeque<(Instant, Vec<u8>)>,
     /// Log of all bytes written to the port.
     write_log: Vec<Vec<u8>>,
     /// Expected write operations (for verification).
     expected_writes: VecDeque<Vec<u8>>,
     /// Whether the next operation should time out.
     should_timeout: bool,
+    /// Whether the next read should report a framing error.
+    should_framing_error: bool,
     /// Configured timeout duration.
     timeout: Duration,
     /// Whether buffers have been cleared.
     buffers_cleared: bool,
+    /// Number of `flush` calls.
+    flush_count: usize,
+    /// Log of all control signal writes, in order.
+    signal_log: Vec<(ControlSignal, bool)>,
+    /// Current DTR level, if set.
+    dtr: Option<bool>,
+    /// Current RTS level, if set.
+    rts: Option<bool>,
+    /// Signal whose next write should fail.
+    fail_signal: Option<ControlSignal>,
+    /// Modem status input levels; `None` reports them as unsupported.
+    modem_status: Option<ModemStatus>,
+    /// Whether written bytes are looped back into the read queue.
+    echo: bool,
+    /// Reply queued after every write, readable once its latency has passed.
+    auto_response: Option<(Duration, Vec<u8>)>,
+    /// Most bytes accepted by one write, to simulate short writes.
+    write_chunk: Option<usize>,
+    /// Bytes still accepted before writes start timing out.
+    write_capacity: Option<usize>,
+    /// Writes accept nothing until this time, as if the device sent XOFF.
+    xoff_until: Option<Instant>,
+    /// Requests made through `set_buffer_sizes`, in order.
+    buffer_size_requests: Vec<(Option<usize>, Option<usize>)>,
+    /// Current driver buffer sizes.
+    buffer_sizes: BufferSizes,
+    /// Capabilities reported by `capabilities()`.
+    capabilities: PortCapabilities,
+    /// Framing changes applied to the open port, in order.
+    framing_log: Vec<FramingChange>,
+    /// Framing change that should fail the next time it is requested.
+    fail_framing: Option<FramingChange>,
+    /// Seeded misbehaviour set by `with_behavior`, with its RNG.
+    behavior: Option<(Behavior, SeededRng)>,
 }
 
+/// A framing setting changed on an open [`MockSerialPort`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FramingChange {
+    DataBits(DataBits),
+    Parity(Parity),
+    StopBits(StopBits),
+}
+
+/// Seeded, reproducible misbehaviour for property tests.
+///
+/// Every decision is drawn from an RNG seeded with `seed`, so two mocks with
+/// the same behavior that see the same calls produce the same results.
+///
+/// # Example
+/// ```
+/// use serial_mcp_agent::port::{Behavior, MockSerialPort};
+///
+/// let port = MockSerialPort::new("MOCK0").with_behavior(Behavior {
+///     seed: 42,
+///     max_read_chunk: Some(3),
+///     timeout_probability: 0.1,
+///     short_write_fraction: 0.5,
+/// });
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Behavior {
+    pub seed: u64,
+    /// Each read returns between 1 and this many bytes; unlimited if `None`
+    pub max_read_chunk: Option<usize>,
+    /// Chance (0.0 to 1.0) that a read or write times out
+    pub timeout_probability: f64,
+    /// Fraction (0.0 to 1.0) of writes that accept only a random prefix
+    pub short_write_fraction: f64,
+}
+
+/// SplitMix64: tiny and seedable, which is all the mock needs.
+#[derive(Debug, Clone)]
+struct SeededRng(u64);
+
+impl SeededRng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// `true` with probability `p`.
+    fn chance(&mut self, p: f64) -> bool {
+        p > 0.0 && ((self.next_u64() >> 11) as f64 / (1u64 << 53) as f64) < p
+    }
+
+    /// Uniform in `1..=max` (`max` of 0 is treated as 1).
+    fn one_to(&mut self, max: usize) -> usize {
+        1 + (self.next_u64() % max.max(1) as u64) as usize
+    }
+}
+
+/// Largest buffer the mock driver grants; bigger requests are clamped to it.
+pub const MOCK_MAX_BUFFER_SIZE: usize = 64 * 1024;
+
 /// Mock serial port implementation for testing.
 ///
 /// This implementation allows you to:
@@ -73,11 +176,21 @@ impl MockSerialPort {
             name: name.into(),
             state: Arc::new(Mutex::new(MockPortState {
                 timeout: Duration::from_secs(1),
+                buffer_sizes: BufferSizes {
+                    rx: Some(4096),
+                    tx: Some(4096),
+                },
                 ..Default::default()
             })),
         }
     }
 
+    /// Drive partial reads, short writes and timeouts from `behavior`.
+    pub fn with_behavior(self, behavior: Behavior) -> Self {
+        self.state.lock().unwrap().behavior = Some((behavior, SeededRng(behavior.seed)));
+        self
+    }
+
     /// Enqueue bytes to be returned by subsequent read operations.
     ///
     /// The bytes are added to the end of the read queue.
@@ -86,6 +199,18 @@ impl MockSerialPort {
         state.read_queue.extend(data);
     }
 
+    /// Enqueue bytes that only become readable after `delay` has elapsed.
+    ///
+    /// Useful for simulating devices that send data in bursts. While a burst
+    /// is pending, a read with an empty queue waits for it up to the port
+    /// timeout, and returns a `TimedOut` error if it does not arrive in time.
+    pub fn enqueue_read_after(&mut self, delay: Duration, data: &[u8]) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .delayed_reads
+            .push_back((Instant::now() + delay, data.to_vec()));
+    }
+
     /// Expect a specific write operation.
     ///
     /// This adds an expectation that the given data will be written.
@@ -130,6 +255,63 @@ impl MockSerialPort {
         state.should_timeout = should_timeout;
     }
 
+    /// Set whether the next read operation should report a framing error.
+    pub fn set_framing_error(&mut self, framing_error: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.should_framing_error = framing_error;
+    }
+
+    /// Loop written bytes back into the read queue, like a loopback plug.
+    pub fn set_echo(&mut self, echo: bool) {
+        let mut state = self.state.lock().unwrap();
+        state.echo = echo;
+    }
+
+    /// Answer every write with `response`, readable `latency` after the write,
+    /// like a device with a fixed turnaround time. `None` stops answering.
+    pub fn set_auto_response(&mut self, response: Option<(Duration, &[u8])>) {
+        let mut state = self.state.lock().unwrap();
+        state.auto_response = response.map(|(latency, bytes)| (latency, bytes.to_vec()));
+    }
+
+    /// Accept at most `chunk` bytes per write, like a driver doing short writes.
+    pub fn set_write_chunk(&mut self, chunk: Option<usize>) {
+        let mut state = self.state.lock().unwrap();
+        state.write_chunk = chunk;
+    }
+
+    /// Accept `capacity` more bytes in total, then time out every write, like
+    /// a transmit buffer that stops draining.
+    pub fn set_write_capacity(&mut self, capacity: Option<usize>) {
+        let mut state = self.state.lock().unwrap();
+        state.write_capacity = capacity;
+    }
+
+    /// Accept no writes for `duration`, as if the device sent XOFF and then
+    /// XON. Once called, `flow_paused` reports the flow-control state.
+    pub fn pause_flow(&mut self, duration: Duration) {
+        let mut state = self.state.lock().unwrap();
+        state.xoff_until = Some(Instant::now() + duration);
+    }
+
+    /// Set the capabilities the port reports; none are reported by default.
+    pub fn set_capabilities(&mut self, capabilities: PortCapabilities) {
+        let mut state = self.state.lock().unwrap();
+        state.capabilities = capabilities;
+    }
+
+    /// Get every `set_buffer_sizes` request as `(rx, tx)`, in order.
+    pub fn get_buffer_size_requests(&self) -> Vec<(Option<usize>, Option<usize>)> {
+        let state = self.state.lock().unwrap();
+        state.buffer_size_requests.clone()
+    }
+
+    /// Get every framing change applied without reopening, in order.
+    pub fn get_framing_log(&self) -> Vec<FramingChange> {
+        let state = self.state.lock().unwrap();
+        state.framing_log.clone()
+    }
+
     /// Get whether buffers have been cleared since the last reset.
     pub fn was_cleared(&self) -> bool {
         let state = self.state.lock().unwrap();
@@ -142,11 +324,57 @@ impl MockSerialPort {
         state.buffers_cleared = false;
     }
 
+    /// Get the number of times `flush` was called.
+    pub fn flush_count(&self) -> usize {
+        let state = self.state.lock().unwrap();
+        state.flush_count
+    }
+
+    /// Get all control signal writes, in order.
+    pub fn get_signal_log(&self) -> Vec<(ControlSignal, bool)> {
+        let state = self.state.lock().unwrap();
+        state.signal_log.clone()
+    }
+
+    /// Set the modem status input levels the port reports; by default it
+    /// cannot report them.
+    pub fn set_modem_status(&mut self, status: ModemStatus) {
+        let mut state = self.state.lock().unwrap();
+        state.modem_status = Some(status);
+    }
+
+    /// Make the next write to `signal` fail.
+    pub fn fail_next_signal_write(&mut self, signal: ControlSignal) {
+        let mut state = self.state.lock().unwrap();
+        state.fail_signal = Some(signal);
+    }
+
+    /// Make the next request for `change` fail.
+    pub fn fail_next_framing_change(&mut self, change: FramingChange) {
+        let mut state = self.state.lock().unwrap();
+        state.fail_framing = Some(change);
+    }
+
     /// Get the number of bytes available to read.
     pub fn available_bytes(&self) -> usize {
         let state = self.state.lock().unwrap();
         state.read_queue.len()
     }
+
+    /// Record a live framing change, or refuse it like a port that must be
+    /// reopened unless `supports_set_framing_live` is set.
+    fn change_framing(&mut self, change: FramingChange) -> Result<(), PortError> {
+        let mut state = self.state.lock().unwrap();
+        if !state.capabilities.supports_set_framing_live {
+            return Err(PortError::unsupported("changing framing on an open port"));
+        }
+        if state.fail_framing == Some(change) {
+            state.fail_framing = None;
+            return Err(PortError::config(format!("simulated {:?} failure", change)));
+        }
+        state.framing_log.push(change);
+        Ok(())
+    }
 }
 
 impl SerialPortAdapter for MockSerialPort {
@@ -159,8 +387,41 @@ impl SerialPortAdapter for MockSerialPort {
             return Err(PortError::timeout(state.timeout));
         }
 
+        // A paused device takes nothing until it resumes
+        if state.xoff_until.is_some_and(|until| Instant::now() < until) {
+            return Ok(0);
+        }
+
+        // Short writes accept only a prefix of the data
+        let mut accepted = state.write_chunk.map_or(data.len(), |c| c.min(data.len()));
+        let timeout = state.timeout;
+        if let Some((behavior, rng)) = &mut state.behavior {
+            if rng.chance(behavior.timeout_probability) {
+                return Err(PortError::timeout(timeout));
+            }
+            if data.len() > 1 && rng.chance(behavior.short_write_fraction) {
+                accepted = accepted.min(rng.one_to(data.len() - 1));
+            }
+        }
+        if let Some(capacity) = state.write_capacity {
+            if capacity == 0 {
+                return Err(PortError::timeout(state.timeout));
+            }
+            accepted = accepted.min(capacity);
+            state.write_capacity = Some(capacity - accepted);
+        }
+        let data = &data[..accepted];
+
         // Log the write
         state.write_log.push(data.to_vec());
+        if state.echo {
+            state.read_queue.extend(data);
+        }
+        if let Some((latency, response)) = state.auto_response.clone() {
+            state
+                .delayed_reads
+                .push_back((Instant::now() + latency, response));
+        }
 
         // Check expectations if any exist
         if let Some(expected) = state.expected_writes.pop_front() {
@@ -184,6 +445,55 @@ impl SerialPortAdapter for MockSerialPort {
             return Err(PortError::timeout(state.timeout));
         }
 
+        // Check if we should simulate a framing error
+        if state.should_framing_error {
+            state.should_framing_error = false;
+            return Err(PortError::framing("simulated framing error"));
+        }
+
+        let mut buffer = buffer;
+        let timeout = state.timeout;
+        if let Some((behavior, rng)) = &mut state.behavior {
+            if rng.chance(behavior.timeout_probability) {
+                return Err(PortError::timeout(timeout));
+            }
+            if let Some(max) = behavior.max_read_chunk {
+                let limit = rng.one_to(max).min(buffer.len());
+                buffer = &mut std::mem::take(&mut buffer)[..limit];
+            }
+        }
+
+        // Deliver pending bursts, waiting up to the timeout for the next one
+        if state.read_queue.is_empty() {
+            if let Some(&(due, _)) = state.delayed_reads.front() {
+                let now = Instant::now();
+                let timeout = state.timeout;
+                if due > now + timeout {
+                    drop(state);
+                    std::thread::sleep(timeout);
+                    return Err(PortError::Io(std::io::Error::new(
+                        std::io::ErrorKind::TimedOut,
+                        "Operation timed out",
+                    )));
+                }
+                if due > now {
+                    drop(state);
+                    std::thread::sleep(due - now);
+                    state = self.state.lock().unwrap();
+                }
+            }
+        }
+        let now = Instant::now();
+        while state
+            .delayed_reads
+            .front()
+            .is_some_and(|(due, _)| *due <= now)
+        {
+            if let Some((_, data)) = state.delayed_reads.pop_front() {
+                state.read_queue.extend(data);
+            }
+        }
+
         // Read as many bytes as possible from the queue
         let mut bytes_read = 0;
         for byte in buffer.iter_mut() {
@@ -223,15 +533,104 @@ impl SerialPortAdapter for MockSerialPort {
         Ok(())
     }
 
+    fn flush(&mut self) -> Result<(), PortError> {
+        let mut state = self.state.lock().unwrap();
+        state.flush_count += 1;
+        Ok(())
+    }
+
     fn bytes_to_read(&self) -> Option<usize> {
         let state = self.state.lock().unwrap();
-        Some(state.read_queue.len())
+        // Delayed bursts count once due, as the next read would deliver them
+        let now = Instant::now();
+        let due: usize = state
+            .delayed_reads
+            .iter()
+            .take_while(|(due, _)| *due <= now)
+            .map(|(_, data)| data.len())
+            .sum();
+        Some(state.read_queue.len() + due)
     }
 
     fn bytes_to_write(&self) -> Option<usize> {
         // For a mock port, there's no write buffer
         Some(0)
     }
+
+    fn buffer_sizes(&self) -> Result<BufferSizes, PortError> {
+        Ok(self.state.lock().unwrap().buffer_sizes)
+    }
+
+    fn set_buffer_sizes(
+        &mut self,
+        rx: Option<usize>,
+        tx: Option<usize>,
+    ) -> Result<BufferSizes, PortError> {
+        let mut state = self.state.lock().unwrap();
+        state.buffer_size_requests.push((rx, tx));
+        if let Some(rx) = rx {
+            state.buffer_sizes.rx = Some(rx.min(MOCK_MAX_BUFFER_SIZE));
+        }
+        if let Some(tx) = tx {
+            state.buffer_sizes.tx = Some(tx.min(MOCK_MAX_BUFFER_SIZE));
+        }
+        Ok(state.buffer_sizes)
+    }
+
+    fn flow_paused(&self) -> Option<bool> {
+        let state = self.state.lock().unwrap();
+        state.xoff_until.map(|until| Instant::now() < until)
+    }
+
+    fn detects_framing_errors(&self) -> bool {
+        true
+    }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<(), PortError> {
+        self.change_framing(FramingChange::DataBits(data_bits))
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<(), PortError> {
+        self.change_framing(FramingChange::Parity(parity))
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<(), PortError> {
+        self.change_framing(FramingChange::StopBits(stop_bits))
+    }
+
+    fn write_signal(&mut self, signal: ControlSignal, level: bool) -> Result<(), PortError> {
+        let mut state = self.state.lock().unwrap();
+        if state.fail_signal == Some(signal) {
+            state.fail_signal = None;
+            return Err(PortError::config(format!("simulated {:?} failure", signal)));
+        }
+        state.signal_log.push((signal, level));
+        match signal {
+            ControlSignal::Dtr => state.dtr = Some(level),
+            ControlSignal::Rts => state.rts = Some(level),
+        }
+        Ok(())
+    }
+
+    fn modem_status(&mut self) -> Result<ModemStatus, PortError> {
+        self.state
+            .lock()
+            .unwrap()
+            .modem_status
+            .ok_or_else(|| PortError::unsupported("reading modem status lines"))
+    }
+
+    fn signal_level(&self, signal: ControlSignal) -> Option<bool> {
+        let state = self.state.lock().unwrap();
+        match signal {
+            ControlSignal::Dtr => state.dtr,
+            ControlSignal::Rts => state.rts,
+        }
+    }
+
+    fn capabilities(&self) -> PortCapabilities {
+        self.state.lock().unwrap().capabilities.clone()
+    }
 }
 
 impl std::fmt::Debug for MockSerialPort {
@@ -302,6 +701,45 @@ mod tests {
         assert!(matches!(result, Err(PortError::Timeout(_))));
     }
 
+    #[test]
+    fn test_framing_error_simulation() {
+        let mut port = MockSerialPort::new("MOCK0");
+        port.enqueue_read(b"data");
+        port.set_framing_error(true);
+
+        let mut buffer = [0u8; 10];
+        let result = port.read_bytes(&mut buffer);
+        assert!(matches!(result, Err(PortError::Framing(_))));
+
+        // The simulated error is one-shot; queued data is still readable
+        let n = port.read_bytes(&mut buffer).unwrap();
+        assert_eq!(&buffer[..n], b"data");
+    }
+
+    #[test]
+    fn test_delayed_read_burst() {
+        let mut port = MockSerialPort::new("MOCK0");
+        port.set_timeout(Duration::from_millis(500)).unwrap();
+        port.enqueue_read_after(Duration::from_millis(20), b"late");
+
+        let mut buffer = [0u8; 10];
+        let n = port.read_bytes(&mut buffer).unwrap();
+        assert_eq!(&buffer[..n], b"late");
+    }
+
+    #[test]
+    fn test_delayed_read_beyond_timeout() {
+        let mut port = MockSerialPort::new("MOCK0");
+        port.set_timeout(Duration::from_millis(10)).unwrap();
+        port.enqueue_read_after(Duration::from_secs(5), b"late");
+
+        let mut buffer = [0u8; 10];
+        let result = port.read_bytes(&mut buffer);
+        assert!(
+            matches!(result, Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::TimedOut)
+        );
+    }
+
     #[test]
     fn test_clear_buffers() {
         let mut port = MockSerialPort::new("MOCK0");
@@ -361,6 +799,32 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_framing_changes_are_recorded() {
+        let mut port = MockSerialPort::new("MOCK0");
+        assert!(matches!(
+            port.set_parity(Parity::Even),
+            Err(PortError::Unsupported(_))
+        ));
+        assert!(port.get_framing_log().is_empty());
+
+        port.set_capabilities(PortCapabilities {
+            supports_set_framing_live: true,
+            ..Default::default()
+        });
+        port.set_data_bits(DataBits::Seven).unwrap();
+        port.set_parity(Parity::Even).unwrap();
+        port.set_stop_bits(StopBits::Two).unwrap();
+        assert_eq!(
+            port.get_framing_log(),
+            vec![
+                FramingChange::DataBits(DataBits::Seven),
+                FramingChange::Parity(Parity::Even),
+                FramingChange::StopBits(StopBits::Two),
+            ]
+        );
+    }
+
     #[test]
     fn test_bytes_to_read() {
         let mut port = MockSerialPort::new("MOCK0");
@@ -368,4 +832,68 @@ mod tests {
 
         assert_eq!(port.bytes_to_read(), Some(9));
     }
+
+    /// Run a fixed script of writes and reads, recording every outcome.
+    fn run_script(port: &mut MockSerialPort) -> Vec<Result<Vec<u8>, String>> {
+        let mut outcomes = Vec::new();
+        for i in 0..40u8 {
+            let data: Vec<u8> = (0..=i % 9).map(|b| b'a' + b).collect();
+            outcomes.push(
+                port.write_bytes(&data)
+                    .map(|n| data[..n].to_vec())
+                    .map_err(|e| e.to_string()),
+            );
+            port.enqueue_read(&data);
+            let mut buffer = [0u8; 16];
+            outcomes.push(
+                port.read_bytes(&mut buffer)
+                    .map(|n| buffer[..n].to_vec())
+                    .map_err(|e| e.to_string()),
+            );
+        }
+        outcomes
+    }
+
+    #[test]
+    fn test_behavior_is_reproducible_from_seed() {
+        let behavior = Behavior {
+            seed: 7,
+            max_read_chunk: Some(4),
+            timeout_probability: 0.2,
+            short_write_fraction: 0.5,
+        };
+        let mut first = MockSerialPort::new("MOCK0").with_behavior(behavior);
+        let mut second = MockSerialPort::new("MOCK1").with_behavior(behavior);
+        let outcomes = run_script(&mut first);
+        assert_eq!(outcomes, run_script(&mut second));
+        assert_eq!(first.get_write_log(), second.get_write_log());
+
+        // Every kind of misbehaviour shows up, within its bounds
+        assert!(outcomes.iter().any(|o| o.is_err()));
+        for (i, write) in outcomes.iter().step_by(2).enumerate() {
+            if let Ok(accepted) = write {
+                assert!(!accepted.is_empty() && accepted.len() <= i % 9 + 1);
+            }
+        }
+        assert!(outcomes
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .all(|read| read.as_ref().map_or(true, |r| r.len() <= 4)));
+
+        let mut reseeded = MockSerialPort::new("MOCK2").with_behavior(Behavior {
+            seed: 8,
+            ..behavior
+        });
+        assert_ne!(outcomes, run_script(&mut reseeded));
+    }
+
+    #[test]
+    fn test_default_behavior_changes_nothing() {
+        let mut port = MockSerialPort::new("MOCK0").with_behavior(Behavior::default());
+        assert_eq!(port.write_bytes(b"Hello").unwrap(), 5);
+        port.enqueue_read(b"World");
+        let mut buffer = [0u8; 10];
+        assert_eq!(port.read_bytes(&mut buffer).unwrap(), 5);
+    }
 }
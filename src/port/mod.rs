@@ -3,8 +3,12 @@
 //! Provides traits and implementations for both sync and async serial I/O,
 //! enabling dependency injection and testing via mocks.
 
+pub mod decode;
+pub mod enumeration;
 pub mod error;
+pub mod hexdump;
 pub mod mock;
+pub mod modbus;
 pub mod sync_port;
 pub mod traits;
 
@@ -12,8 +16,12 @@ pub mod traits;
 #[cfg(feature = "async-serial")]
 pub mod async_port;
 
+pub use decode::{decode_values, DecodeSpec, Endianness, NumericType};
+pub use enumeration::{PortEnumerator, PortListCache, PortQuery};
 pub use error::PortError;
-pub use mock::MockSerialPort;
+pub use hexdump::hexdump;
+pub use mock::{Behavior, FramingChange, MockSerialPort, MOCK_MAX_BUFFER_SIZE};
+pub use modbus::{coalesce_ranges, RegisterKind, RegisterRange, MAX_READ_REGISTERS};
 pub use sync_port::*;
 pub use traits::*;
 
@@ -0,0 +1,286 @@
+//! Modbus RTU register reads.
+//!
+//! Builds read-register requests, validates their responses, and merges
+//! scattered register ranges into as few requests as the protocol allows.
+
+use crate::state::ChecksumKind;
+use rust_mcp_sdk::macros::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Most registers a single read request may ask for.
+pub const MAX_READ_REGISTERS: u16 = 125;
+
+/// Register table to read from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RegisterKind {
+    /// Holding registers, function 0x03
+    #[default]
+    Holding,
+    /// Input registers, function 0x04
+    Input,
+}
+
+impl RegisterKind {
+    /// Modbus function code of a read from this table.
+    pub fn function_code(self) -> u8 {
+        match self {
+            Self::Holding => 0x03,
+            Self::Input => 0x04,
+        }
+    }
+}
+
+/// `count` consecutive registers starting at `start`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub struct RegisterRange {
+    pub start: u16,
+    pub count: u16,
+}
+
+impl RegisterRange {
+    /// One past the last address, which may be 65536.
+    pub fn end(self) -> u32 {
+        u32::from(self.start) + u32::from(self.count)
+    }
+}
+
+/// Merge `ranges` into read requests of at most [`MAX_READ_REGISTERS`].
+///
+/// Overlapping and adjacent ranges always merge. Ranges separated by at most
+/// `max_gap` unrequested registers merge too, reading the gap, as long as the
+/// result still fits one request. Longer ranges are split. The requests come
+/// back sorted by address; empty ranges are dropped.
+///
+/// # Example
+/// ```
+/// use serial_mcp_agent::port::{coalesce_ranges, RegisterRange};
+///
+/// let r = |start, count| RegisterRange { start, count };
+/// assert_eq!(
+///     coalesce_ranges(&[r(10, 2), r(0, 10), r(500, 1)], 0),
+///     vec![r(0, 12), r(500, 1)]
+/// );
+/// ```
+pub fn coalesce_ranges(ranges: &[RegisterRange], max_gap: u16) -> Vec<RegisterRange> {
+    let max = u32::from(MAX_READ_REGISTERS);
+    let mut spans: Vec<(u32, u32)> = ranges
+        .iter()
+        .filter(|r| r.count > 0)
+        .map(|r| (u32::from(r.start), r.end()))
+        .collect();
+    spans.sort_unstable();
+
+    let mut requests = Vec::new();
+    let mut current: Option<(u32, u32)> = None;
+    for (start, end) in spans {
+        if let Some((block_start, block_end)) = current {
+            if start <= block_end + u32::from(max_gap) && end.max(block_end) - block_start <= max {
+                current = Some((block_start, end.max(block_end)));
+                continue;
+            }
+            requests.push((block_start, block_end));
+        }
+        // Registers before the previous end were already requested
+        let mut block_start = current.map_or(start, |(_, block_end)| start.max(block_end));
+        while end - block_start > max {
+            requests.push((block_start, block_start + max));
+            block_start += max;
+        }
+        current = Some((block_start, end));
+    }
+    requests.extend(current);
+
+    requests
+        .into_iter()
+        .map(|(start, end)| RegisterRange {
+            start: start as u16,
+            count: (end - start) as u16,
+        })
+        .collect()
+}
+
+/// Request frame reading `range` from `kind` on unit `unit`, CRC included.
+pub fn read_request(unit: u8, kind: RegisterKind, range: RegisterRange) -> Vec<u8> {
+    let mut frame = vec![unit, kind.function_code()];
+    frame.extend_from_slice(&range.start.to_be_bytes());
+    frame.extend_from_slice(&range.count.to_be_bytes());
+    let crc = ChecksumKind::Crc16Modbus.compute(&frame);
+    frame.extend_from_slice(&crc);
+    frame
+}
+
+/// Length of the complete response to a read of `count` registers, given
+/// the first bytes `received` so far: an exception reply is shorter.
+pub fn response_len(received: &[u8], count: u16) -> usize {
+    match received.get(1) {
+        Some(function) if function & 0x80 != 0 => 5,
+        _ => 5 + 2 * usize::from(count),
+    }
+}
+
+/// Check a complete response to [`read_request`] and return the register
+/// values, or a description of what was wrong with it.
+pub fn parse_read_response(
+    frame: &[u8],
+    unit: u8,
+    kind: RegisterKind,
+    count: u16,
+) -> Result<Vec<u16>, String> {
+    if frame.len() < 5 {
+        return Err(format!("response too short ({} bytes)", frame.len()));
+    }
+    let (body, crc) = frame.split_at(frame.len() - 2);
+    if ChecksumKind::Crc16Modbus.compute(body) != crc {
+        return Err("response CRC mismatch".to_string());
+    }
+    if body[0] != unit {
+        return Err(format!(
+            "response from unit {} (expected {})",
+            body[0], unit
+        ));
+    }
+    let function = kind.function_code();
+    if body[1] == function | 0x80 {
+        return Err(format!(
+            "exception {} ({})",
+            body[2],
+            exception_name(body[2])
+        ));
+    }
+    if body[1] != function {
+        return Err(format!("unexpected function code 0x{:02x}", body[1]));
+    }
+    let expected = 2 * usize::from(count);
+    if usize::from(body[2]) != expected || body.len() != 3 + expected {
+        return Err(format!(
+            "response carries {} data bytes (expected {})",
+            body[2], expected
+        ));
+    }
+    Ok(body[3..]
+        .chunks_exact(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect())
+}
+
+fn exception_name(code: u8) -> &'static str {
+    match code {
+        1 => "illegal function",
+        2 => "illegal data address",
+        3 => "illegal data value",
+        4 => "server device failure",
+        5 => "acknowledge",
+        6 => "server device busy",
+        _ => "unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn r(start: u16, count: u16) -> RegisterRange {
+        RegisterRange { start, count }
+    }
+
+    #[test]
+    fn test_coalesce_adjacent_ranges() {
+        assert_eq!(
+            coalesce_ranges(&[r(0, 10), r(10, 5), r(15, 1)], 0),
+            vec![r(0, 16)]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_overlapping_ranges() {
+        assert_eq!(coalesce_ranges(&[r(5, 10), r(0, 8)], 0), vec![r(0, 15)]);
+        // Contained and duplicate ranges add nothing
+        assert_eq!(
+            coalesce_ranges(&[r(0, 20), r(5, 3), r(0, 20)], 0),
+            vec![r(0, 20)]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_keeps_far_apart_ranges_separate() {
+        assert_eq!(
+            coalesce_ranges(&[r(1000, 2), r(0, 2), r(40000, 1)], 0),
+            vec![r(0, 2), r(1000, 2), r(40000, 1)]
+        );
+        // One unrequested register between them is a gap
+        assert_eq!(
+            coalesce_ranges(&[r(0, 2), r(3, 2)], 0),
+            vec![r(0, 2), r(3, 2)]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_bridges_gaps_up_to_max_gap() {
+        assert_eq!(coalesce_ranges(&[r(0, 2), r(5, 2)], 3), vec![r(0, 7)]);
+        assert_eq!(
+            coalesce_ranges(&[r(0, 2), r(6, 2)], 3),
+            vec![r(0, 2), r(6, 2)]
+        );
+        // Bridging stops where the request would exceed the limit
+        assert_eq!(
+            coalesce_ranges(&[r(0, 100), r(110, 20)], 50),
+            vec![r(0, 100), r(110, 20)]
+        );
+    }
+
+    #[test]
+    fn test_coalesce_respects_request_limit() {
+        assert_eq!(
+            coalesce_ranges(&[r(0, 300)], 0),
+            vec![r(0, 125), r(125, 125), r(250, 50)]
+        );
+        // An overlap that would overflow the block is not read twice
+        assert_eq!(
+            coalesce_ranges(&[r(0, 100), r(90, 100)], 0),
+            vec![r(0, 100), r(100, 90)]
+        );
+        assert_eq!(
+            coalesce_ranges(&[r(65535, 1), r(0, 0)], 0),
+            vec![r(65535, 1)]
+        );
+        assert!(coalesce_ranges(&[], 10).is_empty());
+    }
+
+    #[test]
+    fn test_read_request_frame() {
+        // Reference frame: unit 1, read 2 holding registers at 0
+        assert_eq!(
+            read_request(1, RegisterKind::Holding, r(0, 2)),
+            vec![0x01, 0x03, 0x00, 0x00, 0x00, 0x02, 0xC4, 0x0B]
+        );
+    }
+
+    #[test]
+    fn test_parse_read_response() {
+        let mut frame = vec![0x01, 0x03, 0x04, 0x12, 0x34, 0xff, 0xfe];
+        let crc = ChecksumKind::Crc16Modbus.compute(&frame);
+        frame.extend_from_slice(&crc);
+        assert_eq!(response_len(&frame, 2), frame.len());
+        assert_eq!(
+            parse_read_response(&frame, 1, RegisterKind::Holding, 2),
+            Ok(vec![0x1234, 0xfffe])
+        );
+        assert!(parse_read_response(&frame, 2, RegisterKind::Holding, 2).is_err());
+        assert!(parse_read_response(&frame, 1, RegisterKind::Input, 2).is_err());
+        frame[3] ^= 1;
+        assert_eq!(
+            parse_read_response(&frame, 1, RegisterKind::Holding, 2),
+            Err("response CRC mismatch".to_string())
+        );
+
+        let mut exception = vec![0x01, 0x83, 0x02];
+        let crc = ChecksumKind::Crc16Modbus.compute(&exception);
+        exception.extend_from_slice(&crc);
+        assert_eq!(response_len(&exception[..2], 2), 5);
+        assert_eq!(
+            parse_read_response(&exception, 1, RegisterKind::Holding, 2),
+            Err("exception 2 (illegal data address)".to_string())
+        );
+    }
+}
@@ -4,23 +4,148 @@
 //! trait for dependency injection and testing.
 
 use super::error::PortError;
-use super::traits::{PortConfiguration, SerialPortAdapter};
+use super::traits::{
+    ControlSignal, DataBits, ModemStatus, Parity, PortConfiguration, SerialPortAdapter, StopBits,
+};
+use std::borrow::Cow;
 use std::io::{Read, Write};
 use std::time::Duration;
 
+/// Rewrite `COMn` with n > 9 to the `\\.\COMn` device path that Windows
+/// requires for high-numbered ports. Other names are returned unchanged.
+///
+/// The open functions apply this on Windows only, so callers can always pass
+/// the plain `COM10` spelling.
+///
+/// # Example
+/// ```
+/// use serial_mcp_agent::port::normalize_port_name;
+///
+/// assert_eq!(normalize_port_name("COM10"), r"\\.\COM10");
+/// assert_eq!(normalize_port_name("COM3"), "COM3");
+/// assert_eq!(normalize_port_name("/dev/ttyUSB0"), "/dev/ttyUSB0");
+/// ```
+pub fn normalize_port_name(port_name: &str) -> Cow<'_, str> {
+    let number = port_name
+        .get(..3)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("COM"))
+        .map(|_| &port_name[3..])
+        .filter(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+        .and_then(|digits| digits.parse::<u32>().ok());
+    match number {
+        Some(n) if n > 9 => Cow::Owned(format!(r"\\.\{port_name}")),
+        _ => Cow::Borrowed(port_name),
+    }
+}
+
+/// Device path to hand to the OS for `port_name` on this platform.
+pub(crate) fn os_port_path(port_name: &str) -> Cow<'_, str> {
+    if cfg!(windows) {
+        normalize_port_name(port_name)
+    } else {
+        Cow::Borrowed(port_name)
+    }
+}
+
+/// Whether an open failed because another process holds the port (EBUSY).
+///
+/// serialport reports EBUSY as `NoDevice` or an I/O error depending on
+/// where it is raised, so the message is what identifies it.
+fn is_busy(e: &serialport::Error) -> bool {
+    e.description.to_lowercase().contains("busy")
+}
+
+/// The process holding `port_name` open, as `minicom (pid 4242)`, if it can
+/// be found.
+///
+/// UUCP lock files (`/var/lock/LCK..ttyUSB0`) are checked first, then
+/// `/proc/*/fd` is scanned for a descriptor on the device. Processes of other
+/// users are only visible to root. Always `None` off Linux.
+fn port_holder(port_name: &str) -> Option<String> {
+    #[cfg(target_os = "linux")]
+    {
+        linux::port_holder(port_name)
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = port_name;
+        None
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::path::Path;
+
+    pub(super) fn port_holder(port_name: &str) -> Option<String> {
+        let device = std::fs::canonicalize(port_name).ok()?;
+        let base = device.file_name()?.to_str()?;
+        let alive = |pid: &u32| Path::new(&format!("/proc/{pid}")).exists();
+        let pid = ["/var/lock", "/run/lock"]
+            .iter()
+            .find_map(|dir| {
+                let contents = std::fs::read(Path::new(dir).join(format!("LCK..{base}"))).ok()?;
+                parse_lock_pid(&contents).filter(alive)
+            })
+            .or_else(|| fd_holder(&device))?;
+        let comm = std::fs::read_to_string(format!("/proc/{pid}/comm")).ok();
+        Some(describe_holder(pid, comm.as_deref()))
+    }
+
+    /// First process other than this one with a descriptor open on `device`.
+    fn fd_holder(device: &Path) -> Option<u32> {
+        let own = std::process::id();
+        std::fs::read_dir("/proc")
+            .ok()?
+            .flatten()
+            .find_map(|entry| {
+                let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+                let fds = std::fs::read_dir(entry.path().join("fd")).ok()?;
+                let holds = fds
+                    .flatten()
+                    .any(|fd| std::fs::read_link(fd.path()).is_ok_and(|target| target == device));
+                (holds && pid != own).then_some(pid)
+            })
+    }
+
+    /// PID in a UUCP lock file: ASCII decimal (usually padded to 10 columns)
+    /// or, from older tools, a 4-byte native-endian integer.
+    pub(super) fn parse_lock_pid(contents: &[u8]) -> Option<u32> {
+        let text = std::str::from_utf8(contents).map(str::trim).unwrap_or("");
+        let pid = if !text.is_empty() && text.bytes().all(|b| b.is_ascii_digit()) {
+            text.parse().ok()
+        } else {
+            <[u8; 4]>::try_from(contents).ok().map(u32::from_ne_bytes)
+        };
+        pid.filter(|&pid| pid > 0)
+    }
+
+    pub(super) fn describe_holder(pid: u32, comm: Option<&str>) -> String {
+        match comm.map(str::trim).filter(|c| !c.is_empty()) {
+            Some(comm) => format!("{comm} (pid {pid})"),
+            None => format!("pid {pid}"),
+        }
+    }
+}
+
 /// Synchronous serial port implementation wrapping `serialport::SerialPort`.
 pub struct SyncSerialPort {
     /// The underlying serial port implementation.
     port: Box<dyn serialport::SerialPort>,
     /// The port name/path for identification.
     name: String,
+    /// Last DTR level written, if any.
+    dtr: Option<bool>,
+    /// Last RTS level written, if any.
+    rts: Option<bool>,
 }
 
 impl SyncSerialPort {
     /// Open a serial port with the given configuration.
     ///
     /// # Arguments
-    /// * `port_name` - The system path to the serial port (e.g., "/dev/ttyUSB0" or "COM3")
+    /// * `port_name` - The system path to the serial port (e.g., "/dev/ttyUSB0" or "COM3");
+    ///   on Windows `COM10` and up are opened via [`normalize_port_name`]
     /// * `config` - Configuration parameters for the port
     ///
     /// # Example
@@ -32,22 +157,28 @@ impl SyncSerialPort {
     /// # Ok::<(), Box<dyn std::error::Error>>(())
     /// ```
     pub fn open(port_name: &str, config: PortConfiguration) -> Result<Self, PortError> {
-        let port = serialport::new(port_name, config.baud_rate)
+        let port = serialport::new(os_port_path(port_name), config.baud_rate)
             .data_bits(config.data_bits.into())
             .flow_control(config.flow_control.into())
             .parity(config.parity.into())
-            .stop_bits(config.stop_bits.into())
+            .stop_bits(config.stop_bits.try_into()?)
             .timeout(config.timeout)
             .open()
             .map_err(|e| match e.kind() {
+                _ if is_busy(&e) => PortError::busy(port_name, port_holder(port_name)),
                 serialport::ErrorKind::NoDevice => PortError::not_found(port_name),
                 serialport::ErrorKind::InvalidInput => PortError::config(e.to_string()),
+                serialport::ErrorKind::Io(std::io::ErrorKind::PermissionDenied) => {
+                    PortError::PermissionDenied(port_name.to_string())
+                }
                 _ => PortError::Serial(e),
             })?;
 
         Ok(Self {
             port,
             name: port_name.to_string(),
+            dtr: None,
+            rts: None,
         })
     }
 
@@ -97,6 +228,10 @@ impl SerialPortAdapter for SyncSerialPort {
             .map_err(PortError::Serial)
     }
 
+    fn flush(&mut self) -> Result<(), PortError> {
+        self.port.flush().map_err(PortError::Io)
+    }
+
     fn bytes_to_read(&self) -> Option<usize> {
         self.port.bytes_to_read().ok().map(|n| n as usize)
     }
@@ -104,6 +239,57 @@ impl SerialPortAdapter for SyncSerialPort {
     fn bytes_to_write(&self) -> Option<usize> {
         self.port.bytes_to_write().ok().map(|n| n as usize)
     }
+
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<(), PortError> {
+        self.port
+            .set_data_bits(data_bits.into())
+            .map_err(PortError::Serial)
+    }
+
+    fn set_parity(&mut self, parity: Parity) -> Result<(), PortError> {
+        self.port
+            .set_parity(parity.into())
+            .map_err(PortError::Serial)
+    }
+
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<(), PortError> {
+        self.port
+            .set_stop_bits(stop_bits.try_into()?)
+            .map_err(PortError::Serial)
+    }
+
+    fn write_signal(&mut self, signal: ControlSignal, level: bool) -> Result<(), PortError> {
+        match signal {
+            ControlSignal::Dtr => {
+                self.port
+                    .write_data_terminal_ready(level)
+                    .map_err(PortError::Serial)?;
+                self.dtr = Some(level);
+            }
+            ControlSignal::Rts => {
+                self.port
+                    .write_request_to_send(level)
+                    .map_err(PortError::Serial)?;
+                self.rts = Some(level);
+            }
+        }
+        Ok(())
+    }
+
+    fn modem_status(&mut self) -> Result<ModemStatus, PortError> {
+        Ok(ModemStatus {
+            cts: self.port.read_clear_to_send().map_err(PortError::Serial)?,
+            dsr: self.port.read_data_set_ready().map_err(PortError::Serial)?,
+            cd: self.port.read_carrier_detect().map_err(PortError::Serial)?,
+        })
+    }
+
+    fn signal_level(&self, signal: ControlSignal) -> Option<bool> {
+        match signal {
+            ControlSignal::Dtr => self.dtr,
+            ControlSignal::Rts => self.rts,
+        }
+    }
 }
 
 impl std::fmt::Debug for SyncSerialPort {
@@ -135,6 +321,51 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_normalize_port_name() {
+        assert_eq!(normalize_port_name("COM10"), r"\\.\COM10");
+        assert_eq!(normalize_port_name("com255"), r"\\.\com255");
+        // Low-numbered ports and anything that is not COMn stay as given
+        for name in [
+            "COM1",
+            "COM9",
+            "COM",
+            "COM1A",
+            "COMX10",
+            r"\\.\COM12",
+            "/dev/ttyUSB10",
+        ] {
+            assert!(matches!(normalize_port_name(name), Cow::Borrowed(n) if n == name));
+        }
+    }
+
+    #[test]
+    fn test_busy_detection() {
+        let busy =
+            serialport::Error::new(serialport::ErrorKind::NoDevice, "Device or resource busy");
+        assert!(is_busy(&busy));
+        let missing = serialport::Error::new(serialport::ErrorKind::NoDevice, "No such device");
+        assert!(!is_busy(&missing));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_lock_file_pid_parsing() {
+        use linux::{describe_holder, parse_lock_pid};
+
+        assert_eq!(parse_lock_pid(b"      4242\n"), Some(4242));
+        assert_eq!(parse_lock_pid(&4242u32.to_ne_bytes()), Some(4242));
+        assert_eq!(parse_lock_pid(b""), None);
+        assert_eq!(parse_lock_pid(b"0000000000\n"), None);
+        assert_eq!(parse_lock_pid(b"not a pid"), None);
+
+        assert_eq!(
+            describe_holder(4242, Some("minicom\n")),
+            "minicom (pid 4242)"
+        );
+        assert_eq!(describe_holder(4242, None), "pid 4242");
+    }
+
     #[test]
     fn test_default_configuration() {
         let config = PortConfiguration::default();
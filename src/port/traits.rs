@@ -102,18 +102,102 @@ impl From<Parity> for serialport::Parity {
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum StopBits {
     One,
+    /// 1.5 stop bits, used by some legacy devices with 5 data bits
+    OnedotFive,
     Two,
 }
 
-impl From<StopBits> for serialport::StopBits {
-    fn from(bits: StopBits) -> Self {
+/// Fails for [`StopBits::OnedotFive`], which `serialport` cannot configure.
+impl TryFrom<StopBits> for serialport::StopBits {
+    type Error = PortError;
+
+    fn try_from(bits: StopBits) -> Result<Self, Self::Error> {
         match bits {
-            StopBits::One => serialport::StopBits::One,
-            StopBits::Two => serialport::StopBits::Two,
+            StopBits::One => Ok(serialport::StopBits::One),
+            StopBits::OnedotFive => Err(PortError::config(
+                "1.5 stop bits are not supported by the serial driver on this platform",
+            )),
+            StopBits::Two => Ok(serialport::StopBits::Two),
         }
     }
 }
 
+/// Modem control output lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlSignal {
+    /// Data Terminal Ready
+    Dtr,
+    /// Request To Send
+    Rts,
+}
+
+/// Features a port supports, so callers can check before trying them.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PortCapabilities {
+    /// Can send a break condition.
+    pub supports_break: bool,
+    /// Supports RTS/CTS hardware flow control.
+    pub supports_flow_control_hardware: bool,
+    /// Can change the baud rate without reopening the port.
+    pub supports_set_baud_live: bool,
+    /// Can change data bits, parity and stop bits without reopening the port.
+    #[serde(default)]
+    pub supports_set_framing_live: bool,
+    /// Can read the modem status lines (CTS, DSR, RI, CD).
+    pub supports_modem_status: bool,
+    /// Can resize the driver's RX/TX buffers.
+    #[serde(default)]
+    pub supports_buffer_sizes: bool,
+    /// Supported baud rates, when the driver reports them.
+    pub baud_rate_range: Option<BaudRateRange>,
+}
+
+impl PortCapabilities {
+    /// What the `serialport` backend supports on the current platform.
+    ///
+    /// Native Unix and Windows ports support the first five features; the
+    /// crate has no API for driver buffer sizes. Neither reports a baud rate
+    /// range: drivers accept arbitrary rates and reject unsupported ones at
+    /// open time.
+    pub fn platform() -> Self {
+        let native = cfg!(any(unix, windows));
+        Self {
+            supports_break: native,
+            supports_flow_control_hardware: native,
+            supports_set_baud_live: native,
+            supports_set_framing_live: native,
+            supports_modem_status: native,
+            supports_buffer_sizes: false,
+            baud_rate_range: None,
+        }
+    }
+}
+
+/// Driver receive/transmit buffer sizes in bytes; `None` if unknown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BufferSizes {
+    pub rx: Option<usize>,
+    pub tx: Option<usize>,
+}
+
+/// Levels of the modem status input lines, `true` when asserted.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModemStatus {
+    /// Clear To Send
+    pub cts: bool,
+    /// Data Set Ready
+    pub dsr: bool,
+    /// Carrier Detect
+    pub cd: bool,
+}
+
+/// Inclusive range of supported baud rates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BaudRateRange {
+    pub min: u32,
+    pub max: u32,
+}
+
 /// Trait for serial port I/O operations.
 ///
 /// This trait abstracts over synchronous serial port operations, allowing both
@@ -141,6 +225,13 @@ pub trait SerialPortAdapter: Send + std::fmt::Debug {
     /// data in the transmit buffer.
     fn clear_buffers(&mut self) -> Result<(), PortError>;
 
+    /// Block until all written bytes have been transmitted.
+    ///
+    /// The default does nothing, for ports without a transmit buffer.
+    fn flush(&mut self) -> Result<(), PortError> {
+        Ok(())
+    }
+
     /// Get the current bytes available to read (if supported).
     ///
     /// Returns `None` if the operation is not supported or cannot be determined.
@@ -154,6 +245,100 @@ pub trait SerialPortAdapter: Send + std::fmt::Debug {
     fn bytes_to_write(&self) -> Option<usize> {
         None
     }
+
+    /// Current driver buffer sizes.
+    ///
+    /// Returns `PortError::Unsupported` where the platform cannot report them.
+    fn buffer_sizes(&self) -> Result<BufferSizes, PortError> {
+        Err(PortError::unsupported("reading driver buffer sizes"))
+    }
+
+    /// Request driver buffer sizes; `None` leaves that side unchanged.
+    ///
+    /// Returns the sizes in effect afterwards, which the driver may have
+    /// clamped, or `PortError::Unsupported` where buffers cannot be resized.
+    fn set_buffer_sizes(
+        &mut self,
+        rx: Option<usize>,
+        tx: Option<usize>,
+    ) -> Result<BufferSizes, PortError> {
+        let _ = (rx, tx);
+        Err(PortError::unsupported("setting driver buffer sizes"))
+    }
+
+    /// Whether the device has paused transmission with XOFF (software flow
+    /// control).
+    ///
+    /// Returns `None` if the platform does not expose the flow-control state;
+    /// the `serialport` crate does not, so hardware ports return `None`.
+    fn flow_paused(&self) -> Option<bool> {
+        None
+    }
+
+    /// Whether this port can detect framing/parity errors on read.
+    ///
+    /// Ports that return `true` report such errors as `PortError::Framing`
+    /// from `read_bytes`. The `serialport` crate does not expose error
+    /// counters, so hardware ports return `false`.
+    fn detects_framing_errors(&self) -> bool {
+        false
+    }
+
+    /// Change the number of data bits without reopening the port.
+    ///
+    /// Returns `PortError::Unsupported` where the port must be reopened instead.
+    fn set_data_bits(&mut self, data_bits: DataBits) -> Result<(), PortError> {
+        let _ = data_bits;
+        Err(PortError::unsupported("changing data bits on an open port"))
+    }
+
+    /// Change the parity without reopening the port.
+    ///
+    /// Returns `PortError::Unsupported` where the port must be reopened instead.
+    fn set_parity(&mut self, parity: Parity) -> Result<(), PortError> {
+        let _ = parity;
+        Err(PortError::unsupported("changing parity on an open port"))
+    }
+
+    /// Change the number of stop bits without reopening the port.
+    ///
+    /// Returns `PortError::Unsupported` where the port must be reopened instead.
+    fn set_stop_bits(&mut self, stop_bits: StopBits) -> Result<(), PortError> {
+        let _ = stop_bits;
+        Err(PortError::unsupported("changing stop bits on an open port"))
+    }
+
+    /// Drive a modem control output line high (`true`) or low (`false`).
+    fn write_signal(&mut self, signal: ControlSignal, level: bool) -> Result<(), PortError> {
+        let _ = level;
+        Err(PortError::config(format!(
+            "{:?} control is not supported by this port",
+            signal
+        )))
+    }
+
+    /// Read the modem status input lines (CTS, DSR, CD) from the device.
+    ///
+    /// Returns `PortError::Unsupported` where the port cannot report them.
+    fn modem_status(&mut self) -> Result<ModemStatus, PortError> {
+        Err(PortError::unsupported("reading modem status lines"))
+    }
+
+    /// Last level written to a control output line.
+    ///
+    /// Output lines cannot be read back from hardware, so this is `None`
+    /// until the line has been set through [`write_signal`](Self::write_signal).
+    fn signal_level(&self, signal: ControlSignal) -> Option<bool> {
+        let _ = signal;
+        None
+    }
+
+    /// Features this port supports.
+    ///
+    /// Defaults to [`PortCapabilities::platform`].
+    fn capabilities(&self) -> PortCapabilities {
+        PortCapabilities::platform()
+    }
 }
 
 #[cfg(test)]
@@ -195,7 +380,10 @@ mod tests {
     #[test]
     fn test_stop_bits_conversion() {
         let stop_bits = StopBits::Two;
-        let serialport_stop_bits: serialport::StopBits = stop_bits.into();
+        let serialport_stop_bits: serialport::StopBits = stop_bits.try_into().unwrap();
         assert_eq!(serialport_stop_bits, serialport::StopBits::Two);
+
+        let err = serialport::StopBits::try_from(StopBits::OnedotFive).unwrap_err();
+        assert!(matches!(err, PortError::Config(_)));
     }
 }
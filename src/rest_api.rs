@@ -2,36 +2,52 @@
 //! REST API surface providing HTTP access to serial port and session tools.
 //! This mirrors (a subset of) the MCP tool surface for environments where
 //! HTTP integration is preferred. Returns JSON responses with a stable shape.
+//! Handlers fail with [`AppError`], whose HTTP status is kept at 200 when
+//! `server.legacy_error_status` is set.
 
 use axum::{
     extract::{Path, Query, State as AxumState},
+    http::StatusCode,
+    response::Response,
     routing::{get, post},
     Json, Router,
 };
 use serde::Deserialize;
 use serde_json::{json, Value};
-use std::{sync::Arc, time::Duration};
+use std::sync::Arc;
 
 use crate::{
-    session::SessionStore,
+    error::{AppError, AppResult},
+    session::{DeviceSignature, SessionStore},
     state::{
-        default_data_bits, default_flow_control, default_parity, default_reconfig_baud,
-        default_stop_bits, default_timeout, AppState, DataBitsCfg, FlowControlCfg, ParityCfg,
-        StopBitsCfg,
+        default_data_bits, default_flow_control, default_parity, default_stop_bits,
+        default_timeout, AppState, ChecksumKind, DataBitsCfg, FlowControlCfg, ParityCfg,
+        PortConfigPatch, ReadEncoding, ReadStrategy, ResponseProbe, StopBitsCfg,
     },
 };
 
 #[cfg(feature = "auto-negotiation")]
-use crate::state::{PortConfig, PortState};
-
-#[cfg(feature = "auto-negotiation")]
-use crate::port::{PortConfiguration, SyncSerialPort};
+use crate::state::PortState;
 
 #[derive(Clone)]
 pub struct RestContext {
     pub state: AppState,
     pub sessions: Arc<SessionStore>,
     pub service: crate::service::PortService,
+    pub port_cache: Arc<crate::port::PortListCache>,
+    /// Loaded configuration, reported by `GET /config` and swapped by `POST /config/reload`
+    pub config: crate::config::SharedConfig,
+    #[cfg(feature = "websocket")]
+    pub ws: crate::websocket::WsSettings,
+    /// Live WebSocket connections, checked against `ws.max_connections`
+    #[cfg(feature = "websocket")]
+    pub ws_connections: Arc<std::sync::atomic::AtomicUsize>,
+    /// Serial readers of those connections, restarted by `POST /admin/reset`
+    #[cfg(feature = "websocket")]
+    pub ws_readers: crate::websocket::WsReaders,
+    /// Negotiator shared by the detect and auto-open routes
+    #[cfg(feature = "auto-negotiation")]
+    pub negotiator: Arc<crate::negotiation::AutoNegotiator>,
 }
 
 // ---------- Serial Port DTOs ----------
@@ -51,8 +67,49 @@ pub struct OpenRequest {
     pub flow_control: FlowControlCfg,
     #[serde(default)]
     pub terminator: Option<String>,
-    #[serde(default)]
+    /// Omit to inherit `serial.default_idle_disconnect_ms`; 0 or null disables
+    #[serde(default, deserialize_with = "null_disables_idle_disconnect")]
     pub idle_disconnect_ms: Option<u64>,
+    #[serde(default)]
+    pub read_idle_gap_ms: Option<u64>,
+    #[serde(default)]
+    pub write_checksum: Option<ChecksumKind>,
+    /// Consume the local echo of each write (RS-485 half-duplex)
+    #[serde(default)]
+    pub half_duplex_echo: bool,
+    /// Block until each write has been transmitted
+    #[serde(default)]
+    pub flush_on_write: bool,
+    /// Infer `terminator` from the line endings of the first reads
+    #[serde(default)]
+    pub auto_detect_terminator: bool,
+    /// Clear the high bit of received bytes, which carries parity with 7 data
+    /// bits; defaults to on for `data_bits: seven`, off otherwise
+    #[serde(default)]
+    pub mask_parity_bit: Option<bool>,
+    /// Remove the shell's echo of the last write from the next read
+    #[serde(default)]
+    pub strip_echo: bool,
+    /// Shell prompt (e.g. "> ") removed from the end of read data
+    #[serde(default)]
+    pub strip_prompt: Option<String>,
+    /// Close the port after this long open, even while busy. Omit to inherit
+    /// `serial.default_max_open_duration_ms`; 0 disables
+    #[serde(default)]
+    pub max_open_duration_ms: Option<u64>,
+    #[serde(default)]
+    pub open_retries: Option<u32>,
+    #[serde(default)]
+    pub open_retry_delay_ms: Option<u64>,
+    /// Probe the device must answer, else the port is closed and open fails
+    #[serde(default)]
+    pub require_response: Option<ResponseProbe>,
+    /// `blocking` (default) or `{"poll": {"interval_ms": 1}}`
+    #[serde(default)]
+    pub read_strategy: ReadStrategy,
+    /// Session to link the port to; it stays linked across reconfigure
+    #[serde(default)]
+    pub session_id: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -61,26 +118,51 @@ pub struct WriteRequest {
 }
 
 #[derive(Deserialize)]
-pub struct ReconfigureRequest {
-    pub port_name: Option<String>,
-    #[serde(default = "default_reconfig_baud")]
-    pub baud_rate: u32,
-    #[serde(default = "default_timeout")]
+pub struct WriteRepeatRequest {
+    pub data: String,
+    pub count: u32,
+    pub delay_ms: Option<u64>,
+}
+
+#[derive(Deserialize)]
+pub struct WaitForPatternRequest {
+    pub pattern: String,
     pub timeout_ms: u64,
-    #[serde(default = "default_data_bits")]
-    pub data_bits: DataBitsCfg,
-    #[serde(default = "default_parity")]
-    pub parity: ParityCfg,
-    #[serde(default = "default_stop_bits")]
-    pub stop_bits: StopBitsCfg,
-    #[serde(default = "default_flow_control")]
-    pub flow_control: FlowControlCfg,
-    #[serde(default)]
-    pub terminator: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ReadLineRequest {
+    #[serde(default = "default_read_line_timeout_ms")]
+    pub overall_timeout_ms: u64,
     #[serde(default)]
-    pub idle_disconnect_ms: Option<u64>,
+    pub idle_timeout_ms: Option<u64>,
+}
+
+fn default_read_line_timeout_ms() -> u64 {
+    1000
+}
+
+#[derive(Deserialize)]
+pub struct LoopbackTestRequest {
+    #[serde(default = "default_loopback_token_len")]
+    pub token_len: usize,
+    #[serde(default = "default_loopback_timeout_ms")]
+    pub timeout_ms: u64,
 }
 
+fn default_loopback_token_len() -> usize {
+    crate::service::DEFAULT_LOOPBACK_TOKEN_LEN
+}
+
+fn default_loopback_timeout_ms() -> u64 {
+    1000
+}
+
+/// Omitted settings keep the open port's values; see [`ReconfigureConfig`]
+///
+/// [`ReconfigureConfig`]: crate::service::ReconfigureConfig
+pub type ReconfigureRequest = crate::service::ReconfigureConfig;
+
 // ---------- Auto-Negotiation DTOs (feature-gated) ----------
 #[cfg(feature = "auto-negotiation")]
 #[derive(Deserialize)]
@@ -98,12 +180,49 @@ pub struct DetectPortRequest {
     pub timeout_ms: u64,
     #[serde(default)]
     pub preferred_strategy: Option<String>,
+    /// Wait after each open before probing (0 = use the manufacturer profile)
+    #[serde(default)]
+    pub settle_delay_ms: u64,
+    /// Reuse the last successful detection of this port instead of probing
+    #[serde(default = "default_use_cache")]
+    pub use_cache: bool,
+    /// Probe again even if a result is cached, replacing it
+    #[serde(default)]
+    pub force: bool,
+}
+#[cfg(feature = "auto-negotiation")]
+fn default_use_cache() -> bool {
+    true
 }
+/// Map an explicit `null` idle-disconnect to `Some(0)` (disabled) so it is not
+/// confused with an omitted field, which inherits the server default.
+fn null_disables_idle_disconnect<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    Ok(Some(Option::<u64>::deserialize(deserializer)?.unwrap_or(0)))
+}
+
 #[cfg(feature = "auto-negotiation")]
 fn default_detect_timeout_ms() -> u64 {
     500
 }
 
+#[cfg(feature = "auto-negotiation")]
+#[derive(Deserialize)]
+pub struct DetectAllRequest {
+    #[serde(default = "default_detect_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Ports probed at the same time
+    #[serde(default = "default_detect_concurrency")]
+    pub concurrency: usize,
+}
+
+#[cfg(feature = "auto-negotiation")]
+fn default_detect_concurrency() -> usize {
+    4
+}
+
 #[cfg(feature = "auto-negotiation")]
 #[derive(Deserialize)]
 pub struct OpenPortAutoRequest {
@@ -118,8 +237,25 @@ pub struct OpenPortAutoRequest {
     pub timeout_ms: u64,
     #[serde(default)]
     pub terminator: Option<String>,
-    #[serde(default)]
+    /// Omit to inherit `serial.default_idle_disconnect_ms`; 0 or null disables
+    #[serde(default, deserialize_with = "null_disables_idle_disconnect")]
     pub idle_disconnect_ms: Option<u64>,
+    #[serde(default)]
+    pub read_idle_gap_ms: Option<u64>,
+    #[serde(default)]
+    pub write_checksum: Option<ChecksumKind>,
+    /// Consume the local echo of each write (RS-485 half-duplex)
+    #[serde(default)]
+    pub half_duplex_echo: bool,
+    /// Block until each write has been transmitted
+    #[serde(default)]
+    pub flush_on_write: bool,
+    /// Reuse the last successful detection of this port instead of probing
+    #[serde(default = "default_use_cache")]
+    pub use_cache: bool,
+    /// Probe again even if a result is cached, replacing it
+    #[serde(default)]
+    pub force: bool,
 }
 
 // ---------- Session DTOs ----------
@@ -127,6 +263,9 @@ pub struct OpenPortAutoRequest {
 pub struct CreateSessionRequest {
     pub device_id: String,
     pub port_name: Option<String>,
+    /// Optional `vid`, `pid`, `device_serial` and `manufacturer`
+    #[serde(flatten)]
+    pub signature: DeviceSignature,
 }
 #[derive(Deserialize)]
 pub struct AppendMessageRequest {
@@ -138,6 +277,23 @@ pub struct AppendMessageRequest {
     pub latency_ms: Option<i64>,
 }
 
+#[derive(Deserialize)]
+pub struct ListPortsParams {
+    #[serde(default)]
+    pub fresh: bool,
+}
+#[derive(Deserialize, Default)]
+pub struct VerifyParams {
+    /// Also report whether the open port's device is still attached
+    #[serde(default)]
+    pub verify: bool,
+}
+#[derive(Deserialize)]
+pub struct ReadParams {
+    pub max_bytes: Option<usize>,
+    #[serde(default)]
+    pub encoding: ReadEncoding,
+}
 #[derive(Deserialize)]
 pub struct ListMessagesParams {
     pub limit: Option<u64>,
@@ -154,14 +310,27 @@ pub struct FilterMessagesParams {
 pub fn build_router(ctx: RestContext) -> Router {
     let mut router = Router::new()
         .route("/health", get(health))
+        .route("/config", get(get_config))
+        .route("/config/reload", post(reload_config))
+        .route("/admin/reset", post(reset_subsystem))
         .route("/ports", get(list_ports))
         .route("/ports/extended", get(list_ports_extended))
         .route("/port/open", post(open_port))
         .route("/port/write", post(write_port))
+        .route("/port/write_repeat", post(write_repeat_port))
+        .route("/port/wait_for_pattern", post(wait_for_pattern_port))
+        .route("/port/loopback_test", post(loopback_test_port))
+        .route("/port/signals", post(set_signals_port))
         .route("/port/read", post(read_port))
+        .route("/port/read_line", post(read_line_port))
         .route("/port/close", post(close_port))
+        .route("/port/cancel_read", post(cancel_read_port))
         .route("/port/status", get(status_port))
+        .route("/port/patch_config", post(patch_config_port))
+        .route("/port/capabilities", get(capabilities_port))
         .route("/port/metrics", get(metrics_port))
+        .route("/port/metrics/history", get(metrics_history_port))
+        .route("/diagnostics/errors", get(recent_errors))
         .route("/port/reconfigure", post(reconfigure_port))
         .route("/sessions", post(create_session))
         .route("/sessions/{id}/messages", get(list_messages))
@@ -182,11 +351,41 @@ pub fn build_router(ctx: RestContext) -> Router {
     {
         router = router
             .route("/port/detect", post(detect_port))
+            .route("/ports/detect_all", post(detect_all_ports))
             .route("/port/open_auto", post(open_port_auto))
             .route("/manufacturers", get(list_manufacturer_profiles));
     }
 
-    router.with_state(ctx)
+    router
+        .layer(axum::middleware::map_response_with_state(
+            ctx.clone(),
+            legacy_error_status,
+        ))
+        .with_state(ctx)
+}
+
+/// Answer [`AppError`] responses with HTTP 200 when
+/// `server.legacy_error_status` is set; the JSON body still marks the failure.
+async fn legacy_error_status(
+    AxumState(ctx): AxumState<RestContext>,
+    mut response: Response,
+) -> Response {
+    let legacy = ctx
+        .config
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .config()
+        .server
+        .legacy_error_status;
+    if legacy
+        && response
+            .extensions()
+            .get::<crate::error::ErrorResponse>()
+            .is_some()
+    {
+        *response.status_mut() = StatusCode::OK;
+    }
+    response
 }
 
 // ---------- Handlers ----------
@@ -194,62 +393,133 @@ async fn health() -> &'static str {
     "ok"
 }
 
-async fn list_ports(AxumState(_ctx): AxumState<RestContext>) -> Json<Value> {
-    match serialport::available_ports() {
-        Ok(ports) => Json(
-            json!({"ports": ports.into_iter().map(|p| json!({"port_name": p.port_name})).collect::<Vec<_>>() }),
-        ),
-        Err(e) => Json(err_json("ListPortsError", &e.to_string())),
+async fn get_config(AxumState(ctx): AxumState<RestContext>) -> Json<Value> {
+    let mut body = ctx
+        .config
+        .read()
+        .unwrap_or_else(std::sync::PoisonError::into_inner)
+        .effective_config();
+    body["status"] = json!("ok");
+    Json(body)
+}
+
+async fn reload_config(AxumState(ctx): AxumState<RestContext>) -> AppResult<Json<Value>> {
+    let mut loader = ctx
+        .config
+        .write()
+        .unwrap_or_else(std::sync::PoisonError::into_inner);
+    let changes = loader
+        .reload_validated()
+        .map_err(|e| AppError::failed("ConfigReloadError", e))?;
+    ctx.service.apply_config(loader.config());
+    let log_level_applied = crate::logging::reload_level(&loader.config().logging);
+    Ok(Json(json!({
+        "status": "ok",
+        "source": loader.source(),
+        "changes": changes,
+        "log_level_applied": log_level_applied,
+    })))
+}
+
+/// Close the port, restart the writer and the WebSocket readers, and clear
+/// the port list and detection caches, keeping sessions. The fresh readers
+/// pick up the next port opened.
+async fn reset_subsystem(AxumState(ctx): AxumState<RestContext>) -> AppResult<Json<Value>> {
+    let service = ctx.service.clone();
+    // Waiting out a busy port blocks, so keep it off the async executor
+    let reset = tokio::task::spawn_blocking(move || service.reset())
+        .await
+        .map_err(|e| AppError::failed("ResetError", e))?;
+    ctx.port_cache.invalidate();
+    let mut body = json!({
+        "status": "ok",
+        "port_closed": reset.closed.is_some(),
+        "port_busy": reset.port_busy,
+        "writer_restarted": reset.writer_restarted,
+        "port_list_cache_cleared": true,
+    });
+    if let Some(metrics) = reset.closed.and_then(|closed| closed.final_metrics) {
+        body["final_metrics"] = json!(metrics);
+    }
+    #[cfg(feature = "websocket")]
+    {
+        body["ws_readers_restarted"] = json!(ctx.ws_readers.restart(&ctx));
+    }
+    #[cfg(feature = "auto-negotiation")]
+    {
+        body["detections_cleared"] = json!(ctx.negotiator.clear_cache());
+    }
+    Ok(Json(body))
+}
+
+async fn list_ports(
+    AxumState(ctx): AxumState<RestContext>,
+    Query(q): Query<ListPortsParams>,
+) -> Json<Value> {
+    let (ports, warning) = ctx.port_cache.list_or_warn(q.fresh);
+    let mut response = json!({
+        "status":"ok",
+        "ports": ports.into_iter().map(|p| json!({"port_name": p.port_name})).collect::<Vec<_>>()
+    });
+    if let Some(w) = warning {
+        response["warning"] = json!(w);
     }
+    Json(response)
 }
 
-async fn list_ports_extended(AxumState(_ctx): AxumState<RestContext>) -> Json<Value> {
+async fn list_ports_extended(
+    AxumState(ctx): AxumState<RestContext>,
+    Query(q): Query<ListPortsParams>,
+) -> Json<Value> {
     use serialport::SerialPortType;
-    match serialport::available_ports() {
-        Ok(ports) => {
-            let detailed: Vec<_> = ports
-                .into_iter()
-                .map(|p| {
-                    let mut obj = serde_json::Map::new();
-                    obj.insert("port_name".into(), json!(p.port_name));
-                    match p.port_type {
-                        SerialPortType::UsbPort(info) => {
-                            obj.insert("transport".into(), json!("usb"));
-                            obj.insert("vid".into(), json!(format!("0x{:04x}", info.vid)));
-                            obj.insert("pid".into(), json!(format!("0x{:04x}", info.pid)));
-                            if let Some(sn) = info.serial_number {
-                                obj.insert("serial_number".into(), json!(sn));
-                            }
-                            if let Some(mf) = info.manufacturer {
-                                obj.insert("manufacturer".into(), json!(mf));
-                            }
-                            if let Some(prod) = info.product {
-                                obj.insert("product".into(), json!(prod));
-                            }
-                        }
-                        SerialPortType::BluetoothPort => {
-                            obj.insert("transport".into(), json!("bluetooth"));
-                        }
-                        SerialPortType::PciPort => {
-                            obj.insert("transport".into(), json!("pci"));
-                        }
-                        SerialPortType::Unknown => {
-                            obj.insert("transport".into(), json!("unknown"));
-                        }
+    let (ports, warning) = ctx.port_cache.list_or_warn(q.fresh);
+    let detailed: Vec<_> = ports
+        .into_iter()
+        .map(|p| {
+            let mut obj = serde_json::Map::new();
+            obj.insert("port_name".into(), json!(p.port_name));
+            match p.port_type {
+                SerialPortType::UsbPort(info) => {
+                    obj.insert("transport".into(), json!("usb"));
+                    obj.insert("vid".into(), json!(format!("0x{:04x}", info.vid)));
+                    obj.insert("pid".into(), json!(format!("0x{:04x}", info.pid)));
+                    if let Some(sn) = info.serial_number {
+                        obj.insert("serial_number".into(), json!(sn));
                     }
-                    json!(obj)
-                })
-                .collect();
-            Json(json!({"ports": detailed}))
-        }
-        Err(e) => Json(err_json("ListPortsError", &e.to_string())),
+                    if let Some(mf) = info.manufacturer {
+                        obj.insert("manufacturer".into(), json!(mf));
+                    }
+                    if let Some(prod) = info.product {
+                        obj.insert("product".into(), json!(prod));
+                    }
+                    if let Some(location) = info.location {
+                        obj.insert("usb_location".into(), json!(location.to_string()));
+                    }
+                }
+                SerialPortType::BluetoothPort => {
+                    obj.insert("transport".into(), json!("bluetooth"));
+                }
+                SerialPortType::PciPort => {
+                    obj.insert("transport".into(), json!("pci"));
+                }
+                SerialPortType::Unknown => {
+                    obj.insert("transport".into(), json!("unknown"));
+                }
+            }
+            json!(obj)
+        })
+        .collect();
+    let mut response = json!({"status":"ok","ports": detailed});
+    if let Some(w) = warning {
+        response["warning"] = json!(w);
     }
+    Json(response)
 }
 
 async fn open_port(
     AxumState(ctx): AxumState<RestContext>,
     Json(req): Json<OpenRequest>,
-) -> Json<Value> {
+) -> AppResult<Json<Value>> {
     use crate::service::OpenConfig;
 
     let config = OpenConfig {
@@ -262,133 +532,354 @@ async fn open_port(
         flow_control: req.flow_control,
         terminator: req.terminator,
         idle_disconnect_ms: req.idle_disconnect_ms,
+        read_idle_gap_ms: req.read_idle_gap_ms,
+        write_checksum: req.write_checksum,
+        half_duplex_echo: req.half_duplex_echo,
+        flush_on_write: req.flush_on_write,
+        auto_detect_terminator: req.auto_detect_terminator,
+        mask_parity_bit: req.mask_parity_bit,
+        strip_echo: req.strip_echo,
+        strip_prompt: req.strip_prompt,
+        max_open_duration_ms: req.max_open_duration_ms,
+        open_retries: req.open_retries,
+        open_retry_delay_ms: req.open_retry_delay_ms,
+        require_response: req.require_response,
+        read_strategy: req.read_strategy,
     };
 
-    match ctx.service.open(config) {
-        Ok(_result) => Json(json!({"status":"ok","message":"opened"})),
-        Err(e) => {
-            let err_type = match e {
-                crate::service::ServiceError::PortAlreadyOpen => "PortAlreadyOpen",
-                _ => "OpenError",
-            };
-            Json(err_json(err_type, &e.to_string()))
+    let service = ctx.service.clone();
+    let session_id = req.session_id.clone();
+    // Open retries back off and require_response waits for a reply, so keep
+    // them off the async executor
+    let result = tokio::task::spawn_blocking(move || -> crate::service::ServiceResult<_> {
+        let result = service.open(config)?;
+        if session_id.is_some() {
+            service.link_session(session_id)?;
         }
+        Ok(result)
+    })
+    .await
+    .map_err(|e| AppError::failed("OpenError", e))?
+    .map_err(|e| AppError::operation("OpenError", e))?;
+    let mut body = json!({
+        "status":"ok",
+        "message":"opened",
+        "open_id": result.open_id,
+        "session_id": req.session_id
+    });
+    if !result.warnings.is_empty() {
+        body["warnings"] = json!(result.warnings);
     }
+    Ok(Json(body))
 }
 
 async fn write_port(
     AxumState(ctx): AxumState<RestContext>,
     Json(req): Json<WriteRequest>,
-) -> Json<Value> {
+) -> AppResult<Json<Value>> {
     match ctx.service.write(&req.data) {
-        Ok(result) => Json(json!({
+        Ok(result) => Ok(Json(json!({
             "status":"ok",
+            "open_id": result.open_id,
+            "payload_bytes": result.payload_bytes,
+            "terminator_bytes": result.terminator_bytes,
             "bytes_written": result.bytes_written,
-            "bytes_written_total": result.bytes_written_total
-        })),
-        Err(e) => {
-            let err_type = match e {
-                crate::service::ServiceError::PortNotOpen => "PortNotOpen",
-                _ => "WriteError",
-            };
-            Json(err_json(err_type, &e.to_string()))
-        }
+            "bytes_written_total": result.bytes_written_total,
+            "checksum": result.checksum,
+            "echo_matched": result.echo_matched,
+            "flush_duration_ms": result.flush_duration_ms,
+            "flow_paused_ms": result.flow_paused_ms
+        }))),
+        Err(e) => Err(AppError::operation("WriteError", e)),
     }
 }
 
-async fn read_port(AxumState(ctx): AxumState<RestContext>) -> Json<Value> {
-    match ctx.service.read() {
-        Ok(result) => {
-            if let Some(auto_close) = result.auto_closed {
-                Json(json!({
-                    "status":"ok",
-                    "event":"auto_close",
-                    "reason": auto_close.reason,
-                    "idle_close_count": auto_close.idle_close_count
-                }))
-            } else {
-                Json(json!({
-                    "status":"ok",
-                    "data": result.data,
-                    "bytes_read": result.bytes_read,
-                    "bytes_read_total": result.bytes_read_total
-                }))
-            }
-        }
-        Err(e) => {
-            let err_type = match e {
-                crate::service::ServiceError::PortNotOpen => "PortNotOpen",
-                _ => "ReadError",
-            };
-            Json(err_json(err_type, &e.to_string()))
-        }
+async fn write_repeat_port(
+    AxumState(ctx): AxumState<RestContext>,
+    Json(req): Json<WriteRepeatRequest>,
+) -> AppResult<Json<Value>> {
+    let service = ctx.service.clone();
+    // The writes and delays block, so keep them off the async executor
+    let result = tokio::task::spawn_blocking(move || {
+        service.write_repeat(&req.data, req.count, req.delay_ms.unwrap_or(0))
+    })
+    .await;
+    match result {
+        Ok(Ok(result)) => Ok(Json(json!({
+            "status":"ok",
+            "count": result.count,
+            "bytes_written": result.bytes_written,
+            "bytes_written_total": result.bytes_written_total,
+            "elapsed_ms": result.elapsed_ms
+        }))),
+        Ok(Err(e)) => Err(AppError::operation("WriteError", e)),
+        Err(e) => Err(AppError::failed("WriteError", e)),
     }
 }
 
-async fn close_port(AxumState(ctx): AxumState<RestContext>) -> Json<Value> {
-    match ctx.service.close() {
-        Ok(result) => Json(json!({"status":"ok","message": result.message})),
-        Err(e) => Json(err_json("CloseError", &e.to_string())),
+async fn read_line_port(
+    AxumState(ctx): AxumState<RestContext>,
+    Json(req): Json<ReadLineRequest>,
+) -> AppResult<Json<Value>> {
+    let service = ctx.service.clone();
+    // Line reads block on serial reads, so keep them off the async executor
+    let result = tokio::task::spawn_blocking(move || {
+        service.read_line(req.overall_timeout_ms, req.idle_timeout_ms)
+    })
+    .await;
+    match result {
+        Ok(Ok(result)) => Ok(Json(json!({
+            "status":"ok",
+            "open_id": result.open_id,
+            "data": result.data,
+            "termination": result.termination,
+            "bytes_read": result.bytes_read,
+            "bytes_read_total": result.bytes_read_total,
+            "framing_errors": result.framing_errors
+        }))),
+        Ok(Err(e)) => Err(AppError::operation("ReadError", e)),
+        Err(e) => Err(AppError::failed("ReadError", e)),
     }
 }
 
-async fn status_port(AxumState(ctx): AxumState<RestContext>) -> Json<Value> {
-    match ctx.service.status() {
-        Ok(status) => {
-            let port_value = serde_json::to_value(&status).unwrap_or(json!({"status":"unknown"}));
-            Json(json!({"status":"ok","port": port_value}))
-        }
-        Err(e) => Json(err_json("StatusError", &e.to_string())),
+async fn wait_for_pattern_port(
+    AxumState(ctx): AxumState<RestContext>,
+    Json(req): Json<WaitForPatternRequest>,
+) -> AppResult<Json<Value>> {
+    let service = ctx.service.clone();
+    // Waiting blocks on serial reads, so keep it off the async executor
+    let result =
+        tokio::task::spawn_blocking(move || service.wait_for_pattern(&req.pattern, req.timeout_ms))
+            .await;
+    match result {
+        Ok(Ok(result)) => Ok(Json(json!({
+            "status":"ok",
+            "matched": result.matched,
+            "timed_out": result.timed_out,
+            "data": result.data,
+            "bytes_read": result.bytes_read,
+            "elapsed_ms": result.elapsed_ms
+        }))),
+        Ok(Err(e)) => Err(AppError::operation("ReadError", e)),
+        Err(e) => Err(AppError::failed("ReadError", e)),
     }
 }
 
-async fn metrics_port(AxumState(ctx): AxumState<RestContext>) -> Json<Value> {
-    match ctx.service.metrics() {
-        Ok(metrics) => {
-            let mut response = json!({"status":"ok","state": metrics.state});
-            if let Some(bytes_read) = metrics.bytes_read_total {
-                response["bytes_read_total"] = json!(bytes_read);
-            }
-            if let Some(bytes_written) = metrics.bytes_written_total {
-                response["bytes_written_total"] = json!(bytes_written);
-            }
-            if let Some(idle_count) = metrics.idle_close_count {
-                response["idle_close_count"] = json!(idle_count);
-            }
-            if let Some(duration) = metrics.open_duration_ms {
-                response["open_duration_ms"] = json!(duration);
-            }
-            if let Some(activity) = metrics.last_activity_ms {
-                response["last_activity_ms"] = json!(activity);
-            }
-            if let Some(streak) = metrics.timeout_streak {
-                response["timeout_streak"] = json!(streak);
-            }
-            Json(response)
-        }
-        Err(e) => Json(err_json("MetricsError", &e.to_string())),
+async fn loopback_test_port(
+    AxumState(ctx): AxumState<RestContext>,
+    Json(req): Json<LoopbackTestRequest>,
+) -> AppResult<Json<Value>> {
+    let service = ctx.service.clone();
+    let result =
+        tokio::task::spawn_blocking(move || service.loopback_test(req.token_len, req.timeout_ms))
+            .await;
+    match result {
+        Ok(Ok(result)) => Ok(Json(json!({
+            "status":"ok",
+            "passed": result.passed,
+            "sent": result.sent,
+            "received": result.received,
+            "latency_ms": result.latency_ms
+        }))),
+        Ok(Err(e)) => Err(AppError::operation("LoopbackError", e)),
+        Err(e) => Err(AppError::failed("LoopbackError", e)),
+    }
+}
+
+async fn set_signals_port(
+    AxumState(ctx): AxumState<RestContext>,
+    Json(req): Json<crate::service::SignalsRequest>,
+) -> AppResult<Json<Value>> {
+    let service = ctx.service.clone();
+    // A hold sleeps on the port, so keep it off the async executor
+    match tokio::task::spawn_blocking(move || service.set_signals(req)).await {
+        Ok(Ok(result)) => Ok(Json(json!({
+            "status":"ok",
+            "dtr": result.dtr,
+            "rts": result.rts,
+            "restored": result.restored
+        }))),
+        Ok(Err(e)) => Err(AppError::operation("SignalError", e)),
+        Err(e) => Err(AppError::failed("SignalError", e)),
+    }
+}
+
+async fn read_port(
+    AxumState(ctx): AxumState<RestContext>,
+    Query(q): Query<ReadParams>,
+) -> AppResult<Json<Value>> {
+    let read = ctx.service.read_encoded(
+        q.max_bytes.unwrap_or(crate::service::DEFAULT_READ_BYTES),
+        q.encoding,
+    );
+    let result = read.map_err(|e| AppError::operation("ReadError", e))?;
+    if let Some(auto_close) = result.auto_closed {
+        Ok(Json(json!({
+            "status":"ok",
+            "open_id": result.open_id,
+            "event":"auto_close",
+            "reason": auto_close.reason,
+            "idle_close_count": auto_close.idle_close_count
+        })))
+    } else {
+        Ok(Json(json!({
+            "status":"ok",
+            "open_id": result.open_id,
+            "data": result.data,
+            "encoding": q.encoding,
+            "bytes_read": result.bytes_read,
+            "bytes_read_total": result.bytes_read_total,
+            "framing_errors": result.framing_errors
+        })))
+    }
+}
+
+async fn cancel_read_port(AxumState(ctx): AxumState<RestContext>) -> Json<Value> {
+    ctx.service.cancel_read();
+    Json(json!({"status":"ok","message":"cancel requested"}))
+}
+
+async fn close_port(AxumState(ctx): AxumState<RestContext>) -> AppResult<Json<Value>> {
+    let result = ctx
+        .service
+        .close()
+        .map_err(|e| AppError::operation("CloseError", e))?;
+    Ok(Json(json!({
+        "status":"ok",
+        "message": result.message,
+        "reason": result.reason,
+        "final_metrics": result.final_metrics
+    })))
+}
+
+/// `physically_present` for the open port when `verify` is set; `None` with
+/// no port open or when presence cannot be determined.
+async fn port_presence(ctx: &RestContext, verify: bool) -> Option<bool> {
+    if !verify {
+        return None;
+    }
+    let Ok(crate::service::StatusResult::Open { config, .. }) = ctx.service.status() else {
+        return None;
+    };
+    let port_cache = ctx.port_cache.clone();
+    // The presence check enumerates ports afresh, so keep it off the async
+    // executor
+    tokio::task::spawn_blocking(move || port_cache.is_present(&config.port_name))
+        .await
+        .ok()
+        .flatten()
+}
+
+async fn status_port(
+    AxumState(ctx): AxumState<RestContext>,
+    Query(q): Query<VerifyParams>,
+) -> AppResult<Json<Value>> {
+    let status = ctx
+        .service
+        .status()
+        .map_err(|e| AppError::operation("StatusError", e))?;
+    let port_value = serde_json::to_value(&status).unwrap_or(json!({"status":"unknown"}));
+    let mut body = json!({"status":"ok","port": port_value});
+    if let Some(present) = port_presence(&ctx, q.verify).await {
+        body["physically_present"] = json!(present);
+    }
+    Ok(Json(body))
+}
+
+async fn patch_config_port(
+    AxumState(ctx): AxumState<RestContext>,
+    Json(patch): Json<PortConfigPatch>,
+) -> AppResult<Json<Value>> {
+    let service = ctx.service.clone();
+    match tokio::task::spawn_blocking(move || service.patch_config(patch)).await {
+        Ok(Ok(result)) => Ok(Json(json!({
+            "status": "ok",
+            "open_id": result.open_id,
+            "reopened": result.reopened,
+            "config": result.config,
+        }))),
+        Ok(Err(e)) => Err(AppError::operation("PatchConfigError", e)),
+        Err(e) => Err(AppError::failed("PatchConfigError", e)),
+    }
+}
+
+async fn capabilities_port(AxumState(ctx): AxumState<RestContext>) -> Json<Value> {
+    let mut body = serde_json::to_value(ctx.service.capabilities()).unwrap_or(json!({}));
+    body["status"] = json!("ok");
+    Json(body)
+}
+
+async fn metrics_history_port(AxumState(ctx): AxumState<RestContext>) -> Json<Value> {
+    let samples = ctx.service.metrics_history();
+    Json(json!({"status": "ok", "count": samples.len(), "samples": samples}))
+}
+
+async fn recent_errors(AxumState(ctx): AxumState<RestContext>) -> Json<Value> {
+    let errors = ctx.service.recent_errors();
+    Json(json!({"status": "ok", "count": errors.len(), "errors": errors}))
+}
+
+async fn metrics_port(
+    AxumState(ctx): AxumState<RestContext>,
+    Query(q): Query<VerifyParams>,
+) -> AppResult<Json<Value>> {
+    let metrics = ctx
+        .service
+        .metrics()
+        .map_err(|e| AppError::operation("MetricsError", e))?;
+    let mut response = json!({"status":"ok","state": metrics.state});
+    if let Some(present) = port_presence(&ctx, q.verify).await {
+        response["physically_present"] = json!(present);
+    }
+    if let Some(bytes_read) = metrics.bytes_read_total {
+        response["bytes_read_total"] = json!(bytes_read);
+    }
+    if let Some(bytes_written) = metrics.bytes_written_total {
+        response["bytes_written_total"] = json!(bytes_written);
+    }
+    if let Some(idle_count) = metrics.idle_close_count {
+        response["idle_close_count"] = json!(idle_count);
+    }
+    if let Some(duration) = metrics.open_duration_ms {
+        response["open_duration_ms"] = json!(duration);
+    }
+    if let Some(activity) = metrics.last_activity_ms {
+        response["last_activity_ms"] = json!(activity);
+    }
+    if let Some(started) = metrics.open_started_at {
+        response["open_started_at"] = json!(started);
+    }
+    if let Some(activity) = metrics.last_activity_at {
+        response["last_activity_at"] = json!(activity);
+    }
+    if let Some(streak) = metrics.timeout_streak {
+        response["timeout_streak"] = json!(streak);
+    }
+    if let Some(pending) = metrics.pending_frame_bytes {
+        response["pending_frame_bytes"] = json!(pending);
     }
+    Ok(Json(response))
 }
 
 // ---------- Session Handlers ----------
 async fn create_session(
     AxumState(ctx): AxumState<RestContext>,
     Json(req): Json<CreateSessionRequest>,
-) -> Json<Value> {
+) -> AppResult<Json<Value>> {
     match ctx
         .sessions
-        .create_session(&req.device_id, req.port_name.as_deref())
+        .create_session_with_signature(&req.device_id, req.port_name.as_deref(), req.signature)
         .await
     {
-        Ok(s) => Json(json!({"status":"ok","session":s})),
-        Err(e) => Json(err_json("CreateSessionError", &e.to_string())),
+        Ok(s) => Ok(Json(json!({"status":"ok","session":s}))),
+        Err(e) => Err(AppError::failed("CreateSessionError", e)),
     }
 }
 
 async fn append_message(
     AxumState(ctx): AxumState<RestContext>,
     Json(req): Json<AppendMessageRequest>,
-) -> Json<Value> {
+) -> AppResult<Json<Value>> {
     match ctx
         .sessions
         .append_message(
@@ -401,8 +892,8 @@ async fn append_message(
         )
         .await
     {
-        Ok((id, ts)) => Json(json!({"status":"ok","message_id":id,"created_at":ts})),
-        Err(e) => Json(err_json("AppendMessageError", &e.to_string())),
+        Ok((id, ts)) => Ok(Json(json!({"status":"ok","message_id":id,"created_at":ts}))),
+        Err(e) => Err(AppError::failed("AppendMessageError", e)),
     }
 }
 
@@ -410,42 +901,42 @@ async fn list_messages(
     Path(id): Path<String>,
     AxumState(ctx): AxumState<RestContext>,
     Query(q): Query<ListMessagesParams>,
-) -> Json<Value> {
+) -> AppResult<Json<Value>> {
     let limit = q.limit.unwrap_or(100) as i64;
     match ctx.sessions.list_messages(&id, limit).await {
-        Ok(msgs) => Json(json!({"status":"ok","messages":msgs})),
-        Err(e) => Json(err_json("ListMessagesError", &e.to_string())),
+        Ok(msgs) => Ok(Json(json!({"status":"ok","messages":msgs}))),
+        Err(e) => Err(AppError::failed("ListMessagesError", e)),
     }
 }
 
 async fn export_session(
     Path(id): Path<String>,
     AxumState(ctx): AxumState<RestContext>,
-) -> Json<Value> {
+) -> AppResult<Json<Value>> {
     match ctx.sessions.export_session_json(&id).await {
-        Ok(v) => Json(json!({"status":"ok","export":v})),
-        Err(e) => Json(err_json("ExportSessionError", &e.to_string())),
+        Ok(v) => Ok(Json(json!({"status":"ok","export":v}))),
+        Err(e) => Err(AppError::failed("ExportSessionError", e)),
     }
 }
 
 async fn feature_index(
     Path(id): Path<String>,
     AxumState(ctx): AxumState<RestContext>,
-) -> Json<Value> {
+) -> AppResult<Json<Value>> {
     match ctx.sessions.export_features_index(&id).await {
-        Ok(idx) => Json(json!({"status":"ok","index":idx})),
-        Err(e) => Json(err_json("FeatureIndexError", &e.to_string())),
+        Ok(idx) => Ok(Json(json!({"status":"ok","index":idx}))),
+        Err(e) => Err(AppError::failed("FeatureIndexError", e)),
     }
 }
 
 async fn session_stats(
     Path(id): Path<String>,
     AxumState(ctx): AxumState<RestContext>,
-) -> Json<Value> {
+) -> AppResult<Json<Value>> {
     match ctx.sessions.session_stats(&id).await {
-        Ok(Some(stats)) => Json(json!({"status":"ok","stats":stats})),
-        Ok(None) => Json(json!({"status":"ok","stats":null})),
-        Err(e) => Json(err_json("SessionStatsError", &e.to_string())),
+        Ok(Some(stats)) => Ok(Json(json!({"status":"ok","stats":stats}))),
+        Ok(None) => Ok(Json(json!({"status":"ok","stats":null}))),
+        Err(e) => Err(AppError::failed("SessionStatsError", e)),
     }
 }
 
@@ -453,7 +944,7 @@ async fn filter_messages(
     Path(id): Path<String>,
     AxumState(ctx): AxumState<RestContext>,
     Query(params): Query<FilterMessagesParams>,
-) -> Json<Value> {
+) -> AppResult<Json<Value>> {
     let limit = params.limit.unwrap_or(100) as i64;
     match ctx
         .sessions
@@ -466,8 +957,8 @@ async fn filter_messages(
         )
         .await
     {
-        Ok(msgs) => Json(json!({"status":"ok","messages":msgs})),
-        Err(e) => Json(err_json("FilterMessagesError", &e.to_string())),
+        Ok(msgs) => Ok(Json(json!({"status":"ok","messages":msgs}))),
+        Err(e) => Err(AppError::failed("FilterMessagesError", e)),
     }
 }
 
@@ -475,48 +966,51 @@ async fn filter_messages(
 async fn reconfigure_port(
     AxumState(ctx): AxumState<RestContext>,
     Json(req): Json<ReconfigureRequest>,
-) -> Json<Value> {
-    use crate::service::ReconfigureConfig;
-
-    let config = ReconfigureConfig {
-        port_name: req.port_name,
-        baud_rate: req.baud_rate,
-        timeout_ms: req.timeout_ms,
-        data_bits: req.data_bits,
-        parity: req.parity,
-        stop_bits: req.stop_bits,
-        flow_control: req.flow_control,
-        terminator: req.terminator,
-        idle_disconnect_ms: req.idle_disconnect_ms,
-    };
-
-    match ctx.service.reconfigure(config) {
-        Ok(result) => Json(json!({
-            "status": "ok",
-            "message": result.message,
-            "port_name": result.port_name,
-            "baud_rate": result.baud_rate
-        })),
-        Err(e) => {
-            let err_type = match e {
-                crate::service::ServiceError::NoPortSpecified => "InvalidPayload",
-                _ => "ReconfigureError",
-            };
-            Json(err_json(err_type, &e.to_string()))
+) -> AppResult<Json<Value>> {
+    let service = ctx.service.clone();
+    // Closing and reopening blocks, so keep it off the async executor
+    let result = tokio::task::spawn_blocking(move || service.reconfigure(req))
+        .await
+        .map_err(|e| AppError::failed("ReconfigureError", e))?
+        .map_err(|e| AppError::operation("ReconfigureError", e))?;
+    if let Some(sid) = &result.active_session {
+        let note = format!(
+            "reconfigured {}: {}",
+            result.port_name,
+            result.config.summary()
+        );
+        if let Err(e) = ctx
+            .sessions
+            .append_message(sid, "system", None, &note, None, None)
+            .await
+        {
+            tracing::warn!(session_id = %sid, error = %e, "failed to record reconfigure in session");
         }
     }
+    let mut body = json!({
+        "status": "ok",
+        "message": result.message,
+        "port_name": result.port_name,
+        "baud_rate": result.baud_rate,
+        "session_id": result.active_session
+    });
+    if !result.warnings.is_empty() {
+        body["warnings"] = json!(result.warnings);
+    }
+    Ok(Json(body))
 }
 
 // ---------- Auto-Negotiation Handlers (feature-gated) ----------
 #[cfg(feature = "auto-negotiation")]
 async fn detect_port(
-    AxumState(_ctx): AxumState<RestContext>,
+    AxumState(ctx): AxumState<RestContext>,
     Json(req): Json<DetectPortRequest>,
-) -> Json<Value> {
-    use crate::negotiation::{AutoNegotiator, NegotiationHints};
+) -> AppResult<Json<Value>> {
+    use crate::negotiation::NegotiationHints;
 
     let mut hints = NegotiationHints {
         timeout_ms: req.timeout_ms,
+        settle_delay_ms: req.settle_delay_ms,
         ..Default::default()
     };
 
@@ -524,13 +1018,13 @@ async fn detect_port(
     if let Some(vid_str) = &req.vid {
         match u16::from_str_radix(vid_str.trim_start_matches("0x"), 16) {
             Ok(vid) => hints.vid = Some(vid),
-            Err(e) => return Json(err_json("InvalidVID", &e.to_string())),
+            Err(e) => return Err(AppError::rejected("InvalidVID", e)),
         }
     }
     if let Some(pid_str) = &req.pid {
         match u16::from_str_radix(pid_str.trim_start_matches("0x"), 16) {
             Ok(pid) => hints.pid = Some(pid),
-            Err(e) => return Json(err_json("InvalidPID", &e.to_string())),
+            Err(e) => return Err(AppError::rejected("InvalidPID", e)),
         }
     }
 
@@ -539,43 +1033,107 @@ async fn detect_port(
         hints.suggested_baud_rates = rates;
     }
 
-    let negotiator = AutoNegotiator::new();
-    let params = if let Some(strategy) = &req.preferred_strategy {
-        negotiator
-            .detect_with_preference(&req.port_name, Some(hints), strategy)
-            .await
-    } else {
-        negotiator.detect(&req.port_name, Some(hints)).await
-    };
+    let params = ctx
+        .negotiator
+        .detect_cached(
+            &req.port_name,
+            Some(hints),
+            req.preferred_strategy.as_deref(),
+            req.use_cache,
+            req.force,
+        )
+        .await;
 
     match params {
-        Ok(p) => Json(json!({
-            "status": "ok",
-            "port_name": req.port_name,
-            "baud_rate": p.baud_rate,
-            "data_bits": format!("{:?}", p.data_bits).to_lowercase(),
-            "parity": format!("{:?}", p.parity).to_lowercase(),
-            "stop_bits": format!("{:?}", p.stop_bits).to_lowercase(),
-            "flow_control": format!("{:?}", p.flow_control).to_lowercase(),
-            "strategy_used": p.strategy_used,
-            "confidence": p.confidence
-        })),
-        Err(e) => Json(err_json("DetectionFailed", &e.to_string())),
+        Ok((p, from_cache)) => {
+            let mut body = negotiated_params_json(&p);
+            body["status"] = json!("ok");
+            body["port_name"] = json!(req.port_name);
+            body["from_cache"] = json!(from_cache);
+            Ok(Json(body))
+        }
+        Err(e) => Err(AppError::failed("DetectionFailed", e)),
+    }
+}
+
+/// JSON fields describing negotiated parameters, shared by the detect routes.
+#[cfg(feature = "auto-negotiation")]
+fn negotiated_params_json(p: &crate::negotiation::NegotiatedParams) -> Value {
+    let mut body = crate::state::line_settings_json(
+        p.data_bits.into(),
+        p.parity.into(),
+        p.stop_bits.into(),
+        p.flow_control.into(),
+    );
+    body.insert("baud_rate".into(), json!(p.baud_rate));
+    body.insert("strategy_used".into(), json!(p.strategy_used));
+    body.insert("confidence".into(), json!(p.confidence));
+    Value::Object(body)
+}
+
+/// Run auto-negotiation against every enumerated port.
+///
+/// The currently open port is reported as an error rather than probed.
+#[cfg(feature = "auto-negotiation")]
+async fn detect_all_ports(
+    AxumState(ctx): AxumState<RestContext>,
+    Json(req): Json<DetectAllRequest>,
+) -> Json<Value> {
+    use crate::negotiation::NegotiationHints;
+    use serialport::SerialPortType;
+
+    let open_port = match &*crate::state::lock_state(&ctx.state) {
+        PortState::Open { config, .. } => Some(config.port_name.clone()),
+        PortState::Closed => None,
+    };
+    let (ports, warning) = ctx.port_cache.list_or_warn(false);
+
+    let mut results = serde_json::Map::new();
+    let mut probes = Vec::new();
+    for p in ports {
+        if open_port.as_deref() == Some(p.port_name.as_str()) {
+            results.insert(p.port_name, json!({"error": "port is currently open"}));
+            continue;
+        }
+        let mut hints = NegotiationHints {
+            timeout_ms: req.timeout_ms,
+            ..Default::default()
+        };
+        if let SerialPortType::UsbPort(usb) = &p.port_type {
+            hints.vid = Some(usb.vid);
+            hints.pid = Some(usb.pid);
+            hints.manufacturer = usb.manufacturer.clone();
+        }
+        probes.push((p.port_name, Some(hints)));
+    }
+
+    for (port_name, result) in ctx.negotiator.detect_all(probes, req.concurrency).await {
+        let entry = match result {
+            Ok(p) => negotiated_params_json(&p),
+            Err(e) => json!({"error": e.to_string()}),
+        };
+        results.insert(port_name, entry);
+    }
+
+    let mut response = json!({"status": "ok", "results": results});
+    if let Some(w) = warning {
+        response["warning"] = json!(w);
     }
+    Json(response)
 }
 
 #[cfg(feature = "auto-negotiation")]
 async fn open_port_auto(
     AxumState(ctx): AxumState<RestContext>,
     Json(req): Json<OpenPortAutoRequest>,
-) -> Json<Value> {
-    use crate::negotiation::{AutoNegotiator, NegotiationHints};
+) -> AppResult<Json<Value>> {
+    use crate::negotiation::NegotiationHints;
 
     // Check if port is already open
     {
-        let st = ctx.state.lock().unwrap();
+        let st = crate::state::lock_state(&ctx.state);
         if matches!(&*st, PortState::Open { .. }) {
-            return Json(err_json("PortAlreadyOpen", "Port already open"));
+            return Err(AppError::PortAlreadyOpen);
         }
     }
 
@@ -588,83 +1146,72 @@ async fn open_port_auto(
     if let Some(vid_str) = &req.vid {
         match u16::from_str_radix(vid_str.trim_start_matches("0x"), 16) {
             Ok(vid) => hints.vid = Some(vid),
-            Err(e) => return Json(err_json("InvalidVID", &e.to_string())),
+            Err(e) => return Err(AppError::rejected("InvalidVID", e)),
         }
     }
     if let Some(pid_str) = &req.pid {
         match u16::from_str_radix(pid_str.trim_start_matches("0x"), 16) {
             Ok(pid) => hints.pid = Some(pid),
-            Err(e) => return Json(err_json("InvalidPID", &e.to_string())),
+            Err(e) => return Err(AppError::rejected("InvalidPID", e)),
         }
     }
     hints.manufacturer = req.manufacturer.clone();
 
-    // Auto-detect parameters
-    let negotiator = AutoNegotiator::new();
-    let params = match negotiator.detect(&req.port_name, Some(hints)).await {
-        Ok(p) => p,
-        Err(e) => return Json(err_json("DetectionFailed", &e.to_string())),
+    // Auto-detect parameters, reusing an earlier detection of this port
+    // unless the caller opts out
+    let (params, from_cache) = match ctx
+        .negotiator
+        .detect_cached(&req.port_name, Some(hints), None, req.use_cache, req.force)
+        .await
+    {
+        Ok(detected) => detected,
+        Err(e) => return Err(AppError::failed("DetectionFailed", e)),
     };
 
     // Open the port with detected parameters
-    let config = PortConfiguration {
+    let config = crate::service::OpenConfig {
+        port_name: req.port_name.clone(),
         baud_rate: params.baud_rate,
-        timeout: Duration::from_millis(req.timeout_ms),
-        data_bits: params.data_bits,
-        parity: params.parity,
-        stop_bits: params.stop_bits,
-        flow_control: params.flow_control,
+        timeout_ms: req.timeout_ms,
+        data_bits: params.data_bits.into(),
+        parity: params.parity.into(),
+        stop_bits: params.stop_bits.into(),
+        flow_control: params.flow_control.into(),
+        // An explicit terminator wins over the one learned during detection
+        terminator: req.terminator.or_else(|| params.terminator.clone()),
+        idle_disconnect_ms: req.idle_disconnect_ms,
+        read_idle_gap_ms: req.read_idle_gap_ms,
+        write_checksum: req.write_checksum,
+        half_duplex_echo: req.half_duplex_echo,
+        flush_on_write: req.flush_on_write,
+        auto_detect_terminator: false,
+        mask_parity_bit: None,
+        strip_echo: false,
+        strip_prompt: None,
+        max_open_duration_ms: None,
+        open_retries: None,
+        open_retry_delay_ms: None,
+        require_response: None,
+        read_strategy: Default::default(),
     };
 
-    match SyncSerialPort::open(&req.port_name, config) {
-        Ok(port) => {
-            let mut st = ctx.state.lock().unwrap();
-            *st = PortState::Open {
-                port: Box::new(port),
-                config: PortConfig {
-                    port_name: req.port_name.clone(),
-                    baud_rate: params.baud_rate,
-                    timeout_ms: req.timeout_ms,
-                    data_bits: match params.data_bits {
-                        crate::port::DataBits::Five => DataBitsCfg::Five,
-                        crate::port::DataBits::Six => DataBitsCfg::Six,
-                        crate::port::DataBits::Seven => DataBitsCfg::Seven,
-                        crate::port::DataBits::Eight => DataBitsCfg::Eight,
-                    },
-                    parity: match params.parity {
-                        crate::port::Parity::None => ParityCfg::None,
-                        crate::port::Parity::Odd => ParityCfg::Odd,
-                        crate::port::Parity::Even => ParityCfg::Even,
-                    },
-                    stop_bits: match params.stop_bits {
-                        crate::port::StopBits::One => StopBitsCfg::One,
-                        crate::port::StopBits::Two => StopBitsCfg::Two,
-                    },
-                    flow_control: match params.flow_control {
-                        crate::port::FlowControl::None => FlowControlCfg::None,
-                        crate::port::FlowControl::Hardware => FlowControlCfg::Hardware,
-                        crate::port::FlowControl::Software => FlowControlCfg::Software,
-                    },
-                    terminator: req.terminator,
-                    idle_disconnect_ms: req.idle_disconnect_ms,
-                },
-                last_activity: std::time::Instant::now(),
-                timeout_streak: 0,
-                bytes_read_total: 0,
-                bytes_written_total: 0,
-                idle_close_count: 0,
-                open_started: std::time::Instant::now(),
-            };
-            Json(json!({
-                "status": "ok",
-                "message": "opened (auto-detected)",
-                "port_name": req.port_name,
-                "baud_rate": params.baud_rate,
-                "strategy_used": params.strategy_used,
-                "confidence": params.confidence
-            }))
-        }
-        Err(e) => Json(err_json("OpenError", &e.to_string())),
+    let service = ctx.service.clone();
+    // Keep the open's retries off the async executor
+    let opened = tokio::task::spawn_blocking(move || service.open(config))
+        .await
+        .map_err(|e| AppError::failed("OpenError", e))?;
+    match opened {
+        Ok(opened) => Ok(Json(json!({
+            "status": "ok",
+            "message": "opened (auto-detected)",
+            "port_name": req.port_name,
+            "baud_rate": params.baud_rate,
+            "terminator": opened.config.terminator,
+            "strategy_used": params.strategy_used,
+            "confidence": params.confidence,
+            "from_cache": from_cache
+        }))),
+        Err(e) => Err(AppError::operation("OpenError", e)),
     }
 }
 
@@ -681,6 +1228,7 @@ async fn list_manufacturer_profiles(AxumState(_ctx): AxumState<RestContext>) ->
                 "name": p.name,
                 "default_baud": p.default_baud,
                 "common_bauds": p.common_bauds,
+                "settle_delay_ms": p.settle_delay_ms,
             })
         })
         .collect();
@@ -692,7 +1240,698 @@ async fn list_manufacturer_profiles(AxumState(_ctx): AxumState<RestContext>) ->
     }))
 }
 
-// ---------- Helpers ----------
-fn err_json(kind: &str, msg: &str) -> Value {
-    json!({"status":"error","error":{"type":kind,"message":msg}})
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::PortListCache;
+    use crate::state::PortState;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    async fn context_with_cache(port_cache: PortListCache) -> RestContext {
+        let state: AppState = Arc::new(Mutex::new(PortState::Closed));
+        RestContext {
+            state: state.clone(),
+            sessions: Arc::new(SessionStore::new("sqlite::memory:").await.unwrap()),
+            service: crate::service::PortService::new(state),
+            port_cache: Arc::new(port_cache),
+            config: Arc::new(std::sync::RwLock::new(
+                crate::config::ConfigLoader::with_defaults(),
+            )),
+            #[cfg(feature = "websocket")]
+            ws: Default::default(),
+            #[cfg(feature = "websocket")]
+            ws_connections: Default::default(),
+            #[cfg(feature = "websocket")]
+            ws_readers: Default::default(),
+            #[cfg(feature = "auto-negotiation")]
+            negotiator: Default::default(),
+        }
+    }
+
+    /// A context whose service opens mock ports.
+    async fn mock_context() -> RestContext {
+        let mut ctx = context_with_cache(PortListCache::default()).await;
+        ctx.service = ctx
+            .service
+            .clone()
+            .with_port_opener(Arc::new(|name, _config| {
+                Ok(Box::new(crate::port::MockSerialPort::new(name)))
+            }));
+        ctx
+    }
+
+    #[tokio::test]
+    async fn test_error_responses_carry_http_status() {
+        use crate::service::ServiceError;
+        use axum::response::IntoResponse;
+
+        async fn respond(ctx: &RestContext, response: Response) -> (StatusCode, Value) {
+            let response = legacy_error_status(AxumState(ctx.clone()), response).await;
+            let status = response.status();
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+                .await
+                .unwrap();
+            (
+                status,
+                serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+            )
+        }
+
+        let ctx = context_with_cache(PortListCache::default()).await;
+        let write = WriteRequest {
+            data: "PING".to_string(),
+        };
+        let not_open = write_port(AxumState(ctx.clone()), Json(write)).await;
+        let (status, body) = respond(&ctx, not_open.into_response()).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        assert_eq!(body["error"]["type"], "PortNotOpen");
+
+        for (error, status, kind) in [
+            (
+                AppError::operation("OpenError", ServiceError::DeviceNotFound("MOCK9".into())),
+                StatusCode::NOT_FOUND,
+                "DeviceNotFound",
+            ),
+            (
+                AppError::operation("OpenError", ServiceError::PermissionDenied("MOCK9".into())),
+                StatusCode::FORBIDDEN,
+                "PermissionDenied",
+            ),
+            (
+                AppError::operation("ReadError", ServiceError::InvalidConfig("bad".into())),
+                StatusCode::BAD_REQUEST,
+                "InvalidConfig",
+            ),
+            (
+                AppError::operation(
+                    "WriteError",
+                    ServiceError::Io {
+                        kind: std::io::ErrorKind::BrokenPipe,
+                        message: "gone".into(),
+                    },
+                ),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "WriteError",
+            ),
+            (
+                AppError::rejected("InvalidVID", "not hex"),
+                StatusCode::BAD_REQUEST,
+                "InvalidVID",
+            ),
+            (
+                AppError::failed("CreateSessionError", "db locked"),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "CreateSessionError",
+            ),
+        ] {
+            let (got, body) = respond(&ctx, error.into_response()).await;
+            assert_eq!(got, status, "{kind}");
+            assert_eq!(body["error"]["type"], kind);
+        }
+
+        let timeout = AppError::operation(
+            "WriteError",
+            ServiceError::WriteTimeout {
+                written: 3,
+                expected: 5,
+                flow_paused: true,
+            },
+        );
+        let (status, body) = respond(&ctx, timeout.into_response()).await;
+        assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(body["error"]["type"], "WriteTimeout");
+        assert_eq!(body["bytes_written"], 3);
+        assert_eq!(body["flow_paused"], true);
+
+        ctx.config
+            .write()
+            .unwrap()
+            .config_mut()
+            .server
+            .legacy_error_status = true;
+        let (status, body) = respond(&ctx, AppError::PortNotOpen.into_response()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["status"], "error");
+        // Only error responses built from AppError are rewritten
+        let busy = (StatusCode::SERVICE_UNAVAILABLE, "busy").into_response();
+        assert_eq!(respond(&ctx, busy).await.0, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_list_ports_degrades_when_enumeration_fails() {
+        let cache = PortListCache::with_enumerator(
+            Duration::from_secs(1),
+            Arc::new(|| {
+                Err(serialport::Error::new(
+                    serialport::ErrorKind::Unknown,
+                    "enumeration unsupported",
+                ))
+            }),
+        );
+        let ctx = context_with_cache(cache).await;
+
+        let Json(body) = list_ports(
+            AxumState(ctx.clone()),
+            Query(ListPortsParams { fresh: false }),
+        )
+        .await;
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["ports"], json!([]));
+        assert!(body["warning"]
+            .as_str()
+            .unwrap()
+            .contains("enumeration unsupported"));
+
+        let Json(body) =
+            list_ports_extended(AxumState(ctx), Query(ListPortsParams { fresh: true })).await;
+        assert_eq!(body["status"], "ok");
+        assert_eq!(body["ports"], json!([]));
+        assert!(body.get("warning").is_some());
+    }
+
+    #[cfg(feature = "mcp")]
+    #[tokio::test]
+    async fn test_status_payload_matches_mcp() {
+        // Field names with leaves replaced by their JSON type
+        fn shape(value: &Value) -> Value {
+            match value {
+                Value::Object(map) => Value::Object(
+                    map.iter()
+                        .map(|(key, value)| (key.clone(), shape(value)))
+                        .collect(),
+                ),
+                Value::Array(items) => Value::Array(items.iter().map(shape).collect()),
+                Value::Null => json!("null"),
+                Value::Bool(_) => json!("bool"),
+                Value::Number(_) => json!("number"),
+                Value::String(_) => json!("string"),
+            }
+        }
+
+        let ctx = mock_context().await;
+        ctx.service
+            .open(
+                serde_json::from_value(json!({"port_name": "MOCK0", "terminator": "\n"})).unwrap(),
+            )
+            .unwrap();
+        let handler = crate::mcp::test_handler(ctx.state.clone(), ctx.service.clone()).await;
+
+        let Json(rest) = status_port(AxumState(ctx), Query(VerifyParams::default()))
+            .await
+            .unwrap();
+        let mcp = handler
+            .status_impl(false)
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        assert_eq!(rest["port"]["state"], "Open");
+        assert_eq!(shape(&rest["port"]), shape(&mcp["status"]));
+    }
+
+    #[tokio::test]
+    async fn test_verify_reports_unplugged_port_without_closing() {
+        use serialport::{SerialPortInfo, SerialPortType};
+        use std::sync::atomic::{AtomicBool, Ordering};
+
+        const PORT: &str = "/dev/serial-mcp-test-unplugged";
+        let attached = Arc::new(AtomicBool::new(true));
+        let listed = attached.clone();
+        let cache = PortListCache::with_enumerator(
+            Duration::ZERO,
+            Arc::new(move || {
+                let mut ports = vec![SerialPortInfo {
+                    port_name: "/dev/ttyS0".to_string(),
+                    port_type: SerialPortType::Unknown,
+                }];
+                if listed.load(Ordering::SeqCst) {
+                    ports.push(SerialPortInfo {
+                        port_name: PORT.to_string(),
+                        port_type: SerialPortType::Unknown,
+                    });
+                }
+                Ok(ports)
+            }),
+        );
+        let mut ctx = mock_context().await;
+        ctx.port_cache = Arc::new(cache);
+        ctx.service
+            .open(serde_json::from_value(json!({"port_name": PORT})).unwrap())
+            .unwrap();
+        let verify = || Query(VerifyParams { verify: true });
+
+        let Json(body) = status_port(AxumState(ctx.clone()), verify()).await.unwrap();
+        assert_eq!(body["physically_present"], true);
+        let Json(body) = status_port(AxumState(ctx.clone()), Query(VerifyParams::default()))
+            .await
+            .unwrap();
+        assert!(body.get("physically_present").is_none());
+
+        attached.store(false, Ordering::SeqCst);
+        let Json(body) = status_port(AxumState(ctx.clone()), verify()).await.unwrap();
+        assert_eq!(body["physically_present"], false);
+        assert_eq!(body["port"]["state"], "Open");
+        let Json(body) = metrics_port(AxumState(ctx.clone()), verify())
+            .await
+            .unwrap();
+        assert_eq!(body["physically_present"], false);
+        assert!(matches!(
+            ctx.service.status().unwrap(),
+            crate::service::StatusResult::Open { .. }
+        ));
+    }
+
+    #[cfg(feature = "mcp")]
+    #[tokio::test]
+    async fn test_line_settings_are_canonical_in_both_apis() {
+        let ctx = mock_context().await;
+        ctx.service
+            .open(
+                serde_json::from_value(json!({
+                    "port_name": "MOCK0",
+                    "data_bits": "seven",
+                    "parity": "even",
+                    "stop_bits": "two",
+                    "flow_control": "hardware",
+                }))
+                .unwrap(),
+            )
+            .unwrap();
+        let handler = crate::mcp::test_handler(ctx.state.clone(), ctx.service.clone()).await;
+
+        let Json(rest) = status_port(AxumState(ctx), Query(VerifyParams::default()))
+            .await
+            .unwrap();
+        let mcp = handler
+            .status_impl(false)
+            .await
+            .unwrap()
+            .structured_content
+            .unwrap();
+        let expected = json!({
+            "data_bits": 7,
+            "parity": "even",
+            "stop_bits": 2,
+            "flow_control": "hardware",
+        });
+        for config in [&rest["port"]["config"], &mcp["status"]["config"]] {
+            for (key, value) in expected.as_object().unwrap() {
+                assert_eq!(&config[key], value, "{key} in {config}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_ports_extended_reports_usb_location() {
+        use serialport::{SerialPortInfo, SerialPortType, UsbPortInfo};
+
+        let cache = PortListCache::with_enumerator(
+            std::time::Duration::from_secs(60),
+            Arc::new(|| {
+                let usb = |name: &str, chain: Vec<u8>| SerialPortInfo {
+                    port_name: name.to_string(),
+                    port_type: SerialPortType::UsbPort(UsbPortInfo {
+                        vid: 0x0403,
+                        pid: 0x6001,
+                        serial_number: None,
+                        manufacturer: None,
+                        product: None,
+                        location: Some(serialport::Location::new("1".to_string(), chain)),
+                    }),
+                };
+                Ok(vec![
+                    usb("ttyUSB0", vec![1, 2]),
+                    usb("ttyUSB1", vec![1, 3]),
+                    SerialPortInfo {
+                        port_name: "ttyS0".to_string(),
+                        port_type: SerialPortType::PciPort,
+                    },
+                ])
+            }),
+        );
+        let ctx = context_with_cache(cache).await;
+
+        let Json(body) =
+            list_ports_extended(AxumState(ctx), Query(ListPortsParams { fresh: false })).await;
+        let ports = body["ports"].as_array().unwrap();
+        assert_eq!(ports[0]["usb_location"], "1-1.2");
+        assert_eq!(ports[1]["usb_location"], "1-1.3");
+        assert_eq!(ports[0]["vid"], ports[1]["vid"]);
+        assert_eq!(ports[2]["transport"], "pci");
+        assert!(ports[2].get("usb_location").is_none());
+    }
+
+    #[test]
+    fn test_open_request_null_idle_disconnect_disables() {
+        let parse = |body: Value| {
+            serde_json::from_value::<OpenRequest>(body)
+                .unwrap()
+                .idle_disconnect_ms
+        };
+        let base = json!({"port_name": "MOCK0", "baud_rate": 9600});
+        assert_eq!(parse(base.clone()), None);
+
+        let mut explicit_null = base.clone();
+        explicit_null["idle_disconnect_ms"] = Value::Null;
+        assert_eq!(parse(explicit_null), Some(0));
+
+        let mut set = base;
+        set["idle_disconnect_ms"] = json!(250);
+        assert_eq!(parse(set), Some(250));
+    }
+
+    #[tokio::test]
+    async fn test_session_survives_reconfigure_and_records_it() {
+        let ctx = mock_context().await;
+        let session = ctx.sessions.create_session("dev-1", None).await.unwrap();
+
+        let Json(body) = open_port(
+            AxumState(ctx.clone()),
+            Json(
+                serde_json::from_value(json!({
+                    "port_name": "MOCK0",
+                    "baud_rate": 9600,
+                    "session_id": session.id
+                }))
+                .unwrap(),
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["status"], "ok", "{body}");
+
+        let Json(body) = reconfigure_port(
+            AxumState(ctx.clone()),
+            Json(serde_json::from_value(json!({"baud_rate": 115200})).unwrap()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["status"], "ok", "{body}");
+        assert_eq!(body["session_id"], json!(session.id));
+
+        let messages = ctx.sessions.list_messages(&session.id, 10).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].role, "system");
+        assert!(messages[0]
+            .content
+            .starts_with("reconfigured MOCK0: baud_rate=115200"));
+    }
+
+    #[tokio::test]
+    async fn test_reload_config_applies_changes_and_rejects_invalid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        std::fs::write(&path, "[serial]\nmax_read_bytes = 1024\n").unwrap();
+        let mut ctx = context_with_cache(PortListCache::default()).await;
+        ctx.config = Arc::new(std::sync::RwLock::new(
+            crate::config::ConfigLoader::load_from(&path).unwrap(),
+        ));
+        // Clones share settings, so handlers holding one see the reload
+        let service = ctx.service.clone();
+
+        std::fs::write(
+            &path,
+            "[serial]\nmax_read_bytes = 2048\ntimeout_warn_interval_ms = 500\n",
+        )
+        .unwrap();
+        let Json(body) = reload_config(AxumState(ctx.clone())).await.unwrap();
+        assert_eq!(body["status"], "ok", "{body}");
+        assert_eq!(
+            body["changes"],
+            json!([
+                {"key": "serial.max_read_bytes", "old": 1024, "new": 2048},
+                {"key": "serial.timeout_warn_interval_ms", "old": 10000, "new": 500},
+            ])
+        );
+        assert_eq!(service.max_read_bytes(), 2048);
+        assert_eq!(service.timeout_warn_interval(), Duration::from_millis(500));
+
+        std::fs::write(&path, "[serial]\nmax_read_bytes = 0\n").unwrap();
+        let error = reload_config(AxumState(ctx.clone())).await.unwrap_err();
+        assert!(matches!(
+            error,
+            AppError::Failed {
+                kind: "ConfigReloadError",
+                ..
+            }
+        ));
+        assert!(error.to_string().contains("serial.max_read_bytes"));
+        assert_eq!(service.max_read_bytes(), 2048);
+        let Json(body) = get_config(AxumState(ctx)).await;
+        assert_eq!(body["config"]["serial"]["max_read_bytes"], 2048);
+    }
+
+    #[cfg(feature = "auto-negotiation")]
+    #[tokio::test]
+    async fn test_detect_all_aggregates_per_port_results() {
+        use crate::negotiation::{
+            AutoNegotiator, NegotiatedParams, NegotiationError, NegotiationHints,
+            NegotiationStrategy,
+        };
+
+        /// Answers at 115200 on MOCK_OK and fails everywhere else.
+        struct StubStrategy;
+
+        #[async_trait::async_trait]
+        impl NegotiationStrategy for StubStrategy {
+            fn name(&self) -> &'static str {
+                "stub"
+            }
+
+            async fn negotiate(
+                &self,
+                port_name: &str,
+                hints: &NegotiationHints,
+            ) -> Result<NegotiatedParams, NegotiationError> {
+                assert_eq!(hints.timeout_ms, 250);
+                match port_name {
+                    "MOCK_OK" => Ok(NegotiatedParams::new(115200, "stub")),
+                    _ => Err(NegotiationError::Timeout),
+                }
+            }
+        }
+
+        let cache = PortListCache::with_enumerator(
+            Duration::from_secs(1),
+            Arc::new(|| {
+                Ok(["MOCK_OK", "MOCK_SILENT", "MOCK_OPEN"]
+                    .into_iter()
+                    .map(|name| serialport::SerialPortInfo {
+                        port_name: name.to_string(),
+                        port_type: serialport::SerialPortType::Unknown,
+                    })
+                    .collect())
+            }),
+        );
+        let mut ctx = mock_context().await;
+        ctx.port_cache = Arc::new(cache);
+        ctx.negotiator = Arc::new(AutoNegotiator::with_strategies(vec![Box::new(
+            StubStrategy,
+        )]));
+        let Json(body) = open_port(
+            AxumState(ctx.clone()),
+            Json(
+                serde_json::from_value(json!({"port_name": "MOCK_OPEN", "baud_rate": 9600}))
+                    .unwrap(),
+            ),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["status"], "ok", "{body}");
+
+        let Json(body) = detect_all_ports(
+            AxumState(ctx),
+            Json(serde_json::from_value(json!({"timeout_ms": 250, "concurrency": 2})).unwrap()),
+        )
+        .await;
+        assert_eq!(body["status"], "ok", "{body}");
+        let results = body["results"].as_object().unwrap();
+        assert_eq!(results.len(), 3);
+        assert_eq!(results["MOCK_OK"]["baud_rate"], 115200);
+        assert_eq!(results["MOCK_OK"]["strategy_used"], "stub");
+        assert_eq!(results["MOCK_SILENT"]["error"], "All strategies failed");
+        assert_eq!(results["MOCK_OPEN"]["error"], "port is currently open");
+    }
+
+    #[cfg(feature = "auto-negotiation")]
+    #[tokio::test]
+    async fn test_open_auto_applies_terminator_learned_by_detect() {
+        use crate::negotiation::{
+            AutoNegotiator, NegotiatedParams, NegotiationError, NegotiationHints,
+            NegotiationStrategy,
+        };
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        /// Answers at 115200 with CRLF-terminated replies.
+        struct CrlfStrategy(Arc<AtomicU32>);
+
+        #[async_trait::async_trait]
+        impl NegotiationStrategy for CrlfStrategy {
+            fn name(&self) -> &'static str {
+                "crlf"
+            }
+
+            async fn negotiate(
+                &self,
+                _port_name: &str,
+                _hints: &NegotiationHints,
+            ) -> Result<NegotiatedParams, NegotiationError> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(NegotiatedParams::new(115200, "crlf").with_terminator(Some("\r\n")))
+            }
+        }
+
+        let runs = Arc::new(AtomicU32::new(0));
+        let mut ctx = mock_context().await;
+        ctx.negotiator = Arc::new(AutoNegotiator::with_strategies(vec![Box::new(
+            CrlfStrategy(runs.clone()),
+        )]));
+
+        let Json(body) = detect_port(
+            AxumState(ctx.clone()),
+            Json(serde_json::from_value(json!({"port_name": "MOCK0"})).unwrap()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["status"], "ok", "{body}");
+        assert_eq!(body["from_cache"], false);
+
+        let Json(body) = open_port_auto(
+            AxumState(ctx.clone()),
+            Json(serde_json::from_value(json!({"port_name": "MOCK0"})).unwrap()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["status"], "ok", "{body}");
+        assert_eq!(body["baud_rate"], 115200);
+        assert_eq!(body["terminator"], "\r\n");
+        assert_eq!(body["from_cache"], true);
+        assert_eq!(
+            runs.load(Ordering::SeqCst),
+            1,
+            "open_auto should reuse the detection"
+        );
+        match ctx.service.status().unwrap() {
+            crate::service::StatusResult::Open { config, .. } => {
+                assert_eq!(config.terminator.as_deref(), Some("\r\n"));
+            }
+            crate::service::StatusResult::Closed => panic!("port should be open"),
+        }
+
+        ctx.service.close().unwrap();
+        let Json(body) = open_port_auto(
+            AxumState(ctx.clone()),
+            Json(serde_json::from_value(json!({"port_name": "MOCK0", "force": true})).unwrap()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["from_cache"], false);
+        assert_eq!(runs.load(Ordering::SeqCst), 2, "force should probe again");
+    }
+
+    #[tokio::test]
+    async fn test_reset_closes_port_and_clears_caches_but_keeps_sessions() {
+        use std::sync::atomic::{AtomicU32, Ordering};
+
+        let enumerations = Arc::new(AtomicU32::new(0));
+        let counter = enumerations.clone();
+        let cache = PortListCache::with_enumerator(
+            Duration::from_secs(60),
+            Arc::new(move || {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(Vec::new())
+            }),
+        );
+        let mut ctx = mock_context().await;
+        ctx.port_cache = Arc::new(cache);
+        let session = ctx.sessions.create_session("dev-1", None).await.unwrap();
+        ctx.sessions
+            .append_message(&session.id, "user", Some("sent"), "AT", None, None)
+            .await
+            .unwrap();
+
+        let Json(body) = open_port(
+            AxumState(ctx.clone()),
+            Json(serde_json::from_value(json!({"port_name": "MOCK0", "baud_rate": 9600})).unwrap()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body["status"], "ok", "{body}");
+        ctx.service.write("AT").unwrap();
+        ctx.port_cache.list(false).unwrap();
+        ctx.port_cache.list(false).unwrap();
+        assert_eq!(enumerations.load(Ordering::SeqCst), 1);
+        #[cfg(feature = "websocket")]
+        let (_broadcast, reader) = ctx.ws_readers.start(&ctx);
+
+        #[cfg(feature = "auto-negotiation")]
+        {
+            use crate::negotiation::{
+                AutoNegotiator, NegotiatedParams, NegotiationError, NegotiationHints,
+                NegotiationStrategy,
+            };
+
+            struct Fixed;
+
+            #[async_trait::async_trait]
+            impl NegotiationStrategy for Fixed {
+                fn name(&self) -> &'static str {
+                    "fixed"
+                }
+
+                async fn negotiate(
+                    &self,
+                    _port_name: &str,
+                    _hints: &NegotiationHints,
+                ) -> Result<NegotiatedParams, NegotiationError> {
+                    Ok(NegotiatedParams::new(9600, "fixed"))
+                }
+            }
+
+            ctx.negotiator = Arc::new(AutoNegotiator::with_strategies(vec![Box::new(Fixed)]));
+            ctx.negotiator
+                .detect_cached("MOCK1", None, None, true, false)
+                .await
+                .unwrap();
+        }
+
+        let Json(body) = reset_subsystem(AxumState(ctx.clone())).await.unwrap();
+        assert_eq!(body["status"], "ok", "{body}");
+        assert_eq!(body["port_closed"], true);
+        assert_eq!(body["writer_restarted"], true);
+        assert!(body["final_metrics"].is_object());
+        #[cfg(feature = "websocket")]
+        {
+            assert_eq!(body["ws_readers_restarted"], 1);
+            tokio::task::yield_now().await;
+            assert_eq!(ctx.ws_readers.running(), 1);
+            drop(reader);
+            assert_eq!(ctx.ws_readers.running(), 0);
+        }
+        #[cfg(feature = "auto-negotiation")]
+        {
+            assert_eq!(body["detections_cleared"], 1);
+            assert!(ctx.negotiator.cached("MOCK1").is_none());
+        }
+
+        assert!(matches!(
+            ctx.service.status().unwrap(),
+            crate::service::StatusResult::Closed
+        ));
+        ctx.port_cache.list(false).unwrap();
+        assert_eq!(
+            enumerations.load(Ordering::SeqCst),
+            2,
+            "the port list should be enumerated afresh"
+        );
+        let messages = ctx.sessions.list_messages(&session.id, 10).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "AT");
+
+        // A second reset finds nothing left to do
+        let Json(body) = reset_subsystem(AxumState(ctx.clone())).await.unwrap();
+        assert_eq!(body["port_closed"], false);
+        assert_eq!(body["writer_restarted"], false);
+    }
 }
@@ -20,11 +20,25 @@
 //! - **Type Safety**: Strong typing with dedicated result types
 
 use crate::{
-    port::{DataBits, FlowControl, Parity, PortConfiguration, StopBits, SyncSerialPort},
-    state::{AppState, DataBitsCfg, FlowControlCfg, ParityCfg, PortConfig, PortState, StopBitsCfg},
+    config::{resolve_device_alias, Config, DeviceAlias, ProtocolDescriptor, SerialConfig},
+    port::{
+        coalesce_ranges, modbus, BufferSizes, ControlSignal, DataBits, FlowControl, Parity,
+        PortCapabilities, PortConfiguration, PortError, RegisterKind, RegisterRange,
+        SerialPortAdapter, StopBits, SyncSerialPort,
+    },
+    state::{
+        lock_state, wall_clock_at, AppState, CancelToken, ChecksumKind, ControlActivity,
+        ControlCallGuard, DataBitsCfg, FlowControlCfg, ParityCfg, PortAdapter, PortConfig,
+        PortConfigPatch, PortState, ReadEncoding, ReadStrategy, ReadTransform, ResetBoard,
+        ResponseProbe, SignalStep, StopBitsCfg, TerminatorDetector, WriteEncoding,
+    },
 };
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{mpsc, Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, TryLockError};
 use std::time::Duration;
+use uuid::Uuid;
 
 // ========== Error Types ==========
 
@@ -41,8 +55,32 @@ pub enum ServiceError {
     InvalidConfig(String),
     /// Port operation failed
     PortError(String),
+    /// The current user may not open the named port
+    PermissionDenied(String),
+    /// The named port or aliased device is not present
+    DeviceNotFound(String),
     /// No port name provided when required
     NoPortSpecified,
+    /// A long-running read was interrupted by `cancel_read`
+    Cancelled,
+    /// The port stopped accepting data before the whole write was sent;
+    /// `flow_paused` if the device was holding it off with XOFF
+    WriteTimeout {
+        written: usize,
+        expected: usize,
+        flow_paused: bool,
+    },
+    /// The open port or platform cannot perform the operation
+    Unsupported(String),
+    /// The port opened but nothing answered the `require_response` probe
+    DeviceNotResponding { timeout_ms: u64 },
+    /// A port operation did not complete in time
+    Timeout(Duration),
+    /// The operating system reported an I/O error of the given kind
+    Io {
+        kind: std::io::ErrorKind,
+        message: String,
+    },
 }
 
 impl std::fmt::Display for ServiceError {
@@ -53,44 +91,221 @@ impl std::fmt::Display for ServiceError {
             Self::StateLockPoisoned => write!(f, "State lock is poisoned"),
             Self::InvalidConfig(msg) => write!(f, "Invalid configuration: {}", msg),
             Self::PortError(msg) => write!(f, "Port operation failed: {}", msg),
+            Self::PermissionDenied(port) => {
+                write!(f, "{}", PortError::PermissionDenied(port.clone()))
+            }
+            Self::DeviceNotFound(msg) => write!(f, "{}", msg),
             Self::NoPortSpecified => write!(f, "No port name specified"),
+            Self::Cancelled => write!(f, "Operation cancelled"),
+            Self::WriteTimeout {
+                written,
+                expected,
+                flow_paused,
+            } => {
+                write!(f, "Write timed out after {} of {} bytes", written, expected)?;
+                if *flow_paused {
+                    write!(f, " (device paused transmission with XOFF)")?;
+                }
+                Ok(())
+            }
+            Self::Unsupported(msg) => write!(f, "{}", msg),
+            Self::DeviceNotResponding { timeout_ms } => write!(
+                f,
+                "No response to the open probe within {} ms; is the device connected and powered?",
+                timeout_ms
+            ),
+            Self::Timeout(duration) => write!(f, "Operation timed out after {:?}", duration),
+            Self::Io { message, .. } => write!(f, "I/O error: {}", message),
         }
     }
 }
 
 impl std::error::Error for ServiceError {}
 
+impl ServiceError {
+    /// Name of the variant, used as the `type` of REST error bodies.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::PortAlreadyOpen => "PortAlreadyOpen",
+            Self::PortNotOpen => "PortNotOpen",
+            Self::StateLockPoisoned => "StateLockPoisoned",
+            Self::InvalidConfig(_) => "InvalidConfig",
+            Self::PortError(_) => "PortError",
+            Self::PermissionDenied(_) => "PermissionDenied",
+            Self::DeviceNotFound(_) => "DeviceNotFound",
+            Self::NoPortSpecified => "NoPortSpecified",
+            Self::Cancelled => "Cancelled",
+            Self::WriteTimeout { .. } => "WriteTimeout",
+            Self::Unsupported(_) => "Unsupported",
+            Self::DeviceNotResponding { .. } => "DeviceNotResponding",
+            Self::Timeout(_) => "Timeout",
+            Self::Io { .. } => "IoError",
+        }
+    }
+
+    /// Whether the operation failed because something did not happen in
+    /// time, whichever layer noticed it.
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            Self::Timeout(_) | Self::WriteTimeout { .. } | Self::DeviceNotResponding { .. } => true,
+            Self::Io { kind, .. } => *kind == std::io::ErrorKind::TimedOut,
+            _ => false,
+        }
+    }
+
+    /// Whether the port or device failed, as opposed to the caller asking for
+    /// something the current state does not allow.
+    pub fn is_failure(&self) -> bool {
+        !matches!(
+            self,
+            Self::PortAlreadyOpen
+                | Self::PortNotOpen
+                | Self::InvalidConfig(_)
+                | Self::NoPortSpecified
+                | Self::Cancelled
+                | Self::Unsupported(_)
+        )
+    }
+}
+
+/// Keep the parts of a port error callers act on (timeouts, missing devices,
+/// permissions, I/O error kinds); everything else becomes a `PortError`
+/// message.
+impl From<PortError> for ServiceError {
+    fn from(err: PortError) -> Self {
+        match err {
+            PortError::Timeout(duration) => Self::Timeout(duration),
+            PortError::PermissionDenied(port) => Self::PermissionDenied(port),
+            e @ PortError::NotFound(_) => Self::DeviceNotFound(e.to_string()),
+            e @ PortError::Unsupported(_) => Self::Unsupported(e.to_string()),
+            PortError::AlreadyOpen => Self::PortAlreadyOpen,
+            PortError::NotOpen => Self::PortNotOpen,
+            PortError::Io(e) => Self::Io {
+                kind: e.kind(),
+                message: e.to_string(),
+            },
+            PortError::Serial(e) => match e.kind() {
+                serialport::ErrorKind::NoDevice => Self::DeviceNotFound(e.to_string()),
+                serialport::ErrorKind::Io(kind) => Self::Io {
+                    kind,
+                    message: e.to_string(),
+                },
+                _ => Self::PortError(e.to_string()),
+            },
+            e => Self::PortError(e.to_string()),
+        }
+    }
+}
+
 /// Convenient Result type for service operations
 pub type ServiceResult<T> = Result<T, ServiceError>;
 
 // ========== Request/Response DTOs ==========
 
 /// Configuration for opening a port
+///
+/// Omitted line settings take the same defaults as the `open_port` tool.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct OpenConfig {
     pub port_name: String,
+    #[serde(default = "crate::state::default_baud")]
     pub baud_rate: u32,
+    #[serde(default = "crate::state::default_timeout")]
     pub timeout_ms: u64,
+    #[serde(default = "crate::state::default_data_bits")]
     pub data_bits: DataBitsCfg,
+    #[serde(default = "crate::state::default_parity")]
     pub parity: ParityCfg,
+    #[serde(default = "crate::state::default_stop_bits")]
     pub stop_bits: StopBitsCfg,
+    #[serde(default = "crate::state::default_flow_control")]
     pub flow_control: FlowControlCfg,
     pub terminator: Option<String>,
+    /// `None` inherits the server default; `Some(0)` disables idle-disconnect
     pub idle_disconnect_ms: Option<u64>,
+    pub read_idle_gap_ms: Option<u64>,
+    pub write_checksum: Option<ChecksumKind>,
+    /// Consume the local echo of each write (RS-485 half-duplex)
+    #[serde(default)]
+    pub half_duplex_echo: bool,
+    /// Block until each write has been transmitted
+    #[serde(default)]
+    pub flush_on_write: bool,
+    /// Infer `terminator` from the line endings of the first reads
+    #[serde(default)]
+    pub auto_detect_terminator: bool,
+    /// Clear the high bit of received bytes, which carries parity with 7 data
+    /// bits; defaults to on for `data_bits: seven`, off otherwise
+    #[serde(default)]
+    pub mask_parity_bit: Option<bool>,
+    /// Remove the shell's echo of the last write from the next read
+    #[serde(default)]
+    pub strip_echo: bool,
+    /// Shell prompt removed from the end of read data
+    #[serde(default)]
+    pub strip_prompt: Option<String>,
+    /// Close the port after this long open, even while busy. `None` inherits
+    /// the server default; `Some(0)` disables the limit
+    #[serde(default)]
+    pub max_open_duration_ms: Option<u64>,
+    /// Extra open attempts after a transient failure (busy / permission denied)
+    #[serde(default)]
+    pub open_retries: Option<u32>,
+    /// Delay between open attempts (default 100 ms)
+    #[serde(default)]
+    pub open_retry_delay_ms: Option<u64>,
+    /// Fail the open unless the device answers this probe
+    #[serde(default)]
+    pub require_response: Option<ResponseProbe>,
+    /// How reads wait for incoming bytes
+    #[serde(default)]
+    pub read_strategy: ReadStrategy,
+}
+
+impl From<PortConfig> for OpenConfig {
+    /// Open settings that reproduce `config`, as used by
+    /// [`PortService::reconnect`].
+    fn from(config: PortConfig) -> Self {
+        Self {
+            port_name: config.port_name,
+            baud_rate: config.baud_rate,
+            timeout_ms: config.timeout_ms,
+            data_bits: config.data_bits,
+            parity: config.parity,
+            stop_bits: config.stop_bits,
+            flow_control: config.flow_control,
+            terminator: config.terminator,
+            // `None` here means idle-disconnect was off, not "use the default"
+            idle_disconnect_ms: Some(config.idle_disconnect_ms.unwrap_or(0)),
+            read_idle_gap_ms: config.read_idle_gap_ms,
+            write_checksum: config.write_checksum,
+            half_duplex_echo: config.half_duplex_echo,
+            flush_on_write: config.flush_on_write,
+            auto_detect_terminator: config.auto_detect_terminator,
+            mask_parity_bit: config.mask_parity_bit,
+            strip_echo: config.strip_echo,
+            strip_prompt: config.strip_prompt,
+            max_open_duration_ms: Some(config.max_open_duration_ms.unwrap_or(0)),
+            open_retries: None,
+            open_retry_delay_ms: None,
+            require_response: None,
+            read_strategy: config.read_strategy,
+        }
+    }
 }
 
 /// Configuration for reconfiguring a port
-#[derive(Debug, Clone, Deserialize, Serialize)]
+///
+/// Settings left unset in `settings` keep the open port's values. With no
+/// port open they take the `open_port` defaults, except a 9600 baud rate.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
 pub struct ReconfigureConfig {
     pub port_name: Option<String>,
-    pub baud_rate: u32,
-    pub timeout_ms: u64,
-    pub data_bits: DataBitsCfg,
-    pub parity: ParityCfg,
-    pub stop_bits: StopBitsCfg,
-    pub flow_control: FlowControlCfg,
-    pub terminator: Option<String>,
-    pub idle_disconnect_ms: Option<u64>,
+    #[serde(flatten)]
+    pub settings: PortConfigPatch,
+    /// Keep partially received frame bytes across the reopen
+    #[serde(default)]
+    pub soft_reconfigure: bool,
 }
 
 /// Result from opening a port
@@ -99,47 +314,481 @@ pub struct OpenResult {
     pub port_name: String,
     pub baud_rate: u32,
     pub message: String,
+    /// Correlation id shared by every operation until the port is closed
+    pub open_id: Uuid,
+    /// Session linked to the port; carried across a reconfigure
+    pub active_session: Option<String>,
+    /// Settings the port was opened with
+    pub config: PortConfig,
+    /// Non-fatal problems with the requested settings (e.g. an unusual baud rate)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// What the device sent back to `require_response`'s probe
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probe_response: Option<String>,
+}
+
+/// Why a port was closed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// An explicit [`close`](PortService::close)
+    Manual,
+    /// No data within the port's `idle_disconnect_ms`
+    IdleTimeout,
+    /// No MCP call within `serial.inactivity_close_ms`
+    Inactivity,
+    /// Open longer than the port's `max_open_duration_ms`
+    MaxDuration,
+    /// Replaced by a reopen with new settings
+    Reconfigure,
+}
+
+impl CloseReason {
+    /// The reason as it is serialized, e.g. `idle_timeout`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Manual => "manual",
+            Self::IdleTimeout => "idle_timeout",
+            Self::Inactivity => "inactivity",
+            Self::MaxDuration => "max_duration",
+            Self::Reconfigure => "reconfigure",
+        }
+    }
+}
+
+/// A port that closed on its own: idle, inactive or open too long.
+#[derive(Debug, Clone)]
+pub struct AutoCloseEvent {
+    pub port_name: String,
+    /// Session linked to the port when it closed
+    pub session_id: Option<String>,
+    pub close: CloseResult,
 }
 
+/// Called after each automatic close, with the state lock still held: it
+/// must not call back into the service, and should hand slow work off.
+pub type CloseHook = Arc<dyn Fn(&AutoCloseEvent) + Send + Sync>;
+
 /// Result from closing a port
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CloseResult {
     pub message: String,
+    pub reason: CloseReason,
+    /// Metrics of the port just before it closed; `None` if it was already closed
+    pub final_metrics: Option<PortMetrics>,
+}
+
+/// What [`PortService::reset`] did
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemResetResult {
+    /// Close of the port that was open; `None` if none was
+    pub closed: Option<CloseResult>,
+    /// The port stayed locked by another operation past
+    /// [`RESET_LOCK_WAIT`], so it was left open
+    pub port_busy: bool,
+    /// Whether a writer thread was retired; the next write starts a fresh one
+    pub writer_restarted: bool,
 }
 
 /// Result from writing data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WriteResult {
+    pub open_id: Uuid,
+    /// Bytes of the caller's data, without checksum or appended terminator
+    pub payload_bytes: usize,
+    /// Bytes of the configured terminator appended after the payload
+    pub terminator_bytes: usize,
+    /// Bytes actually written: payload, checksum and terminator
     pub bytes_written: usize,
     pub bytes_written_total: u64,
+    /// Checksum appended to the payload as uppercase hex, if configured
+    pub checksum: Option<String>,
+    /// With `half_duplex_echo`, whether the consumed echo equaled the bytes sent
+    pub echo_matched: Option<bool>,
+    /// With `flush_on_write`, how long the flush blocked until the bytes were sent
+    pub flush_duration_ms: Option<u64>,
+    /// How long the device held the write off with XOFF; `None` if the
+    /// platform does not expose the flow-control state
+    pub flow_paused_ms: Option<u64>,
+}
+
+/// Result from a repeated write
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WriteRepeatResult {
+    pub count: u32,
+    pub bytes_written: u64,
+    pub bytes_written_total: u64,
+    pub elapsed_ms: u64,
+}
+
+/// Result from waiting for a pattern
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WaitForPatternResult {
+    /// Text matched by the pattern, if it matched before the timeout
+    pub matched: Option<String>,
+    pub timed_out: bool,
+    /// Everything received while waiting (lossy UTF-8)
+    pub data: String,
+    pub bytes_read: usize,
+    pub elapsed_ms: u64,
+}
+
+/// Result from a loopback self-check
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoopbackResult {
+    pub passed: bool,
+    /// Random token written to the port
+    pub sent: String,
+    /// Bytes read back (lossy UTF-8)
+    pub received: String,
+    /// Time from write until the token was read back, or until the timeout
+    pub latency_ms: u64,
+}
+
+/// Round-trip latency distribution from [`PortService::benchmark`], in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub min_ms: f64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p90_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    /// Summarise `samples`, or `None` if there are none.
+    ///
+    /// Percentiles use the nearest-rank method, so every reported value is
+    /// a round trip that actually happened.
+    fn from_samples(samples: &[Duration]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted: Vec<f64> = samples.iter().map(|d| d.as_secs_f64() * 1000.0).collect();
+        sorted.sort_by(f64::total_cmp);
+        let rank = |p: f64| {
+            let index = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+            sorted[index.clamp(1, sorted.len()) - 1]
+        };
+        Some(Self {
+            min_ms: sorted[0],
+            mean_ms: sorted.iter().sum::<f64>() / sorted.len() as f64,
+            p50_ms: rank(50.0),
+            p90_ms: rank(90.0),
+            p99_ms: rank(99.0),
+            max_ms: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+/// Result from a round-trip benchmark
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkResult {
+    pub iterations: u32,
+    /// Round trips that saw the delimiter before the timeout
+    pub completed: u32,
+    /// Round trips that gave up waiting for the delimiter
+    pub timeouts: u32,
+    /// Latency of the completed round trips; `None` if none completed
+    pub latency: Option<LatencyStats>,
+    pub bytes_written: u64,
+    pub bytes_read: u64,
+    pub elapsed_ms: u64,
+    /// Bytes written plus bytes read per second of the whole run
+    pub throughput_bytes_per_sec: f64,
+    pub transactions_per_sec: f64,
+}
+
+/// One write-then-read exchange performed by `PortService::transact`.
+struct Transaction {
+    bytes_written: usize,
+    bytes_read: usize,
+    /// Whether the delimiter arrived before the timeout
+    completed: bool,
+}
+
+/// How a [`PortService::protocol_command`] response was classified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseStatus {
+    Success,
+    Error,
+    /// No response line arrived within the protocol timeout
+    Timeout,
+}
+
+/// Result of a command sent with [`PortService::protocol_command`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProtocolCommandResult {
+    pub protocol: String,
+    pub status: ResponseStatus,
+    /// Response line with the matched prefix removed
+    pub payload: Option<String>,
+    /// Response line as received (trimmed)
+    pub response: Option<String>,
+    /// Lines received before the response, e.g. the command echo
+    pub skipped: Vec<String>,
+    pub elapsed_ms: u64,
+}
+
+/// Result of [`PortService::modbus_read_many`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModbusReadResult {
+    /// Register values by address, for the requested addresses only
+    pub values: BTreeMap<u16, u16>,
+    /// Read requests issued after coalescing, in order
+    pub requests: Vec<RegisterRange>,
+    pub elapsed_ms: u64,
+}
+
+/// Classify one trimmed response line, returning its status and payload, or
+/// `None` if the line is not a response (echo, unsolicited output).
+fn classify_response(
+    protocol: &ProtocolDescriptor,
+    line: &str,
+) -> Option<(ResponseStatus, String)> {
+    let strip = |prefix: &Option<String>| {
+        prefix
+            .as_deref()
+            .and_then(|p| line.strip_prefix(p))
+            .map(|rest| rest.trim().to_string())
+    };
+    if let Some(payload) = strip(&protocol.error_prefix) {
+        return Some((ResponseStatus::Error, payload));
+    }
+    match &protocol.response_prefix {
+        Some(_) => strip(&protocol.response_prefix).map(|p| (ResponseStatus::Success, p)),
+        None if line.is_empty() => None,
+        None => Some((ResponseStatus::Success, line.to_string())),
+    }
+}
+
+/// Control signal levels to apply with [`PortService::set_signals`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SignalsRequest {
+    pub dtr: Option<bool>,
+    pub rts: Option<bool>,
+    /// How long to hold the requested levels before returning (or restoring)
+    pub hold_ms: Option<u64>,
+    /// Restore the previous levels after the hold
+    #[serde(default)]
+    pub restore: bool,
+}
+
+/// Result from patching the open port's configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchResult {
+    /// New id if the port was reopened, otherwise unchanged
+    pub open_id: Uuid,
+    /// Full configuration after the patch
+    pub config: PortConfig,
+    /// Whether line settings changed and the port was reopened
+    pub reopened: bool,
+}
+
+/// Framing settings to change with [`PortService::set_framing`]; `None`
+/// keeps a setting
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct FramingRequest {
+    pub data_bits: Option<DataBitsCfg>,
+    pub parity: Option<ParityCfg>,
+    pub stop_bits: Option<StopBitsCfg>,
+}
+
+/// Result from changing the open port's framing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FramingResult {
+    /// New id if the port was reopened, otherwise unchanged
+    pub open_id: Uuid,
+    /// Full configuration after the change
+    pub config: PortConfig,
+    /// Whether the port could not change framing live and was reopened
+    pub reopened: bool,
+    /// Why the port was reopened, if it was
+    pub warning: Option<String>,
+}
+
+/// Result from setting control signals
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignalsResult {
+    /// DTR level after the call (`None` if never set)
+    pub dtr: Option<bool>,
+    /// RTS level after the call (`None` if never set)
+    pub rts: Option<bool>,
+    pub restored: bool,
+}
+
+/// Result from [`PortService::set_buffer_sizes`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BufferSizesResult {
+    pub port_name: String,
+    /// Sizes asked for; both `None` for a query
+    pub requested: BufferSizes,
+    /// Sizes in effect afterwards, after any clamping by the driver
+    pub effective: BufferSizes,
+}
+
+/// Result from [`PortService::reset_device`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResetResult {
+    pub board: ResetBoard,
+    /// Steps executed, in order
+    pub steps: Vec<SignalStep>,
+    /// DTR level after the sequence
+    pub dtr: Option<bool>,
+    /// RTS level after the sequence
+    pub rts: Option<bool>,
+}
+
+/// Outcome of one [`PortService::diagnose`] step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pass,
+    Fail,
+    /// Not run, because an earlier step failed or it does not apply
+    Skipped,
+}
+
+/// One entry of a [`DiagnoseReport`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnosticStep {
+    pub name: String,
+    pub status: StepStatus,
+    pub detail: String,
+    /// What to try next when the step did not pass
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suggestion: Option<String>,
+}
+
+impl DiagnosticStep {
+    pub fn pass(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: StepStatus::Pass,
+            detail: detail.into(),
+            suggestion: None,
+        }
+    }
+
+    pub fn fail(name: &str, detail: impl Into<String>, suggestion: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: StepStatus::Fail,
+            detail: detail.into(),
+            suggestion: Some(suggestion.into()),
+        }
+    }
+
+    pub fn skipped(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: StepStatus::Skipped,
+            detail: detail.into(),
+            suggestion: None,
+        }
+    }
+}
+
+/// Result from [`PortService::diagnose`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiagnoseReport {
+    /// Port path, after alias resolution
+    pub port_name: String,
+    pub baud_rate: u32,
+    /// Steps in the order they ran
+    pub steps: Vec<DiagnosticStep>,
+}
+
+impl DiagnoseReport {
+    /// True when no step failed.
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|s| s.status != StepStatus::Fail)
+    }
+}
+
+/// Result from querying port capabilities
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitiesResult {
+    /// Operating system, as in `std::env::consts::OS`
+    pub platform: String,
+    /// Port the capabilities were read from; `None` means platform defaults
+    pub port_name: Option<String>,
+    #[serde(flatten)]
+    pub capabilities: PortCapabilities,
 }
 
 /// Result from reading data
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadResult {
+    pub open_id: Uuid,
     pub data: String,
     pub bytes_read: usize,
     pub bytes_read_total: u64,
-    /// If Some, indicates the port was auto-closed due to idle timeout
+    /// Framing/parity errors seen since the port was opened, or `None` if the
+    /// port cannot detect them
+    pub framing_errors: Option<u64>,
+    /// If Some, indicates the port was auto-closed (idle timeout or open-duration limit)
     pub auto_closed: Option<AutoCloseInfo>,
+    /// Why a [`read_line`](PortService::read_line) or
+    /// [`capture_until_silence`](PortService::capture_until_silence) stopped;
+    /// `None` for plain reads
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub termination: Option<ReadTermination>,
+    /// `data` before a [`ReadTransform`] was applied
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    /// The bytes behind `data` as uppercase hex, when requested; taken before
+    /// parity masking, terminator stripping and any encoding of `data`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hex: Option<String>,
+}
+
+/// Which limit ended a [`read_line`](PortService::read_line) or
+/// [`capture_until_silence`](PortService::capture_until_silence).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadTermination {
+    /// The line delimiter was received
+    Delimiter,
+    /// No byte arrived within `idle_timeout_ms` (`silence_ms` for a capture)
+    IdleTimeout,
+    /// `overall_timeout_ms` elapsed while data was still arriving
+    OverallTimeout,
+    /// The byte limit was reached (`serial.max_read_bytes` for a line,
+    /// `max_bytes` for a capture)
+    MaxBytes,
 }
 
 /// Information about an auto-close event
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutoCloseInfo {
-    pub reason: String,
+    pub reason: CloseReason,
     pub idle_close_count: u64,
 }
 
 /// Port status information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "state", rename_all = "PascalCase")]
+// Built once per status call, so the size of `Open` is irrelevant.
+#[allow(clippy::large_enum_variant)]
 pub enum StatusResult {
     Closed,
     Open {
+        open_id: Uuid,
         config: PortConfig,
         #[serde(skip_serializing_if = "Option::is_none")]
         metrics: Option<PortMetrics>,
+        /// Received bytes kept for the next read, such as an unterminated line
+        /// or a partial UTF-8 character
+        pending_frame_bytes: usize,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        active_session: Option<String>,
+        /// Reads are still sampled for `auto_detect_terminator`; `config.terminator`
+        /// holds the detected value once this is false
+        detecting_terminator: bool,
     },
 }
 
@@ -151,6 +800,10 @@ pub struct PortMetrics {
     pub idle_close_count: u64,
     pub open_duration_ms: u64,
     pub last_activity_ms: u64,
+    /// Wall-clock time the port was opened
+    pub open_started_at: chrono::DateTime<chrono::Utc>,
+    /// Wall-clock time of the last read or write
+    pub last_activity_at: chrono::DateTime<chrono::Utc>,
     pub timeout_streak: u32,
 }
 
@@ -163,518 +816,6477 @@ pub struct MetricsResult {
     pub idle_close_count: Option<u64>,
     pub open_duration_ms: Option<u64>,
     pub last_activity_ms: Option<u64>,
+    pub open_started_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub last_activity_at: Option<chrono::DateTime<chrono::Utc>>,
     pub timeout_streak: Option<u32>,
+    pub pending_frame_bytes: Option<usize>,
+}
+
+/// Cumulative counters of the open port at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Changes when the port is reopened; the counters restart from zero
+    pub open_id: Uuid,
+    pub bytes_read_total: u64,
+    pub bytes_written_total: u64,
+    pub idle_close_count: u64,
+    pub timeout_streak: u32,
+}
+
+/// A failed port operation kept in the recent-errors buffer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErrorEvent {
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Service operation that failed: `open`, `reconfigure`, `read`, `read_line` or `write`
+    pub operation: String,
+    /// [`ServiceError::kind`] of the error
+    pub kind: String,
+    pub message: String,
 }
 
 // ========== Service Implementation ==========
 
-/// Port service providing business logic for serial port operations.
+/// Compiled size limit for `wait_for_pattern` regexes (256 KiB).
 ///
-/// This service encapsulates all port management logic, allowing API handlers
-/// to focus on protocol-specific concerns (HTTP, MCP, WebSocket).
-#[derive(Clone)]
-pub struct PortService {
-    state: AppState,
-}
+/// Patterns whose compiled program would exceed this (e.g. huge counted
+/// repetitions) are rejected up front.
+pub const WAIT_PATTERN_SIZE_LIMIT: usize = 256 * 1024;
 
-impl PortService {
-    /// Create a new port service with the given shared state.
-    pub fn new(state: AppState) -> Self {
-        Self { state }
-    }
+/// Longest single read while waiting for a pattern, so the deadline is honoured.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
 
-    /// Open a serial port with the specified configuration.
-    ///
-    /// # Errors
-    ///
-    /// - `ServiceError::PortAlreadyOpen` if a port is already open
-    /// - `ServiceError::StateLockPoisoned` if the state lock is poisoned
-    /// - `ServiceError::PortError` if the port cannot be opened
-    pub fn open(&self, config: OpenConfig) -> ServiceResult<OpenResult> {
-        let mut st = self
-            .state
-            .lock()
-            .map_err(|_| ServiceError::StateLockPoisoned)?;
+/// Pause before retrying a write the port accepted no bytes of.
+const WRITE_RETRY_INTERVAL: Duration = Duration::from_millis(5);
 
-        // Check if port is already open
-        if matches!(&*st, PortState::Open { .. }) {
-            return Err(ServiceError::PortAlreadyOpen);
-        }
+/// Most round trips one [`PortService::benchmark`] run may perform.
+pub const MAX_BENCHMARK_ITERATIONS: u32 = 10_000;
 
-        // Convert config enums to port module types
-        let port_config = PortConfiguration {
-            baud_rate: config.baud_rate,
-            data_bits: Self::convert_data_bits(config.data_bits),
-            parity: Self::convert_parity(config.parity),
-            stop_bits: Self::convert_stop_bits(config.stop_bits),
-            flow_control: Self::convert_flow_control(config.flow_control),
-            timeout: Duration::from_millis(config.timeout_ms),
-        };
+/// Default token length for [`PortService::loopback_test`].
+pub const DEFAULT_LOOPBACK_TOKEN_LEN: usize = 16;
 
-        // Open the port
-        let port = SyncSerialPort::open(&config.port_name, port_config)
-            .map_err(|e| ServiceError::PortError(e.to_string()))?;
+/// Default number of bytes requested by a plain [`PortService::read`].
+pub const DEFAULT_READ_BYTES: usize = 1024;
 
-        // Update state
-        *st = PortState::Open {
-            port: Box::new(port),
-            config: PortConfig {
-                port_name: config.port_name.clone(),
-                baud_rate: config.baud_rate,
-                timeout_ms: config.timeout_ms,
-                data_bits: config.data_bits,
-                parity: config.parity,
-                stop_bits: config.stop_bits,
-                flow_control: config.flow_control,
-                terminator: config.terminator,
-                idle_disconnect_ms: config.idle_disconnect_ms,
-            },
-            last_activity: std::time::Instant::now(),
-            timeout_streak: 0,
-            bytes_read_total: 0,
-            bytes_written_total: 0,
-            idle_close_count: 0,
-            open_started: std::time::Instant::now(),
-        };
+/// Longest hold accepted by [`PortService::set_signals`] (10 s), and the
+/// longest total hold of one [`PortService::reset_device`] sequence.
+pub const MAX_SIGNAL_HOLD_MS: u64 = 10_000;
 
-        Ok(OpenResult {
-            port_name: config.port_name,
-            baud_rate: config.baud_rate,
-            message: "opened".to_string(),
-        })
-    }
+/// Most steps a custom [`PortService::reset_device`] sequence may have.
+pub const MAX_RESET_STEPS: usize = 32;
 
-    /// Close the currently open port.
-    ///
-    /// This operation is idempotent - closing an already-closed port succeeds.
-    ///
-    /// # Errors
-    ///
-    /// - `ServiceError::StateLockPoisoned` if the state lock is poisoned
-    pub fn close(&self) -> ServiceResult<CloseResult> {
-        let mut st = self
-            .state
-            .lock()
-            .map_err(|_| ServiceError::StateLockPoisoned)?;
+/// Longest pause between writes of [`PortService::write_repeat`] (10 s).
+pub const MAX_WRITE_REPEAT_DELAY_MS: u64 = 10_000;
 
-        let message = match &*st {
-            PortState::Closed => "already closed".to_string(),
-            PortState::Open { .. } => {
-                *st = PortState::Closed;
-                "closed".to_string()
-            }
-        };
+/// Longest total pause, `delay_ms` times the gaps between writes, of one
+/// [`PortService::write_repeat`] (60 s).
+pub const MAX_WRITE_REPEAT_DURATION_MS: u64 = 60_000;
 
-        Ok(CloseResult { message })
-    }
+/// Longest window of [`PortService::read_with_long_poll`] (60 s).
+pub const MAX_LONG_POLL_MS: u64 = 60_000;
 
-    /// Write data to the open port.
-    ///
-    /// If a terminator is configured and the data doesn't end with it,
-    /// the terminator will be automatically appended.
-    ///
-    /// # Errors
-    ///
-    /// - `ServiceError::PortNotOpen` if no port is open
-    /// - `ServiceError::StateLockPoisoned` if the state lock is poisoned
-    /// - `ServiceError::PortError` if the write operation fails
-    pub fn write(&self, data: &str) -> ServiceResult<WriteResult> {
-        let mut st = self
-            .state
-            .lock()
-            .map_err(|_| ServiceError::StateLockPoisoned)?;
+/// A long poll waits in slices of this length, releasing the port between
+/// them so writes and cancellation are not held up for the whole window.
+const LONG_POLL_SLICE: Duration = Duration::from_millis(100);
 
-        match &mut *st {
-            PortState::Open {
-                port,
-                config,
-                last_activity,
-                bytes_written_total,
-                ..
-            } => {
-                // Prepare data with terminator if configured
-                let mut write_data = data.to_string();
-                if let Some(term) = &config.terminator {
-                    if !write_data.ends_with(term) {
-                        write_data.push_str(term);
-                    }
-                }
+/// Default capture size for [`PortService::capture_until_silence`] (64 KiB).
+pub const DEFAULT_CAPTURE_BYTES: usize = 64 * 1024;
 
-                // Write to port
-                let bytes = port
-                    .write_bytes(write_data.as_bytes())
-                    .map_err(|e| ServiceError::PortError(e.to_string()))?;
+/// Default upper bound for a single read request (16 MiB).
+///
+/// Read buffers are allocated up front, so a caller-supplied size above this
+/// cap is rejected instead of being allocated.
+pub const DEFAULT_MAX_READ_BYTES: usize = 16 * 1024 * 1024;
 
-                // Update metrics
-                *bytes_written_total += bytes as u64;
-                *last_activity = std::time::Instant::now();
+/// Default upper bound for the total payload of a single repeated write (16 MiB).
+pub const DEFAULT_MAX_WRITE_REPEAT_BYTES: u64 = 16 * 1024 * 1024;
 
-                Ok(WriteResult {
-                    bytes_written: bytes,
-                    bytes_written_total: *bytes_written_total,
-                })
-            }
-            PortState::Closed => Err(ServiceError::PortNotOpen),
-        }
-    }
+/// Default minimum interval between warnings about consecutive read timeouts.
+pub const DEFAULT_TIMEOUT_WARN_INTERVAL: Duration = Duration::from_secs(10);
 
-    /// Read data from the open port.
-    ///
-    /// Reads up to 1024 bytes. If a terminator is configured, it will be
-    /// stripped from the returned data. Timeouts are handled gracefully
-    /// and return zero-length data.
-    ///
-    /// If idle disconnect is configured and the timeout is reached, the port
-    /// will be automatically closed and the result will indicate this.
-    ///
-    /// # Errors
-    ///
-    /// - `ServiceError::PortNotOpen` if no port is open
-    /// - `ServiceError::StateLockPoisoned` if the state lock is poisoned
-    /// - `ServiceError::PortError` if a non-timeout read error occurs
-    pub fn read(&self) -> ServiceResult<ReadResult> {
-        let mut st = self
-            .state
-            .lock()
-            .map_err(|_| ServiceError::StateLockPoisoned)?;
+/// Default number of samples kept by the metrics history.
+pub const DEFAULT_METRICS_HISTORY_LEN: usize = 300;
 
-        // Extract read result while holding lock
-        let result = match &mut *st {
-            PortState::Open {
-                port,
-                config,
-                last_activity,
-                timeout_streak,
-                bytes_read_total,
-                idle_close_count,
-                ..
-            } => {
-                let mut buffer = vec![0u8; 1024];
+/// Default number of failures kept by the recent-errors buffer.
+pub const DEFAULT_RECENT_ERRORS_LEN: usize = 50;
 
-                // Attempt read
-                let bytes_read = match port.read_bytes(buffer.as_mut_slice()) {
-                    Ok(n) => n,
-                    Err(e) => {
-                        // Check if it's a timeout error
-                        if let crate::port::PortError::Io(ref io_err) = e {
-                            if io_err.kind() == std::io::ErrorKind::TimedOut {
-                                0 // Treat timeout as zero bytes read
-                            } else {
-                                return Err(ServiceError::PortError(e.to_string()));
-                            }
-                        } else {
-                            return Err(ServiceError::PortError(e.to_string()));
-                        }
-                    }
-                };
+/// How long [`PortService::reset`] waits for a busy port before giving up on
+/// closing it.
+pub const RESET_LOCK_WAIT: Duration = Duration::from_secs(2);
 
-                let raw = String::from_utf8_lossy(&buffer[..bytes_read]).to_string();
+/// How often the watchdog checks the open port against its `max_open_duration_ms`.
+pub const MAX_DURATION_CHECK_INTERVAL: Duration = Duration::from_millis(100);
 
-                // Update metrics
-                if bytes_read > 0 {
-                    *last_activity = std::time::Instant::now();
-                    *timeout_streak = 0;
-                    *bytes_read_total += bytes_read as u64;
-                } else {
-                    *timeout_streak += 1;
-                }
+/// Default delay between open attempts when `open_retries` is set.
+pub const DEFAULT_OPEN_RETRY_DELAY_MS: u64 = 100;
 
-                // Check for idle timeout
-                let idle_expired = bytes_read == 0
-                    && config
-                        .idle_disconnect_ms
-                        .map(|ms| last_activity.elapsed() >= Duration::from_millis(ms))
-                        .unwrap_or(false);
+/// Default per-probe reply timeout for [`PortService::diagnose`].
+pub const DEFAULT_DIAGNOSE_PROBE_TIMEOUT_MS: u64 = 300;
 
-                if idle_expired {
-                    *idle_close_count += 1;
-                    let count = *idle_close_count;
-                    // Return early to indicate port should be closed
-                    Err((count, *bytes_read_total))
-                } else {
-                    // Strip terminator if configured
-                    let data = if let Some(term) = &config.terminator {
-                        raw.trim_end_matches(term).to_string()
-                    } else {
-                        raw
-                    };
+/// A probe [`PortService::diagnose`] sends, and the reply that identifies a
+/// device family.
+#[derive(Debug, Clone, Copy)]
+pub struct DeviceSignature {
+    pub device: &'static str,
+    pub command: &'static str,
+    /// Regex matched against the reply
+    pub reply: &'static str,
+}
+
+/// Probes tried in order by [`PortService::diagnose`].
+pub const DIAGNOSE_SIGNATURES: &[DeviceSignature] = &[
+    DeviceSignature {
+        device: "AT command modem",
+        command: "AT\r\n",
+        reply: r"OK|ERROR",
+    },
+    DeviceSignature {
+        device: "SCPI instrument",
+        command: "*IDN?\n",
+        reply: r"[^,\r\n]+,[^,\r\n]+,",
+    },
+    DeviceSignature {
+        device: "shell or REPL",
+        command: "\r\n",
+        reply: r"(>>>|[>$#]) ?$",
+    },
+];
+
+/// Baud rates accepted by [`validate_baud`] without checking clock dividers.
+pub const COMMON_BAUD_RATES: &[u32] = &[
+    300, 600, 1200, 2400, 4800, 9600, 14400, 19200, 28800, 38400, 57600, 76800, 115200, 230400,
+    250000, 460800, 500000, 921600, 1000000, 1500000, 2000000, 3000000,
+];
+
+/// Largest baud rate error, in percent, that still frames reliably.
+pub const BAUD_TOLERANCE_PERCENT: f64 = 2.0;
+
+/// UART reference clocks of common USB adapters and on-board UARTs.
+const UART_CLOCKS_HZ: &[u32] = &[1_843_200, 14_745_600, 16_000_000, 24_000_000, 48_000_000];
+
+/// Check that `baud_rate` is something real hardware can generate.
+///
+/// Common rates always pass. Any other rate passes if an integer divider of
+/// one of the typical UART clocks (with 16x oversampling) comes within
+/// [`BAUD_TOLERANCE_PERCENT`] of it. Returns a warning otherwise.
+pub fn validate_baud(baud_rate: u32) -> Option<String> {
+    if COMMON_BAUD_RATES.contains(&baud_rate) {
+        return None;
+    }
+    if baud_rate == 0 {
+        return Some("baud_rate 0 cannot be generated by any UART".to_string());
+    }
+    let baud = f64::from(baud_rate);
+    let best_error = UART_CLOCKS_HZ
+        .iter()
+        .filter_map(|&clock| {
+            let ticks = f64::from(clock) / 16.0;
+            let divider = (ticks / baud).round();
+            (divider >= 1.0).then(|| (ticks / divider - baud).abs() / baud * 100.0)
+        })
+        .fold(f64::INFINITY, f64::min);
+    if best_error <= BAUD_TOLERANCE_PERCENT {
+        return None;
+    }
+    Some(format!(
+        "baud_rate {} is not a common rate and typical UART clocks miss it by {:.1}% or more; \
+         the device may see garbled data",
+        baud_rate, best_error
+    ))
+}
+
+/// Function used to open a port (injectable for tests).
+pub type PortOpener =
+    Arc<dyn Fn(&str, PortConfiguration) -> Result<PortAdapter, PortError> + Send + Sync>;
 
-                    Ok((data, bytes_read, *bytes_read_total))
+/// Length of an incomplete multibyte UTF-8 sequence at the end of `bytes`.
+///
+/// Returns 0 if `bytes` ends on a character boundary (or with bytes that can
+/// never form a valid character, which are left for lossy decoding).
+fn incomplete_utf8_tail(bytes: &[u8]) -> usize {
+    for back in 1..=bytes.len().min(3) {
+        let b = bytes[bytes.len() - back];
+        if b & 0xC0 == 0x80 {
+            // Continuation byte; keep looking for the lead byte
+            continue;
+        }
+        let needed = match b {
+            0xC2..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF4 => 4,
+            _ => return 0,
+        };
+        return if back < needed { back } else { 0 };
+    }
+    0
+}
+
+/// Position of the first occurrence of `needle` in `haystack`.
+fn find_subslice(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|w| w == needle)
+}
+
+/// Whether `read_line` treats `terminator` as any line ending (`\r\n`, `\r`
+/// or `\n`) rather than matching it exactly.
+fn is_line_terminator(terminator: &str) -> bool {
+    matches!(terminator, "\n" | "\r" | "\r\n")
+}
+
+/// Length of the first line in `bytes` and the bytes it takes up including
+/// its ending, where `\r\n`, `\r` and `\n` all end a line.
+///
+/// A `\r` as the last byte may be the first half of a `\r\n` split across
+/// reads, so it only ends the line once `cr_final` says no more bytes came.
+fn find_line_end(bytes: &[u8], cr_final: bool) -> Option<(usize, usize)> {
+    let pos = bytes.iter().position(|&b| b == b'\n' || b == b'\r')?;
+    match (bytes[pos], bytes.get(pos + 1)) {
+        (b'\r', Some(b'\n')) => Some((pos, pos + 2)),
+        (b'\r', None) if !cr_final => None,
+        _ => Some((pos, pos + 1)),
+    }
+}
+
+/// Render received bytes as `data`, stripping any trailing terminator.
+fn encode_read_data(bytes: &[u8], terminator: Option<&str>, encoding: ReadEncoding) -> String {
+    match encoding {
+        ReadEncoding::Utf8 => {
+            let raw = String::from_utf8_lossy(bytes);
+            match terminator {
+                Some(term) => raw.trim_end_matches(term).to_string(),
+                None => raw.into_owned(),
+            }
+        }
+        ReadEncoding::Base64 => {
+            use base64::Engine;
+            let mut payload = bytes;
+            if let Some(term) = terminator.map(str::as_bytes).filter(|t| !t.is_empty()) {
+                while let Some(rest) = payload.strip_suffix(term) {
+                    payload = rest;
                 }
             }
-            PortState::Closed => return Err(ServiceError::PortNotOpen),
+            base64::engine::general_purpose::STANDARD.encode(payload)
+        }
+        ReadEncoding::Hexdump => crate::port::hexdump(bytes),
+    }
+}
+
+fn open_sync_port(port_name: &str, config: PortConfiguration) -> Result<PortAdapter, PortError> {
+    Ok(Box::new(SyncSerialPort::open(port_name, config)?))
+}
+
+/// Port service providing business logic for serial port operations.
+///
+/// This service encapsulates all port management logic, allowing API handlers
+/// to focus on protocol-specific concerns (HTTP, MCP, WebSocket).
+#[derive(Clone)]
+pub struct PortService {
+    state: AppState,
+    settings: Arc<RwLock<ServiceSettings>>,
+    opener: PortOpener,
+    cancel: CancelToken,
+    activity: ControlActivity,
+    metrics_history: Arc<Mutex<VecDeque<MetricsSnapshot>>>,
+    recent_errors: Arc<Mutex<VecDeque<ErrorEvent>>>,
+    /// Sender to the writer thread, started by the first write
+    write_queue: Arc<Mutex<Option<mpsc::Sender<WriteJob>>>>,
+    /// Settings of the last open port, kept across automatic closes for
+    /// [`reconnect`](PortService::reconnect)
+    last_config: Arc<Mutex<Option<PortConfig>>>,
+    /// How the most recently open port was closed
+    last_close: Arc<Mutex<Option<CloseResult>>>,
+    close_hook: Option<CloseHook>,
+    /// Whether the max-duration watchdog task is running
+    max_duration_watchdog: Arc<std::sync::atomic::AtomicBool>,
+}
+
+/// A payload waiting in the write queue, with where to send its result.
+struct WriteJob {
+    /// The submitting service, so the write sees its current settings
+    service: PortService,
+    data: Vec<u8>,
+    /// Whether the configured checksum and terminator are added
+    append_terminator: bool,
+    reply: mpsc::SyncSender<ServiceResult<WriteResult>>,
+}
+
+/// A write submitted with [`PortService::enqueue_write`].
+#[derive(Debug)]
+pub struct PendingWrite(mpsc::Receiver<ServiceResult<WriteResult>>);
+
+impl PendingWrite {
+    /// Block until the writer has performed this write.
+    pub fn wait(self) -> ServiceResult<WriteResult> {
+        self.0
+            .recv()
+            .unwrap_or_else(|_| Err(ServiceError::PortError("write queue stopped".to_string())))
+    }
+}
+
+/// Limits and defaults taken from the `[serial]` config section.
+///
+/// Shared by every clone of a [`PortService`] so
+/// [`apply_config`](PortService::apply_config) reaches all of them.
+#[derive(Debug, Clone)]
+struct ServiceSettings {
+    max_read_bytes: usize,
+    max_write_repeat_bytes: u64,
+    device_aliases: Vec<DeviceAlias>,
+    default_idle_disconnect_ms: Option<u64>,
+    default_max_open_duration_ms: Option<u64>,
+    timeout_warn_interval: Duration,
+    strict_baud: bool,
+    metrics_history_len: usize,
+    recent_errors_len: usize,
+    /// `None` permits every write; otherwise a write must match one entry
+    write_allowlist: Option<Vec<Regex>>,
+}
+
+/// Compile one `serial.write_allowlist` entry.
+///
+/// Entries must match the whole payload, so a plain literal such as `AT`
+/// permits only `AT`; write `AT.*` to permit every `AT` command.
+pub fn write_allowlist_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+/// Compile the configured allowlist; an empty list permits everything.
+///
+/// An entry that fails to compile is logged and dropped, so it permits
+/// nothing rather than lifting the restriction.
+fn compile_write_allowlist(patterns: &[String]) -> Option<Vec<Regex>> {
+    if patterns.is_empty() {
+        return None;
+    }
+    let compiled = patterns
+        .iter()
+        .filter_map(|pattern| match write_allowlist_regex(pattern) {
+            Ok(re) => Some(re),
+            Err(e) => {
+                tracing::error!(pattern = %pattern, error = %e, "Ignoring invalid write_allowlist entry");
+                None
+            }
+        })
+        .collect();
+    Some(compiled)
+}
+
+impl PortService {
+    /// Create a new port service with the given shared state.
+    pub fn new(state: AppState) -> Self {
+        Self {
+            state,
+            settings: Arc::new(RwLock::new(ServiceSettings {
+                max_read_bytes: DEFAULT_MAX_READ_BYTES,
+                max_write_repeat_bytes: DEFAULT_MAX_WRITE_REPEAT_BYTES,
+                device_aliases: Vec::new(),
+                default_idle_disconnect_ms: None,
+                default_max_open_duration_ms: None,
+                timeout_warn_interval: DEFAULT_TIMEOUT_WARN_INTERVAL,
+                strict_baud: false,
+                metrics_history_len: DEFAULT_METRICS_HISTORY_LEN,
+                recent_errors_len: DEFAULT_RECENT_ERRORS_LEN,
+                write_allowlist: None,
+            })),
+            opener: Arc::new(open_sync_port),
+            cancel: CancelToken::default(),
+            activity: ControlActivity::default(),
+            metrics_history: Arc::default(),
+            recent_errors: Arc::default(),
+            write_queue: Arc::default(),
+            last_config: Arc::default(),
+            last_close: Arc::default(),
+            close_hook: None,
+            max_duration_watchdog: Arc::default(),
+        }
+    }
+
+    fn settings(&self) -> RwLockReadGuard<'_, ServiceSettings> {
+        self.settings.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Builder helper: give this service its own copy of the settings with
+    /// `update` applied, leaving any service it was cloned from untouched.
+    fn with_settings(mut self, update: impl FnOnce(&mut ServiceSettings)) -> Self {
+        let mut settings = self.settings().clone();
+        update(&mut settings);
+        self.settings = Arc::new(RwLock::new(settings));
+        self
+    }
+
+    /// Apply the `[serial]` limits and defaults and the device aliases from
+    /// `config` to this service and every clone of it.
+    ///
+    /// Takes effect for the next operation; open ports keep their settings.
+    pub fn apply_config(&self, config: &Config) {
+        let mut settings = self
+            .settings
+            .write()
+            .unwrap_or_else(PoisonError::into_inner);
+        settings.max_read_bytes = config.serial.max_read_bytes;
+        settings.max_write_repeat_bytes = config.serial.max_write_repeat_bytes;
+        settings.device_aliases = config.device_aliases.clone();
+        settings.default_idle_disconnect_ms = config
+            .serial
+            .default_idle_disconnect_ms
+            .filter(|&ms| ms > 0);
+        settings.default_max_open_duration_ms = config
+            .serial
+            .default_max_open_duration_ms
+            .filter(|&ms| ms > 0);
+        settings.timeout_warn_interval = config.serial.timeout_warn_interval();
+        settings.strict_baud = config.serial.strict_baud;
+        settings.metrics_history_len = config.serial.metrics_history_len;
+        settings.recent_errors_len = config.serial.recent_errors_len;
+        settings.write_allowlist = compile_write_allowlist(&config.serial.write_allowlist);
+    }
+
+    /// Set the maximum number of bytes a single read may request.
+    pub fn with_max_read_bytes(self, max_read_bytes: usize) -> Self {
+        self.with_settings(|s| s.max_read_bytes = max_read_bytes)
+    }
+
+    /// Maximum number of bytes a single read may request.
+    pub fn max_read_bytes(&self) -> usize {
+        self.settings().max_read_bytes
+    }
+
+    /// Set the maximum total payload a single repeated write may send.
+    pub fn with_max_write_repeat_bytes(self, max_write_repeat_bytes: u64) -> Self {
+        self.with_settings(|s| s.max_write_repeat_bytes = max_write_repeat_bytes)
+    }
+
+    /// Restrict writes to payloads matching one of `patterns`; see
+    /// [`write_allowlist_regex`]. An empty list permits every write.
+    pub fn with_write_allowlist(self, patterns: &[String]) -> Self {
+        let allowlist = compile_write_allowlist(patterns);
+        self.with_settings(|s| s.write_allowlist = allowlist)
+    }
+
+    /// Reject `payload` unless the write allowlist permits it.
+    ///
+    /// While an allowlist is active a payload containing `terminator` is
+    /// rejected too, so one permitted command cannot smuggle a second.
+    fn check_write_permitted(&self, payload: &[u8], terminator: Option<&str>) -> ServiceResult<()> {
+        let settings = self.settings();
+        let Some(allowlist) = &settings.write_allowlist else {
+            return Ok(());
         };
+        let text = String::from_utf8_lossy(payload);
+        let embeds_terminator = terminator
+            .filter(|t| !t.is_empty())
+            .is_some_and(|t| find_subslice(payload, t.as_bytes()).is_some());
+        if !embeds_terminator && allowlist.iter().any(|re| re.is_match(&text)) {
+            return Ok(());
+        }
+        tracing::warn!(payload = %text, "Write rejected by write_allowlist");
+        Err(ServiceError::InvalidConfig(
+            "write not permitted".to_string(),
+        ))
+    }
 
-        // Handle result outside borrow scope
-        match result {
-            Ok((data, bytes_read, total)) => Ok(ReadResult {
-                data,
-                bytes_read,
-                bytes_read_total: total,
-                auto_closed: None,
-            }),
-            Err((idle_count, total)) => {
-                // Close the port due to idle timeout
-                *st = PortState::Closed;
-                Ok(ReadResult {
-                    data: String::new(),
-                    bytes_read: 0,
-                    bytes_read_total: total,
-                    auto_closed: Some(AutoCloseInfo {
-                        reason: "idle_timeout".to_string(),
-                        idle_close_count: idle_count,
-                    }),
-                })
+    /// Call `hook` whenever a port closes on its own; see [`CloseHook`].
+    pub fn with_close_hook(mut self, hook: CloseHook) -> Self {
+        self.close_hook = Some(hook);
+        self
+    }
+
+    /// Replace the function used by [`open`](Self::open) to open ports.
+    pub fn with_port_opener(mut self, opener: PortOpener) -> Self {
+        self.opener = opener;
+        self
+    }
+
+    /// Set the idle-disconnect applied to opens that leave `idle_disconnect_ms` unset.
+    pub fn with_default_idle_disconnect_ms(self, ms: Option<u64>) -> Self {
+        self.with_settings(|s| s.default_idle_disconnect_ms = ms.filter(|&ms| ms > 0))
+    }
+
+    /// Resolve a requested idle-disconnect against the server default.
+    ///
+    /// `None` inherits the default, `Some(0)` disables idle-disconnect.
+    pub fn effective_idle_disconnect_ms(&self, requested: Option<u64>) -> Option<u64> {
+        match requested {
+            None => self.settings().default_idle_disconnect_ms,
+            Some(0) => None,
+            Some(ms) => Some(ms),
+        }
+    }
+
+    /// Set the open-duration limit applied to opens that leave
+    /// `max_open_duration_ms` unset.
+    pub fn with_default_max_open_duration_ms(self, ms: Option<u64>) -> Self {
+        self.with_settings(|s| s.default_max_open_duration_ms = ms.filter(|&ms| ms > 0))
+    }
+
+    /// Resolve a requested open-duration limit against the server default.
+    ///
+    /// `None` inherits the default, `Some(0)` disables the limit.
+    pub fn effective_max_open_duration_ms(&self, requested: Option<u64>) -> Option<u64> {
+        match requested {
+            None => self.settings().default_max_open_duration_ms,
+            Some(0) => None,
+            Some(ms) => Some(ms),
+        }
+    }
+
+    /// Set the minimum interval between warnings about consecutive read timeouts.
+    pub fn with_timeout_warn_interval(self, interval: Duration) -> Self {
+        self.with_settings(|s| s.timeout_warn_interval = interval)
+    }
+
+    /// Minimum interval between warnings about consecutive read timeouts.
+    pub fn timeout_warn_interval(&self) -> Duration {
+        self.settings().timeout_warn_interval
+    }
+
+    /// Reject baud rates that fail [`validate_baud`] instead of warning.
+    pub fn with_strict_baud(self, strict: bool) -> Self {
+        self.with_settings(|s| s.strict_baud = strict)
+    }
+
+    /// Run [`validate_baud`], returning the warnings to report.
+    fn check_baud(&self, baud_rate: u32) -> ServiceResult<Vec<String>> {
+        match validate_baud(baud_rate) {
+            None => Ok(Vec::new()),
+            Some(warning) if self.settings().strict_baud => {
+                Err(ServiceError::InvalidConfig(warning))
+            }
+            Some(warning) => {
+                tracing::warn!(baud_rate, "{}", warning);
+                Ok(vec![warning])
             }
         }
     }
 
-    /// Reconfigure the port (close and reopen with new settings).
+    /// Token that [`cancel_read`](Self::cancel_read) sets.
     ///
-    /// If no port_name is provided in the config, uses the currently open port's name.
-    /// This operation resets all metrics (bytes read/written, idle close count).
+    /// Ports opened through this service carry it in their state; code that
+    /// builds `PortState::Open` directly should pass it too so reads on that
+    /// port can be cancelled.
+    pub fn cancel_token(&self) -> CancelToken {
+        self.cancel.clone()
+    }
+
+    /// Link the open port to a session (or unlink it with `None`).
+    ///
+    /// The link survives [`reconfigure`](Self::reconfigure) so a session's
+    /// transcript continues across parameter changes.
     ///
     /// # Errors
     ///
-    /// - `ServiceError::NoPortSpecified` if no port name provided and no port is open
-    /// - `ServiceError::StateLockPoisoned` if the state lock is poisoned
-    /// - `ServiceError::PortError` if the port cannot be opened with new settings
-    pub fn reconfigure(&self, config: ReconfigureConfig) -> ServiceResult<OpenResult> {
-        let mut st = self
-            .state
-            .lock()
-            .map_err(|_| ServiceError::StateLockPoisoned)?;
+    /// - `ServiceError::PortNotOpen` if no port is open
+    pub fn link_session(&self, session_id: Option<String>) -> ServiceResult<()> {
+        match &mut *lock_state(&self.state) {
+            PortState::Open { active_session, .. } => {
+                *active_session = session_id;
+                Ok(())
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
 
-        // Determine target port name
-        let target = match (&config.port_name, &*st) {
-            (Some(p), _) => p.clone(),
-            (None, PortState::Open { config, .. }) => config.port_name.clone(),
-            (None, PortState::Closed) => return Err(ServiceError::NoPortSpecified),
+    /// Interrupt a long-running read loop (`wait_for_pattern`, `read_line`,
+    /// `loopback_test`, idle-gap reads) on the open port.
+    ///
+    /// Does not take the state lock, so it works while a read holds it. The
+    /// interrupted call returns `ServiceError::Cancelled`. With no read in
+    /// progress this has no effect: each read clears the flag when it starts.
+    pub fn cancel_read(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Record a control-plane (MCP tool) call for the inactivity watchdog.
+    ///
+    /// The call counts as running until the returned guard is dropped.
+    pub fn begin_control_call(&self) -> ControlCallGuard {
+        self.activity.begin()
+    }
+
+    /// Close the open port if no control call has run for `window`.
+    ///
+    /// Returns whether a port was closed. Blocks while another operation
+    /// holds the port.
+    pub fn close_if_inactive(&self, window: Duration) -> bool {
+        let inactive = || self.activity.idle_for().is_some_and(|idle| idle >= window);
+        if !inactive() {
+            return false;
+        }
+        let mut st = lock_state(&self.state);
+        // A call may have started while we waited for the lock
+        if !inactive() {
+            return false;
+        }
+        match &*st {
+            PortState::Open { config, .. } => {
+                tracing::warn!(
+                    port = %config.port_name,
+                    window_ms = window.as_millis() as u64,
+                    "No MCP activity within serial.inactivity_close_ms; closing port"
+                );
+            }
+            PortState::Closed => return false,
+        }
+        self.auto_close(&mut st, CloseReason::Inactivity);
+        true
+    }
+
+    /// Close the open port if it has been open longer than its
+    /// `max_open_duration_ms`.
+    ///
+    /// Returns whether a port was closed. Blocks while another operation
+    /// holds the port.
+    pub fn close_if_expired(&self) -> bool {
+        let mut st = lock_state(&self.state);
+        self.close_if_over_max_duration(&mut st)
+    }
+
+    /// [`close_if_expired`](Self::close_if_expired) without waiting for a busy
+    /// port; the operation holding it checks the limit when it finishes.
+    ///
+    /// Returns whether the port, if open, still has a limit to watch.
+    fn watch_max_duration(&self) -> bool {
+        let mut st = match self.state.try_lock() {
+            Ok(st) => st,
+            Err(TryLockError::Poisoned(e)) => e.into_inner(),
+            Err(TryLockError::WouldBlock) => return true,
         };
+        let limited = matches!(
+            &*st,
+            PortState::Open { config, .. } if config.max_open_duration_ms.is_some()
+        );
+        limited && !self.close_if_over_max_duration(&mut st)
+    }
 
-        // Build port configuration
-        let port_config = PortConfiguration {
-            baud_rate: config.baud_rate,
-            data_bits: Self::convert_data_bits(config.data_bits),
-            parity: Self::convert_parity(config.parity),
-            stop_bits: Self::convert_stop_bits(config.stop_bits),
-            flow_control: Self::convert_flow_control(config.flow_control),
-            timeout: Duration::from_millis(config.timeout_ms),
+    /// Start the max-duration watchdog for a port just opened with a limit,
+    /// when running inside a Tokio runtime. Reads and writes enforce the
+    /// limit either way.
+    fn watch_open_duration(&self, config: &PortConfig) {
+        if config.max_open_duration_ms.is_some() && tokio::runtime::Handle::try_current().is_ok() {
+            self.spawn_max_duration_watchdog();
+        }
+    }
+
+    /// [`close_if_expired`](Self::close_if_expired) for callers that already
+    /// hold the state lock.
+    fn close_if_over_max_duration(&self, st: &mut PortState) -> bool {
+        match &*st {
+            PortState::Open {
+                config,
+                open_started,
+                ..
+            } => {
+                let Some(limit_ms) = config.max_open_duration_ms else {
+                    return false;
+                };
+                if open_started.elapsed() < Duration::from_millis(limit_ms) {
+                    return false;
+                }
+                tracing::warn!(
+                    port = %config.port_name,
+                    limit_ms,
+                    "Port open longer than max_open_duration_ms; closing port"
+                );
+            }
+            PortState::Closed => return false,
+        }
+        self.auto_close(st, CloseReason::MaxDuration);
+        true
+    }
+
+    /// Close the port in `st` if it is over its open-duration limit and
+    /// describe that as the result of a read, as for an idle auto-close.
+    fn expired_read_result(&self, st: &mut PortState) -> Option<ReadResult> {
+        let (open_id, bytes_read_total, idle_close_count) = match &*st {
+            PortState::Open {
+                open_id,
+                bytes_read_total,
+                idle_close_count,
+                ..
+            } => (*open_id, *bytes_read_total, *idle_close_count),
+            PortState::Closed => return None,
+        };
+        if !self.close_if_over_max_duration(st) {
+            return None;
+        }
+        Some(ReadResult {
+            open_id,
+            data: String::new(),
+            bytes_read: 0,
+            bytes_read_total,
+            framing_errors: None,
+            auto_closed: Some(AutoCloseInfo {
+                reason: CloseReason::MaxDuration,
+                idle_close_count,
+            }),
+            termination: None,
+            raw: None,
+            hex: None,
+        })
+    }
+
+    /// Close the port in `st` on the server's own initiative (idle,
+    /// inactivity or open-duration limit), keeping its settings for [`reconnect`](Self::reconnect).
+    ///
+    /// Callers pass the state they already hold locked.
+    pub fn auto_close(&self, st: &mut PortState, reason: CloseReason) {
+        let closing = match &*st {
+            PortState::Open {
+                config,
+                active_session,
+                ..
+            } => {
+                self.remember_config(config);
+                Some((config.port_name.clone(), active_session.clone()))
+            }
+            PortState::Closed => None,
+        };
+        let close = self.record_close(st, reason);
+        *st = PortState::Closed;
+        if let (Some(hook), Some((port_name, session_id)), Some(close)) =
+            (&self.close_hook, closing, close)
+        {
+            hook(&AutoCloseEvent {
+                port_name,
+                session_id,
+                close,
+            });
+        }
+    }
+
+    /// Keep the final metrics of the port in `st`, about to be closed for
+    /// `reason`, as the [`last_close`](Self::last_close).
+    ///
+    /// Returns `None` without recording anything if no port is open.
+    fn record_close(&self, st: &PortState, reason: CloseReason) -> Option<CloseResult> {
+        let final_metrics = Self::port_metrics(st)?;
+        if let PortState::Open { config, .. } = st {
+            tracing::info!(port = %config.port_name, ?reason, "Port closed");
+        }
+        let result = CloseResult {
+            message: "closed".to_string(),
+            reason,
+            final_metrics: Some(final_metrics),
+        };
+        *self
+            .last_close
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(result.clone());
+        Some(result)
+    }
+
+    /// Metrics of the port in `st`, or `None` if it is closed.
+    fn port_metrics(st: &PortState) -> Option<PortMetrics> {
+        match st {
+            PortState::Open {
+                bytes_read_total,
+                bytes_written_total,
+                idle_close_count,
+                open_started,
+                open_started_at,
+                last_activity,
+                timeout_streak,
+                ..
+            } => Some(PortMetrics {
+                bytes_read_total: *bytes_read_total,
+                bytes_written_total: *bytes_written_total,
+                idle_close_count: *idle_close_count,
+                open_duration_ms: open_started.elapsed().as_millis() as u64,
+                last_activity_ms: last_activity.elapsed().as_millis() as u64,
+                open_started_at: *open_started_at,
+                last_activity_at: wall_clock_at(*open_started, *open_started_at, *last_activity),
+                timeout_streak: *timeout_streak,
+            }),
+            PortState::Closed => None,
+        }
+    }
+
+    /// How the most recently open port was closed, with its final metrics.
+    ///
+    /// `None` until a port has been closed.
+    pub fn last_close(&self) -> Option<CloseResult> {
+        self.last_close
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    fn remember_config(&self, config: &PortConfig) {
+        *self
+            .last_config
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(config.clone());
+    }
+
+    /// Spawn a task that calls [`close_if_inactive`](Self::close_if_inactive)
+    /// every quarter `window` (at least every 10 ms).
+    pub fn spawn_inactivity_watchdog(&self, window: Duration) -> tokio::task::JoinHandle<()> {
+        let service = self.clone();
+        let period = (window / 4).max(Duration::from_millis(10));
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                let service = service.clone();
+                if let Err(e) =
+                    tokio::task::spawn_blocking(move || service.close_if_inactive(window)).await
+                {
+                    tracing::warn!(error = %e, "Inactivity check failed");
+                }
+            }
+        })
+    }
+
+    /// Spawn a task that closes the open port once it is over its
+    /// `max_open_duration_ms`, checking every [`MAX_DURATION_CHECK_INTERVAL`]
+    /// so a port is closed on time even when nothing reads or writes it.
+    ///
+    /// Opening a port with a limit starts the task, which skips a busy port
+    /// and stops once no open port has a limit. Returns `None` if the task is
+    /// already running.
+    pub fn spawn_max_duration_watchdog(&self) -> Option<tokio::task::JoinHandle<()>> {
+        use std::sync::atomic::Ordering;
+
+        if self.max_duration_watchdog.swap(true, Ordering::SeqCst) {
+            return None;
+        }
+        let service = self.clone();
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(MAX_DURATION_CHECK_INTERVAL);
+            loop {
+                ticker.tick().await;
+                let check = service.clone();
+                match tokio::task::spawn_blocking(move || check.watch_max_duration()).await {
+                    Ok(true) => continue,
+                    Ok(false) => {}
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Open-duration check failed");
+                        continue;
+                    }
+                }
+                service.max_duration_watchdog.store(false, Ordering::SeqCst);
+                // A port opened with a limit just now saw the task still running
+                let check = service.clone();
+                let limited = tokio::task::spawn_blocking(move || check.watch_max_duration())
+                    .await
+                    .unwrap_or(false);
+                if !limited || service.max_duration_watchdog.swap(true, Ordering::SeqCst) {
+                    break;
+                }
+            }
+        }))
+    }
+
+    /// Spawn a task that reads the open port every `interval` and hands each
+    /// non-empty read (or idle auto-close) to `on_data`.
+    ///
+    /// The task keeps polling while no port is open, so a stream survives a
+    /// close/reopen. It stops when `on_data` returns `false` or the task is
+    /// aborted. Data it reads is not returned by other reads.
+    pub fn spawn_read_stream<F>(
+        &self,
+        interval: Duration,
+        encoding: ReadEncoding,
+        mut on_data: F,
+    ) -> tokio::task::JoinHandle<()>
+    where
+        F: FnMut(ReadResult) -> bool + Send + 'static,
+    {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let reader = service.clone();
+                let max_bytes = DEFAULT_READ_BYTES.min(reader.max_read_bytes());
+                match tokio::task::spawn_blocking(move || reader.read_encoded(max_bytes, encoding))
+                    .await
+                {
+                    Ok(Ok(result)) if result.bytes_read > 0 || result.auto_closed.is_some() => {
+                        if !on_data(result) {
+                            break;
+                        }
+                    }
+                    Ok(Ok(_)) | Ok(Err(ServiceError::PortNotOpen)) => {}
+                    Ok(Err(e)) => tracing::debug!(error = %e, "Read stream poll failed"),
+                    Err(e) => {
+                        tracing::warn!(error = %e, "Read stream task failed");
+                        break;
+                    }
+                }
+            }
+        })
+    }
+
+    /// Set how many samples the metrics history keeps.
+    pub fn with_metrics_history_len(self, len: usize) -> Self {
+        self.with_settings(|s| s.metrics_history_len = len)
+    }
+
+    /// Append a [`MetricsSnapshot`] of the open port to the history,
+    /// dropping the oldest samples beyond `serial.metrics_history_len`.
+    ///
+    /// Returns whether a sample was taken; nothing is recorded while the port
+    /// is closed.
+    pub fn record_metrics_snapshot(&self) -> bool {
+        let snapshot = match &*lock_state(&self.state) {
+            PortState::Open {
+                open_id,
+                bytes_read_total,
+                bytes_written_total,
+                idle_close_count,
+                timeout_streak,
+                ..
+            } => MetricsSnapshot {
+                timestamp: chrono::Utc::now(),
+                open_id: *open_id,
+                bytes_read_total: *bytes_read_total,
+                bytes_written_total: *bytes_written_total,
+                idle_close_count: *idle_close_count,
+                timeout_streak: *timeout_streak,
+            },
+            PortState::Closed => return false,
+        };
+        let len = self.settings().metrics_history_len;
+        let mut history = self
+            .metrics_history
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        history.push_back(snapshot);
+        while history.len() > len {
+            history.pop_front();
+        }
+        true
+    }
+
+    /// Recorded metrics samples, oldest first.
+    pub fn metrics_history(&self) -> Vec<MetricsSnapshot> {
+        self.metrics_history
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Spawn a task that calls
+    /// [`record_metrics_snapshot`](Self::record_metrics_snapshot) every `interval`.
+    pub fn spawn_metrics_history(&self, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let service = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let service = service.clone();
+                if let Err(e) =
+                    tokio::task::spawn_blocking(move || service.record_metrics_snapshot()).await
+                {
+                    tracing::warn!(error = %e, "Metrics snapshot failed");
+                }
+            }
+        })
+    }
+
+    /// Set how many failures the recent-errors buffer keeps.
+    pub fn with_recent_errors_len(self, len: usize) -> Self {
+        self.with_settings(|s| s.recent_errors_len = len)
+    }
+
+    /// Keep the error of `result`, if it is a port failure, in the
+    /// recent-errors buffer, dropping the oldest beyond `serial.recent_errors_len`.
+    ///
+    /// Caller mistakes (port not open, invalid arguments) and cancelled reads
+    /// are not failures and are not kept. Neither are plain timeouts, which
+    /// polling a quiet port produces all the time and which would push the
+    /// real failures out of the buffer.
+    fn note_error<T>(&self, operation: &str, result: ServiceResult<T>) -> ServiceResult<T> {
+        if let Err(e) = &result {
+            if matches!(e, ServiceError::Timeout(_)) {
+                tracing::debug!(operation, error = %e, "Port operation timed out");
+            } else if e.is_failure() {
+                tracing::error!(operation, error = %e, "Port operation failed");
+                let event = ErrorEvent {
+                    timestamp: chrono::Utc::now(),
+                    operation: operation.to_string(),
+                    kind: e.kind().to_string(),
+                    message: e.to_string(),
+                };
+                let len = self.settings().recent_errors_len;
+                let mut errors = self
+                    .recent_errors
+                    .lock()
+                    .unwrap_or_else(PoisonError::into_inner);
+                errors.push_back(event);
+                while errors.len() > len {
+                    errors.pop_front();
+                }
+            }
+        }
+        result
+    }
+
+    /// Recent port failures, oldest first.
+    pub fn recent_errors(&self) -> Vec<ErrorEvent> {
+        self.recent_errors
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .iter()
+            .cloned()
+            .collect()
+    }
+
+    /// Set the device aliases that `open`/`reconfigure` accept in place of a port path.
+    pub fn with_device_aliases(self, device_aliases: Vec<DeviceAlias>) -> Self {
+        self.with_settings(|s| s.device_aliases = device_aliases)
+    }
+
+    /// Open a serial port with the specified configuration.
+    ///
+    /// `port_name` may be a configured device alias, which is resolved to the
+    /// current path of the matching USB device.
+    ///
+    /// With `open_retries` set, opens that fail with a transient error (port
+    /// busy or permission denied) are retried after `open_retry_delay_ms`.
+    /// A missing port fails immediately.
+    ///
+    /// With `require_response` set, the probe is written once the port is
+    /// open and any reply within its timeout counts as an answer. If nothing
+    /// arrives the port is closed again, so a driver with no device behind it
+    /// does not look like a working connection.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::PortAlreadyOpen` if a port is already open
+    /// - `ServiceError::InvalidConfig` if `serial.strict_baud` is set and the baud rate fails [`validate_baud`],
+    ///   `require_response` has an empty probe or a zero timeout, or a poll
+    ///   `read_strategy` has a zero `interval_ms`
+    /// - `ServiceError::PortError` if the port cannot be opened or an alias matches no device
+    /// - `ServiceError::DeviceNotResponding` if the probe gets no reply
+    pub fn open(&self, config: OpenConfig) -> ServiceResult<OpenResult> {
+        self.note_error("open", self.open_now(config))
+    }
+
+    fn open_now(&self, mut config: OpenConfig) -> ServiceResult<OpenResult> {
+        if let Some(probe) = &config.require_response {
+            if probe.probe.is_empty() || probe.timeout_ms == 0 {
+                return Err(ServiceError::InvalidConfig(
+                    "require_response needs a non-empty probe and a timeout_ms above 0".to_string(),
+                ));
+            }
+            // The probe is written as-is, trailing terminator included
+            let terminator = config.terminator.as_deref().filter(|t| !t.is_empty());
+            let payload = terminator
+                .and_then(|t| probe.probe.strip_suffix(t))
+                .unwrap_or(&probe.probe);
+            self.check_write_permitted(payload.as_bytes(), terminator)?;
+        }
+        // A zero interval would spin the reading thread flat out
+        if config.read_strategy == (ReadStrategy::Poll { interval_ms: 0 }) {
+            return Err(ServiceError::InvalidConfig(
+                "read_strategy poll needs an interval_ms above 0".to_string(),
+            ));
+        }
+        let warnings = self.check_baud(config.baud_rate)?;
+        config.port_name = self.resolve_port_name(&config.port_name)?;
+        let mut st = lock_state(&self.state);
+
+        // Check if port is already open
+        if matches!(&*st, PortState::Open { .. }) {
+            return Err(ServiceError::PortAlreadyOpen);
+        }
+
+        // Convert config enums to port module types
+        let port_config = PortConfiguration {
+            baud_rate: config.baud_rate,
+            data_bits: Self::convert_data_bits(config.data_bits),
+            parity: Self::convert_parity(config.parity),
+            stop_bits: Self::convert_stop_bits(config.stop_bits),
+            flow_control: Self::convert_flow_control(config.flow_control),
+            timeout: Duration::from_millis(config.timeout_ms),
+        };
+
+        // Open the port, retrying transient failures if configured
+        let retries = config.open_retries.unwrap_or(0);
+        let retry_delay = Duration::from_millis(
+            config
+                .open_retry_delay_ms
+                .unwrap_or(DEFAULT_OPEN_RETRY_DELAY_MS),
+        );
+        let mut attempt = 0;
+        let mut port = loop {
+            match (self.opener)(&config.port_name, port_config.clone()) {
+                Ok(port) => break port,
+                Err(e) if attempt < retries && e.is_transient_open_error() => {
+                    attempt += 1;
+                    tracing::debug!(
+                        port = %config.port_name,
+                        attempt,
+                        error = %e,
+                        "Transient open failure; retrying"
+                    );
+                    std::thread::sleep(retry_delay);
+                }
+                Err(e) => return Err(e.into()),
+            }
+        };
+
+        // Dropping the port on failure closes it again
+        let (probe_written, probe_response) = match &config.require_response {
+            Some(probe) => {
+                let response = Self::probe_device(port.as_mut(), probe, port_config.timeout)?;
+                (probe.probe.len() as u64, Some(response))
+            }
+            None => (0, None),
+        };
+
+        // Update state
+        let open_id = Uuid::new_v4();
+        let opened = PortConfig {
+            port_name: config.port_name.clone(),
+            baud_rate: config.baud_rate,
+            timeout_ms: config.timeout_ms,
+            data_bits: config.data_bits,
+            parity: config.parity,
+            stop_bits: config.stop_bits,
+            flow_control: config.flow_control,
+            terminator: config.terminator,
+            idle_disconnect_ms: self.effective_idle_disconnect_ms(config.idle_disconnect_ms),
+            read_idle_gap_ms: config.read_idle_gap_ms,
+            write_checksum: config.write_checksum,
+            half_duplex_echo: config.half_duplex_echo,
+            flush_on_write: config.flush_on_write,
+            auto_detect_terminator: config.auto_detect_terminator,
+            mask_parity_bit: config.mask_parity_bit,
+            strip_echo: config.strip_echo,
+            strip_prompt: config.strip_prompt,
+            max_open_duration_ms: self.effective_max_open_duration_ms(config.max_open_duration_ms),
+            read_strategy: config.read_strategy,
+        };
+        *st = PortState::Open {
+            port,
+            config: opened.clone(),
+            last_activity: std::time::Instant::now(),
+            timeout_streak: 0,
+            bytes_read_total: probe_response.as_ref().map_or(0, |r| r.len() as u64),
+            bytes_written_total: probe_written,
+            idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer: Vec::new(),
+            open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id,
+            timeout_log: crate::state::TimeoutLogThrottle::default(),
+            cancel: self.cancel.clone(),
+            active_session: None,
+            terminator_detect: opened
+                .auto_detect_terminator
+                .then(TerminatorDetector::default),
+            pending_echo: None,
+        };
+        self.remember_config(&opened);
+        self.watch_open_duration(&opened);
+
+        Ok(OpenResult {
+            port_name: config.port_name,
+            baud_rate: config.baud_rate,
+            message: "opened".to_string(),
+            open_id,
+            active_session: None,
+            config: opened,
+            warnings,
+            probe_response: probe_response
+                .map(|response| String::from_utf8_lossy(&response).into_owned()),
+        })
+    }
+
+    /// Write `probe` and wait for the first bytes of a reply, then restore
+    /// the port's read timeout to `timeout`.
+    fn probe_device(
+        port: &mut dyn SerialPortAdapter,
+        probe: &ResponseProbe,
+        timeout: Duration,
+    ) -> ServiceResult<Vec<u8>> {
+        let probe_timeout = Duration::from_millis(probe.timeout_ms);
+        let deadline = std::time::Instant::now() + probe_timeout;
+        let (_, outcome) = Self::write_all(port, probe.probe.as_bytes(), probe_timeout);
+        outcome?;
+
+        let mut buffer = [0u8; 256];
+        let outcome = loop {
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                break Err(ServiceError::DeviceNotResponding {
+                    timeout_ms: probe.timeout_ms,
+                });
+            }
+            let wait = (deadline - now).min(WAIT_POLL_INTERVAL);
+            if let Err(e) = port.set_timeout(wait) {
+                break Err(ServiceError::from(e));
+            }
+            match port.read_bytes(&mut buffer) {
+                Ok(0) => {}
+                Ok(n) => break Ok(buffer[..n].to_vec()),
+                Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(wait.min(Duration::from_millis(5)));
+                }
+                Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(PortError::Timeout(_)) | Err(PortError::Framing(_)) => {}
+                Err(e) => break Err(ServiceError::from(e)),
+            }
+        };
+
+        port.set_timeout(timeout)?;
+        outcome
+    }
+
+    /// Open the port configured in `[serial.auto_open]`, if any.
+    ///
+    /// `serial.port_aliases` are resolved first; device aliases are resolved
+    /// by [`open`](Self::open). Returns `None` when no port is configured.
+    pub fn auto_open(&self, serial: &SerialConfig) -> Option<ServiceResult<OpenResult>> {
+        let mut config = serial.auto_open.clone()?;
+        config.port_name = serial.resolve_port(&config.port_name);
+        Some(self.open(config))
+    }
+
+    /// Close the currently open port.
+    ///
+    /// This operation is idempotent - closing an already-closed port succeeds.
+    /// An explicit close also forgets the settings kept for
+    /// [`reconnect`](Self::reconnect).
+    pub fn close(&self) -> ServiceResult<CloseResult> {
+        let mut st = lock_state(&self.state);
+        Ok(self.close_locked(&mut st))
+    }
+
+    /// [`close`](Self::close) with the state lock already held.
+    fn close_locked(&self, st: &mut PortState) -> CloseResult {
+        *self
+            .last_config
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+
+        let result = self
+            .record_close(st, CloseReason::Manual)
+            .unwrap_or_else(|| CloseResult {
+                message: "already closed".to_string(),
+                reason: CloseReason::Manual,
+                final_metrics: None,
+            });
+        *st = PortState::Closed;
+        result
+    }
+
+    /// Return the serial stack to its startup state, for recovering from a
+    /// wedged port or stuck reader without restarting the process.
+    ///
+    /// Cancels any read in progress so it releases the port, then closes the
+    /// port as [`close`](Self::close) does. An operation that still holds the
+    /// port after [`RESET_LOCK_WAIT`] is not waited for: the port is left open
+    /// and `port_busy` reported. The writer thread is retired either way; the
+    /// next write starts a fresh one. Sessions are not the service's and are
+    /// left alone.
+    pub fn reset(&self) -> SubsystemResetResult {
+        self.cancel_read();
+        let deadline = std::time::Instant::now() + RESET_LOCK_WAIT;
+        let st = loop {
+            match self.state.try_lock() {
+                Ok(guard) => break Some(guard),
+                // Recover the poisoned lock the usual way
+                Err(TryLockError::Poisoned(_)) => break Some(lock_state(&self.state)),
+                Err(TryLockError::WouldBlock) if std::time::Instant::now() < deadline => {
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Err(TryLockError::WouldBlock) => break None,
+            }
+        };
+        let port_busy = st.is_none();
+        let closed = st
+            .map(|mut st| self.close_locked(&mut st))
+            .filter(|result| result.final_metrics.is_some());
+        // Dropping the sender ends the old writer once its queue drains
+        let writer_restarted = self
+            .write_queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take()
+            .is_some();
+        if port_busy {
+            tracing::warn!("Serial subsystem reset could not lock the port; it stays open");
+        }
+        tracing::info!(
+            port_closed = closed.is_some(),
+            port_busy,
+            writer_restarted,
+            "Serial subsystem reset"
+        );
+        SubsystemResetResult {
+            closed,
+            port_busy,
+            writer_restarted,
+        }
+    }
+
+    /// Reopen the port with the settings it had when it was last open.
+    ///
+    /// Settings are kept from every open and reconfigure, and from the open
+    /// port when it is closed for idleness or inactivity, so recovering from
+    /// an automatic close needs no arguments. Only an explicit
+    /// [`close`](Self::close) forgets them. The session link is not restored.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::NoPortSpecified` if no port has been open since the last explicit close
+    /// - Any error from [`open`](Self::open), including `ServiceError::PortAlreadyOpen`
+    pub fn reconnect(&self) -> ServiceResult<OpenResult> {
+        let config = self
+            .last_config
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+            .ok_or(ServiceError::NoPortSpecified)?;
+        let mut result = self.open(config.into())?;
+        result.message = "reconnected".to_string();
+        Ok(result)
+    }
+
+    /// Write data to the open port.
+    ///
+    /// If a terminator is configured and the data doesn't end with it,
+    /// the terminator will be automatically appended. If a write checksum is
+    /// configured, it is appended after the payload and before the terminator.
+    /// With `flush_on_write`, the call blocks until the bytes have left the
+    /// driver's transmit buffer.
+    ///
+    /// The write goes through [`enqueue_write`](Self::enqueue_write), so
+    /// concurrent callers are written whole and in the order they called.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if the write or flush fails
+    pub fn write(&self, data: &str) -> ServiceResult<WriteResult> {
+        self.write_bytes(data.as_bytes())
+    }
+
+    /// Write raw bytes to the open port, as [`write`](Self::write) does for
+    /// text.
+    pub fn write_bytes(&self, data: &[u8]) -> ServiceResult<WriteResult> {
+        self.enqueue_write(data).wait()
+    }
+
+    /// Write raw bytes, choosing for this call whether the configured
+    /// terminator is appended; `None` keeps the default of appending it.
+    ///
+    /// With `Some(false)` `data` goes out verbatim: no checksum is computed,
+    /// no terminator is added, and a terminator already at the end of `data`
+    /// is not stripped. This suits commands assembled over several writes,
+    /// where a checksum over one fragment would be meaningless.
+    pub fn write_bytes_with(
+        &self,
+        data: &[u8],
+        append_terminator: Option<bool>,
+    ) -> ServiceResult<WriteResult> {
+        self.submit_write(data, append_terminator.unwrap_or(true))
+            .wait()
+    }
+
+    /// The exact bytes [`write_bytes_with`](Self::write_bytes_with) would
+    /// put on the wire for `data`, without writing anything.
+    ///
+    /// `data` is decoded per `encoding`, then checked against the write
+    /// allowlist and given the open port's checksum and terminator exactly as
+    /// a real write with the same `append_terminator` would be.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if `data` does not decode as `encoding`
+    ///   or the allowlist rejects it
+    /// - `ServiceError::PortNotOpen` if no port is open
+    pub fn preview_write(
+        &self,
+        data: &str,
+        encoding: WriteEncoding,
+        append_terminator: Option<bool>,
+    ) -> ServiceResult<Vec<u8>> {
+        let bytes = encoding.decode(data).map_err(ServiceError::InvalidConfig)?;
+        match &*lock_state(&self.state) {
+            PortState::Open { config, .. } => Ok(self
+                .encode_checked_write(config, &bytes, append_terminator.unwrap_or(true))?
+                .0),
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// The bytes a write of `data` puts on the wire and the checksum among
+    /// them, once the allowlist has permitted it. Writes and previews share
+    /// this, so a preview shows what the write will do.
+    fn encode_checked_write(
+        &self,
+        config: &PortConfig,
+        data: &[u8],
+        append_terminator: bool,
+    ) -> ServiceResult<(Vec<u8>, Option<Vec<u8>>)> {
+        if !append_terminator {
+            self.check_write_permitted(data, config.terminator.as_deref())?;
+            return Ok((data.to_vec(), None));
+        }
+        self.check_write_permitted(config.write_payload(data), config.terminator.as_deref())?;
+        Ok(config.encode_write(data))
+    }
+
+    /// Queue `data` for the port's single writer and return without waiting.
+    ///
+    /// Payloads are written one at a time in submission order; each is sent
+    /// completely before the next starts. The writer thread is started on the
+    /// first call and exits once every clone of the service is dropped.
+    pub fn enqueue_write(&self, data: impl AsRef<[u8]>) -> PendingWrite {
+        self.submit_write(data.as_ref(), true)
+    }
+
+    fn submit_write(&self, data: &[u8], append_terminator: bool) -> PendingWrite {
+        let (reply, result) = mpsc::sync_channel(1);
+        let mut job = WriteJob {
+            // Without a handle on the queue the writer does not keep itself alive
+            service: Self {
+                write_queue: Arc::default(),
+                ..self.clone()
+            },
+            data: data.to_vec(),
+            append_terminator,
+            reply,
+        };
+        let mut queue = self
+            .write_queue
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        loop {
+            let tx = queue.get_or_insert_with(Self::spawn_writer);
+            match tx.send(job) {
+                Ok(()) => break,
+                // The writer died (a write panicked); start a fresh one
+                Err(mpsc::SendError(returned)) => {
+                    job = returned;
+                    *queue = None;
+                }
+            }
+        }
+        PendingWrite(result)
+    }
+
+    /// Start the writer thread and return the sender feeding it.
+    fn spawn_writer() -> mpsc::Sender<WriteJob> {
+        let (tx, rx) = mpsc::channel::<WriteJob>();
+        std::thread::spawn(move || {
+            for job in rx {
+                let service = &job.service;
+                let result = service
+                    .note_error("write", service.write_now(&job.data, job.append_terminator));
+                let _ = job.reply.send(result);
+            }
+        });
+        tx
+    }
+
+    /// Perform one write while holding the state lock.
+    fn write_now(&self, data: &[u8], append_terminator: bool) -> ServiceResult<WriteResult> {
+        let mut st = lock_state(&self.state);
+        // Past its limit the port is closed rather than written
+        self.close_if_over_max_duration(&mut st);
+
+        match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                bytes_written_total,
+                framing_errors,
+                open_id,
+                pending_echo,
+                ..
+            } => {
+                // Prepare data with checksum and terminator if configured
+                let (write_data, checksum) =
+                    self.encode_checked_write(config, data, append_terminator)?;
+                if config.strip_echo {
+                    let payload = if append_terminator {
+                        config.write_payload(data)
+                    } else {
+                        data
+                    };
+                    *pending_echo = Some(String::from_utf8_lossy(payload).into_owned());
+                }
+                let terminator_bytes = if append_terminator {
+                    config.terminator.as_ref().map_or(0, String::len)
+                } else {
+                    0
+                };
+                let payload_bytes =
+                    write_data.len() - terminator_bytes - checksum.as_ref().map_or(0, Vec::len);
+
+                // Write to port; bytes sent before a failure still count
+                let (bytes, flow_paused, outcome) = Self::write_all_observed(
+                    port.as_mut(),
+                    &write_data,
+                    Duration::from_millis(config.timeout_ms),
+                );
+
+                // Update metrics
+                *bytes_written_total += bytes as u64;
+                *last_activity = std::time::Instant::now();
+                outcome?;
+
+                let flush_duration_ms = if config.flush_on_write {
+                    let started = std::time::Instant::now();
+                    port.flush()?;
+                    Some(started.elapsed().as_millis() as u64)
+                } else {
+                    None
+                };
+
+                // On a half-duplex bus our own transmission comes back first
+                let echo_matched = if config.half_duplex_echo {
+                    Some(Self::consume_echo(
+                        port.as_mut(),
+                        &write_data[..bytes],
+                        Duration::from_millis(config.timeout_ms),
+                        framing_errors,
+                    )?)
+                } else {
+                    None
+                };
+
+                Ok(WriteResult {
+                    open_id: *open_id,
+                    payload_bytes,
+                    terminator_bytes,
+                    bytes_written: bytes,
+                    bytes_written_total: *bytes_written_total,
+                    checksum: checksum
+                        .map(|sum| sum.iter().map(|b| format!("{:02X}", b)).collect::<String>()),
+                    echo_matched,
+                    flush_duration_ms,
+                    flow_paused_ms: flow_paused.map(|d| d.as_millis() as u64),
+                })
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// Write the same payload `count` times, optionally pausing `delay_ms`
+    /// between writes.
+    ///
+    /// All writes happen under a single state lock, so no other operation can
+    /// interleave. The checksum and terminator are appended to each repetition as in
+    /// [`write`](Self::write). The total payload is checked against the
+    /// repeat cap before anything is written. Since the pauses hold the lock,
+    /// `delay_ms` is capped at [`MAX_WRITE_REPEAT_DELAY_MS`] and the pauses
+    /// together at [`MAX_WRITE_REPEAT_DURATION_MS`].
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if `count` is zero, or the total or a
+    ///   delay exceeds its cap
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if a write operation fails
+    /// - `ServiceError::Cancelled` if [`cancel_read`](Self::cancel_read) is called
+    pub fn write_repeat(
+        &self,
+        data: &str,
+        count: u32,
+        delay_ms: u64,
+    ) -> ServiceResult<WriteRepeatResult> {
+        if count == 0 {
+            return Err(ServiceError::InvalidConfig(
+                "count must be greater than 0".to_string(),
+            ));
+        }
+        if delay_ms > MAX_WRITE_REPEAT_DELAY_MS {
+            return Err(ServiceError::InvalidConfig(format!(
+                "delay_ms {} exceeds the maximum of {} ms",
+                delay_ms, MAX_WRITE_REPEAT_DELAY_MS
+            )));
+        }
+        let paused = delay_ms.saturating_mul(u64::from(count - 1));
+        if paused > MAX_WRITE_REPEAT_DURATION_MS {
+            return Err(ServiceError::InvalidConfig(format!(
+                "total delay {} ms exceeds the maximum of {} ms",
+                paused, MAX_WRITE_REPEAT_DURATION_MS
+            )));
+        }
+
+        let mut st = lock_state(&self.state);
+
+        match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                bytes_written_total,
+                cancel,
+                ..
+            } => {
+                self.check_write_permitted(
+                    config.write_payload(data.as_bytes()),
+                    config.terminator.as_deref(),
+                )?;
+                let (write_data, _) = config.encode_write(data);
+
+                let total = write_data.len() as u64 * count as u64;
+                let limit = self.settings().max_write_repeat_bytes;
+                if total > limit {
+                    return Err(ServiceError::InvalidConfig(format!(
+                        "total payload {} bytes exceeds repeat limit of {} bytes",
+                        total, limit
+                    )));
+                }
+
+                cancel.reset();
+                let started = std::time::Instant::now();
+                let mut written = 0u64;
+                for i in 0..count {
+                    if i > 0 && delay_ms > 0 {
+                        // Pause in slices so a cancel is not held up by the delay
+                        let resume = std::time::Instant::now() + Duration::from_millis(delay_ms);
+                        loop {
+                            let left = resume.saturating_duration_since(std::time::Instant::now());
+                            if left.is_zero() || cancel.is_cancelled() {
+                                break;
+                            }
+                            std::thread::sleep(left.min(LONG_POLL_SLICE));
+                        }
+                    }
+                    if cancel.is_cancelled() {
+                        return Err(ServiceError::Cancelled);
+                    }
+                    let (bytes, outcome) = Self::write_all(
+                        port.as_mut(),
+                        &write_data,
+                        Duration::from_millis(config.timeout_ms),
+                    );
+                    written += bytes as u64;
+                    *bytes_written_total += bytes as u64;
+                    *last_activity = std::time::Instant::now();
+                    outcome?;
+                }
+
+                Ok(WriteRepeatResult {
+                    count,
+                    bytes_written: written,
+                    bytes_written_total: *bytes_written_total,
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                })
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// Read from the open port until `pattern` matches the received text or
+    /// `timeout_ms` elapses.
+    ///
+    /// Received bytes accumulate (as lossy UTF-8) and the regex is applied to
+    /// the whole accumulation after every read. Bytes received after the match
+    /// within the same read are included in `data` but not consumed further.
+    /// The accumulation is capped at the read limit; older bytes are dropped.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if the pattern is invalid or too large
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if a non-timeout read error occurs
+    pub fn wait_for_pattern(
+        &self,
+        pattern: &str,
+        timeout_ms: u64,
+    ) -> ServiceResult<WaitForPatternResult> {
+        let regex = regex::RegexBuilder::new(pattern)
+            .size_limit(WAIT_PATTERN_SIZE_LIMIT)
+            .build()
+            .map_err(|e| ServiceError::InvalidConfig(format!("invalid pattern: {}", e)))?;
+
+        let mut st = lock_state(&self.state);
+
+        match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                timeout_streak,
+                bytes_read_total,
+                framing_errors,
+                cancel,
+                ..
+            } => {
+                cancel.reset();
+                let started = std::time::Instant::now();
+                let deadline = started + Duration::from_millis(timeout_ms);
+                let mut received: Vec<u8> = Vec::new();
+                let mut bytes_read = 0usize;
+                let mut buffer = vec![0u8; DEFAULT_READ_BYTES];
+
+                let outcome = loop {
+                    let text = String::from_utf8_lossy(&received);
+                    if let Some(m) = regex.find(&text) {
+                        break Ok(Some(m.as_str().to_string()));
+                    }
+                    let now = std::time::Instant::now();
+                    if now >= deadline {
+                        break Ok(None);
+                    }
+                    if cancel.is_cancelled() {
+                        break Err(ServiceError::Cancelled);
+                    }
+                    let wait = (deadline - now).min(WAIT_POLL_INTERVAL);
+                    if let Err(e) = port.set_timeout(wait) {
+                        break Err(ServiceError::from(e));
+                    }
+
+                    match port.read_bytes(&mut buffer) {
+                        Ok(0) => {}
+                        Ok(n) => {
+                            config.mask_received(&mut buffer[..n]);
+                            received.extend_from_slice(&buffer[..n]);
+                            if received.len() > self.max_read_bytes() {
+                                received.drain(..received.len() - self.max_read_bytes());
+                            }
+                            bytes_read += n;
+                            *bytes_read_total += n as u64;
+                            *last_activity = std::time::Instant::now();
+                            *timeout_streak = 0;
+                        }
+                        Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            // Non-blocking backends return immediately; avoid spinning
+                            std::thread::sleep(wait.min(Duration::from_millis(5)));
+                        }
+                        Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                        Err(PortError::Timeout(_)) => {}
+                        Err(PortError::Framing(_)) => *framing_errors += 1,
+                        Err(e) => break Err(ServiceError::from(e)),
+                    }
+                };
+
+                port.set_timeout(Duration::from_millis(config.timeout_ms))?;
+                let matched = outcome?;
+
+                Ok(WaitForPatternResult {
+                    timed_out: matched.is_none(),
+                    matched,
+                    data: String::from_utf8_lossy(&received).to_string(),
+                    bytes_read,
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                })
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// Send `command` framed per `protocol` and wait for its response line.
+    ///
+    /// The command is followed by `protocol.command_terminator` instead of the
+    /// port terminator. Received lines are read up to
+    /// `protocol.response_terminator`: the first starting with `error_prefix`
+    /// is an error, the first starting with `response_prefix` (or, without
+    /// one, any non-empty line) a success. Lines before it are returned as
+    /// `skipped`; bytes after it stay buffered for the next read.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::Cancelled` if [`cancel_read`](Self::cancel_read) is called
+    /// - `ServiceError::PortError` if the write or a read fails
+    pub fn protocol_command(
+        &self,
+        protocol: &ProtocolDescriptor,
+        command: &str,
+    ) -> ServiceResult<ProtocolCommandResult> {
+        self.check_write_permitted(command.as_bytes(), Some(&protocol.command_terminator))?;
+        let mut st = lock_state(&self.state);
+
+        match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                timeout_streak,
+                bytes_read_total,
+                bytes_written_total,
+                framing_errors,
+                frame_buffer,
+                cancel,
+                ..
+            } => {
+                cancel.reset();
+                let timeout = Duration::from_millis(protocol.timeout_ms);
+                let frame = format!("{}{}", command, protocol.command_terminator);
+                let (written, outcome) = Self::write_all(port.as_mut(), frame.as_bytes(), timeout);
+                *bytes_written_total += written as u64;
+                *last_activity = std::time::Instant::now();
+                outcome?;
+
+                let started = std::time::Instant::now();
+                let deadline = started + timeout;
+                let delimiter = protocol.response_terminator.as_bytes();
+                let mut received = std::mem::take(frame_buffer);
+                let mut skipped = Vec::new();
+                let mut buffer = vec![0u8; DEFAULT_READ_BYTES];
+
+                let outcome = loop {
+                    if let Some(pos) = find_subslice(&received, delimiter) {
+                        let rest = received.split_off(pos + delimiter.len());
+                        let line = String::from_utf8_lossy(&received[..pos]).trim().to_string();
+                        received = rest;
+                        match classify_response(protocol, &line) {
+                            Some((status, payload)) => break Ok(Some((status, payload, line))),
+                            None if line.is_empty() => {}
+                            None => skipped.push(line),
+                        }
+                        continue;
+                    }
+                    let now = std::time::Instant::now();
+                    if now >= deadline {
+                        break Ok(None);
+                    }
+                    if cancel.is_cancelled() {
+                        break Err(ServiceError::Cancelled);
+                    }
+                    let wait = (deadline - now).min(WAIT_POLL_INTERVAL);
+                    if let Err(e) = port.set_timeout(wait) {
+                        break Err(ServiceError::from(e));
+                    }
+
+                    match port.read_bytes(&mut buffer) {
+                        Ok(0) => {}
+                        Ok(n) => {
+                            config.mask_received(&mut buffer[..n]);
+                            received.extend_from_slice(&buffer[..n]);
+                            if received.len() > self.max_read_bytes() {
+                                received.drain(..received.len() - self.max_read_bytes());
+                            }
+                            *bytes_read_total += n as u64;
+                            *last_activity = std::time::Instant::now();
+                            *timeout_streak = 0;
+                        }
+                        Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            // Non-blocking backends return immediately; avoid spinning
+                            std::thread::sleep(wait.min(Duration::from_millis(5)));
+                        }
+                        Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                        Err(PortError::Timeout(_)) => {}
+                        Err(PortError::Framing(_)) => *framing_errors += 1,
+                        Err(e) => break Err(ServiceError::from(e)),
+                    }
+                };
+
+                // Keep whatever followed the response (or a partial line) for later reads
+                *frame_buffer = received;
+                port.set_timeout(Duration::from_millis(config.timeout_ms))?;
+                let (status, payload, response) = match outcome? {
+                    Some((status, payload, line)) => (status, Some(payload), Some(line)),
+                    None => (ResponseStatus::Timeout, None, None),
+                };
+
+                Ok(ProtocolCommandResult {
+                    protocol: protocol.name.clone(),
+                    status,
+                    payload,
+                    response,
+                    skipped,
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                })
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// Read scattered Modbus RTU registers from unit `unit` with as few
+    /// requests as possible.
+    ///
+    /// `ranges` are merged by [`coalesce_ranges`], bridging gaps of up to
+    /// `max_gap` registers, and each request waits up to `timeout_ms` for
+    /// its response. Holds the port for the whole batch; stale input is
+    /// discarded first.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if `ranges` is empty or a range is
+    ///   empty or runs past address 65535
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::Timeout` if a response is incomplete after `timeout_ms`
+    /// - `ServiceError::PortError` if a response is malformed or an exception
+    /// - `ServiceError::Cancelled` if [`cancel_read`](Self::cancel_read) is called
+    pub fn modbus_read_many(
+        &self,
+        unit: u8,
+        kind: RegisterKind,
+        ranges: &[RegisterRange],
+        max_gap: u16,
+        timeout_ms: u64,
+    ) -> ServiceResult<ModbusReadResult> {
+        if ranges.is_empty() {
+            return Err(ServiceError::InvalidConfig(
+                "ranges must not be empty".to_string(),
+            ));
+        }
+        if let Some(range) = ranges.iter().find(|r| r.count == 0 || r.end() > 0x1_0000) {
+            return Err(ServiceError::InvalidConfig(format!(
+                "register range start {} count {} must be non-empty and end by address 65535",
+                range.start, range.count
+            )));
+        }
+        let requests = coalesce_ranges(ranges, max_gap);
+        let frames: Vec<Vec<u8>> = requests
+            .iter()
+            .map(|&range| modbus::read_request(unit, kind, range))
+            .collect();
+        for frame in &frames {
+            self.check_write_permitted(frame, None)?;
+        }
+
+        let mut st = lock_state(&self.state);
+
+        match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                bytes_read_total,
+                bytes_written_total,
+                framing_errors,
+                frame_buffer,
+                cancel,
+                ..
+            } => {
+                cancel.reset();
+                port.clear_buffers()?;
+                frame_buffer.clear();
+
+                let started = std::time::Instant::now();
+                let timeout = Duration::from_millis(timeout_ms);
+                let mut read = BTreeMap::new();
+                let mut outcome = Ok(());
+                for (&range, frame) in requests.iter().zip(&frames) {
+                    let exchange = Self::modbus_exchange(
+                        port.as_mut(),
+                        frame,
+                        range.count,
+                        timeout,
+                        framing_errors,
+                        cancel,
+                    );
+                    *last_activity = std::time::Instant::now();
+                    let response = match exchange {
+                        Ok(response) => response,
+                        Err(e) => {
+                            outcome = Err(e);
+                            break;
+                        }
+                    };
+                    *bytes_written_total += frame.len() as u64;
+                    *bytes_read_total += response.len() as u64;
+                    match modbus::parse_read_response(&response, unit, kind, range.count) {
+                        Ok(values) => read.extend((u32::from(range.start)..).zip(values)),
+                        Err(reason) => {
+                            outcome = Err(ServiceError::PortError(format!(
+                                "Modbus read of {} registers at {} from unit {}: {}",
+                                range.count, range.start, unit, reason
+                            )));
+                            break;
+                        }
+                    }
+                }
+                port.set_timeout(Duration::from_millis(config.timeout_ms))?;
+                outcome?;
+
+                // Gap registers were read only to save requests
+                let values = ranges
+                    .iter()
+                    .flat_map(|r| u32::from(r.start)..r.end())
+                    .filter_map(|address| Some((address as u16, *read.get(&address)?)))
+                    .collect();
+                Ok(ModbusReadResult {
+                    values,
+                    requests,
+                    elapsed_ms: started.elapsed().as_millis() as u64,
+                })
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// Write a random token and check that the same bytes are read back.
+    ///
+    /// Intended for loopback plugs and echo devices. Buffers are cleared
+    /// first so stale data cannot cause a false pass. The token is ASCII hex,
+    /// so it never contains a line terminator.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if `token_len` is 0 or above the read cap
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if the port cannot be cleared or written
+    pub fn loopback_test(
+        &self,
+        token_len: usize,
+        timeout_ms: u64,
+    ) -> ServiceResult<LoopbackResult> {
+        if token_len == 0 || token_len > self.max_read_bytes() {
+            return Err(ServiceError::InvalidConfig(format!(
+                "token_len must be between 1 and {}",
+                self.max_read_bytes()
+            )));
+        }
+        let token = loopback_token(token_len);
+
+        let mut st = lock_state(&self.state);
+
+        match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                timeout_streak,
+                bytes_read_total,
+                bytes_written_total,
+                framing_errors,
+                frame_buffer,
+                cancel,
+                ..
+            } => {
+                cancel.reset();
+                port.clear_buffers()?;
+                frame_buffer.clear();
+
+                let started = std::time::Instant::now();
+                let deadline = started + Duration::from_millis(timeout_ms);
+                let (written, outcome) = Self::write_all(
+                    port.as_mut(),
+                    token.as_bytes(),
+                    Duration::from_millis(timeout_ms),
+                );
+                *bytes_written_total += written as u64;
+                *last_activity = std::time::Instant::now();
+                outcome?;
+
+                let mut received: Vec<u8> = Vec::with_capacity(token.len());
+                let mut buffer = vec![0u8; token.len()];
+                let outcome = loop {
+                    if received.len() >= token.len() {
+                        break Ok(());
+                    }
+                    let now = std::time::Instant::now();
+                    if now >= deadline {
+                        break Ok(());
+                    }
+                    if cancel.is_cancelled() {
+                        break Err(ServiceError::Cancelled);
+                    }
+                    let wait = (deadline - now).min(WAIT_POLL_INTERVAL);
+                    if let Err(e) = port.set_timeout(wait) {
+                        break Err(ServiceError::from(e));
+                    }
+
+                    let remaining = token.len() - received.len();
+                    match port.read_bytes(&mut buffer[..remaining]) {
+                        Ok(0) => {}
+                        Ok(n) => {
+                            received.extend_from_slice(&buffer[..n]);
+                            *bytes_read_total += n as u64;
+                            *last_activity = std::time::Instant::now();
+                            *timeout_streak = 0;
+                        }
+                        Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            std::thread::sleep(wait.min(Duration::from_millis(5)));
+                        }
+                        Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                        Err(PortError::Timeout(_)) => {}
+                        Err(PortError::Framing(_)) => *framing_errors += 1,
+                        Err(e) => break Err(ServiceError::from(e)),
+                    }
+                };
+                let latency_ms = started.elapsed().as_millis() as u64;
+
+                port.set_timeout(Duration::from_millis(config.timeout_ms))?;
+                outcome?;
+
+                Ok(LoopbackResult {
+                    passed: received == token.as_bytes(),
+                    received: String::from_utf8_lossy(&received).to_string(),
+                    sent: token,
+                    latency_ms,
+                })
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// Time `iterations` round trips of `payload`, each written like
+    /// [`write`](Self::write) and answered once `read_until` arrives.
+    ///
+    /// `read_until` defaults to the configured terminator, or `"\n"` if there
+    /// is none. The whole run holds the state lock, so nothing else can
+    /// interleave with the measurements. Input is discarded before every
+    /// round trip, so a late reply to one iteration cannot complete the next.
+    /// A round trip that times out is counted but left out of the latency
+    /// statistics.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if `iterations` is 0 or above
+    ///   [`MAX_BENCHMARK_ITERATIONS`], or `read_until` is empty
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::Cancelled` if the run is cancelled
+    /// - `ServiceError::PortError` if a write or read fails
+    pub fn benchmark(
+        &self,
+        iterations: u32,
+        payload: &str,
+        read_until: Option<&str>,
+        timeout_ms: u64,
+    ) -> ServiceResult<BenchmarkResult> {
+        if iterations == 0 || iterations > MAX_BENCHMARK_ITERATIONS {
+            return Err(ServiceError::InvalidConfig(format!(
+                "iterations must be between 1 and {}",
+                MAX_BENCHMARK_ITERATIONS
+            )));
+        }
+        if read_until == Some("") {
+            return Err(ServiceError::InvalidConfig(
+                "read_until must not be empty".to_string(),
+            ));
+        }
+        let mut st = lock_state(&self.state);
+
+        match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                timeout_streak,
+                bytes_read_total,
+                bytes_written_total,
+                framing_errors,
+                frame_buffer,
+                cancel,
+                ..
+            } => {
+                self.check_write_permitted(
+                    config.write_payload(payload.as_bytes()),
+                    config.terminator.as_deref(),
+                )?;
+                cancel.reset();
+                frame_buffer.clear();
+                let (frame, _) = config.encode_write(payload);
+                let delimiter = read_until
+                    .or(config.terminator.as_deref())
+                    .filter(|d| !d.is_empty())
+                    .unwrap_or("\n")
+                    .as_bytes()
+                    .to_vec();
+                let timeout = Duration::from_millis(timeout_ms);
+
+                let mut samples = Vec::with_capacity(iterations as usize);
+                let (mut bytes_written, mut bytes_read) = (0u64, 0u64);
+                let started = std::time::Instant::now();
+                let outcome = (0..iterations).try_for_each(|_| -> ServiceResult<()> {
+                    port.clear_buffers()?;
+                    let round_trip = std::time::Instant::now();
+                    let exchange = Self::transact(
+                        port.as_mut(),
+                        &frame,
+                        &delimiter,
+                        timeout,
+                        self.max_read_bytes(),
+                        framing_errors,
+                        cancel,
+                    )?;
+                    let elapsed = round_trip.elapsed();
+
+                    bytes_written += exchange.bytes_written as u64;
+                    bytes_read += exchange.bytes_read as u64;
+                    *bytes_written_total += exchange.bytes_written as u64;
+                    *bytes_read_total += exchange.bytes_read as u64;
+                    *last_activity = std::time::Instant::now();
+                    if exchange.completed {
+                        *timeout_streak = 0;
+                        samples.push(elapsed);
+                    }
+                    Ok(())
+                });
+                let elapsed = started.elapsed();
+
+                port.set_timeout(Duration::from_millis(config.timeout_ms))?;
+                outcome?;
+
+                let completed = samples.len() as u32;
+                let secs = elapsed.as_secs_f64();
+                let per_sec = |n: f64| if secs > 0.0 { n / secs } else { 0.0 };
+                Ok(BenchmarkResult {
+                    iterations,
+                    completed,
+                    timeouts: iterations - completed,
+                    latency: LatencyStats::from_samples(&samples),
+                    bytes_written,
+                    bytes_read,
+                    elapsed_ms: elapsed.as_millis() as u64,
+                    throughput_bytes_per_sec: per_sec((bytes_written + bytes_read) as f64),
+                    transactions_per_sec: per_sec(completed as f64),
+                })
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// Set DTR and/or RTS together, optionally hold them, and optionally
+    /// restore the previous levels.
+    ///
+    /// Signals are applied DTR first, then RTS. If any write fails, the lines
+    /// already touched are rolled back to their previous levels before the
+    /// error is returned. Only levels the port could report are restored; a
+    /// line whose previous level is unknown (never set since open) keeps the
+    /// requested level.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if neither `dtr` nor `rts` is given, or
+    ///   `hold_ms` exceeds [`MAX_SIGNAL_HOLD_MS`]
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if a signal cannot be set or restored
+    pub fn set_signals(&self, request: SignalsRequest) -> ServiceResult<SignalsResult> {
+        let changes = Self::signal_changes(&request)?;
+
+        let mut st = lock_state(&self.state);
+        let port = match &mut *st {
+            PortState::Open { port, .. } => port,
+            PortState::Closed => return Err(ServiceError::PortNotOpen),
+        };
+        Self::set_signals_locked(port.as_mut(), &changes, &request)
+    }
+
+    /// The line changes `request` asks for, DTR first, after checking it.
+    fn signal_changes(request: &SignalsRequest) -> ServiceResult<Vec<(ControlSignal, bool)>> {
+        let changes: Vec<(ControlSignal, bool)> = [
+            (ControlSignal::Dtr, request.dtr),
+            (ControlSignal::Rts, request.rts),
+        ]
+        .into_iter()
+        .filter_map(|(signal, level)| level.map(|l| (signal, l)))
+        .collect();
+        if changes.is_empty() {
+            return Err(ServiceError::InvalidConfig(
+                "at least one of dtr or rts must be given".to_string(),
+            ));
+        }
+        if request.hold_ms.is_some_and(|ms| ms > MAX_SIGNAL_HOLD_MS) {
+            return Err(ServiceError::InvalidConfig(format!(
+                "hold_ms must not exceed {}",
+                MAX_SIGNAL_HOLD_MS
+            )));
+        }
+        Ok(changes)
+    }
+
+    /// Apply checked `changes` to `port` while the caller holds the state
+    /// lock, as described on [`set_signals`](Self::set_signals).
+    fn set_signals_locked(
+        port: &mut dyn SerialPortAdapter,
+        changes: &[(ControlSignal, bool)],
+        request: &SignalsRequest,
+    ) -> ServiceResult<SignalsResult> {
+        let prior: Vec<(ControlSignal, bool)> = changes
+            .iter()
+            .filter_map(|&(signal, _)| port.signal_level(signal).map(|level| (signal, level)))
+            .collect();
+
+        for (i, &(signal, level)) in changes.iter().enumerate() {
+            if let Err(e) = port.write_signal(signal, level) {
+                let touched: Vec<_> = prior
+                    .iter()
+                    .copied()
+                    .filter(|(s, _)| changes[..=i].iter().any(|(c, _)| c == s))
+                    .collect();
+                if let Err(restore_err) = Self::restore_signals(port, &touched) {
+                    tracing::warn!(error = %restore_err, "Failed to roll back control signals");
+                }
+                return Err(e.into());
+            }
+        }
+
+        if let Some(ms) = request.hold_ms {
+            std::thread::sleep(Duration::from_millis(ms));
+        }
+
+        if request.restore {
+            Self::restore_signals(port, &prior)?;
+        }
+
+        Ok(SignalsResult {
+            dtr: port.signal_level(ControlSignal::Dtr),
+            rts: port.signal_level(ControlSignal::Rts),
+            restored: request.restore,
+        })
+    }
+
+    /// Resize the open port's driver RX/TX buffers, or report their current
+    /// sizes when neither `rx` nor `tx` is given.
+    ///
+    /// The driver may clamp a request; the result carries both the requested
+    /// and the effective sizes.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if a requested size is zero
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::Unsupported` if the port cannot report or resize its
+    ///   buffers
+    /// - `ServiceError::PortError` if the driver rejects the request
+    pub fn set_buffer_sizes(
+        &self,
+        rx: Option<usize>,
+        tx: Option<usize>,
+    ) -> ServiceResult<BufferSizesResult> {
+        if rx == Some(0) || tx == Some(0) {
+            return Err(ServiceError::InvalidConfig(
+                "buffer sizes must be greater than zero".to_string(),
+            ));
+        }
+
+        let mut st = lock_state(&self.state);
+        let (port, config) = match &mut *st {
+            PortState::Open { port, config, .. } => (port, config),
+            PortState::Closed => return Err(ServiceError::PortNotOpen),
+        };
+
+        let effective = if rx.is_none() && tx.is_none() {
+            port.buffer_sizes()
+        } else {
+            port.set_buffer_sizes(rx, tx)
+        }
+        .map_err(ServiceError::from)?;
+
+        Ok(BufferSizesResult {
+            port_name: config.port_name.clone(),
+            requested: BufferSizes { rx, tx },
+            effective,
+        })
+    }
+
+    /// Reset the attached board by running its DTR/RTS sequence as
+    /// [`set_signals`](Self::set_signals) steps.
+    ///
+    /// Presets use [`ResetBoard::steps`]; [`ResetBoard::Custom`] runs
+    /// `custom_steps` instead. Each step sets its lines and then holds them
+    /// for `hold_ms`. The whole sequence runs under one state lock, so no
+    /// read or write lands between pulses. If a step fails, DTR and RTS are
+    /// deasserted before the error is returned.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if a custom sequence is empty or longer
+    ///   than [`MAX_RESET_STEPS`], a step sets neither line, or the holds add
+    ///   up to more than [`MAX_SIGNAL_HOLD_MS`]
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if a signal cannot be set
+    pub fn reset_device(
+        &self,
+        board: ResetBoard,
+        custom_steps: Option<Vec<SignalStep>>,
+    ) -> ServiceResult<ResetResult> {
+        let steps = match board {
+            ResetBoard::Custom => {
+                custom_steps
+                    .filter(|steps| !steps.is_empty())
+                    .ok_or_else(|| {
+                        ServiceError::InvalidConfig(
+                            "custom reset needs at least one step".to_string(),
+                        )
+                    })?
+            }
+            preset => preset.steps(),
+        };
+        if steps.len() > MAX_RESET_STEPS {
+            return Err(ServiceError::InvalidConfig(format!(
+                "reset sequence has {} steps; at most {} are allowed",
+                steps.len(),
+                MAX_RESET_STEPS
+            )));
+        }
+        let held: u64 = steps.iter().map(|step| step.hold_ms).sum();
+        if held > MAX_SIGNAL_HOLD_MS {
+            return Err(ServiceError::InvalidConfig(format!(
+                "reset sequence holds for {} ms in total; at most {} ms is allowed",
+                held, MAX_SIGNAL_HOLD_MS
+            )));
+        }
+        let requests = steps
+            .iter()
+            .map(|step| {
+                let request = SignalsRequest {
+                    dtr: step.dtr,
+                    rts: step.rts,
+                    hold_ms: Some(step.hold_ms).filter(|&ms| ms > 0),
+                    restore: false,
+                };
+                Self::signal_changes(&request).map(|changes| (changes, request))
+            })
+            .collect::<ServiceResult<Vec<_>>>()?;
+
+        let mut st = lock_state(&self.state);
+        let port = match &mut *st {
+            PortState::Open { port, .. } => port,
+            PortState::Closed => return Err(ServiceError::PortNotOpen),
+        };
+        let mut levels = SignalsResult {
+            dtr: None,
+            rts: None,
+            restored: false,
+        };
+        for (changes, request) in &requests {
+            match Self::set_signals_locked(port.as_mut(), changes, request) {
+                Ok(result) => levels = result,
+                Err(e) => {
+                    let released = [(ControlSignal::Dtr, false), (ControlSignal::Rts, false)];
+                    if let Err(restore_err) = Self::restore_signals(port.as_mut(), &released) {
+                        tracing::warn!(error = %restore_err, "Failed to release control signals after a failed reset");
+                    }
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(ResetResult {
+            board,
+            steps,
+            dtr: levels.dtr,
+            rts: levels.rts,
+        })
+    }
+
+    /// Run the bring-up checklist on `port_name` at `baud_rate`.
+    ///
+    /// Steps, each reported pass/fail/skipped with a suggestion on failure:
+    ///
+    /// 1. `port_present`: the port is in `enumerated` (the caller's port
+    ///    listing; `Err` when enumeration failed)
+    /// 2. `open`: the port opens with 8N1 and no terminator
+    /// 3. `modem_lines`: CTS, DSR and CD can be read from the device
+    /// 4. `signature_probe`: one of [`DIAGNOSE_SIGNATURES`] gets its
+    ///    expected reply within `probe_timeout_ms` each
+    ///
+    /// The port is closed again before returning, so the caller can follow up
+    /// with steps that need the device to itself, such as auto-negotiation.
+    /// A port already open is left alone and the checklist stops at `open`.
+    pub fn diagnose(
+        &self,
+        port_name: &str,
+        baud_rate: u32,
+        probe_timeout_ms: u64,
+        enumerated: Result<Vec<String>, String>,
+    ) -> DiagnoseReport {
+        let mut steps = Vec::new();
+        let resolved = self
+            .resolve_port_name(port_name)
+            .unwrap_or_else(|_| port_name.to_string());
+
+        steps.push(match enumerated {
+            Ok(names) if names.contains(&resolved) => {
+                DiagnosticStep::pass("port_present", format!("{} is listed", resolved))
+            }
+            Ok(names) => DiagnosticStep::fail(
+                "port_present",
+                format!("{} is not among {} listed port(s)", resolved, names.len()),
+                format!(
+                    "Check the cable and USB driver; available ports: {}",
+                    if names.is_empty() {
+                        "none".to_string()
+                    } else {
+                        names.join(", ")
+                    }
+                ),
+            ),
+            Err(e) => DiagnosticStep::skipped("port_present", e),
+        });
+
+        let config = OpenConfig {
+            port_name: port_name.to_string(),
+            baud_rate,
+            timeout_ms: probe_timeout_ms,
+            data_bits: DataBitsCfg::Eight,
+            parity: ParityCfg::None,
+            stop_bits: StopBitsCfg::One,
+            flow_control: FlowControlCfg::None,
+            terminator: None,
+            idle_disconnect_ms: Some(0),
+            read_idle_gap_ms: None,
+            write_checksum: None,
+            half_duplex_echo: false,
+            flush_on_write: false,
+            auto_detect_terminator: false,
+            mask_parity_bit: None,
+            strip_echo: false,
+            strip_prompt: None,
+            max_open_duration_ms: None,
+            open_retries: None,
+            open_retry_delay_ms: None,
+            require_response: None,
+            read_strategy: Default::default(),
+        };
+        if let Err(e) = self.open(config) {
+            let suggestion = match &e {
+                ServiceError::PortAlreadyOpen => "Close the open port before running diagnose",
+                ServiceError::PermissionDenied(_) => {
+                    "Add your user to the dialout (Linux) or uucp group and log in again"
+                }
+                ServiceError::DeviceNotFound(_) => {
+                    "Reconnect the device; it may have re-enumerated under another name"
+                }
+                _ => "Make sure no other program holds the port, then retry",
+            };
+            steps.push(DiagnosticStep::fail("open", e.to_string(), suggestion));
+            for name in ["modem_lines", "signature_probe"] {
+                steps.push(DiagnosticStep::skipped(name, "port did not open"));
+            }
+            return DiagnoseReport {
+                port_name: resolved,
+                baud_rate,
+                steps,
+            };
+        }
+        steps.push(DiagnosticStep::pass(
+            "open",
+            format!("opened {} at {} baud", resolved, baud_rate),
+        ));
+
+        let (modem_status, dtr, rts) = match &mut *lock_state(&self.state) {
+            PortState::Open { port, .. } => (
+                port.modem_status(),
+                port.signal_level(ControlSignal::Dtr),
+                port.signal_level(ControlSignal::Rts),
+            ),
+            PortState::Closed => (Err(PortError::NotOpen), None, None),
+        };
+        let levels = format!("DTR={:?}, RTS={:?}", dtr, rts);
+        steps.push(match modem_status {
+            Ok(status) => DiagnosticStep::pass(
+                "modem_lines",
+                format!(
+                    "CTS={}, DSR={}, CD={}; {}",
+                    status.cts, status.dsr, status.cd, levels
+                ),
+            ),
+            Err(e) => DiagnosticStep::fail(
+                "modem_lines",
+                format!("could not read CTS/DSR/CD: {}; {}", e, levels),
+                "Use flow_control none; hardware flow control needs CTS",
+            ),
+        });
+
+        let mut matched = None;
+        for signature in DIAGNOSE_SIGNATURES {
+            if self.write(signature.command).is_err() {
+                break;
+            }
+            if let Ok(result) = self.wait_for_pattern(signature.reply, probe_timeout_ms) {
+                if !result.timed_out {
+                    matched = Some(signature.device);
+                    break;
+                }
+            }
+        }
+        steps.push(match matched {
+            Some(device) => {
+                DiagnosticStep::pass("signature_probe", format!("answered as {}", device))
+            }
+            None => DiagnosticStep::fail(
+                "signature_probe",
+                format!("no reply to {} known probes", DIAGNOSE_SIGNATURES.len()),
+                "Try another baud rate (detect_port) or reset the board (reset_device)",
+            ),
+        });
+
+        let _ = self.close();
+        DiagnoseReport {
+            port_name: resolved,
+            baud_rate,
+            steps,
+        }
+    }
+
+    /// Read data from the open port.
+    ///
+    /// Reads up to 1024 bytes. If a terminator is configured, it will be
+    /// stripped from the returned data. Timeouts are handled gracefully
+    /// and return zero-length data.
+    ///
+    /// If `read_idle_gap_ms` is configured, reading continues as long as new
+    /// bytes keep arriving within the gap, so bursts are coalesced into one
+    /// result.
+    ///
+    /// If idle disconnect is configured and the timeout is reached, the port
+    /// will be automatically closed and the result will indicate this.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if a non-timeout read error occurs
+    pub fn read(&self) -> ServiceResult<ReadResult> {
+        self.read_bytes_n(DEFAULT_READ_BYTES)
+    }
+
+    /// Read up to `max_bytes` bytes from the open port.
+    ///
+    /// The request is checked against the configured read cap before any
+    /// buffer is allocated.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if `max_bytes` is zero or exceeds the read cap
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if a non-timeout read error occurs
+    pub fn read_bytes_n(&self, max_bytes: usize) -> ServiceResult<ReadResult> {
+        self.read_encoded(max_bytes, ReadEncoding::Utf8)
+    }
+
+    /// Read up to `max_bytes` bytes, returning `data` in the given encoding.
+    ///
+    /// With [`ReadEncoding::Base64`] or [`ReadEncoding::Hexdump`] the received
+    /// bytes are encoded directly, without first building a lossy UTF-8
+    /// `String`, so binary data survives intact and no intermediate copy is
+    /// made.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_bytes_n`](Self::read_bytes_n).
+    pub fn read_encoded(
+        &self,
+        max_bytes: usize,
+        encoding: ReadEncoding,
+    ) -> ServiceResult<ReadResult> {
+        self.read_encoded_with_hex(max_bytes, encoding, false, None)
+    }
+
+    /// [`read_encoded`](Self::read_encoded), optionally also returning the
+    /// bytes read as `hex`, and waiting up to `long_poll` for the first byte.
+    fn read_encoded_with_hex(
+        &self,
+        max_bytes: usize,
+        encoding: ReadEncoding,
+        include_hex: bool,
+        long_poll: Option<Duration>,
+    ) -> ServiceResult<ReadResult> {
+        self.note_error(
+            "read",
+            self.read_now(max_bytes, encoding, include_hex, long_poll),
+        )
+    }
+
+    fn read_now(
+        &self,
+        max_bytes: usize,
+        encoding: ReadEncoding,
+        include_hex: bool,
+        long_poll: Option<Duration>,
+    ) -> ServiceResult<ReadResult> {
+        if max_bytes == 0 {
+            return Err(ServiceError::InvalidConfig(
+                "max_bytes must be greater than 0".to_string(),
+            ));
+        }
+        if max_bytes > self.max_read_bytes() {
+            return Err(ServiceError::InvalidConfig(format!(
+                "max_bytes {} exceeds read limit of {} bytes",
+                max_bytes,
+                self.max_read_bytes()
+            )));
+        }
+
+        let mut st = lock_state(&self.state);
+        if let Some(closed) = self.expired_read_result(&mut st) {
+            return Ok(closed);
+        }
+
+        // Extract read result while holding lock
+        let result = match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                timeout_streak,
+                bytes_read_total,
+                idle_close_count,
+                framing_errors,
+                frame_buffer,
+                timeout_log,
+                open_id,
+                cancel,
+                terminator_detect,
+                pending_echo,
+                ..
+            } => {
+                cancel.reset();
+                let mut buffer = vec![0u8; max_bytes];
+
+                // Bytes left over by an earlier command are returned first,
+                // without waiting on the port; a lone partial UTF-8 character
+                // still needs the rest of its bytes
+                let buffered = !frame_buffer.is_empty()
+                    && (encoding != ReadEncoding::Utf8
+                        || incomplete_utf8_tail(frame_buffer) < frame_buffer.len());
+
+                // A long poll waits the whole window for the first byte
+                // instead of the port timeout, returning as soon as data arrives
+                let window = long_poll.unwrap_or(Duration::from_millis(config.timeout_ms));
+                let first_read = if buffered {
+                    Ok(0)
+                } else if Self::poll_for_data(port.as_ref(), config.read_strategy, window, cancel)?
+                {
+                    if let Some(window) = long_poll {
+                        port.set_timeout(window)?;
+                    }
+                    let read = port.read_bytes(buffer.as_mut_slice());
+                    if long_poll.is_some() {
+                        port.set_timeout(Duration::from_millis(config.timeout_ms))?;
+                    }
+                    read
+                } else {
+                    Ok(0)
+                };
+
+                // Attempt read
+                let mut framing_error = false;
+                let mut bytes_read = match first_read {
+                    Ok(n) => n,
+                    Err(crate::port::PortError::Framing(_)) => {
+                        // Received data is garbage; discard it but record the error
+                        *framing_errors += 1;
+                        framing_error = true;
+                        0
+                    }
+                    Err(e) => {
+                        // Check if it's a timeout error
+                        if let crate::port::PortError::Io(ref io_err) = e {
+                            if io_err.kind() == std::io::ErrorKind::TimedOut {
+                                0 // Treat timeout as zero bytes read
+                            } else {
+                                return Err(e.into());
+                            }
+                        } else {
+                            return Err(e.into());
+                        }
+                    }
+                };
+
+                // Keep accumulating while new bytes arrive within the idle gap
+                if let Some(gap_ms) = config.read_idle_gap_ms {
+                    if bytes_read > 0 && bytes_read < max_bytes {
+                        bytes_read += Self::read_until_gap(
+                            port.as_mut(),
+                            &mut buffer[bytes_read..],
+                            Duration::from_millis(gap_ms),
+                            Duration::from_millis(config.timeout_ms),
+                            None,
+                            framing_errors,
+                            cancel,
+                        )?;
+                    }
+                }
+
+                let framing = port.detects_framing_errors().then_some(*framing_errors);
+
+                // Update metrics
+                if bytes_read > 0 {
+                    *last_activity = std::time::Instant::now();
+                    *timeout_streak = 0;
+                    *bytes_read_total += bytes_read as u64;
+                    timeout_log.reset();
+                } else if framing_error {
+                    // The line is active even though nothing usable arrived
+                    *last_activity = std::time::Instant::now();
+                } else if !buffered {
+                    *timeout_streak += 1;
+                    if let Some(suppressed) = timeout_log.record(self.timeout_warn_interval()) {
+                        tracing::warn!(
+                            port = %config.port_name,
+                            timeout_streak = *timeout_streak,
+                            suppressed,
+                            "Read timed out; port is silent"
+                        );
+                    }
+                }
+
+                // Check for idle timeout
+                let idle_expired = bytes_read == 0
+                    && !buffered
+                    && config
+                        .idle_disconnect_ms
+                        .map(|ms| last_activity.elapsed() >= Duration::from_millis(ms))
+                        .unwrap_or(false);
+
+                if idle_expired {
+                    *idle_close_count += 1;
+                    let count = *idle_close_count;
+                    // Return early to indicate port should be closed
+                    Err((count, *bytes_read_total, framing, *open_id))
+                } else {
+                    // Prepend any partial UTF-8 character held back by the last read
+                    let mut received = std::mem::take(frame_buffer);
+                    let start = received.len();
+                    received.extend_from_slice(&buffer[..bytes_read]);
+                    // `hex` covers the same bytes as `data`, this call's as
+                    // they came off the wire; bytes carried over from an
+                    // earlier read were masked when that read took them
+                    let unmasked = include_hex.then(|| received.clone());
+                    config.mask_received(&mut received[start..]);
+                    if encoding == ReadEncoding::Utf8 {
+                        let complete = received.len() - incomplete_utf8_tail(&received);
+                        *frame_buffer = received.split_off(complete);
+                    }
+                    // Data is returned raw while the terminator is still being detected
+                    let terminator = match terminator_detect {
+                        Some(_) => None,
+                        None => config.terminator.as_deref(),
+                    };
+                    let mut data = encode_read_data(&received, terminator, encoding);
+                    if encoding == ReadEncoding::Utf8 {
+                        let (clean, rest) =
+                            config.strip_shell_noise(&data, pending_echo.take().as_deref());
+                        data = clean;
+                        *pending_echo = rest;
+                    }
+                    let hex = unmasked.map(|unmasked| {
+                        unmasked[..received.len()]
+                            .iter()
+                            .map(|b| format!("{:02X}", b))
+                            .collect::<String>()
+                    });
+                    if let Some(detector) = terminator_detect.as_mut() {
+                        if detector.observe(&buffer[..bytes_read]) {
+                            match detector.detected() {
+                                Some(term) => {
+                                    tracing::info!(
+                                        port = %config.port_name,
+                                        terminator = ?term,
+                                        "Detected line terminator"
+                                    );
+                                    config.terminator = Some(term.to_string());
+                                }
+                                None => tracing::warn!(
+                                    port = %config.port_name,
+                                    "No line ending seen; keeping configured terminator"
+                                ),
+                            }
+                            *terminator_detect = None;
+                        }
+                    }
+
+                    Ok((data, hex, bytes_read, *bytes_read_total, framing, *open_id))
+                }
+            }
+            PortState::Closed => return Err(ServiceError::PortNotOpen),
+        };
+
+        // Handle result outside borrow scope
+        match result {
+            Ok((data, hex, bytes_read, total, framing_errors, open_id)) => Ok(ReadResult {
+                open_id,
+                data,
+                bytes_read,
+                bytes_read_total: total,
+                framing_errors,
+                auto_closed: None,
+                termination: None,
+                raw: None,
+                hex,
+            }),
+            Err((idle_count, total, framing_errors, open_id)) => {
+                // Close the port due to idle timeout
+                self.auto_close(&mut st, CloseReason::IdleTimeout);
+                Ok(ReadResult {
+                    open_id,
+                    data: String::new(),
+                    bytes_read: 0,
+                    bytes_read_total: total,
+                    framing_errors,
+                    auto_closed: Some(AutoCloseInfo {
+                        reason: CloseReason::IdleTimeout,
+                        idle_close_count: idle_count,
+                    }),
+                    termination: None,
+                    raw: None,
+                    hex: None,
+                })
+            }
+        }
+    }
+
+    /// Read like [`read_encoded`](Self::read_encoded), then clean up UTF-8
+    /// `data` with `transform`.
+    ///
+    /// When `transform` does anything, the untransformed text is returned in
+    /// `raw`. Base64 and hexdump data are left untouched. With `include_hex`
+    /// the bytes behind `data` are also returned as `hex`, so mixed
+    /// text/binary data can be inspected from a single read.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`read_bytes_n`](Self::read_bytes_n).
+    pub fn read_transformed(
+        &self,
+        max_bytes: usize,
+        encoding: ReadEncoding,
+        transform: ReadTransform,
+        include_hex: bool,
+    ) -> ServiceResult<ReadResult> {
+        self.read_with_long_poll(max_bytes, encoding, transform, include_hex, None)
+    }
+
+    /// [`read_transformed`](Self::read_transformed) that, given a `long_poll`
+    /// window, waits up to that long for data instead of the port timeout.
+    ///
+    /// The read returns as soon as the first bytes arrive (then coalesces a
+    /// burst as usual when `read_idle_gap_ms` is configured), or empty once
+    /// the window passes, so polling loops on a quiet device make one call
+    /// per window instead of one per port timeout. The port is only held for
+    /// one short slice of the window at a time, so other operations can run
+    /// in between.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if `long_poll` is zero or exceeds
+    ///   [`MAX_LONG_POLL_MS`]
+    /// - `ServiceError::Cancelled` if [`cancel_read`](Self::cancel_read) is called
+    /// - Otherwise the same as [`read_bytes_n`](Self::read_bytes_n)
+    pub fn read_with_long_poll(
+        &self,
+        max_bytes: usize,
+        encoding: ReadEncoding,
+        transform: ReadTransform,
+        include_hex: bool,
+        long_poll: Option<Duration>,
+    ) -> ServiceResult<ReadResult> {
+        let mut result = match long_poll {
+            Some(window) => self.long_poll_now(max_bytes, encoding, include_hex, window)?,
+            None => self.read_encoded_with_hex(max_bytes, encoding, include_hex, None)?,
+        };
+        if encoding == ReadEncoding::Utf8 && !transform.is_identity() {
+            let data = transform.apply(&result.data);
+            result.raw = Some(std::mem::replace(&mut result.data, data));
+        }
+        Ok(result)
+    }
+
+    /// Read in [`LONG_POLL_SLICE`] pieces until data arrives, the port closes
+    /// or `window` passes.
+    fn long_poll_now(
+        &self,
+        max_bytes: usize,
+        encoding: ReadEncoding,
+        include_hex: bool,
+        window: Duration,
+    ) -> ServiceResult<ReadResult> {
+        if window.is_zero() || window > Duration::from_millis(MAX_LONG_POLL_MS) {
+            return Err(ServiceError::InvalidConfig(format!(
+                "long_poll_ms must be between 1 and {}",
+                MAX_LONG_POLL_MS
+            )));
+        }
+        let deadline = std::time::Instant::now() + window;
+        loop {
+            let slice =
+                LONG_POLL_SLICE.min(deadline.saturating_duration_since(std::time::Instant::now()));
+            let result = self.read_encoded_with_hex(
+                max_bytes,
+                encoding,
+                include_hex,
+                Some(slice.max(Duration::from_millis(1))),
+            )?;
+            if result.bytes_read > 0
+                || !result.data.is_empty()
+                || result.auto_closed.is_some()
+                || std::time::Instant::now() >= deadline
+            {
+                return Ok(result);
+            }
+            // Each slice starts afresh, so look for a cancel that came between them
+            if self.cancel.is_cancelled() {
+                return Err(ServiceError::Cancelled);
+            }
+        }
+    }
+
+    /// Read one line, up to and including the port's terminator (`\n` if none
+    /// is configured).
+    ///
+    /// When the terminator is `\n`, `\r` or `\r\n`, any of the three ends a
+    /// line, so devices mixing them still give clean lines. A `\r` that ends
+    /// the data so far is held until the next read shows whether a `\n`
+    /// follows; only a `\n` arriving after a gap of a poll interval or more is
+    /// then seen as an empty line.
+    ///
+    /// Two limits bound the read: `overall_timeout_ms` caps the total time,
+    /// while `idle_timeout_ms` (if given) gives up as soon as the gap since the
+    /// last received byte (or since the call, before any byte) exceeds it. A
+    /// device that is silent is therefore distinguished from one that keeps
+    /// talking without sending a delimiter; `termination` reports which case
+    /// ended the read. Bytes received after the delimiter are kept for the
+    /// next read. The returned `data` has the terminator stripped.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::Cancelled` if [`cancel_read`](Self::cancel_read) is called
+    /// - `ServiceError::PortError` if a non-timeout read error occurs
+    pub fn read_line(
+        &self,
+        overall_timeout_ms: u64,
+        idle_timeout_ms: Option<u64>,
+    ) -> ServiceResult<ReadResult> {
+        self.note_error(
+            "read_line",
+            self.read_line_now(overall_timeout_ms, idle_timeout_ms),
+        )
+    }
+
+    fn read_line_now(
+        &self,
+        overall_timeout_ms: u64,
+        idle_timeout_ms: Option<u64>,
+    ) -> ServiceResult<ReadResult> {
+        let mut st = lock_state(&self.state);
+        if let Some(closed) = self.expired_read_result(&mut st) {
+            return Ok(closed);
+        }
+
+        match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                timeout_streak,
+                bytes_read_total,
+                framing_errors,
+                frame_buffer,
+                open_id,
+                cancel,
+                pending_echo,
+                ..
+            } => {
+                cancel.reset();
+                let delimiter = config
+                    .terminator
+                    .clone()
+                    .filter(|t| !t.is_empty())
+                    .unwrap_or_else(|| "\n".to_string());
+                let started = std::time::Instant::now();
+                let deadline = started + Duration::from_millis(overall_timeout_ms);
+                let idle = idle_timeout_ms.map(Duration::from_millis);
+                let mut last_byte = started;
+                // Start with anything left over from the previous read
+                let mut received = std::mem::take(frame_buffer);
+                let mut bytes_read = 0usize;
+                let mut buffer = vec![0u8; DEFAULT_READ_BYTES];
+                let any_line_end = is_line_terminator(&delimiter);
+                // Whether the last read found nothing, so a trailing `\r` is
+                // not waiting for its `\n`
+                let mut cr_final = false;
+
+                let outcome = loop {
+                    let line_end = if any_line_end {
+                        find_line_end(&received, cr_final)
+                    } else {
+                        find_subslice(&received, delimiter.as_bytes())
+                            .map(|pos| (pos, pos + delimiter.len()))
+                    };
+                    if let Some((end, next)) = line_end {
+                        // With strip_echo, the first line back may be the shell
+                        // repeating the last write
+                        let line = &received[..end];
+                        let line = line.strip_suffix(b"\r").unwrap_or(line);
+                        if pending_echo
+                            .take()
+                            .is_some_and(|echo| line == echo.as_bytes())
+                        {
+                            received.drain(..next);
+                            continue;
+                        }
+                        *frame_buffer = received.split_off(next);
+                        received.truncate(end);
+                        break Ok(ReadTermination::Delimiter);
+                    }
+                    if received.len() >= self.max_read_bytes() {
+                        *frame_buffer = received.split_off(self.max_read_bytes());
+                        break Ok(ReadTermination::MaxBytes);
+                    }
+                    let now = std::time::Instant::now();
+                    if now >= deadline {
+                        break Ok(ReadTermination::OverallTimeout);
+                    }
+                    let idle_deadline = idle.map(|idle| last_byte + idle);
+                    if idle_deadline.is_some_and(|d| now >= d) {
+                        break Ok(ReadTermination::IdleTimeout);
+                    }
+                    if cancel.is_cancelled() {
+                        break Err(ServiceError::Cancelled);
+                    }
+                    let limit = idle_deadline.map_or(deadline, |d| d.min(deadline));
+                    let wait = (limit - now).min(WAIT_POLL_INTERVAL);
+                    match Self::poll_for_data(port.as_ref(), config.read_strategy, wait, cancel) {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            cr_final = true;
+                            continue;
+                        }
+                        Err(e) => break Err(e),
+                    }
+                    if let Err(e) = port.set_timeout(wait) {
+                        break Err(ServiceError::from(e));
+                    }
+
+                    match port.read_bytes(&mut buffer) {
+                        Ok(0) => {
+                            cr_final = true;
+                            std::thread::sleep(wait.min(Duration::from_millis(5)));
+                        }
+                        Ok(n) => {
+                            config.mask_received(&mut buffer[..n]);
+                            received.extend_from_slice(&buffer[..n]);
+                            bytes_read += n;
+                            *bytes_read_total += n as u64;
+                            *last_activity = std::time::Instant::now();
+                            *timeout_streak = 0;
+                            last_byte = *last_activity;
+                            cr_final = false;
+                        }
+                        Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                            // Non-blocking backends return immediately; avoid spinning
+                            cr_final = true;
+                            std::thread::sleep(wait.min(Duration::from_millis(5)));
+                        }
+                        Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::TimedOut => {
+                            cr_final = true;
+                        }
+                        Err(PortError::Timeout(_)) => cr_final = true,
+                        Err(PortError::Framing(_)) => *framing_errors += 1,
+                        Err(e) => break Err(ServiceError::from(e)),
+                    }
+                };
+
+                port.set_timeout(Duration::from_millis(config.timeout_ms))?;
+                let termination = match outcome {
+                    Ok(t) => t,
+                    Err(e) => {
+                        // Keep what arrived so a later read can still see it
+                        *frame_buffer = received;
+                        return Err(e);
+                    }
+                };
+                if bytes_read == 0 && termination != ReadTermination::Delimiter {
+                    *timeout_streak += 1;
+                }
+
+                let (data, _) = config.strip_shell_noise(
+                    &encode_read_data(&received, Some(&delimiter), ReadEncoding::Utf8),
+                    None,
+                );
+                Ok(ReadResult {
+                    open_id: *open_id,
+                    data,
+                    bytes_read,
+                    bytes_read_total: *bytes_read_total,
+                    framing_errors: port.detects_framing_errors().then_some(*framing_errors),
+                    auto_closed: None,
+                    termination: Some(termination),
+                    raw: None,
+                    hex: None,
+                })
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// Read everything that arrives until the port goes quiet, e.g. a boot
+    /// banner.
+    ///
+    /// Bytes accumulate until none has arrived for `silence_ms` (counted from
+    /// the call until the first byte), `max_bytes` have been received, or
+    /// `overall_timeout_ms` elapses; `termination` reports which
+    /// (`idle_timeout`, `max_bytes` or `overall_timeout`). This is the same
+    /// inter-byte gap read as `read_idle_gap_ms`, without waiting for a first
+    /// byte. The returned `data` is lossy UTF-8 with a trailing terminator
+    /// stripped, as for [`read`](Self::read).
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if `silence_ms` or `max_bytes` is zero,
+    ///   or `max_bytes` exceeds the read cap
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::Cancelled` if [`cancel_read`](Self::cancel_read) is called
+    /// - `ServiceError::PortError` if a non-timeout read error occurs
+    pub fn capture_until_silence(
+        &self,
+        silence_ms: u64,
+        max_bytes: usize,
+        overall_timeout_ms: u64,
+    ) -> ServiceResult<ReadResult> {
+        if silence_ms == 0 {
+            return Err(ServiceError::InvalidConfig(
+                "silence_ms must be greater than 0".to_string(),
+            ));
+        }
+        if max_bytes == 0 || max_bytes > self.max_read_bytes() {
+            return Err(ServiceError::InvalidConfig(format!(
+                "max_bytes must be between 1 and the read limit of {} bytes",
+                self.max_read_bytes()
+            )));
+        }
+
+        let mut st = lock_state(&self.state);
+        if let Some(closed) = self.expired_read_result(&mut st) {
+            return Ok(closed);
+        }
+
+        match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                last_activity,
+                timeout_streak,
+                bytes_read_total,
+                framing_errors,
+                frame_buffer,
+                open_id,
+                cancel,
+                pending_echo,
+                ..
+            } => {
+                cancel.reset();
+                let deadline =
+                    std::time::Instant::now() + Duration::from_millis(overall_timeout_ms);
+                let mut buffer = vec![0u8; max_bytes];
+                let bytes_read = Self::read_until_gap(
+                    port.as_mut(),
+                    &mut buffer,
+                    Duration::from_millis(silence_ms),
+                    Duration::from_millis(config.timeout_ms),
+                    Some(deadline),
+                    framing_errors,
+                    cancel,
+                )?;
+                let termination = if bytes_read == max_bytes {
+                    ReadTermination::MaxBytes
+                } else if std::time::Instant::now() >= deadline {
+                    ReadTermination::OverallTimeout
+                } else {
+                    ReadTermination::IdleTimeout
+                };
+
+                if bytes_read > 0 {
+                    *last_activity = std::time::Instant::now();
+                    *timeout_streak = 0;
+                    *bytes_read_total += bytes_read as u64;
+                } else {
+                    *timeout_streak += 1;
+                }
+
+                // Prepend any partial UTF-8 character held back by the last read
+                config.mask_received(&mut buffer[..bytes_read]);
+                let mut received = std::mem::take(frame_buffer);
+                received.extend_from_slice(&buffer[..bytes_read]);
+                let complete = received.len() - incomplete_utf8_tail(&received);
+                *frame_buffer = received.split_off(complete);
+
+                let (data, rest) = config.strip_shell_noise(
+                    &encode_read_data(&received, config.terminator.as_deref(), ReadEncoding::Utf8),
+                    pending_echo.take().as_deref(),
+                );
+                *pending_echo = rest;
+
+                Ok(ReadResult {
+                    open_id: *open_id,
+                    data,
+                    bytes_read,
+                    bytes_read_total: *bytes_read_total,
+                    framing_errors: port.detects_framing_errors().then_some(*framing_errors),
+                    auto_closed: None,
+                    termination: Some(termination),
+                    raw: None,
+                    hex: None,
+                })
+            }
+            PortState::Closed => Err(ServiceError::PortNotOpen),
+        }
+    }
+
+    /// Reconfigure the port (close and reopen with new settings).
+    ///
+    /// If no port_name is provided in the config, uses the currently open port's name.
+    /// A provided port_name may be a device alias, as with [`open`](Self::open).
+    /// This operation resets all metrics (bytes read/written, idle close count).
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::NoPortSpecified` if no port name provided and no port is open
+    /// - `ServiceError::PortError` if the port cannot be opened with new settings
+    pub fn reconfigure(&self, config: ReconfigureConfig) -> ServiceResult<OpenResult> {
+        self.note_error("reconfigure", self.reconfigure_now(config))
+    }
+
+    fn reconfigure_now(&self, mut config: ReconfigureConfig) -> ServiceResult<OpenResult> {
+        if let Some(name) = &config.port_name {
+            config.port_name = Some(self.resolve_port_name(name)?);
+        }
+        let mut st = lock_state(&self.state);
+        self.reconfigure_locked(&mut st, config)
+    }
+
+    /// Reopen with `config` while the caller holds the state lock. `st` is
+    /// left untouched unless the new port opens, so on error the old port
+    /// and settings stay in place.
+    fn reconfigure_locked(
+        &self,
+        st: &mut PortState,
+        config: ReconfigureConfig,
+    ) -> ServiceResult<OpenResult> {
+        // Start from the open port's settings so omitted fields are kept
+        let mut new_config = match (&config.port_name, &*st) {
+            (_, PortState::Open { config, .. }) => config.clone(),
+            (Some(p), PortState::Closed) => PortConfig {
+                port_name: p.clone(),
+                baud_rate: crate::state::DEFAULT_RECONFIG_BAUD_RATE,
+                timeout_ms: crate::state::default_timeout(),
+                data_bits: crate::state::default_data_bits(),
+                parity: crate::state::default_parity(),
+                stop_bits: crate::state::default_stop_bits(),
+                flow_control: crate::state::default_flow_control(),
+                terminator: None,
+                idle_disconnect_ms: None,
+                read_idle_gap_ms: None,
+                write_checksum: None,
+                half_duplex_echo: false,
+                flush_on_write: false,
+                auto_detect_terminator: false,
+                mask_parity_bit: None,
+                strip_echo: false,
+                strip_prompt: None,
+                max_open_duration_ms: None,
+                read_strategy: Default::default(),
+            },
+            (None, PortState::Closed) => return Err(ServiceError::NoPortSpecified),
+        };
+        if let Some(p) = &config.port_name {
+            new_config.port_name = p.clone();
+        }
+        new_config.apply_patch(config.settings);
+        let warnings = self.check_baud(new_config.baud_rate)?;
+        let target = new_config.port_name.clone();
+
+        // Build port configuration
+        let port_config = PortConfiguration {
+            baud_rate: new_config.baud_rate,
+            data_bits: Self::convert_data_bits(new_config.data_bits),
+            parity: Self::convert_parity(new_config.parity),
+            stop_bits: Self::convert_stop_bits(new_config.stop_bits),
+            flow_control: Self::convert_flow_control(new_config.flow_control),
+            timeout: Duration::from_millis(new_config.timeout_ms),
+        };
+
+        // Open port with new configuration
+        let port = (self.opener)(&target, port_config)?;
+
+        self.record_close(st, CloseReason::Reconfigure);
+
+        // Carry the session link (and, for a soft reconfigure, any partial
+        // frame) over from the port being replaced
+        let (active_session, frame_buffer) = match std::mem::take(st) {
+            PortState::Open {
+                active_session,
+                frame_buffer,
+                ..
+            } if config.soft_reconfigure => (active_session, frame_buffer),
+            PortState::Open { active_session, .. } => (active_session, Vec::new()),
+            PortState::Closed => (None, Vec::new()),
+        };
+
+        // Replace state
+        let open_id = Uuid::new_v4();
+        *st = PortState::Open {
+            port,
+            config: new_config.clone(),
+            last_activity: std::time::Instant::now(),
+            timeout_streak: 0,
+            bytes_read_total: 0,
+            bytes_written_total: 0,
+            idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer,
+            open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id,
+            timeout_log: crate::state::TimeoutLogThrottle::default(),
+            cancel: self.cancel.clone(),
+            active_session: active_session.clone(),
+            terminator_detect: new_config
+                .auto_detect_terminator
+                .then(TerminatorDetector::default),
+            pending_echo: None,
+        };
+        self.remember_config(&new_config);
+        self.watch_open_duration(&new_config);
+
+        Ok(OpenResult {
+            port_name: target,
+            baud_rate: new_config.baud_rate,
+            message: "reconfigured".to_string(),
+            open_id,
+            active_session,
+            config: new_config,
+            warnings,
+            probe_response: None,
+        })
+    }
+
+    /// Change only the given settings of the open port.
+    ///
+    /// Line settings (baud rate, data bits, parity, stop bits, flow control)
+    /// need a reopen, done as a soft [`reconfigure`](Self::reconfigure) that
+    /// keeps the session link and pending frame bytes but resets metrics.
+    /// The state lock is held throughout, and if the reopen fails the port
+    /// stays open with its old settings. Other settings are updated in place
+    /// on the open port.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if the port cannot be reopened or its timeout set
+    pub fn patch_config(&self, patch: PortConfigPatch) -> ServiceResult<PatchResult> {
+        let mut st = lock_state(&self.state);
+        let (port, config, open_id) = match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                open_id,
+                ..
+            } => (port, config, open_id),
+            PortState::Closed => return Err(ServiceError::PortNotOpen),
+        };
+        if !patch.requires_reopen() {
+            if let Some(ms) = patch.timeout_ms {
+                port.set_timeout(Duration::from_millis(ms))?;
+            }
+            config.apply_patch(patch);
+            return Ok(PatchResult {
+                open_id: *open_id,
+                config: config.clone(),
+                reopened: false,
+            });
+        }
+
+        // Keep the lock so nothing can use or replace the port mid-reopen
+        let opened = self.note_error(
+            "reconfigure",
+            self.reconfigure_locked(
+                &mut st,
+                ReconfigureConfig {
+                    port_name: None,
+                    settings: patch,
+                    soft_reconfigure: true,
+                },
+            ),
+        )?;
+        Ok(PatchResult {
+            open_id: opened.open_id,
+            config: opened.config,
+            reopened: true,
+        })
+    }
+
+    /// Change data bits, parity and/or stop bits of the open port, without
+    /// reopening it where the port supports that.
+    ///
+    /// Buffered data survives a live change. Ports that cannot change framing
+    /// live are reopened with a soft [`reconfigure`](Self::reconfigure),
+    /// which keeps the session link and pending frame bytes but resets
+    /// metrics; the result then carries a warning.
+    ///
+    /// # Errors
+    ///
+    /// - `ServiceError::InvalidConfig` if no setting is given
+    /// - `ServiceError::PortNotOpen` if no port is open
+    /// - `ServiceError::PortError` if the driver rejects a setting
+    pub fn set_framing(&self, request: FramingRequest) -> ServiceResult<FramingResult> {
+        if request.data_bits.is_none() && request.parity.is_none() && request.stop_bits.is_none() {
+            return Err(ServiceError::InvalidConfig(
+                "at least one of data_bits, parity or stop_bits must be given".to_string(),
+            ));
+        }
+        let patch = PortConfigPatch {
+            data_bits: request.data_bits,
+            parity: request.parity,
+            stop_bits: request.stop_bits,
+            ..Default::default()
+        };
+
+        let mut st = lock_state(&self.state);
+        let (port, config, open_id) = match &mut *st {
+            PortState::Open {
+                port,
+                config,
+                open_id,
+                ..
+            } => (port, config, open_id),
+            PortState::Closed => return Err(ServiceError::PortNotOpen),
+        };
+        let warning = match Self::set_framing_live(port.as_mut(), config, &request) {
+            Ok(()) => {
+                config.apply_patch(patch);
+                return Ok(FramingResult {
+                    open_id: *open_id,
+                    config: config.clone(),
+                    reopened: false,
+                    warning: None,
+                });
+            }
+            Err(PortError::Unsupported(what)) => format!(
+                "{} does not support {}; reopened the port instead",
+                config.port_name, what
+            ),
+            Err(e) => return Err(e.into()),
+        };
+        drop(st);
+
+        tracing::warn!("{}", warning);
+        let opened = self.reconfigure(ReconfigureConfig {
+            port_name: None,
+            settings: patch,
+            soft_reconfigure: true,
+        })?;
+        Ok(FramingResult {
+            open_id: opened.open_id,
+            config: opened.config,
+            reopened: true,
+            warning: Some(warning),
+        })
+    }
+
+    /// Apply the requested framing to `port` in place; fails with
+    /// `PortError::Unsupported` if the port has to be reopened instead.
+    ///
+    /// If a setting fails, those already applied are put back to `config`;
+    /// one that cannot be put back is recorded in `config` instead, so it
+    /// still describes the port.
+    fn set_framing_live(
+        port: &mut dyn SerialPortAdapter,
+        config: &mut PortConfig,
+        request: &FramingRequest,
+    ) -> Result<(), PortError> {
+        let mut applied = FramingRequest::default();
+        let result = (|| {
+            if let Some(bits) = request.data_bits {
+                port.set_data_bits(Self::convert_data_bits(bits))?;
+                applied.data_bits = Some(bits);
+            }
+            if let Some(parity) = request.parity {
+                port.set_parity(Self::convert_parity(parity))?;
+                applied.parity = Some(parity);
+            }
+            if let Some(bits) = request.stop_bits {
+                port.set_stop_bits(Self::convert_stop_bits(bits))?;
+                applied.stop_bits = Some(bits);
+            }
+            Ok(())
+        })();
+        if result.is_err() {
+            let mut kept = PortConfigPatch::default();
+            if applied.data_bits.is_some()
+                && port
+                    .set_data_bits(Self::convert_data_bits(config.data_bits))
+                    .is_err()
+            {
+                kept.data_bits = applied.data_bits;
+            }
+            if applied.parity.is_some()
+                && port
+                    .set_parity(Self::convert_parity(config.parity))
+                    .is_err()
+            {
+                kept.parity = applied.parity;
+            }
+            if applied.stop_bits.is_some()
+                && port
+                    .set_stop_bits(Self::convert_stop_bits(config.stop_bits))
+                    .is_err()
+            {
+                kept.stop_bits = applied.stop_bits;
+            }
+            config.apply_patch(kept);
+        }
+        result
+    }
+
+    /// Get current port status.
+    pub fn status(&self) -> ServiceResult<StatusResult> {
+        let st = lock_state(&self.state);
+
+        let result = match &*st {
+            PortState::Closed => StatusResult::Closed,
+            PortState::Open {
+                config,
+                frame_buffer,
+                open_id,
+                active_session,
+                terminator_detect,
+                ..
+            } => StatusResult::Open {
+                open_id: *open_id,
+                active_session: active_session.clone(),
+                config: config.clone(),
+                metrics: Self::port_metrics(&st),
+                pending_frame_bytes: frame_buffer.len(),
+                detecting_terminator: terminator_detect.is_some(),
+            },
+        };
+
+        Ok(result)
+    }
+
+    /// Report what the open port supports, or the platform defaults if no
+    /// port is open.
+    pub fn capabilities(&self) -> CapabilitiesResult {
+        let st = lock_state(&self.state);
+        let (port_name, capabilities) = match &*st {
+            PortState::Open { port, config, .. } => {
+                (Some(config.port_name.clone()), port.capabilities())
+            }
+            PortState::Closed => (None, PortCapabilities::platform()),
+        };
+        CapabilitiesResult {
+            platform: std::env::consts::OS.to_string(),
+            port_name,
+            capabilities,
+        }
+    }
+
+    /// Get port metrics.
+    pub fn metrics(&self) -> ServiceResult<MetricsResult> {
+        let st = lock_state(&self.state);
+
+        let result = match &*st {
+            PortState::Closed => MetricsResult {
+                state: "Closed".to_string(),
+                bytes_read_total: None,
+                bytes_written_total: None,
+                idle_close_count: None,
+                open_duration_ms: None,
+                last_activity_ms: None,
+                open_started_at: None,
+                last_activity_at: None,
+                timeout_streak: None,
+                pending_frame_bytes: None,
+            },
+            PortState::Open {
+                bytes_read_total,
+                bytes_written_total,
+                idle_close_count,
+                open_started,
+                open_started_at,
+                last_activity,
+                timeout_streak,
+                frame_buffer,
+                ..
+            } => MetricsResult {
+                state: "Open".to_string(),
+                bytes_read_total: Some(*bytes_read_total),
+                bytes_written_total: Some(*bytes_written_total),
+                idle_close_count: Some(*idle_close_count),
+                open_duration_ms: Some(open_started.elapsed().as_millis() as u64),
+                last_activity_ms: Some(last_activity.elapsed().as_millis() as u64),
+                open_started_at: Some(*open_started_at),
+                last_activity_at: Some(wall_clock_at(
+                    *open_started,
+                    *open_started_at,
+                    *last_activity,
+                )),
+                timeout_streak: Some(*timeout_streak),
+                pending_frame_bytes: Some(frame_buffer.len()),
+            },
+        };
+
+        Ok(result)
+    }
+
+    /// Check if a port is currently open.
+    pub fn is_open(&self) -> bool {
+        matches!(&*lock_state(&self.state), PortState::Open { .. })
+    }
+
+    // ========== Helper Methods ==========
+
+    /// Resolve a device alias to a port path; other names are returned as-is.
+    fn resolve_port_name(&self, name: &str) -> ServiceResult<String> {
+        let aliases = self.settings().device_aliases.clone();
+        if !aliases.iter().any(|a| a.name == name) {
+            return Ok(name.to_string());
+        }
+        resolve_device_alias(&aliases, name, serialport::available_ports).ok_or_else(|| {
+            ServiceError::DeviceNotFound(format!("No connected device matches alias '{}'", name))
+        })
+    }
+
+    /// Keep reading into `buffer` until no new bytes arrive within `gap`, the
+    /// buffer is full or `deadline` (if any) passes. The port timeout is
+    /// restored to `timeout` afterwards.
+    /// With [`ReadStrategy::Poll`], check the port's pending byte count every
+    /// poll interval until it is nonzero or `window` has passed; `false` means
+    /// nothing arrived. Blocking reads, and ports that cannot report pending
+    /// bytes, return `true` at once and leave the waiting to the read.
+    fn poll_for_data(
+        port: &dyn SerialPortAdapter,
+        strategy: ReadStrategy,
+        window: Duration,
+        cancel: &CancelToken,
+    ) -> ServiceResult<bool> {
+        let ReadStrategy::Poll { interval_ms } = strategy else {
+            return Ok(true);
+        };
+        let interval = Duration::from_millis(interval_ms);
+        let deadline = std::time::Instant::now() + window;
+        while port.bytes_to_read() == Some(0) {
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                return Ok(false);
+            }
+            if cancel.is_cancelled() {
+                return Err(ServiceError::Cancelled);
+            }
+            std::thread::sleep(interval.min(deadline - now));
+        }
+        Ok(true)
+    }
+
+    fn read_until_gap(
+        port: &mut dyn SerialPortAdapter,
+        buffer: &mut [u8],
+        gap: Duration,
+        timeout: Duration,
+        deadline: Option<std::time::Instant>,
+        framing_errors: &mut u64,
+        cancel: &CancelToken,
+    ) -> ServiceResult<usize> {
+        let mut filled = 0;
+        let mut applied = None;
+        let result = loop {
+            if filled == buffer.len() {
+                break Ok(filled);
+            }
+            if cancel.is_cancelled() {
+                break Err(ServiceError::Cancelled);
+            }
+            // Never block past the deadline
+            let wait = deadline.map_or(gap, |d| {
+                gap.min(d.saturating_duration_since(std::time::Instant::now()))
+            });
+            if wait.is_zero() {
+                break Ok(filled);
+            }
+            if applied != Some(wait) {
+                if let Err(e) = port.set_timeout(wait) {
+                    break Err(ServiceError::from(e));
+                }
+                applied = Some(wait);
+            }
+            match port.read_bytes(&mut buffer[filled..]) {
+                Ok(0) => break Ok(filled),
+                Ok(n) => filled += n,
+                Err(PortError::Io(ref e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                    ) =>
+                {
+                    break Ok(filled)
+                }
+                Err(PortError::Timeout(_)) => break Ok(filled),
+                Err(PortError::Framing(_)) => {
+                    *framing_errors += 1;
+                    break Ok(filled);
+                }
+                Err(e) => break Err(ServiceError::from(e)),
+            }
+        };
+
+        port.set_timeout(timeout)?;
+        result
+    }
+
+    /// Write a Modbus read `frame`, then read its response to a read of
+    /// `count` registers, which must complete within `timeout`.
+    ///
+    /// The port timeout is left at the last poll interval; callers restore it.
+    fn modbus_exchange(
+        port: &mut dyn SerialPortAdapter,
+        frame: &[u8],
+        count: u16,
+        timeout: Duration,
+        framing_errors: &mut u64,
+        cancel: &CancelToken,
+    ) -> ServiceResult<Vec<u8>> {
+        let (_, outcome) = Self::write_all(port, frame, timeout);
+        outcome?;
+
+        let deadline = std::time::Instant::now() + timeout;
+        let mut received: Vec<u8> = Vec::new();
+        let mut buffer = vec![0u8; DEFAULT_READ_BYTES];
+        loop {
+            let expected = modbus::response_len(&received, count);
+            if received.len() >= expected {
+                received.truncate(expected);
+                break Ok(received);
+            }
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                break Err(ServiceError::Timeout(timeout));
+            }
+            if cancel.is_cancelled() {
+                break Err(ServiceError::Cancelled);
+            }
+            let wait = (deadline - now).min(WAIT_POLL_INTERVAL);
+            port.set_timeout(wait)?;
+
+            match port.read_bytes(&mut buffer[..expected - received.len()]) {
+                Ok(n) => received.extend_from_slice(&buffer[..n]),
+                Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(wait.min(Duration::from_millis(5)));
+                }
+                Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(PortError::Timeout(_)) => {}
+                Err(PortError::Framing(_)) => *framing_errors += 1,
+                Err(e) => break Err(ServiceError::from(e)),
+            }
+        }
+    }
+
+    /// Write `frame`, then read until `delimiter` arrives or `timeout` elapses.
+    ///
+    /// Bytes after the delimiter are discarded, as is anything beyond
+    /// `max_bytes`. The port timeout is left at the last poll interval;
+    /// callers restore it.
+    fn transact(
+        port: &mut dyn SerialPortAdapter,
+        frame: &[u8],
+        delimiter: &[u8],
+        timeout: Duration,
+        max_bytes: usize,
+        framing_errors: &mut u64,
+        cancel: &CancelToken,
+    ) -> ServiceResult<Transaction> {
+        let (bytes_written, outcome) = Self::write_all(port, frame, timeout);
+        outcome?;
+
+        let deadline = std::time::Instant::now() + timeout;
+        let mut received: Vec<u8> = Vec::new();
+        let mut bytes_read = 0;
+        let mut buffer = vec![0u8; DEFAULT_READ_BYTES];
+        loop {
+            if find_subslice(&received, delimiter).is_some() {
+                break Ok(Transaction {
+                    bytes_written,
+                    bytes_read,
+                    completed: true,
+                });
+            }
+            let now = std::time::Instant::now();
+            if now >= deadline {
+                break Ok(Transaction {
+                    bytes_written,
+                    bytes_read,
+                    completed: false,
+                });
+            }
+            if cancel.is_cancelled() {
+                break Err(ServiceError::Cancelled);
+            }
+            let wait = (deadline - now).min(WAIT_POLL_INTERVAL);
+            port.set_timeout(wait)?;
+
+            match port.read_bytes(&mut buffer) {
+                Ok(0) => {}
+                Ok(n) => {
+                    received.extend_from_slice(&buffer[..n]);
+                    if received.len() > max_bytes {
+                        received.drain(..received.len() - max_bytes);
+                    }
+                    bytes_read += n;
+                }
+                Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(wait.min(Duration::from_millis(5)));
+                }
+                Err(PortError::Io(ref e)) if e.kind() == std::io::ErrorKind::TimedOut => {}
+                Err(PortError::Timeout(_)) => {}
+                Err(PortError::Framing(_)) => *framing_errors += 1,
+                Err(e) => break Err(ServiceError::from(e)),
+            }
+        }
+    }
+
+    /// Write all of `data`, continuing after short writes.
+    ///
+    /// Returns the bytes written, which is less than `data.len()` only when
+    /// the result is an error. If the port accepts nothing for `timeout`
+    /// (zero-length writes or a write timeout), the error is
+    /// `ServiceError::WriteTimeout` carrying the bytes sent so far.
+    fn write_all(
+        port: &mut dyn SerialPortAdapter,
+        data: &[u8],
+        timeout: Duration,
+    ) -> (usize, ServiceResult<()>) {
+        let (written, _, outcome) = Self::write_all_observed(port, data, timeout);
+        (written, outcome)
+    }
+
+    /// [`write_all`](Self::write_all), also returning how long the device
+    /// held the write off with XOFF, or `None` if the port cannot tell.
+    fn write_all_observed(
+        port: &mut dyn SerialPortAdapter,
+        data: &[u8],
+        timeout: Duration,
+    ) -> (usize, Option<Duration>, ServiceResult<()>) {
+        let mut flow_paused = port.flow_paused().map(|_| Duration::ZERO);
+        let timed_out = |written, port: &dyn SerialPortAdapter| ServiceError::WriteTimeout {
+            written,
+            expected: data.len(),
+            flow_paused: port.flow_paused() == Some(true),
+        };
+        let mut written = 0;
+        let mut stalled_since = None;
+        while written < data.len() {
+            match port.write_bytes(&data[written..]) {
+                Ok(0) => {
+                    let since = *stalled_since.get_or_insert_with(std::time::Instant::now);
+                    if since.elapsed() >= timeout {
+                        return (written, flow_paused, Err(timed_out(written, &*port)));
+                    }
+                    let xoff = port.flow_paused() == Some(true);
+                    let slept = std::time::Instant::now();
+                    std::thread::sleep(WRITE_RETRY_INTERVAL);
+                    if let (true, Some(paused)) = (xoff, flow_paused.as_mut()) {
+                        *paused += slept.elapsed();
+                    }
+                }
+                Ok(n) => {
+                    written += n;
+                    stalled_since = None;
+                }
+                Err(PortError::Io(e)) if e.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(PortError::Timeout(_)) => {
+                    return (written, flow_paused, Err(timed_out(written, &*port)))
+                }
+                Err(PortError::Io(e)) if e.kind() == std::io::ErrorKind::TimedOut => {
+                    return (written, flow_paused, Err(timed_out(written, &*port)))
+                }
+                Err(e) => return (written, flow_paused, Err(ServiceError::from(e))),
+            }
+        }
+        (written, flow_paused, Ok(()))
+    }
+
+    /// Read and discard the echo of `sent`, waiting up to `timeout` for it.
+    ///
+    /// Exactly `sent.len()` bytes are consumed (fewer if the echo does not
+    /// arrive in time), so the device's reply stays queued for the next read.
+    /// Returns whether the echo matched what was sent.
+    fn consume_echo(
+        port: &mut dyn SerialPortAdapter,
+        sent: &[u8],
+        timeout: Duration,
+        framing_errors: &mut u64,
+    ) -> ServiceResult<bool> {
+        let deadline = std::time::Instant::now() + timeout;
+        let mut echo = vec![0u8; sent.len()];
+        let mut filled = 0;
+        while filled < echo.len() {
+            match port.read_bytes(&mut echo[filled..]) {
+                Ok(n) if n > 0 => {
+                    filled += n;
+                    continue;
+                }
+                Ok(_) | Err(PortError::Timeout(_)) => {}
+                Err(PortError::Io(ref e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                    ) => {}
+                Err(PortError::Framing(_)) => *framing_errors += 1,
+                Err(e) => return Err(e.into()),
+            }
+            if std::time::Instant::now() >= deadline {
+                break;
+            }
+            // Nothing yet; the echo is normally only a few bit-times away
+            std::thread::sleep(Duration::from_millis(1));
+        }
+        Ok(filled == sent.len() && echo == sent)
+    }
+
+    /// Write `levels` back in reverse order, attempting every line even if one
+    /// fails. Returns the first error.
+    fn restore_signals(
+        port: &mut dyn SerialPortAdapter,
+        levels: &[(ControlSignal, bool)],
+    ) -> Result<(), PortError> {
+        let mut result = Ok(());
+        for &(signal, level) in levels.iter().rev() {
+            if let Err(e) = port.write_signal(signal, level) {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
+    fn convert_data_bits(bits: DataBitsCfg) -> DataBits {
+        match bits {
+            DataBitsCfg::Five => DataBits::Five,
+            DataBitsCfg::Six => DataBits::Six,
+            DataBitsCfg::Seven => DataBits::Seven,
+            DataBitsCfg::Eight => DataBits::Eight,
+        }
+    }
+
+    fn convert_parity(parity: ParityCfg) -> Parity {
+        match parity {
+            ParityCfg::None => Parity::None,
+            ParityCfg::Odd => Parity::Odd,
+            ParityCfg::Even => Parity::Even,
+        }
+    }
+
+    fn convert_stop_bits(bits: StopBitsCfg) -> StopBits {
+        match bits {
+            StopBitsCfg::One => StopBits::One,
+            StopBitsCfg::OnedotFive => StopBits::OnedotFive,
+            StopBitsCfg::Two => StopBits::Two,
+        }
+    }
+
+    fn convert_flow_control(flow: FlowControlCfg) -> FlowControl {
+        match flow {
+            FlowControlCfg::None => FlowControl::None,
+            FlowControlCfg::Hardware => FlowControl::Hardware,
+            FlowControlCfg::Software => FlowControl::Software,
+        }
+    }
+}
+
+// ========== Tests ==========
+
+/// Random ASCII hex token of exactly `len` bytes.
+fn loopback_token(len: usize) -> String {
+    let mut token = String::with_capacity(len + 32);
+    while token.len() < len {
+        token.push_str(&uuid::Uuid::new_v4().simple().to_string());
+    }
+    token.truncate(len);
+    token
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::port::{FramingChange, MockSerialPort, ModemStatus};
+    use crate::state::PortState;
+    use std::sync::{Arc, Mutex};
+
+    fn create_test_service() -> PortService {
+        let state = Arc::new(Mutex::new(PortState::Closed));
+        PortService::new(state)
+    }
+
+    fn create_open_config(port_name: &str) -> OpenConfig {
+        OpenConfig {
+            port_name: port_name.to_string(),
+            baud_rate: 9600,
+            timeout_ms: 1000,
+            data_bits: DataBitsCfg::Eight,
+            parity: ParityCfg::None,
+            stop_bits: StopBitsCfg::One,
+            flow_control: FlowControlCfg::None,
+            terminator: Some("\n".to_string()),
+            idle_disconnect_ms: None,
+            read_idle_gap_ms: None,
+            write_checksum: None,
+            half_duplex_echo: false,
+            flush_on_write: false,
+            auto_detect_terminator: false,
+            mask_parity_bit: None,
+            strip_echo: false,
+            strip_prompt: None,
+            max_open_duration_ms: None,
+            open_retries: None,
+            open_retry_delay_ms: None,
+            require_response: None,
+            read_strategy: Default::default(),
+        }
+    }
+
+    fn mock_port_config() -> PortConfig {
+        PortConfig {
+            port_name: "MOCK0".to_string(),
+            baud_rate: 9600,
+            timeout_ms: 1000,
+            data_bits: DataBitsCfg::Eight,
+            parity: ParityCfg::None,
+            stop_bits: StopBitsCfg::One,
+            flow_control: FlowControlCfg::None,
+            terminator: None,
+            idle_disconnect_ms: None,
+            read_idle_gap_ms: None,
+            write_checksum: None,
+            half_duplex_echo: false,
+            flush_on_write: false,
+            auto_detect_terminator: false,
+            mask_parity_bit: None,
+            strip_echo: false,
+            strip_prompt: None,
+            max_open_duration_ms: None,
+            read_strategy: Default::default(),
+        }
+    }
+
+    fn create_open_mock_service(max_read_bytes: usize, mock: MockSerialPort) -> PortService {
+        create_open_mock_service_with_config(max_read_bytes, mock, mock_port_config())
+    }
+
+    fn create_open_mock_service_with_config(
+        max_read_bytes: usize,
+        mock: MockSerialPort,
+        config: PortConfig,
+    ) -> PortService {
+        let service = PortService::new(Arc::new(Mutex::new(PortState::Closed)))
+            .with_max_read_bytes(max_read_bytes);
+        *lock_state(&service.state) = PortState::Open {
+            port: Box::new(mock),
+            config,
+            last_activity: std::time::Instant::now(),
+            timeout_streak: 0,
+            bytes_read_total: 0,
+            bytes_written_total: 0,
+            idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer: Vec::new(),
+            open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id: uuid::Uuid::new_v4(),
+            timeout_log: crate::state::TimeoutLogThrottle::default(),
+            cancel: service.cancel_token(),
+            active_session: None,
+            terminator_detect: None,
+            pending_echo: None,
+        };
+        service
+    }
+
+    #[test]
+    fn test_service_creation() {
+        let service = create_test_service();
+        assert!(!service.is_open());
+    }
+
+    #[test]
+    fn test_close_when_already_closed() {
+        let service = create_test_service();
+        let result = service.close();
+        assert!(result.is_ok());
+        let result = result.unwrap();
+        assert_eq!(result.message, "already closed");
+        assert_eq!(result.reason, CloseReason::Manual);
+        assert!(result.final_metrics.is_none());
+        assert!(service.last_close().is_none());
+    }
+
+    #[test]
+    fn test_manual_close_returns_final_metrics() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"OK");
+        let service = create_open_mock_service(1024, mock);
+        service.write("PING").unwrap();
+        service.read().unwrap();
+
+        let result = service.close().unwrap();
+        assert_eq!(result.message, "closed");
+        assert_eq!(result.reason, CloseReason::Manual);
+        let metrics = result.final_metrics.expect("final metrics");
+        assert_eq!(metrics.bytes_read_total, 2);
+        assert_eq!(metrics.bytes_written_total, 4);
+        assert_eq!(metrics.idle_close_count, 0);
+        assert_eq!(
+            service
+                .last_close()
+                .unwrap()
+                .final_metrics
+                .unwrap()
+                .bytes_written_total,
+            4
+        );
+    }
+
+    #[test]
+    fn test_write_when_not_open() {
+        let service = create_test_service();
+        let result = service.write("test");
+        assert!(matches!(result, Err(ServiceError::PortNotOpen)));
+    }
+
+    #[test]
+    fn test_read_when_not_open() {
+        let service = create_test_service();
+        let result = service.read();
+        assert!(matches!(result, Err(ServiceError::PortNotOpen)));
+    }
+
+    #[test]
+    fn test_default_max_read_bytes() {
+        let service = create_test_service();
+        assert_eq!(service.max_read_bytes(), DEFAULT_MAX_READ_BYTES);
+    }
+
+    #[test]
+    fn test_read_transform_keeps_raw() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                terminator: Some("\n".to_string()),
+                ..mock_port_config()
+            },
+        );
+        let transform = ReadTransform {
+            strip_ansi: true,
+            strip_control: true,
+            trim: true,
+        };
+
+        mock.enqueue_read(b"\x1b[33m  VOLT 12.1\x1b[0m\r\n");
+        let result = service
+            .read_transformed(1024, ReadEncoding::Utf8, transform, false)
+            .unwrap();
+        assert_eq!(result.data, "VOLT 12.1");
+        assert_eq!(result.raw.as_deref(), Some("\x1b[33m  VOLT 12.1\x1b[0m\r"));
+
+        // Binary encodings and the identity transform are left alone
+        mock.enqueue_read(b"\x1b[0m");
+        let result = service
+            .read_transformed(1024, ReadEncoding::Base64, transform, false)
+            .unwrap();
+        assert_eq!(result.data, "G1swbQ==");
+        assert!(result.raw.is_none());
+        mock.enqueue_read(b" x ");
+        let result = service
+            .read_transformed(1024, ReadEncoding::Utf8, ReadTransform::default(), false)
+            .unwrap();
+        assert_eq!(result.data, " x ");
+        assert!(result.raw.is_none());
+    }
+
+    #[test]
+    fn test_read_includes_hex_of_the_same_bytes() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                terminator: Some("\r\n".to_string()),
+                ..mock_port_config()
+            },
+        );
+
+        mock.enqueue_read(b"T=21\x00\xff\r\n");
+        let result = service
+            .read_transformed(1024, ReadEncoding::Utf8, ReadTransform::default(), true)
+            .unwrap();
+        assert_eq!(result.data, "T=21\0\u{fffd}");
+        assert_eq!(result.hex.as_deref(), Some("543D323100FF0D0A"));
+        assert_eq!(result.bytes_read * 2, result.hex.unwrap().len());
+
+        mock.enqueue_read(b"\x01\x02");
+        let result = service
+            .read_transformed(1024, ReadEncoding::Base64, ReadTransform::default(), true)
+            .unwrap();
+        assert_eq!(result.data, "AQI=");
+        assert_eq!(result.hex.as_deref(), Some("0102"));
+
+        // Off by default
+        mock.enqueue_read(b"x");
+        assert!(service.read().unwrap().hex.is_none());
+    }
+
+    #[test]
+    fn test_read_hex_follows_held_back_utf8_bytes() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        // The partial euro sign is held back, so neither view includes it yet
+        mock.enqueue_read(b"A\xE2\x82");
+        let result = service
+            .read_transformed(1024, ReadEncoding::Utf8, ReadTransform::default(), true)
+            .unwrap();
+        assert_eq!(result.data, "A");
+        assert_eq!(result.hex.as_deref(), Some("41"));
+
+        // Once completed, both views cover the whole character
+        mock.enqueue_read(b"\xAC!");
+        let result = service
+            .read_transformed(1024, ReadEncoding::Utf8, ReadTransform::default(), true)
+            .unwrap();
+        assert_eq!(result.data, "\u{20ac}!");
+        assert_eq!(result.hex.as_deref(), Some("E282AC21"));
+    }
+
+    #[test]
+    fn test_protocol_command_classifies_responses() {
+        let sensor = ProtocolDescriptor {
+            name: "sensor".to_string(),
+            command_terminator: "\r\n".to_string(),
+            response_terminator: "\r\n".to_string(),
+            response_prefix: Some("$OK,".to_string()),
+            error_prefix: Some("$ERR,".to_string()),
+            timeout_ms: 200,
+        };
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        // The echo is skipped and the prefix stripped from the payload
+        mock.enqueue_read(b"READ TEMP\r\n$OK,21.5\r\n$EVT");
+        let result = service.protocol_command(&sensor, "READ TEMP").unwrap();
+        assert_eq!(mock.get_write_log().concat(), b"READ TEMP\r\n");
+        assert_eq!(result.status, ResponseStatus::Success);
+        assert_eq!(result.payload.as_deref(), Some("21.5"));
+        assert_eq!(result.response.as_deref(), Some("$OK,21.5"));
+        assert_eq!(result.skipped, vec!["READ TEMP"]);
+        // The partial line after the response is kept for the next read
+        assert_eq!(service.read().unwrap().data, "$EVT");
+
+        mock.enqueue_read(b"$ERR,unknown command\r\n");
+        let result = service.protocol_command(&sensor, "FOO").unwrap();
+        assert_eq!(result.status, ResponseStatus::Error);
+        assert_eq!(result.payload.as_deref(), Some("unknown command"));
+
+        // Lines without either prefix never complete the command
+        mock.enqueue_read(b"booting\r\n");
+        let result = service.protocol_command(&sensor, "READ TEMP").unwrap();
+        assert_eq!(result.status, ResponseStatus::Timeout);
+        assert_eq!(result.payload, None);
+        assert_eq!(result.skipped, vec!["booting"]);
+    }
+
+    #[test]
+    fn test_protocol_command_without_response_prefix() {
+        // A shell-like device: the first non-error line is the answer
+        let shell = ProtocolDescriptor {
+            name: "shell".to_string(),
+            command_terminator: "\n".to_string(),
+            response_terminator: "\n".to_string(),
+            response_prefix: None,
+            error_prefix: Some("ERR ".to_string()),
+            timeout_ms: 200,
+        };
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        mock.enqueue_read(b"\r\nv1.4.2\r\n");
+        let result = service.protocol_command(&shell, "version").unwrap();
+        assert_eq!(mock.get_write_log().concat(), b"version\n");
+        assert_eq!(result.status, ResponseStatus::Success);
+        assert_eq!(result.payload.as_deref(), Some("v1.4.2"));
+        assert!(result.skipped.is_empty());
+
+        mock.enqueue_read(b"ERR no such file\n");
+        let result = service.protocol_command(&shell, "cat x").unwrap();
+        assert_eq!(result.status, ResponseStatus::Error);
+        assert_eq!(result.payload.as_deref(), Some("no such file"));
+    }
+
+    #[test]
+    fn test_seven_bit_reads_mask_the_parity_bit() {
+        // "READY\n" with even parity in the high bit
+        let even_parity = [0xD2, 0xC5, 0x41, 0x44, 0x59, 0x0A];
+        let seven_bit = PortConfig {
+            data_bits: DataBitsCfg::Seven,
+            parity: ParityCfg::Even,
+            terminator: Some("\n".to_string()),
+            ..mock_port_config()
+        };
+
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(1024, mock.clone(), seven_bit.clone());
+        mock.enqueue_read(&even_parity);
+        let result = service
+            .read_transformed(1024, ReadEncoding::Utf8, ReadTransform::default(), true)
+            .unwrap();
+        assert_eq!(result.data, "READY");
+        // The hex view keeps the bytes as received
+        assert_eq!(result.hex.as_deref(), Some("D2C54144590A"));
+
+        mock.enqueue_read(&even_parity);
+        assert_eq!(service.read_line(1000, None).unwrap().data, "READY");
+
+        // Hardware that already strips the bit can turn masking off
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                mask_parity_bit: Some(false),
+                strip_echo: false,
+                strip_prompt: None,
+                max_open_duration_ms: None,
+                ..seven_bit
+            },
+        );
+        mock.enqueue_read(&even_parity);
+        assert_ne!(service.read().unwrap().data, "READY");
+    }
+
+    #[test]
+    fn test_read_bytes_n_above_cap_rejected() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"hello");
+        let service = create_open_mock_service(64, mock);
+        let result = service.read_bytes_n(65);
+        assert!(matches!(result, Err(ServiceError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_read_bytes_n_below_cap_succeeds() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"hello");
+        let service = create_open_mock_service(64, mock);
+        let result = service.read_bytes_n(63).unwrap();
+        assert_eq!(result.data, "hello");
+        assert_eq!(result.bytes_read, 5);
+    }
+
+    #[test]
+    fn test_read_reports_framing_errors() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        mock.enqueue_read(b"ok");
+        let result = service.read().unwrap();
+        assert_eq!(result.framing_errors, Some(0));
+
+        mock.set_framing_error(true);
+        mock.enqueue_read(b"x");
+        let result = service.read().unwrap();
+        assert_eq!(result.bytes_read, 0);
+        assert_eq!(result.framing_errors, Some(1));
+
+        let result = service.read().unwrap();
+        assert_eq!(result.data, "x");
+        assert_eq!(result.framing_errors, Some(1));
+    }
+
+    #[test]
+    fn test_write_repeat_writes_count_times() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        let result = service.write_repeat("PING", 5, 0).unwrap();
+        assert_eq!(result.count, 5);
+        assert_eq!(result.bytes_written, 20);
+        assert_eq!(result.bytes_written_total, 20);
+        assert_eq!(mock.get_write_log().len(), 5);
+    }
+
+    #[test]
+    fn test_write_repeat_above_cap_rejected() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone()).with_max_write_repeat_bytes(10);
+        let result = service.write_repeat("PING", 3, 0);
+        assert!(matches!(result, Err(ServiceError::InvalidConfig(_))));
+        assert!(mock.get_write_log().is_empty());
+    }
+
+    #[test]
+    fn test_write_repeat_caps_delay_and_stops_on_cancel() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = Arc::new(create_open_mock_service(1024, mock.clone()));
+        for (count, delay_ms) in [
+            (2, MAX_WRITE_REPEAT_DELAY_MS + 1),
+            (8, MAX_WRITE_REPEAT_DURATION_MS / 5),
+        ] {
+            assert!(matches!(
+                service.write_repeat("PING", count, delay_ms),
+                Err(ServiceError::InvalidConfig(_))
+            ));
+        }
+        assert!(mock.get_write_log().is_empty());
+
+        let writer = Arc::clone(&service);
+        let repeat = std::thread::spawn(move || writer.write_repeat("PING", 5, 5_000));
+        std::thread::sleep(Duration::from_millis(50));
+        service.cancel_read();
+        assert!(matches!(
+            repeat.join().unwrap(),
+            Err(ServiceError::Cancelled)
+        ));
+        assert_eq!(mock.get_write_log().len(), 1);
+    }
+
+    #[test]
+    fn test_wait_for_pattern_matches_stream() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"booting...\r\n");
+        mock.enqueue_read_after(Duration::from_millis(30), b"READY v1.2\r\n");
+        let service = create_open_mock_service(1024, mock);
+
+        let result = service.wait_for_pattern(r"READY v\d+\.\d+", 2000).unwrap();
+        assert!(!result.timed_out);
+        assert_eq!(result.matched.as_deref(), Some("READY v1.2"));
+        assert!(result.data.starts_with("booting..."));
+        assert_eq!(result.bytes_read, result.data.len());
+    }
+
+    #[test]
+    fn test_wait_for_pattern_times_out() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"noise");
+        let service = create_open_mock_service(1024, mock);
+
+        let result = service.wait_for_pattern("READY", 100).unwrap();
+        assert!(result.timed_out);
+        assert!(result.matched.is_none());
+        assert_eq!(result.data, "noise");
+        assert!(result.elapsed_ms >= 100);
+    }
+
+    #[test]
+    fn test_wait_for_pattern_rejects_oversized_regex() {
+        let service = create_open_mock_service(1024, MockSerialPort::new("MOCK0"));
+        let result = service.wait_for_pattern(r"\w{1000}{1000}", 100);
+        assert!(matches!(result, Err(ServiceError::InvalidConfig(_))));
+    }
+
+    fn flaky_opener(
+        failures: usize,
+        error: fn() -> PortError,
+    ) -> (PortOpener, Arc<std::sync::atomic::AtomicUsize>) {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = calls.clone();
+        let opener: PortOpener = Arc::new(move |name, _config| {
+            let n = counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            if n < failures {
+                Err(error())
+            } else {
+                Ok(Box::new(MockSerialPort::new(name)) as PortAdapter)
+            }
+        });
+        (opener, calls)
+    }
+
+    #[test]
+    fn test_auto_detect_terminator_settles_on_crlf() {
+        let mock = MockSerialPort::new("MOCK0");
+        let port = mock.clone();
+        let opener: PortOpener =
+            Arc::new(move |_name, _config| Ok(Box::new(port.clone()) as PortAdapter));
+        let service = create_test_service().with_port_opener(opener);
+        let mut config = create_open_config("MOCK0");
+        config.terminator = None;
+        config.auto_detect_terminator = true;
+        service.open(config).unwrap();
+
+        let detecting = |service: &PortService| match service.status().unwrap() {
+            StatusResult::Open {
+                detecting_terminator,
+                config,
+                ..
+            } => (detecting_terminator, config.terminator),
+            StatusResult::Closed => panic!("port should be open"),
+        };
+        assert_eq!(detecting(&service), (true, None));
+
+        // Raw data until detection completes; the second `\r\n` spans two reads
+        mock.clone().enqueue_read(b"OK\r\nREADY\r");
+        assert_eq!(service.read().unwrap().data, "OK\r\nREADY\r");
+        assert_eq!(detecting(&service), (true, None));
+        mock.clone().enqueue_read(b"\nTEMP 21\r\n");
+        assert_eq!(service.read().unwrap().data, "\nTEMP 21\r\n");
+        assert_eq!(detecting(&service), (false, Some("\r\n".to_string())));
+
+        mock.clone().enqueue_read(b"TEMP 22\r\n");
+        assert_eq!(service.read().unwrap().data, "TEMP 22");
+    }
+
+    #[test]
+    fn test_open_retries_transient_failure() {
+        let (opener, calls) = flaky_opener(1, || PortError::busy("MOCK0", None));
+        let service = create_test_service().with_port_opener(opener);
+        let mut config = create_open_config("MOCK0");
+        config.open_retries = Some(2);
+        config.open_retry_delay_ms = Some(1);
+
+        service.open(config).unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(matches!(
+            service.status().unwrap(),
+            StatusResult::Open { .. }
+        ));
+    }
+
+    #[test]
+    fn test_open_require_response_probes_device() {
+        let mock = MockSerialPort::new("MOCK0");
+        let port = mock.clone();
+        let opener: PortOpener =
+            Arc::new(move |_name, _config| Ok(Box::new(port.clone()) as PortAdapter));
+        let service = create_test_service().with_port_opener(opener);
+        let mut config = create_open_config("MOCK0");
+        config.require_response = Some(ResponseProbe {
+            probe: "AT\r\n".to_string(),
+            timeout_ms: 100,
+        });
+
+        // Responsive device: the reply is reported and the port stays open
+        mock.clone().enqueue_read(b"OK\r\n");
+        let result = service.open(config.clone()).unwrap();
+        assert_eq!(result.probe_response.as_deref(), Some("OK\r\n"));
+        assert_eq!(mock.get_write_log().concat(), b"AT\r\n");
+        assert!(service.is_open());
+        service.close().unwrap();
+
+        // Silent device: the open fails and the port is closed again
+        assert_eq!(
+            service.open(config).unwrap_err(),
+            ServiceError::DeviceNotResponding { timeout_ms: 100 }
+        );
+        assert!(!service.is_open());
+    }
+
+    #[test]
+    fn test_open_does_not_retry_not_found() {
+        let (opener, calls) = flaky_opener(1, || PortError::not_found("MOCK0"));
+        let service = create_test_service().with_port_opener(opener);
+        let mut config = create_open_config("MOCK0");
+        config.open_retries = Some(3);
+        config.open_retry_delay_ms = Some(1);
+
+        assert!(matches!(
+            service.open(config),
+            Err(ServiceError::DeviceNotFound(_))
+        ));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_open_without_retries_fails_on_transient_error() {
+        let (opener, calls) = flaky_opener(1, || PortError::busy("MOCK0", None));
+        let service = create_test_service().with_port_opener(opener);
+
+        assert!(service.open(create_open_config("MOCK0")).is_err());
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_open_inherits_default_idle_disconnect() {
+        let open_with = |requested: Option<u64>| {
+            let (opener, _) = flaky_opener(0, || PortError::busy("MOCK0", None));
+            let service = create_test_service()
+                .with_port_opener(opener)
+                .with_default_idle_disconnect_ms(Some(30_000));
+            let mut config = create_open_config("MOCK0");
+            config.idle_disconnect_ms = requested;
+            service.open(config).unwrap();
+            match service.status().unwrap() {
+                StatusResult::Open { config, .. } => config.idle_disconnect_ms,
+                StatusResult::Closed => panic!("port should be open"),
+            }
+        };
+
+        assert_eq!(open_with(None), Some(30_000));
+        assert_eq!(open_with(Some(500)), Some(500));
+        // Explicit 0 (what a null maps to at the tool boundary) disables it
+        assert_eq!(open_with(Some(0)), None);
+    }
+
+    #[test]
+    fn test_max_open_duration_closes_busy_port() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                max_open_duration_ms: Some(50),
+                ..mock_port_config()
+            },
+        );
+
+        // Steady traffic keeps the port from ever being idle
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        let closed = loop {
+            assert!(std::time::Instant::now() < deadline, "port never closed");
+            match service.write("PING") {
+                Ok(_) => {}
+                Err(ServiceError::PortNotOpen) => break None,
+                Err(e) => panic!("unexpected write error: {e}"),
+            }
+            mock.clone().enqueue_read(b"PONG\n");
+            let result = service.read().unwrap();
+            if result.auto_closed.is_some() {
+                break result.auto_closed;
+            }
+            std::thread::sleep(Duration::from_millis(5));
+        };
+
+        if let Some(info) = closed {
+            assert_eq!(info.reason, CloseReason::MaxDuration);
+        }
+        assert!(matches!(service.status().unwrap(), StatusResult::Closed));
+        assert_eq!(
+            service.last_close().unwrap().reason,
+            CloseReason::MaxDuration
+        );
+
+        let service = service.with_default_max_open_duration_ms(Some(60_000));
+        assert_eq!(service.effective_max_open_duration_ms(None), Some(60_000));
+        assert_eq!(service.effective_max_open_duration_ms(Some(10)), Some(10));
+        assert_eq!(service.effective_max_open_duration_ms(Some(0)), None);
+    }
+
+    #[test]
+    fn test_capture_until_silence_closes_expired_port() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                max_open_duration_ms: Some(20),
+                ..mock_port_config()
+            },
+        );
+        std::thread::sleep(Duration::from_millis(40));
+
+        mock.enqueue_read(b"BOOT\n");
+        let result = service.capture_until_silence(50, 1024, 500).unwrap();
+        assert_eq!(result.bytes_read, 0);
+        assert_eq!(
+            result.auto_closed.map(|info| info.reason),
+            Some(CloseReason::MaxDuration)
+        );
+        assert!(matches!(service.status().unwrap(), StatusResult::Closed));
+    }
+
+    #[test]
+    fn test_unusual_baud_warns_but_opens() {
+        assert!(validate_baud(115200).is_none());
+        // ESP8266 boot rate: not in the list, but an exact 48 MHz divider
+        assert!(validate_baud(74880).is_none());
+
+        let (opener, calls) = flaky_opener(0, || PortError::busy("MOCK0", None));
+        let service = create_test_service().with_port_opener(opener);
+        let mut config = create_open_config("MOCK0");
+        config.baud_rate = 1_234_567;
+
+        let result = service.open(config.clone()).unwrap();
+        assert_eq!(result.warnings.len(), 1);
+        assert!(result.warnings[0].contains("1234567"));
+        service.close().unwrap();
+
+        let strict = service.with_strict_baud(true);
+        assert!(matches!(
+            strict.open(config),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_permission_denied_open_is_distinct() {
+        let (opener, calls) = flaky_opener(usize::MAX, || {
+            PortError::PermissionDenied("/dev/ttyUSB0".into())
+        });
+        let service = create_test_service().with_port_opener(opener);
+
+        let err = service
+            .open(create_open_config("/dev/ttyUSB0"))
+            .unwrap_err();
+        assert!(matches!(&err, ServiceError::PermissionDenied(p) if p == "/dev/ttyUSB0"));
+        assert!(err.to_string().contains("dialout"));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(matches!(service.status().unwrap(), StatusResult::Closed));
+    }
+
+    #[test]
+    fn test_reconnect_restores_settings_after_idle_close() {
+        let mock = MockSerialPort::new("MOCK0");
+        let port = mock.clone();
+        let opener: PortOpener =
+            Arc::new(move |_name, _config| Ok(Box::new(port.clone()) as PortAdapter));
+        let service = create_test_service().with_port_opener(opener);
+        let mut config = create_open_config("MOCK0");
+        config.baud_rate = 57600;
+        config.terminator = Some("\r\n".to_string());
+        service.open(config).unwrap();
+
+        let window = Duration::from_millis(20);
+        drop(service.begin_control_call());
+        std::thread::sleep(window * 2);
+        assert!(service.close_if_inactive(window));
+        assert!(!service.is_open());
+        assert_eq!(
+            service.last_close().unwrap().reason,
+            CloseReason::Inactivity
+        );
+
+        let result = service.reconnect().unwrap();
+        assert_eq!(result.message, "reconnected");
+        assert_eq!(result.config.port_name, "MOCK0");
+        assert_eq!(result.config.baud_rate, 57600);
+        assert_eq!(result.config.terminator.as_deref(), Some("\r\n"));
+        assert!(matches!(
+            service.reconnect(),
+            Err(ServiceError::PortAlreadyOpen)
+        ));
+
+        // An explicit close forgets the settings
+        service.close().unwrap();
+        assert!(matches!(
+            service.reconnect(),
+            Err(ServiceError::NoPortSpecified)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_max_duration_watchdog_runs_only_while_a_limit_applies() {
+        use std::sync::atomic::Ordering;
+
+        let service = create_test_service().with_port_opener(Arc::new(|name, _config| {
+            Ok(Box::new(MockSerialPort::new(name)) as PortAdapter)
+        }));
+        let open = |max_open_duration_ms| {
+            let mut config: OpenConfig =
+                serde_json::from_value(serde_json::json!({"port_name": "MOCK0"})).unwrap();
+            config.max_open_duration_ms = max_open_duration_ms;
+            service.open(config).unwrap();
+        };
+
+        open(None);
+        assert!(!service.max_duration_watchdog.load(Ordering::SeqCst));
+        service.close().unwrap();
+
+        open(Some(50));
+        assert!(service.max_duration_watchdog.load(Ordering::SeqCst));
+        // A busy port is skipped rather than waited for
+        {
+            let _busy = lock_state(&service.state);
+            assert!(service.watch_max_duration());
+        }
+        tokio::time::sleep(Duration::from_millis(400)).await;
+        assert!(matches!(service.status().unwrap(), StatusResult::Closed));
+        assert_eq!(
+            service.last_close().unwrap().reason,
+            CloseReason::MaxDuration
+        );
+        assert!(!service.max_duration_watchdog.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_inactivity_watchdog_closes_idle_port() {
+        let service = create_open_mock_service(1024, MockSerialPort::new("MOCK0"));
+        let window = Duration::from_millis(50);
+
+        // A running call holds the port open past the window
+        let call = service.begin_control_call();
+        std::thread::sleep(window * 2);
+        assert!(!service.close_if_inactive(window));
+        drop(call);
+        assert!(!service.close_if_inactive(window));
+
+        let watchdog = service.spawn_inactivity_watchdog(window);
+        tokio::time::sleep(window * 4).await;
+        watchdog.abort();
+        assert!(matches!(service.status().unwrap(), StatusResult::Closed));
+    }
+
+    #[tokio::test]
+    async fn test_metrics_history_is_bounded() {
+        let service = create_test_service().with_metrics_history_len(3);
+        assert!(!service.record_metrics_snapshot());
+
+        let service = create_open_mock_service(1024, MockSerialPort::new("MOCK0"))
+            .with_metrics_history_len(3);
+        let sampler = service.spawn_metrics_history(Duration::from_millis(10));
+        tokio::time::sleep(Duration::from_millis(150)).await;
+        sampler.abort();
+
+        let history = service.metrics_history();
+        assert_eq!(history.len(), 3);
+        assert!(history.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        assert_eq!(history[0].bytes_read_total, 0);
+    }
+
+    #[test]
+    fn test_recent_errors_keep_failures_up_to_cap() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone()).with_recent_errors_len(2);
+        // A quiet port timing out is routine, not a failure
+        for _ in 0..3 {
+            mock.set_should_timeout(true);
+            assert!(matches!(service.read(), Err(ServiceError::Timeout(_))));
+        }
+        assert!(service.recent_errors().is_empty());
+
+        mock.set_write_capacity(Some(0));
+        for _ in 0..3 {
+            assert!(service.write("PING").is_err());
+        }
+        // Caller mistakes are not failures
+        assert!(service.read_bytes_n(0).is_err());
+
+        let errors = service.recent_errors();
+        assert_eq!(errors.len(), 2);
+        assert!(errors.windows(2).all(|w| w[0].timestamp <= w[1].timestamp));
+        for event in &errors {
+            assert_eq!(event.operation, "write");
+        }
+
+        service.close().unwrap();
+        assert!(service.read().is_err());
+        assert_eq!(service.recent_errors().len(), 2);
+    }
+
+    #[test]
+    fn test_metrics_timestamps_match_elapsed() {
+        let service = create_open_mock_service(1024, MockSerialPort::new("MOCK0"));
+        std::thread::sleep(Duration::from_millis(50));
+        service.write("PING").unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+
+        let before = chrono::Utc::now();
+        let metrics = service.metrics().unwrap();
+        let started = metrics.open_started_at.unwrap();
+        let activity = metrics.last_activity_at.unwrap();
+        let open_ms = metrics.open_duration_ms.unwrap() as i64;
+        let idle_ms = metrics.last_activity_ms.unwrap() as i64;
+
+        assert!(started <= activity && activity <= before);
+        let gap_ms = (activity - started).num_milliseconds();
+        assert!((gap_ms - (open_ms - idle_ms)).abs() <= 5, "gap {gap_ms}ms");
+        let age_ms = (before - started).num_milliseconds();
+        assert!(
+            (age_ms - open_ms).abs() <= 20,
+            "age {age_ms}ms vs {open_ms}ms"
+        );
+
+        let StatusResult::Open {
+            metrics: Some(status),
+            ..
+        } = service.status().unwrap()
+        else {
+            panic!("port should be open");
+        };
+        assert_eq!(status.open_started_at, started);
+    }
+
+    #[tokio::test]
+    async fn test_read_stream_delivers_incoming_data() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let stream = service.spawn_read_stream(
+            Duration::from_millis(10),
+            ReadEncoding::Utf8,
+            move |result| tx.send(result).is_ok(),
+        );
+
+        mock.clone().enqueue_read(b"ALARM 3");
+        let result = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+            .await
+            .expect("no read delivered")
+            .unwrap();
+        assert_eq!(result.data, "ALARM 3");
+        assert_eq!(result.bytes_read, 7);
+
+        // Dropping the receiver ends the stream on its next delivery
+        drop(rx);
+        mock.clone().enqueue_read(b"more");
+        tokio::time::timeout(Duration::from_secs(2), stream)
+            .await
+            .expect("stream did not stop")
+            .unwrap();
+    }
+
+    #[test]
+    fn test_auto_open_from_config() {
+        let config: Config = toml::from_str(
+            "[serial.port_aliases]\nbench = \"MOCK7\"\n\n\
+             [serial.auto_open]\nport_name = \"bench\"\nbaud_rate = 115200\nparity = \"even\"\n",
+        )
+        .unwrap();
+        let (opener, calls) = flaky_opener(0, || PortError::busy("MOCK7", None));
+        let service = create_test_service().with_port_opener(opener);
+
+        service.auto_open(&config.serial).unwrap().unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        match service.status().unwrap() {
+            StatusResult::Open { config, .. } => {
+                assert_eq!(config.port_name, "MOCK7");
+                assert_eq!(config.baud_rate, 115200);
+                assert!(matches!(config.parity, ParityCfg::Even));
+                assert!(matches!(config.data_bits, DataBitsCfg::Eight));
+            }
+            StatusResult::Closed => panic!("port should be open"),
+        }
+        // A later open sees the port already taken
+        assert!(matches!(
+            service.open(create_open_config("MOCK0")),
+            Err(ServiceError::PortAlreadyOpen)
+        ));
+
+        assert!(create_test_service()
+            .auto_open(&Config::default().serial)
+            .is_none());
+    }
+
+    #[test]
+    fn test_loopback_passes_on_echo_device() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_echo(true);
+        let service = create_open_mock_service(1024, mock);
+
+        let result = service.loopback_test(32, 200).unwrap();
+        assert!(result.passed);
+        assert_eq!(result.sent.len(), 32);
+        assert_eq!(result.received, result.sent);
+    }
+
+    #[test]
+    fn test_benchmark_stats_cover_every_round_trip() {
+        let latency = Duration::from_millis(5);
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_auto_response(Some((latency, b"OK\r\n".as_slice())));
+        let service = create_open_mock_service(1024, mock.clone());
+
+        let result = service.benchmark(8, "PING\r\n", Some("\r\n"), 500).unwrap();
+        assert_eq!(result.iterations, 8);
+        assert_eq!(result.completed, 8);
+        assert_eq!(result.timeouts, 0);
+        assert_eq!(mock.get_write_log().len(), 8);
+        assert_eq!(result.bytes_written, 8 * 6);
+        assert_eq!(result.bytes_read, 8 * 4);
+        let stats = result.latency.unwrap();
+        assert!(stats.min_ms >= latency.as_secs_f64() * 1000.0);
+        assert!(stats.min_ms <= stats.p50_ms && stats.p50_ms <= stats.p90_ms);
+        assert!(stats.p90_ms <= stats.p99_ms && stats.p99_ms <= stats.max_ms);
+        assert!(result.transactions_per_sec > 0.0);
+
+        // Silent device: every round trip times out and no latency is reported
+        mock.set_auto_response(None);
+        let result = service.benchmark(3, "PING\r\n", Some("\r\n"), 20).unwrap();
+        assert_eq!((result.completed, result.timeouts), (0, 3));
+        assert!(result.latency.is_none());
+        assert!(matches!(
+            service.benchmark(0, "PING", None, 20),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_latency_percentiles_use_nearest_rank() {
+        let samples: Vec<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+        let stats = LatencyStats::from_samples(&samples).unwrap();
+        assert_eq!(stats.min_ms, 1.0);
+        assert_eq!(stats.p50_ms, 50.0);
+        assert_eq!(stats.p90_ms, 90.0);
+        assert_eq!(stats.p99_ms, 99.0);
+        assert_eq!(stats.max_ms, 100.0);
+        assert_eq!(stats.mean_ms, 50.5);
+        assert!(LatencyStats::from_samples(&[]).is_none());
+    }
+
+    #[test]
+    fn test_loopback_fails_on_silent_device() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        // Stale data must be cleared, not mistaken for the echo
+        mock.enqueue_read(b"stale");
+        let service = create_open_mock_service(1024, mock);
+
+        let result = service
+            .loopback_test(DEFAULT_LOOPBACK_TOKEN_LEN, 50)
+            .unwrap();
+        assert!(!result.passed);
+        assert!(result.received.is_empty());
+        assert!(result.latency_ms >= 50);
+        assert!(matches!(
+            service.loopback_test(0, 50),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_repeated_read_timeouts_warn_once_per_interval() {
+        use crate::port::SerialPortAdapter;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tracing_subscriber::layer::SubscriberExt;
+
+        struct WarnCounter(Arc<AtomicUsize>);
+        impl<S: tracing::Subscriber> tracing_subscriber::Layer<S> for WarnCounter {
+            fn on_event(
+                &self,
+                event: &tracing::Event<'_>,
+                _ctx: tracing_subscriber::layer::Context<'_, S>,
+            ) {
+                if *event.metadata().level() == tracing::Level::WARN {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        // A silent device: every read times out after 1 ms
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_timeout(Duration::from_millis(1)).unwrap();
+        mock.enqueue_read_after(Duration::from_secs(3600), b"never");
+        let service = create_open_mock_service(1024, mock)
+            .with_timeout_warn_interval(Duration::from_secs(3600));
+
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let subscriber = tracing_subscriber::registry().with(WarnCounter(warnings.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            for _ in 0..20 {
+                assert_eq!(service.read().unwrap().bytes_read, 0);
+            }
+        });
+
+        assert_eq!(warnings.load(Ordering::SeqCst), 1);
+        let st = lock_state(&service.state);
+        match &*st {
+            PortState::Open { timeout_log, .. } => assert_eq!(timeout_log.suppressed(), 19),
+            PortState::Closed => panic!("port should stay open"),
+        }
+    }
+
+    #[test]
+    fn test_open_id_stable_across_reads_and_new_after_reopen() {
+        let (opener, _) = flaky_opener(0, || PortError::busy("MOCK0", None));
+        let service = create_test_service().with_port_opener(opener);
+
+        let opened = service.open(create_open_config("MOCK0")).unwrap();
+        // Mock reads with no data queued report WouldBlock, so exercise writes
+        // plus status for the same lifecycle
+        let first = service.write("a").unwrap();
+        let second = service.write("b").unwrap();
+        assert_eq!(first.open_id, opened.open_id);
+        assert_eq!(second.open_id, opened.open_id);
+        match service.status().unwrap() {
+            StatusResult::Open { open_id, .. } => assert_eq!(open_id, opened.open_id),
+            StatusResult::Closed => panic!("port should be open"),
+        }
+
+        service.close().unwrap();
+        let reopened = service.open(create_open_config("MOCK0")).unwrap();
+        assert_ne!(reopened.open_id, opened.open_id);
+        assert_eq!(service.write("c").unwrap().open_id, reopened.open_id);
+    }
+
+    #[test]
+    fn test_read_results_share_open_id() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"one");
+        let service = create_open_mock_service(3, mock.clone());
+
+        let first = service.read_bytes_n(3).unwrap();
+        mock.enqueue_read(b"two");
+        let second = service.read_bytes_n(3).unwrap();
+        assert_eq!(first.data, "one");
+        assert_eq!(first.open_id, second.open_id);
+    }
+
+    #[test]
+    fn test_cancel_read_interrupts_wait_for_pattern() {
+        // The mock never delivers data, so only cancellation ends the wait early
+        let service = create_open_mock_service(1024, MockSerialPort::new("MOCK0"));
+        let waiter = service.clone();
+        let started = std::time::Instant::now();
+        let handle = std::thread::spawn(move || waiter.wait_for_pattern("READY", 10_000));
+
+        std::thread::sleep(Duration::from_millis(100));
+        service.cancel_read();
+
+        let result = handle.join().unwrap();
+        assert!(matches!(result, Err(ServiceError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // A stale cancel does not affect the next read
+        service.cancel_read();
+        let result = service.wait_for_pattern("READY", 20).unwrap();
+        assert!(result.timed_out);
+    }
+
+    #[test]
+    fn test_base64_direct_encode_matches_string_path() {
+        use base64::Engine;
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let all_bytes: Vec<u8> = (0..=255).collect();
+        let patterns: Vec<(Vec<u8>, Option<&str>)> = vec![
+            (b"hello world".to_vec(), None),
+            (b"OK\r\n".to_vec(), Some("\r\n")),
+            (b"line\n\n\n".to_vec(), Some("\n")),
+            ("temp=21.5\u{00b0}C\n".as_bytes().to_vec(), Some("\n")),
+            (vec![0u8; 4096], None),
+            (all_bytes.clone(), None),
+            (Vec::new(), Some("\n")),
+        ];
+
+        for (bytes, term) in &patterns {
+            let direct = encode_read_data(bytes, *term, ReadEncoding::Base64);
+            let via_string = encode_read_data(bytes, *term, ReadEncoding::Utf8);
+            if std::str::from_utf8(bytes).is_ok() {
+                assert_eq!(direct, b64.encode(via_string.as_bytes()), "{:?}", bytes);
+            }
+            let mut expected: &[u8] = bytes;
+            if let Some(t) = term {
+                while let Some(rest) = expected.strip_suffix(t.as_bytes()) {
+                    expected = rest;
+                }
+            }
+            assert_eq!(b64.decode(&direct).unwrap(), expected);
+        }
+
+        // Non-UTF-8 input is preserved exactly, unlike the lossy string path
+        let direct = encode_read_data(&all_bytes, None, ReadEncoding::Base64);
+        assert_eq!(b64.decode(direct).unwrap(), all_bytes);
+    }
+
+    #[test]
+    fn test_read_base64_encoding() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(&[0xFF, 0x00, 0x80, b'\n']);
+        let mut config = mock_port_config();
+        config.terminator = Some("\n".to_string());
+        let service = create_open_mock_service_with_config(1024, mock, config);
+
+        let result = service.read_encoded(64, ReadEncoding::Base64).unwrap();
+        assert_eq!(result.data, "/wCA");
+        assert_eq!(result.bytes_read, 4);
+    }
+
+    #[test]
+    fn test_read_line_stops_at_delimiter_and_keeps_rest() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"first\nsec");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        let result = service.read_line(1000, Some(200)).unwrap();
+        assert_eq!(result.termination, Some(ReadTermination::Delimiter));
+        assert_eq!(result.data, "first");
+        assert_eq!(result.bytes_read, 9);
+
+        // The bytes after the delimiter start the next line
+        mock.enqueue_read(b"ond\n");
+        let next = service.read_line(1000, Some(200)).unwrap();
+        assert_eq!(next.termination, Some(ReadTermination::Delimiter));
+        assert_eq!(next.data, "second");
+        assert_eq!(next.bytes_read, 4);
+    }
+
+    #[test]
+    fn test_read_line_joins_crlf_split_across_reads() {
+        for terminator in [None, Some("\r"), Some("\r\n")] {
+            let mut mock = MockSerialPort::new("MOCK0");
+            mock.enqueue_read(b"hello\r");
+            mock.enqueue_read_after(Duration::from_millis(20), b"\nworld\r\n");
+            let mut config = mock_port_config();
+            config.terminator = terminator.map(String::from);
+            let service = create_open_mock_service_with_config(1024, mock, config);
+
+            let first = service.read_line(1000, Some(500)).unwrap();
+            assert_eq!(first.termination, Some(ReadTermination::Delimiter));
+            assert_eq!(first.data, "hello", "terminator {terminator:?}");
+            let second = service.read_line(1000, Some(500)).unwrap();
+            assert_eq!(second.data, "world", "terminator {terminator:?}");
+        }
+    }
+
+    #[test]
+    fn test_read_line_accepts_lone_cr_once_quiet() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"OK\rnext\n");
+        let service = create_open_mock_service(1024, mock.clone());
+        assert_eq!(service.read_line(1000, None).unwrap().data, "OK");
+        assert_eq!(service.read_line(1000, None).unwrap().data, "next");
+
+        // Nothing follows the `\r`, so it ends the line on its own
+        mock.enqueue_read(b"prompt\r");
+        let result = service.read_line(1000, None).unwrap();
+        assert_eq!(result.termination, Some(ReadTermination::Delimiter));
+        assert_eq!(result.data, "prompt");
+    }
+
+    #[test]
+    fn test_find_line_end() {
+        assert_eq!(find_line_end(b"ab\r\ncd", false), Some((2, 4)));
+        assert_eq!(find_line_end(b"ab\ncd", false), Some((2, 3)));
+        assert_eq!(find_line_end(b"ab\rcd", false), Some((2, 3)));
+        assert_eq!(find_line_end(b"ab\r", false), None);
+        assert_eq!(find_line_end(b"ab\r", true), Some((2, 3)));
+        assert_eq!(find_line_end(b"abc", true), None);
+        assert!(is_line_terminator("\r\n"));
+        assert!(!is_line_terminator(">"));
+    }
+
+    #[test]
+    fn test_read_line_idle_timeout_on_silence() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"partial");
+        let service = create_open_mock_service(1024, mock);
+
+        let started = std::time::Instant::now();
+        let result = service.read_line(2000, Some(50)).unwrap();
+        assert_eq!(result.termination, Some(ReadTermination::IdleTimeout));
+        assert_eq!(result.data, "partial");
+        assert!(started.elapsed() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_read_line_overall_timeout_on_chatty_device() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        // A byte every 20ms never leaves a 100ms gap, but never ends the line
+        for i in 1..=20 {
+            mock.enqueue_read_after(Duration::from_millis(i * 20), b"x");
+        }
+        let service = create_open_mock_service(1024, mock);
+
+        let result = service.read_line(150, Some(100)).unwrap();
+        assert_eq!(result.termination, Some(ReadTermination::OverallTimeout));
+        assert!(result.bytes_read > 0);
+        assert!(result.data.chars().all(|c| c == 'x'));
+    }
+
+    #[test]
+    fn test_read_line_stops_at_max_read_bytes() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"0123456789");
+        let service = create_open_mock_service(4, mock);
+
+        let result = service.read_line(1000, None).unwrap();
+        assert_eq!(result.termination, Some(ReadTermination::MaxBytes));
+        assert_eq!(result.data, "0123");
+    }
+
+    #[test]
+    fn test_read_hexdump_encoding() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(&[0xE2, 0x82]);
+        let service = create_open_mock_service(1024, mock);
+
+        // Partial UTF-8 is not held back: hexdump shows exactly what arrived
+        let result = service.read_encoded(64, ReadEncoding::Hexdump).unwrap();
+        assert_eq!(result.data, crate::port::hexdump(&[0xE2, 0x82]));
+        assert_eq!(result.bytes_read, 2);
+    }
+
+    #[test]
+    fn test_set_signals_hold_and_restore_sequence() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        service
+            .set_signals(SignalsRequest {
+                dtr: Some(true),
+                rts: Some(true),
+                ..Default::default()
+            })
+            .unwrap();
+        let result = service
+            .set_signals(SignalsRequest {
+                dtr: Some(false),
+                rts: Some(false),
+                hold_ms: Some(5),
+                restore: true,
+            })
+            .unwrap();
+
+        assert_eq!(result.dtr, Some(true));
+        assert_eq!(result.rts, Some(true));
+        assert!(result.restored);
+        assert_eq!(
+            mock.get_signal_log(),
+            vec![
+                (ControlSignal::Dtr, true),
+                (ControlSignal::Rts, true),
+                (ControlSignal::Dtr, false),
+                (ControlSignal::Rts, false),
+                (ControlSignal::Rts, true),
+                (ControlSignal::Dtr, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_signals_rolls_back_on_failure() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        service
+            .set_signals(SignalsRequest {
+                dtr: Some(false),
+                rts: Some(false),
+                ..Default::default()
+            })
+            .unwrap();
+        mock.fail_next_signal_write(ControlSignal::Rts);
+
+        let result = service.set_signals(SignalsRequest {
+            dtr: Some(true),
+            rts: Some(true),
+            ..Default::default()
+        });
+
+        assert!(matches!(result, Err(ServiceError::PortError(_))));
+        // DTR was raised, RTS failed; both go back to their previous levels
+        assert_eq!(
+            mock.get_signal_log()[2..],
+            [
+                (ControlSignal::Dtr, true),
+                (ControlSignal::Rts, false),
+                (ControlSignal::Dtr, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_set_signals_restores_only_known_levels() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        service
+            .set_signals(SignalsRequest {
+                dtr: Some(true),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // RTS was never set, so its previous level is unknown and it stays up
+        let result = service
+            .set_signals(SignalsRequest {
+                dtr: Some(false),
+                rts: Some(true),
+                hold_ms: None,
+                restore: true,
+            })
+            .unwrap();
+        assert_eq!((result.dtr, result.rts), (Some(true), Some(true)));
+
+        let result = service.set_signals(SignalsRequest {
+            dtr: Some(true),
+            hold_ms: Some(MAX_SIGNAL_HOLD_MS + 1),
+            ..Default::default()
+        });
+        assert!(matches!(result, Err(ServiceError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_reset_device_arduino_toggles_dtr() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        let result = service.reset_device(ResetBoard::Arduino, None).unwrap();
+        assert_eq!(result.steps, ResetBoard::Arduino.steps());
+        assert_eq!((result.dtr, result.rts), (Some(false), Some(false)));
+        let dtr: Vec<bool> = mock
+            .get_signal_log()
+            .into_iter()
+            .filter(|(signal, _)| *signal == ControlSignal::Dtr)
+            .map(|(_, level)| level)
+            .collect();
+        assert_eq!(dtr, vec![false, true, false]);
+
+        let err = service
+            .reset_device(ResetBoard::Custom, Some(Vec::new()))
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::InvalidConfig(_)));
+    }
+
+    #[test]
+    fn test_reset_device_caps_custom_steps_and_releases_lines_on_error() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        let step = |hold_ms| SignalStep {
+            dtr: Some(true),
+            rts: Some(true),
+            hold_ms,
+        };
+        for steps in [
+            vec![step(0); MAX_RESET_STEPS + 1],
+            vec![step(MAX_SIGNAL_HOLD_MS / 2 + 1); 2],
+        ] {
+            let err = service
+                .reset_device(ResetBoard::Custom, Some(steps))
+                .unwrap_err();
+            assert!(matches!(err, ServiceError::InvalidConfig(_)));
+        }
+        assert!(mock.get_signal_log().is_empty());
+
+        // DTR is asserted by the first step, then the second fails on RTS
+        let mut sequence = vec![step(0), step(0)];
+        sequence[0].rts = None;
+        mock.fail_next_signal_write(ControlSignal::Rts);
+        let err = service
+            .reset_device(ResetBoard::Custom, Some(sequence))
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::PortError(_)));
+        let last: Vec<_> = mock.get_signal_log().into_iter().rev().take(2).collect();
+        assert_eq!(
+            last,
+            vec![(ControlSignal::Dtr, false), (ControlSignal::Rts, false)]
+        );
+    }
+
+    #[test]
+    fn test_diagnose_reports_each_step() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"OK\r\n");
+        let port = mock.clone();
+        let opener: PortOpener =
+            Arc::new(move |_name, _config| Ok(Box::new(port.clone()) as PortAdapter));
+        let service = create_test_service().with_port_opener(opener);
+
+        let report = service.diagnose("MOCK0", 115200, 100, Ok(vec!["/dev/ttyUSB1".into()]));
+        let statuses: Vec<_> = report
+            .steps
+            .iter()
+            .map(|s| (s.name.as_str(), s.status))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("port_present", StepStatus::Fail),
+                ("open", StepStatus::Pass),
+                ("modem_lines", StepStatus::Fail),
+                ("signature_probe", StepStatus::Pass),
+            ]
+        );
+        assert!(report.steps[0]
+            .suggestion
+            .as_deref()
+            .unwrap()
+            .contains("/dev/ttyUSB1"));
+        assert_eq!(report.steps[3].detail, "answered as AT command modem");
+        assert!(!report.passed());
+        assert_eq!(mock.get_write_log(), vec![b"AT\r\n".to_vec()]);
+        assert!(!service.is_open());
+
+        // A port that is already open is left alone
+        service.open(create_open_config("MOCK0")).unwrap();
+        let report = service.diagnose("MOCK0", 9600, 100, Err("no udev".into()));
+        assert_eq!(report.steps[0].status, StepStatus::Skipped);
+        assert_eq!(report.steps[1].status, StepStatus::Fail);
+        assert_eq!(report.steps[3].status, StepStatus::Skipped);
+        assert!(service.is_open());
+    }
+
+    #[test]
+    fn test_diagnose_reads_modem_lines() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_modem_status(ModemStatus {
+            cts: true,
+            dsr: false,
+            cd: true,
+        });
+        let port = mock.clone();
+        let opener: PortOpener =
+            Arc::new(move |_name, _config| Ok(Box::new(port.clone()) as PortAdapter));
+        let service = create_test_service().with_port_opener(opener);
+
+        let report = service.diagnose("MOCK0", 115200, 10, Ok(vec!["MOCK0".into()]));
+        let step = &report.steps[2];
+        assert_eq!(step.name, "modem_lines");
+        assert_eq!(step.status, StepStatus::Pass);
+        assert!(step.detail.starts_with("CTS=true, DSR=false, CD=true"));
+    }
+
+    #[test]
+    fn test_set_signals_requires_a_signal() {
+        let service = create_open_mock_service(1024, MockSerialPort::new("MOCK0"));
+        let result = service.set_signals(SignalsRequest::default());
+        assert!(matches!(result, Err(ServiceError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_capabilities_come_from_the_open_port() {
+        let service = create_test_service();
+        let closed = service.capabilities();
+        assert_eq!(closed.port_name, None);
+        assert_eq!(closed.capabilities, PortCapabilities::platform());
+
+        let mut mock = MockSerialPort::new("MOCK0");
+        let reported = PortCapabilities {
+            supports_break: true,
+            supports_modem_status: true,
+            baud_rate_range: Some(crate::port::BaudRateRange {
+                min: 300,
+                max: 115_200,
+            }),
+            ..Default::default()
+        };
+        mock.set_capabilities(reported.clone());
+        let service = create_open_mock_service(1024, mock);
+
+        let open = service.capabilities();
+        assert_eq!(open.port_name.as_deref(), Some("MOCK0"));
+        assert_eq!(open.capabilities, reported);
+        let json = serde_json::to_value(&open).unwrap();
+        assert_eq!(json["supports_flow_control_hardware"], false);
+        assert_eq!(json["baud_rate_range"]["max"], 115_200);
+    }
+
+    #[test]
+    fn test_utf8_char_split_across_reads() {
+        // U+20AC EURO SIGN is E2 82 AC
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(&[b'A', 0xE2, 0x82]);
+        let service = create_open_mock_service(1024, mock.clone());
+
+        let first = service.read().unwrap();
+        assert_eq!(first.data, "A");
+        assert_eq!(first.bytes_read, 3);
+        match service.status().unwrap() {
+            StatusResult::Open {
+                pending_frame_bytes,
+                ..
+            } => assert_eq!(pending_frame_bytes, 2),
+            StatusResult::Closed => panic!("port should be open"),
+        }
+
+        mock.enqueue_read(&[0xAC, b'B']);
+        let second = service.read().unwrap();
+        assert_eq!(second.data, "\u{20AC}B");
+        assert!(!second.data.contains('\u{FFFD}'));
+    }
+
+    #[test]
+    fn test_incomplete_utf8_tail() {
+        assert_eq!(incomplete_utf8_tail(b"abc"), 0);
+        assert_eq!(incomplete_utf8_tail(&[b'a', 0xE2]), 1);
+        assert_eq!(incomplete_utf8_tail(&[0xE2, 0x82]), 2);
+        assert_eq!(incomplete_utf8_tail(&[0xE2, 0x82, 0xAC]), 0);
+        assert_eq!(incomplete_utf8_tail(&[0xF0, 0x9F, 0x98]), 3);
+        assert_eq!(incomplete_utf8_tail(&[0xF0, 0x9F, 0x98, 0x80]), 0);
+        // Invalid bytes are left to lossy decoding
+        assert_eq!(incomplete_utf8_tail(&[b'a', 0xFF]), 0);
+        assert_eq!(incomplete_utf8_tail(&[0x80, 0x80, 0x80]), 0);
+    }
+
+    #[test]
+    fn test_poisoned_lock_recovers_to_closed() {
+        let service = create_open_mock_service(1024, MockSerialPort::new("MOCK0"));
+        let state = service.state.clone();
+        let _ = std::thread::spawn(move || {
+            let _guard = state.lock().unwrap();
+            panic!("poison the state lock");
+        })
+        .join();
+        assert!(service.state.is_poisoned());
+
+        assert!(matches!(service.status(), Ok(StatusResult::Closed)));
+        assert!(!service.state.is_poisoned());
+        assert!(matches!(service.read(), Err(ServiceError::PortNotOpen)));
+    }
+
+    #[test]
+    fn test_poll_read_strategy_rejects_zero_interval() {
+        let (opener, calls) = flaky_opener(0, || PortError::busy("MOCK0", None));
+        let service = create_test_service().with_port_opener(opener);
+        let mut config: OpenConfig =
+            serde_json::from_value(serde_json::json!({"port_name": "MOCK0"})).unwrap();
+        config.read_strategy = ReadStrategy::Poll { interval_ms: 0 };
+
+        assert!(matches!(
+            service.open(config.clone()),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        config.read_strategy = ReadStrategy::Poll { interval_ms: 1 };
+        service.open(config).unwrap();
+    }
+
+    #[test]
+    fn test_poll_read_strategy_returns_once_bytes_are_pending() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                timeout_ms: 50,
+                read_strategy: ReadStrategy::Poll { interval_ms: 1 },
+                ..mock_port_config()
+            },
+        );
+        let feed_after = |delay_ms, data: &'static [u8]| {
+            let mut feeder = mock.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(delay_ms));
+                feeder.enqueue_read(data);
+            })
+        };
+
+        // Nothing pending: the poll gives up after the port timeout
+        let started = std::time::Instant::now();
+        assert_eq!(service.read().unwrap().data, "");
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        // Bytes showing up mid-poll are read straight away
+        let feeder = feed_after(20, b"hello");
+        let started = std::time::Instant::now();
+        let result = service
+            .read_with_long_poll(
+                1024,
+                ReadEncoding::Utf8,
+                ReadTransform::default(),
+                false,
+                Some(Duration::from_secs(5)),
+            )
+            .unwrap();
+        assert_eq!(result.data, "hello");
+        assert!(started.elapsed() < Duration::from_secs(2));
+        feeder.join().unwrap();
+        assert_eq!(mock.available_bytes(), 0);
+
+        let feeder = feed_after(20, b"line\n");
+        assert_eq!(service.read_line(5_000, None).unwrap().data, "line");
+        feeder.join().unwrap();
+    }
+
+    #[test]
+    fn test_long_poll_returns_when_data_arrives() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_timeout(Duration::from_millis(20)).unwrap();
+        mock.enqueue_read_after(Duration::from_millis(150), b"hello");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                timeout_ms: 20,
+                ..mock_port_config()
+            },
+        );
+        let long_poll = |window_ms| {
+            service.read_with_long_poll(
+                1024,
+                ReadEncoding::Utf8,
+                ReadTransform::default(),
+                false,
+                Some(Duration::from_millis(window_ms)),
+            )
+        };
+
+        // A plain read gives up after the port timeout
+        assert_eq!(service.read().unwrap().data, "");
+
+        // Data arriving mid-window ends the poll without waiting it out
+        let started = std::time::Instant::now();
+        assert_eq!(long_poll(5_000).unwrap().data, "hello");
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        // Nothing arrives: empty once the window passes
+        mock.clone()
+            .enqueue_read_after(Duration::from_secs(3600), b"never");
+        let started = std::time::Instant::now();
+        assert_eq!(long_poll(50).unwrap().data, "");
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        assert!(matches!(long_poll(0), Err(ServiceError::InvalidConfig(_))));
+        assert!(matches!(
+            long_poll(MAX_LONG_POLL_MS + 1),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_long_poll_releases_the_port_and_can_be_cancelled() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read_after(Duration::from_secs(3600), b"never");
+        let service = create_open_mock_service(1024, mock.clone());
+        let poller = {
+            let service = service.clone();
+            std::thread::spawn(move || {
+                service.read_with_long_poll(
+                    1024,
+                    ReadEncoding::Utf8,
+                    ReadTransform::default(),
+                    false,
+                    Some(Duration::from_secs(30)),
+                )
+            })
+        };
+        std::thread::sleep(Duration::from_millis(50));
+
+        // Writes get the port between slices of the poll
+        let started = std::time::Instant::now();
+        service.write("PING").unwrap();
+        assert!(started.elapsed() < Duration::from_secs(2));
+
+        service.cancel_read();
+        assert!(matches!(
+            poller.join().unwrap(),
+            Err(ServiceError::Cancelled)
+        ));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_read_idle_gap_coalesces_bursts() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"AB");
+        mock.enqueue_read_after(Duration::from_millis(20), b"CD");
+        mock.enqueue_read_after(Duration::from_millis(40), b"EF");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock,
+            PortConfig {
+                read_idle_gap_ms: Some(200),
+                ..mock_port_config()
+            },
+        );
+
+        let result = service.read().unwrap();
+        assert_eq!(result.data, "ABCDEF");
+        assert_eq!(result.bytes_read, 6);
+    }
+
+    #[test]
+    fn test_read_without_idle_gap_splits_bursts() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"AB");
+        mock.enqueue_read_after(Duration::from_millis(20), b"CD");
+        let service = create_open_mock_service(1024, mock);
+
+        assert_eq!(service.read().unwrap().data, "AB");
+        assert_eq!(service.read().unwrap().data, "CD");
+    }
+
+    #[test]
+    fn test_read_idle_gap_stops_after_gap() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"AB");
+        mock.enqueue_read_after(Duration::from_millis(300), b"CD");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock,
+            PortConfig {
+                read_idle_gap_ms: Some(20),
+                ..mock_port_config()
+            },
+        );
+
+        assert_eq!(service.read().unwrap().data, "AB");
+        assert_eq!(service.read().unwrap().data, "CD");
+    }
+
+    #[test]
+    fn test_read_idle_gap_respects_max_bytes() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"AB");
+        mock.enqueue_read_after(Duration::from_millis(10), b"CDEF");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock,
+            PortConfig {
+                read_idle_gap_ms: Some(200),
+                ..mock_port_config()
+            },
+        );
+
+        assert_eq!(service.read_bytes_n(4).unwrap().data, "ABCD");
+    }
+
+    #[test]
+    fn test_capture_until_silence_collects_burst() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.enqueue_read(b"U-Boot 2024.01\r\n");
+        mock.enqueue_read_after(Duration::from_millis(20), b"Booting kernel...\r\n");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        let result = service.capture_until_silence(200, 1024, 5000).unwrap();
+        assert_eq!(result.data, "U-Boot 2024.01\r\nBooting kernel...\r\n");
+        assert_eq!(result.bytes_read, 35);
+        assert_eq!(result.termination, Some(ReadTermination::IdleTimeout));
+
+        // The byte cap ends a capture that is still talking
+        mock.clone().enqueue_read(b"0123456789");
+        let result = service.capture_until_silence(200, 4, 5000).unwrap();
+        assert_eq!(result.data, "0123");
+        assert_eq!(result.termination, Some(ReadTermination::MaxBytes));
+    }
+
+    #[test]
+    fn test_pending_frame_bytes_reported() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        // The second sentence has not seen its terminator yet
+        mock.enqueue_read(b"$GPRMC,1\n$GPGGA,123");
+        let line = service.read_line(500, None).unwrap();
+        assert_eq!(line.data, "$GPRMC,1");
+
+        match service.status().unwrap() {
+            StatusResult::Open {
+                pending_frame_bytes,
+                ..
+            } => assert_eq!(pending_frame_bytes, 10),
+            StatusResult::Closed => panic!("expected open status"),
+        }
+        assert_eq!(service.metrics().unwrap().pending_frame_bytes, Some(10));
+    }
+
+    #[test]
+    fn test_read_returns_buffered_bytes_before_the_port() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        if let PortState::Open { frame_buffer, .. } = &mut *lock_state(&service.state) {
+            frame_buffer.extend_from_slice(b"$EVT\xE2\x82");
+        }
+        mock.enqueue_read(b"\xAC!");
+
+        // The complete text is returned without touching the silent port
+        let result = service.read().unwrap();
+        assert_eq!(result.data, "$EVT");
+        assert_eq!(result.bytes_read, 0);
+        assert_eq!(service.metrics().unwrap().timeout_streak, Some(0));
+        // A lone partial character waits for the rest of its bytes
+        assert_eq!(service.read().unwrap().data, "\u{20AC}!");
+    }
+
+    #[test]
+    fn test_write_reports_payload_and_terminator_bytes() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        let result = service.write("PING").unwrap();
+        assert_eq!(result.payload_bytes, 4);
+        assert_eq!(result.terminator_bytes, 0);
+        assert_eq!(result.bytes_written, 4);
+
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                terminator: Some("\r\n".to_string()),
+                ..mock_port_config()
+            },
+        );
+        // A terminator the caller already supplied is not sent twice
+        for (data, total) in [("PING", 6), ("PING\r\n", 12)] {
+            let result = service.write(data).unwrap();
+            assert_eq!(result.payload_bytes, 4);
+            assert_eq!(result.terminator_bytes, 2);
+            assert_eq!(result.bytes_written, 6);
+            assert_eq!(result.bytes_written_total, total);
+        }
+    }
+
+    #[test]
+    fn test_reset_leaves_a_busy_port_open() {
+        let service = create_open_mock_service(1024, MockSerialPort::new("MOCK0"));
+        service.write("PING").unwrap();
+
+        let held = lock_state(&service.state);
+        let reset = std::thread::spawn({
+            let service = service.clone();
+            move || service.reset()
+        })
+        .join()
+        .unwrap();
+        drop(held);
+        assert!(reset.port_busy);
+        assert!(reset.closed.is_none());
+        assert!(reset.writer_restarted);
+        assert!(service.is_open());
+
+        let reset = service.reset();
+        assert!(!reset.port_busy);
+        assert!(reset.closed.is_some());
+        assert!(!service.is_open());
+    }
+
+    #[test]
+    fn test_write_can_skip_the_terminator_per_call() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                terminator: Some("\n".to_string()),
+                write_checksum: Some(ChecksumKind::Crc16Modbus),
+                ..mock_port_config()
+            },
+        );
+
+        let result = service.write_bytes_with(b"12345", Some(false)).unwrap();
+        assert_eq!(result.checksum, None);
+        assert_eq!(result.payload_bytes, 5);
+        assert_eq!(result.terminator_bytes, 0);
+        // A trailing terminator is kept rather than stripped
+        service.write_bytes_with(b"6789\n", Some(false)).unwrap();
+        let result = service.write_bytes_with(b"123456789", Some(true)).unwrap();
+        assert_eq!(result.checksum.as_deref(), Some("374B"));
+        assert_eq!(result.terminator_bytes, 1);
+        service.write_bytes_with(b"X", None).unwrap();
+
+        let mut framed = b"123456789".to_vec();
+        framed.extend_from_slice(&[0x37, 0x4B, b'\n']);
+        let log = mock.get_write_log();
+        assert_eq!(log[..3], [b"12345".to_vec(), b"6789\n".to_vec(), framed]);
+        assert_eq!(log[3].first(), Some(&b'X'));
+        assert_eq!(log[3].last(), Some(&b'\n'));
+    }
+
+    #[test]
+    fn test_write_resumes_after_short_writes() {
+        let mock = MockSerialPort::new("MOCK0");
+        let config = PortConfig {
+            terminator: Some("\r\n".to_string()),
+            timeout_ms: 50,
+            ..mock_port_config()
+        };
+        let service = create_open_mock_service_with_config(1024, mock.clone(), config.clone());
+
+        mock.clone().set_write_chunk(Some(2));
+        let result = service.write("HELLO").unwrap();
+        assert_eq!(result.bytes_written, 7);
+        let log = mock.get_write_log();
+        assert_eq!(log.len(), 4);
+        assert_eq!(log.concat(), b"HELLO\r\n");
+
+        // The port stops draining partway: the tail is reported, not dropped
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(1024, mock.clone(), config);
+        mock.clone().set_write_chunk(Some(2));
+        mock.clone().set_write_capacity(Some(3));
+        assert_eq!(
+            service.write("HELLO").unwrap_err(),
+            ServiceError::WriteTimeout {
+                written: 3,
+                expected: 7,
+                flow_paused: false,
+            }
+        );
+        assert_eq!(mock.get_write_log().concat(), b"HEL");
+        assert_eq!(service.metrics().unwrap().bytes_written_total, Some(3));
+    }
+
+    #[test]
+    fn test_write_reports_xoff_stalls() {
+        let config = PortConfig {
+            timeout_ms: 200,
+            ..mock_port_config()
+        };
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(1024, mock.clone(), config);
+
+        // Not observable until the port reports flow-control state
+        assert_eq!(service.write("A").unwrap().flow_paused_ms, None);
+
+        // XON arrives before the write timeout: the pause is reported
+        mock.clone().pause_flow(Duration::from_millis(60));
+        let result = service.write("HELLO").unwrap();
+        assert!(result.flow_paused_ms.unwrap() >= 40, "{result:?}");
+        assert_eq!(mock.get_write_log().concat(), b"AHELLO");
+        assert_eq!(service.write("B").unwrap().flow_paused_ms, Some(0));
+
+        // XOFF outlasts the write timeout
+        mock.clone().pause_flow(Duration::from_secs(5));
+        let err = service.write("HELLO").unwrap_err();
+        assert_eq!(
+            err,
+            ServiceError::WriteTimeout {
+                written: 0,
+                expected: 5,
+                flow_paused: true,
+            }
+        );
+        assert!(err.to_string().contains("XOFF"));
+    }
+
+    #[test]
+    fn test_set_buffer_sizes_reports_clamped_sizes() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+
+        let result = service.set_buffer_sizes(Some(1 << 20), Some(8192)).unwrap();
+        assert_eq!(result.port_name, "MOCK0");
+        assert_eq!(result.requested.rx, Some(1 << 20));
+        assert_eq!(result.effective.rx, Some(crate::port::MOCK_MAX_BUFFER_SIZE));
+        assert_eq!(result.effective.tx, Some(8192));
+
+        // A query leaves the sizes alone
+        let query = service.set_buffer_sizes(None, None).unwrap();
+        assert_eq!(query.effective, result.effective);
+        assert_eq!(
+            mock.get_buffer_size_requests(),
+            vec![(Some(1 << 20), Some(8192))]
+        );
+
+        assert!(matches!(
+            service.set_buffer_sizes(Some(0), None),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+        assert_eq!(
+            create_test_service()
+                .set_buffer_sizes(Some(4096), None)
+                .unwrap_err(),
+            ServiceError::PortNotOpen
+        );
+    }
+
+    #[test]
+    fn test_strip_echo_and_prompt_from_shell_output() {
+        let config = PortConfig {
+            terminator: Some("\r\n".to_string()),
+            timeout_ms: 50,
+            strip_echo: true,
+            strip_prompt: Some("> ".to_string()),
+            max_open_duration_ms: None,
+            ..mock_port_config()
         };
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_echo(true);
+        let service = create_open_mock_service_with_config(1024, mock.clone(), config);
 
-        // Open port with new configuration
-        let port = SyncSerialPort::open(&target, port_config)
-            .map_err(|e| ServiceError::PortError(e.to_string()))?;
+        service.write("ls").unwrap();
+        mock.clone().enqueue_read(b"boot.txt log.txt\r\n> ");
+        assert_eq!(service.read().unwrap().data, "boot.txt log.txt");
 
-        // Replace state
-        *st = PortState::Open {
-            port: Box::new(port),
-            config: PortConfig {
-                port_name: target.clone(),
-                baud_rate: config.baud_rate,
-                timeout_ms: config.timeout_ms,
-                data_bits: config.data_bits,
-                parity: config.parity,
-                stop_bits: config.stop_bits,
-                flow_control: config.flow_control,
-                terminator: config.terminator,
-                idle_disconnect_ms: config.idle_disconnect_ms,
-            },
-            last_activity: std::time::Instant::now(),
-            timeout_streak: 0,
-            bytes_read_total: 0,
-            bytes_written_total: 0,
-            idle_close_count: 0,
-            open_started: std::time::Instant::now(),
-        };
+        // The echo line is skipped and the trailing prompt dropped
+        service.write("ver").unwrap();
+        mock.clone().enqueue_read(b"v1.2\r\n> ");
+        assert_eq!(service.read_line(200, None).unwrap().data, "v1.2");
+        assert_eq!(service.read_line(100, None).unwrap().data, "");
 
-        Ok(OpenResult {
-            port_name: target,
-            baud_rate: config.baud_rate,
-            message: "reconfigured".to_string(),
-        })
+        // Output that is not an echo is left alone
+        mock.clone().set_echo(false);
+        service.write("id").unwrap();
+        mock.clone().enqueue_read(b"uid=0\r\n");
+        assert_eq!(
+            service.capture_until_silence(20, 64, 200).unwrap().data,
+            "uid=0"
+        );
     }
 
-    /// Get current port status.
-    ///
-    /// # Errors
-    ///
-    /// - `ServiceError::StateLockPoisoned` if the state lock is poisoned
-    pub fn status(&self) -> ServiceResult<StatusResult> {
-        let st = self
-            .state
-            .lock()
-            .map_err(|_| ServiceError::StateLockPoisoned)?;
-
-        let result = match &*st {
-            PortState::Closed => StatusResult::Closed,
-            PortState::Open {
-                config,
-                bytes_read_total,
-                bytes_written_total,
-                idle_close_count,
-                open_started,
-                last_activity,
-                timeout_streak,
-                ..
-            } => StatusResult::Open {
-                config: config.clone(),
-                metrics: Some(PortMetrics {
-                    bytes_read_total: *bytes_read_total,
-                    bytes_written_total: *bytes_written_total,
-                    idle_close_count: *idle_close_count,
-                    open_duration_ms: open_started.elapsed().as_millis() as u64,
-                    last_activity_ms: last_activity.elapsed().as_millis() as u64,
-                    timeout_streak: *timeout_streak,
-                }),
-            },
+    #[test]
+    fn test_concurrent_writes_are_not_interleaved() {
+        let mock = MockSerialPort::new("MOCK0");
+        let config = PortConfig {
+            terminator: Some("\n".to_string()),
+            ..mock_port_config()
         };
+        let service = create_open_mock_service_with_config(1024, mock.clone(), config);
+        // Every payload takes several port writes
+        mock.clone().set_write_chunk(Some(3));
 
-        Ok(result)
-    }
+        let payloads: Vec<String> = (0..8)
+            .map(|i| format!("CMD{}:{}", i, "x".repeat(20)))
+            .collect();
+        let handles: Vec<_> = payloads
+            .iter()
+            .cloned()
+            .map(|payload| {
+                let service = service.clone();
+                std::thread::spawn(move || service.write(&payload).unwrap())
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(handle.join().unwrap().bytes_written, 26);
+        }
 
-    /// Get port metrics.
-    ///
-    /// # Errors
-    ///
-    /// - `ServiceError::StateLockPoisoned` if the state lock is poisoned
-    pub fn metrics(&self) -> ServiceResult<MetricsResult> {
-        let st = self
-            .state
-            .lock()
-            .map_err(|_| ServiceError::StateLockPoisoned)?;
+        let written = String::from_utf8(mock.get_write_log().concat()).unwrap();
+        let mut lines: Vec<&str> = written.lines().collect();
+        lines.sort_unstable();
+        assert_eq!(lines, payloads);
 
-        let result = match &*st {
-            PortState::Closed => MetricsResult {
-                state: "Closed".to_string(),
-                bytes_read_total: None,
-                bytes_written_total: None,
-                idle_close_count: None,
-                open_duration_ms: None,
-                last_activity_ms: None,
-                timeout_streak: None,
-            },
-            PortState::Open {
-                bytes_read_total,
-                bytes_written_total,
-                idle_close_count,
-                open_started,
-                last_activity,
-                timeout_streak,
-                ..
-            } => MetricsResult {
-                state: "Open".to_string(),
-                bytes_read_total: Some(*bytes_read_total),
-                bytes_written_total: Some(*bytes_written_total),
-                idle_close_count: Some(*idle_close_count),
-                open_duration_ms: Some(open_started.elapsed().as_millis() as u64),
-                last_activity_ms: Some(last_activity.elapsed().as_millis() as u64),
-                timeout_streak: Some(*timeout_streak),
+        // Queued writes go out in submission order
+        mock.clone().clear_write_log();
+        let pending: Vec<_> = ["first", "second", "third"]
+            .iter()
+            .map(|payload| service.enqueue_write(payload))
+            .collect();
+        for write in pending {
+            write.wait().unwrap();
+        }
+        assert_eq!(mock.get_write_log().concat(), b"first\nsecond\nthird\n");
+    }
+
+    #[test]
+    fn test_write_appends_checksum_before_terminator() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                terminator: Some("\n".to_string()),
+                write_checksum: Some(ChecksumKind::Crc16Modbus),
+                ..mock_port_config()
             },
-        };
+        );
 
-        Ok(result)
+        let result = service.write("123456789").unwrap();
+        assert_eq!(result.checksum.as_deref(), Some("374B"));
+        assert_eq!(result.payload_bytes, 9);
+        assert_eq!(result.terminator_bytes, 1);
+        assert_eq!(result.bytes_written, 12);
+        let mut expected = b"123456789".to_vec();
+        expected.extend_from_slice(&[0x37, 0x4B, b'\n']);
+        assert_eq!(mock.get_write_log(), vec![expected]);
     }
 
-    /// Check if a port is currently open.
-    ///
-    /// Returns false if the state lock is poisoned.
-    pub fn is_open(&self) -> bool {
-        self.state
-            .lock()
-            .map(|st| matches!(&*st, PortState::Open { .. }))
-            .unwrap_or(false)
+    #[test]
+    fn test_write_allowlist_permits_matching_writes() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone())
+            .with_write_allowlist(&[r"AT(\+\w+)?".to_string(), "LED (ON|OFF)".to_string()]);
+
+        service.write("AT+GMR").unwrap();
+        service.write("LED ON").unwrap();
+        assert_eq!(
+            mock.get_write_log(),
+            vec![b"AT+GMR".to_vec(), b"LED ON".to_vec()]
+        );
+
+        // An empty allowlist lifts the restriction
+        let open = service.with_write_allowlist(&[]);
+        open.write("REBOOT").unwrap();
+        assert_eq!(mock.get_write_log().len(), 3);
     }
 
-    // ========== Helper Methods ==========
+    #[test]
+    fn test_write_allowlist_rejects_other_writes() {
+        let mock = MockSerialPort::new("MOCK0");
+        let mut config = mock_port_config();
+        config.terminator = Some("\r\n".to_string());
+        let service = create_open_mock_service_with_config(1024, mock.clone(), config)
+            .with_write_allowlist(&["AT.*".to_string(), "LED (ON|OFF)".to_string()]);
 
-    fn convert_data_bits(bits: DataBitsCfg) -> DataBits {
-        match bits {
-            DataBitsCfg::Five => DataBits::Five,
-            DataBitsCfg::Six => DataBits::Six,
-            DataBitsCfg::Seven => DataBits::Seven,
-            DataBitsCfg::Eight => DataBits::Eight,
+        // Entries must match the whole payload, and may not carry a second
+        // command after an embedded terminator
+        for data in ["REBOOT", "xAT", "LED ONCE", "AT\r\nREBOOT"] {
+            match service.write(data) {
+                Err(ServiceError::InvalidConfig(msg)) => assert_eq!(msg, "write not permitted"),
+                other => panic!("expected rejection of {:?}, got {:?}", data, other),
+            }
         }
+        assert!(matches!(
+            service.write_repeat("REBOOT", 3, 0),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+        assert!(mock.get_write_log().is_empty());
     }
 
-    fn convert_parity(parity: ParityCfg) -> Parity {
-        match parity {
-            ParityCfg::None => Parity::None,
-            ParityCfg::Odd => Parity::Odd,
-            ParityCfg::Even => Parity::Even,
-        }
+    #[test]
+    fn test_write_allowlist_applies_to_queued_writes_of_clones() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        // Start the writer before the restricted clone exists
+        service.write("PING").unwrap();
+
+        let restricted = service.clone().with_write_allowlist(&["AT.*".to_string()]);
+        assert!(matches!(
+            restricted.write("REBOOT"),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+        service.write("REBOOT").unwrap();
+        assert_eq!(mock.get_write_log().len(), 2);
     }
 
-    fn convert_stop_bits(bits: StopBitsCfg) -> StopBits {
-        match bits {
-            StopBitsCfg::One => StopBits::One,
-            StopBitsCfg::Two => StopBits::Two,
-        }
+    #[test]
+    fn test_modbus_read_many_coalesces_requests() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        let r = |start, count| RegisterRange { start, count };
+        let reply = |data: &[u8]| {
+            let mut frame = vec![0x11, 0x03, data.len() as u8];
+            frame.extend_from_slice(data);
+            let crc = ChecksumKind::Crc16Modbus.compute(&frame);
+            frame.extend_from_slice(&crc);
+            frame
+        };
+        // Delayed so that discarding stale input before the batch keeps them
+        mock.enqueue_read_after(Duration::from_millis(1), &reply(&[0, 1, 0, 2, 0, 3]));
+        mock.enqueue_read_after(Duration::from_millis(1), &reply(&[0xff, 0xfe]));
+
+        let result = service
+            .modbus_read_many(
+                0x11,
+                RegisterKind::Holding,
+                &[r(102, 1), r(100, 2), r(110, 1)],
+                0,
+                500,
+            )
+            .unwrap();
+        assert_eq!(result.requests, vec![r(100, 3), r(110, 1)]);
+        assert_eq!(
+            result.values,
+            BTreeMap::from([(100, 1), (101, 2), (102, 3), (110, 0xfffe)])
+        );
+        assert_eq!(
+            mock.get_write_log(),
+            vec![
+                modbus::read_request(0x11, RegisterKind::Holding, r(100, 3)),
+                modbus::read_request(0x11, RegisterKind::Holding, r(110, 1)),
+            ]
+        );
+
+        // A silent device times out rather than returning partial values
+        assert!(matches!(
+            service.modbus_read_many(0x11, RegisterKind::Holding, &[r(0, 1)], 0, 50),
+            Err(ServiceError::Timeout(_))
+        ));
+        assert!(matches!(
+            service.modbus_read_many(0x11, RegisterKind::Holding, &[r(65535, 2)], 0, 50),
+            Err(ServiceError::InvalidConfig(_))
+        ));
     }
 
-    fn convert_flow_control(flow: FlowControlCfg) -> FlowControl {
-        match flow {
-            FlowControlCfg::None => FlowControl::None,
-            FlowControlCfg::Hardware => FlowControl::Hardware,
-            FlowControlCfg::Software => FlowControl::Software,
+    #[test]
+    fn test_preview_write_matches_write_without_sending() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                terminator: Some("\r\n".to_string()),
+                write_checksum: Some(ChecksumKind::Crc16Modbus),
+                ..mock_port_config()
+            },
+        );
+
+        let mut expected = b"123456789".to_vec();
+        expected.extend_from_slice(&[0x37, 0x4B, b'\r', b'\n']);
+        // A terminator already on the data is not doubled
+        for data in ["123456789", "123456789\r\n"] {
+            let preview = service
+                .preview_write(data, WriteEncoding::Text, None)
+                .unwrap();
+            assert_eq!(preview, expected);
         }
-    }
-}
+        assert_eq!(
+            service
+                .preview_write("31 32 33 34 35 36 37 38 39", WriteEncoding::Hex, None)
+                .unwrap(),
+            expected
+        );
+        assert!(mock.get_write_log().is_empty());
 
-// ========== Tests ==========
+        service.write("123456789").unwrap();
+        assert_eq!(mock.get_write_log(), vec![expected]);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::state::PortState;
-    use std::sync::{Arc, Mutex};
+        // Without the terminator the bytes go out verbatim, as for a write
+        assert_eq!(
+            service
+                .preview_write("123456789", WriteEncoding::Text, Some(false))
+                .unwrap(),
+            b"123456789"
+        );
 
-    fn create_test_service() -> PortService {
-        let state = Arc::new(Mutex::new(PortState::Closed));
-        PortService::new(state)
+        assert!(matches!(
+            service.preview_write("zz", WriteEncoding::Hex, None),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+        let plain = create_open_mock_service(1024, MockSerialPort::new("MOCK0"));
+        assert_eq!(
+            plain
+                .preview_write("AT", WriteEncoding::Text, None)
+                .unwrap(),
+            b"AT"
+        );
+        // The allowlist rejects a preview the write would be refused for
+        let restricted = plain.with_write_allowlist(&["AT".to_string()]);
+        assert!(matches!(
+            restricted.preview_write("ATZ", WriteEncoding::Text, None),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+        service.close().unwrap();
+        assert_eq!(
+            service
+                .preview_write("AT", WriteEncoding::Text, None)
+                .unwrap_err(),
+            ServiceError::PortNotOpen
+        );
     }
 
-    #[allow(dead_code)]
-    fn create_open_config(port_name: &str) -> OpenConfig {
-        OpenConfig {
-            port_name: port_name.to_string(),
-            baud_rate: 9600,
-            timeout_ms: 1000,
-            data_bits: DataBitsCfg::Eight,
-            parity: ParityCfg::None,
-            stop_bits: StopBitsCfg::One,
-            flow_control: FlowControlCfg::None,
-            terminator: Some("\n".to_string()),
-            idle_disconnect_ms: None,
+    #[test]
+    fn test_write_flushes_only_when_configured() {
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service(1024, mock.clone());
+        let result = service.write("AT").unwrap();
+        assert_eq!(result.flush_duration_ms, None);
+        assert_eq!(mock.flush_count(), 0);
+
+        let mock = MockSerialPort::new("MOCK0");
+        let service = create_open_mock_service_with_config(
+            1024,
+            mock.clone(),
+            PortConfig {
+                flush_on_write: true,
+                ..mock_port_config()
+            },
+        );
+        let result = service.write("AT").unwrap();
+        assert!(result.flush_duration_ms.is_some());
+        assert_eq!(mock.flush_count(), 1);
+        assert_eq!(mock.get_write_log(), vec![b"AT".to_vec()]);
+    }
+
+    fn half_duplex_config() -> PortConfig {
+        PortConfig {
+            terminator: Some("\r\n".to_string()),
+            half_duplex_echo: true,
+            ..mock_port_config()
         }
     }
 
     #[test]
-    fn test_service_creation() {
-        let service = create_test_service();
-        assert!(!service.is_open());
+    fn test_half_duplex_write_consumes_matching_echo() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_echo(true);
+        let service =
+            create_open_mock_service_with_config(1024, mock.clone(), half_duplex_config());
+
+        let result = service.write("AT").unwrap();
+        assert_eq!(result.echo_matched, Some(true));
+        assert_eq!(mock.available_bytes(), 0);
+
+        // Only the device's reply is left for the read
+        mock.set_echo(false);
+        mock.clone().enqueue_read(b"OK\r\n");
+        assert_eq!(service.read_bytes_n(64).unwrap().data, "OK");
     }
 
     #[test]
-    fn test_close_when_already_closed() {
-        let service = create_test_service();
-        let result = service.close();
-        assert!(result.is_ok());
-        assert_eq!(result.unwrap().message, "already closed");
+    fn test_half_duplex_write_reports_corrupted_echo() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        // Bus noise flipped a bit in the echo; the reply follows it
+        mock.enqueue_read(b"AU\r\nOK\r\n");
+        let service =
+            create_open_mock_service_with_config(1024, mock.clone(), half_duplex_config());
+
+        let result = service.write("AT").unwrap();
+        assert_eq!(result.echo_matched, Some(false));
+        // Exactly the echo's length was consumed
+        assert_eq!(service.read_bytes_n(64).unwrap().data, "OK");
     }
 
     #[test]
-    fn test_write_when_not_open() {
-        let service = create_test_service();
-        let result = service.write("test");
-        assert!(matches!(result, Err(ServiceError::PortNotOpen)));
+    fn test_full_duplex_write_leaves_echo_alone() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_echo(true);
+        let service = create_open_mock_service(1024, mock.clone());
+
+        let result = service.write("AT").unwrap();
+        assert_eq!(result.echo_matched, None);
+        assert_eq!(mock.available_bytes(), 2);
     }
 
     #[test]
-    fn test_read_when_not_open() {
+    fn test_read_bytes_n_zero_rejected() {
         let service = create_test_service();
-        let result = service.read();
-        assert!(matches!(result, Err(ServiceError::PortNotOpen)));
+        let result = service.read_bytes_n(0);
+        assert!(matches!(result, Err(ServiceError::InvalidConfig(_))));
     }
 
     #[test]
@@ -701,19 +7313,273 @@ mod tests {
         let service = create_test_service();
         let config = ReconfigureConfig {
             port_name: None,
-            baud_rate: 9600,
-            timeout_ms: 1000,
-            data_bits: DataBitsCfg::Eight,
-            parity: ParityCfg::None,
-            stop_bits: StopBitsCfg::One,
-            flow_control: FlowControlCfg::None,
-            terminator: None,
-            idle_disconnect_ms: None,
+            settings: PortConfigPatch {
+                baud_rate: Some(9600),
+                ..Default::default()
+            },
+            soft_reconfigure: false,
         };
         let result = service.reconfigure(config);
         assert!(matches!(result, Err(ServiceError::NoPortSpecified)));
     }
 
+    #[test]
+    fn test_reconfigure_inherits_omitted_settings() {
+        let (opener, _) = flaky_opener(0, || PortError::busy("MOCK0", None));
+        let mut config = mock_port_config();
+        config.baud_rate = 115200;
+        config.terminator = Some("\r\n".into());
+        let service =
+            create_open_mock_service_with_config(1024, MockSerialPort::new("MOCK0"), config)
+                .with_port_opener(opener.clone());
+
+        let result = service
+            .reconfigure(ReconfigureConfig {
+                settings: PortConfigPatch {
+                    parity: Some(ParityCfg::Even),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(result.baud_rate, 115200);
+        assert_eq!(result.config.terminator.as_deref(), Some("\r\n"));
+        assert!(matches!(result.config.parity, ParityCfg::Even));
+
+        // With nothing open to inherit from, baud falls back to 9600
+        let service = create_test_service().with_port_opener(opener);
+        let result = service
+            .reconfigure(ReconfigureConfig {
+                port_name: Some("MOCK1".into()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(result.baud_rate, 9600);
+    }
+
+    #[test]
+    fn test_reconfigure_keeps_linked_session() {
+        let reconfigure = |soft_reconfigure: bool| {
+            let (opener, _) = flaky_opener(0, || PortError::busy("MOCK0", None));
+            let service = create_open_mock_service(1024, MockSerialPort::new("MOCK0"))
+                .with_port_opener(opener);
+            service.link_session(Some("session-1".into())).unwrap();
+            if let PortState::Open { frame_buffer, .. } = &mut *lock_state(&service.state) {
+                frame_buffer.extend_from_slice(b"partial");
+            }
+            let result = service
+                .reconfigure(ReconfigureConfig {
+                    port_name: None,
+                    settings: PortConfigPatch {
+                        baud_rate: Some(115200),
+                        ..Default::default()
+                    },
+                    soft_reconfigure,
+                })
+                .unwrap();
+            assert_eq!(result.active_session.as_deref(), Some("session-1"));
+            match service.status().unwrap() {
+                StatusResult::Open {
+                    active_session,
+                    pending_frame_bytes,
+                    config,
+                    ..
+                } => {
+                    assert_eq!(active_session.as_deref(), Some("session-1"));
+                    assert_eq!(config.baud_rate, 115200);
+                    pending_frame_bytes
+                }
+                StatusResult::Closed => panic!("port should be open"),
+            }
+        };
+
+        assert_eq!(reconfigure(true), 7);
+        assert_eq!(reconfigure(false), 0);
+    }
+
+    #[test]
+    fn test_patch_config_reopens_only_for_line_settings() {
+        let (opener, calls) = flaky_opener(0, || PortError::busy("MOCK0", None));
+        let mut config = mock_port_config();
+        config.terminator = Some("\r\n".into());
+        let service =
+            create_open_mock_service_with_config(1024, MockSerialPort::new("MOCK0"), config)
+                .with_port_opener(opener);
+        let open_id = match service.status().unwrap() {
+            StatusResult::Open { open_id, .. } => open_id,
+            StatusResult::Closed => panic!("port should be open"),
+        };
+
+        let terminator_only = PortConfigPatch {
+            terminator: Some(Some(">".into())),
+            ..Default::default()
+        };
+        let result = service.patch_config(terminator_only).unwrap();
+        assert!(!result.reopened);
+        assert_eq!(result.open_id, open_id);
+        assert_eq!(result.config.terminator.as_deref(), Some(">"));
+        assert_eq!(result.config.baud_rate, 9600);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        let baud_only = PortConfigPatch {
+            baud_rate: Some(115200),
+            ..Default::default()
+        };
+        let result = service.patch_config(baud_only).unwrap();
+        assert!(result.reopened);
+        assert_ne!(result.open_id, open_id);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+        match service.status().unwrap() {
+            StatusResult::Open { config, .. } => {
+                assert_eq!(config.baud_rate, 115200);
+                // Not reset to the reconfigure defaults
+                assert_eq!(config.terminator.as_deref(), Some(">"));
+            }
+            StatusResult::Closed => panic!("port should be open"),
+        }
+
+        // A failed reopen keeps the port and its previous settings
+        let (opener, _) = flaky_opener(1, || PortError::busy("MOCK0", None));
+        let service = service.with_port_opener(opener);
+        let open_id = result.open_id;
+        let err = service
+            .patch_config(PortConfigPatch {
+                baud_rate: Some(57600),
+                ..Default::default()
+            })
+            .unwrap_err();
+        assert!(matches!(err, ServiceError::PortError(_)));
+        match service.status().unwrap() {
+            StatusResult::Open {
+                config,
+                open_id: still_open,
+                ..
+            } => {
+                assert_eq!(config.baud_rate, 115200);
+                assert_eq!(still_open, open_id);
+            }
+            StatusResult::Closed => panic!("port should stay open"),
+        }
+
+        service.close().unwrap();
+        assert!(matches!(
+            service.patch_config(PortConfigPatch::default()),
+            Err(ServiceError::PortNotOpen)
+        ));
+    }
+
+    #[test]
+    fn test_set_framing_live_keeps_buffered_data() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_capabilities(PortCapabilities {
+            supports_set_framing_live: true,
+            ..Default::default()
+        });
+        mock.enqueue_read(b"pending");
+        let (opener, calls) = flaky_opener(0, || PortError::busy("MOCK0", None));
+        let service = create_open_mock_service(1024, mock.clone()).with_port_opener(opener);
+        let open_id = match service.status().unwrap() {
+            StatusResult::Open { open_id, .. } => open_id,
+            StatusResult::Closed => panic!("port should be open"),
+        };
+
+        let result = service
+            .set_framing(FramingRequest {
+                parity: Some(ParityCfg::Even),
+                stop_bits: Some(StopBitsCfg::Two),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(!result.reopened);
+        assert!(result.warning.is_none());
+        assert_eq!(result.open_id, open_id);
+        assert!(matches!(result.config.parity, ParityCfg::Even));
+        assert!(matches!(result.config.stop_bits, StopBitsCfg::Two));
+        assert!(matches!(result.config.data_bits, DataBitsCfg::Eight));
+        assert_eq!(
+            mock.get_framing_log(),
+            vec![
+                FramingChange::Parity(Parity::Even),
+                FramingChange::StopBits(StopBits::Two),
+            ]
+        );
+        assert_eq!(mock.available_bytes(), 7);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 0);
+
+        assert!(matches!(
+            service.set_framing(FramingRequest::default()),
+            Err(ServiceError::InvalidConfig(_))
+        ));
+    }
+
+    #[test]
+    fn test_set_framing_live_rolls_back_when_a_setting_fails() {
+        let mut mock = MockSerialPort::new("MOCK0");
+        mock.set_capabilities(PortCapabilities {
+            supports_set_framing_live: true,
+            ..Default::default()
+        });
+        mock.fail_next_framing_change(FramingChange::StopBits(StopBits::Two));
+        let service = create_open_mock_service(1024, mock.clone());
+
+        let result = service.set_framing(FramingRequest {
+            data_bits: Some(DataBitsCfg::Seven),
+            parity: Some(ParityCfg::Even),
+            stop_bits: Some(StopBitsCfg::Two),
+        });
+        assert!(matches!(result, Err(ServiceError::PortError(_))));
+        assert_eq!(
+            mock.get_framing_log(),
+            vec![
+                FramingChange::DataBits(DataBits::Seven),
+                FramingChange::Parity(Parity::Even),
+                FramingChange::DataBits(DataBits::Eight),
+                FramingChange::Parity(Parity::None),
+            ]
+        );
+        match service.status().unwrap() {
+            StatusResult::Open { config, .. } => {
+                assert!(matches!(config.data_bits, DataBitsCfg::Eight));
+                assert!(matches!(config.parity, ParityCfg::None));
+                assert!(matches!(config.stop_bits, StopBitsCfg::One));
+            }
+            StatusResult::Closed => panic!("port should stay open"),
+        }
+    }
+
+    #[test]
+    fn test_set_framing_reopens_when_live_change_unsupported() {
+        let mock = MockSerialPort::new("MOCK0");
+        let (opener, calls) = flaky_opener(0, || PortError::busy("MOCK0", None));
+        let service = create_open_mock_service(1024, mock.clone()).with_port_opener(opener);
+        let open_id = match service.status().unwrap() {
+            StatusResult::Open { open_id, .. } => open_id,
+            StatusResult::Closed => panic!("port should be open"),
+        };
+
+        let result = service
+            .set_framing(FramingRequest {
+                data_bits: Some(DataBitsCfg::Seven),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(result.reopened);
+        assert!(result.warning.unwrap().contains("reopened"));
+        assert_ne!(result.open_id, open_id);
+        assert!(matches!(result.config.data_bits, DataBitsCfg::Seven));
+        assert!(mock.get_framing_log().is_empty());
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        service.close().unwrap();
+        assert!(matches!(
+            service.set_framing(FramingRequest {
+                parity: Some(ParityCfg::Odd),
+                ..Default::default()
+            }),
+            Err(ServiceError::PortNotOpen)
+        ));
+    }
+
     #[test]
     fn test_service_error_display() {
         assert_eq!(
@@ -772,6 +7638,10 @@ mod tests {
             PortService::convert_stop_bits(StopBitsCfg::One),
             StopBits::One
         );
+        assert_eq!(
+            PortService::convert_stop_bits(StopBitsCfg::OnedotFive),
+            StopBits::OnedotFive
+        );
         assert_eq!(
             PortService::convert_stop_bits(StopBitsCfg::Two),
             StopBits::Two
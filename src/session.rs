@@ -1,9 +1,19 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, SecondsFormat, SubsecRound, Utc};
+use regex::Regex;
+use rust_mcp_sdk::macros::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::{FromRow, Row, SqlitePool};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::future::Future;
 use std::path::Path;
+use std::sync::{Arc, PoisonError, RwLock};
+use std::time::Duration;
 use uuid::Uuid;
 
+use crate::config::MessageOversize;
+use crate::service::{AutoCloseEvent, CloseHook};
+
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
 pub struct Session {
     pub id: String,                // UUID string
@@ -12,6 +22,42 @@ pub struct Session {
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
     pub closed: i32, // 0 = open, 1 = closed (use integer for sqlite boolean compatibility)
+    /// USB identity of the device, if known
+    #[serde(flatten)]
+    #[sqlx(flatten)]
+    pub signature: DeviceSignature,
+}
+
+/// USB identity of the device a session talks to, for later correlation.
+///
+/// IDs use the `0x2341` form reported by `list_ports_extended`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, FromRow, JsonSchema)]
+pub struct DeviceSignature {
+    #[serde(default)]
+    pub vid: Option<String>,
+    #[serde(default)]
+    pub pid: Option<String>,
+    #[serde(default)]
+    pub device_serial: Option<String>,
+    #[serde(default)]
+    pub manufacturer: Option<String>,
+}
+
+impl DeviceSignature {
+    /// Whether no part of the signature is known.
+    pub fn is_empty(&self) -> bool {
+        self == &Self::default()
+    }
+
+    /// This signature with unknown parts taken from `other`.
+    pub fn or(self, other: Self) -> Self {
+        Self {
+            vid: self.vid.or(other.vid),
+            pid: self.pid.or(other.pid),
+            device_serial: self.device_serial.or(other.device_serial),
+            manufacturer: self.manufacturer.or(other.manufacturer),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
@@ -24,13 +70,137 @@ pub struct Message {
     pub features: Option<String>, // comma separated feature tags (e.g. "error,warning,command")
     pub latency_ms: Option<i64>,  // optional measured round-trip or processing latency
     pub created_at: DateTime<Utc>,
+    /// Byte length of `content` before truncation; `None` if stored intact
+    #[serde(default)]
+    pub original_length: Option<i64>,
+    /// Matches of `session.redact_patterns` replaced in `content`
+    #[serde(default)]
+    pub redactions: i64,
+    /// Per-session insertion counter; orders messages sharing a `created_at`
+    #[serde(default)]
+    pub seq: i64,
+}
+
+/// A message to store with [`SessionStore::append_messages`].
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NewMessage {
+    pub role: String,
+    pub content: String,
+    #[serde(default)]
+    pub direction: Option<String>,
+    #[serde(default)]
+    pub features: Option<String>,
+    #[serde(default)]
+    pub latency_ms: Option<i64>,
 }
 
+/// Default reconnect attempts after a connection-level database error.
+pub const DEFAULT_RECONNECT_RETRIES: u32 = 3;
+
+/// Default delay before the first reconnect attempt; doubled for each retry.
+pub const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_millis(100);
+
 #[derive(Clone)]
 pub struct SessionStore {
-    pool: SqlitePool,
+    /// Current pool; replaced when the database is reconnected
+    pool: Arc<RwLock<SqlitePool>>,
+    database_url: String,
+    max_message_bytes: Option<usize>,
+    oversize: MessageOversize,
+    /// Content matching any of these is replaced before storage
+    redact_patterns: Arc<Vec<Regex>>,
+    reconnect_retries: u32,
+    reconnect_delay: Duration,
+}
+
+/// Replacement for content matched by a redaction pattern.
+pub const REDACTED: &str = "***";
+
+/// Message timestamp as stored: RFC 3339 with a fixed six fractional digits,
+/// so text comparison in SQL matches chronological order.
+fn db_timestamp(t: DateTime<Utc>) -> String {
+    t.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Current time at the precision [`db_timestamp`] keeps.
+fn message_now() -> DateTime<Utc> {
+    Utc::now().trunc_subsecs(6)
+}
+
+/// Order of a session's messages in every listing and export: oldest first,
+/// with messages sharing a `created_at` in insertion order.
+const MESSAGE_ORDER: &str = "ORDER BY created_at ASC, seq ASC";
+
+/// Next `seq` for a message in session `?1`.
+const NEXT_SEQ: &str = "(SELECT COALESCE(MAX(seq), 0) + 1 FROM messages WHERE session_id = ?1)";
+
+/// `created_at` for a new message in session `?1` stamped `?7`: never before
+/// the session's latest message, so a writer that took its timestamp first
+/// but committed second still sorts after the earlier commit.
+const NEXT_CREATED_AT: &str =
+    "MAX(?7, (SELECT COALESCE(MAX(created_at), '') FROM messages WHERE session_id = ?1))";
+
+/// Whether `err` means the database could not be reached, as opposed to a
+/// bad query. Only these are worth reconnecting for.
+fn is_connection_error(err: &sqlx::Error) -> bool {
+    match err {
+        sqlx::Error::Io(_)
+        | sqlx::Error::PoolClosed
+        | sqlx::Error::PoolTimedOut
+        | sqlx::Error::WorkerCrashed => true,
+        // SQLITE_IOERR and SQLITE_CANTOPEN, including their extended codes
+        sqlx::Error::Database(db) => db
+            .code()
+            .and_then(|code| code.parse::<i32>().ok())
+            .is_some_and(|code| matches!(code & 0xff, 10 | 14)),
+        _ => false,
+    }
+}
+
+/// A schema change made after the initial `sessions` and `messages` tables.
+struct Migration {
+    /// Logged and stored in `schema_version`
+    description: &'static str,
+    /// `(table, column, definition)` of each column added
+    columns: &'static [(&'static str, &'static str, &'static str)],
+    /// Run after the columns were added, to fill them for existing rows
+    backfill: Option<&'static str>,
 }
 
+/// Session schema migrations, oldest first; the database is at version `n`
+/// once the first `n` were applied. Only ever append to this list.
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        description: "messages.original_length",
+        columns: &[("messages", "original_length", "INTEGER")],
+        backfill: None,
+    },
+    Migration {
+        description: "messages.redactions",
+        columns: &[("messages", "redactions", "INTEGER NOT NULL DEFAULT 0")],
+        backfill: None,
+    },
+    Migration {
+        description: "messages.seq",
+        columns: &[("messages", "seq", "INTEGER NOT NULL DEFAULT 0")],
+        // Row ids preserve insertion order
+        backfill: Some("UPDATE messages SET seq = id"),
+    },
+    Migration {
+        description: "sessions device signature",
+        columns: &[
+            ("sessions", "vid", "TEXT"),
+            ("sessions", "pid", "TEXT"),
+            ("sessions", "device_serial", "TEXT"),
+            ("sessions", "manufacturer", "TEXT"),
+        ],
+        backfill: None,
+    },
+];
+
+/// Schema version of a fully migrated session database.
+pub const SCHEMA_VERSION: i64 = MIGRATIONS.len() as i64;
+
 impl SessionStore {
     pub async fn new(database_url: &str) -> sqlx::Result<Self> {
         // If this is a file path (sqlite://path/to/file.db) ensure directory exists
@@ -46,7 +216,220 @@ impl SessionStore {
         }
         let pool = SqlitePool::connect(database_url).await?;
         Self::run_migrations(&pool).await?;
-        Ok(Self { pool })
+        Ok(Self {
+            pool: Arc::new(RwLock::new(pool)),
+            database_url: database_url.to_string(),
+            max_message_bytes: None,
+            oversize: MessageOversize::default(),
+            redact_patterns: Arc::new(Vec::new()),
+            reconnect_retries: DEFAULT_RECONNECT_RETRIES,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+        })
+    }
+
+    /// [`new`](Self::new), retried up to `retries` times when the database
+    /// cannot be opened, waiting `delay` before the first retry and doubling
+    /// it each time. Each failed attempt is logged.
+    pub async fn connect_with_retry(
+        database_url: &str,
+        retries: u32,
+        delay: Duration,
+    ) -> sqlx::Result<Self> {
+        let mut attempt = 0;
+        loop {
+            match Self::new(database_url).await {
+                Err(e) if attempt < retries => {
+                    let wait = delay.saturating_mul(2u32.saturating_pow(attempt));
+                    attempt += 1;
+                    tracing::warn!(
+                        error = %e,
+                        attempt,
+                        retries,
+                        delay_ms = wait.as_millis() as u64,
+                        "Failed to open session database; retrying"
+                    );
+                    tokio::time::sleep(wait).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// A [`CloseHook`] that appends a `system` message, tagged `auto_close`,
+    /// to the linked session whenever a port closes on its own, recording
+    /// the reason and final metrics. Ports without a session are skipped.
+    ///
+    /// The message is written by a task on the current tokio runtime, so
+    /// this must be called from within one.
+    pub fn auto_close_note_hook(&self) -> CloseHook {
+        let store = self.clone();
+        let runtime = tokio::runtime::Handle::current();
+        Arc::new(move |event: &AutoCloseEvent| {
+            let Some(session_id) = event.session_id.clone() else {
+                return;
+            };
+            let metrics = serde_json::to_string(&event.close.final_metrics)
+                .unwrap_or_else(|_| "null".to_string());
+            let note = format!(
+                "auto-closed {} ({}); final metrics: {}",
+                event.port_name,
+                event.close.reason.as_str(),
+                metrics
+            );
+            let store = store.clone();
+            runtime.spawn(async move {
+                if let Err(e) = store
+                    .append_message(&session_id, "system", None, &note, Some("auto_close"), None)
+                    .await
+                {
+                    tracing::warn!(session_id = %session_id, error = %e, "failed to record auto-close in session");
+                }
+            });
+        })
+    }
+
+    /// Reconnect up to `retries` times when an operation fails because the
+    /// database is unreachable, waiting `delay` before the first attempt and
+    /// doubling it each time. Query errors are never retried.
+    pub fn with_reconnect_policy(mut self, retries: u32, delay: Duration) -> Self {
+        self.reconnect_retries = retries;
+        self.reconnect_delay = delay;
+        self
+    }
+
+    fn pool(&self) -> SqlitePool {
+        self.pool
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    /// Open a fresh pool on the same URL and swap it in.
+    async fn reconnect(&self) -> sqlx::Result<()> {
+        let pool = SqlitePool::connect(&self.database_url).await?;
+        Self::run_migrations(&pool).await?;
+        *self.pool.write().unwrap_or_else(PoisonError::into_inner) = pool;
+        Ok(())
+    }
+
+    /// Run `op` on the current pool, reconnecting with backoff and running it
+    /// again after a connection-level error.
+    ///
+    /// `op` is retried as a whole, so multi-statement operations should run
+    /// in a transaction.
+    async fn with_reconnect<T, F, Fut>(&self, op: F) -> sqlx::Result<T>
+    where
+        F: Fn(SqlitePool) -> Fut,
+        Fut: Future<Output = sqlx::Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match op(self.pool()).await {
+                Err(e) if is_connection_error(&e) && attempt < self.reconnect_retries => {
+                    let delay = self
+                        .reconnect_delay
+                        .saturating_mul(2u32.saturating_pow(attempt));
+                    attempt += 1;
+                    tracing::warn!(
+                        error = %e,
+                        attempt,
+                        delay_ms = delay.as_millis() as u64,
+                        "Session database unreachable; reconnecting"
+                    );
+                    tokio::time::sleep(delay).await;
+                    if let Err(e) = self.reconnect().await {
+                        tracing::warn!(error = %e, "Session database reconnect failed");
+                    }
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Cap the stored `content` of each appended message at `max` bytes.
+    ///
+    /// Longer content is truncated (at a UTF-8 boundary, followed by a
+    /// `...[truncated N bytes]` marker, with the original length kept in
+    /// `original_length`) or rejected, per `oversize`. `None` disables the cap.
+    pub fn with_max_message_bytes(mut self, max: Option<usize>, oversize: MessageOversize) -> Self {
+        self.max_message_bytes = max;
+        self.oversize = oversize;
+        self
+    }
+
+    /// Replace every match of `patterns` in appended message content with
+    /// [`REDACTED`] before it is stored, counting matches in `redactions`.
+    ///
+    /// Patterns are applied in order, each to the output of the previous one.
+    pub fn with_redact_patterns(mut self, patterns: &[String]) -> Result<Self, regex::Error> {
+        let patterns = patterns
+            .iter()
+            .map(|p| Regex::new(p))
+            .collect::<Result<Vec<_>, _>>()?;
+        self.redact_patterns = Arc::new(patterns);
+        Ok(self)
+    }
+
+    /// Apply the redaction patterns to `content`, returning the redacted
+    /// content and the number of matches replaced.
+    fn redact<'a>(&self, content: &'a str) -> (Cow<'a, str>, i64) {
+        let mut content = Cow::Borrowed(content);
+        let mut redactions = 0;
+        for pattern in self.redact_patterns.iter() {
+            let matches = pattern.find_iter(&content).count();
+            if matches > 0 {
+                redactions += matches as i64;
+                content = Cow::Owned(pattern.replace_all(&content, REDACTED).into_owned());
+            }
+        }
+        (content, redactions)
+    }
+
+    /// Redact `content`, then apply the size cap.
+    ///
+    /// Returns the content to store, its original length if truncated, and
+    /// the redaction count.
+    fn prepare_content<'a>(
+        &self,
+        content: &'a str,
+    ) -> sqlx::Result<(Cow<'a, str>, Option<i64>, i64)> {
+        let (redacted, redactions) = self.redact(content);
+        let (content, original_length) = match redacted {
+            Cow::Borrowed(content) => self.limit_content(content)?,
+            Cow::Owned(content) => {
+                let (limited, original_length) = self.limit_content(&content)?;
+                (Cow::Owned(limited.into_owned()), original_length)
+            }
+        };
+        Ok((content, original_length, redactions))
+    }
+
+    /// Apply the message size cap to `content`.
+    ///
+    /// Returns the content to store and, if it was truncated, the original
+    /// byte length.
+    fn limit_content<'a>(&self, content: &'a str) -> sqlx::Result<(Cow<'a, str>, Option<i64>)> {
+        let max = match self.max_message_bytes {
+            Some(max) if content.len() > max => max,
+            _ => return Ok((Cow::Borrowed(content), None)),
+        };
+        if self.oversize == MessageOversize::Reject {
+            return Err(sqlx::Error::Protocol(format!(
+                "message content is {} bytes, over session.max_message_bytes ({})",
+                content.len(),
+                max
+            )));
+        }
+        let mut end = max;
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        let truncated = format!(
+            "{}...[truncated {} bytes]",
+            &content[..end],
+            content.len() - end
+        );
+        Ok((Cow::Owned(truncated), Some(content.len() as i64)))
     }
 
     async fn run_migrations(pool: &SqlitePool) -> sqlx::Result<()> {
@@ -57,7 +440,11 @@ impl SessionStore {
             port_name TEXT,
             created_at TEXT NOT NULL,
             updated_at TEXT NOT NULL,
-            closed INTEGER NOT NULL DEFAULT 0
+            closed INTEGER NOT NULL DEFAULT 0,
+            vid TEXT,
+            pid TEXT,
+            device_serial TEXT,
+            manufacturer TEXT
         )"#,
         )
         .execute(pool)
@@ -72,11 +459,15 @@ impl SessionStore {
             features TEXT,
             latency_ms INTEGER,
             created_at TEXT NOT NULL,
+            original_length INTEGER,
+            redactions INTEGER NOT NULL DEFAULT 0,
+            seq INTEGER NOT NULL DEFAULT 0,
             FOREIGN KEY(session_id) REFERENCES sessions(id)
         )"#,
         )
         .execute(pool)
         .await?;
+        Self::apply_schema_migrations(pool).await?;
         sqlx::query(r#"CREATE INDEX IF NOT EXISTS idx_messages_session ON messages(session_id)"#)
             .execute(pool)
             .await?;
@@ -85,16 +476,92 @@ impl SessionStore {
         )
         .execute(pool)
         .await?;
+        sqlx::query(
+            r#"CREATE INDEX IF NOT EXISTS idx_messages_session_order ON messages(session_id, created_at, seq)"#,
+        )
+        .execute(pool)
+        .await?;
         sqlx::query(r#"CREATE INDEX IF NOT EXISTS idx_messages_features ON messages(features)"#)
             .execute(pool)
             .await?;
         Ok(())
     }
 
+    /// Bring the schema up to [`SCHEMA_VERSION`], applying each migration
+    /// newer than the version recorded in `schema_version`.
+    ///
+    /// Databases from before versioning record no version, so every
+    /// migration is considered; columns they already have are left alone and
+    /// only the version is recorded.
+    async fn apply_schema_migrations(pool: &SqlitePool) -> sqlx::Result<()> {
+        sqlx::query(
+            r#"CREATE TABLE IF NOT EXISTS schema_version (
+            version INTEGER PRIMARY KEY,
+            description TEXT NOT NULL,
+            applied_at TEXT NOT NULL
+        )"#,
+        )
+        .execute(pool)
+        .await?;
+        let current: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM schema_version")
+            .fetch_one(pool)
+            .await?;
+        let current = current.unwrap_or(0);
+
+        for (version, migration) in (1i64..).zip(MIGRATIONS).skip(current.max(0) as usize) {
+            let mut tx = pool.begin().await?;
+            let mut added = false;
+            for &(table, column, definition) in migration.columns {
+                let exists: i64 = sqlx::query_scalar(
+                    "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+                )
+                .bind(table)
+                .bind(column)
+                .fetch_one(&mut *tx)
+                .await?;
+                if exists == 0 {
+                    sqlx::query(&format!(
+                        "ALTER TABLE {} ADD COLUMN {} {}",
+                        table, column, definition
+                    ))
+                    .execute(&mut *tx)
+                    .await?;
+                    added = true;
+                }
+            }
+            if let Some(backfill) = migration.backfill.filter(|_| added) {
+                sqlx::query(backfill).execute(&mut *tx).await?;
+            }
+            sqlx::query(
+                "INSERT OR IGNORE INTO schema_version (version, description, applied_at) VALUES (?1, ?2, ?3)",
+            )
+            .bind(version)
+            .bind(migration.description)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+            tx.commit().await?;
+            if added {
+                tracing::info!(
+                    version,
+                    migration = migration.description,
+                    "Applied session schema migration"
+                );
+            } else {
+                tracing::debug!(
+                    version,
+                    migration = migration.description,
+                    "Recorded session schema migration already in place"
+                );
+            }
+        }
+        Ok(())
+    }
+
     /// Explicit helper to idempotently create / migrate the database without keeping a pool instance.
     pub async fn ensure_database(database_url: &str) -> sqlx::Result<()> {
         let store = Self::new(database_url).await?;
-        let _ = store.pool.acquire().await?;
+        let _ = store.pool().acquire().await?;
         Ok(())
     }
 
@@ -102,12 +569,28 @@ impl SessionStore {
         &self,
         device_id: &str,
         port_name: Option<&str>,
+    ) -> sqlx::Result<Session> {
+        self.create_session_with_signature(device_id, port_name, DeviceSignature::default())
+            .await
+    }
+
+    /// [`create_session`](Self::create_session), recording the device's USB identity.
+    pub async fn create_session_with_signature(
+        &self,
+        device_id: &str,
+        port_name: Option<&str>,
+        signature: DeviceSignature,
     ) -> sqlx::Result<Session> {
         let now = Utc::now();
         let id = Uuid::new_v4().to_string();
-        sqlx::query("INSERT INTO sessions (id, device_id, port_name, created_at, updated_at, closed) VALUES (?1, ?2, ?3, ?4, ?5, 0)")
-            .bind(&id).bind(device_id).bind(port_name).bind(now).bind(now)
-            .execute(&self.pool).await?;
+        let (id_ref, sig) = (id.as_str(), &signature);
+        self.with_reconnect(|pool| async move {
+            sqlx::query("INSERT INTO sessions (id, device_id, port_name, created_at, updated_at, closed, vid, pid, device_serial, manufacturer) VALUES (?1, ?2, ?3, ?4, ?5, 0, ?6, ?7, ?8, ?9)")
+                .bind(id_ref).bind(device_id).bind(port_name).bind(now).bind(now)
+                .bind(&sig.vid).bind(&sig.pid).bind(&sig.device_serial).bind(&sig.manufacturer)
+                .execute(&pool).await
+        })
+        .await?;
         Ok(Session {
             id,
             device_id: device_id.to_string(),
@@ -115,14 +598,61 @@ impl SessionStore {
             created_at: now,
             updated_at: now,
             closed: 0,
+            signature,
         })
     }
 
     pub async fn get_session(&self, id: &str) -> sqlx::Result<Option<Session>> {
-        sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE id = ?1")
-            .bind(id)
-            .fetch_optional(&self.pool)
+        self.with_reconnect(|pool| async move {
+            sqlx::query_as::<_, Session>("SELECT * FROM sessions WHERE id = ?1")
+                .bind(id)
+                .fetch_optional(&pool)
+                .await
+        })
+        .await
+    }
+
+    /// Most recently created open (not closed) session for a device id.
+    pub async fn find_open_session_by_device(
+        &self,
+        device_id: &str,
+    ) -> sqlx::Result<Option<Session>> {
+        self.with_reconnect(|pool| async move {
+            sqlx::query_as::<_, Session>(
+                "SELECT * FROM sessions WHERE device_id = ?1 AND closed = 0 ORDER BY created_at DESC LIMIT 1",
+            )
+            .bind(device_id)
+            .fetch_optional(&pool)
             .await
+        })
+        .await
+    }
+
+    /// Return the open session for `device_id`, creating one if none exists.
+    ///
+    /// The existence check and insert happen in a single statement, so
+    /// concurrent callers for the same device get the same session. The
+    /// returned flag is `true` if a new session was created.
+    pub async fn get_or_create_session(
+        &self,
+        device_id: &str,
+        port_name: Option<&str>,
+    ) -> sqlx::Result<(Session, bool)> {
+        let now = Utc::now();
+        let id = Uuid::new_v4().to_string();
+        let id_ref = id.as_str();
+        let inserted = self.with_reconnect(|pool| async move {
+            sqlx::query("INSERT INTO sessions (id, device_id, port_name, created_at, updated_at, closed) SELECT ?1, ?2, ?3, ?4, ?5, 0 WHERE NOT EXISTS (SELECT 1 FROM sessions WHERE device_id = ?2 AND closed = 0)")
+                .bind(id_ref).bind(device_id).bind(port_name).bind(now).bind(now)
+                .execute(&pool).await
+        })
+        .await?
+        .rows_affected();
+        let session = self
+            .find_open_session_by_device(device_id)
+            .await?
+            .ok_or(sqlx::Error::RowNotFound)?;
+        Ok((session, inserted > 0))
     }
 
     /// List all sessions, optionally filtered by open/closed status.
@@ -140,9 +670,11 @@ impl SessionStore {
         if let Some(lim) = limit {
             sql.push_str(&format!(" LIMIT {}", lim));
         }
-        sqlx::query_as::<_, Session>(&sql)
-            .fetch_all(&self.pool)
-            .await
+        let sql = sql.as_str();
+        self.with_reconnect(|pool| async move {
+            sqlx::query_as::<_, Session>(sql).fetch_all(&pool).await
+        })
+        .await
     }
 
     pub async fn append_message(
@@ -154,35 +686,84 @@ impl SessionStore {
         features: Option<&str>,
         latency_ms: Option<i64>,
     ) -> sqlx::Result<(i64, DateTime<Utc>)> {
-        let now = Utc::now();
-        // Use a single connection so last_insert_rowid() is correct for the just-executed INSERT
-        let mut conn = self.pool.acquire().await?;
-        sqlx::query("INSERT INTO messages (session_id, role, direction, content, features, latency_ms, created_at) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)")
-            .bind(session_id).bind(role).bind(direction).bind(content).bind(features).bind(latency_ms).bind(now)
-            .execute(&mut *conn).await?;
-        let last_id: i64 = sqlx::query_scalar("SELECT last_insert_rowid()")
-            .fetch_one(&mut *conn)
-            .await?;
-        sqlx::query("UPDATE sessions SET updated_at = ?1 WHERE id = ?2")
-            .bind(now)
-            .bind(session_id)
-            .execute(&mut *conn)
-            .await?;
-        Ok((last_id, now))
+        let (content, original_length, redactions) = self.prepare_content(content)?;
+        let content = &*content;
+        let now = message_now();
+        // One transaction on one connection, so a retry after reconnecting
+        // cannot store the message twice
+        self.with_reconnect(|pool| async move {
+            let mut tx = pool.begin().await?;
+            let (last_id, created_at): (i64, DateTime<Utc>) = sqlx::query_as(&format!("INSERT INTO messages (session_id, role, direction, content, features, latency_ms, created_at, original_length, redactions, seq) VALUES (?1, ?2, ?3, ?4, ?5, ?6, {NEXT_CREATED_AT}, ?8, ?9, {NEXT_SEQ}) RETURNING id, created_at"))
+                .bind(session_id).bind(role).bind(direction).bind(content).bind(features).bind(latency_ms).bind(db_timestamp(now)).bind(original_length).bind(redactions)
+                .fetch_one(&mut *tx).await?;
+            sqlx::query("UPDATE sessions SET updated_at = ?1 WHERE id = ?2")
+                .bind(now)
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok((last_id, created_at))
+        })
+        .await
     }
 
+    /// Append `messages` to a session in a single transaction.
+    ///
+    /// Either every message is stored or none is. The insert statement is
+    /// prepared once and reused for each row. Returns the assigned ids in
+    /// input order; all messages share one `created_at`. With the `reject`
+    /// oversize policy, one oversized message fails the whole batch.
+    pub async fn append_messages(
+        &self,
+        session_id: &str,
+        messages: &[NewMessage],
+    ) -> sqlx::Result<Vec<i64>> {
+        let prepared = messages
+            .iter()
+            .map(|m| self.prepare_content(&m.content))
+            .collect::<sqlx::Result<Vec<_>>>()?;
+        let prepared = &prepared;
+        let now = message_now();
+        self.with_reconnect(|pool| async move {
+            let mut tx = pool.begin().await?;
+            let mut ids = Vec::with_capacity(messages.len());
+            for (m, (content, original_length, redactions)) in messages.iter().zip(prepared) {
+                let id: i64 = sqlx::query_scalar(&format!("INSERT INTO messages (session_id, role, direction, content, features, latency_ms, created_at, original_length, redactions, seq) VALUES (?1, ?2, ?3, ?4, ?5, ?6, {NEXT_CREATED_AT}, ?8, ?9, {NEXT_SEQ}) RETURNING id"))
+                    .bind(session_id).bind(&m.role).bind(&m.direction).bind(&**content).bind(&m.features).bind(m.latency_ms).bind(db_timestamp(now)).bind(*original_length).bind(*redactions)
+                    .fetch_one(&mut *tx).await?;
+                ids.push(id);
+            }
+            sqlx::query("UPDATE sessions SET updated_at = ?1 WHERE id = ?2")
+                .bind(now)
+                .bind(session_id)
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            Ok(ids)
+        })
+        .await
+    }
+
+    /// The first `limit` messages of a session, oldest first.
+    ///
+    /// Messages with the same `created_at` (e.g. from one
+    /// [`append_messages`](Self::append_messages) batch) keep insertion order.
     pub async fn list_messages(&self, session_id: &str, limit: i64) -> sqlx::Result<Vec<Message>> {
-        sqlx::query_as::<_, Message>(
-            "SELECT * FROM messages WHERE session_id = ?1 ORDER BY id ASC LIMIT ?2",
-        )
-        .bind(session_id)
-        .bind(limit)
-        .fetch_all(&self.pool)
+        self.with_reconnect(|pool| async move {
+            sqlx::query_as::<_, Message>(&format!(
+                "SELECT * FROM messages WHERE session_id = ?1 {MESSAGE_ORDER} LIMIT ?2"
+            ))
+            .bind(session_id)
+            .bind(limit)
+            .fetch_all(&pool)
+            .await
+        })
         .await
     }
 
     /// List messages with pagination support using cursor-based pagination.
-    /// Returns messages starting after the specified message ID.
+    /// Returns messages that come after the specified message ID in
+    /// [`list_messages`](Self::list_messages) order.
     pub async fn list_messages_range(
         &self,
         session_id: &str,
@@ -190,13 +771,16 @@ impl SessionStore {
         limit: i64,
     ) -> sqlx::Result<Vec<Message>> {
         let messages = if let Some(after_id) = start_after_id {
-            sqlx::query_as::<_, Message>(
-                "SELECT * FROM messages WHERE session_id = ?1 AND id > ?2 ORDER BY id ASC LIMIT ?3",
-            )
-            .bind(session_id)
-            .bind(after_id)
-            .bind(limit)
-            .fetch_all(&self.pool)
+            self.with_reconnect(|pool| async move {
+                sqlx::query_as::<_, Message>(&format!(
+                    "SELECT * FROM messages WHERE session_id = ?1 AND (created_at, seq) > (SELECT created_at, seq FROM messages WHERE id = ?2) {MESSAGE_ORDER} LIMIT ?3"
+                ))
+                .bind(session_id)
+                .bind(after_id)
+                .bind(limit)
+                .fetch_all(&pool)
+                .await
+            })
             .await?
         } else {
             // No cursor provided, start from beginning
@@ -225,31 +809,38 @@ impl SessionStore {
         if feature_substring.is_some() {
             sql.push_str(" AND features LIKE ?");
         }
-        sql.push_str(" ORDER BY id ASC LIMIT ?");
+        sql.push_str(&format!(" {MESSAGE_ORDER} LIMIT ?"));
 
-        let mut query = sqlx::query_as::<_, Message>(&sql).bind(session_id);
-        if let Some(r) = role {
-            query = query.bind(r);
-        }
-        if let Some(d) = direction {
-            query = query.bind(d);
-        }
-        if let Some(f) = feature_substring {
-            query = query.bind(format!("%{}%", f));
-        }
-        query = query.bind(limit);
-        query.fetch_all(&self.pool).await
+        let sql = sql.as_str();
+        self.with_reconnect(|pool| async move {
+            let mut query = sqlx::query_as::<_, Message>(sql).bind(session_id);
+            if let Some(r) = role {
+                query = query.bind(r);
+            }
+            if let Some(d) = direction {
+                query = query.bind(d);
+            }
+            if let Some(f) = feature_substring {
+                query = query.bind(format!("%{}%", f));
+            }
+            query = query.bind(limit);
+            query.fetch_all(&pool).await
+        })
+        .await
     }
 
     pub async fn export_features_index(&self, session_id: &str) -> sqlx::Result<serde_json::Value> {
         // Aggregate features into counts
-        let rows = sqlx::query(
-            "SELECT features FROM messages WHERE session_id = ?1 AND features IS NOT NULL",
-        )
-        .bind(session_id)
-        .fetch_all(&self.pool)
-        .await?;
-        use std::collections::HashMap;
+        let rows = self
+            .with_reconnect(|pool| async move {
+                sqlx::query(
+                    "SELECT features FROM messages WHERE session_id = ?1 AND features IS NOT NULL",
+                )
+                .bind(session_id)
+                .fetch_all(&pool)
+                .await
+            })
+            .await?;
         let mut counts: HashMap<String, u64> = HashMap::new();
         for row in rows {
             let fval: Option<String> = row.try_get("features").ok();
@@ -271,21 +862,29 @@ impl SessionStore {
         if feature_filter.is_some() {
             q.push_str(" AND features LIKE ?2");
         }
-        q.push_str(" ORDER BY id ASC");
-        let mut query = sqlx::query_as::<_, Message>(&q).bind(session_id);
-        if let Some(f) = feature_filter {
-            query = query.bind(format!("%{}%", f));
-        }
-        let msgs = query.fetch_all(&self.pool).await?;
+        q.push_str(&format!(" {MESSAGE_ORDER}"));
+        let q = q.as_str();
+        let msgs = self
+            .with_reconnect(|pool| async move {
+                let mut query = sqlx::query_as::<_, Message>(q).bind(session_id);
+                if let Some(f) = feature_filter {
+                    query = query.bind(format!("%{}%", f));
+                }
+                query.fetch_all(&pool).await
+            })
+            .await?;
         Ok(serde_json::json!({"session_id": session_id, "messages": msgs}))
     }
 
     pub async fn close_session(&self, session_id: &str) -> sqlx::Result<()> {
-        sqlx::query("UPDATE sessions SET closed = 1, updated_at = ?1 WHERE id = ?2")
-            .bind(Utc::now())
-            .bind(session_id)
-            .execute(&self.pool)
-            .await?;
+        self.with_reconnect(|pool| async move {
+            sqlx::query("UPDATE sessions SET closed = 1, updated_at = ?1 WHERE id = ?2")
+                .bind(Utc::now())
+                .bind(session_id)
+                .execute(&pool)
+                .await
+        })
+        .await?;
         Ok(())
     }
 
@@ -302,13 +901,68 @@ impl SessionStore {
         }
     }
 
+    /// Recreate a session from an [`export_session_json`](Self::export_session_json) document.
+    ///
+    /// The session is inserted under a new id, keeping its device, port,
+    /// timestamps and closed flag; messages are inserted in export order in a
+    /// single transaction. Returns the new session id.
+    ///
+    /// Malformed exports (missing `session`/`messages`, wrong field types, or
+    /// messages belonging to another session) are rejected with
+    /// `sqlx::Error::Decode` before anything is written.
+    pub async fn import_session(&self, export: &serde_json::Value) -> sqlx::Result<String> {
+        let malformed =
+            |msg: String| sqlx::Error::Decode(format!("invalid session export: {}", msg).into());
+        let session: Session = serde_json::from_value(
+            export
+                .get("session")
+                .cloned()
+                .ok_or_else(|| malformed("missing 'session'".into()))?,
+        )
+        .map_err(|e| malformed(format!("session: {}", e)))?;
+        let messages: Vec<Message> = serde_json::from_value(
+            export
+                .get("messages")
+                .cloned()
+                .ok_or_else(|| malformed("missing 'messages'".into()))?,
+        )
+        .map_err(|e| malformed(format!("messages: {}", e)))?;
+        if let Some(m) = messages.iter().find(|m| m.session_id != session.id) {
+            return Err(malformed(format!(
+                "message {} belongs to session {}, expected {}",
+                m.id, m.session_id, session.id
+            )));
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let (id_ref, session, messages) = (id.as_str(), &session, &messages);
+        self.with_reconnect(|pool| async move {
+            let mut tx = pool.begin().await?;
+            let sig = &session.signature;
+            sqlx::query("INSERT INTO sessions (id, device_id, port_name, created_at, updated_at, closed, vid, pid, device_serial, manufacturer) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)")
+                .bind(id_ref).bind(&session.device_id).bind(&session.port_name).bind(session.created_at).bind(session.updated_at).bind(session.closed)
+                .bind(&sig.vid).bind(&sig.pid).bind(&sig.device_serial).bind(&sig.manufacturer)
+                .execute(&mut *tx).await?;
+            for m in messages {
+                sqlx::query(&format!("INSERT INTO messages (session_id, role, direction, content, features, latency_ms, created_at, original_length, redactions, seq) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, {NEXT_SEQ})"))
+                    .bind(id_ref).bind(&m.role).bind(&m.direction).bind(&m.content).bind(&m.features).bind(m.latency_ms).bind(db_timestamp(m.created_at)).bind(m.original_length).bind(m.redactions)
+                    .execute(&mut *tx).await?;
+            }
+            tx.commit().await
+        })
+        .await?;
+        Ok(id)
+    }
+
     /// Lightweight stats for a session without pulling all messages.
     pub async fn session_stats(&self, session_id: &str) -> sqlx::Result<Option<serde_json::Value>> {
-        // Use a single connection/transaction-like sequence
-        let mut conn = self.pool.acquire().await?;
         // Count + last id + first timestamp + last timestamp
-        let row = sqlx::query("SELECT COUNT(*) as cnt, MAX(id) as last_id, MIN(created_at) as first_ts, MAX(created_at) as last_ts FROM messages WHERE session_id = ?1")
-            .bind(session_id).fetch_one(&mut *conn).await?;
+        let row = self
+            .with_reconnect(|pool| async move {
+                sqlx::query("SELECT COUNT(*) as cnt, MAX(id) as last_id, MIN(created_at) as first_ts, MAX(created_at) as last_ts FROM messages WHERE session_id = ?1")
+                    .bind(session_id).fetch_one(&pool).await
+            })
+            .await?;
         let count: i64 = row.try_get("cnt").unwrap_or(0);
         if count == 0 {
             return Ok(None);
@@ -340,6 +994,152 @@ fn ft_parse(s: &str) -> Result<DateTime<Utc>, chrono::ParseError> {
     s.parse::<DateTime<Utc>>()
 }
 
+/// How a pair of aligned messages differs in a [`SessionDiff`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageDiffKind {
+    /// Both sessions have the message but with different content
+    ContentMismatch,
+    /// Same content, but the latency changed by more than the threshold
+    Latency,
+    /// Only the expected session has the message
+    Missing,
+    /// Only the actual session has the message
+    Unexpected,
+}
+
+/// One difference found by [`diff_messages`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageDiff {
+    pub kind: MessageDiffKind,
+    pub role: String,
+    pub direction: Option<String>,
+    /// Position among the messages with this role and direction, from 0
+    pub index: usize,
+    pub expected_id: Option<i64>,
+    pub actual_id: Option<i64>,
+    pub expected_content: Option<String>,
+    pub actual_content: Option<String>,
+    /// Actual minus expected latency, when both were measured
+    pub latency_delta_ms: Option<i64>,
+}
+
+/// Result of comparing an expected (golden) session with an actual run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionDiff {
+    /// Aligned pairs with identical content
+    pub matched: usize,
+    /// Differences in conversation order
+    pub differences: Vec<MessageDiff>,
+    /// Largest absolute latency change over all aligned pairs
+    pub max_latency_delta_ms: Option<i64>,
+}
+
+/// Role, direction and index among the messages sharing both, for each message.
+fn stream_positions(messages: &[Message]) -> Vec<(&str, Option<&str>, usize)> {
+    let mut seen: HashMap<(&str, Option<&str>), usize> = HashMap::new();
+    messages
+        .iter()
+        .map(|m| {
+            let (role, direction) = (m.role.as_str(), m.direction.as_deref());
+            let n = seen.entry((role, direction)).or_default();
+            *n += 1;
+            (role, direction, *n - 1)
+        })
+        .collect()
+}
+
+/// Compare two sessions' messages, each in conversation order.
+///
+/// Messages are aligned by role and direction: the n-th `sent` command of one
+/// session is compared with the n-th of the other, and likewise for replies,
+/// so one extra reply does not shift every later command out of line. Pairs
+/// whose content matches are reported only if their latency changed by more
+/// than `latency_threshold_ms`.
+pub fn diff_messages(
+    expected: &[Message],
+    actual: &[Message],
+    latency_threshold_ms: Option<i64>,
+) -> SessionDiff {
+    let expected_keys = stream_positions(expected);
+    let actual_keys = stream_positions(actual);
+    let actual_at: HashMap<_, usize> = actual_keys
+        .iter()
+        .enumerate()
+        .map(|(i, k)| (*k, i))
+        .collect();
+
+    let mut diff = SessionDiff {
+        matched: 0,
+        differences: Vec::new(),
+        max_latency_delta_ms: None,
+    };
+    // Each difference with its position in its own session, to sort by
+    let mut ordered = Vec::new();
+    let mut paired = vec![false; actual.len()];
+    for (i, (m, k)) in expected.iter().zip(&expected_keys).enumerate() {
+        let other = actual_at.get(k).map(|&j| {
+            paired[j] = true;
+            &actual[j]
+        });
+        let latency_delta_ms = other.and_then(|o| Some(o.latency_ms? - m.latency_ms?));
+        if let Some(delta) = latency_delta_ms {
+            let max = diff.max_latency_delta_ms.get_or_insert(0);
+            if delta.abs() > max.abs() {
+                *max = delta;
+            }
+        }
+        let kind = match other {
+            None => Some(MessageDiffKind::Missing),
+            Some(o) if o.content != m.content => Some(MessageDiffKind::ContentMismatch),
+            Some(_) => {
+                diff.matched += 1;
+                latency_threshold_ms
+                    .zip(latency_delta_ms)
+                    .filter(|(limit, delta)| delta.abs() > *limit)
+                    .map(|_| MessageDiffKind::Latency)
+            }
+        };
+        if let Some(kind) = kind {
+            ordered.push((
+                i,
+                MessageDiff {
+                    kind,
+                    role: m.role.clone(),
+                    direction: m.direction.clone(),
+                    index: k.2,
+                    expected_id: Some(m.id),
+                    actual_id: other.map(|o| o.id),
+                    expected_content: Some(m.content.clone()),
+                    actual_content: other.map(|o| o.content.clone()),
+                    latency_delta_ms,
+                },
+            ));
+        }
+    }
+    for (j, (m, k)) in actual.iter().zip(&actual_keys).enumerate() {
+        if !paired[j] {
+            ordered.push((
+                j,
+                MessageDiff {
+                    kind: MessageDiffKind::Unexpected,
+                    role: m.role.clone(),
+                    direction: m.direction.clone(),
+                    index: k.2,
+                    expected_id: None,
+                    actual_id: Some(m.id),
+                    expected_content: None,
+                    actual_content: Some(m.content.clone()),
+                    latency_delta_ms: None,
+                },
+            ));
+        }
+    }
+    ordered.sort_by_key(|(position, _)| *position);
+    diff.differences = ordered.into_iter().map(|(_, d)| d).collect();
+    diff
+}
+
 // MCP integration will wrap this store (future tools: create_session, resume_session, append_message, export_session)
 
 #[cfg(test)]
@@ -368,6 +1168,417 @@ mod tests {
         assert_eq!(fetched.closed, 0);
     }
 
+    #[tokio::test]
+    async fn device_signature_round_trips() {
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+        let signature = DeviceSignature {
+            vid: Some("0x2341".into()),
+            pid: Some("0x0043".into()),
+            device_serial: Some("75735303331351F0E0A1".into()),
+            manufacturer: Some("Arduino LLC".into()),
+        };
+        let s = store
+            .create_session_with_signature("uno", Some("arduino"), signature.clone())
+            .await
+            .expect("create");
+        assert_eq!(s.signature, signature);
+
+        let fetched = store
+            .get_session(&s.id)
+            .await
+            .expect("get")
+            .expect("exists");
+        assert_eq!(fetched.signature, signature);
+        let listed = store.list_sessions(true, None).await.expect("list");
+        let listed = listed.iter().find(|l| l.id == s.id).expect("listed");
+        assert_eq!(listed.signature, signature);
+        let json = serde_json::to_value(&fetched).unwrap();
+        assert_eq!(json["vid"], "0x2341");
+        assert_eq!(json["device_serial"], "75735303331351F0E0A1");
+
+        let plain = store.create_session("plain", None).await.expect("create");
+        let plain = store
+            .get_session(&plain.id)
+            .await
+            .expect("get")
+            .expect("exists");
+        assert!(plain.signature.is_empty());
+    }
+
+    #[tokio::test]
+    async fn signature_columns_added_to_existing_database() {
+        let dir = tempfile::tempdir().unwrap();
+        let url = format!("sqlite://{}", dir.path().join("old.db").display());
+        let old = SqlitePool::connect(&format!("{}?mode=rwc", url))
+            .await
+            .expect("create old db");
+        sqlx::query("CREATE TABLE sessions (id TEXT PRIMARY KEY, device_id TEXT NOT NULL, port_name TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, closed INTEGER NOT NULL DEFAULT 0)")
+            .execute(&old)
+            .await
+            .expect("old schema");
+        old.close().await;
+
+        let store = SessionStore::new(&url).await.expect("migrate");
+        let signature = DeviceSignature {
+            vid: Some("0x0403".into()),
+            ..Default::default()
+        };
+        let s = store
+            .create_session_with_signature("ftdi", None, signature.clone())
+            .await
+            .expect("create");
+        let fetched = store
+            .get_session(&s.id)
+            .await
+            .expect("get")
+            .expect("exists");
+        assert_eq!(fetched.signature, signature);
+        // Migrating again is a no-op
+        SessionStore::new(&url).await.expect("re-open");
+    }
+
+    #[tokio::test]
+    async fn migrations_upgrade_older_schema() {
+        let pool = SqlitePool::connect(memory_db()).await.expect("connect");
+        // Schema from before message redaction, ordering and device signatures
+        sqlx::query("CREATE TABLE sessions (id TEXT PRIMARY KEY, device_id TEXT NOT NULL, port_name TEXT, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, closed INTEGER NOT NULL DEFAULT 0)")
+            .execute(&pool)
+            .await
+            .expect("old sessions");
+        sqlx::query("CREATE TABLE messages (id INTEGER PRIMARY KEY AUTOINCREMENT, session_id TEXT NOT NULL, role TEXT NOT NULL, direction TEXT, content TEXT NOT NULL, features TEXT, latency_ms INTEGER, created_at TEXT NOT NULL, original_length INTEGER)")
+            .execute(&pool)
+            .await
+            .expect("old messages");
+        sqlx::query(
+            "INSERT INTO sessions VALUES ('s1', 'dev', NULL, '2024-01-01', '2024-01-01', 0)",
+        )
+        .execute(&pool)
+        .await
+        .expect("old session");
+        sqlx::query("INSERT INTO messages (session_id, role, content, created_at) VALUES ('s1', 'user', 'a', '2024-01-01'), ('s1', 'user', 'b', '2024-01-01')")
+            .execute(&pool)
+            .await
+            .expect("old messages");
+
+        SessionStore::run_migrations(&pool).await.expect("migrate");
+        let columns: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM pragma_table_info('messages') UNION ALL SELECT name FROM pragma_table_info('sessions')",
+        )
+        .fetch_all(&pool)
+        .await
+        .expect("columns");
+        for column in [
+            "redactions",
+            "seq",
+            "vid",
+            "pid",
+            "device_serial",
+            "manufacturer",
+        ] {
+            assert!(columns.iter().any(|c| c == column), "missing {column}");
+        }
+        let seqs: Vec<i64> = sqlx::query_scalar("SELECT seq FROM messages ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .expect("seq");
+        assert_eq!(seqs, [1, 2]);
+
+        // Every version is recorded once, and migrating again changes nothing
+        SessionStore::run_migrations(&pool).await.expect("re-run");
+        let versions: Vec<i64> =
+            sqlx::query_scalar("SELECT version FROM schema_version ORDER BY version")
+                .fetch_all(&pool)
+                .await
+                .expect("versions");
+        assert_eq!(versions, (1..=SCHEMA_VERSION).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn append_messages_in_one_batch() {
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+        let s = store.create_session("bulk", None).await.expect("create");
+        let batch: Vec<NewMessage> = (0..500)
+            .map(|i| NewMessage {
+                role: "device".into(),
+                content: format!("line {}", i),
+                direction: Some("received".into()),
+                features: None,
+                latency_ms: Some(i),
+            })
+            .collect();
+
+        let ids = store.append_messages(&s.id, &batch).await.expect("batch");
+        assert_eq!(ids.len(), 500);
+        assert!(ids.windows(2).all(|w| w[0] < w[1]));
+        let stored = store.list_messages(&s.id, 1000).await.expect("list");
+        assert_eq!(stored.len(), 500);
+        for (i, (m, id)) in stored.iter().zip(&ids).enumerate() {
+            assert_eq!(m.id, *id);
+            assert_eq!(m.content, format!("line {}", i));
+        }
+
+        // Unknown session violates the foreign key; nothing is stored
+        let err = store.append_messages("missing", &batch[..2]).await;
+        assert!(err.is_err());
+        assert_eq!(store.list_messages("missing", 10).await.unwrap().len(), 0);
+    }
+
+    #[tokio::test]
+    async fn every_listing_uses_the_same_message_order() {
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+        let s = store.create_session("order", None).await.expect("create");
+        let mut ids = Vec::new();
+        for content in ["a", "b", "c"] {
+            let (id, _) = store
+                .append_message(
+                    &s.id,
+                    "device",
+                    Some("received"),
+                    content,
+                    Some("tag"),
+                    None,
+                )
+                .await
+                .expect("append");
+            ids.push(id);
+        }
+        // As after importing a transcript: the first row inserted is the latest
+        sqlx::query("UPDATE messages SET created_at = ?1 WHERE id = ?2")
+            .bind(db_timestamp(Utc::now() + chrono::Duration::hours(1)))
+            .bind(ids[0])
+            .execute(&store.pool())
+            .await
+            .expect("backdate");
+
+        let contents =
+            |msgs: &[Message]| msgs.iter().map(|m| m.content.clone()).collect::<Vec<_>>();
+        let listed = store.list_messages(&s.id, 10).await.expect("list");
+        assert_eq!(contents(&listed), ["b", "c", "a"]);
+        let first_page = store
+            .list_messages_range(&s.id, None, 2)
+            .await
+            .expect("page");
+        assert_eq!(contents(&first_page), ["b", "c"]);
+        let next_page = store
+            .list_messages_range(&s.id, Some(first_page[1].id), 2)
+            .await
+            .expect("page");
+        assert_eq!(contents(&next_page), ["a"]);
+        let filtered = store
+            .filter_messages(&s.id, Some("device"), Some("tag"), None, 10)
+            .await
+            .expect("filter");
+        assert_eq!(contents(&filtered), ["b", "c", "a"]);
+        let exported = store
+            .export_messages_with_features(&s.id, Some("tag"))
+            .await
+            .expect("export");
+        let exported: Vec<_> = exported["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["content"].as_str().unwrap())
+            .collect();
+        assert_eq!(exported, ["b", "c", "a"]);
+    }
+
+    #[tokio::test]
+    async fn messages_in_the_same_millisecond_keep_insertion_order() {
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+        let s = store.create_session("fast", None).await.expect("create");
+        // One batch shares a single created_at
+        let batch: Vec<NewMessage> = (0..5)
+            .map(|i| NewMessage {
+                role: "device".into(),
+                content: format!("burst {}", i),
+                direction: Some("received".into()),
+                features: None,
+                latency_ms: None,
+            })
+            .collect();
+        store.append_messages(&s.id, &batch).await.expect("batch");
+        let (_, ts) = store
+            .append_message(&s.id, "user", Some("sent"), "after", None, None)
+            .await
+            .expect("append");
+
+        let stored = store.list_messages(&s.id, 10).await.expect("list");
+        let contents: Vec<_> = stored.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(
+            contents,
+            ["burst 0", "burst 1", "burst 2", "burst 3", "burst 4", "after"]
+        );
+        assert!(stored[..5]
+            .iter()
+            .all(|m| m.created_at == stored[0].created_at));
+        assert!(stored.windows(2).all(|w| w[0].seq < w[1].seq));
+        assert_eq!(stored[5].created_at, ts);
+
+        // Stored with microseconds, fixed width
+        let raw: String = sqlx::query_scalar("SELECT created_at FROM messages WHERE id = ?1")
+            .bind(stored[5].id)
+            .fetch_one(&store.pool())
+            .await
+            .expect("raw timestamp");
+        assert_eq!(raw, db_timestamp(ts));
+        assert_eq!(raw.len(), "2024-01-01T00:00:00.000000Z".len());
+    }
+
+    #[tokio::test]
+    async fn diff_pinpoints_the_one_diverging_reply() {
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+        let mut ids = Vec::new();
+        for reply in ["OK", "ERROR"] {
+            let s = store.create_session("golden", None).await.expect("create");
+            let messages = [
+                ("user", "sent", "AT", None),
+                ("device", "received", "OK", Some(10)),
+                ("user", "sent", "AT+GMR", None),
+                ("device", "received", reply, Some(12)),
+                ("user", "sent", "AT+RST", None),
+                ("device", "received", "OK", Some(40)),
+            ];
+            for (role, direction, content, latency) in messages {
+                store
+                    .append_message(&s.id, role, Some(direction), content, None, latency)
+                    .await
+                    .expect("append");
+            }
+            ids.push(s.id);
+        }
+        let expected = store.list_messages(&ids[0], 100).await.expect("list");
+        let mut actual = store.list_messages(&ids[1], 100).await.expect("list");
+        actual[5].latency_ms = Some(95);
+
+        let diff = diff_messages(&expected, &actual, None);
+        assert_eq!(diff.matched, 5);
+        assert_eq!(diff.max_latency_delta_ms, Some(55));
+        let [only] = diff.differences.as_slice() else {
+            panic!("expected one difference: {:?}", diff.differences);
+        };
+        assert_eq!(only.kind, MessageDiffKind::ContentMismatch);
+        assert_eq!((only.role.as_str(), only.index), ("device", 1));
+        assert_eq!(only.expected_id, Some(expected[3].id));
+        assert_eq!(only.actual_id, Some(actual[3].id));
+        assert_eq!(only.expected_content.as_deref(), Some("OK"));
+        assert_eq!(only.actual_content.as_deref(), Some("ERROR"));
+        assert_eq!(only.latency_delta_ms, Some(0));
+
+        // Slow replies are reported once past the threshold
+        let diff = diff_messages(&expected, &actual, Some(50));
+        assert_eq!(diff.differences.len(), 2);
+        assert_eq!(diff.differences[1].kind, MessageDiffKind::Latency);
+        assert_eq!(diff.differences[1].actual_id, Some(actual[5].id));
+
+        // An extra reply is unexpected without shifting the later commands
+        let mut extra = actual.clone();
+        extra.insert(2, actual[1].clone());
+        let diff = diff_messages(&expected, &extra, None);
+        let kinds: Vec<_> = diff.differences.iter().map(|d| d.kind).collect();
+        assert!(!kinds.contains(&MessageDiffKind::Missing));
+        assert!(kinds.contains(&MessageDiffKind::Unexpected));
+        assert!(diff
+            .differences
+            .iter()
+            .all(|d| d.role == "device" && d.direction.as_deref() == Some("received")));
+    }
+
+    #[tokio::test]
+    async fn oversized_message_is_truncated_with_original_length() {
+        let store = SessionStore::new(memory_db())
+            .await
+            .expect("init store")
+            .with_max_message_bytes(Some(8), MessageOversize::Truncate);
+        let s = store.create_session("chatty", None).await.expect("create");
+        let long = "0123456789abcdef";
+
+        store
+            .append_message(&s.id, "device", Some("received"), long, None, None)
+            .await
+            .expect("append");
+        store
+            .append_messages(
+                &s.id,
+                &[NewMessage {
+                    role: "device".into(),
+                    content: "short".into(),
+                    direction: None,
+                    features: None,
+                    latency_ms: None,
+                }],
+            )
+            .await
+            .expect("batch");
+
+        let stored = store.list_messages(&s.id, 10).await.expect("list");
+        assert_eq!(stored[0].content, "01234567...[truncated 8 bytes]");
+        assert_eq!(stored[0].original_length, Some(16));
+        assert_eq!(stored[1].content, "short");
+        assert_eq!(stored[1].original_length, None);
+
+        let strict = store
+            .clone()
+            .with_max_message_bytes(Some(8), MessageOversize::Reject);
+        let err = strict
+            .append_message(&s.id, "device", None, long, None, None)
+            .await
+            .expect_err("rejected");
+        assert!(matches!(err, sqlx::Error::Protocol(_)), "{:?}", err);
+        assert_eq!(store.list_messages(&s.id, 10).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn configured_patterns_are_redacted_before_storage() {
+        let store = SessionStore::new(memory_db())
+            .await
+            .expect("init store")
+            .with_redact_patterns(&[r"(?i)password=\S+".into(), r"AT\+KEY=\w+".into()])
+            .expect("valid patterns");
+        let s = store.create_session("secret", None).await.expect("create");
+
+        store
+            .append_message(
+                &s.id,
+                "user",
+                Some("sent"),
+                "login admin PASSWORD=hunter2 then AT+KEY=abc123",
+                None,
+                None,
+            )
+            .await
+            .expect("append");
+        store
+            .append_messages(
+                &s.id,
+                &[NewMessage {
+                    role: "device".into(),
+                    content: "OK password=x password=y".into(),
+                    direction: Some("received".into()),
+                    features: None,
+                    latency_ms: None,
+                }],
+            )
+            .await
+            .expect("batch");
+        store
+            .append_message(&s.id, "device", None, "AT+GMR", None, None)
+            .await
+            .expect("append plain");
+
+        let stored = store.list_messages(&s.id, 10).await.expect("list");
+        assert_eq!(stored[0].content, "login admin *** then ***");
+        assert_eq!(stored[0].redactions, 2);
+        assert_eq!(stored[1].content, "OK *** ***");
+        assert_eq!(stored[1].redactions, 2);
+        assert_eq!(stored[2].content, "AT+GMR");
+        assert_eq!(stored[2].redactions, 0);
+
+        assert!(store
+            .clone()
+            .with_redact_patterns(&["(unclosed".into()])
+            .is_err());
+    }
+
     #[tokio::test]
     async fn append_list_filter_and_feature_index() {
         let store = SessionStore::new(memory_db()).await.expect("init store");
@@ -451,4 +1662,213 @@ mod tests {
             1
         );
     }
+
+    #[tokio::test]
+    async fn export_import_round_trip() {
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+        let s = store
+            .create_session("devRT", Some("COM7"))
+            .await
+            .expect("create");
+        store
+            .append_message(&s.id, "user", Some("sent"), "AT", Some("command"), None)
+            .await
+            .expect("append1");
+        store
+            .append_message(&s.id, "device", Some("received"), "OK", None, Some(8))
+            .await
+            .expect("append2");
+        let export = store.export_session_json(&s.id).await.expect("export");
+
+        let new_id = store.import_session(&export).await.expect("import");
+        assert_ne!(new_id, s.id);
+        let imported = store
+            .get_session(&new_id)
+            .await
+            .expect("get")
+            .expect("exists");
+        assert_eq!(imported.device_id, "devRT");
+        assert_eq!(imported.port_name.as_deref(), Some("COM7"));
+
+        let original = store.list_messages(&s.id, 100).await.expect("list");
+        let restored = store.list_messages(&new_id, 100).await.expect("list");
+        assert_eq!(restored.len(), original.len());
+        for (a, b) in original.iter().zip(&restored) {
+            assert_eq!(b.session_id, new_id);
+            assert_eq!(a.role, b.role);
+            assert_eq!(a.direction, b.direction);
+            assert_eq!(a.content, b.content);
+            assert_eq!(a.features, b.features);
+            assert_eq!(a.latency_ms, b.latency_ms);
+            assert_eq!(a.created_at, b.created_at);
+        }
+    }
+
+    #[tokio::test]
+    async fn import_rejects_malformed_export() {
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+
+        for bad in [
+            serde_json::json!({"error": "not found"}),
+            serde_json::json!({"session": {"id": "x"}, "messages": []}),
+            serde_json::json!({"session": null, "messages": "nope"}),
+        ] {
+            let err = store.import_session(&bad).await.expect_err("rejected");
+            assert!(matches!(err, sqlx::Error::Decode(_)), "{:?}", err);
+        }
+    }
+
+    #[tokio::test]
+    async fn get_or_create_reuses_open_session() {
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+        let (first, created) = store
+            .get_or_create_session("devUnique", Some("COM3"))
+            .await
+            .expect("first");
+        assert!(created);
+        let (second, created) = store
+            .get_or_create_session("devUnique", None)
+            .await
+            .expect("second");
+        assert!(!created);
+        assert_eq!(first.id, second.id);
+
+        // A closed session is not reused
+        store.close_session(&first.id).await.expect("close");
+        assert!(store
+            .find_open_session_by_device("devUnique")
+            .await
+            .expect("find")
+            .is_none());
+        let (third, created) = store
+            .get_or_create_session("devUnique", None)
+            .await
+            .expect("third");
+        assert!(created);
+        assert_ne!(third.id, first.id);
+    }
+
+    #[tokio::test]
+    async fn reconnects_after_connection_loss() {
+        let store = SessionStore::new(memory_db())
+            .await
+            .expect("init store")
+            .with_reconnect_policy(0, Duration::from_millis(1));
+        store.pool().close().await;
+        let err = store.create_session("dev", None).await.unwrap_err();
+        assert!(matches!(err, sqlx::Error::PoolClosed), "{err:?}");
+
+        let store = store.with_reconnect_policy(2, Duration::from_millis(1));
+        let s = store
+            .create_session("dev", None)
+            .await
+            .expect("create after reconnect");
+        assert!(store.get_session(&s.id).await.expect("get").is_some());
+    }
+
+    #[tokio::test]
+    async fn connect_retries_until_database_path_appears() {
+        let dir = tempfile::tempdir().unwrap();
+        // A file where the database directory should be makes opening fail
+        let blocker = dir.path().join("data");
+        std::fs::write(&blocker, b"").unwrap();
+        let url = format!(
+            "sqlite://{}?mode=rwc",
+            blocker.join("sessions.db").display()
+        );
+        assert!(
+            SessionStore::connect_with_retry(&url, 0, Duration::from_millis(1))
+                .await
+                .is_err()
+        );
+
+        let unblock = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            std::fs::remove_file(&blocker).unwrap();
+        });
+        let store = SessionStore::connect_with_retry(&url, 8, Duration::from_millis(20))
+            .await
+            .expect("open once the path is available");
+        unblock.await.unwrap();
+        let s = store.create_session("dev", None).await.expect("create");
+        assert!(store.get_session(&s.id).await.expect("get").is_some());
+    }
+
+    #[tokio::test]
+    async fn idle_close_of_linked_port_is_noted_in_session() {
+        use crate::port::{MockSerialPort, SerialPortAdapter};
+        use crate::service::{PortOpener, PortService};
+
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+        let session = store.create_session("dev", None).await.expect("create");
+        // Nothing arrives in time, so a read times out as on a silent device
+        let opener: PortOpener = Arc::new(|name: &str, config| {
+            let mut port = MockSerialPort::new(name);
+            port.set_timeout(config.timeout)?;
+            port.enqueue_read_after(Duration::from_secs(60), b"late");
+            Ok(Box::new(port) as crate::state::PortAdapter)
+        });
+        let state: crate::state::AppState = Default::default();
+        let service = PortService::new(state)
+            .with_port_opener(opener)
+            .with_close_hook(store.auto_close_note_hook());
+        service
+            .open(
+                serde_json::from_value(serde_json::json!({
+                    "port_name": "MOCK0",
+                    "timeout_ms": 10,
+                    "idle_disconnect_ms": 1
+                }))
+                .unwrap(),
+            )
+            .expect("open");
+        service.link_session(Some(session.id.clone())).unwrap();
+
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let read = service.read_bytes_n(16).expect("read");
+        assert!(read.auto_closed.is_some());
+
+        // The note is written by a spawned task
+        let mut notes = Vec::new();
+        for _ in 0..100 {
+            notes = store.list_messages(&session.id, 10).await.expect("list");
+            if !notes.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        assert_eq!(notes.len(), 1);
+        assert_eq!(notes[0].role, "system");
+        assert_eq!(notes[0].features.as_deref(), Some("auto_close"));
+        assert!(
+            notes[0]
+                .content
+                .starts_with("auto-closed MOCK0 (idle_timeout); final metrics: {"),
+            "{}",
+            notes[0].content
+        );
+        assert!(notes[0].content.contains("\"idle_close_count\":1"));
+    }
+
+    #[tokio::test]
+    async fn query_errors_are_not_retried() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let store = SessionStore::new(memory_db()).await.expect("init store");
+        let calls = AtomicUsize::new(0);
+        let calls = &calls;
+        let err = store
+            .with_reconnect(|pool| async move {
+                calls.fetch_add(1, Ordering::SeqCst);
+                sqlx::query("SELECT * FROM no_such_table")
+                    .execute(&pool)
+                    .await
+            })
+            .await
+            .unwrap_err();
+        assert!(!is_connection_error(&err));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(is_connection_error(&sqlx::Error::PoolClosed));
+        assert!(!is_connection_error(&sqlx::Error::RowNotFound));
+    }
 }
@@ -1,13 +1,35 @@
+use chrono::{DateTime, Utc};
 use rust_mcp_sdk::macros::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::sync::{Arc, Mutex};
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
 
 use crate::port::SerialPortAdapter;
 
 /// A type alias for the shared, thread-safe application state.
 pub type AppState = Arc<Mutex<PortState>>;
 
+/// Lock the shared port state, recovering from a poisoned mutex.
+///
+/// A poisoned lock means the previous holder panicked mid-operation, so the
+/// port may be in an unknown state. Rather than failing every later call, the
+/// guard is taken back, the port is reset to `Closed` (dropping the adapter),
+/// and the poison flag is cleared so the server stays usable.
+pub fn lock_state(state: &AppState) -> MutexGuard<'_, PortState> {
+    match state.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => {
+            tracing::warn!("Port state lock was poisoned; resetting port to Closed");
+            let mut guard = poisoned.into_inner();
+            *guard = PortState::Closed;
+            state.clear_poison();
+            guard
+        }
+    }
+}
+
 /// Type alias for the port adapter used in PortState.
 /// This abstraction enables:
 /// - Dependency injection for testing (MockSerialPort)
@@ -23,11 +45,13 @@ pub struct PortConfig {
     pub baud_rate: u32,
     #[serde(default = "default_timeout")]
     pub timeout_ms: u64,
-    #[serde(default = "default_data_bits")]
+    /// Serialized as the number of bits; the names are also accepted
+    #[serde(default = "default_data_bits", with = "canonical_data_bits")]
     pub data_bits: DataBitsCfg,
     #[serde(default = "default_parity")]
     pub parity: ParityCfg,
-    #[serde(default = "default_stop_bits")]
+    /// Serialized as 1, 1.5 or 2; the names are also accepted
+    #[serde(default = "default_stop_bits", with = "canonical_stop_bits")]
     pub stop_bits: StopBitsCfg,
     #[serde(default = "default_flow_control")]
     pub flow_control: FlowControlCfg,
@@ -35,6 +59,35 @@ pub struct PortConfig {
     pub terminator: Option<String>,
     #[serde(default)]
     pub idle_disconnect_ms: Option<u64>,
+    #[serde(default)]
+    pub read_idle_gap_ms: Option<u64>,
+    #[serde(default)]
+    pub write_checksum: Option<ChecksumKind>,
+    /// Consume the local echo of each write (RS-485 half-duplex)
+    #[serde(default)]
+    pub half_duplex_echo: bool,
+    /// Block until each write has been transmitted
+    #[serde(default)]
+    pub flush_on_write: bool,
+    /// Infer `terminator` from the line endings of the first reads
+    #[serde(default)]
+    pub auto_detect_terminator: bool,
+    /// Clear the high bit of received bytes, which carries parity with 7 data
+    /// bits; defaults to on for `data_bits: seven`, off otherwise
+    #[serde(default)]
+    pub mask_parity_bit: Option<bool>,
+    /// Remove the shell's echo of the last write from the next read
+    #[serde(default)]
+    pub strip_echo: bool,
+    /// Shell prompt (e.g. `"> "`) removed from the end of read data
+    #[serde(default)]
+    pub strip_prompt: Option<String>,
+    /// Close the port once it has been open this long, whatever its activity
+    #[serde(default)]
+    pub max_open_duration_ms: Option<u64>,
+    /// How `read` and `read_line` wait for incoming bytes
+    #[serde(default)]
+    pub read_strategy: ReadStrategy,
 }
 
 // Default configuration constants
@@ -82,7 +135,8 @@ pub fn default_reconfig_baud() -> u32 {
     DEFAULT_RECONFIG_BAUD_RATE
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, JsonSchema)]
+/// Data bits; the JSON schema is written by hand (see [`DataBitsCfg::json_schema`])
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum DataBitsCfg {
     Five,
@@ -99,10 +153,14 @@ pub enum ParityCfg {
     Even,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, JsonSchema)]
+/// Stop bits; the JSON schema is written by hand (see [`StopBitsCfg::json_schema`])
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 #[serde(rename_all = "snake_case")]
 pub enum StopBitsCfg {
     One,
+    /// 1.5 stop bits; `"1.5"` is accepted as an alias
+    #[serde(rename = "one_point_five", alias = "1.5")]
+    OnedotFive,
     Two,
 }
 
@@ -114,10 +172,713 @@ pub enum FlowControlCfg {
     Software,
 }
 
+// Canonical forms used in responses: data and stop bits as numbers, parity
+// and flow control as their snake_case names.
+
+impl DataBitsCfg {
+    /// Number of data bits (5-8).
+    pub fn bits(self) -> u8 {
+        match self {
+            Self::Five => 5,
+            Self::Six => 6,
+            Self::Seven => 7,
+            Self::Eight => 8,
+        }
+    }
+
+    fn from_bits(bits: u64) -> Option<Self> {
+        match bits {
+            5 => Some(Self::Five),
+            6 => Some(Self::Six),
+            7 => Some(Self::Seven),
+            8 => Some(Self::Eight),
+            _ => None,
+        }
+    }
+
+    /// `5`, `6`, `7` or `8`.
+    pub fn canonical(self) -> serde_json::Value {
+        self.bits().into()
+    }
+
+    /// The bit count responses report, or a name as also accepted.
+    pub fn json_schema() -> serde_json::Map<String, serde_json::Value> {
+        numeric_setting_schema(
+            "integer",
+            serde_json::json!([5, 6, 7, 8]),
+            &["five", "six", "seven", "eight"],
+        )
+    }
+}
+
+impl ParityCfg {
+    /// `"none"`, `"odd"` or `"even"`, as in requests.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Odd => "odd",
+            Self::Even => "even",
+        }
+    }
+
+    pub fn canonical(self) -> serde_json::Value {
+        self.as_str().into()
+    }
+}
+
+impl StopBitsCfg {
+    /// `1`, `1.5` or `2`.
+    pub fn canonical(self) -> serde_json::Value {
+        match self {
+            Self::One => 1.into(),
+            Self::OnedotFive => 1.5.into(),
+            Self::Two => 2.into(),
+        }
+    }
+
+    /// The stop-bit count responses report, or a name as also accepted.
+    pub fn json_schema() -> serde_json::Map<String, serde_json::Value> {
+        numeric_setting_schema(
+            "number",
+            serde_json::json!([1, 1.5, 2]),
+            &["one", "one_point_five", "two"],
+        )
+    }
+
+    fn from_count(count: f64) -> Option<Self> {
+        if count == 1.0 {
+            Some(Self::One)
+        } else if count == 1.5 {
+            Some(Self::OnedotFive)
+        } else if count == 2.0 {
+            Some(Self::Two)
+        } else {
+            None
+        }
+    }
+}
+
+impl FlowControlCfg {
+    /// `"none"`, `"hardware"` or `"software"`, as in requests; `rtscts` and
+    /// `xonxoff` are input aliases only.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Hardware => "hardware",
+            Self::Software => "software",
+        }
+    }
+
+    pub fn canonical(self) -> serde_json::Value {
+        self.as_str().into()
+    }
+}
+
+impl From<crate::port::DataBits> for DataBitsCfg {
+    fn from(bits: crate::port::DataBits) -> Self {
+        match bits {
+            crate::port::DataBits::Five => Self::Five,
+            crate::port::DataBits::Six => Self::Six,
+            crate::port::DataBits::Seven => Self::Seven,
+            crate::port::DataBits::Eight => Self::Eight,
+        }
+    }
+}
+
+impl From<crate::port::Parity> for ParityCfg {
+    fn from(parity: crate::port::Parity) -> Self {
+        match parity {
+            crate::port::Parity::None => Self::None,
+            crate::port::Parity::Odd => Self::Odd,
+            crate::port::Parity::Even => Self::Even,
+        }
+    }
+}
+
+impl From<crate::port::StopBits> for StopBitsCfg {
+    fn from(bits: crate::port::StopBits) -> Self {
+        match bits {
+            crate::port::StopBits::One => Self::One,
+            crate::port::StopBits::OnedotFive => Self::OnedotFive,
+            crate::port::StopBits::Two => Self::Two,
+        }
+    }
+}
+
+impl From<crate::port::FlowControl> for FlowControlCfg {
+    fn from(flow: crate::port::FlowControl) -> Self {
+        match flow {
+            crate::port::FlowControl::None => Self::None,
+            crate::port::FlowControl::Hardware => Self::Hardware,
+            crate::port::FlowControl::Software => Self::Software,
+        }
+    }
+}
+
+/// Canonical line settings, as the `data_bits`/`parity`/`stop_bits`/
+/// `flow_control` fields of a response.
+pub fn line_settings_json(
+    data_bits: DataBitsCfg,
+    parity: ParityCfg,
+    stop_bits: StopBitsCfg,
+    flow_control: FlowControlCfg,
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut fields = serde_json::Map::new();
+    fields.insert("data_bits".into(), data_bits.canonical());
+    fields.insert("parity".into(), parity.canonical());
+    fields.insert("stop_bits".into(), stop_bits.canonical());
+    fields.insert("flow_control".into(), flow_control.canonical());
+    fields
+}
+
+/// Schema of a line setting reported as a number: one of `counts`, or one of
+/// `names`. Shaped like the derived enum schemas.
+fn numeric_setting_schema(
+    number_type: &str,
+    counts: serde_json::Value,
+    names: &[&str],
+) -> serde_json::Map<String, serde_json::Value> {
+    let mut schema = serde_json::Map::new();
+    schema.insert(
+        "oneOf".to_string(),
+        serde_json::json!([
+            {"type": number_type, "enum": counts},
+            {"type": "string", "enum": names},
+        ]),
+    );
+    schema
+}
+
+/// `PortConfig.data_bits` as a number, read back from a number or a name.
+mod canonical_data_bits {
+    use super::DataBitsCfg;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bits: &DataBitsCfg, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_u8(bits.bits())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<DataBitsCfg, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Bits(u64),
+            Name(DataBitsCfg),
+        }
+        match Repr::deserialize(d)
+            .map_err(|_| D::Error::custom("data_bits must be 5-8 or five/six/seven/eight"))?
+        {
+            Repr::Name(bits) => Ok(bits),
+            Repr::Bits(n) => DataBitsCfg::from_bits(n)
+                .ok_or_else(|| D::Error::custom(format!("data_bits must be 5-8, got {n}"))),
+        }
+    }
+}
+
+/// `PortConfig.stop_bits` as a number, read back from a number or a name.
+mod canonical_stop_bits {
+    use super::StopBitsCfg;
+    use serde::{de::Error, Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(bits: &StopBitsCfg, s: S) -> Result<S::Ok, S::Error> {
+        bits.canonical().serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<StopBitsCfg, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Count(f64),
+            Name(StopBitsCfg),
+        }
+        match Repr::deserialize(d)
+            .map_err(|_| D::Error::custom("stop_bits must be 1, 1.5, 2 or one/1.5/two"))?
+        {
+            Repr::Name(bits) => Ok(bits),
+            Repr::Count(n) => StopBitsCfg::from_count(n)
+                .ok_or_else(|| D::Error::custom(format!("stop_bits must be 1, 1.5 or 2, got {n}"))),
+        }
+    }
+}
+
+/// How read data is returned to the caller.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadEncoding {
+    /// Lossy UTF-8 text (invalid sequences become U+FFFD)
+    #[default]
+    Utf8,
+    /// Standard base64 of the raw bytes
+    Base64,
+    /// `hexdump -C` style lines (see [`crate::port::hexdump`])
+    Hexdump,
+}
+
+/// How the `data` of a write is turned into bytes.
+///
+/// Never guessed from the data: text that happens to be valid base64 is
+/// still sent as text unless the caller asks otherwise.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WriteEncoding {
+    /// UTF-8 text, sent as is
+    #[default]
+    Text,
+    /// Standard base64 of the raw bytes
+    Base64,
+    /// Hex digits, two per byte; whitespace between bytes is ignored
+    Hex,
+}
+
+impl WriteEncoding {
+    /// Decode `data` into the bytes to send.
+    pub fn decode(self, data: &str) -> Result<Vec<u8>, String> {
+        match self {
+            Self::Text => Ok(data.as_bytes().to_vec()),
+            Self::Base64 => {
+                use base64::Engine;
+                base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .map_err(|e| format!("data is not valid base64: {e}"))
+            }
+            Self::Hex => {
+                let digits: Vec<u8> = data.bytes().filter(|b| !b.is_ascii_whitespace()).collect();
+                if digits.len() % 2 != 0 {
+                    return Err("hex data has an odd number of digits".to_string());
+                }
+                digits
+                    .chunks(2)
+                    .map(|pair| {
+                        let text = String::from_utf8_lossy(pair);
+                        if !pair.iter().all(u8::is_ascii_hexdigit) {
+                            return Err(format!("invalid hex byte '{text}'"));
+                        }
+                        Ok(u8::from_str_radix(&text, 16).expect("two hex digits"))
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Text clean-up applied to UTF-8 read data after the terminator is stripped.
+///
+/// Steps run in field order: escape sequences first (they start with a
+/// control byte), then remaining control bytes, then surrounding whitespace.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+pub struct ReadTransform {
+    /// Remove ANSI escape sequences (colors, cursor movement, OSC titles)
+    #[serde(default)]
+    pub strip_ansi: bool,
+    /// Remove control characters other than `\n` and `\t`
+    #[serde(default)]
+    pub strip_control: bool,
+    /// Remove leading and trailing whitespace, including stray CR/LF
+    #[serde(default)]
+    pub trim: bool,
+}
+
+impl ReadTransform {
+    /// Whether no step is enabled.
+    pub fn is_identity(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Apply the enabled steps to `text`.
+    pub fn apply(&self, text: &str) -> String {
+        let mut out = if self.strip_ansi {
+            strip_ansi_escapes(text)
+        } else {
+            text.to_string()
+        };
+        if self.strip_control {
+            out.retain(|c| !c.is_control() || c == '\n' || c == '\t');
+        }
+        if self.trim {
+            out = out.trim().to_string();
+        }
+        out
+    }
+}
+
+/// Remove ANSI escape sequences from `text`.
+///
+/// Handles CSI (`ESC [` or the 8-bit `0x9B`, up to a final byte in `@`..`~`),
+/// OSC (`ESC ]`, up to BEL or `ESC \`), `ESC` + intermediates + final byte,
+/// and two-character escapes.
+fn strip_ansi_escapes(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    let skip_csi = |chars: &mut std::iter::Peekable<std::str::Chars<'_>>| {
+        for c in chars.by_ref() {
+            if ('@'..='~').contains(&c) {
+                break;
+            }
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '\u{1b}' => match chars.next() {
+                Some('[') => skip_csi(&mut chars),
+                Some(']') => {
+                    while let Some(c) = chars.next() {
+                        if c == '\u{7}' {
+                            break;
+                        }
+                        if c == '\u{1b}' {
+                            chars.next_if_eq(&'\\');
+                            break;
+                        }
+                    }
+                }
+                Some(c) if (' '..='/').contains(&c) => {
+                    while chars.next_if(|c| (' '..='/').contains(c)).is_some() {}
+                    chars.next();
+                }
+                _ => {}
+            },
+            '\u{9b}' => skip_csi(&mut chars),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// How reads wait for the first bytes to arrive.
+///
+/// `Blocking` sleeps in the driver until data or the port timeout, costing no
+/// CPU while idle. `Poll` checks the driver's pending byte count every
+/// `interval_ms` and reads the moment it is nonzero, trading a busy thread
+/// for lower latency on quick request/response exchanges. Ports that cannot
+/// report pending bytes fall back to blocking.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReadStrategy {
+    #[default]
+    Blocking,
+    Poll {
+        #[serde(default = "default_poll_interval_ms")]
+        interval_ms: u64,
+    },
+}
+
+/// Default [`ReadStrategy::Poll`] interval (1 ms).
+pub fn default_poll_interval_ms() -> u64 {
+    1
+}
+
+/// Checksum appended to every write when configured on a port.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChecksumKind {
+    /// CRC-16/MODBUS (poly 0xA001 reflected, init 0xFFFF), low byte first
+    Crc16Modbus,
+    /// CRC-8/SMBUS (poly 0x07, init 0x00)
+    Crc8,
+    /// XOR of all bytes
+    Xor8,
+    /// Sum of all bytes modulo 256
+    Sum8,
+}
+
+impl ChecksumKind {
+    /// Compute the checksum bytes for `data` in transmission order.
+    pub fn compute(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Crc16Modbus => {
+                let mut crc: u16 = 0xFFFF;
+                for &b in data {
+                    crc ^= b as u16;
+                    for _ in 0..8 {
+                        crc = if crc & 1 != 0 {
+                            (crc >> 1) ^ 0xA001
+                        } else {
+                            crc >> 1
+                        };
+                    }
+                }
+                crc.to_le_bytes().to_vec()
+            }
+            Self::Crc8 => {
+                let mut crc: u8 = 0;
+                for &b in data {
+                    crc ^= b;
+                    for _ in 0..8 {
+                        crc = if crc & 0x80 != 0 {
+                            (crc << 1) ^ 0x07
+                        } else {
+                            crc << 1
+                        };
+                    }
+                }
+                vec![crc]
+            }
+            Self::Xor8 => vec![data.iter().fold(0u8, |acc, &b| acc ^ b)],
+            Self::Sum8 => vec![data.iter().fold(0u8, |acc, &b| acc.wrapping_add(b))],
+        }
+    }
+}
+
+/// One step of a DTR/RTS sequence: set the given lines, then wait `hold_ms`.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+pub struct SignalStep {
+    #[serde(default)]
+    pub dtr: Option<bool>,
+    #[serde(default)]
+    pub rts: Option<bool>,
+    #[serde(default)]
+    pub hold_ms: u64,
+}
+
+impl SignalStep {
+    const fn new(dtr: bool, rts: bool, hold_ms: u64) -> Self {
+        Self {
+            dtr: Some(dtr),
+            rts: Some(rts),
+            hold_ms,
+        }
+    }
+}
+
+/// Board whose auto-reset circuit a reset sequence targets.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ResetBoard {
+    /// DTR drives RESET through a capacitor (Uno, Nano, Mega): the
+    /// deasserted-to-asserted edge resets the board
+    Arduino,
+    /// RTS drives EN and DTR drives GPIO0 (esptool wiring): pulse EN low with
+    /// GPIO0 high to reboot into the application
+    Esp32,
+    /// Same auto-reset wiring as [`ResetBoard::Esp32`] (RTS to RST)
+    Esp8266,
+    /// Caller-supplied steps
+    Custom,
+}
+
+/// Payload written right after opening, which the device must answer within
+/// `timeout_ms` for the open to succeed.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, JsonSchema)]
+pub struct ResponseProbe {
+    /// Bytes to send, written as given (no terminator is appended)
+    pub probe: String,
+    #[serde(default = "default_probe_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+pub fn default_probe_timeout_ms() -> u64 {
+    500
+}
+
+impl ResetBoard {
+    /// The canonical sequence for a preset; empty for [`ResetBoard::Custom`].
+    pub fn steps(self) -> Vec<SignalStep> {
+        match self {
+            Self::Arduino => vec![
+                SignalStep::new(false, false, 250),
+                SignalStep::new(true, true, 50),
+                SignalStep::new(false, false, 0),
+            ],
+            Self::Esp32 | Self::Esp8266 => vec![
+                SignalStep::new(false, true, 100),
+                SignalStep::new(false, false, 0),
+            ],
+            Self::Custom => Vec::new(),
+        }
+    }
+}
+
+impl PortConfig {
+    /// Clear the high (parity) bit of received bytes before decoding, per
+    /// `mask_parity_bit` or, when unset, whenever 7 data bits are configured.
+    pub fn mask_received(&self, bytes: &mut [u8]) {
+        let mask = self
+            .mask_parity_bit
+            .unwrap_or(matches!(self.data_bits, DataBitsCfg::Seven));
+        if mask {
+            for b in bytes {
+                *b &= 0x7f;
+            }
+        }
+    }
+
+    /// `data` without a trailing configured terminator.
+    pub fn write_payload<'a>(&self, data: &'a [u8]) -> &'a [u8] {
+        match &self.terminator {
+            Some(term) if !term.is_empty() => data.strip_suffix(term.as_bytes()).unwrap_or(data),
+            _ => data,
+        }
+    }
+
+    /// Build the bytes to transmit for `data`.
+    ///
+    /// Any trailing terminator on `data` is set aside, the configured checksum
+    /// is appended to the payload, and the terminator (if configured) is added
+    /// last. Returns the bytes to write and the checksum, if any.
+    pub fn encode_write(&self, data: impl AsRef<[u8]>) -> (Vec<u8>, Option<Vec<u8>>) {
+        let payload = self.write_payload(data.as_ref());
+        let mut bytes = payload.to_vec();
+        let checksum = self.write_checksum.map(|kind| kind.compute(payload));
+        if let Some(sum) = &checksum {
+            bytes.extend_from_slice(sum);
+        }
+        if let Some(term) = &self.terminator {
+            bytes.extend_from_slice(term.as_bytes());
+        }
+        (bytes, checksum)
+    }
+
+    /// Remove interactive-shell noise from read `text`.
+    ///
+    /// With `strip_echo`, a leading copy of `echo` (the last payload written)
+    /// is removed along with the line ending the shell sent after it. With
+    /// `strip_prompt`, the prompt is removed from the end, followed by the
+    /// configured terminator that preceded it.
+    ///
+    /// Returns the cleaned text and the part of `echo` still expected, when
+    /// `text` holds only its start.
+    pub fn strip_shell_noise(&self, text: &str, echo: Option<&str>) -> (String, Option<String>) {
+        let mut out = text;
+        let mut pending = None;
+        if let Some(echo) = echo.filter(|e| self.strip_echo && !e.is_empty()) {
+            if let Some(rest) = out.strip_prefix(echo) {
+                out = ["\r\n", "\n", "\r"]
+                    .iter()
+                    .find_map(|eol| rest.strip_prefix(eol))
+                    .unwrap_or(rest);
+            } else if let Some(rest) = echo.strip_prefix(out) {
+                pending = Some(rest.to_string());
+                out = "";
+            }
+        }
+        if let Some(prompt) = self.strip_prompt.as_deref().filter(|p| !p.is_empty()) {
+            if let Some(rest) = out
+                .strip_suffix(prompt)
+                .or_else(|| out.strip_suffix(prompt.trim_end()))
+            {
+                out = match self.terminator.as_deref().filter(|t| !t.is_empty()) {
+                    Some(term) => rest.trim_end_matches(term),
+                    None => rest,
+                };
+            }
+        }
+        (out.to_string(), pending)
+    }
+
+    /// Line settings as `key=value` pairs, for session transcripts.
+    pub fn summary(&self) -> String {
+        format!(
+            "baud_rate={} data_bits={} parity={} stop_bits={} flow_control={}",
+            self.baud_rate,
+            self.data_bits.canonical(),
+            self.parity.as_str(),
+            self.stop_bits.canonical(),
+            self.flow_control.as_str()
+        )
+    }
+
+    /// Overwrite the fields set in `patch`, leaving the rest unchanged.
+    ///
+    /// An `idle_disconnect_ms` of 0 is stored as `None` (disabled), as on open.
+    pub fn apply_patch(&mut self, patch: PortConfigPatch) {
+        if let Some(v) = patch.baud_rate {
+            self.baud_rate = v;
+        }
+        if let Some(v) = patch.timeout_ms {
+            self.timeout_ms = v;
+        }
+        if let Some(v) = patch.data_bits {
+            self.data_bits = v;
+        }
+        if let Some(v) = patch.parity {
+            self.parity = v;
+        }
+        if let Some(v) = patch.stop_bits {
+            self.stop_bits = v;
+        }
+        if let Some(v) = patch.flow_control {
+            self.flow_control = v;
+        }
+        if let Some(v) = patch.terminator {
+            self.terminator = v;
+        }
+        if let Some(v) = patch.idle_disconnect_ms {
+            self.idle_disconnect_ms = v.filter(|&ms| ms > 0);
+        }
+        if let Some(v) = patch.read_idle_gap_ms {
+            self.read_idle_gap_ms = v;
+        }
+        if let Some(v) = patch.write_checksum {
+            self.write_checksum = v;
+        }
+        if let Some(v) = patch.half_duplex_echo {
+            self.half_duplex_echo = v;
+        }
+        if let Some(v) = patch.flush_on_write {
+            self.flush_on_write = v;
+        }
+    }
+}
+
+/// Partial update of a [`PortConfig`]; `None` leaves a field unchanged.
+///
+/// The nullable settings take `Some(None)` (JSON `null`) to clear them.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct PortConfigPatch {
+    #[serde(default)]
+    pub baud_rate: Option<u32>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default)]
+    pub data_bits: Option<DataBitsCfg>,
+    #[serde(default)]
+    pub parity: Option<ParityCfg>,
+    #[serde(default)]
+    pub stop_bits: Option<StopBitsCfg>,
+    #[serde(default)]
+    pub flow_control: Option<FlowControlCfg>,
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub terminator: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub idle_disconnect_ms: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub read_idle_gap_ms: Option<Option<u64>>,
+    #[serde(default, deserialize_with = "present_or_null")]
+    pub write_checksum: Option<Option<ChecksumKind>>,
+    #[serde(default)]
+    pub half_duplex_echo: Option<bool>,
+    #[serde(default)]
+    pub flush_on_write: Option<bool>,
+}
+
+impl PortConfigPatch {
+    /// Whether applying the patch changes line settings, which only take
+    /// effect when the port is reopened.
+    pub fn requires_reopen(&self) -> bool {
+        self.baud_rate.is_some()
+            || self.data_bits.is_some()
+            || self.parity.is_some()
+            || self.stop_bits.is_some()
+            || self.flow_control.is_some()
+    }
+}
+
+/// Deserialize a present field (including `null`) as `Some`, so an absent
+/// field (`None` via `#[serde(default)]`) can be told apart from a cleared one.
+fn present_or_null<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
 /// Represents the current state of the serial port.
 #[derive(Serialize, Debug)]
 #[serde(tag = "status", content = "details")]
 #[derive(Default)]
+// Only a single PortState exists per server, so the size of `Open` is irrelevant.
+#[allow(clippy::large_enum_variant)]
 pub enum PortState {
     #[default]
     Closed,
@@ -139,6 +900,480 @@ pub enum PortState {
         #[serde(skip_serializing)]
         idle_close_count: u64,
         #[serde(skip_serializing)]
+        framing_errors: u64,
+        /// Received bytes kept for the next read: the unterminated line left
+        /// after a `read_line` or protocol command took a complete one, bytes
+        /// beyond a read's limit, or a partial UTF-8 character.
+        #[serde(skip_serializing)]
+        frame_buffer: Vec<u8>,
+        #[serde(skip_serializing)]
         open_started: Instant,
+        /// Wall-clock time of `open_started`, captured once at open so later
+        /// system clock changes do not skew timestamps derived from it.
+        #[serde(skip_serializing)]
+        open_started_at: DateTime<Utc>,
+        /// Correlation id for this open-port lifecycle, new on every open.
+        #[serde(skip_serializing)]
+        open_id: Uuid,
+        /// Rate limiter for warnings about consecutive read timeouts.
+        #[serde(skip_serializing)]
+        timeout_log: TimeoutLogThrottle,
+        /// Set from another caller to interrupt a long-running read loop.
+        #[serde(skip_serializing)]
+        cancel: CancelToken,
+        /// Session this port's traffic belongs to, kept across reconfigure.
+        #[serde(skip_serializing)]
+        active_session: Option<String>,
+        /// Line-ending sampler, present until terminator detection finishes.
+        #[serde(skip_serializing)]
+        terminator_detect: Option<TerminatorDetector>,
+        /// Last payload written, until stripped from a read (`strip_echo`).
+        #[serde(skip_serializing)]
+        pending_echo: Option<String>,
     },
 }
+
+/// Convert a monotonic `at` to wall-clock time using an anchor pair
+/// (`anchor`, `anchor_at`) captured together.
+///
+/// Only the monotonic offset from the anchor is applied, so the result stays
+/// consistent with elapsed durations even if the system clock moves.
+pub fn wall_clock_at(anchor: Instant, anchor_at: DateTime<Utc>, at: Instant) -> DateTime<Utc> {
+    let offset = |d: Duration| chrono::Duration::from_std(d).unwrap_or_default();
+    match at.checked_duration_since(anchor) {
+        Some(after) => anchor_at + offset(after),
+        None => anchor_at - offset(anchor.duration_since(at)),
+    }
+}
+
+/// Flag used to interrupt long-running reads from another caller.
+///
+/// Read loops hold the state lock while they run, so the flag is shared
+/// through an `Arc` and can be set without taking that lock.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    /// Request cancellation of the read in progress.
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether cancellation has been requested since the last reset.
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+
+    /// Clear a pending request; done when a new read starts.
+    pub fn reset(&self) {
+        self.0.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Tracks MCP tool calls for the inactivity watchdog.
+///
+/// Shared by every clone of a service. A call in progress never counts as
+/// inactivity; the timer restarts when the last running call finishes.
+#[derive(Debug, Clone)]
+pub struct ControlActivity(Arc<Mutex<ControlActivityInner>>);
+
+#[derive(Debug)]
+struct ControlActivityInner {
+    last: Instant,
+    in_flight: usize,
+}
+
+impl Default for ControlActivity {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(ControlActivityInner {
+            last: Instant::now(),
+            in_flight: 0,
+        })))
+    }
+}
+
+impl ControlActivity {
+    fn inner(&self) -> MutexGuard<'_, ControlActivityInner> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Mark a call as started; it ends when the returned guard is dropped.
+    pub fn begin(&self) -> ControlCallGuard {
+        let mut inner = self.inner();
+        inner.in_flight += 1;
+        inner.last = Instant::now();
+        ControlCallGuard(self.clone())
+    }
+
+    /// Time since the last call finished, or `None` while one is running.
+    pub fn idle_for(&self) -> Option<Duration> {
+        let inner = self.inner();
+        (inner.in_flight == 0).then(|| inner.last.elapsed())
+    }
+}
+
+/// A running tool call, returned by [`ControlActivity::begin`].
+#[derive(Debug)]
+pub struct ControlCallGuard(ControlActivity);
+
+impl Drop for ControlCallGuard {
+    fn drop(&mut self) {
+        let mut inner = self.0.inner();
+        inner.in_flight = inner.in_flight.saturating_sub(1);
+        inner.last = Instant::now();
+    }
+}
+
+/// Rate limiter for "read timed out" warnings on a silent port.
+///
+/// A silent device times out on every poll; logging each one floods the
+/// logs. The throttle lets one warning through per interval and counts the
+/// timeouts it suppressed in between.
+#[derive(Debug, Clone, Default)]
+pub struct TimeoutLogThrottle {
+    last_warned: Option<Instant>,
+    suppressed: u64,
+}
+
+impl TimeoutLogThrottle {
+    /// Record a timeout. Returns `Some(suppressed)` when a warning should be
+    /// emitted, carrying the number of timeouts suppressed since the last one.
+    pub fn record(&mut self, interval: Duration) -> Option<u64> {
+        match self.last_warned {
+            Some(at) if at.elapsed() < interval => {
+                self.suppressed += 1;
+                None
+            }
+            _ => {
+                self.last_warned = Some(Instant::now());
+                Some(std::mem::take(&mut self.suppressed))
+            }
+        }
+    }
+
+    /// Forget the streak once data arrives, so the next timeout warns at once.
+    pub fn reset(&mut self) {
+        self.last_warned = None;
+        self.suppressed = 0;
+    }
+
+    /// Timeouts suppressed since the last warning.
+    pub fn suppressed(&self) -> u64 {
+        self.suppressed
+    }
+}
+
+/// Non-empty reads sampled before terminator detection gives up.
+pub const TERMINATOR_DETECT_MAX_READS: u32 = 8;
+
+/// Line endings to see before terminator detection settles early.
+const TERMINATOR_DETECT_MIN_LINES: u32 = 3;
+
+/// Infers a device's line ending from the first data it sends.
+///
+/// Counts `\r\n`, `\n` and lone `\r` endings across reads (a `\r\n` split
+/// between two reads still counts once) until enough lines were seen or the
+/// read window is used up.
+#[derive(Debug, Clone, Default)]
+pub struct TerminatorDetector {
+    reads: u32,
+    crlf: u32,
+    lf: u32,
+    cr: u32,
+    pending_cr: bool,
+}
+
+impl TerminatorDetector {
+    /// Sample one read. Returns `true` once detection is finished.
+    pub fn observe(&mut self, bytes: &[u8]) -> bool {
+        if bytes.is_empty() {
+            return false;
+        }
+        self.reads += 1;
+        for &b in bytes {
+            match b {
+                b'\n' if self.pending_cr => {
+                    self.crlf += 1;
+                    self.pending_cr = false;
+                }
+                b'\n' => self.lf += 1,
+                b'\r' => {
+                    if self.pending_cr {
+                        self.cr += 1;
+                    }
+                    self.pending_cr = true;
+                }
+                _ if self.pending_cr => {
+                    self.cr += 1;
+                    self.pending_cr = false;
+                }
+                _ => {}
+            }
+        }
+        self.crlf + self.lf + self.cr >= TERMINATOR_DETECT_MIN_LINES
+            || self.reads >= TERMINATOR_DETECT_MAX_READS
+    }
+
+    /// The most frequent line ending so far, preferring `\r\n`, then `\n`,
+    /// on ties; `None` if no line ending was seen.
+    pub fn detected(&self) -> Option<&'static str> {
+        // `max_by_key` keeps the last of equal maxima, so list in reverse preference
+        [("\r", self.cr), ("\n", self.lf), ("\r\n", self.crlf)]
+            .into_iter()
+            .filter(|(_, count)| *count > 0)
+            .max_by_key(|(_, count)| *count)
+            .map(|(term, _)| term)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_transform_strips_ansi() {
+        let colored = "\u{1b}[1;32mOK\u{1b}[0m \u{1b}]0;title\u{7}ready\u{1b}(B\u{1b}7!\r\n";
+        let ansi = ReadTransform {
+            strip_ansi: true,
+            ..Default::default()
+        };
+        assert_eq!(ansi.apply(colored), "OK ready!\r\n");
+        let all = ReadTransform { trim: true, ..ansi };
+        assert_eq!(all.apply(colored), "OK ready!");
+        // ST-terminated OSC and 8-bit CSI
+        assert_eq!(ansi.apply("a\u{1b}]8;;x\u{1b}\\b\u{9b}2Kc"), "abc");
+    }
+
+    #[test]
+    fn test_read_transform_strips_control_bytes() {
+        let noisy = "\0\u{2}TEMP=21.5\u{8}\r\n\tRH=40\u{7f}\r\n";
+        let control = ReadTransform {
+            strip_control: true,
+            ..Default::default()
+        };
+        assert_eq!(control.apply(noisy), "TEMP=21.5\n\tRH=40\n");
+        let trimmed = ReadTransform {
+            trim: true,
+            ..control
+        };
+        assert_eq!(trimmed.apply(noisy), "TEMP=21.5\n\tRH=40");
+        assert!(ReadTransform::default().is_identity());
+        assert_eq!(ReadTransform::default().apply(noisy), noisy);
+    }
+
+    #[test]
+    fn test_stop_bits_serde_round_trip() {
+        for (bits, name) in [
+            (StopBitsCfg::One, "one"),
+            (StopBitsCfg::OnedotFive, "one_point_five"),
+            (StopBitsCfg::Two, "two"),
+        ] {
+            let json = serde_json::to_value(bits).unwrap();
+            assert_eq!(json, name);
+            let back: StopBitsCfg = serde_json::from_value(json).unwrap();
+            assert_eq!(format!("{:?}", back), format!("{:?}", bits));
+        }
+        let alias: StopBitsCfg = serde_json::from_str("\"1.5\"").unwrap();
+        assert!(matches!(alias, StopBitsCfg::OnedotFive));
+    }
+
+    #[test]
+    fn test_line_settings_schema_covers_the_numbers_reported() {
+        let mut config = config_with(None, None);
+        config.stop_bits = StopBitsCfg::OnedotFive;
+        let json = serde_json::to_value(&config).unwrap();
+        let schema = PortConfig::json_schema();
+        for key in ["data_bits", "stop_bits"] {
+            let variants = schema["properties"][key]["oneOf"].as_array().unwrap();
+            assert!(
+                variants
+                    .iter()
+                    .any(|v| v["enum"].as_array().unwrap().contains(&json[key])),
+                "{key}={} missing from its schema",
+                json[key]
+            );
+        }
+    }
+
+    #[test]
+    fn test_port_config_line_settings_serialize_canonically() {
+        let mut config = config_with(None, None);
+        config.data_bits = DataBitsCfg::Seven;
+        config.stop_bits = StopBitsCfg::OnedotFive;
+        config.flow_control = FlowControlCfg::Hardware;
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["data_bits"], 7);
+        assert_eq!(json["stop_bits"], 1.5);
+        assert_eq!(json["parity"], "none");
+        assert_eq!(json["flow_control"], "hardware");
+        assert_eq!(
+            config.summary(),
+            "baud_rate=9600 data_bits=7 parity=none stop_bits=1.5 flow_control=hardware"
+        );
+
+        // Canonical values and names both read back
+        let back: PortConfig = serde_json::from_value(json).unwrap();
+        assert!(matches!(back.data_bits, DataBitsCfg::Seven));
+        assert!(matches!(back.stop_bits, StopBitsCfg::OnedotFive));
+        let named: PortConfig = serde_json::from_value(serde_json::json!({
+            "port_name": "MOCK0",
+            "data_bits": "six",
+            "stop_bits": "two",
+        }))
+        .unwrap();
+        assert!(matches!(named.data_bits, DataBitsCfg::Six));
+        assert!(matches!(named.stop_bits, StopBitsCfg::Two));
+        for bad in [
+            serde_json::json!({"port_name": "MOCK0", "data_bits": 9}),
+            serde_json::json!({"port_name": "MOCK0", "stop_bits": 3}),
+        ] {
+            assert!(serde_json::from_value::<PortConfig>(bad).is_err());
+        }
+    }
+
+    fn config_with(terminator: Option<&str>, checksum: Option<ChecksumKind>) -> PortConfig {
+        PortConfig {
+            port_name: "MOCK0".to_string(),
+            baud_rate: default_baud(),
+            timeout_ms: default_timeout(),
+            data_bits: default_data_bits(),
+            parity: default_parity(),
+            stop_bits: default_stop_bits(),
+            flow_control: default_flow_control(),
+            terminator: terminator.map(str::to_string),
+            idle_disconnect_ms: None,
+            read_idle_gap_ms: None,
+            write_checksum: checksum,
+            half_duplex_echo: false,
+            flush_on_write: false,
+            auto_detect_terminator: false,
+            mask_parity_bit: None,
+            strip_echo: false,
+            strip_prompt: None,
+            max_open_duration_ms: None,
+            read_strategy: ReadStrategy::Blocking,
+        }
+    }
+
+    #[test]
+    fn test_checksum_known_values() {
+        let data = b"123456789";
+        assert_eq!(ChecksumKind::Crc16Modbus.compute(data), vec![0x37, 0x4B]);
+        assert_eq!(ChecksumKind::Crc8.compute(data), vec![0xF4]);
+        assert_eq!(ChecksumKind::Xor8.compute(data), vec![0x31]);
+        assert_eq!(ChecksumKind::Sum8.compute(data), vec![0xDD]);
+    }
+
+    #[test]
+    fn test_crc16_modbus_frame() {
+        // Read holding registers: slave 1, addr 0, count 1
+        let frame = [0x01, 0x03, 0x00, 0x00, 0x00, 0x01];
+        assert_eq!(ChecksumKind::Crc16Modbus.compute(&frame), vec![0x84, 0x0A]);
+    }
+
+    #[test]
+    fn test_encode_write_checksum_before_terminator() {
+        let config = config_with(Some("\r\n"), Some(ChecksumKind::Sum8));
+        let (bytes, checksum) = config.encode_write("AB");
+        assert_eq!(checksum, Some(vec![0x83]));
+        assert_eq!(bytes, vec![b'A', b'B', 0x83, b'\r', b'\n']);
+
+        // A terminator already present on the input is not checksummed
+        let (bytes, _) = config.encode_write("AB\r\n");
+        assert_eq!(bytes, vec![b'A', b'B', 0x83, b'\r', b'\n']);
+    }
+
+    #[test]
+    fn test_write_encoding_decode() {
+        assert_eq!(WriteEncoding::Text.decode("QUJD").unwrap(), b"QUJD");
+        assert_eq!(
+            WriteEncoding::Base64.decode("AP8NCg==").unwrap(),
+            vec![0x00, 0xff, b'\r', b'\n']
+        );
+        assert_eq!(
+            WriteEncoding::Hex.decode("01 03 0a FF").unwrap(),
+            vec![0x01, 0x03, 0x0a, 0xff]
+        );
+        assert!(WriteEncoding::Base64.decode("not base64!").is_err());
+        assert!(WriteEncoding::Hex.decode("abc").is_err());
+        assert!(WriteEncoding::Hex.decode("zz").is_err());
+        // A sign is not a hex digit
+        assert!(WriteEncoding::Hex.decode("+1").is_err());
+
+        // Every byte value survives encoding and decoding
+        use base64::Engine;
+        let all: Vec<u8> = (0..=255).collect();
+        let b64 = base64::engine::general_purpose::STANDARD.encode(&all);
+        assert_eq!(WriteEncoding::Base64.decode(&b64).unwrap(), all);
+        let hex: String = all.iter().map(|b| format!("{:02X}", b)).collect();
+        assert_eq!(WriteEncoding::Hex.decode(&hex).unwrap(), all);
+    }
+
+    #[test]
+    fn test_encode_write_without_checksum() {
+        let config = config_with(Some("\n"), None);
+        assert_eq!(config.encode_write("AT"), (b"AT\n".to_vec(), None));
+        assert_eq!(config.encode_write("AT\n"), (b"AT\n".to_vec(), None));
+
+        let config = config_with(None, Some(ChecksumKind::Xor8));
+        assert_eq!(
+            config.encode_write("AB"),
+            (vec![b'A', b'B', 0x03], Some(vec![0x03]))
+        );
+    }
+
+    #[test]
+    fn test_patch_baud_only() {
+        let mut config = config_with(Some("\r\n"), Some(ChecksumKind::Crc8));
+        config.idle_disconnect_ms = Some(5_000);
+        let patch: PortConfigPatch = serde_json::from_str(r#"{"baud_rate": 115200}"#).unwrap();
+        assert!(patch.requires_reopen());
+
+        config.apply_patch(patch);
+        assert_eq!(config.baud_rate, 115200);
+        assert_eq!(config.terminator.as_deref(), Some("\r\n"));
+        assert_eq!(config.write_checksum, Some(ChecksumKind::Crc8));
+        assert_eq!(config.idle_disconnect_ms, Some(5_000));
+        assert_eq!(config.timeout_ms, default_timeout());
+    }
+
+    #[test]
+    fn test_patch_terminator_only() {
+        let mut config = config_with(Some("\n"), None);
+        config.baud_rate = 57600;
+        let patch: PortConfigPatch = serde_json::from_str(r#"{"terminator": "\r"}"#).unwrap();
+        assert!(!patch.requires_reopen());
+
+        config.apply_patch(patch);
+        assert_eq!(config.terminator.as_deref(), Some("\r"));
+        assert_eq!(config.baud_rate, 57600);
+
+        // An explicit null clears the terminator; an absent field does not
+        let clear: PortConfigPatch = serde_json::from_str(r#"{"terminator": null}"#).unwrap();
+        assert_eq!(clear.terminator, Some(None));
+        config.apply_patch(clear);
+        assert_eq!(config.terminator, None);
+        let empty: PortConfigPatch = serde_json::from_str("{}").unwrap();
+        assert_eq!(empty.terminator, None);
+    }
+
+    #[test]
+    fn test_timeout_throttle_warns_once_per_interval() {
+        let mut throttle = TimeoutLogThrottle::default();
+        let interval = Duration::from_secs(3600);
+        let warnings = (0..100)
+            .filter(|_| throttle.record(interval).is_some())
+            .count();
+        assert_eq!(warnings, 1);
+        assert_eq!(throttle.suppressed(), 99);
+
+        // Data arriving resets the streak, so the next timeout warns again
+        throttle.reset();
+        assert_eq!(throttle.record(interval), Some(0));
+
+        // An elapsed interval lets the next warning through with the count
+        let mut throttle = TimeoutLogThrottle::default();
+        throttle.record(Duration::ZERO);
+        assert_eq!(throttle.record(Duration::ZERO), Some(0));
+    }
+}
@@ -4,6 +4,11 @@
 //! It is only compiled when the `legacy-stdio` feature is enabled AND the `mcp` feature is disabled.
 //!
 //! **DEPRECATED**: Use MCP interface instead. This module will be removed in a future release.
+//!
+//! The interface no longer reads or writes the port (those commands return an error pointing at
+//! the MCP tools), so device data never appears in its responses. For binary-safe I/O use the MCP
+//! `read` tool with `encoding = "base64"`; `serde_json` escapes any newline in a string value, so
+//! a response line is never split by payload bytes.
 
 #[cfg(all(feature = "legacy-stdio", not(feature = "mcp")))]
 use crate::error::AppError;
@@ -8,6 +8,7 @@ use std::collections::VecDeque;
 use std::io;
 // use std::sync::{Arc, Mutex}; // TODO: Will be needed for shared state
 use std::time::Instant;
+use tokio::sync::watch;
 
 use super::event::{Event, EventHandler};
 use super::theme::Theme;
@@ -86,6 +87,9 @@ pub struct App {
     pub mode: Mode,
     /// Current theme
     pub theme: Theme,
+    /// Remote theme selection ([`super::shared_theme`]), applied on each
+    /// render tick
+    pub theme_rx: Option<watch::Receiver<&'static str>>,
     /// Focus area
     pub focus: FocusArea,
 
@@ -97,8 +101,12 @@ pub struct App {
 
     /// Receive buffer for terminal display
     pub rx_buffer: VecDeque<DataLine>,
-    /// Maximum buffer size
+    /// Maximum buffer size in lines
     pub buffer_size: usize,
+    /// Maximum buffer size in bytes of line data
+    pub buffer_bytes: usize,
+    /// Bytes of line data currently in `rx_buffer`
+    pub rx_bytes: usize,
 
     /// Current input text
     pub input: String,
@@ -143,11 +151,13 @@ impl App {
             state: AppState::Running,
             mode: Mode::Normal,
             theme,
+            theme_rx: Some(super::shared_theme().subscribe()),
             focus: FocusArea::Input,
-            config,
             port_service: None,
-            rx_buffer: VecDeque::with_capacity(1000),
-            buffer_size: 1000,
+            rx_buffer: VecDeque::with_capacity(config.tui.buffer_lines),
+            buffer_size: config.tui.buffer_lines,
+            buffer_bytes: config.tui.buffer_bytes,
+            rx_bytes: 0,
             input: String::new(),
             cursor_pos: 0,
             history: Vec::new(),
@@ -159,6 +169,7 @@ impl App {
             status_message: None,
             show_hex: false,
             scroll_offset: 0,
+            config,
         })
     }
 
@@ -169,6 +180,20 @@ impl App {
         Ok(app)
     }
 
+    /// Switch to the remotely selected theme if it changed since the last tick.
+    pub fn apply_shared_theme(&mut self) {
+        let Some(rx) = self.theme_rx.as_mut() else {
+            return;
+        };
+        if !rx.has_changed().unwrap_or(false) {
+            return;
+        }
+        let name = *rx.borrow_and_update();
+        if let Some(theme) = Theme::by_name(name) {
+            self.theme = theme.clone();
+        }
+    }
+
     /// Run the application main loop.
     pub async fn run(&mut self) -> io::Result<()> {
         // Set up terminal
@@ -184,6 +209,7 @@ impl App {
         // Main loop
         while self.state == AppState::Running {
             // Draw UI
+            self.apply_shared_theme();
             terminal.draw(|frame| ui::render(self, frame))?;
 
             // Handle events
@@ -257,7 +283,7 @@ impl App {
                 self.show_hex = !self.show_hex;
             }
             KeyCode::Char('l') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                self.rx_buffer.clear();
+                self.clear_buffer();
             }
             KeyCode::F(1) | KeyCode::Char('?') => self.mode = Mode::Help,
             KeyCode::Tab => self.cycle_focus(),
@@ -496,17 +522,12 @@ impl App {
         let mut tx_data = data.as_bytes().to_vec();
         tx_data.extend_from_slice(b"\r\n");
 
-        self.rx_buffer.push_back(DataLine {
+        self.push_line(DataLine {
             timestamp: Instant::now(),
             is_tx: true,
-            data: tx_data.clone(),
+            data: tx_data,
         });
 
-        // Trim buffer if needed
-        while self.rx_buffer.len() > self.buffer_size {
-            self.rx_buffer.pop_front();
-        }
-
         // Clear input
         self.input.clear();
         self.cursor_pos = 0;
@@ -522,7 +543,7 @@ impl App {
             "q" | "quit" => self.state = AppState::Quitting,
             "config" => self.mode = Mode::ConfigEdit,
             "hex" => self.show_hex = !self.show_hex,
-            "clear" => self.rx_buffer.clear(),
+            "clear" => self.clear_buffer(),
             "help" => self.mode = Mode::Help,
             "refresh" => self.refresh_ports(),
             _ => {
@@ -564,17 +585,34 @@ impl App {
 
     /// Add received data to the buffer.
     pub fn add_rx_data(&mut self, data: Vec<u8>) {
-        self.rx_buffer.push_back(DataLine {
+        self.push_line(DataLine {
             timestamp: Instant::now(),
             is_tx: false,
             data,
         });
+    }
 
-        while self.rx_buffer.len() > self.buffer_size {
-            self.rx_buffer.pop_front();
+    /// Append a line to the terminal buffer, dropping the oldest lines until
+    /// both the line and byte limits hold. The newest line is always kept,
+    /// even if it alone is larger than the byte limit.
+    fn push_line(&mut self, line: DataLine) {
+        self.rx_bytes += line.data.len();
+        self.rx_buffer.push_back(line);
+        while self.rx_buffer.len() > self.buffer_size
+            || (self.rx_bytes > self.buffer_bytes && self.rx_buffer.len() > 1)
+        {
+            if let Some(old) = self.rx_buffer.pop_front() {
+                self.rx_bytes -= old.data.len();
+            }
         }
     }
 
+    /// Empty the terminal buffer.
+    fn clear_buffer(&mut self) {
+        self.rx_buffer.clear();
+        self.rx_bytes = 0;
+    }
+
     /// Get uptime string.
     pub fn uptime_string(&self) -> String {
         match self.connect_time {
@@ -596,3 +634,49 @@ impl Default for App {
         Self::new().expect("Failed to create default app")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rx_buffer_keeps_line_and_byte_limits() {
+        let mut app = App::new().unwrap();
+        app.buffer_size = 50;
+        app.buffer_bytes = 400;
+
+        // Alternate runs of tiny lines (line limit binds) and long lines
+        // (byte limit binds)
+        let len_of = |i: usize| if (i / 100) % 2 == 0 { 1 } else { 1 + i % 40 };
+        for i in 0..500 {
+            app.add_rx_data(vec![b'x'; len_of(i)]);
+            assert!(app.rx_buffer.len() <= 50);
+            assert!(app.rx_bytes <= 400);
+            assert_eq!(
+                app.rx_bytes,
+                app.rx_buffer.iter().map(|l| l.data.len()).sum::<usize>()
+            );
+            match i {
+                99 => assert_eq!(app.rx_buffer.len(), 50),
+                199 => assert!(app.rx_buffer.len() < 50),
+                _ => {}
+            }
+        }
+
+        app.clear_buffer();
+        assert!(app.rx_buffer.is_empty());
+        assert_eq!(app.rx_bytes, 0);
+    }
+
+    #[test]
+    fn test_app_follows_remote_theme_selection() {
+        let mut app = App::new().unwrap();
+        assert!(app.theme_rx.is_some());
+
+        let selection = super::super::ThemeSender::new("dark");
+        app.theme_rx = Some(selection.subscribe());
+        selection.send_replace("nord");
+        app.apply_shared_theme();
+        assert_eq!(app.theme.name, "nord");
+    }
+}
@@ -38,5 +38,5 @@ pub mod widgets;
 
 pub use app::{App, AppState, FocusArea, Mode};
 pub use event::{Event, EventHandler};
-pub use theme::{Theme, THEMES};
+pub use theme::{shared_theme, Theme, ThemeSender, THEMES};
 pub use ui::render;
@@ -1,6 +1,21 @@
 //! Theme definitions for the TUI.
 
 use ratatui::style::Color;
+use std::sync::OnceLock;
+use tokio::sync::watch;
+
+/// Sender half of the shared theme selection.
+///
+/// Holds the active theme name. The MCP `set_theme` tool publishes to
+/// [`shared_theme`], and every [`crate::tui::App`] in the same process
+/// switches theme on its next render tick.
+pub type ThemeSender = watch::Sender<&'static str>;
+
+/// The process-wide theme selection followed by the TUI.
+pub fn shared_theme() -> &'static ThemeSender {
+    static SHARED: OnceLock<ThemeSender> = OnceLock::new();
+    SHARED.get_or_init(|| ThemeSender::new(Theme::default().name))
+}
 
 /// A color theme for the TUI.
 #[derive(Debug, Clone)]
@@ -139,6 +154,11 @@ impl Theme {
     pub fn by_name(name: &str) -> Option<&'static Theme> {
         THEMES.iter().find(|t| t.name == name)
     }
+
+    /// Names of the built-in themes, in [`THEMES`] order.
+    pub fn names() -> Vec<&'static str> {
+        THEMES.iter().map(|t| t.name).collect()
+    }
 }
 
 impl Default for Theme {
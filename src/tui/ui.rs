@@ -184,8 +184,8 @@ fn render_terminal(app: &App, frame: &mut Frame, area: Rect) {
         .rx_buffer
         .iter()
         .skip(app.scroll_offset)
+        .flat_map(|data_line| format_data_line(app, data_line))
         .take(area.height.saturating_sub(2) as usize)
-        .map(|data_line| format_data_line(app, data_line))
         .collect();
 
     let terminal_widget = Paragraph::new(lines)
@@ -200,35 +200,37 @@ fn render_terminal(app: &App, frame: &mut Frame, area: Rect) {
     frame.render_widget(terminal_widget, area);
 }
 
-fn format_data_line<'a>(app: &App, line: &DataLine) -> Line<'a> {
-    let direction_span = if line.is_tx {
-        Span::styled("TX: ", Style::default().fg(Color::from(app.theme.tx_color)))
+/// Render one received/sent chunk; in hex view it spans one line per
+/// 16 bytes of the hex dump.
+fn format_data_line<'a>(app: &App, line: &DataLine) -> Vec<Line<'a>> {
+    let style = Style::default().fg(if line.is_tx {
+        Color::from(app.theme.tx_color)
     } else {
-        Span::styled("RX: ", Style::default().fg(Color::from(app.theme.rx_color)))
-    };
-
-    let data_str = if app.show_hex {
-        line.data
-            .iter()
-            .map(|b| format!("{:02X}", b))
-            .collect::<Vec<_>>()
-            .join(" ")
+        Color::from(app.theme.rx_color)
+    });
+    let direction = if line.is_tx { "TX: " } else { "RX: " };
+
+    if app.show_hex {
+        crate::port::hexdump(&line.data)
+            .lines()
+            .enumerate()
+            .map(|(i, dump_line)| {
+                let prefix = if i == 0 { direction } else { "    " };
+                Line::from(vec![
+                    Span::styled(prefix, style),
+                    Span::styled(dump_line.to_string(), style),
+                ])
+            })
+            .collect()
     } else {
-        String::from_utf8_lossy(&line.data)
+        let data_str = String::from_utf8_lossy(&line.data)
             .replace('\r', "")
-            .replace('\n', "↵")
-    };
-
-    let data_span = Span::styled(
-        data_str,
-        Style::default().fg(if line.is_tx {
-            Color::from(app.theme.tx_color)
-        } else {
-            Color::from(app.theme.rx_color)
-        }),
-    );
-
-    Line::from(vec![direction_span, data_span])
+            .replace('\n', "↵");
+        vec![Line::from(vec![
+            Span::styled(direction, style),
+            Span::styled(data_str, style),
+        ])]
+    }
 }
 
 fn render_input(app: &App, frame: &mut Frame, area: Rect) {
@@ -7,38 +7,102 @@
 use axum::{
     extract::{
         ws::{Message, WebSocket},
-        State as AxumState, WebSocketUpgrade,
+        Query, State as AxumState, WebSocketUpgrade,
     },
-    response::IntoResponse,
+    http::StatusCode,
+    response::{IntoResponse, Response},
 };
 use chrono::Utc;
 use futures::{SinkExt, StreamExt};
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use std::time::Duration;
 use tokio::sync::broadcast;
 use tokio_stream::wrappers::{errors::BroadcastStreamRecvError, BroadcastStream};
 use tracing::{debug, error, info, warn};
 
-use crate::{rest_api::RestContext, state::PortState};
+use crate::{
+    config::{ServerConfig, WsBackpressure},
+    rest_api::RestContext,
+    service::ServiceError,
+    state::{lock_state, PortConfig, PortConfigPatch, PortState, TimeoutLogThrottle},
+};
 
-/// Maximum number of messages buffered per WebSocket connection.
-/// Prevents slow clients from consuming unlimited memory.
-const WS_BUFFER_SIZE: usize = 100;
+/// WebSocket streaming settings, taken from the `[server]` config section.
+#[derive(Debug, Clone, Copy)]
+pub struct WsSettings {
+    /// Interval for reading serial data when the port is open.
+    pub read_interval: Duration,
+    /// Interval of the periodic status broadcast; `None` disables it.
+    pub status_interval: Option<Duration>,
+    /// Maximum number of messages buffered per WebSocket connection.
+    /// Prevents slow clients from consuming unlimited memory.
+    pub buffer_size: usize,
+    /// What happens when a client falls behind the buffer.
+    pub backpressure: WsBackpressure,
+    /// Maximum concurrent connections; further upgrades are rejected with 503.
+    pub max_connections: usize,
+}
 
-/// Interval for reading serial data when port is open (milliseconds).
-const SERIAL_READ_INTERVAL_MS: u64 = 50;
+impl From<&ServerConfig> for WsSettings {
+    fn from(config: &ServerConfig) -> Self {
+        Self {
+            read_interval: config.ws_read_interval(),
+            status_interval: config.ws_status_interval(),
+            buffer_size: config.ws_buffer_size.max(1),
+            backpressure: config.ws_backpressure,
+            max_connections: config.max_ws_connections,
+        }
+    }
+}
+
+impl Default for WsSettings {
+    fn default() -> Self {
+        Self::from(&ServerConfig::default())
+    }
+}
+
+/// How serial data is framed, chosen with `?encoding=` on the upgrade URL.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WsEncoding {
+    /// JSON `data` text frames (default)
+    #[default]
+    Json,
+    /// Binary frames carrying the exact bytes read; control messages stay JSON
+    Raw,
+}
+
+/// Query parameters accepted on the WebSocket upgrade.
+#[derive(Debug, Default, Deserialize)]
+pub struct WsParams {
+    #[serde(default)]
+    pub encoding: WsEncoding,
+}
 
 /// WebSocket message types for client communication.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
 enum WsMessage {
     /// Data received from serial port
-    Data { data: String, timestamp: String },
+    Data {
+        data: String,
+        timestamp: String,
+        /// Unstripped bytes as read, sent as-is under [`WsEncoding::Raw`]
+        #[serde(skip)]
+        bytes: Vec<u8>,
+    },
     /// Port status update
     Status {
         state: PortStatusState,
         #[serde(skip_serializing_if = "Option::is_none")]
         metrics: Option<PortMetrics>,
+        /// Settings of the open port
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        config: Option<PortConfig>,
     },
     /// Error notification
     Error { message: String },
@@ -50,10 +114,16 @@ enum WsMessage {
 enum WsCommand {
     /// Write data to serial port
     Write { data: String },
-    /// Subscribe to serial data stream
-    Subscribe,
+    /// Subscribe to serial data stream, optionally only to data matching
+    /// the `filter` regex
+    Subscribe {
+        #[serde(default)]
+        filter: Option<String>,
+    },
     /// Unsubscribe from serial data stream
     Unsubscribe,
+    /// Change settings of the open port; fields as in `POST /port/patch_config`
+    Reconfigure(PortConfigPatch),
 }
 
 /// Port connection state.
@@ -76,13 +146,13 @@ struct PortMetrics {
 
 /// Shared state for broadcasting serial data to all connected WebSocket clients.
 #[derive(Clone)]
-struct BroadcastState {
+pub(crate) struct BroadcastState {
     tx: broadcast::Sender<WsMessage>,
 }
 
 impl BroadcastState {
-    fn new() -> Self {
-        let (tx, _) = broadcast::channel(WS_BUFFER_SIZE);
+    fn new(buffer_size: usize) -> Self {
+        let (tx, _) = broadcast::channel(buffer_size);
         Self { tx }
     }
 
@@ -96,25 +166,129 @@ impl BroadcastState {
     }
 }
 
+/// Slot in the live WebSocket connection count, released on drop.
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    /// Take a slot if fewer than `max` connections are live.
+    fn acquire(count: &Arc<AtomicUsize>, max: usize) -> Option<Self> {
+        count
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| {
+                (n < max).then_some(n + 1)
+            })
+            .ok()
+            .map(|_| Self(count.clone()))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Serial reader tasks of the live WebSocket connections, kept so
+/// `POST /admin/reset` can restart them.
+#[derive(Clone, Default)]
+pub struct WsReaders(Arc<Mutex<HashMap<uuid::Uuid, WsReader>>>);
+
+struct WsReader {
+    task: tokio::task::JoinHandle<()>,
+    broadcast: BroadcastState,
+}
+
+impl WsReaders {
+    fn lock(&self) -> MutexGuard<'_, HashMap<uuid::Uuid, WsReader>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Start a reader for a new connection, broadcasting on a fresh channel.
+    /// The reader stops when the returned guard is dropped.
+    pub(crate) fn start(&self, ctx: &RestContext) -> (BroadcastState, ReaderGuard) {
+        let broadcast = BroadcastState::new(ctx.ws.buffer_size);
+        let id = uuid::Uuid::new_v4();
+        let task = tokio::spawn(serial_reader_task(ctx.clone(), broadcast.clone()));
+        self.lock().insert(
+            id,
+            WsReader {
+                task,
+                broadcast: broadcast.clone(),
+            },
+        );
+        let guard = ReaderGuard {
+            readers: self.clone(),
+            id,
+        };
+        (broadcast, guard)
+    }
+
+    /// Stop every reader and start a fresh one on the same connection.
+    /// Returns how many were restarted.
+    pub fn restart(&self, ctx: &RestContext) -> usize {
+        let mut readers = self.lock();
+        for reader in readers.values_mut() {
+            reader.task.abort();
+            reader.task = tokio::spawn(serial_reader_task(ctx.clone(), reader.broadcast.clone()));
+        }
+        readers.len()
+    }
+
+    /// Number of readers whose task is still running.
+    pub fn running(&self) -> usize {
+        self.lock()
+            .values()
+            .filter(|reader| !reader.task.is_finished())
+            .count()
+    }
+}
+
+/// A connection's entry in [`WsReaders`], stopped and removed on drop.
+pub(crate) struct ReaderGuard {
+    readers: WsReaders,
+    id: uuid::Uuid,
+}
+
+impl Drop for ReaderGuard {
+    fn drop(&mut self) {
+        if let Some(reader) = self.readers.lock().remove(&self.id) {
+            reader.task.abort();
+        }
+    }
+}
+
 /// WebSocket upgrade handler.
 ///
 /// This is the main entry point for WebSocket connections at `/ws/serial`.
+/// Upgrades beyond `max_connections` are rejected with 503 before upgrading.
 pub async fn ws_handler(
     ws: WebSocketUpgrade,
+    Query(params): Query<WsParams>,
     AxumState(ctx): AxumState<RestContext>,
-) -> impl IntoResponse {
-    // Create a broadcast channel for this connection
-    let broadcast_state = BroadcastState::new();
-    let broadcast_clone = broadcast_state.clone();
+) -> Response {
+    let Some(slot) = ConnectionSlot::acquire(&ctx.ws_connections, ctx.ws.max_connections) else {
+        warn!(
+            max = ctx.ws.max_connections,
+            "Rejecting WebSocket upgrade: connection limit reached"
+        );
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            "WebSocket connection limit reached",
+        )
+            .into_response();
+    };
 
     // Spawn a background task to read from serial port and broadcast data
-    let ctx_clone = ctx.clone();
-    tokio::spawn(async move {
-        serial_reader_task(ctx_clone, broadcast_clone).await;
-    });
-
-    // Upgrade the HTTP connection to WebSocket
-    ws.on_upgrade(move |socket| handle_socket(socket, ctx, broadcast_state))
+    // on this connection's channel
+    let (broadcast_state, reader) = ctx.ws_readers.start(&ctx);
+
+    // Upgrade the HTTP connection to WebSocket; the reader is stopped and the
+    // slot released when the socket handler finishes (or the upgrade is
+    // abandoned)
+    ws.on_upgrade(move |socket| async move {
+        handle_socket(socket, ctx, broadcast_state, params.encoding).await;
+        drop(reader);
+        drop(slot);
+    })
 }
 
 /// Main WebSocket connection handler.
@@ -122,14 +296,20 @@ pub async fn ws_handler(
 /// Manages bidirectional communication:
 /// - Receives commands from client (write, subscribe, unsubscribe)
 /// - Sends serial data, status updates, and errors to client
-async fn handle_socket(socket: WebSocket, ctx: RestContext, broadcast: BroadcastState) {
+async fn handle_socket(
+    socket: WebSocket,
+    ctx: RestContext,
+    broadcast: BroadcastState,
+    encoding: WsEncoding,
+) {
     let (mut sender, mut receiver) = socket.split();
     let client_id = uuid::Uuid::new_v4();
 
-    info!("WebSocket client connected: {}", client_id);
+    info!("WebSocket client connected: {} ({:?})", client_id, encoding);
 
     // Track subscription state
     let mut subscribed = false;
+    let mut filter: Option<Regex> = None;
     let mut broadcast_stream = broadcast.subscribe();
 
     // Send initial status
@@ -144,7 +324,7 @@ async fn handle_socket(socket: WebSocket, ctx: RestContext, broadcast: Broadcast
             msg = receiver.next() => {
                 match msg {
                     Some(Ok(Message::Text(text))) => {
-                        let result = handle_client_message(&text, &ctx, &mut sender, &mut subscribed).await;
+                        let result = handle_client_message(&text, &ctx, &broadcast, &mut sender, &mut subscribed, &mut filter).await;
                         if let Err(e) = result {
                             let error_msg = format!("Command error: {}", e);
                             drop(e); // Explicitly drop the error before await
@@ -180,7 +360,10 @@ async fn handle_socket(socket: WebSocket, ctx: RestContext, broadcast: Broadcast
             msg = broadcast_stream.next(), if subscribed => {
                 match msg {
                     Some(Ok(ws_msg)) => {
-                        if let Err(e) = send_message(&mut sender, &ws_msg).await {
+                        if !passes_filter(filter.as_ref(), &ws_msg) {
+                            continue;
+                        }
+                        if let Err(e) = send_encoded(&mut sender, &ws_msg, encoding).await {
                             error!("Failed to send broadcast to {}: {}", client_id, e);
                             break;
                         }
@@ -188,6 +371,11 @@ async fn handle_socket(socket: WebSocket, ctx: RestContext, broadcast: Broadcast
                     Some(Err(BroadcastStreamRecvError::Lagged(skipped))) => {
                         warn!("Client {} lagged, skipped {} messages", client_id, skipped);
                         let _ = send_error(&mut sender, &format!("Lagged: {} messages skipped", skipped)).await;
+                        if disconnect_on_lag(ctx.ws.backpressure) {
+                            info!("Closing lagging WebSocket client {}", client_id);
+                            let _ = sender.send(Message::Close(None)).await;
+                            break;
+                        }
                     }
                     None => {
                         debug!("Broadcast stream ended for {}", client_id);
@@ -201,12 +389,36 @@ async fn handle_socket(socket: WebSocket, ctx: RestContext, broadcast: Broadcast
     info!("WebSocket handler finished for {}", client_id);
 }
 
+/// Whether a client that lagged behind the buffer should be disconnected.
+fn disconnect_on_lag(policy: WsBackpressure) -> bool {
+    policy == WsBackpressure::CloseOnLag
+}
+
+/// Whether a subscriber with `filter` is sent `msg`: data must match the
+/// filter, status and errors always go through.
+fn passes_filter(filter: Option<&Regex>, msg: &WsMessage) -> bool {
+    match (filter, msg) {
+        (Some(filter), WsMessage::Data { data, .. }) => filter.is_match(data),
+        _ => true,
+    }
+}
+
+/// Compile a `subscribe` filter, rejecting invalid patterns.
+fn compile_filter(pattern: Option<&str>) -> Result<Option<Regex>, String> {
+    pattern
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| format!("invalid filter: {}", e))
+}
+
 /// Handle incoming client command messages.
 async fn handle_client_message(
     text: &str,
     ctx: &RestContext,
+    broadcast: &BroadcastState,
     sender: &mut futures::stream::SplitSink<WebSocket, Message>,
     subscribed: &mut bool,
+    filter: &mut Option<Regex>,
 ) -> Result<(), String> {
     let command: WsCommand = serde_json::from_str(text).map_err(|e| e.to_string())?;
 
@@ -214,14 +426,20 @@ async fn handle_client_message(
         WsCommand::Write { data } => {
             handle_write_command(ctx, data, sender).await?;
         }
-        WsCommand::Subscribe => {
+        WsCommand::Subscribe { filter: pattern } => {
+            // An invalid pattern leaves the subscription as it was
+            *filter = compile_filter(pattern.as_deref())?;
             *subscribed = true;
-            debug!("Client subscribed to serial data stream");
+            debug!(filter = ?pattern, "Client subscribed to serial data stream");
         }
         WsCommand::Unsubscribe => {
             *subscribed = false;
+            *filter = None;
             debug!("Client unsubscribed from serial data stream");
         }
+        WsCommand::Reconfigure(patch) => {
+            handle_reconfigure_command(ctx, patch, broadcast, sender, *subscribed).await?;
+        }
     }
 
     Ok(())
@@ -233,60 +451,39 @@ async fn handle_write_command(
     data: String,
     sender: &mut futures::stream::SplitSink<WebSocket, Message>,
 ) -> Result<(), String> {
-    // Perform the write and collect the response message
-    let response = {
-        let mut st = ctx
-            .state
-            .lock()
-            .map_err(|e| format!("State lock error: {}", e))?;
-
-        match &mut *st {
-            PortState::Open {
-                port,
-                config,
-                last_activity,
-                bytes_written_total,
-                ..
-            } => {
-                let mut write_data = data.clone();
-
-                // Append terminator if configured
-                if let Some(term) = &config.terminator {
-                    if !write_data.ends_with(term) {
-                        write_data.push_str(term);
-                    }
-                }
-
-                match port.write_bytes(write_data.as_bytes()) {
-                    Ok(bytes) => {
-                        *bytes_written_total += bytes as u64;
-                        *last_activity = std::time::Instant::now();
-
-                        debug!("Wrote {} bytes to serial port", bytes);
-
-                        // Build acknowledgment
-                        Ok(WsMessage::Status {
-                            state: PortStatusState::Open,
-                            metrics: Some(PortMetrics {
-                                bytes_read_total: 0, // Not tracked here
-                                bytes_written_total: *bytes_written_total,
-                                open_duration_ms: 0,
-                                last_activity_ms: 0,
-                                timeout_streak: 0,
-                            }),
-                        })
-                    }
-                    Err(e) => {
-                        error!("Write error: {}", e);
-                        Err(format!("Write failed: {}", e))
-                    }
-                }
-            }
-            PortState::Closed => Err("Port not open".to_string()),
+    // Go through the service's write queue so concurrent clients' payloads
+    // are never interleaved; the write blocks, so keep it off the async
+    // executor and this socket loop
+    let service = ctx.service.clone();
+    let response = match tokio::task::spawn_blocking(move || service.write(&data)).await {
+        Ok(Ok(result)) => {
+            debug!("Wrote {} bytes to serial port", result.bytes_written);
+
+            // Build acknowledgment
+            Ok(WsMessage::Status {
+                state: PortStatusState::Open,
+                metrics: Some(PortMetrics {
+                    bytes_read_total: 0, // Not tracked here
+                    bytes_written_total: result.bytes_written_total,
+                    open_duration_ms: 0,
+                    last_activity_ms: 0,
+                    timeout_streak: 0,
+                }),
+                config: None,
+            })
+        }
+        Ok(Err(ServiceError::PortNotOpen)) => Err("Port not open".to_string()),
+        Ok(Err(e)) => {
+            error!("Write error: {}", e);
+            Err(format!("Write failed: {}", e))
+        }
+        Err(e) => {
+            error!("Write task failed: {}", e);
+            Err(format!("Write failed: {}", e))
         }
-    }; // st is dropped here
+    };
 
-    // Send the response (after mutex is released)
+    // Send the response
     match response {
         Ok(msg) => send_message(sender, &msg).await?,
         Err(error_msg) => send_error(sender, &error_msg).await?,
@@ -295,24 +492,101 @@ async fn handle_write_command(
     Ok(())
 }
 
+/// Handle reconfigure command - patch the open port's settings.
+///
+/// Line settings reopen the port softly, keeping partial frames; the reader
+/// task takes the new port and config from the shared state on its next
+/// tick, so the stream continues without a restart. The resulting status
+/// goes out on the stream for subscribers, and straight back otherwise.
+async fn handle_reconfigure_command(
+    ctx: &RestContext,
+    patch: PortConfigPatch,
+    broadcast: &BroadcastState,
+    sender: &mut futures::stream::SplitSink<WebSocket, Message>,
+    subscribed: bool,
+) -> Result<(), String> {
+    let service = ctx.service.clone();
+    // A reopen blocks, so keep it off the async executor and this socket loop
+    match tokio::task::spawn_blocking(move || service.patch_config(patch)).await {
+        Ok(Ok(result)) => {
+            debug!(reopened = result.reopened, "Reconfigured serial port");
+            let msg = WsMessage::Status {
+                state: PortStatusState::Open,
+                metrics: None,
+                config: Some(result.config),
+            };
+            if subscribed {
+                broadcast.broadcast(msg);
+            } else {
+                send_message(sender, &msg).await?;
+            }
+        }
+        Ok(Err(ServiceError::PortNotOpen)) => send_error(sender, "Port not open").await?,
+        Ok(Err(e)) => {
+            error!("Reconfigure error: {}", e);
+            send_error(sender, &format!("Reconfigure failed: {}", e)).await?;
+        }
+        Err(e) => {
+            error!("Reconfigure task failed: {}", e);
+            send_error(sender, &format!("Reconfigure failed: {}", e)).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Log a read timeout, at most once per `interval` while the port stays silent.
+fn warn_on_timeout(
+    throttle: &mut TimeoutLogThrottle,
+    interval: Duration,
+    config: &PortConfig,
+    timeout_streak: u32,
+) {
+    if let Some(suppressed) = throttle.record(interval) {
+        warn!(
+            port = %config.port_name,
+            timeout_streak,
+            suppressed,
+            "WebSocket reader: read timed out; port is silent"
+        );
+    }
+}
+
+/// Wait for the next tick of `ticker`, or forever if there is none.
+async fn next_tick(ticker: &mut Option<tokio::time::Interval>) {
+    match ticker {
+        Some(ticker) => {
+            ticker.tick().await;
+        }
+        None => std::future::pending().await,
+    }
+}
+
 /// Background task that continuously reads from serial port and broadcasts data.
+///
+/// Every `status_interval` it also broadcasts the current status, so
+/// long-lived clients see uptime and throughput move between writes.
 async fn serial_reader_task(ctx: RestContext, broadcast: BroadcastState) {
-    let mut interval = tokio::time::interval(Duration::from_millis(SERIAL_READ_INTERVAL_MS));
+    let mut interval = tokio::time::interval(ctx.ws.read_interval);
+    let mut status_ticker = ctx.ws.status_interval.map(|period| {
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        ticker
+    });
+    let warn_interval = ctx.service.timeout_warn_interval();
 
     loop {
-        interval.tick().await;
-
-        // Check if lock failed
-        let lock_ok = ctx.state.lock().is_ok();
-        if !lock_ok {
-            error!("Failed to acquire state lock in reader task");
-            tokio::time::sleep(Duration::from_secs(1)).await;
-            continue;
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = next_tick(&mut status_ticker) => {
+                broadcast.broadcast(current_status(&ctx));
+                continue;
+            }
         }
 
         // Check port state and read data
         let read_result = {
-            let mut st = ctx.state.lock().unwrap();
+            let mut st = lock_state(&ctx.state);
 
             match &mut *st {
                 PortState::Open {
@@ -322,18 +596,21 @@ async fn serial_reader_task(ctx: RestContext, broadcast: BroadcastState) {
                     timeout_streak,
                     bytes_read_total,
                     idle_close_count,
+                    timeout_log,
                     ..
                 } => {
                     let mut buffer = vec![0u8; 1024];
 
                     match port.read_bytes(buffer.as_mut_slice()) {
                         Ok(bytes_read) if bytes_read > 0 => {
-                            let raw = String::from_utf8_lossy(&buffer[..bytes_read]).to_string();
+                            let bytes = buffer[..bytes_read].to_vec();
+                            let raw = String::from_utf8_lossy(&bytes).to_string();
 
                             // Update metrics
                             *last_activity = std::time::Instant::now();
                             *timeout_streak = 0;
                             *bytes_read_total += bytes_read as u64;
+                            timeout_log.reset();
 
                             // Strip terminator if configured
                             let data = if let Some(term) = &config.terminator {
@@ -342,11 +619,12 @@ async fn serial_reader_task(ctx: RestContext, broadcast: BroadcastState) {
                                 raw
                             };
 
-                            Some(Ok(data))
+                            Some(Ok((data, bytes)))
                         }
                         Ok(_) => {
                             // No data (timeout)
                             *timeout_streak += 1;
+                            warn_on_timeout(timeout_log, warn_interval, config, *timeout_streak);
 
                             // Check for idle disconnect
                             let idle_expired = config
@@ -366,6 +644,12 @@ async fn serial_reader_task(ctx: RestContext, broadcast: BroadcastState) {
                             if let crate::port::PortError::Io(ref io_err) = e {
                                 if io_err.kind() == std::io::ErrorKind::TimedOut {
                                     *timeout_streak += 1;
+                                    warn_on_timeout(
+                                        timeout_log,
+                                        warn_interval,
+                                        config,
+                                        *timeout_streak,
+                                    );
                                     None
                                 } else {
                                     Some(Err(e.to_string()))
@@ -382,10 +666,11 @@ async fn serial_reader_task(ctx: RestContext, broadcast: BroadcastState) {
 
         // Process read result and broadcast
         match read_result {
-            Some(Ok(data)) => {
+            Some(Ok((data, bytes))) => {
                 let msg = WsMessage::Data {
                     data,
                     timestamp: Utc::now().to_rfc3339(),
+                    bytes,
                 };
                 broadcast.broadcast(msg);
             }
@@ -395,12 +680,14 @@ async fn serial_reader_task(ctx: RestContext, broadcast: BroadcastState) {
                     let msg = WsMessage::Status {
                         state: PortStatusState::Closed,
                         metrics: None,
+                        config: None,
                     };
                     broadcast.broadcast(msg);
 
                     // Close the port
-                    let mut st = ctx.state.lock().unwrap();
-                    *st = PortState::Closed;
+                    let mut st = lock_state(&ctx.state);
+                    ctx.service
+                        .auto_close(&mut st, crate::service::CloseReason::IdleTimeout);
                 } else {
                     // Other error
                     let msg = WsMessage::Error { message: error_msg };
@@ -414,19 +701,39 @@ async fn serial_reader_task(ctx: RestContext, broadcast: BroadcastState) {
     }
 }
 
-/// Send a WebSocket message to the client.
-async fn send_message(
+/// Frame a message for the wire: data as binary under `Raw`, everything else
+/// as JSON text.
+fn encode_frame(msg: &WsMessage, encoding: WsEncoding) -> Result<Message, String> {
+    match (msg, encoding) {
+        (WsMessage::Data { bytes, .. }, WsEncoding::Raw) => {
+            Ok(Message::Binary(bytes.clone().into()))
+        }
+        _ => {
+            let json = serde_json::to_string(msg).map_err(|e| e.to_string())?;
+            Ok(Message::Text(json.into()))
+        }
+    }
+}
+
+/// Send a WebSocket message to the client in the connection's encoding.
+async fn send_encoded(
     sender: &mut futures::stream::SplitSink<WebSocket, Message>,
     msg: &WsMessage,
+    encoding: WsEncoding,
 ) -> Result<(), String> {
-    let json = serde_json::to_string(msg).map_err(|e| e.to_string())?;
-    sender
-        .send(Message::Text(json.into()))
-        .await
-        .map_err(|e| e.to_string())?;
+    let frame = encode_frame(msg, encoding)?;
+    sender.send(frame).await.map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// Send a WebSocket message to the client as JSON text.
+async fn send_message(
+    sender: &mut futures::stream::SplitSink<WebSocket, Message>,
+    msg: &WsMessage,
+) -> Result<(), String> {
+    send_encoded(sender, msg, WsEncoding::Json).await
+}
+
 /// Send an error message to the client.
 async fn send_error(
     sender: &mut futures::stream::SplitSink<WebSocket, Message>,
@@ -438,42 +745,44 @@ async fn send_error(
     send_message(sender, &msg).await
 }
 
+/// Status message for the current port state.
+fn current_status(ctx: &RestContext) -> WsMessage {
+    let st = lock_state(&ctx.state);
+
+    match &*st {
+        PortState::Closed => WsMessage::Status {
+            state: PortStatusState::Closed,
+            metrics: None,
+            config: None,
+        },
+        PortState::Open {
+            config,
+            bytes_read_total,
+            bytes_written_total,
+            open_started,
+            last_activity,
+            timeout_streak,
+            ..
+        } => WsMessage::Status {
+            state: PortStatusState::Open,
+            metrics: Some(PortMetrics {
+                bytes_read_total: *bytes_read_total,
+                bytes_written_total: *bytes_written_total,
+                open_duration_ms: open_started.elapsed().as_millis() as u64,
+                last_activity_ms: last_activity.elapsed().as_millis() as u64,
+                timeout_streak: *timeout_streak,
+            }),
+            config: Some(config.clone()),
+        },
+    }
+}
+
 /// Send current port status to the client.
 async fn send_status(
     sender: &mut futures::stream::SplitSink<WebSocket, Message>,
     ctx: &RestContext,
 ) -> Result<(), String> {
-    let msg = {
-        let st = ctx
-            .state
-            .lock()
-            .map_err(|e| format!("State lock error: {}", e))?;
-
-        match &*st {
-            PortState::Closed => WsMessage::Status {
-                state: PortStatusState::Closed,
-                metrics: None,
-            },
-            PortState::Open {
-                bytes_read_total,
-                bytes_written_total,
-                open_started,
-                last_activity,
-                timeout_streak,
-                ..
-            } => WsMessage::Status {
-                state: PortStatusState::Open,
-                metrics: Some(PortMetrics {
-                    bytes_read_total: *bytes_read_total,
-                    bytes_written_total: *bytes_written_total,
-                    open_duration_ms: open_started.elapsed().as_millis() as u64,
-                    last_activity_ms: last_activity.elapsed().as_millis() as u64,
-                    timeout_streak: *timeout_streak,
-                }),
-            },
-        }
-    }; // st is dropped here
-
+    let msg = current_status(ctx);
     send_message(sender, &msg).await
 }
 
@@ -487,12 +796,39 @@ mod tests {
         let msg = WsMessage::Data {
             data: "test data".to_string(),
             timestamp: "2024-01-01T00:00:00Z".to_string(),
+            bytes: b"test data\n".to_vec(),
         };
 
         let json = serde_json::to_value(&msg).unwrap();
         assert_eq!(json["type"], "data");
         assert_eq!(json["data"], "test data");
         assert_eq!(json["timestamp"], "2024-01-01T00:00:00Z");
+        assert!(json.get("bytes").is_none());
+    }
+
+    #[test]
+    fn test_raw_encoding_frames_only_data_as_binary() {
+        let data = WsMessage::Data {
+            data: "ab".to_string(),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            bytes: vec![b'a', b'b', 0xff, b'\n'],
+        };
+        match encode_frame(&data, WsEncoding::Raw).unwrap() {
+            Message::Binary(bytes) => assert_eq!(&bytes[..], &[b'a', b'b', 0xff, b'\n']),
+            other => panic!("expected binary frame, got {:?}", other),
+        }
+        assert!(matches!(
+            encode_frame(&data, WsEncoding::Json).unwrap(),
+            Message::Text(_)
+        ));
+
+        let error = WsMessage::Error {
+            message: "boom".to_string(),
+        };
+        assert!(matches!(
+            encode_frame(&error, WsEncoding::Raw).unwrap(),
+            Message::Text(_)
+        ));
     }
 
     #[test]
@@ -513,7 +849,62 @@ mod tests {
     fn test_subscribe_command() {
         let json = json!({"type": "subscribe"});
         let cmd: WsCommand = serde_json::from_value(json).unwrap();
-        matches!(cmd, WsCommand::Subscribe);
+        assert!(matches!(cmd, WsCommand::Subscribe { filter: None }));
+
+        let json = json!({"type": "subscribe", "filter": "^ERR"});
+        let cmd: WsCommand = serde_json::from_value(json).unwrap();
+        assert!(matches!(cmd, WsCommand::Subscribe { filter: Some(f) } if f == "^ERR"));
+    }
+
+    #[tokio::test]
+    async fn test_filtered_subscriber_gets_only_matching_data() {
+        let broadcast = BroadcastState::new(16);
+        let mut stream = broadcast.subscribe();
+        let data = |line: &str| WsMessage::Data {
+            data: line.to_string(),
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+            bytes: line.as_bytes().to_vec(),
+        };
+        broadcast.broadcast(data("TEMP 21"));
+        broadcast.broadcast(data("ERR sensor offline"));
+        broadcast.broadcast(WsMessage::Error {
+            message: "Lagged".to_string(),
+        });
+        broadcast.broadcast(data("TEMP 22"));
+        broadcast.broadcast(WsMessage::Status {
+            state: PortStatusState::Closed,
+            metrics: None,
+            config: None,
+        });
+        broadcast.broadcast(data("ERR overheat"));
+        drop(broadcast);
+
+        let filter = compile_filter(Some("^ERR")).unwrap().unwrap();
+        let mut forwarded = Vec::new();
+        while let Some(Ok(msg)) = stream.next().await {
+            if passes_filter(Some(&filter), &msg) {
+                forwarded.push(serde_json::to_value(&msg).unwrap());
+            }
+        }
+        let summary: Vec<_> = forwarded
+            .iter()
+            .map(|m| {
+                m["data"]
+                    .as_str()
+                    .unwrap_or_else(|| m["type"].as_str().unwrap())
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            ["ERR sensor offline", "error", "status", "ERR overheat"]
+        );
+
+        // Without a filter every message goes through
+        assert!(passes_filter(None, &data("TEMP 23")));
+        assert!(compile_filter(None).unwrap().is_none());
+        assert!(compile_filter(Some("(unclosed"))
+            .unwrap_err()
+            .starts_with("invalid filter"));
     }
 
     #[test]
@@ -523,6 +914,20 @@ mod tests {
         matches!(cmd, WsCommand::Unsubscribe);
     }
 
+    #[test]
+    fn test_reconfigure_command() {
+        let json = json!({"type": "reconfigure", "baud_rate": 115200, "terminator": null});
+        let cmd: WsCommand = serde_json::from_value(json).unwrap();
+        match cmd {
+            WsCommand::Reconfigure(patch) => {
+                assert_eq!(patch.baud_rate, Some(115200));
+                assert_eq!(patch.terminator, Some(None));
+                assert!(patch.timeout_ms.is_none());
+            }
+            _ => panic!("Expected Reconfigure command"),
+        }
+    }
+
     #[test]
     fn test_error_message_serialization() {
         let msg = WsMessage::Error {
@@ -545,6 +950,7 @@ mod tests {
                 last_activity_ms: 100,
                 timeout_streak: 0,
             }),
+            config: None,
         };
 
         let json = serde_json::to_value(&msg).unwrap();
@@ -559,6 +965,7 @@ mod tests {
         let msg = WsMessage::Status {
             state: PortStatusState::Closed,
             metrics: None,
+            config: None,
         };
 
         let json = serde_json::to_value(&msg).unwrap();
@@ -566,4 +973,51 @@ mod tests {
         assert_eq!(json["state"], "Closed");
         assert!(json.get("metrics").is_none() || json["metrics"].is_null());
     }
+
+    #[tokio::test]
+    async fn test_lagging_subscriber_under_close_on_lag() {
+        let settings = WsSettings::from(&ServerConfig {
+            ws_buffer_size: 2,
+            ws_backpressure: WsBackpressure::CloseOnLag,
+            ..Default::default()
+        });
+        let broadcast = BroadcastState::new(settings.buffer_size);
+        let mut stream = broadcast.subscribe();
+
+        for i in 0..5 {
+            broadcast.broadcast(WsMessage::Error {
+                message: format!("msg {}", i),
+            });
+        }
+
+        match stream.next().await {
+            Some(Err(BroadcastStreamRecvError::Lagged(skipped))) => assert_eq!(skipped, 3),
+            other => panic!("expected lag, got {:?}", other),
+        }
+        assert!(disconnect_on_lag(settings.backpressure));
+        assert!(!disconnect_on_lag(WsBackpressure::DropOldest));
+
+        // Under drop_oldest the stream would continue with the newest messages
+        assert!(matches!(
+            stream.next().await,
+            Some(Ok(WsMessage::Error { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_ws_settings_clamp_zero_values() {
+        let settings = WsSettings::from(&ServerConfig {
+            ws_read_interval_ms: 0,
+            ws_status_interval_ms: 0,
+            ws_buffer_size: 0,
+            ..Default::default()
+        });
+        assert_eq!(settings.read_interval, Duration::from_millis(1));
+        assert_eq!(settings.status_interval, None);
+        assert_eq!(settings.buffer_size, 1);
+        assert_eq!(
+            WsSettings::default().status_interval,
+            Some(Duration::from_secs(2))
+        );
+    }
 }
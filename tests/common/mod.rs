@@ -134,6 +134,16 @@ impl TestHarness {
             flow_control: serial_mcp_agent::state::FlowControlCfg::None,
             terminator: Some("\n".to_string()),
             idle_disconnect_ms: None,
+            read_idle_gap_ms: None,
+            write_checksum: None,
+            half_duplex_echo: false,
+            flush_on_write: false,
+            auto_detect_terminator: false,
+            mask_parity_bit: None,
+            strip_echo: false,
+            strip_prompt: None,
+            max_open_duration_ms: None,
+            read_strategy: Default::default(),
         };
 
         let mut state_guard = harness.state.lock().unwrap();
@@ -145,7 +155,16 @@ impl TestHarness {
             bytes_read_total: 0,
             bytes_written_total: 0,
             idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer: Vec::new(),
             open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id: uuid::Uuid::new_v4(),
+            timeout_log: Default::default(),
+            cancel: Default::default(),
+            active_session: None,
+            terminator_detect: None,
+            pending_echo: None,
         };
         drop(state_guard);
 
@@ -167,7 +186,16 @@ impl TestHarness {
                     bytes_read_total: 0,
                     bytes_written_total: 0,
                     idle_close_count: 0,
+                    framing_errors: 0,
+                    frame_buffer: Vec::new(),
                     open_started: std::time::Instant::now(),
+                    open_started_at: chrono::Utc::now(),
+                    open_id: uuid::Uuid::new_v4(),
+                    timeout_log: Default::default(),
+                    cancel: Default::default(),
+                    active_session: None,
+                    terminator_detect: None,
+                    pending_echo: None,
                 }
             }
         }
@@ -216,6 +244,16 @@ impl PortConfigBuilder {
             flow_control: serial_mcp_agent::state::FlowControlCfg::None,
             terminator: Some("\n".to_string()),
             idle_disconnect_ms: None,
+            read_idle_gap_ms: None,
+            write_checksum: None,
+            half_duplex_echo: false,
+            flush_on_write: false,
+            auto_detect_terminator: false,
+            mask_parity_bit: None,
+            strip_echo: false,
+            strip_prompt: None,
+            max_open_duration_ms: None,
+            read_strategy: Default::default(),
         }
     }
 }
@@ -13,6 +13,7 @@ use serial_mcp_agent::{
     rest_api::RestContext,
     session::SessionStore,
     state::{AppState, PortConfig, PortState},
+    websocket::WsSettings,
 };
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -26,6 +27,11 @@ fn create_test_state_with_mock() -> AppState {
     // Configure mock to return data on read
     mock_port.enqueue_read(b"test response\n");
 
+    create_test_state_with_port(mock_port)
+}
+
+/// Helper to create test application state with `mock_port` open.
+fn create_test_state_with_port(mock_port: MockSerialPort) -> AppState {
     let state = PortState::Open {
         port: Box::new(mock_port),
         config: PortConfig {
@@ -38,13 +44,32 @@ fn create_test_state_with_mock() -> AppState {
             flow_control: serial_mcp_agent::state::FlowControlCfg::None,
             terminator: Some("\n".to_string()),
             idle_disconnect_ms: None,
+            read_idle_gap_ms: None,
+            write_checksum: None,
+            half_duplex_echo: false,
+            flush_on_write: false,
+            auto_detect_terminator: false,
+            mask_parity_bit: None,
+            strip_echo: false,
+            strip_prompt: None,
+            max_open_duration_ms: None,
+            read_strategy: Default::default(),
         },
         last_activity: std::time::Instant::now(),
         timeout_streak: 0,
         bytes_read_total: 0,
         bytes_written_total: 0,
         idle_close_count: 0,
+        framing_errors: 0,
+        frame_buffer: Vec::new(),
         open_started: std::time::Instant::now(),
+        open_started_at: chrono::Utc::now(),
+        open_id: uuid::Uuid::new_v4(),
+        timeout_log: Default::default(),
+        cancel: Default::default(),
+        active_session: None,
+        terminator_detect: None,
+        pending_echo: None,
     };
 
     Arc::new(Mutex::new(state))
@@ -57,6 +82,11 @@ fn create_test_state_closed() -> AppState {
 
 /// Helper to start a test server and return its address.
 async fn start_test_server(app_state: AppState) -> String {
+    start_test_server_with_ws(app_state, WsSettings::default()).await
+}
+
+/// Helper to start a test server with custom WebSocket settings.
+async fn start_test_server_with_ws(app_state: AppState, ws: WsSettings) -> String {
     let session_store = SessionStore::new("sqlite::memory:")
         .await
         .expect("Failed to create session store");
@@ -66,6 +96,15 @@ async fn start_test_server(app_state: AppState) -> String {
         state: app_state,
         sessions: Arc::new(session_store),
         service,
+        port_cache: Arc::new(serial_mcp_agent::port::PortListCache::default()),
+        config: Arc::new(std::sync::RwLock::new(
+            serial_mcp_agent::ConfigLoader::with_defaults(),
+        )),
+        ws,
+        ws_connections: Default::default(),
+        ws_readers: Default::default(),
+        #[cfg(feature = "auto-negotiation")]
+        negotiator: Default::default(),
     };
 
     let app = serial_mcp_agent::rest_api::build_router(ctx);
@@ -164,6 +203,55 @@ async fn test_websocket_write_command() {
     }
 }
 
+#[tokio::test]
+async fn test_websocket_reconfigure_command() {
+    let state = create_test_state_with_mock();
+    let url = start_test_server(state.clone()).await;
+
+    let (ws_stream, _) = connect_async(&url).await.expect("Failed to connect");
+    let (mut write, mut read) = ws_stream.split();
+
+    // Consume initial status message
+    let _ = read.next().await;
+
+    let reconfigure_cmd = json!({
+        "type": "reconfigure",
+        "terminator": "\r\n",
+        "timeout_ms": 250
+    });
+    write
+        .send(TungsteniteMessage::Text(reconfigure_cmd.to_string()))
+        .await
+        .expect("Failed to send");
+
+    // Not subscribed, so the new status comes straight back
+    let msg = tokio::time::timeout(Duration::from_secs(1), read.next())
+        .await
+        .expect("Timeout waiting for response")
+        .expect("No message received")
+        .expect("WebSocket error");
+    match msg {
+        TungsteniteMessage::Text(text) => {
+            let json: serde_json::Value = serde_json::from_str(&text).expect("Invalid JSON");
+            assert_eq!(json["type"], "status");
+            assert_eq!(json["state"], "Open");
+            assert_eq!(json["config"]["terminator"], "\r\n");
+            assert_eq!(json["config"]["timeout_ms"], 250);
+        }
+        _ => panic!("Expected text message"),
+    }
+
+    // The shared port state carries the new settings for the reader task
+    let st = state.lock().unwrap();
+    if let PortState::Open { config, .. } = &*st {
+        assert_eq!(config.terminator.as_deref(), Some("\r\n"));
+        assert_eq!(config.timeout_ms, 250);
+        assert_eq!(config.baud_rate, 9600);
+    } else {
+        panic!("Port should be open");
+    }
+}
+
 #[tokio::test]
 async fn test_websocket_write_to_closed_port() {
     let state = create_test_state_closed();
@@ -382,6 +470,64 @@ async fn test_websocket_concurrent_connections() {
     }
 }
 
+#[tokio::test]
+async fn test_websocket_connection_limit() {
+    let ws = WsSettings {
+        max_connections: 2,
+        ..WsSettings::default()
+    };
+    let url = start_test_server_with_ws(create_test_state_closed(), ws).await;
+
+    // Up to the limit connects and receives the initial status
+    let mut clients = vec![];
+    for _ in 0..2 {
+        let (mut ws_stream, _) = connect_async(&url).await.expect("Failed to connect");
+        let _ = tokio::time::timeout(Duration::from_secs(1), ws_stream.next()).await;
+        clients.push(ws_stream);
+    }
+
+    // One beyond is rejected before upgrading
+    match connect_async(&url).await {
+        Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+            assert_eq!(response.status(), 503);
+        }
+        other => panic!("Expected 503 rejection, got {:?}", other.map(|_| ())),
+    }
+
+    // Closing a client frees its slot
+    let mut closed = clients.pop().unwrap();
+    closed.close(None).await.expect("Failed to close");
+    drop(closed);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    let (_ws_stream, _) = connect_async(&url)
+        .await
+        .expect("Slot should be released after disconnect");
+}
+
+#[tokio::test]
+async fn test_websocket_reader_stops_after_disconnect() {
+    let mut mock = MockSerialPort::new("TEST_PORT");
+    let state = create_test_state_with_port(mock.clone());
+    let url = start_test_server(state.clone()).await;
+
+    let (mut ws_stream, _) = connect_async(&url).await.expect("Failed to connect");
+    let _ = tokio::time::timeout(Duration::from_secs(1), ws_stream.next()).await;
+    ws_stream.close(None).await.expect("Failed to close");
+    drop(ws_stream);
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    // No reader is left to drain the port
+    mock.enqueue_read(b"after\n");
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    let mut st = state.lock().unwrap();
+    let PortState::Open { port, .. } = &mut *st else {
+        panic!("port should still be open");
+    };
+    let mut buffer = [0u8; 16];
+    let n = port.read_bytes(&mut buffer).expect("read failed");
+    assert_eq!(&buffer[..n], b"after\n");
+}
+
 #[tokio::test]
 async fn test_websocket_graceful_disconnect() {
     let state = create_test_state_closed();
@@ -436,6 +582,47 @@ async fn test_websocket_status_with_metrics() {
     }
 }
 
+#[tokio::test]
+async fn test_websocket_periodic_status_broadcast() {
+    let ws = WsSettings {
+        status_interval: Some(Duration::from_millis(100)),
+        ..WsSettings::default()
+    };
+    let url = start_test_server_with_ws(create_test_state_with_mock(), ws).await;
+
+    let (ws_stream, _) = connect_async(&url).await.expect("Failed to connect");
+    let (mut write, mut read) = ws_stream.split();
+
+    // Consume initial status
+    let _ = read.next().await;
+
+    let subscribe_cmd = json!({"type": "subscribe"});
+    write
+        .send(TungsteniteMessage::Text(subscribe_cmd.to_string()))
+        .await
+        .expect("Failed to send");
+
+    // Collect status messages for a short window; serial data is interleaved
+    let mut uptimes = vec![];
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(600);
+    while let Ok(Some(Ok(msg))) = tokio::time::timeout_at(deadline, read.next()).await {
+        if let TungsteniteMessage::Text(text) = msg {
+            let json: serde_json::Value = serde_json::from_str(&text).expect("Invalid JSON");
+            if json["type"] == "status" {
+                assert_eq!(json["state"], "Open");
+                uptimes.push(json["metrics"]["open_duration_ms"].as_u64().unwrap());
+            }
+        }
+    }
+
+    assert!(
+        uptimes.len() >= 3,
+        "expected periodic status, got {}",
+        uptimes.len()
+    );
+    assert!(uptimes.windows(2).all(|w| w[0] <= w[1]));
+}
+
 #[tokio::test]
 async fn test_websocket_terminator_stripping() {
     let state = create_test_state_with_mock();
@@ -470,3 +657,57 @@ async fn test_websocket_terminator_stripping() {
         // This is verified in the write command test
     }
 }
+
+/// Subscribe on `url` and return the first frame that is not a status message.
+async fn first_data_frame(url: &str) -> TungsteniteMessage {
+    let (ws_stream, _) = connect_async(url).await.expect("Failed to connect");
+    let (mut write, mut read) = ws_stream.split();
+
+    // Consume initial status
+    let _ = read.next().await;
+
+    write
+        .send(TungsteniteMessage::Text(
+            json!({"type": "subscribe"}).to_string(),
+        ))
+        .await
+        .expect("Failed to send");
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        loop {
+            match read.next().await {
+                Some(Ok(TungsteniteMessage::Text(text))) => {
+                    let json: serde_json::Value =
+                        serde_json::from_str(&text).expect("Invalid JSON");
+                    if json["type"] == "data" {
+                        return TungsteniteMessage::Text(text);
+                    }
+                }
+                Some(Ok(msg @ TungsteniteMessage::Binary(_))) => return msg,
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("Timeout waiting for data frame")
+}
+
+#[tokio::test]
+async fn test_websocket_raw_encoding() {
+    // Raw: the exact bytes read, terminator included, in a binary frame
+    let url = start_test_server(create_test_state_with_mock()).await;
+    match first_data_frame(&format!("{}?encoding=raw", url)).await {
+        TungsteniteMessage::Binary(bytes) => assert_eq!(bytes, b"test response\n"),
+        other => panic!("Expected binary frame, got {:?}", other),
+    }
+
+    // Default: JSON text frame with the terminator stripped
+    let url = start_test_server(create_test_state_with_mock()).await;
+    match first_data_frame(&url).await {
+        TungsteniteMessage::Text(text) => {
+            let json: serde_json::Value = serde_json::from_str(&text).expect("Invalid JSON");
+            assert_eq!(json["data"], "test response");
+        }
+        other => panic!("Expected text frame, got {:?}", other),
+    }
+}
@@ -32,6 +32,16 @@ async fn test_full_workflow_open_write_read_close() {
         flow_control: serial_mcp_agent::state::FlowControlCfg::None,
         terminator: Some("\n".to_string()),
         idle_disconnect_ms: None,
+        read_idle_gap_ms: None,
+        write_checksum: None,
+        half_duplex_echo: false,
+        flush_on_write: false,
+        auto_detect_terminator: false,
+        mask_parity_bit: None,
+        strip_echo: false,
+        strip_prompt: None,
+        max_open_duration_ms: None,
+        read_strategy: Default::default(),
     };
 
     // Open port
@@ -45,7 +55,16 @@ async fn test_full_workflow_open_write_read_close() {
             bytes_read_total: 0,
             bytes_written_total: 0,
             idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer: Vec::new(),
             open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id: uuid::Uuid::new_v4(),
+            timeout_log: Default::default(),
+            cancel: Default::default(),
+            active_session: None,
+            terminator_detect: None,
+            pending_echo: None,
         };
     }
 
@@ -170,6 +189,16 @@ async fn test_idle_disconnect_workflow() {
         flow_control: serial_mcp_agent::state::FlowControlCfg::None,
         terminator: Some("\n".to_string()),
         idle_disconnect_ms: Some(100), // 100ms idle timeout
+        read_idle_gap_ms: None,
+        write_checksum: None,
+        half_duplex_echo: false,
+        flush_on_write: false,
+        auto_detect_terminator: false,
+        mask_parity_bit: None,
+        strip_echo: false,
+        strip_prompt: None,
+        max_open_duration_ms: None,
+        read_strategy: Default::default(),
     };
 
     // Open port
@@ -183,7 +212,16 @@ async fn test_idle_disconnect_workflow() {
             bytes_read_total: 0,
             bytes_written_total: 0,
             idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer: Vec::new(),
             open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id: uuid::Uuid::new_v4(),
+            timeout_log: Default::default(),
+            cancel: Default::default(),
+            active_session: None,
+            terminator_detect: None,
+            pending_echo: None,
         };
     }
 
@@ -228,6 +266,16 @@ async fn test_reconfigure_port_workflow() {
         flow_control: serial_mcp_agent::state::FlowControlCfg::None,
         terminator: Some("\n".to_string()),
         idle_disconnect_ms: None,
+        read_idle_gap_ms: None,
+        write_checksum: None,
+        half_duplex_echo: false,
+        flush_on_write: false,
+        auto_detect_terminator: false,
+        mask_parity_bit: None,
+        strip_echo: false,
+        strip_prompt: None,
+        max_open_duration_ms: None,
+        read_strategy: Default::default(),
     };
 
     // Open with initial config
@@ -241,7 +289,16 @@ async fn test_reconfigure_port_workflow() {
             bytes_read_total: 0,
             bytes_written_total: 0,
             idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer: Vec::new(),
             open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id: uuid::Uuid::new_v4(),
+            timeout_log: Default::default(),
+            cancel: Default::default(),
+            active_session: None,
+            terminator_detect: None,
+            pending_echo: None,
         };
     }
 
@@ -268,7 +325,16 @@ async fn test_reconfigure_port_workflow() {
             bytes_read_total: 0,
             bytes_written_total: 0,
             idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer: Vec::new(),
             open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id: uuid::Uuid::new_v4(),
+            timeout_log: Default::default(),
+            cancel: Default::default(),
+            active_session: None,
+            terminator_detect: None,
+            pending_echo: None,
         };
     }
 
@@ -346,6 +412,16 @@ async fn test_timeout_streak_tracking() {
         flow_control: serial_mcp_agent::state::FlowControlCfg::None,
         terminator: Some("\n".to_string()),
         idle_disconnect_ms: None,
+        read_idle_gap_ms: None,
+        write_checksum: None,
+        half_duplex_echo: false,
+        flush_on_write: false,
+        auto_detect_terminator: false,
+        mask_parity_bit: None,
+        strip_echo: false,
+        strip_prompt: None,
+        max_open_duration_ms: None,
+        read_strategy: Default::default(),
     };
 
     // Open port
@@ -359,7 +435,16 @@ async fn test_timeout_streak_tracking() {
             bytes_read_total: 0,
             bytes_written_total: 0,
             idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer: Vec::new(),
             open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id: uuid::Uuid::new_v4(),
+            timeout_log: Default::default(),
+            cancel: Default::default(),
+            active_session: None,
+            terminator_detect: None,
+            pending_echo: None,
         };
     }
 
@@ -409,6 +494,16 @@ async fn test_byte_counting() {
         flow_control: serial_mcp_agent::state::FlowControlCfg::None,
         terminator: Some("\n".to_string()),
         idle_disconnect_ms: None,
+        read_idle_gap_ms: None,
+        write_checksum: None,
+        half_duplex_echo: false,
+        flush_on_write: false,
+        auto_detect_terminator: false,
+        mask_parity_bit: None,
+        strip_echo: false,
+        strip_prompt: None,
+        max_open_duration_ms: None,
+        read_strategy: Default::default(),
     };
 
     // Open port
@@ -422,7 +517,16 @@ async fn test_byte_counting() {
             bytes_read_total: 0,
             bytes_written_total: 0,
             idle_close_count: 0,
+            framing_errors: 0,
+            frame_buffer: Vec::new(),
             open_started: std::time::Instant::now(),
+            open_started_at: chrono::Utc::now(),
+            open_id: uuid::Uuid::new_v4(),
+            timeout_log: Default::default(),
+            cancel: Default::default(),
+            active_session: None,
+            terminator_detect: None,
+            pending_echo: None,
         };
     }
 
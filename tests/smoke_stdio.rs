@@ -117,6 +117,68 @@ fn initialize_round_trip() {
         result.get("serverInfo").is_some(),
         "missing serverInfo field: {v:?}"
     );
+    // MCP_DEBUG_BOOT is set, yet stdout must carry protocol frames only
+    for frame in parse_messages(&raw_final) {
+        assert_eq!(
+            frame["jsonrpc"], "2.0",
+            "non-protocol output on stdout: {msg}"
+        );
+    }
+    assert!(
+        stderr_msg.contains("boot_marker"),
+        "debug boot marker not logged: {stderr_msg}"
+    );
+}
+
+#[test]
+fn ping_returns_empty_result() {
+    let mut child = spawn_stdio();
+    let stdin = child.stdin.as_mut().expect("stdin");
+    let mut child_stdout = child.stdout.take().expect("stdout");
+    let mut child_stderr = child.stderr.take().expect("stderr");
+    // Drain stderr so boot logging cannot fill the pipe and stall the server
+    thread::spawn(move || std::io::copy(&mut child_stderr, &mut std::io::sink()));
+    let out_buf: Arc<Mutex<Vec<u8>>> = Arc::new(Mutex::new(Vec::new()));
+    let out_buf_clone = Arc::clone(&out_buf);
+    let stdout_handle = thread::spawn(move || {
+        let mut local = [0u8; 512];
+        while let Ok(n) = child_stdout.read(&mut local) {
+            if n == 0 {
+                break;
+            }
+            out_buf_clone.lock().unwrap().extend_from_slice(&local[..n]);
+        }
+    });
+    thread::sleep(Duration::from_millis(120));
+
+    // Newline-delimited, as the stdio transport reads it
+    let init_body = r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2025-06-18","capabilities":{},"clientInfo":{"name":"test","version":"0.0.0"}}}"#;
+    let ping_body = r#"{"jsonrpc":"2.0","id":2,"method":"ping"}"#;
+    for body in [init_body, ping_body] {
+        stdin.write_all(format!("{}\n", body).as_bytes()).unwrap();
+        stdin.flush().unwrap();
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let mut pong = None;
+    while Instant::now() < deadline && pong.is_none() {
+        pong = parse_messages(&out_buf.lock().unwrap())
+            .into_iter()
+            .find(|msg| msg.get("id").and_then(|i| i.as_i64()) == Some(2));
+        thread::sleep(Duration::from_millis(25));
+    }
+
+    let _ = child.kill();
+    let _ = child.wait();
+    let _ = stdout_handle.join();
+    let raw = String::from_utf8_lossy(&out_buf.lock().unwrap()).to_string();
+    let pong = pong.unwrap_or_else(|| panic!("no ping response within timeout. Raw: {raw}"));
+    assert_eq!(
+        pong,
+        serde_json::json!({"jsonrpc": "2.0", "id": 2, "result": {}}),
+        "ping response is not an empty result"
+    );
 }
 
 // Attempt to parse a single Content-Length framed JSON message from the accumulated buffer.